@@ -8,18 +8,26 @@
  * 2. 提取用户提示词中的技术关键词
  * 3. 调用 search_context 工具获取相关代码
  * 4. 格式化上下文信息并附加到提示词
+ * 5. 可选使用离线的本地代码索引（local_index）代替 acemcp
  */
 use anyhow::Result;
 use log::{debug, error, info, warn};
+use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::{Duration, Instant};
 use tauri::AppHandle;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::process::Command;
+use tokio::process::{ChildStdout, Command};
+use tokio::sync::watch;
+
+use super::local_index;
 
 // Windows: 导入 CommandExt trait 以使用 creation_flags
 #[cfg(target_os = "windows")]
@@ -75,10 +83,68 @@ pub struct EnhancementResult {
     pub enhanced_prompt: String,
     /// 找到的上下文条目数
     pub context_count: usize,
-    /// 是否成功调用 acemcp
+    /// 是否使用了 acemcp 检索
     pub acemcp_used: bool,
+    /// 实际完成检索的后端（未检索时为空）
+    #[serde(default)]
+    pub backend: Option<ContextBackend>,
     /// 错误信息（如果有）
     pub error: Option<String>,
+    /// 附加到提示词的代码片段（用户可在发送前取消勾选）
+    #[serde(default)]
+    pub snippets: Vec<ContextSnippet>,
+}
+
+/// 检索到的代码片段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextSnippet {
+    /// 片段标识（路径和内容的哈希），用于去重和勾选
+    pub id: String,
+    /// 相对项目根目录的文件路径
+    pub path: String,
+    /// 起止行号（从 1 开始，包含结束行）；检索结果未带行号时为 None
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+    /// 相关度得分（本地索引的 BM25 分数；acemcp 不返回分数）
+    pub score: Option<f64>,
+    /// 找到该片段的查询轮次（从 1 开始）
+    pub round: usize,
+    /// 该轮使用的查询
+    pub query: String,
+    pub content: String,
+}
+
+impl ContextSnippet {
+    pub fn new(
+        path: String,
+        lines: Option<(usize, usize)>,
+        score: Option<f64>,
+        round: usize,
+        query: &str,
+        content: String,
+    ) -> Self {
+        let id = format!("{:x}", md5::compute(format!("{}\n{}", path, content)));
+        Self {
+            id,
+            path,
+            start_line: lines.map(|(start, _)| start),
+            end_line: lines.map(|(_, end)| end),
+            score,
+            round,
+            query: query.to_string(),
+            content,
+        }
+    }
+
+    /// 可在编辑器中打开的引用，如 `src/main.rs:10-42`
+    pub fn citation(&self) -> String {
+        match (self.start_line, self.end_line) {
+            (Some(start), Some(end)) if end > start => format!("{}:{}-{}", self.path, start, end),
+            (Some(start), _) => format!("{}:{}", self.path, start),
+            _ => self.path.clone(),
+        }
+    }
 }
 
 // ============================================================================
@@ -288,6 +354,8 @@ fn generate_smart_query(current_prompt: &str, history_info: &HistoryContextInfo)
 /// Acemcp MCP 客户端
 struct AcemcpClient {
     child: tokio::process::Child,
+    /// 常驻的 stdout 读取器，避免每次请求重建缓冲区时丢失已读入的数据
+    stdout: BufReader<ChildStdout>,
     request_id: u64,
 }
 
@@ -391,7 +459,8 @@ impl AcemcpClient {
         cmd.arg(&sidecar_path)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
-            .stderr(Stdio::null());
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
 
         // Windows: 隐藏控制台窗口
         #[cfg(target_os = "windows")]
@@ -400,14 +469,19 @@ impl AcemcpClient {
             cmd.creation_flags(CREATE_NO_WINDOW);
         }
 
-        let child = cmd.spawn().map_err(|e| {
+        let mut child = cmd.spawn().map_err(|e| {
             anyhow::anyhow!("Failed to spawn sidecar: {}. Path: {:?}", e, sidecar_path)
         })?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("stdout not available"))?;
 
         info!("Acemcp sidecar started successfully");
 
         Ok(Self {
             child,
+            stdout: BufReader::new(stdout),
             request_id: 0,
         })
     }
@@ -434,36 +508,54 @@ impl AcemcpClient {
             return Err(anyhow::anyhow!("stdin not available"));
         }
 
-        // 读取响应
-        if let Some(stdout) = self.child.stdout.as_mut() {
-            let mut reader = BufReader::new(stdout);
+        // 读取响应（设置超时 30 秒）
+        // 客户端会被复用：跳过通知和已超时请求的迟到响应，直到读到本次请求的响应
+        let request_id = self.request_id;
+        let timeout = tokio::time::Duration::from_secs(30);
+        let read_response = async {
             let mut line = String::new();
-
-            // 设置超时（30秒）
-            let timeout = tokio::time::Duration::from_secs(30);
-            match tokio::time::timeout(timeout, reader.read_line(&mut line)).await {
-                Ok(Ok(_)) => {
-                    debug!("Received MCP response: {}", line.trim());
-                    let response: JsonRpcResponse = serde_json::from_str(&line)?;
-
-                    if let Some(error) = response.error {
-                        return Err(anyhow::anyhow!(
-                            "MCP error {}: {}",
-                            error.code,
-                            error.message
-                        ));
-                    }
-
-                    response
-                        .result
-                        .ok_or_else(|| anyhow::anyhow!("No result in response"))
+            loop {
+                line.clear();
+                if self.stdout.read_line(&mut line).await? == 0 {
+                    return Err(anyhow::anyhow!("acemcp process closed stdout"));
+                }
+                match serde_json::from_str::<JsonRpcResponse>(&line) {
+                    Ok(response) if response.id == request_id => return Ok(response),
+                    _ => debug!("Skipping MCP message: {}", line.trim()),
                 }
-                Ok(Err(e)) => Err(anyhow::anyhow!("Failed to read response: {}", e)),
-                Err(_) => Err(anyhow::anyhow!("Request timeout (30s)")),
             }
-        } else {
-            Err(anyhow::anyhow!("stdout not available"))
+        };
+        let response = match tokio::time::timeout(timeout, read_response).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(e)) => return Err(anyhow::anyhow!("Failed to read response: {}", e)),
+            Err(_) => return Err(anyhow::anyhow!("Request timeout (30s)")),
+        };
+        debug!("Received MCP response for request {}", request_id);
+
+        if let Some(error) = response.error {
+            return Err(anyhow::anyhow!(
+                "MCP error {}: {}",
+                error.code,
+                error.message
+            ));
         }
+
+        response
+            .result
+            .ok_or_else(|| anyhow::anyhow!("No result in response"))
+    }
+
+    /// 进程是否仍在运行
+    fn is_alive(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+
+    /// 健康检查：发送 MCP ping 并等待响应
+    async fn ping(&mut self) -> Result<()> {
+        tokio::time::timeout(CLIENT_HEALTH_CHECK_TIMEOUT, self.send_request("ping", None))
+            .await
+            .map_err(|_| anyhow::anyhow!("Ping timeout"))??;
+        Ok(())
     }
 
     /// 发送通知（notification，无需响应）
@@ -542,41 +634,47 @@ impl AcemcpClient {
     }
 
     /// 多轮搜索：使用不同的查询策略获取更全面的上下文
+    /// `cancel` 被触发时立即放弃正在进行的一轮并返回错误
     async fn multi_round_search(
         &mut self,
         project_path: &str,
         queries: &[String],
         max_total_length: usize,
-    ) -> Result<String> {
+        cancel: &EnhancementCancellation,
+    ) -> Result<Vec<ContextSnippet>> {
         info!("Starting multi-round search with {} queries", queries.len());
 
-        let mut all_results = Vec::new();
+        let mut all_snippets: Vec<ContextSnippet> = Vec::new();
         let mut seen_snippets = HashSet::new(); // 用于去重
+        let mut total_length = 0;
 
         for (round, query) in queries.iter().enumerate() {
             if query.trim().is_empty() {
                 continue;
             }
 
+            if cancel.is_cancelled() {
+                info!("Multi-round search cancelled before round {}", round + 1);
+                return Err(anyhow::anyhow!("Search cancelled"));
+            }
+
             info!("Round {}: searching with query: {}", round + 1, query);
 
-            match self.search_context(project_path, query).await {
+            let round_result = tokio::select! {
+                result = self.search_context(project_path, query) => result,
+                _ = cancel.cancelled() => {
+                    info!("Multi-round search cancelled during round {}", round + 1);
+                    return Err(anyhow::anyhow!("Search cancelled"));
+                }
+            };
+
+            match round_result {
                 Ok(result) => {
-                    // 简单去重：按代码片段切分
-                    for snippet in result.split("\n\nPath:") {
-                        if !snippet.trim().is_empty() {
-                            // 生成简单的哈希来去重
-                            let snippet_hash = format!("{:x}", md5::compute(snippet));
-                            if !seen_snippets.contains(&snippet_hash) {
-                                seen_snippets.insert(snippet_hash);
-
-                                // 恢复 "Path:" 前缀（除了第一个）
-                                if !all_results.is_empty() {
-                                    all_results.push(format!("\n\nPath:{}", snippet));
-                                } else {
-                                    all_results.push(snippet.to_string());
-                                }
-                            }
+                    // 按代码片段切分，按路径和内容去重
+                    for snippet in parse_search_result(&result, round + 1, query) {
+                        if seen_snippets.insert(snippet.id.clone()) {
+                            total_length += snippet.content.len();
+                            all_snippets.push(snippet);
                         }
                     }
                 }
@@ -587,8 +685,7 @@ impl AcemcpClient {
             }
 
             // 检查是否已经收集够了
-            let current_length: usize = all_results.iter().map(|s| s.len()).sum();
-            if current_length >= max_total_length {
+            if total_length >= max_total_length {
                 info!("Reached max length limit, stopping at round {}", round + 1);
                 break;
             }
@@ -597,14 +694,13 @@ impl AcemcpClient {
             tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
         }
 
-        let combined = all_results.join("");
         info!(
             "Multi-round search completed: {} unique snippets, {} total chars",
-            seen_snippets.len(),
-            combined.len()
+            all_snippets.len(),
+            total_length
         );
 
-        Ok(combined)
+        Ok(all_snippets)
     }
 
     /// 关闭客户端
@@ -620,6 +716,215 @@ impl AcemcpClient {
     }
 }
 
+// ============================================================================
+// Acemcp 客户端池
+// ============================================================================
+
+/// 客户端空闲多久后关闭
+const CLIENT_IDLE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+/// 客户端空闲多久后，下次使用前先做健康检查
+const CLIENT_HEALTH_CHECK_AFTER: Duration = Duration::from_secs(60);
+const CLIENT_HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+/// 空闲客户端的检查间隔
+const CLIENT_REAPER_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 池中某个项目的客户端槽位
+///
+/// 槽位的锁保证同一时间只有一个请求使用该客户端的 stdio 通道；
+/// 客户端在首次使用、崩溃或健康检查失败后（重新）启动，空闲超时后关闭。
+#[derive(Default)]
+struct PooledClient {
+    client: Option<AcemcpClient>,
+    last_used: Option<Instant>,
+}
+
+type ClientSlot = Arc<tokio::sync::Mutex<PooledClient>>;
+
+/// 按项目路径复用的 acemcp 客户端
+static CLIENT_POOL: Lazy<StdMutex<HashMap<String, ClientSlot>>> =
+    Lazy::new(|| StdMutex::new(HashMap::new()));
+static CLIENT_REAPER_STARTED: AtomicBool = AtomicBool::new(false);
+
+fn pool_key(project_path: &str) -> String {
+    super::claude::normalize_path_for_comparison(project_path)
+}
+
+impl PooledClient {
+    /// 返回可用的客户端：进程已退出或健康检查失败时重启
+    async fn ensure_running(&mut self, app: &AppHandle) -> Result<&mut AcemcpClient, String> {
+        let idle_for = self.last_used.map(|t| t.elapsed());
+        if let Some(client) = self.client.as_mut() {
+            if !client.is_alive() {
+                warn!("Acemcp sidecar exited unexpectedly, restarting");
+                self.client = None;
+            } else if idle_for.is_some_and(|idle| idle >= CLIENT_HEALTH_CHECK_AFTER) {
+                if let Err(e) = client.ping().await {
+                    warn!("Acemcp health check failed: {}, restarting", e);
+                    if let Some(client) = self.client.take() {
+                        let _ = client.shutdown().await;
+                    }
+                }
+            }
+        }
+
+        if self.client.is_none() {
+            let mut client = AcemcpClient::start(app).await.map_err(|e| {
+                error!("Failed to start acemcp: {}", e);
+                format!("Failed to start acemcp: {}", e)
+            })?;
+            if let Err(e) = client.initialize().await {
+                error!("Failed to initialize MCP session: {}", e);
+                let _ = client.shutdown().await;
+                return Err(format!("Failed to initialize MCP: {}", e));
+            }
+            self.client = Some(client);
+        }
+
+        self.last_used = Some(Instant::now());
+        Ok(self.client.as_mut().expect("client started above"))
+    }
+
+    /// 请求失败后调用：进程已退出时丢弃客户端，返回是否已丢弃
+    fn discard_if_dead(&mut self) -> bool {
+        if self
+            .client
+            .as_mut()
+            .is_some_and(|client| !client.is_alive())
+        {
+            warn!("Acemcp sidecar crashed during a request");
+            self.client = None;
+            return true;
+        }
+        false
+    }
+}
+
+/// 租用项目的客户端槽位（等待该项目上正在进行的请求完成）
+async fn acquire_client(project_path: &str) -> tokio::sync::OwnedMutexGuard<PooledClient> {
+    if !CLIENT_REAPER_STARTED.swap(true, Ordering::SeqCst) {
+        tauri::async_runtime::spawn(reap_idle_clients());
+    }
+
+    let slot = CLIENT_POOL
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .entry(pool_key(project_path))
+        .or_default()
+        .clone();
+    slot.lock_owned().await
+}
+
+/// 后台任务：关闭空闲超时的客户端，正在使用的客户端会被跳过
+async fn reap_idle_clients() {
+    loop {
+        tokio::time::sleep(CLIENT_REAPER_INTERVAL).await;
+
+        let slots: Vec<(String, ClientSlot)> = CLIENT_POOL
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(key, slot)| (key.clone(), slot.clone()))
+            .collect();
+
+        for (key, slot) in slots {
+            let Ok(mut pooled) = slot.try_lock() else {
+                continue;
+            };
+            let idle = pooled
+                .last_used
+                .is_some_and(|t| t.elapsed() >= CLIENT_IDLE_TIMEOUT);
+            if idle {
+                if let Some(client) = pooled.client.take() {
+                    info!("Shutting down idle acemcp client for {}", key);
+                    let _ = client.shutdown().await;
+                }
+            }
+        }
+    }
+}
+
+/// 关闭池中所有客户端（配置变更后需要以新配置重启 sidecar）
+async fn shutdown_all_clients() {
+    let slots: Vec<ClientSlot> = CLIENT_POOL
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .cloned()
+        .collect();
+
+    for slot in slots {
+        if let Some(client) = slot.lock().await.client.take() {
+            let _ = client.shutdown().await;
+        }
+    }
+}
+
+// ============================================================================
+// 增强请求取消
+// ============================================================================
+
+/// 增强请求被取消时返回的错误信息
+const SEARCH_CANCELLED: &str = "Context search cancelled";
+
+/// 每个项目的增强请求代数：新的增强请求或取消命令会使旧请求失效
+static ENHANCEMENT_GENERATIONS: Lazy<StdMutex<HashMap<String, watch::Sender<u64>>>> =
+    Lazy::new(|| StdMutex::new(HashMap::new()));
+
+/// 一次增强请求的取消状态
+#[derive(Clone)]
+struct EnhancementCancellation {
+    generation: u64,
+    receiver: watch::Receiver<u64>,
+}
+
+impl EnhancementCancellation {
+    /// 开始项目的新一次增强，取消该项目上仍在进行的旧请求
+    fn begin(project_path: &str) -> Self {
+        let mut generations = ENHANCEMENT_GENERATIONS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let sender = generations
+            .entry(pool_key(project_path))
+            .or_insert_with(|| watch::channel(0).0);
+        sender.send_modify(|generation| *generation += 1);
+        let receiver = sender.subscribe();
+        let generation = *receiver.borrow();
+        Self {
+            generation,
+            receiver,
+        }
+    }
+
+    /// 取消项目上正在进行的增强请求
+    fn cancel(project_path: &str) -> bool {
+        let generations = ENHANCEMENT_GENERATIONS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        match generations.get(&pool_key(project_path)) {
+            Some(sender) => {
+                sender.send_modify(|generation| *generation += 1);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        *self.receiver.borrow() != self.generation
+    }
+
+    /// 在请求被取消时完成
+    async fn cancelled(&self) {
+        let mut receiver = self.receiver.clone();
+        while !self.is_cancelled() {
+            if receiver.changed().await.is_err() {
+                // 发送端不会被移除；保险起见永不完成
+                std::future::pending::<()>().await;
+            }
+        }
+    }
+}
+
 // ============================================================================
 // 关键词提取
 // ============================================================================
@@ -1043,6 +1348,8 @@ pub async fn enhance_prompt_with_context(
             enhanced_prompt: prompt.clone(),
             context_count: 0,
             acemcp_used: false,
+            backend: None,
+            snippets: Vec::new(),
             error: Some(format!(
                 "提示词过长（{} 字符），超过最大限制（{} 字符）。请缩短提示词或分批处理。",
                 prompt.len(),
@@ -1051,6 +1358,30 @@ pub async fn enhance_prompt_with_context(
         });
     }
 
+    // 连最小的上下文都放不下时，返回带警告的原提示词
+    let available_space = MAX_TOTAL_OUTPUT_LENGTH.saturating_sub(prompt.len() + 100); // 预留100字符给分隔符
+    if available_space <= 1000 {
+        warn!(
+            "Cannot fit any context, prompt too long: {} chars",
+            prompt.len()
+        );
+        return Ok(EnhancementResult {
+            original_prompt: prompt.clone(),
+            enhanced_prompt: prompt.clone(),
+            context_count: 0,
+            acemcp_used: false,
+            backend: None,
+            snippets: Vec::new(),
+            error: Some(format!(
+                "提示词太长（{} 字符），无法添加项目上下文。\n\
+                建议：\n\
+                1. 缩短提示词长度\n\
+                2. 直接使用原提示词，不添加上下文",
+                prompt.len()
+            )),
+        });
+    }
+
     // 检查项目路径是否存在
     if !std::path::Path::new(&project_path).exists() {
         return Ok(EnhancementResult {
@@ -1058,10 +1389,15 @@ pub async fn enhance_prompt_with_context(
             enhanced_prompt: prompt,
             context_count: 0,
             acemcp_used: false,
+            backend: None,
+            snippets: Vec::new(),
             error: Some("Project path does not exist".to_string()),
         });
     }
 
+    // 开始新的增强请求，取消该项目上仍在进行的旧请求
+    let cancel = EnhancementCancellation::begin(&project_path);
+
     // 🎯 智能查询生成：根据是否有历史上下文选择策略
     let (search_queries, has_history) = if let (Some(sid), Some(pid)) = (&session_id, &project_id) {
         // 有历史：使用智能查询生成
@@ -1133,6 +1469,8 @@ pub async fn enhance_prompt_with_context(
             enhanced_prompt: prompt,
             context_count: 0,
             acemcp_used: false,
+            backend: None,
+            snippets: Vec::new(),
             error: Some("No keywords could be extracted from prompt".to_string()),
         });
     }
@@ -1146,160 +1484,69 @@ pub async fn enhance_prompt_with_context(
         debug!("  Query {}: {}", i + 1, q);
     }
 
-    // 启动 acemcp 客户端
-    let mut client = match AcemcpClient::start(&app).await {
-        Ok(c) => c,
+    // 🚀 执行搜索（单轮或多轮），按配置选择检索后端
+    let backend = load_acemcp_config()
+        .await
+        .map(|config| config.backend)
+        .unwrap_or_default();
+    let multi_round = valid_queries.len() > 1 && enable_multi_round.unwrap_or(true);
+    let search_result = match backend {
+        ContextBackend::Acemcp => {
+            search_with_acemcp(
+                &app,
+                &project_path,
+                &valid_queries,
+                multi_round,
+                max_length,
+                &cancel,
+            )
+            .await
+        }
+        ContextBackend::Local => {
+            search_with_local_index(
+                &project_path,
+                &valid_queries,
+                multi_round,
+                max_length,
+                &cancel,
+            )
+            .await
+        }
+    };
+    let snippets = match search_result {
+        Ok(snippets) => snippets,
         Err(e) => {
-            error!("Failed to start acemcp: {}", e);
             return Ok(EnhancementResult {
                 original_prompt: prompt.clone(),
                 enhanced_prompt: prompt,
                 context_count: 0,
                 acemcp_used: false,
-                error: Some(format!("Failed to start acemcp: {}", e)),
+                backend: None,
+                snippets: Vec::new(),
+                error: Some(e),
             });
         }
     };
 
-    // 初始化 MCP 会话
-    if let Err(e) = client.initialize().await {
-        error!("Failed to initialize MCP session: {}", e);
-        let _ = client.shutdown().await;
-        return Ok(EnhancementResult {
-            original_prompt: prompt.clone(),
-            enhanced_prompt: prompt,
-            context_count: 0,
-            acemcp_used: false,
-            error: Some(format!("Failed to initialize MCP: {}", e)),
-        });
-    }
+    // ⚡ 按整段选取片段，不超过上下文长度和最终输出长度限制
+    let snippets = fit_snippets(snippets, backend, max_length.min(available_space));
+    let context_count = snippets.len();
 
-    // 🚀 执行搜索（单轮或多轮）
-    let context_result = if valid_queries.len() > 1 && enable_multi_round.unwrap_or(true) {
-        info!(
-            "🔄 Using multi-round search with {} queries",
-            valid_queries.len()
-        );
-        match client
-            .multi_round_search(&project_path, &valid_queries, max_length * 2)
-            .await
-        {
-            Ok(ctx) => ctx,
-            Err(e) => {
-                error!("Failed to perform multi-round search: {}", e);
-                let _ = client.shutdown().await;
-                return Ok(EnhancementResult {
-                    original_prompt: prompt.clone(),
-                    enhanced_prompt: prompt,
-                    context_count: 0,
-                    acemcp_used: false,
-                    error: Some(format!("Failed to search context: {}", e)),
-                });
-            }
-        }
-    } else {
-        info!("🔍 Using single-round search");
-        match client
-            .search_context(&project_path, &valid_queries[0])
-            .await
-        {
-            Ok(ctx) => ctx,
-            Err(e) => {
-                error!("Failed to search context: {}", e);
-                let _ = client.shutdown().await;
-                return Ok(EnhancementResult {
-                    original_prompt: prompt.clone(),
-                    enhanced_prompt: prompt,
-                    context_count: 0,
-                    acemcp_used: false,
-                    error: Some(format!("Failed to search context: {}", e)),
-                });
-            }
-        }
-    };
-
-    // 关闭客户端
-    let _ = client.shutdown().await;
-
-    // ⚡ 改进：智能处理上下文结果
-    let trimmed_context = if context_result.len() > max_length {
-        warn!(
-            "Context too long ({} chars), truncating to {} chars",
-            context_result.len(),
-            max_length
-        );
-        format!(
-            "{}...\n\n[上下文过长，已自动截断。建议在设置中降低 maxContextLength 参数]",
-            truncate_utf8_safe(&context_result, max_length)
-        )
-    } else {
-        context_result.clone()
-    };
-
-    // 统计上下文条目数（简单计数 "Path:" 出现次数）
-    let context_count = trimmed_context.matches("Path:").count();
-
-    // ⚡ 改进：格式化增强后的提示词，并验证总长度
-    let enhanced_prompt = if !trimmed_context.trim().is_empty() {
-        let candidate = format!(
-            "{}\n\n--- 项目上下文 (来自 acemcp 语义搜索) ---\n{}",
-            prompt.trim(),
-            trimmed_context
-        );
-
-        // 检查最终输出长度
-        if candidate.len() > MAX_TOTAL_OUTPUT_LENGTH {
-            warn!(
-                "Enhanced prompt too long ({} chars), exceeds maximum ({})",
-                candidate.len(),
-                MAX_TOTAL_OUTPUT_LENGTH
-            );
-
-            // 动态调整上下文长度
-            let available_space = MAX_TOTAL_OUTPUT_LENGTH.saturating_sub(prompt.len() + 100); // 预留100字符给分隔符
-            if available_space > 1000 {
-                let adjusted_context = format!(
-                    "{}...\n\n[上下文已自动调整以适应长度限制]",
-                    truncate_utf8_safe(&trimmed_context, available_space)
-                );
-                format!(
-                    "{}\n\n--- 项目上下文 (来自 acemcp 语义搜索) ---\n{}",
-                    prompt.trim(),
-                    adjusted_context
-                )
-            } else {
-                // 如果连最小的上下文都放不下，返回带警告的原提示词
-                warn!(
-                    "Cannot fit any context, prompt too long: {} chars",
-                    prompt.len()
-                );
-                return Ok(EnhancementResult {
-                    original_prompt: prompt.clone(),
-                    enhanced_prompt: prompt.clone(),
-                    context_count: 0,
-                    acemcp_used: false,
-                    error: Some(format!(
-                        "提示词太长（{} 字符），无法添加项目上下文。\n\
-                        建议：\n\
-                        1. 缩短提示词长度\n\
-                        2. 直接使用原提示词，不添加上下文",
-                        prompt.len()
-                    )),
-                });
-            }
-        } else {
-            candidate
-        }
-    } else {
+    let enhanced_prompt = if snippets.is_empty() {
         // 如果没有找到相关上下文，返回原提示词
         info!("No relevant context found");
         prompt.clone()
+    } else {
+        format!(
+            "{}\n\n{}",
+            prompt.trim(),
+            format_context_snippets(backend, &snippets)
+        )
     };
 
     info!(
-        "Enhanced prompt: original_len={}, context_len={}, enhanced_len={}, context_count={}",
+        "Enhanced prompt: original_len={}, enhanced_len={}, context_count={}",
         prompt.len(),
-        trimmed_context.len(),
         enhanced_prompt.len(),
         context_count
     );
@@ -1308,11 +1555,258 @@ pub async fn enhance_prompt_with_context(
         original_prompt: prompt,
         enhanced_prompt,
         context_count,
-        acemcp_used: true,
+        acemcp_used: backend == ContextBackend::Acemcp,
+        backend: Some(backend),
+        snippets,
         error: None,
     })
 }
 
+/// 截断过长片段时追加在内容后的标记
+const TRUNCATION_MARKER: &str = "...\n[片段过长，已自动截断]";
+
+/// 按顺序选取完整片段，使格式化后的上下文（含标题、引用和分隔）不超过
+/// `max_length`；只有第一个片段就超长时才截断它
+fn fit_snippets(
+    snippets: Vec<ContextSnippet>,
+    backend: ContextBackend,
+    max_length: usize,
+) -> Vec<ContextSnippet> {
+    let mut fitted = Vec::new();
+    // 标题及其后的换行
+    let mut total_length = context_header(backend).len() + 1;
+
+    for mut snippet in snippets {
+        // 片段之间以空行分隔
+        let separator = if fitted.is_empty() { 0 } else { 2 };
+        let block_length = separator + snippet_block(fitted.len(), &snippet).len();
+        if total_length + block_length <= max_length {
+            total_length += block_length;
+            fitted.push(snippet);
+        } else if fitted.is_empty() {
+            // 截断只会缩短引用中的行号，按原引用计算即可
+            let content = std::mem::take(&mut snippet.content);
+            let overhead = snippet_block(0, &snippet).len() + TRUNCATION_MARKER.len();
+            let Some(room) = max_length.checked_sub(total_length + overhead) else {
+                warn!(
+                    "Context budget too small ({} chars) for any snippet",
+                    max_length
+                );
+                break;
+            };
+            warn!(
+                "Snippet too long ({} chars), truncating to {} chars",
+                content.len(),
+                room
+            );
+            let kept = truncate_utf8_safe(&content, room).trim_end();
+            // 截断后结束行号随保留的行数调整
+            if let Some(start) = snippet.start_line {
+                snippet.end_line = Some(start + kept.lines().count().saturating_sub(1));
+            }
+            snippet.content = format!("{}{}", kept, TRUNCATION_MARKER);
+            fitted.push(snippet);
+            break;
+        }
+    }
+
+    fitted
+}
+
+fn context_header(backend: ContextBackend) -> &'static str {
+    match backend {
+        ContextBackend::Acemcp => "--- 项目上下文 (来自 acemcp 语义搜索) ---",
+        ContextBackend::Local => "--- 项目上下文 (来自本地代码索引) ---",
+    }
+}
+
+/// 单个片段的上下文文本：编号、可打开的 `路径:行号` 引用和内容
+fn snippet_block(index: usize, snippet: &ContextSnippet) -> String {
+    format!(
+        "[{}] Path: {}\n{}",
+        index + 1,
+        snippet.citation(),
+        snippet.content.trim_end()
+    )
+}
+
+/// 将代码片段格式化为附加到提示词的上下文，每段带可打开的 `路径:行号` 引用
+fn format_context_snippets(backend: ContextBackend, snippets: &[ContextSnippet]) -> String {
+    let blocks: Vec<String> = snippets
+        .iter()
+        .enumerate()
+        .map(|(i, snippet)| snippet_block(i, snippet))
+        .collect();
+    format!("{}\n{}", context_header(backend), blocks.join("\n\n"))
+}
+
+/// 解析 search_context 返回的文本：按 `Path:` 切分为片段，并从行号前缀中读取行范围
+fn parse_search_result(text: &str, round: usize, query: &str) -> Vec<ContextSnippet> {
+    static LINE_NUMBER: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*(\d+)[\t:|]").unwrap());
+
+    let text = text.trim();
+    if text.is_empty() || text.starts_with("Error:") || text.starts_with("No relevant code context")
+    {
+        return Vec::new();
+    }
+
+    let mut snippets = Vec::new();
+    let mut current: Option<(String, Vec<&str>)> = None;
+    let mut flush = |current: Option<(String, Vec<&str>)>| {
+        let Some((path, lines)) = current else {
+            return;
+        };
+        let numbers: Vec<usize> = lines
+            .iter()
+            .filter_map(|line| LINE_NUMBER.captures(line))
+            .filter_map(|caps| caps[1].parse().ok())
+            .collect();
+        let range = match (numbers.iter().min(), numbers.iter().max()) {
+            (Some(&start), Some(&end)) => Some((start, end)),
+            _ => None,
+        };
+        let content = lines.join("\n").trim_matches('\n').to_string();
+        if !content.trim().is_empty() {
+            snippets.push(ContextSnippet::new(
+                path, range, None, round, query, content,
+            ));
+        }
+    };
+
+    for line in text.lines() {
+        if let Some(path) = line.trim_start().strip_prefix("Path:") {
+            flush(current.take());
+            current = Some((path.trim().to_string(), Vec::new()));
+        } else if let Some((_, lines)) = current.as_mut() {
+            lines.push(line);
+        }
+    }
+    flush(current);
+
+    snippets
+}
+
+/// 通过 acemcp sidecar 检索项目上下文
+///
+/// 使用项目的常驻客户端；sidecar 在搜索中崩溃时以新进程重试一次
+async fn search_with_acemcp(
+    app: &AppHandle,
+    project_path: &str,
+    queries: &[String],
+    multi_round: bool,
+    max_length: usize,
+    cancel: &EnhancementCancellation,
+) -> Result<Vec<ContextSnippet>, String> {
+    // 租用项目的客户端（同一项目的请求排队使用）
+    let mut pooled = acquire_client(project_path).await;
+    let mut retried = false;
+
+    loop {
+        if cancel.is_cancelled() {
+            info!("Context search cancelled");
+            return Err(SEARCH_CANCELLED.to_string());
+        }
+
+        let client = pooled.ensure_running(app).await?;
+        let result = if multi_round {
+            info!("🔄 Using multi-round search with {} queries", queries.len());
+            client
+                .multi_round_search(project_path, queries, max_length * 2, cancel)
+                .await
+        } else {
+            info!("🔍 Using single-round search");
+            tokio::select! {
+                result = client.search_context(project_path, &queries[0]) => {
+                    result.map(|text| parse_search_result(&text, 1, &queries[0]))
+                }
+                _ = cancel.cancelled() => Err(anyhow::anyhow!("Search cancelled")),
+            }
+        };
+
+        // 多轮搜索会跳过失败的轮次，崩溃时可能返回空结果而不是错误
+        let crashed = pooled.discard_if_dead();
+        match result {
+            Err(_) if cancel.is_cancelled() => {
+                info!("Context search cancelled");
+                return Err(SEARCH_CANCELLED.to_string());
+            }
+            Ok(snippets) if !(crashed && snippets.is_empty()) => return Ok(snippets),
+            _ if crashed && !retried => {
+                warn!("Retrying context search with a restarted acemcp sidecar");
+                retried = true;
+            }
+            Ok(snippets) => return Ok(snippets),
+            Err(e) => {
+                error!("Failed to search context: {}", e);
+                return Err(format!("Failed to search context: {}", e));
+            }
+        }
+    }
+}
+
+/// 通过本地代码索引检索项目上下文（离线，不依赖 Node.js 和远程 API）
+async fn search_with_local_index(
+    project_path: &str,
+    queries: &[String],
+    multi_round: bool,
+    max_length: usize,
+    cancel: &EnhancementCancellation,
+) -> Result<Vec<ContextSnippet>, String> {
+    let project_path = project_path.to_string();
+    let queries = if multi_round {
+        info!("🔄 Using local index with {} queries", queries.len());
+        queries.to_vec()
+    } else {
+        info!("🔍 Using local index with a single query");
+        queries[..1].to_vec()
+    };
+
+    // 索引刷新需要读取文件，放到阻塞线程中执行
+    let task_cancel = cancel.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        local_index::multi_round_search(&project_path, &queries, max_length * 2, || {
+            task_cancel.is_cancelled()
+        })
+    })
+    .await
+    .map_err(|e| format!("Local index task failed: {}", e))?;
+
+    match result {
+        Ok(snippets) => Ok(snippets),
+        Err(_) if cancel.is_cancelled() => {
+            info!("Context search cancelled");
+            Err(SEARCH_CANCELLED.to_string())
+        }
+        Err(e) => {
+            error!("Failed to search local index: {}", e);
+            Err(format!("Failed to search local index: {}", e))
+        }
+    }
+}
+
+/// 将用户保留的代码片段重新格式化为提示词上下文（用户在发送前取消勾选部分片段后调用）
+#[tauri::command]
+pub async fn format_enhancement_context(snippets: Vec<ContextSnippet>) -> Result<String, String> {
+    if snippets.is_empty() {
+        return Ok(String::new());
+    }
+    let backend = load_acemcp_config()
+        .await
+        .map(|config| config.backend)
+        .unwrap_or_default();
+    Ok(format_context_snippets(backend, &snippets))
+}
+
+/// 取消项目上正在进行的上下文搜索（用户在增强过程中再次编辑提示词时调用）
+#[tauri::command]
+pub async fn cancel_prompt_enhancement(project_path: String) -> Result<bool, String> {
+    let cancelled = EnhancementCancellation::cancel(&project_path);
+    if cancelled {
+        info!("Cancelled prompt enhancement for: {}", project_path);
+    }
+    Ok(cancelled)
+}
+
 /// 测试 acemcp 是否可用
 #[tauri::command]
 pub async fn test_acemcp_availability(app: AppHandle) -> Result<bool, String> {
@@ -1340,6 +1834,33 @@ pub async fn test_acemcp_availability(app: AppHandle) -> Result<bool, String> {
 // Acemcp 配置管理
 // ============================================================================
 
+/// 项目上下文检索后端
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContextBackend {
+    /// acemcp sidecar（需要 Node.js 和远程 API）
+    #[default]
+    Acemcp,
+    /// 内置的本地代码索引（离线，无需 Node.js）
+    Local,
+}
+
+impl ContextBackend {
+    fn as_str(self) -> &'static str {
+        match self {
+            ContextBackend::Acemcp => "acemcp",
+            ContextBackend::Local => "local",
+        }
+    }
+
+    fn from_config_value(value: &str) -> Self {
+        match value {
+            "local" => ContextBackend::Local,
+            _ => ContextBackend::Acemcp,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AcemcpConfigData {
@@ -1347,6 +1868,8 @@ pub struct AcemcpConfigData {
     pub token: String,
     pub batch_size: Option<u32>,
     pub max_lines_per_blob: Option<u32>,
+    #[serde(default)]
+    pub backend: ContextBackend,
 }
 
 impl Default for AcemcpConfigData {
@@ -1356,6 +1879,7 @@ impl Default for AcemcpConfigData {
             token: String::new(),
             batch_size: Some(10),
             max_lines_per_blob: Some(800),
+            backend: ContextBackend::default(),
         }
     }
 }
@@ -1368,11 +1892,17 @@ pub async fn save_acemcp_config(
     token: String,
     batch_size: Option<u32>,
     max_lines_per_blob: Option<u32>,
+    backend: Option<ContextBackend>,
 ) -> Result<(), String> {
     use std::collections::HashMap;
     use std::fs;
 
-    info!("Saving acemcp config: base_url={}", base_url);
+    let backend = backend.unwrap_or_default();
+    info!(
+        "Saving acemcp config: base_url={}, backend={}",
+        base_url,
+        backend.as_str()
+    );
 
     let config_dir = dirs::home_dir()
         .ok_or("Cannot find home directory")?
@@ -1383,7 +1913,7 @@ pub async fn save_acemcp_config(
     // 注意：不再主动创建 .acemcp 目录
     // acemcp 核心进程首次运行时会自动创建此目录和配置文件
     // 如果目录不存在，说明 acemcp 尚未运行，提示用户先测试连接
-    if !config_dir.exists() {
+    if !config_dir.exists() && backend != ContextBackend::Local {
         return Err(format!(
             "配置目录不存在：{:?}\n\n\
             这是因为 acemcp 尚未运行。请先点击「测试连接」按钮，\n\
@@ -1392,6 +1922,10 @@ pub async fn save_acemcp_config(
         ));
     }
 
+    // 本地索引后端不会启动 acemcp，由这里创建目录
+    fs::create_dir_all(&config_dir)
+        .map_err(|e| format!("Failed to create config directory: {}", e))?;
+
     // 读取现有配置（如果存在）
     // 需要正确处理多行数组格式（如 TEXT_EXTENSIONS = [...] 和 EXCLUDE_PATTERNS = [...]）
     let mut existing_entries: HashMap<String, String> = HashMap::new();
@@ -1442,6 +1976,7 @@ pub async fn save_acemcp_config(
                         && key != "TOKEN"
                         && key != "BATCH_SIZE"
                         && key != "MAX_LINES_PER_BLOB"
+                        && key != "BACKEND"
                     {
                         existing_entries.insert(key.to_string(), multiline_content);
                     }
@@ -1451,6 +1986,7 @@ pub async fn save_acemcp_config(
                         && key != "TOKEN"
                         && key != "BATCH_SIZE"
                         && key != "MAX_LINES_PER_BLOB"
+                        && key != "BACKEND"
                     {
                         existing_entries.insert(key.to_string(), line.to_string());
                     }
@@ -1475,6 +2011,8 @@ pub async fn save_acemcp_config(
         toml_content.push_str(&format!("MAX_LINES_PER_BLOB = {}\n", max_lines));
     }
 
+    toml_content.push_str(&format!("BACKEND = \"{}\"\n", backend.as_str()));
+
     // 保留的其他配置（包括多行数组）
     for entry in existing_entries.values() {
         toml_content.push_str(entry);
@@ -1492,6 +2030,9 @@ pub async fn save_acemcp_config(
     fs::write(&config_file, toml_content).map_err(|e| format!("Failed to write config: {}", e))?;
 
     info!("Acemcp config saved to: {:?}", config_file);
+
+    // sidecar 只在启动时读取配置，关闭现有客户端使新配置生效
+    shutdown_all_clients().await;
     Ok(())
 }
 
@@ -1542,6 +2083,7 @@ pub async fn load_acemcp_config() -> Result<AcemcpConfigData, String> {
     let mut token = String::new();
     let mut batch_size = None;
     let mut max_lines_per_blob = None;
+    let mut backend = ContextBackend::default();
 
     for line in content.lines() {
         let line = line.trim();
@@ -1561,6 +2103,10 @@ pub async fn load_acemcp_config() -> Result<AcemcpConfigData, String> {
             if let Some(value) = extract_toml_number_value(line) {
                 max_lines_per_blob = Some(value);
             }
+        } else if line.starts_with("BACKEND") {
+            if let Some(value) = extract_toml_string_value(line) {
+                backend = ContextBackend::from_config_value(&value);
+            }
         }
     }
 
@@ -1570,6 +2116,7 @@ pub async fn load_acemcp_config() -> Result<AcemcpConfigData, String> {
         token,
         batch_size,
         max_lines_per_blob,
+        backend,
     })
 }
 
@@ -1640,20 +2187,35 @@ pub async fn preindex_project(app: AppHandle, project_path: String) -> Result<()
 async fn preindex_project_internal(app: &AppHandle, project_path: &str) -> Result<()> {
     info!("🔄 Pre-indexing project: {}", project_path);
 
-    // 启动 acemcp 客户端
-    let mut client = AcemcpClient::start(app).await?;
+    // 本地索引后端：只刷新磁盘上的索引
+    let backend = load_acemcp_config()
+        .await
+        .map(|config| config.backend)
+        .unwrap_or_default();
+    if backend == ContextBackend::Local {
+        let project_path = project_path.to_string();
+        tokio::task::spawn_blocking(move || local_index::preindex(&project_path))
+            .await?
+            .map_err(|e| anyhow::anyhow!(e))?;
+        return Ok(());
+    }
 
-    // 初始化 MCP 会话
-    client.initialize().await?;
+    // 租用项目的常驻客户端，索引完成后留给后续的增强请求复用
+    let mut pooled = acquire_client(project_path).await;
+    let client = pooled
+        .ensure_running(app)
+        .await
+        .map_err(|e| anyhow::anyhow!(e))?;
 
     // 调用 search_context，触发自动索引
     // 使用一个通用的查询来触发索引，不关心搜索结果
-    let _ = client
+    if client
         .search_context(project_path, "preindex initialization")
-        .await;
-
-    // 关闭客户端
-    client.shutdown().await?;
+        .await
+        .is_err()
+    {
+        pooled.discard_if_dead();
+    }
 
     Ok(())
 }
@@ -1741,3 +2303,92 @@ pub async fn get_extracted_sidecar_path() -> Result<Option<String>, String> {
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn new_enhancement_or_cancel_supersedes_running_search() {
+        let project = "/tmp/acemcp-cancellation-test";
+
+        let first = EnhancementCancellation::begin(project);
+        assert!(!first.is_cancelled());
+
+        let second = EnhancementCancellation::begin(project);
+        assert!(first.is_cancelled());
+        assert!(!second.is_cancelled());
+        first.cancelled().await;
+
+        let waiting = tokio::spawn({
+            let second = second.clone();
+            async move { second.cancelled().await }
+        });
+        assert!(EnhancementCancellation::cancel(project));
+        tokio::time::timeout(Duration::from_secs(1), waiting)
+            .await
+            .expect("cancellation was not observed")
+            .unwrap();
+        assert!(second.is_cancelled());
+
+        assert!(!EnhancementCancellation::cancel(
+            "/tmp/acemcp-unknown-project"
+        ));
+    }
+
+    #[test]
+    fn search_results_become_cited_snippets() {
+        let text = "The following code sections were retrieved:\n\
+            Path: src/lib.rs\n    12\tfn parse() {}\n    13\t}\n\
+            Path: README.md\nUsage notes\n";
+        let snippets = parse_search_result(text, 2, "parse");
+        assert_eq!(snippets.len(), 2);
+        assert_eq!(snippets[0].citation(), "src/lib.rs:12-13");
+        assert_eq!(
+            (snippets[0].round, snippets[0].query.as_str()),
+            (2, "parse")
+        );
+        assert_eq!(snippets[1].citation(), "README.md");
+        assert!(
+            parse_search_result("No relevant code context found for your query.", 1, "x")
+                .is_empty()
+        );
+
+        let context = format_context_snippets(ContextBackend::Local, &snippets);
+        assert!(context.contains("[1] Path: src/lib.rs:12-13\n    12\tfn parse() {}"));
+        assert!(context.ends_with("[2] Path: README.md\nUsage notes"));
+
+        // 超出长度时保留完整片段，而不是截断下一段
+        let first_only = context.find("\n\n[2]").unwrap();
+        let fitted = fit_snippets(snippets.clone(), ContextBackend::Local, first_only);
+        assert_eq!(fitted.len(), 1);
+        assert_eq!(fitted[0].path, "src/lib.rs");
+        let fitted = fit_snippets(snippets, ContextBackend::Local, context.len());
+        assert_eq!(fitted.len(), 2);
+
+        // 截断标记、标题和引用都计入长度限制
+        let long = vec![ContextSnippet::new(
+            "src/big.rs".to_string(),
+            Some((1, 400)),
+            None,
+            1,
+            "big",
+            (1..=400)
+                .map(|n| format!("{:>6}\tlet value_{} = {};", n, n, n))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )];
+        for max_length in [200, 1000, 3000] {
+            let fitted = fit_snippets(long.clone(), ContextBackend::Acemcp, max_length);
+            assert!(fitted[0].content.ends_with(TRUNCATION_MARKER));
+            let context = format_context_snippets(ContextBackend::Acemcp, &fitted);
+            assert!(
+                context.len() <= max_length,
+                "{} > {}",
+                context.len(),
+                max_length
+            );
+        }
+        assert!(fit_snippets(long, ContextBackend::Acemcp, 40).is_empty());
+    }
+}
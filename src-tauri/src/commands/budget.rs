@@ -0,0 +1,619 @@
+//! Budget Limits
+//!
+//! Daily or monthly spend limits for everything, a project, a model or a
+//! provider config. The runners hand each usage report they see on the stream
+//! to [`record_usage`], which prices it, appends it to the `budget_ledger`
+//! table in agents.db and checks the matching rules. Budgets therefore count
+//! sessions started from the app, as they run.
+//!
+//! Crossing a warning threshold emits a `budget-alert` event once per rule and
+//! period. When a rule with `hard_cap` is exceeded the alert is marked as
+//! cancelling and the caller stops the session.
+
+use chrono::{DateTime, Datelike, Local, SecondsFormat, Utc};
+use once_cell::sync::Lazy;
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use super::pricing::{price_book, TokenCounts};
+use super::session_timeline::SessionEngine;
+use super::storage::AgentDb;
+use super::usage_ingest::local_midnight_utc;
+use crate::utils::config_utils::{load_json_config, save_json_config};
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// What a budget rule counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetScope {
+    /// Every session
+    Global,
+    /// Sessions whose working directory is `target`
+    Project,
+    /// Models whose name contains `target` (case-insensitive)
+    Model,
+    /// Sessions run through the provider config with id `target`
+    Provider,
+}
+
+/// Window a budget resets on (local calendar)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetPeriod {
+    Daily,
+    Monthly,
+}
+
+impl BudgetPeriod {
+    /// Start of the current period as a UTC timestamp
+    fn current_start(&self) -> String {
+        let today = Local::now().date_naive();
+        let first_day = match self {
+            BudgetPeriod::Daily => today,
+            BudgetPeriod::Monthly => today.with_day(1).unwrap_or(today),
+        };
+        local_midnight_utc(first_day)
+    }
+}
+
+fn default_thresholds() -> Vec<f64> {
+    vec![0.5, 0.8, 1.0]
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// A single spend limit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetRule {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub scope: BudgetScope,
+    /// Project path, model name or provider config id (unused for `global`)
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Only count one engine; all engines when omitted
+    #[serde(default)]
+    pub engine: Option<SessionEngine>,
+    pub period: BudgetPeriod,
+    pub limit_usd: f64,
+    /// Fractions of the limit that raise a warning
+    #[serde(default = "default_thresholds")]
+    pub warn_thresholds: Vec<f64>,
+    /// Cancel the running session once the limit is exceeded
+    #[serde(default)]
+    pub hard_cap: bool,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+/// Persisted in `~/.anycode/budgets.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetConfig {
+    #[serde(default)]
+    pub rules: Vec<BudgetRule>,
+}
+
+/// Usage reported by a running session
+#[derive(Debug, Clone)]
+pub struct UsageSample {
+    pub engine: SessionEngine,
+    pub session_id: String,
+    pub project_path: String,
+    pub model: String,
+    pub provider_id: Option<String>,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+}
+
+impl UsageSample {
+    /// Prices the sample with the shared price table
+    fn cost(&self) -> f64 {
+        price_book().cost(
+            self.engine,
+            &self.model,
+            self.provider_id.as_deref(),
+            None,
+            &TokenCounts {
+                input: self.input_tokens,
+                output: self.output_tokens,
+                cache_write: self.cache_creation_tokens,
+                cache_read: self.cache_read_tokens,
+            },
+        )
+    }
+}
+
+/// Payload of the `budget-alert` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetAlert {
+    pub rule_id: String,
+    pub rule_name: Option<String>,
+    pub scope: BudgetScope,
+    pub target: Option<String>,
+    pub period: BudgetPeriod,
+    pub limit_usd: f64,
+    pub spent_usd: f64,
+    /// Highest threshold reached (fraction of the limit)
+    pub threshold: f64,
+    pub exceeded: bool,
+    /// The session that reported this usage is being cancelled
+    pub cancelled: bool,
+    pub engine: SessionEngine,
+    pub session_id: String,
+}
+
+/// Current spend of one rule
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetStatus {
+    pub rule: BudgetRule,
+    pub period_start: String,
+    pub spent_usd: f64,
+    pub remaining_usd: f64,
+    pub ratio: f64,
+}
+
+/// Highest threshold already announced, keyed by rule id and period start
+#[derive(Default)]
+pub struct BudgetState(pub Mutex<HashMap<String, f64>>);
+
+// ============================================================================
+// Config Persistence
+// ============================================================================
+
+fn get_budget_config_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Failed to get home directory")?;
+    Ok(home.join(".anycode").join("budgets.json"))
+}
+
+pub fn load_budget_config() -> Result<BudgetConfig, String> {
+    load_json_config(get_budget_config_path()?)
+}
+
+/// Budget rules checked on every usage sample, refreshed when they are saved
+static BUDGET_CONFIG: Lazy<Mutex<Option<Arc<BudgetConfig>>>> = Lazy::new(|| Mutex::new(None));
+
+fn budget_config() -> Arc<BudgetConfig> {
+    let mut cached = BUDGET_CONFIG.lock().unwrap_or_else(|e| e.into_inner());
+    cached
+        .get_or_insert_with(|| {
+            let config = load_budget_config().unwrap_or_else(|e| {
+                log::warn!("[Budget] Failed to load budgets: {}", e);
+                BudgetConfig::default()
+            });
+            Arc::new(config)
+        })
+        .clone()
+}
+
+fn validate_rule(rule: &mut BudgetRule) -> Result<(), String> {
+    if rule.id.trim().is_empty() {
+        rule.id = uuid::Uuid::new_v4().to_string();
+    }
+    if !rule.limit_usd.is_finite() || rule.limit_usd <= 0.0 {
+        return Err(format!("Budget '{}' needs a positive limit", rule.id));
+    }
+    if rule.scope != BudgetScope::Global
+        && rule
+            .target
+            .as_deref()
+            .map(str::trim)
+            .unwrap_or("")
+            .is_empty()
+    {
+        return Err(format!(
+            "Budget '{}' needs a target for {:?} scope",
+            rule.id, rule.scope
+        ));
+    }
+    if rule
+        .warn_thresholds
+        .iter()
+        .any(|t| !t.is_finite() || *t <= 0.0)
+    {
+        return Err(format!(
+            "Budget '{}' has a non-positive warning threshold",
+            rule.id
+        ));
+    }
+    rule.warn_thresholds.sort_by(f64::total_cmp);
+    rule.warn_thresholds.dedup();
+    Ok(())
+}
+
+// ============================================================================
+// Ledger
+// ============================================================================
+
+/// Creates the spend ledger (called from `storage::init_database`)
+pub fn init_budget_tables(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS budget_ledger (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            engine TEXT NOT NULL,
+            session_id TEXT NOT NULL,
+            project_path TEXT NOT NULL,
+            model TEXT NOT NULL,
+            provider_id TEXT,
+            cost REAL NOT NULL,
+            timestamp TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_budget_ledger_timestamp
+         ON budget_ledger(timestamp)",
+        [],
+    )?;
+    Ok(())
+}
+
+fn normalize_project_path(path: &str) -> String {
+    path.replace('\\', "/").trim_end_matches('/').to_string()
+}
+
+fn insert_ledger(conn: &Connection, sample: &UsageSample, cost: f64) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO budget_ledger
+            (engine, session_id, project_path, model, provider_id, cost, timestamp)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            sample.engine.as_str(),
+            sample.session_id,
+            normalize_project_path(&sample.project_path),
+            sample.model,
+            sample.provider_id,
+            cost,
+            Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        ],
+    )
+    .map_err(|e| format!("Failed to record budget spend: {}", e))?;
+    Ok(())
+}
+
+/// Provider configs the app ran each session with, from the ledger. Usage
+/// ingestion prices transcript rows with them.
+#[derive(Debug, Default)]
+pub struct RunProviders(HashMap<String, Vec<(i64, Option<String>)>>);
+
+impl RunProviders {
+    /// Runs of `engine` in the ledger; empty when it cannot be read
+    pub fn load(conn: &Connection, engine: SessionEngine) -> Self {
+        let rows = conn
+            .prepare(
+                "SELECT session_id, provider_id, timestamp FROM budget_ledger
+                 WHERE engine = ?1 AND session_id != ''",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map([engine.as_str()], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, Option<String>>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                })?
+                .collect::<SqliteResult<Vec<_>>>()
+            });
+
+        let mut runs: HashMap<String, Vec<(i64, Option<String>)>> = HashMap::new();
+        match rows {
+            Ok(rows) => {
+                for (session_id, provider_id, timestamp) in rows {
+                    if let Some(at) = unix_millis(&timestamp) {
+                        runs.entry(session_id).or_default().push((at, provider_id));
+                    }
+                }
+            }
+            Err(e) => log::warn!("[Budget] Failed to read run providers: {}", e),
+        }
+        Self(runs)
+    }
+
+    /// Provider of the run of `session_id` recorded closest to `timestamp`
+    pub fn provider_at(&self, session_id: &str, timestamp: &str) -> Option<String> {
+        let at = unix_millis(timestamp).unwrap_or(0);
+        self.0
+            .get(session_id)?
+            .iter()
+            .min_by_key(|(recorded, _)| (recorded - at).abs())
+            .and_then(|(_, provider_id)| provider_id.clone())
+    }
+}
+
+fn unix_millis(timestamp: &str) -> Option<i64> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|dt| dt.timestamp_millis())
+}
+
+impl BudgetRule {
+    /// Whether usage from `sample` counts towards this rule
+    fn matches(&self, sample: &UsageSample) -> bool {
+        if self.engine.is_some_and(|engine| engine != sample.engine) {
+            return false;
+        }
+        let target = self.target.as_deref().unwrap_or("");
+        match self.scope {
+            BudgetScope::Global => true,
+            BudgetScope::Project => {
+                normalize_project_path(target) == normalize_project_path(&sample.project_path)
+            }
+            BudgetScope::Model => sample.model.to_lowercase().contains(&target.to_lowercase()),
+            BudgetScope::Provider => sample.provider_id.as_deref() == Some(target),
+        }
+    }
+
+    /// Spend recorded for this rule since `since`
+    fn spent_since(&self, conn: &Connection, since: &str) -> Result<f64, String> {
+        let target = self.target.as_deref();
+        conn.query_row(
+            "SELECT COALESCE(SUM(cost), 0) FROM budget_ledger
+             WHERE timestamp >= ?1
+               AND (?2 IS NULL OR engine = ?2)
+               AND (?3 IS NULL OR project_path = ?3)
+               AND (?4 IS NULL OR instr(lower(model), lower(?4)) > 0)
+               AND (?5 IS NULL OR provider_id = ?5)",
+            params![
+                since,
+                self.engine.map(|e| e.as_str()),
+                (self.scope == BudgetScope::Project)
+                    .then(|| target.map(normalize_project_path))
+                    .flatten(),
+                target.filter(|_| self.scope == BudgetScope::Model),
+                target.filter(|_| self.scope == BudgetScope::Provider),
+            ],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to query budget spend: {}", e))
+    }
+
+    /// Highest warning threshold reached at `spent`
+    fn reached_threshold(&self, spent: f64) -> Option<f64> {
+        let ratio = spent / self.limit_usd;
+        self.warn_thresholds
+            .iter()
+            .copied()
+            .filter(|t| ratio >= *t)
+            .reduce(f64::max)
+    }
+}
+
+// ============================================================================
+// Live Checks
+// ============================================================================
+
+/// Records usage from a running session and evaluates the budgets it affects.
+///
+/// Returns the alert to act on when a hard cap is exceeded; the caller is
+/// expected to cancel the session. Failures are logged and never interrupt
+/// the stream.
+pub fn record_usage(app: &AppHandle, sample: &UsageSample) -> Option<BudgetAlert> {
+    let cost = sample.cost();
+    if cost <= 0.0 {
+        return None;
+    }
+
+    let config = budget_config();
+
+    let db = app.try_state::<AgentDb>()?;
+    let conn = match db.0.lock() {
+        Ok(conn) => conn,
+        Err(e) => {
+            log::warn!("[Budget] Database lock poisoned: {}", e);
+            return None;
+        }
+    };
+    if let Err(e) = insert_ledger(&conn, sample, cost) {
+        log::warn!("[Budget] {}", e);
+        return None;
+    }
+
+    let state = app.try_state::<BudgetState>();
+    let mut cancel = None;
+
+    for rule in config
+        .rules
+        .iter()
+        .filter(|r| r.enabled && r.limit_usd > 0.0 && r.matches(sample))
+    {
+        let since = rule.period.current_start();
+        let spent = match rule.spent_since(&conn, &since) {
+            Ok(spent) => spent,
+            Err(e) => {
+                log::warn!("[Budget] {}", e);
+                continue;
+            }
+        };
+
+        let exceeded = spent >= rule.limit_usd;
+        let cancelling = exceeded && rule.hard_cap;
+        let reached = rule.reached_threshold(spent);
+
+        // Announce each threshold once per period
+        let newly_reached = match (reached, &state) {
+            (Some(threshold), Some(state)) => {
+                let mut announced = state.0.lock().unwrap_or_else(|e| e.into_inner());
+                let key = format!("{}:{}", rule.id, since);
+                let previous = announced.get(&key).copied().unwrap_or(0.0);
+                if threshold > previous {
+                    announced.insert(key, threshold);
+                    true
+                } else {
+                    false
+                }
+            }
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+
+        if !newly_reached && !cancelling {
+            continue;
+        }
+
+        let alert = BudgetAlert {
+            rule_id: rule.id.clone(),
+            rule_name: rule.name.clone(),
+            scope: rule.scope,
+            target: rule.target.clone(),
+            period: rule.period,
+            limit_usd: rule.limit_usd,
+            spent_usd: spent,
+            threshold: reached.unwrap_or(spent / rule.limit_usd),
+            exceeded,
+            cancelled: cancelling,
+            engine: sample.engine,
+            session_id: sample.session_id.clone(),
+        };
+
+        log::warn!(
+            "[Budget] Rule {} at ${:.4} of ${:.2} ({} session {}){}",
+            rule.id,
+            spent,
+            rule.limit_usd,
+            sample.engine.as_str(),
+            sample.session_id,
+            if cancelling { ", cancelling" } else { "" }
+        );
+        if let Err(e) = app.emit("budget-alert", &alert) {
+            log::warn!("[Budget] Failed to emit budget-alert: {}", e);
+        }
+
+        if cancelling && cancel.is_none() {
+            cancel = Some(alert);
+        }
+    }
+
+    cancel
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Loads the budget rules
+#[tauri::command]
+pub async fn get_budget_config() -> Result<BudgetConfig, String> {
+    load_budget_config()
+}
+
+/// Validates and saves the budget rules, returning them with ids assigned
+#[tauri::command]
+pub async fn save_budget_config(mut config: BudgetConfig) -> Result<BudgetConfig, String> {
+    for rule in &mut config.rules {
+        validate_rule(rule)?;
+    }
+    save_json_config(&config, get_budget_config_path()?)?;
+    *BUDGET_CONFIG.lock().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(config.clone()));
+    log::info!("[Budget] Saved {} budget rule(s)", config.rules.len());
+    Ok(config)
+}
+
+/// Spend of every rule in its current period
+#[tauri::command]
+pub async fn get_budget_status(db: State<'_, AgentDb>) -> Result<Vec<BudgetStatus>, String> {
+    let config = load_budget_config()?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    config
+        .rules
+        .into_iter()
+        .map(|rule| {
+            let period_start = rule.period.current_start();
+            let spent_usd = rule.spent_since(&conn, &period_start)?;
+            let ratio = if rule.limit_usd > 0.0 {
+                spent_usd / rule.limit_usd
+            } else {
+                0.0
+            };
+            Ok(BudgetStatus {
+                remaining_usd: (rule.limit_usd - spent_usd).max(0.0),
+                rule,
+                period_start,
+                spent_usd,
+                ratio,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(scope: BudgetScope, target: Option<&str>) -> BudgetRule {
+        BudgetRule {
+            id: "r".to_string(),
+            name: None,
+            scope,
+            target: target.map(|t| t.to_string()),
+            engine: None,
+            period: BudgetPeriod::Daily,
+            limit_usd: 10.0,
+            warn_thresholds: default_thresholds(),
+            hard_cap: false,
+            enabled: true,
+        }
+    }
+
+    fn sample(engine: SessionEngine, project: &str, model: &str) -> UsageSample {
+        UsageSample {
+            engine,
+            session_id: "s".to_string(),
+            project_path: project.to_string(),
+            model: model.to_string(),
+            provider_id: Some("relay".to_string()),
+            input_tokens: 0,
+            output_tokens: 0,
+            cache_creation_tokens: 0,
+            cache_read_tokens: 0,
+        }
+    }
+
+    #[test]
+    fn rules_count_only_matching_spend() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_budget_tables(&conn).unwrap();
+
+        let claude = sample(SessionEngine::Claude, "/work/app/", "claude-sonnet-4-5");
+        let codex = sample(SessionEngine::Codex, "/work/other", "gpt-5.1-codex");
+        insert_ledger(&conn, &claude, 4.0).unwrap();
+        insert_ledger(&conn, &codex, 2.5).unwrap();
+
+        let since = "1970-01-01T00:00:00.000Z";
+        let project = rule(BudgetScope::Project, Some("/work/app"));
+        assert!(project.matches(&claude));
+        assert!(!project.matches(&codex));
+        assert_eq!(project.spent_since(&conn, since).unwrap(), 4.0);
+
+        let model = rule(BudgetScope::Model, Some("CODEX"));
+        assert_eq!(model.spent_since(&conn, since).unwrap(), 2.5);
+
+        let mut provider = rule(BudgetScope::Provider, Some("relay"));
+        assert_eq!(provider.spent_since(&conn, since).unwrap(), 6.5);
+        provider.engine = Some(SessionEngine::Claude);
+        assert!(!provider.matches(&codex));
+        assert_eq!(provider.spent_since(&conn, since).unwrap(), 4.0);
+
+        let global = rule(BudgetScope::Global, None);
+        assert_eq!(global.reached_threshold(6.5), Some(0.5));
+        assert_eq!(global.reached_threshold(8.0), Some(0.8));
+        assert_eq!(global.reached_threshold(12.0), Some(1.0));
+        assert_eq!(global.reached_threshold(1.0), None);
+    }
+}
@@ -0,0 +1,771 @@
+//! Rewind Checkpoints
+//!
+//! Snapshots of the project directory taken before and after every prompt, so
+//! the prompt trackers can undo what an agent changed without committing to
+//! the user's branch. A snapshot is built in a throwaway index file, written
+//! as a commit object and kept alive by a private ref
+//! `refs/anycode/checkpoints/<session>/<n>`; `HEAD`, the branch and the real
+//! index are never touched.
+//!
+//! Projects inside a git repository keep their checkpoints in that repository.
+//! Other directories get a private bare repository under
+//! `~/.anycode/checkpoints/<name>-<hash>.git` instead of a `git init` in the
+//! project.
+//!
+//! Reverting applies the reverse diff of a checkpoint range to the working
+//! tree, so later edits to other lines or files survive. Checkpoint ids are
+//! plain commit hashes: records written before checkpoints existed hold real
+//! commits of the project repository and revert the same way.
+//!
+//! Refs are deleted with the prompt records or the session they belong to, and
+//! `prune_checkpoints` drops old ones; git collects the unreachable commits.
+
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+use super::simple_git::{self, RevertResult};
+
+/// Namespace of the checkpoint refs
+const CHECKPOINT_REF_PREFIX: &str = "refs/anycode/checkpoints";
+
+/// Identity of the checkpoint commits; they never show up on a branch
+const CHECKPOINT_AUTHOR_NAME: &str = "Any Code Checkpoint";
+const CHECKPOINT_AUTHOR_EMAIL: &str = "checkpoint@anycode.local";
+
+/// Per-file patches larger than this are cut off in [`CheckpointFileChange::patch`]
+const MAX_FILE_PATCH_BYTES: usize = 256 * 1024;
+
+/// A file changed between two checkpoints
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckpointFileChange {
+    /// Path relative to the repository root
+    pub path: String,
+    /// Git status letter (`A`, `M`, `D`, `T`)
+    pub status: String,
+    /// Line counts; `None` for binary files
+    pub additions: Option<usize>,
+    pub deletions: Option<usize>,
+    pub patch: String,
+    pub truncated: bool,
+}
+
+// ============================================================================
+// Checkpoint Store
+// ============================================================================
+
+/// Git directory holding the checkpoints of a project and the work tree they
+/// snapshot
+struct CheckpointStore {
+    git_dir: PathBuf,
+    work_tree: PathBuf,
+    /// Index of the project repository, used to seed the snapshot index
+    seed_index: Option<PathBuf>,
+}
+
+impl CheckpointStore {
+    fn open(project_path: &str) -> Result<Self, String> {
+        let home = dirs::home_dir().ok_or("Failed to get home directory")?;
+        Self::open_in(project_path, &home.join(".anycode").join("checkpoints"))
+    }
+
+    /// Uses the project's repository, or a private one under `private_root`
+    fn open_in(project_path: &str, private_root: &Path) -> Result<Self, String> {
+        let store = Self::locate_in(project_path, private_root);
+        if !store.exists() {
+            std::fs::create_dir_all(&store.git_dir)
+                .map_err(|e| format!("Failed to create checkpoint store: {}", e))?;
+            simple_git::git_output(
+                project_path,
+                &["init", "-q", "--bare", &path_arg(&store.git_dir)],
+            )?;
+            log::info!(
+                "[Checkpoint] Created private store {:?} for {}",
+                store.git_dir,
+                project_path
+            );
+        }
+        Ok(store)
+    }
+
+    /// The store of a project, if it has one; never creates a private store
+    fn open_existing(project_path: &str) -> Result<Option<Self>, String> {
+        let home = dirs::home_dir().ok_or("Failed to get home directory")?;
+        let store = Self::locate_in(project_path, &home.join(".anycode").join("checkpoints"));
+        Ok(store.exists().then_some(store))
+    }
+
+    fn exists(&self) -> bool {
+        self.git_dir.join("HEAD").exists()
+    }
+
+    /// Where the checkpoints of a project live, without touching the disk
+    fn locate_in(project_path: &str, private_root: &Path) -> Self {
+        if let Ok(paths) = simple_git::git_output(
+            project_path,
+            &["rev-parse", "--show-toplevel", "--absolute-git-dir"],
+        ) {
+            let mut lines = paths.lines();
+            if let (Some(work_tree), Some(git_dir)) = (lines.next(), lines.next()) {
+                let git_dir = PathBuf::from(git_dir);
+                return Self {
+                    seed_index: Some(git_dir.join("index")),
+                    git_dir,
+                    work_tree: PathBuf::from(work_tree),
+                };
+            }
+        }
+
+        let project = Path::new(project_path);
+        let name = project
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "project".to_string());
+        let digest = format!(
+            "{:x}",
+            md5::compute(super::claude::normalize_path_for_comparison(project_path))
+        );
+        Self {
+            git_dir: private_root.join(format!("{}-{}.git", name, &digest[..8])),
+            work_tree: project.to_path_buf(),
+            seed_index: None,
+        }
+    }
+
+    fn command(&self, args: &[&str], index: Option<&Path>) -> Command {
+        let mut cmd = Command::new("git");
+        cmd.arg(format!("--git-dir={}", path_arg(&self.git_dir)))
+            .arg(format!("--work-tree={}", path_arg(&self.work_tree)))
+            .args(args)
+            .current_dir(&self.work_tree)
+            .env("GIT_AUTHOR_NAME", CHECKPOINT_AUTHOR_NAME)
+            .env("GIT_AUTHOR_EMAIL", CHECKPOINT_AUTHOR_EMAIL)
+            .env("GIT_COMMITTER_NAME", CHECKPOINT_AUTHOR_NAME)
+            .env("GIT_COMMITTER_EMAIL", CHECKPOINT_AUTHOR_EMAIL);
+        if let Some(index) = index {
+            cmd.env("GIT_INDEX_FILE", index);
+        }
+
+        #[cfg(target_os = "windows")]
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+        cmd
+    }
+
+    /// Runs git against the store and returns its raw stdout
+    fn run(
+        &self,
+        args: &[&str],
+        index: Option<&Path>,
+        input: Option<&[u8]>,
+    ) -> Result<Vec<u8>, String> {
+        let mut child = self
+            .command(args, index)
+            .stdin(if input.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run git {}: {}", args.join(" "), e))?;
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            stdin
+                .write_all(input)
+                .map_err(|e| format!("Failed to write to git {}: {}", args.join(" "), e))?;
+        }
+        let output = child
+            .wait_with_output()
+            .map_err(|e| format!("Failed to run git {}: {}", args.join(" "), e))?;
+
+        if !output.status.success() {
+            return Err(format!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(output.stdout)
+    }
+
+    /// Runs git against the store and returns its trimmed stdout
+    fn git(&self, args: &[&str], index: Option<&Path>) -> Result<String, String> {
+        let stdout = self.run(args, index, None)?;
+        Ok(String::from_utf8_lossy(&stdout).trim_end().to_string())
+    }
+
+    /// Tree of the current working directory, written without touching the
+    /// real index
+    fn snapshot_tree(&self) -> Result<String, String> {
+        let index = self.git_dir.join(format!(
+            "anycode-checkpoint-{}.index",
+            uuid::Uuid::new_v4().simple()
+        ));
+        // Starting from the real index lets `git add` skip unchanged files
+        if let Some(seed) = self.seed_index.as_ref().filter(|seed| seed.exists()) {
+            let _ = std::fs::copy(seed, &index);
+        }
+
+        let tree = self
+            .git(&["add", "-A"], Some(&index))
+            .and_then(|_| self.git(&["write-tree"], Some(&index)));
+        let _ = std::fs::remove_file(&index);
+        tree
+    }
+
+    /// Checkpoint refs under `prefix` with their commit and its unix time
+    fn refs(&self, prefix: &str) -> Result<Vec<(String, String, i64)>, String> {
+        let refs = self.git(
+            &[
+                "for-each-ref",
+                "--format=%(refname) %(objectname) %(committerdate:unix)",
+                prefix,
+            ],
+            None,
+        )?;
+        Ok(refs
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.split(' ');
+                let name = parts.next()?.to_string();
+                let commit = parts.next()?.to_string();
+                let time = parts.next()?.parse().unwrap_or(0);
+                Some((name, commit, time))
+            })
+            .collect())
+    }
+
+    /// Deletes refs in one transaction and lets git collect what they kept alive
+    fn delete_refs(&self, names: &[String]) -> Result<usize, String> {
+        if names.is_empty() {
+            return Ok(0);
+        }
+        let input: String = names
+            .iter()
+            .map(|name| format!("delete {}\n", name))
+            .collect();
+        self.run(&["update-ref", "--stdin"], None, Some(input.as_bytes()))?;
+        let _ = self.git(&["gc", "--auto", "--quiet"], None);
+        Ok(names.len())
+    }
+
+    /// Deletes the session's checkpoint refs that don't point at a `keep` commit
+    fn retain(&self, session_id: &str, keep: &[&str]) -> Result<usize, String> {
+        let stale: Vec<String> = self
+            .refs(&session_ref(session_id))?
+            .into_iter()
+            .filter(|(_, commit, _)| !keep.contains(&commit.as_str()))
+            .map(|(name, _, _)| name)
+            .collect();
+        self.delete_refs(&stale)
+    }
+
+    /// Deletes the checkpoint refs of all sessions committed before `cutoff`
+    fn prune_before(&self, cutoff: i64) -> Result<usize, String> {
+        let stale: Vec<String> = self
+            .refs(CHECKPOINT_REF_PREFIX)?
+            .into_iter()
+            .filter(|(_, _, time)| *time < cutoff)
+            .map(|(name, _, _)| name)
+            .collect();
+        self.delete_refs(&stale)
+    }
+
+    /// Latest checkpoint number and commit of a session
+    fn latest(&self, session_ref: &str) -> Result<Option<(u64, String)>, String> {
+        let refs = self.git(
+            &[
+                "for-each-ref",
+                "--format=%(refname) %(objectname)",
+                session_ref,
+            ],
+            None,
+        )?;
+        Ok(refs
+            .lines()
+            .filter_map(|line| {
+                let (name, commit) = line.split_once(' ')?;
+                let n = name.rsplit('/').next()?.parse::<u64>().ok()?;
+                Some((n, commit.to_string()))
+            })
+            .max_by_key(|(n, _)| *n))
+    }
+
+    fn create(&self, session_id: &str, message: &str) -> Result<String, String> {
+        let tree = self.snapshot_tree()?;
+        let session_ref = session_ref(session_id);
+        let latest = self.latest(&session_ref)?;
+
+        // The first checkpoint of a session hangs off the branch it started on
+        let parent = match &latest {
+            Some((_, commit)) => Some(commit.clone()),
+            None => self
+                .git(&["rev-parse", "--verify", "-q", "HEAD^{commit}"], None)
+                .ok(),
+        };
+        let mut args = vec!["commit-tree", tree.as_str(), "-m", message];
+        if let Some(parent) = &parent {
+            args.extend(["-p", parent.as_str()]);
+        }
+        let commit = self.git(&args, None)?;
+
+        let n = latest.map(|(n, _)| n + 1).unwrap_or(0);
+        let name = format!("{}/{}", session_ref, n);
+        self.git(&["update-ref", &name, &commit], None)?;
+
+        log::info!(
+            "[Checkpoint] {} -> {} ({})",
+            name,
+            &commit[..8.min(commit.len())],
+            message
+        );
+        Ok(commit)
+    }
+
+    fn has_changes(&self, from: &str, to: &str) -> Result<bool, String> {
+        let output = self
+            .command(&["diff", "--quiet", from, to], None)
+            .output()
+            .map_err(|e| format!("Failed to diff checkpoints: {}", e))?;
+        match output.status.code() {
+            Some(0) => Ok(false),
+            Some(1) => Ok(true),
+            _ => Err(format!(
+                "Git diff failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            )),
+        }
+    }
+
+    /// Binary diff turning `from` into `to`, limited to `paths` when given
+    fn patch(&self, from: &str, to: &str, paths: Option<&[String]>) -> Result<Vec<u8>, String> {
+        let pathspecs: Vec<String> = paths
+            .unwrap_or_default()
+            .iter()
+            .map(|path| literal_pathspec(path))
+            .collect();
+        let mut args = vec!["diff", "--binary", "--full-index", "--no-renames", from, to];
+        if paths.is_some() {
+            args.push("--");
+            args.extend(pathspecs.iter().map(String::as_str));
+        }
+
+        self.run(&args, None, None)
+    }
+
+    /// Applies a patch from [`Self::patch`] to the working tree and tells
+    /// whether there was anything to apply. Nothing is written when any hunk
+    /// does not apply.
+    fn apply(&self, patch: &[u8]) -> Result<bool, String> {
+        if patch.is_empty() {
+            return Ok(false);
+        }
+        self.run(&["apply", "--whitespace=nowarn", "-"], None, Some(patch))
+            .map(|_| true)
+    }
+
+    fn changed_files(&self, from: &str, to: &str) -> Result<Vec<CheckpointFileChange>, String> {
+        let numstat = self.git(&["diff", "--numstat", "-z", "--no-renames", from, to], None)?;
+        let counts: Vec<(&str, Option<usize>, Option<usize>)> = numstat
+            .split('\0')
+            .filter_map(|entry| {
+                let mut parts = entry.splitn(3, '\t');
+                let additions = parts.next()?.parse().ok();
+                let deletions = parts.next()?.parse().ok();
+                Some((parts.next()?, additions, deletions))
+            })
+            .collect();
+
+        let name_status = self.git(
+            &["diff", "--name-status", "-z", "--no-renames", from, to],
+            None,
+        )?;
+        let mut entries = name_status.split('\0').filter(|entry| !entry.is_empty());
+        let mut files = Vec::new();
+        while let (Some(status), Some(path)) = (entries.next(), entries.next()) {
+            let stat = counts.iter().find(|(p, _, _)| *p == path);
+            let mut patch = self.git(
+                &[
+                    "diff",
+                    "--no-renames",
+                    from,
+                    to,
+                    "--",
+                    &literal_pathspec(path),
+                ],
+                None,
+            )?;
+            let truncated = patch.len() > MAX_FILE_PATCH_BYTES;
+            if truncated {
+                let mut end = MAX_FILE_PATCH_BYTES;
+                while !patch.is_char_boundary(end) {
+                    end -= 1;
+                }
+                patch.truncate(end);
+            }
+            files.push(CheckpointFileChange {
+                path: path.to_string(),
+                status: status.to_string(),
+                additions: stat.and_then(|s| s.1),
+                deletions: stat.and_then(|s| s.2),
+                patch,
+                truncated,
+            });
+        }
+        Ok(files)
+    }
+}
+
+/// Path as passed on the git command line
+fn path_arg(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+/// Pathspec matching `path` exactly, without glob expansion
+fn literal_pathspec(path: &str) -> String {
+    format!(":(literal){}", path)
+}
+
+/// Ref namespace of one session's checkpoints
+fn session_ref(session_id: &str) -> String {
+    format!("{}/{}", CHECKPOINT_REF_PREFIX, ref_component(session_id))
+}
+
+/// Session ids as a single ref name component
+fn ref_component(session_id: &str) -> String {
+    let component: String = session_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    if component.is_empty() {
+        "session".to_string()
+    } else {
+        component
+    }
+}
+
+fn short(commit: &str) -> &str {
+    &commit[..8.min(commit.len())]
+}
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Snapshots the project directory and returns the checkpoint id
+pub fn create_checkpoint(
+    project_path: &str,
+    session_id: &str,
+    message: &str,
+) -> Result<String, String> {
+    CheckpointStore::open(project_path)?.create(session_id, message)
+}
+
+/// Whether two checkpoints differ
+pub fn has_changes_between(project_path: &str, from: &str, to: &str) -> Result<bool, String> {
+    CheckpointStore::open(project_path)?.has_changes(from, to)
+}
+
+/// Undoes the changes made between `before` and `after` in the working tree,
+/// leaving later changes that do not overlap in place
+pub fn revert_checkpoint_range(
+    project_path: &str,
+    before: &str,
+    after: &str,
+) -> Result<RevertResult, String> {
+    revert(project_path, before, after, None)
+}
+
+/// Like [`revert_checkpoint_range`], but only for the given repository paths
+pub fn revert_checkpoint_paths(
+    project_path: &str,
+    before: &str,
+    after: &str,
+    paths: &[String],
+) -> Result<RevertResult, String> {
+    revert(project_path, before, after, Some(paths))
+}
+
+fn revert(
+    project_path: &str,
+    before: &str,
+    after: &str,
+    paths: Option<&[String]>,
+) -> Result<RevertResult, String> {
+    log::info!(
+        "[Checkpoint] Reverting {}..{} in {}{}",
+        short(before),
+        short(after),
+        project_path,
+        paths
+            .map(|p| format!(" ({} file(s))", p.len()))
+            .unwrap_or_default()
+    );
+
+    if before == after || paths.is_some_and(|p| p.is_empty()) {
+        return Ok(RevertResult {
+            success: true,
+            commits_reverted: 0,
+            new_commit: None,
+            message: "没有代码更改需要撤回".to_string(),
+            has_conflicts: false,
+        });
+    }
+
+    let store = CheckpointStore::open(project_path)?;
+    let patch = store.patch(after, before, paths)?;
+    match store.apply(&patch) {
+        Ok(applied) => Ok(RevertResult {
+            success: true,
+            commits_reverted: usize::from(applied),
+            new_commit: None,
+            message: if applied {
+                "成功撤回代码更改".to_string()
+            } else {
+                "代码已经处于目标状态，无需更改".to_string()
+            },
+            has_conflicts: false,
+        }),
+        Err(e) => {
+            log::warn!("[Checkpoint] Revert does not apply cleanly: {}", e);
+            Ok(RevertResult {
+                success: false,
+                commits_reverted: 0,
+                new_commit: None,
+                message: format!(
+                    "撤回时发生冲突，无法自动完成。建议手动处理或使用'仅删除对话'模式。\n详情: {}",
+                    e.lines().take(3).collect::<Vec<_>>().join("\n")
+                ),
+                has_conflicts: true,
+            })
+        }
+    }
+}
+
+/// Files changed between two checkpoints, with a diff for each
+pub fn changed_files_between(
+    project_path: &str,
+    from: &str,
+    to: &str,
+) -> Result<Vec<CheckpointFileChange>, String> {
+    CheckpointStore::open(project_path)?.changed_files(from, to)
+}
+
+/// Puts the working tree back into the exact state of a checkpoint
+pub fn restore_checkpoint(project_path: &str, checkpoint: &str) -> Result<(), String> {
+    let store = CheckpointStore::open(project_path)?;
+    let current = store.snapshot_tree()?;
+    let patch = store.patch(&current, checkpoint, None)?;
+    store
+        .apply(&patch)
+        .map_err(|e| format!("Failed to restore checkpoint {}: {}", short(checkpoint), e))?;
+    log::info!(
+        "[Checkpoint] Restored {} in {}",
+        short(checkpoint),
+        project_path
+    );
+    Ok(())
+}
+
+/// Deletes the checkpoints of a session except those in `keep`, e.g. after
+/// the prompt records pointing at them were truncated
+pub fn retain_checkpoints(
+    project_path: &str,
+    session_id: &str,
+    keep: &[&str],
+) -> Result<usize, String> {
+    let Some(store) = CheckpointStore::open_existing(project_path)? else {
+        return Ok(0);
+    };
+    let deleted = store.retain(session_id, keep)?;
+    if deleted > 0 {
+        log::info!(
+            "[Checkpoint] Deleted {} checkpoint(s) of session {}",
+            deleted,
+            session_id
+        );
+    }
+    Ok(deleted)
+}
+
+/// Deletes every checkpoint of a session
+pub fn delete_session_checkpoints(project_path: &str, session_id: &str) -> Result<usize, String> {
+    retain_checkpoints(project_path, session_id, &[])
+}
+
+/// Deletes the checkpoints of all sessions created before `cutoff` (unix seconds)
+fn prune_checkpoints_before(project_path: &str, cutoff: i64) -> Result<usize, String> {
+    match CheckpointStore::open_existing(project_path)? {
+        Some(store) => store.prune_before(cutoff),
+        None => Ok(0),
+    }
+}
+
+/// Deletes checkpoints older than `older_than_days` (30 by default) in a
+/// project; prompts from before then can no longer be rewound
+#[tauri::command]
+pub async fn prune_checkpoints(
+    project_path: String,
+    older_than_days: Option<u64>,
+) -> Result<usize, String> {
+    let days = older_than_days.unwrap_or(30);
+    let cutoff = chrono::Utc::now().timestamp() - (days * 24 * 60 * 60) as i64;
+    let deleted = prune_checkpoints_before(&project_path, cutoff)?;
+    log::info!(
+        "[Checkpoint] Pruned {} checkpoint(s) older than {} day(s) in {}",
+        deleted,
+        days,
+        project_path
+    );
+    Ok(deleted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        simple_git::git_output(dir.to_str().unwrap(), args).unwrap()
+    }
+
+    #[test]
+    fn checkpoints_leave_branch_and_index_alone() {
+        let repo = tempfile::tempdir().unwrap();
+        let private = tempfile::tempdir().unwrap();
+        git(repo.path(), &["init", "-q"]);
+        git(repo.path(), &["config", "user.name", "Test"]);
+        git(repo.path(), &["config", "user.email", "test@example.com"]);
+        std::fs::write(repo.path().join("a.txt"), "one\ntwo\nthree\n").unwrap();
+        git(repo.path(), &["add", "-A"]);
+        git(repo.path(), &["commit", "-q", "-m", "init"]);
+        let head = git(repo.path(), &["rev-parse", "HEAD"]);
+        let project = repo.path().to_str().unwrap();
+        let store = CheckpointStore::open_in(project, private.path()).unwrap();
+
+        let before = store.create("session-1", "before").unwrap();
+        std::fs::write(repo.path().join("a.txt"), "one\nTWO\nthree\n").unwrap();
+        std::fs::write(repo.path().join("new.txt"), "added\n").unwrap();
+        let after = store.create("session-1", "after").unwrap();
+        // A later edit elsewhere in the file survives the revert
+        std::fs::write(repo.path().join("a.txt"), "one\nTWO\nthree\nfour\n").unwrap();
+
+        assert_eq!(git(repo.path(), &["rev-parse", "HEAD"]), head);
+        assert_eq!(git(repo.path(), &["diff", "--cached", "--name-only"]), "");
+        assert_eq!(
+            git(
+                repo.path(),
+                &["rev-parse", "refs/anycode/checkpoints/session-1/1"]
+            ),
+            after
+        );
+        assert!(store.has_changes(&before, &after).unwrap());
+
+        let undo = store.patch(&after, &before, None).unwrap();
+        assert!(store.apply(&undo).unwrap());
+        assert_eq!(
+            std::fs::read_to_string(repo.path().join("a.txt")).unwrap(),
+            "one\ntwo\nthree\nfour\n"
+        );
+        assert!(!repo.path().join("new.txt").exists());
+        assert_eq!(git(repo.path(), &["rev-parse", "HEAD"]), head);
+
+        // Directories outside a repository use the private store
+        let plain = tempfile::tempdir().unwrap();
+        std::fs::write(plain.path().join("notes.md"), "draft\n").unwrap();
+        let plain_store =
+            CheckpointStore::open_in(plain.path().to_str().unwrap(), private.path()).unwrap();
+        let first = plain_store.create("s", "before").unwrap();
+        std::fs::write(plain.path().join("notes.md"), "final\n").unwrap();
+        let current = plain_store.snapshot_tree().unwrap();
+        let restore = plain_store.patch(&current, &first, None).unwrap();
+        assert!(plain_store.apply(&restore).unwrap());
+        assert_eq!(
+            std::fs::read_to_string(plain.path().join("notes.md")).unwrap(),
+            "draft\n"
+        );
+        assert!(!plain.path().join(".git").exists());
+    }
+
+    #[test]
+    fn selective_revert_restores_chosen_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let private = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("keep.txt"), "old\n").unwrap();
+        std::fs::write(dir.path().join("drop [1].txt"), "old\n").unwrap();
+        let store = CheckpointStore::open_in(dir.path().to_str().unwrap(), private.path()).unwrap();
+
+        let before = store.create("s", "before").unwrap();
+        std::fs::write(dir.path().join("keep.txt"), "new\n").unwrap();
+        std::fs::write(dir.path().join("drop [1].txt"), "new\n").unwrap();
+        let after = store.create("s", "after").unwrap();
+
+        let files = store.changed_files(&before, &after).unwrap();
+        assert_eq!(files.len(), 2);
+        let dropped = files.iter().find(|f| f.path == "drop [1].txt").unwrap();
+        assert_eq!(
+            (
+                dropped.status.as_str(),
+                dropped.additions,
+                dropped.deletions
+            ),
+            ("M", Some(1), Some(1))
+        );
+        assert!(dropped.patch.contains("+new"));
+
+        let paths = vec!["drop [1].txt".to_string()];
+        let undo = store.patch(&after, &before, Some(&paths)).unwrap();
+        assert!(store.apply(&undo).unwrap());
+        let read = |name: &str| std::fs::read_to_string(dir.path().join(name)).unwrap();
+        assert_eq!(read("drop [1].txt"), "old\n");
+        assert_eq!(read("keep.txt"), "new\n");
+    }
+
+    #[test]
+    fn checkpoint_refs_are_deleted_and_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        let private = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "one\n").unwrap();
+        let store = CheckpointStore::open_in(dir.path().to_str().unwrap(), private.path()).unwrap();
+        let names = |prefix: &str| -> Vec<String> {
+            store
+                .refs(prefix)
+                .unwrap()
+                .into_iter()
+                .map(|(name, _, _)| name)
+                .collect()
+        };
+
+        let first = store.create("s", "prompt 0").unwrap();
+        std::fs::write(dir.path().join("a.txt"), "two\n").unwrap();
+        store.create("s", "prompt 1").unwrap();
+        store.create("other", "prompt 0").unwrap();
+
+        // Truncated records keep only the first checkpoint of the session
+        assert_eq!(store.retain("s", &[first.as_str()]).unwrap(), 1);
+        assert_eq!(
+            names(&session_ref("s")),
+            vec![format!("{}/0", session_ref("s"))]
+        );
+        assert_eq!(names(&session_ref("other")).len(), 1);
+
+        // Deleting the session leaves other sessions alone
+        assert_eq!(store.retain("s", &[]).unwrap(), 1);
+        assert!(names(&session_ref("s")).is_empty());
+
+        assert_eq!(store.prune_before(0).unwrap(), 0);
+        assert_eq!(store.prune_before(i64::MAX).unwrap(), 1);
+        assert!(names(CHECKPOINT_REF_PREFIX).is_empty());
+    }
+}
@@ -3,12 +3,16 @@ use std::process::Stdio;
 use std::sync::Arc;
 
 use tauri::{AppHandle, Emitter, Manager};
-use tokio::process::{Child, Command};
-use tokio::sync::Mutex;
+use tokio::process::Command;
 
+use crate::commands::budget::{self, UsageSample};
+use crate::commands::enhanced_hooks::{self, HookEvent};
 use crate::commands::permission_config::{
     build_execution_args, ClaudeExecutionConfig, ClaudePermissionConfig,
 };
+use crate::commands::provider::active_provider_id;
+use crate::commands::session_timeline::SessionEngine;
+use crate::commands::session_worktree;
 #[cfg(windows)]
 use crate::process::JobObject;
 
@@ -16,69 +20,6 @@ use super::config::get_claude_execution_config;
 use super::paths::{encode_project_path, get_claude_dir};
 use super::platform;
 
-/// Global state to track current Claude process
-pub struct ClaudeProcessState {
-    pub current_process: Arc<Mutex<Option<Child>>>,
-    pub last_spawned_pid: Arc<Mutex<Option<u32>>>,
-}
-
-impl Default for ClaudeProcessState {
-    fn default() -> Self {
-        Self {
-            current_process: Arc::new(Mutex::new(None)),
-            last_spawned_pid: Arc::new(Mutex::new(None)),
-        }
-    }
-}
-
-impl Drop for ClaudeProcessState {
-    fn drop(&mut self) {
-        // When the application exits, clean up the current process
-        log::info!("ClaudeProcessState dropping, cleaning up current process...");
-
-        // Use a runtime to execute the async cleanup
-        let process = self.current_process.clone();
-        if let Ok(handle) = tokio::runtime::Handle::try_current() {
-            // We're in a tokio runtime context
-            handle.block_on(async move {
-                let mut current_process = process.lock().await;
-                if let Some(mut child) = current_process.take() {
-                    log::info!("Cleanup on drop: Killing Claude process");
-                    match child.kill().await {
-                        Ok(_) => {
-                            log::info!("Cleanup on drop: Successfully killed Claude process");
-                        }
-                        Err(e) => {
-                            log::error!("Cleanup on drop: Failed to kill Claude process: {}", e);
-                        }
-                    }
-                }
-            });
-        } else {
-            // Create a temporary runtime for cleanup
-            if let Ok(rt) = tokio::runtime::Runtime::new() {
-                rt.block_on(async move {
-                    let mut current_process = process.lock().await;
-                    if let Some(mut child) = current_process.take() {
-                        log::info!("Cleanup on drop: Killing Claude process");
-                        match child.kill().await {
-                            Ok(_) => {
-                                log::info!("Cleanup on drop: Successfully killed Claude process");
-                            }
-                            Err(e) => {
-                                log::error!(
-                                    "Cleanup on drop: Failed to kill Claude process: {}",
-                                    e
-                                );
-                            }
-                        }
-                    }
-                });
-            }
-        }
-    }
-}
-
 /// Maps frontend model IDs to Claude CLI model aliases
 /// Converts frontend-friendly model names to official Claude Code model identifiers
 /// Updated to use Claude 4.6 (released February 2026) as the latest models
@@ -210,6 +151,64 @@ fn create_command_with_env(program: &str) -> Command {
     tokio_cmd
 }
 
+/// Input, output, cache creation and cache read tokens
+type UsageTokens = [u64; 4];
+
+/// Usage an `assistant` line reports for its API response
+struct ResponseUsage {
+    model: Option<String>,
+    /// Usage reported so far for the response
+    total: UsageTokens,
+    /// Part of `total` that earlier lines of the response didn't report
+    delta: UsageTokens,
+}
+
+/// Token usage of one API response in a stream-json line.
+///
+/// The CLI emits one `assistant` line per content block, each repeating the
+/// response's usage so far; output tokens keep growing while it streams. The
+/// largest usage seen per message id is kept in `counted`, so only growth is
+/// returned and a line repeating it yields `None`.
+fn response_usage(
+    msg: &serde_json::Value,
+    counted: &mut std::collections::HashMap<String, UsageTokens>,
+) -> Option<ResponseUsage> {
+    if msg["type"] != "assistant" {
+        return None;
+    }
+    let message = msg.get("message")?;
+    let usage = message.get("usage")?;
+
+    let tokens = |key: &str| usage.get(key).and_then(|t| t.as_u64()).unwrap_or(0);
+    let reported = [
+        tokens("input_tokens"),
+        tokens("output_tokens"),
+        tokens("cache_creation_input_tokens"),
+        tokens("cache_read_input_tokens"),
+    ];
+    let (total, delta) = match message.get("id").and_then(|v| v.as_str()) {
+        Some(id) => {
+            let previous = counted.get(id).copied().unwrap_or_default();
+            let total: UsageTokens = std::array::from_fn(|i| previous[i].max(reported[i]));
+            counted.insert(id.to_string(), total);
+            (total, std::array::from_fn(|i| total[i] - previous[i]))
+        }
+        None => (reported, reported),
+    };
+    if delta.iter().all(|&t| t == 0) {
+        return None;
+    }
+
+    Some(ResponseUsage {
+        model: message
+            .get("model")
+            .and_then(|m| m.as_str())
+            .map(|m| m.to_string()),
+        total,
+        delta,
+    })
+}
+
 /// Helper function to spawn Claude process and handle streaming
 /// Enhanced for Windows compatibility with router support
 fn create_system_command(
@@ -293,6 +292,10 @@ pub async fn execute_claude_code(
 
     let claude_path = crate::claude_binary::find_claude_binary(&app)?;
 
+    // New sessions get their own worktree when session isolation is on
+    let project_path =
+        session_worktree::prepare_session_worktree(&project_path, SessionEngine::Claude)?;
+
     // 获取当前执行配置
     let mut execution_config = get_claude_execution_config(app.clone())
         .await
@@ -346,8 +349,18 @@ pub async fn continue_claude_code(
     plan_mode: Option<bool>,
     max_thinking_tokens: Option<u32>,
     tab_id: Option<String>,
+    session_id: Option<String>,
 ) -> Result<(), String> {
     let plan_mode = plan_mode.unwrap_or(false);
+    // `-c` picks the latest conversation of the directory, so a session that
+    // ran in a worktree has to be continued there
+    let project_path = match session_id.as_deref() {
+        Some(id) => {
+            let id = wait_for_compaction(&app, id).await;
+            session_worktree::resolve_project_path(&id, &project_path)
+        }
+        None => project_path,
+    };
     log::info!(
         "Continuing Claude Code conversation in: {} with model: {}, plan_mode: {}",
         project_path,
@@ -402,6 +415,18 @@ pub async fn continue_claude_code(
     spawn_claude_process(app, cmd, prompt, model, project_path, tab_id).await
 }
 
+/// Holds a new run back while its session is being compacted and returns the
+/// id to resume, which changes when the CLI continued the session under a new id
+async fn wait_for_compaction(app: &AppHandle, session_id: &str) -> String {
+    let Some(state) = app
+        .try_state::<crate::commands::context_manager::AutoCompactState>()
+        .map(|state| state.inner().clone())
+    else {
+        return session_id.to_string();
+    };
+    state.0.wait_for_compaction(app, session_id).await
+}
+
 /// Resume an existing Claude Code session by ID with streaming output
 /// Enhanced for Windows with better error handling
 #[tauri::command]
@@ -416,6 +441,9 @@ pub async fn resume_claude_code(
     tab_id: Option<String>,
 ) -> Result<(), String> {
     let plan_mode = plan_mode.unwrap_or(false);
+    let session_id = wait_for_compaction(&app, &session_id).await;
+    // A session started in a worktree has to be resumed there
+    let project_path = session_worktree::resolve_project_path(&session_id, &project_path);
     log::info!(
         "Resuming Claude Code session: {} in: {} with model: {}, plan_mode: {}",
         session_id,
@@ -515,7 +543,11 @@ pub async fn resume_claude_code(
     }
 }
 
-/// Cancel the currently running Claude Code execution
+/// Cancel a running Claude Code execution
+///
+/// `session_id` may be a Claude session ID or the tab ID a run was started
+/// from (before the CLI has reported its session ID). Without an ID the most
+/// recently started run is cancelled. Other runs are left untouched.
 #[tauri::command]
 pub async fn cancel_claude_execution(
     app: AppHandle,
@@ -526,121 +558,108 @@ pub async fn cancel_claude_execution(
         session_id
     );
 
-    let mut killed = false;
-    let mut attempted_methods = Vec::new();
-
-    // Method 1: Try to find and kill via ProcessRegistry using session ID
-    if let Some(sid) = &session_id {
-        let registry = app.state::<crate::process::ProcessRegistryState>();
-        match registry.0.get_claude_session_by_id(sid) {
-            Ok(Some(process_info)) => {
-                log::info!(
-                    "Found process in registry for session {}: run_id={}, PID={}",
-                    sid,
-                    process_info.run_id,
-                    process_info.pid
-                );
-                match registry.0.kill_process(process_info.run_id).await {
-                    Ok(success) => {
-                        if success {
-                            log::info!("Successfully killed process via registry");
-                            killed = true;
-                        } else {
-                            log::warn!("Registry kill returned false");
-                        }
-                    }
-                    Err(e) => {
-                        log::warn!("Failed to kill via registry: {}", e);
-                    }
-                }
-                attempted_methods.push("registry");
-            }
-            Ok(None) => {
-                log::warn!("Session {} not found in ProcessRegistry", sid);
-            }
-            Err(e) => {
-                log::error!("Error querying ProcessRegistry: {}", e);
-            }
+    let registry = app.state::<crate::process::ProcessRegistryState>();
+    let target = match &session_id {
+        Some(key) => registry.0.find_claude_run(key)?,
+        None => {
+            log::warn!("No session ID given, cancelling the most recent Claude run");
+            registry.0.latest_claude_run()?
         }
-    }
+    };
 
-    // Method 2: Try the legacy approach via ClaudeProcessState
-    if !killed {
-        let claude_state = app.state::<ClaudeProcessState>();
-        let mut current_process = claude_state.current_process.lock().await;
-
-        if let Some(mut child) = current_process.take() {
-            // Try to get the PID before killing
-            let pid = child.id();
-            log::info!(
-                "Attempting to kill Claude process via ClaudeProcessState with PID: {:?}",
-                pid
-            );
+    let Some(process_info) = target else {
+        log::warn!("No running Claude process found for {:?}", session_id);
+        emit_cancelled(&app, session_id.as_deref(), None).await;
+        return Ok(());
+    };
+    log::info!(
+        "Found Claude run {} (PID {}) for {:?}",
+        process_info.run_id,
+        process_info.pid,
+        session_id
+    );
 
-            // Kill the process
-            match child.kill().await {
-                Ok(_) => {
-                    log::info!("Successfully killed Claude process via ClaudeProcessState");
-                    killed = true;
-                }
-                Err(e) => {
-                    log::error!(
-                        "Failed to kill Claude process via ClaudeProcessState: {}",
-                        e
-                    );
+    stop_claude_run(&app, process_info, session_id.as_deref()).await;
+    Ok(())
+}
 
-                    // Fallback: If we have a PID, try system kill as last resort
-                    if let Some(pid) = pid {
-                        log::info!("Attempting system kill as last resort for PID: {}", pid);
-                        match platform::kill_process_tree(pid) {
-                            Ok(_) => {
-                                log::info!("Successfully killed process tree via platform module");
-                                killed = true;
-                            }
-                            Err(e) => {
-                                log::error!("Failed to kill process tree: {}", e);
-                            }
-                        }
-                    }
-                }
-            }
-            attempted_methods.push("claude_state");
-        } else {
-            log::warn!("No active Claude process in ClaudeProcessState");
+/// Cancels a run by its run ID, which is known before the CLI reports a session ID
+async fn cancel_claude_run(
+    app: AppHandle,
+    run_id: i64,
+    tab_id: Option<String>,
+) -> Result<(), String> {
+    let registry = app.state::<crate::process::ProcessRegistryState>();
+    let Some(process_info) = registry.0.get_process(run_id)? else {
+        log::warn!("Claude run {} already finished", run_id);
+        return Ok(());
+    };
+    stop_claude_run(&app, process_info, tab_id.as_deref()).await;
+    Ok(())
+}
+
+/// Kills a run and emits the cancellation events for `key` and its session
+async fn stop_claude_run(
+    app: &AppHandle,
+    process_info: crate::process::ProcessInfo,
+    key: Option<&str>,
+) {
+    let registry = app.state::<crate::process::ProcessRegistryState>();
+    let killed = match registry.0.kill_process(process_info.run_id).await {
+        Ok(true) => true,
+        Ok(false) => {
+            log::warn!("Registry kill returned false");
+            false
         }
-    }
+        Err(e) => {
+            log::warn!("Failed to kill via registry: {}", e);
+            false
+        }
+    };
 
-    // Method 3: Try killing the last spawned PID when session_id is not available
-    if !killed {
-        let claude_state = app.state::<ClaudeProcessState>();
-        let last_pid = { *claude_state.last_spawned_pid.lock().await };
-        if let Some(pid) = last_pid {
-            log::info!("Attempting to kill Claude process via last spawned PID: {}", pid);
-            match platform::kill_process_tree(pid) {
-                Ok(_) => {
-                    log::info!("Successfully killed process tree via last spawned PID");
-                    let mut last_pid_guard = claude_state.last_spawned_pid.lock().await;
-                    if last_pid_guard.as_ref() == Some(&pid) {
-                        *last_pid_guard = None;
-                    }
-                    killed = true;
-                }
-                Err(e) => {
-                    log::error!("Failed to kill process tree via last spawned PID: {}", e);
-                }
-            }
-            attempted_methods.push("last_spawned_pid");
+    // Fallback: kill the process tree by PID
+    if !killed && process_info.pid != 0 {
+        log::info!(
+            "Attempting system kill as last resort for PID: {}",
+            process_info.pid
+        );
+        if let Err(e) = platform::kill_process_tree(process_info.pid) {
+            log::error!("Failed to kill process tree: {}", e);
         }
     }
 
-    if !killed && attempted_methods.is_empty() {
-        log::warn!("No active Claude process found to cancel");
+    let claude_session_id = match &process_info.process_type {
+        crate::process::ProcessType::ClaudeSession { session_id, .. } if !session_id.is_empty() => {
+            Some(session_id.clone())
+        }
+        _ => None,
+    };
+    let event_payload = serde_json::json!({
+        "session_id": claude_session_id,
+        "run_id": process_info.run_id,
+        "status": "cancelled",
+    });
+    let _ = app.emit("claude-session-state", &event_payload);
+
+    emit_cancelled(app, key, claude_session_id.as_deref()).await;
+    log::info!("Claude run {} cancellation completed", process_info.run_id);
+}
+
+/// Emits the cancellation events for the requested key and the resolved session
+async fn emit_cancelled(app: &AppHandle, key: Option<&str>, claude_session_id: Option<&str>) {
+    let mut ids: Vec<&str> = key.into_iter().collect();
+    if let Some(sid) = claude_session_id {
+        if !ids.contains(&sid) {
+            ids.push(sid);
+        }
     }
 
     // Always emit cancellation events for UI consistency
-    if let Some(sid) = session_id {
+    for sid in &ids {
         let _ = app.emit(&format!("claude-cancelled:{}", sid), true);
-        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    }
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    for sid in &ids {
         let _ = app.emit(&format!("claude-complete:{}", sid), false);
     }
 
@@ -648,14 +667,6 @@ pub async fn cancel_claude_execution(
     let _ = app.emit("claude-cancelled", true);
     tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
     let _ = app.emit("claude-complete", false);
-
-    if killed {
-        log::info!("Claude process cancellation completed successfully");
-    } else if !attempted_methods.is_empty() {
-        log::warn!("Claude process cancellation attempted but process may have already exited. Attempted methods: {:?}", attempted_methods);
-    }
-
-    Ok(())
 }
 
 /// Get all running Claude sessions
@@ -666,20 +677,147 @@ pub async fn list_running_claude_sessions(
     registry.0.get_running_claude_sessions()
 }
 
+/// Get all running Claude runs, including ones that have not reported a session ID yet
+#[tauri::command]
+pub async fn list_running_claude_runs(
+    registry: tauri::State<'_, crate::process::ProcessRegistryState>,
+) -> Result<Vec<crate::process::ProcessInfo>, String> {
+    registry.0.get_running_claude_runs()
+}
+
 /// Get live output from a Claude session
 #[tauri::command]
 pub async fn get_claude_session_output(
     registry: tauri::State<'_, crate::process::ProcessRegistryState>,
     session_id: String,
 ) -> Result<String, String> {
-    // Find the process by session ID
-    if let Some(process_info) = registry.0.get_claude_session_by_id(&session_id)? {
+    // Find the process by session ID (or the tab that started it)
+    if let Some(process_info) = registry.0.find_claude_run(&session_id)? {
         registry.0.get_live_output(process_info.run_id)
     } else {
         Ok(String::new())
     }
 }
 
+/// Result of a headless `/compact` run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompactOutcome {
+    /// Session the CLI reported in its init message
+    pub session_id: Option<String>,
+    /// Context size the CLI measured right before compacting
+    pub pre_tokens: Option<usize>,
+    /// Context size after compacting, when the CLI reports it
+    pub post_tokens: Option<usize>,
+}
+
+/// Upper bound for a compaction run; summarising a full context can take a while
+const COMPACT_TIMEOUT_SECS: u64 = 600;
+
+/// Resumes a session without a UI and sends it `/compact <instructions>`, so the
+/// CLI summarises its own history in place
+pub async fn compact_claude_session(
+    app: &AppHandle,
+    project_path: &str,
+    session_id: &str,
+    model: &str,
+    instructions: &str,
+) -> Result<CompactOutcome, String> {
+    let claude_path = crate::claude_binary::find_claude_binary(app)?;
+    let mapped_model = map_model_to_claude_alias(model);
+
+    // Slash commands are only parsed from a single-line -p argument
+    let instructions = instructions
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let args = vec![
+        "--resume".to_string(),
+        session_id.to_string(),
+        "-p".to_string(),
+        format!("/compact {}", instructions).trim_end().to_string(),
+        "--output-format".to_string(),
+        "stream-json".to_string(),
+        "--verbose".to_string(),
+    ];
+    log::info!(
+        "Compacting Claude session {} in {}",
+        session_id,
+        project_path
+    );
+
+    let mut cmd = create_system_command(
+        &claude_path,
+        args,
+        project_path,
+        (!mapped_model.is_empty()).then_some(mapped_model.as_str()),
+        None,
+    )?;
+    cmd.stdin(Stdio::null()).kill_on_drop(true);
+
+    let output = tokio::time::timeout(
+        tokio::time::Duration::from_secs(COMPACT_TIMEOUT_SECS),
+        cmd.output(),
+    )
+    .await
+    .map_err(|_| format!("Compaction timed out after {}s", COMPACT_TIMEOUT_SECS))?
+    .map_err(|e| format!("Failed to run compaction: {}", e))?;
+
+    let outcome = parse_compact_output(&String::from_utf8_lossy(&output.stdout));
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Compaction failed: {}", stderr.trim()));
+    }
+    outcome
+}
+
+/// Reads the stream-json output of a `/compact` run.
+///
+/// A `compact_boundary` system message is the only proof the history was
+/// actually compacted; without one the CLI's result text says why not.
+fn parse_compact_output(stdout: &str) -> Result<CompactOutcome, String> {
+    let mut outcome = CompactOutcome::default();
+    let mut compacted = false;
+    let mut result_text = None;
+
+    for msg in stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+    {
+        let tokens = |value: &serde_json::Value| value.as_u64().map(|t| t as usize);
+        match (msg["type"].as_str(), msg["subtype"].as_str()) {
+            (Some("system"), Some("init")) => {
+                outcome.session_id = msg["session_id"].as_str().map(|s| s.to_string());
+            }
+            (Some("system"), Some("compact_boundary")) => {
+                compacted = true;
+                let metadata = &msg["compact_metadata"];
+                outcome.pre_tokens = tokens(&metadata["pre_tokens"]);
+                outcome.post_tokens = tokens(&metadata["post_tokens"]);
+            }
+            (Some("result"), _) => {
+                if msg["is_error"].as_bool().unwrap_or(false) {
+                    return Err(format!(
+                        "Compaction failed: {}",
+                        msg["result"].as_str().unwrap_or("unknown error")
+                    ));
+                }
+                result_text = msg["result"].as_str().map(|s| s.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    if !compacted {
+        return Err(format!(
+            "Claude CLI did not compact the session: {}",
+            result_text
+                .as_deref()
+                .unwrap_or("no compact boundary in output")
+        ));
+    }
+    Ok(outcome)
+}
+
 /// Helper function to check if prompt is a slash command
 /// Slash commands start with '/' and are typically short (like /help, /compact, /clear)
 fn is_slash_command(prompt: &str) -> bool {
@@ -796,30 +934,36 @@ async fn spawn_claude_process(
 
     // We'll extract the session ID from Claude's init message
     let session_id_holder: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
-    let run_id_holder: Arc<Mutex<Option<i64>>> = Arc::new(Mutex::new(None));
-    #[cfg(windows)]
-    let job_object_holder: Arc<std::sync::Mutex<Option<Arc<JobObject>>>> =
-        Arc::new(std::sync::Mutex::new(job_object));
-
-    // 🔒 CRITICAL FIX: 不再使用全局 ClaudeProcessState 管理进程生命周期
-    // 原因：全局单例只能存储一个 child，多会话并发时会互相覆盖
-    // 解决：每个进程独立管理自己的 child，取消功能通过 ProcessRegistry 实现
-    //
-    // ClaudeProcessState 仅作为向后兼容的备选取消机制（存储最新进程的 PID）
-    let claude_state = app.state::<ClaudeProcessState>();
-    {
-        let mut current_process = claude_state.current_process.lock().await;
-        // 仅用于记录，不影响进程管理
-        if current_process.is_some() {
-            log::info!("Another Claude process is running, allowing concurrent sessions");
-        }
-        // 不再存储 child，改为独立管理
-        *current_process = None;
-    }
-    if pid != 0 {
-        let mut last_pid = claude_state.last_spawned_pid.lock().await;
-        *last_pid = Some(pid);
+
+    // Track the run in the ProcessRegistry right away, keyed by run ID, so it
+    // can be cancelled and its output read before the CLI reports a session ID.
+    // 🔧 FIX: Pass the pre-created Job Object to avoid orphan processes
+    #[cfg(not(windows))]
+    let job_object: Option<()> = None;
+    let registry = app.state::<crate::process::ProcessRegistryState>();
+    let run_id = registry.0.register_claude_session_with_job(
+        String::new(),
+        pid,
+        project_path.clone(),
+        prompt.clone(),
+        model.clone(),
+        job_object,
+    )?;
+    if let Some(tab) = &tab_id {
+        registry.0.set_claude_tab_id(run_id, tab)?;
     }
+    log::info!("Registered Claude run {} (PID {})", run_id, pid);
+    let _ = app.emit(
+        "claude-session-state",
+        &serde_json::json!({
+            "run_id": run_id,
+            "tab_id": tab_id,
+            "project_path": project_path,
+            "model": model,
+            "status": "spawned",
+            "pid": pid,
+        }),
+    );
 
     // Check if auto-compact state is available
     let auto_compact_available = app
@@ -829,19 +973,17 @@ async fn spawn_claude_process(
     // Spawn tasks to read stdout and stderr
     let app_handle = app.clone();
     let session_id_holder_clone = session_id_holder.clone();
-    let run_id_holder_clone = run_id_holder.clone();
-    let registry = app.state::<crate::process::ProcessRegistryState>();
     let registry_clone = registry.0.clone();
     let project_path_clone = project_path.clone();
-    let prompt_clone = prompt.clone();
     let model_clone = model.clone();
     // 🔒 CRITICAL FIX: 克隆 tab_id 用于事件发送
     let tab_id_for_stdout = tab_id.clone();
-    // 🔧 FIX: Clone job_object_holder for passing to register_claude_session
-    #[cfg(windows)]
-    let job_object_holder_clone = job_object_holder.clone();
+    // Budget tracking: provider preset in effect when the session started
+    let provider_id = active_provider_id();
     let stdout_task = tokio::spawn(async move {
         let mut lines = stdout_reader.lines();
+        let mut counted_usage = std::collections::HashMap::new();
+        let mut budget_cancelled = false;
         while let Ok(Some(line)) = lines.next_line().await {
             // Use trace level to avoid flooding logs in debug mode
             log::trace!("Claude stdout: {}", line);
@@ -854,122 +996,140 @@ async fn spawn_claude_process(
                         if session_id_guard.is_none() {
                             *session_id_guard = Some(claude_session_id.to_string());
                             log::info!("Extracted Claude session ID: {}", claude_session_id);
+                            session_worktree::bind_session(&project_path_clone, claude_session_id);
 
                             // Register with auto-compact manager
                             if auto_compact_available {
                                 if let Some(auto_compact_state) = app_handle.try_state::<crate::commands::context_manager::AutoCompactState>() {
                                     if let Err(e) = auto_compact_state.0.register_session(
+                                    &app_handle,
                                     claude_session_id.to_string(),
                                     project_path_clone.clone(),
+                                    SessionEngine::Claude,
                                     model_clone.clone(),
+                                    provider_id.clone(),
                                 ) {
                                     log::warn!("Failed to register session with auto-compact manager: {}", e);
                                 }
                                 }
                             }
 
-                            // Now register with ProcessRegistry using Claude's session ID
-                            // 🔧 FIX: Pass the pre-created Job Object to avoid orphan processes
-                            #[cfg(windows)]
-                            let job_object_for_register = job_object_holder_clone
-                                .lock()
-                                .unwrap()
-                                .take();
-                            #[cfg(not(windows))]
-                            let job_object_for_register: Option<()> = None;
-
-                            match registry_clone.register_claude_session_with_job(
-                                claude_session_id.to_string(),
-                                pid,
-                                project_path_clone.clone(),
-                                prompt_clone.clone(),
-                                model_clone.clone(),
-                                job_object_for_register,
-                            ) {
-                                Ok(run_id) => {
-                                    log::info!("Registered Claude session with run_id: {}", run_id);
-                                    let mut run_id_guard = run_id_holder_clone.lock().unwrap();
-                                    *run_id_guard = Some(run_id);
-
-                                    // ✨ Phase 2: Emit event for real-time session tracking
-                                    let event_payload = serde_json::json!({
-                                        "session_id": claude_session_id,
-                                        "project_path": project_path_clone,
-                                        "model": model_clone,
-                                        "status": "started",
-                                        "pid": pid,
-                                        "run_id": run_id,
-                                    });
-                                    if let Err(e) =
-                                        app_handle.emit("claude-session-state", &event_payload)
-                                    {
-                                        log::warn!(
-                                            "Failed to emit claude-session-state event: {}",
-                                            e
-                                        );
-                                    } else {
-                                        log::info!(
-                                            "Emitted claude-session-started event for session: {}",
-                                            claude_session_id
-                                        );
-                                    }
+                            // Key the run by Claude's session ID from now on
+                            if let Err(e) =
+                                registry_clone.set_claude_session_id(run_id, claude_session_id)
+                            {
+                                log::error!("Failed to register Claude session: {}", e);
+                            }
 
-                                    log::info!(
-                                        "Claude CLI will handle project creation for session: {}",
-                                        claude_session_id
-                                    );
-                                }
-                                Err(e) => {
-                                    log::error!("Failed to register Claude session: {}", e);
-                                }
+                            enhanced_hooks::fire(
+                                &app_handle,
+                                HookEvent::OnSessionStart,
+                                claude_session_id,
+                                &project_path_clone,
+                                serde_json::json!({
+                                    "engine": "claude",
+                                    "model": model_clone,
+                                    "tab_id": tab_id_for_stdout,
+                                    "run_id": run_id,
+                                }),
+                            );
+
+                            // ✨ Phase 2: Emit event for real-time session tracking
+                            let event_payload = serde_json::json!({
+                                "session_id": claude_session_id,
+                                "tab_id": tab_id_for_stdout,
+                                "project_path": project_path_clone,
+                                "model": model_clone,
+                                "status": "started",
+                                "pid": pid,
+                                "run_id": run_id,
+                            });
+                            if let Err(e) = app_handle.emit("claude-session-state", &event_payload)
+                            {
+                                log::warn!("Failed to emit claude-session-state event: {}", e);
+                            } else {
+                                log::info!(
+                                    "Emitted claude-session-started event for session: {}",
+                                    claude_session_id
+                                );
                             }
                         }
                     }
                 }
 
-                // Check for usage information and update context tracking
-                if let Some(usage) = msg.get("usage") {
-                    if let (Some(input_tokens), Some(output_tokens)) = (
-                        usage.get("input_tokens").and_then(|t| t.as_u64()),
-                        usage.get("output_tokens").and_then(|t| t.as_u64()),
-                    ) {
-                        let total_tokens = (input_tokens + output_tokens) as usize;
-
-                        // Extract cache tokens if available
-                        let _cache_creation_tokens = usage
-                            .get("cache_creation_input_tokens")
-                            .and_then(|t| t.as_u64());
-                        let _cache_read_tokens = usage
-                            .get("cache_read_input_tokens")
-                            .and_then(|t| t.as_u64());
-
-                        // Store usage data in database for real-time token statistics
-                        let session_id_for_update =
-                            { session_id_holder_clone.lock().unwrap().as_ref().cloned() };
-
-                        if let Some(session_id_str) = &session_id_for_update {
-                            // Agent database functionality removed - usage tracking disabled
-
-                            // Update auto-compact manager with token count
-                            if auto_compact_available {
-                                if let Some(auto_compact_state) = app_handle.try_state::<crate::commands::context_manager::AutoCompactState>() {
-                                    let auto_compact_state_clone = auto_compact_state.inner().clone();
-                                    let session_id_for_compact = session_id_str.clone();
-
-                                    // Spawn async task to avoid blocking main output loop
-                                    tokio::spawn(async move {
-                                        match auto_compact_state_clone.0.update_session_tokens(&session_id_for_compact, total_tokens).await {
-                                            Ok(compaction_triggered) => {
-                                                if compaction_triggered {
-                                                    log::info!("Auto-compaction triggered for session {}", session_id_for_compact);
-                                                    // The actual compaction will be handled by the background monitoring thread
-                                                }
-                                            }
-                                            Err(e) => {
-                                                log::warn!("Failed to update session tokens for auto-compact: {}", e);
-                                            }
+                // Token usage this line adds to its API response
+                let response = response_usage(&msg, &mut counted_usage);
+
+                // Check spend against budgets as each API response arrives
+                if !budget_cancelled {
+                    if let Some(ResponseUsage {
+                        model: response_model,
+                        delta: [input, output, cache_creation, cache_read],
+                        ..
+                    }) = &response
+                    {
+                        let session_id_for_budget = session_id_holder_clone
+                            .lock()
+                            .unwrap()
+                            .clone()
+                            .or_else(|| tab_id_for_stdout.clone())
+                            .unwrap_or_default();
+                        let sample = UsageSample {
+                            engine: SessionEngine::Claude,
+                            session_id: session_id_for_budget,
+                            project_path: project_path_clone.clone(),
+                            model: response_model
+                                .clone()
+                                .unwrap_or_else(|| model_clone.clone()),
+                            provider_id: provider_id.clone(),
+                            input_tokens: *input,
+                            output_tokens: *output,
+                            cache_creation_tokens: *cache_creation,
+                            cache_read_tokens: *cache_read,
+                        };
+                        if let Some(alert) = budget::record_usage(&app_handle, &sample) {
+                            budget_cancelled = true;
+                            log::warn!(
+                                "Budget {} exceeded, cancelling Claude run {} (session {})",
+                                alert.rule_id,
+                                run_id,
+                                alert.session_id
+                            );
+                            // By run ID: the CLI may not have reported a session ID yet
+                            let app_for_cancel = app_handle.clone();
+                            let tab_for_cancel = tab_id_for_stdout.clone();
+                            tokio::spawn(async move {
+                                if let Err(e) =
+                                    cancel_claude_run(app_for_cancel, run_id, tab_for_cancel).await
+                                {
+                                    log::error!("Failed to cancel session over budget: {}", e);
+                                }
+                            });
+                        }
+                    }
+                }
+
+                // Feed the context size of each response to auto-compact: everything
+                // the model read (fresh and cached input) plus what it wrote
+                if let Some(response) = &response {
+                    let context_tokens = response.total.iter().sum::<u64>() as usize;
+                    let session_id_for_update =
+                        { session_id_holder_clone.lock().unwrap().as_ref().cloned() };
+
+                    if let Some(session_id_str) = session_id_for_update {
+                        if auto_compact_available {
+                            if let Some(auto_compact_state) = app_handle.try_state::<crate::commands::context_manager::AutoCompactState>() {
+                                // Awaited in order so the exit-time pending check sees every response
+                                match auto_compact_state.0.update_session_tokens(&app_handle, &session_id_str, context_tokens).await {
+                                    Ok(compaction_triggered) => {
+                                        if compaction_triggered {
+                                            // Compaction resumes the session, so it runs once this process exits
+                                            log::info!("Auto-compaction pending for session {}", session_id_str);
                                         }
-                                    });
+                                    }
+                                    Err(e) => {
+                                        log::warn!("Failed to update session tokens for auto-compact: {}", e);
+                                    }
                                 }
                             }
                         }
@@ -977,10 +1137,8 @@ async fn spawn_claude_process(
                 }
             }
 
-            // Store live output in registry if we have a run_id
-            if let Some(run_id) = *run_id_holder_clone.lock().unwrap() {
-                let _ = registry_clone.append_live_output(run_id, &line);
-            }
+            // Store live output in registry
+            let _ = registry_clone.append_live_output(run_id, &line);
 
             // Emit the line to the frontend with session isolation if we have session ID
             if let Some(ref session_id) = *session_id_holder_clone.lock().unwrap() {
@@ -1021,11 +1179,10 @@ async fn spawn_claude_process(
     // 这样每个进程独立管理自己的生命周期，支持真正的多会话并发
     let app_handle_wait = app.clone();
     let session_id_holder_clone3 = session_id_holder.clone();
-    let run_id_holder_clone2 = run_id_holder.clone();
     let registry_clone2 = registry.0.clone();
-    let last_spawned_pid = claude_state.last_spawned_pid.clone();
     // 🔒 CRITICAL FIX: 克隆 tab_id 用于 complete 事件
     let tab_id_for_complete = tab_id;
+    let project_path_for_hooks = project_path;
     tokio::spawn(async move {
         let _ = stdout_task.await;
         let _ = stderr_task.await;
@@ -1041,6 +1198,7 @@ async fn spawn_claude_process(
                     // ✨ Phase 2: Emit state change event
                     let event_payload = serde_json::json!({
                         "session_id": session_id,
+                        "run_id": run_id,
                         "status": "stopped",
                         "success": status.success(),
                     });
@@ -1048,6 +1206,19 @@ async fn spawn_claude_process(
 
                     let _ = app_handle_wait
                         .emit(&format!("claude-complete:{}", session_id), status.success());
+
+                    enhanced_hooks::fire(
+                        &app_handle_wait,
+                        HookEvent::OnSessionEnd,
+                        session_id,
+                        &project_path_for_hooks,
+                        serde_json::json!({
+                            "engine": "claude",
+                            "run_id": run_id,
+                            "success": status.success(),
+                            "exit_code": status.code(),
+                        }),
+                    );
                 }
                 // 🔒 CRITICAL FIX: 全局事件包含 tab_id
                 let global_payload = serde_json::json!({
@@ -1064,6 +1235,7 @@ async fn spawn_claude_process(
                     // ✨ Phase 2: Emit state change event for error case
                     let event_payload = serde_json::json!({
                         "session_id": session_id,
+                        "run_id": run_id,
                         "status": "stopped",
                         "success": false,
                         "error": e.to_string(),
@@ -1072,6 +1244,19 @@ async fn spawn_claude_process(
 
                     let _ =
                         app_handle_wait.emit(&format!("claude-complete:{}", session_id), false);
+
+                    enhanced_hooks::fire(
+                        &app_handle_wait,
+                        HookEvent::OnSessionEnd,
+                        session_id,
+                        &project_path_for_hooks,
+                        serde_json::json!({
+                            "engine": "claude",
+                            "run_id": run_id,
+                            "success": false,
+                            "error": e.to_string(),
+                        }),
+                    );
                 }
                 // 🔒 CRITICAL FIX: 全局事件包含 tab_id
                 let global_payload = serde_json::json!({
@@ -1082,18 +1267,82 @@ async fn spawn_claude_process(
             }
         }
 
-        // Unregister from ProcessRegistry if we have a run_id
-        if let Some(run_id) = *run_id_holder_clone2.lock().unwrap() {
-            let _ = registry_clone2.unregister_process(run_id);
-        }
-
-        if pid != 0 {
-            let mut last_pid = last_spawned_pid.lock().await;
-            if last_pid.as_ref() == Some(&pid) {
-                *last_pid = None;
+        // Unregister from ProcessRegistry
+        let _ = registry_clone2.unregister_process(run_id);
+
+        // Compact now that nothing else is writing to the session
+        let finished_session_id = session_id_holder_clone3.lock().unwrap().clone();
+        if let (Some(session_id), Some(auto_compact_state)) = (
+            finished_session_id,
+            app_handle_wait.try_state::<crate::commands::context_manager::AutoCompactState>(),
+        ) {
+            if auto_compact_state.0.is_compaction_pending(&session_id) {
+                if let Err(e) = auto_compact_state
+                    .0
+                    .execute_compaction(app_handle_wait.clone(), &session_id)
+                    .await
+                {
+                    log::error!("Auto-compaction failed for session {}: {}", session_id, e);
+                }
             }
         }
     });
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compact_output_reports_cli_token_counts() {
+        let output = r#"{"type":"system","subtype":"init","session_id":"s1"}
+{"type":"system","subtype":"compact_boundary","compact_metadata":{"trigger":"manual","pre_tokens":152000}}
+{"type":"result","subtype":"success","is_error":false,"result":""}"#;
+        let outcome = parse_compact_output(output).unwrap();
+        assert_eq!(outcome.session_id.as_deref(), Some("s1"));
+        assert_eq!(outcome.pre_tokens, Some(152000));
+        assert_eq!(outcome.post_tokens, None);
+
+        let refused =
+            r#"{"type":"result","is_error":false,"result":"Not enough messages to compact."}"#;
+        assert!(parse_compact_output(refused)
+            .unwrap_err()
+            .contains("Not enough messages"));
+    }
+
+    #[test]
+    fn streamed_responses_count_only_usage_growth() {
+        let line = |id: &str, output: u64| {
+            serde_json::json!({
+                "type": "assistant",
+                "message": {
+                    "id": id,
+                    "model": "claude-sonnet-4",
+                    "usage": {
+                        "input_tokens": 10,
+                        "output_tokens": output,
+                        "cache_read_input_tokens": 100,
+                    },
+                },
+            })
+        };
+        let mut counted = std::collections::HashMap::new();
+
+        let first = response_usage(&line("m1", 1), &mut counted).unwrap();
+        assert_eq!(first.model.as_deref(), Some("claude-sonnet-4"));
+        assert_eq!(first.delta, [10, 1, 0, 100]);
+
+        // Later blocks of the same response report the streamed output so far
+        let last = response_usage(&line("m1", 40), &mut counted).unwrap();
+        assert_eq!(last.delta, [0, 39, 0, 0]);
+        assert_eq!(last.total, [10, 40, 0, 100]);
+        assert!(response_usage(&line("m1", 40), &mut counted).is_none());
+        assert!(response_usage(&line("m1", 20), &mut counted).is_none());
+
+        let next = response_usage(&line("m2", 5), &mut counted).unwrap();
+        assert_eq!(next.delta, [10, 5, 0, 100]);
+        assert!(response_usage(&serde_json::json!({"type": "user"}), &mut counted).is_none());
+    }
+}
@@ -36,9 +36,20 @@ pub fn get_codex_dir() -> Result<PathBuf> {
 }
 
 /// Encodes a project path to match Claude CLI's encoding scheme
-/// Uses single hyphens to separate path components
+/// Every character other than an ASCII letter or digit becomes a hyphen, so
+/// `C:\work\my.app` is stored as `C--work-my-app`
 pub fn encode_project_path(path: &str) -> String {
-    path.replace('\\', "-").replace('/', "-").replace(':', "")
+    path.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+/// `~/.claude/projects` directory holding a session's transcript and rewind
+/// records, pointing at its worktree's project when the session runs in one
+pub fn get_session_project_dir(session_id: &str, project_id: &str) -> Result<PathBuf> {
+    let project_id =
+        crate::commands::session_worktree::resolve_claude_project_id(session_id, project_id);
+    Ok(get_claude_dir()?.join("projects").join(project_id))
 }
 
 /// Decodes a project directory name back to its original path
@@ -11,6 +11,7 @@ use super::paths::{decode_project_path, get_claude_dir, normalize_path_for_compa
 use super::session_history::{
     extract_first_user_message, extract_last_message_timestamp, extract_session_model,
 };
+use crate::commands::checkpoint;
 
 pub struct ProjectStore {
     claude_dir: PathBuf,
@@ -262,10 +263,10 @@ impl ProjectStore {
 
         let mut session_deleted = false;
 
-        let session_file = self
-            .projects_dir()
-            .join(project_id)
-            .join(format!("{}.jsonl", session_id));
+        let project_dir = self.projects_dir().join(project_id);
+        // Read before the session file goes away; it may be the only one left
+        let project_path = get_project_path_from_sessions(&project_dir).ok();
+        let session_file = project_dir.join(format!("{}.jsonl", session_id));
 
         if session_file.exists() {
             fs::remove_file(&session_file)
@@ -305,6 +306,12 @@ impl ProjectStore {
             }
         }
 
+        if let Some(project_path) = project_path {
+            if let Err(e) = checkpoint::delete_session_checkpoints(&project_path, session_id) {
+                log::warn!("Failed to delete checkpoints of {}: {}", session_id, e);
+            }
+        }
+
         Ok(session_deleted)
     }
 
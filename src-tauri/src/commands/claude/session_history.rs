@@ -7,7 +7,7 @@ use chrono::{DateTime, Utc};
 use serde_json::Value;
 
 use super::models::JsonlEntry;
-use super::paths::get_claude_dir;
+use super::paths::get_session_project_dir;
 
 /// Extracts the first valid user message from a JSONL file
 pub fn extract_first_user_message<P: AsRef<Path>>(
@@ -155,8 +155,12 @@ pub fn load_session_history(session_id: &str, project_id: &str) -> Result<Vec<Va
         project_id
     );
 
-    let claude_dir = get_claude_dir().map_err(|e| e.to_string())?;
-    let project_dir = claude_dir.join("projects").join(project_id);
+    let project_dir = get_session_project_dir(session_id, project_id).map_err(|e| e.to_string())?;
+    load_session_history_from(&project_dir, session_id)
+}
+
+/// Loads a session's history from the `~/.claude/projects` directory holding it
+fn load_session_history_from(project_dir: &Path, session_id: &str) -> Result<Vec<Value>, String> {
     let session_path = project_dir.join(format!("{}.jsonl", session_id));
 
     if !session_path.exists() {
@@ -221,7 +225,7 @@ pub fn load_session_history(session_id: &str, project_id: &str) -> Result<Vec<Va
 
     // Step 2: Load subagent messages from agent-*.jsonl files
     if !agent_to_tool_use_id.is_empty() {
-        if let Ok(entries) = fs::read_dir(&project_dir) {
+        if let Ok(entries) = fs::read_dir(project_dir) {
             for entry in entries.flatten() {
                 let path = entry.path();
                 if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
@@ -311,3 +315,55 @@ pub fn load_session_history(session_id: &str, project_id: &str) -> Result<Vec<Va
     );
     Ok(messages)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::claude::encode_project_path;
+    use crate::commands::session_timeline::SessionEngine;
+    use crate::commands::session_worktree::{claude_project_id_in, SessionWorktree};
+
+    #[test]
+    fn worktree_sessions_reopen_from_the_worktree_project() {
+        let projects = tempfile::tempdir().unwrap();
+        let worktree_dir = tempfile::tempdir().unwrap();
+        let project_path = "/home/dev/my.app";
+        let worktree_path = worktree_dir.path().to_str().unwrap().to_string();
+        let worktree = SessionWorktree {
+            id: "wt".to_string(),
+            engine: SessionEngine::Claude,
+            project_path: project_path.to_string(),
+            worktree_path: worktree_path.clone(),
+            branch: "anycode/session/claude-1".to_string(),
+            base_commit: "abc123".to_string(),
+            session_ids: vec!["s1".to_string()],
+            created_at: String::new(),
+        };
+
+        // The CLI keeps the transcript under the worktree's directory name
+        let session_dir = projects.path().join(encode_project_path(&worktree_path));
+        fs::create_dir_all(&session_dir).unwrap();
+        fs::write(
+            session_dir.join("s1.jsonl"),
+            "{\"type\":\"user\",\"message\":{\"role\":\"user\",\"content\":\"hi\"}}\n",
+        )
+        .unwrap();
+
+        let project_id = encode_project_path(project_path);
+        assert_eq!(project_id, "-home-dev-my-app");
+        assert_eq!(encode_project_path("C:\\work\\my.app"), "C--work-my-app");
+        assert!(load_session_history_from(&projects.path().join(&project_id), "s1").is_err());
+
+        let worktrees = [worktree];
+        let resolved = claude_project_id_in(&worktrees, "s1", &project_id);
+        let messages = load_session_history_from(&projects.path().join(resolved), "s1").unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["type"], "user");
+
+        // Sessions that never ran in the worktree keep their project
+        assert_eq!(
+            claude_project_id_in(&worktrees, "s2", &project_id),
+            project_id
+        );
+    }
+}
@@ -1113,6 +1113,24 @@ pub async fn get_current_codex_config() -> Result<CurrentCodexConfig, String> {
     })
 }
 
+/// Id of the provider preset whose base_url matches the current config.toml
+pub(crate) async fn active_codex_provider_id() -> Option<String> {
+    let base_url = get_current_codex_config().await.ok()?.base_url?;
+    let base_url = base_url.trim_end_matches('/');
+
+    get_codex_provider_presets()
+        .await
+        .ok()?
+        .into_iter()
+        .find(|p| {
+            extract_base_url_from_config(&p.config)
+                .as_deref()
+                .map(|u| u.trim_end_matches('/'))
+                == Some(base_url)
+        })
+        .map(|p| p.id)
+}
+
 /// Switch to a Codex provider configuration
 /// Preserves user's custom settings and OAuth tokens
 /// Supports both Native Windows and WSL modes
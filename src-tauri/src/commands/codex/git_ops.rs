@@ -12,8 +12,9 @@ use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
 
-// Import simple_git for rewind operations
-use super::super::simple_git;
+// Import checkpoints for rewind operations
+use super::super::checkpoint;
+use super::super::session_worktree;
 // Import rewind helpers/types shared with Claude
 use super::super::prompt_tracker::{
     load_execution_config, PromptRecord as ClaudePromptRecord, RewindCapabilities, RewindMode,
@@ -152,6 +153,20 @@ pub fn truncate_codex_git_records(session_id: &str, prompt_index: usize) -> Resu
         prompt_index
     );
 
+    // Checkpoints of the removed prompts are no longer reachable from a record
+    if !git_records.project_path.is_empty() {
+        let keep: Vec<&str> = git_records
+            .records
+            .iter()
+            .flat_map(|r| [Some(r.commit_before.as_str()), r.commit_after.as_deref()])
+            .flatten()
+            .collect();
+        if let Err(e) = checkpoint::retain_checkpoints(&git_records.project_path, session_id, &keep)
+        {
+            log::warn!("[Codex Rewind] Failed to delete stale checkpoints: {}", e);
+        }
+    }
+
     Ok(())
 }
 
@@ -498,6 +513,7 @@ pub async fn record_codex_prompt_sent(
     project_path: String,
     _prompt_text: String,
 ) -> Result<usize, String> {
+    let project_path = session_worktree::resolve_project_path(&session_id, &project_path);
     log::info!(
         "[Codex Record] Recording prompt sent for session: {}",
         session_id
@@ -519,14 +535,6 @@ pub async fn record_codex_prompt_sent(
         return Ok(prompt_index);
     }
 
-    // Ensure Git repository is initialized
-    simple_git::ensure_git_repo(&project_path)
-        .map_err(|e| format!("Failed to ensure Git repo: {}", e))?;
-
-    // Get current commit (state before prompt execution)
-    let commit_before = simple_git::git_current_commit(&project_path)
-        .map_err(|e| format!("Failed to get current commit: {}", e))?;
-
     // Load existing records
     let mut git_records = load_codex_git_records(&session_id)?;
 
@@ -538,6 +546,14 @@ pub async fn record_codex_prompt_sent(
     // Calculate prompt index
     let prompt_index = git_records.records.len();
 
+    // Snapshot the project (state before prompt execution)
+    let commit_before = checkpoint::create_checkpoint(
+        &project_path,
+        &session_id,
+        &format!("[Codex] Before prompt #{}", prompt_index),
+    )
+    .map_err(|e| format!("Failed to create checkpoint: {}", e))?;
+
     // Create new record
     let record = CodexPromptGitRecord {
         prompt_index,
@@ -566,6 +582,7 @@ pub async fn record_codex_prompt_completed(
     prompt_index: usize,
     prompt_text: Option<String>,
 ) -> Result<(), String> {
+    let project_path = session_worktree::resolve_project_path(&session_id, &project_path);
     log::info!(
         "[Codex Record] Recording prompt #{} completed for session: {}",
         prompt_index,
@@ -581,30 +598,11 @@ pub async fn record_codex_prompt_completed(
         return Ok(());
     }
 
-    // Auto-commit any changes made by AI
-    let commit_message = build_prompt_commit_message("[Codex]", prompt_text.as_deref(), prompt_index);
-    match simple_git::git_commit_changes(&project_path, &commit_message) {
-        Ok(true) => {
-            log::info!(
-                "[Codex Record] Auto-committed changes after prompt #{}",
-                prompt_index
-            );
-        }
-        Ok(false) => {
-            log::debug!(
-                "[Codex Record] No changes to commit after prompt #{}",
-                prompt_index
-            );
-        }
-        Err(e) => {
-            log::warn!("[Codex Record] Failed to auto-commit: {}", e);
-            // Continue anyway
-        }
-    }
-
-    // Get current commit (state after AI completion)
-    let commit_after = simple_git::git_current_commit(&project_path)
-        .map_err(|e| format!("Failed to get current commit: {}", e))?;
+    // Snapshot the changes made by AI (state after AI completion)
+    let commit_message =
+        build_prompt_commit_message("[Codex]", prompt_text.as_deref(), prompt_index);
+    let commit_after = checkpoint::create_checkpoint(&project_path, &session_id, &commit_message)
+        .map_err(|e| format!("Failed to create checkpoint: {}", e))?;
 
     // Update the record
     let mut git_records = load_codex_git_records(&session_id)?;
@@ -644,6 +642,7 @@ pub async fn revert_codex_to_prompt(
     prompt_index: usize,
     mode: RewindMode,
 ) -> Result<String, String> {
+    let project_path = session_worktree::resolve_project_path(&session_id, &project_path);
     log::info!(
         "[Codex Rewind] Reverting session {} to prompt #{} with mode: {:?}",
         session_id,
@@ -715,23 +714,17 @@ pub async fn revert_codex_to_prompt(
         RewindMode::CodeOnly => {
             log::info!("[Codex Rewind] Reverting code to state before prompt #{}", prompt_index);
 
-            // Stash uncommitted changes
-            simple_git::git_stash_save(
+            // Checkpoint the current state for atomic rollback on failure
+            let original_state = checkpoint::create_checkpoint(
                 &project_path,
-                &format!(
-                    "Auto-stash before Codex code revert to prompt #{}",
-                    prompt_index
-                ),
+                &session_id,
+                &format!("[Codex] Before code revert to prompt #{}", prompt_index),
             )
-            .map_err(|e| format!("Failed to stash changes: {}", e))?;
-
-            // Record original HEAD for atomic rollback on failure
-            let original_head = simple_git::git_current_commit(&project_path)
-                .map_err(|e| format!("Failed to get current commit: {}", e))?;
+            .map_err(|e| format!("Failed to create checkpoint: {}", e))?;
 
             log::info!(
-                "[Codex Precise Revert] Original HEAD: {} (will rollback here on failure)",
-                &original_head[..8.min(original_head.len())]
+                "[Codex Precise Revert] Original state: {} (will restore it on failure)",
+                &original_state[..8.min(original_state.len())]
             );
 
             // Load ALL git records for this session
@@ -768,7 +761,7 @@ pub async fn revert_codex_to_prompt(
                     }
                 };
 
-                let has_changes = match simple_git::git_has_changes_between_commits(
+                let has_changes = match checkpoint::has_changes_between(
                     &project_path,
                     &record.commit_before,
                     &commit_after,
@@ -801,21 +794,18 @@ pub async fn revert_codex_to_prompt(
                     &commit_after[..8.min(commit_after.len())]
                 );
 
-                let revert_result = simple_git::git_revert_range_with_retry(
+                let revert_result = checkpoint::revert_checkpoint_range(
                     &project_path,
                     &record.commit_before,
                     &commit_after,
-                    &format!("[Codex Revert] 撤回提示词 #{} 的代码更改", record.prompt_index),
-                    3, // Max 3 retries for Git lock conflicts
                 );
 
                 match revert_result {
                     Ok(result) if result.success => {
                         total_reverted += result.commits_reverted;
                         log::info!(
-                            "[Codex Precise Revert] Successfully reverted prompt #{} ({} commits)",
-                            record.prompt_index,
-                            result.commits_reverted
+                            "[Codex Precise Revert] Successfully reverted prompt #{}",
+                            record.prompt_index
                         );
                     }
                     Ok(result) => {
@@ -837,13 +827,13 @@ pub async fn revert_codex_to_prompt(
                 }
             }
 
-            // If revert failed, rollback to original HEAD (atomic operation)
+            // If revert failed, restore the original state (atomic operation)
             if revert_failed {
                 log::warn!(
-                    "[Codex Precise Revert] Rolling back to original HEAD {} due to failure",
-                    &original_head[..8.min(original_head.len())]
+                    "[Codex Precise Revert] Restoring original state {} due to failure",
+                    &original_state[..8.min(original_state.len())]
                 );
-                simple_git::git_reset_hard(&project_path, &original_head)
+                checkpoint::restore_checkpoint(&project_path, &original_state)
                     .map_err(|e| format!("Failed to rollback: {}", e))?;
 
                 return Err(format!(
@@ -853,7 +843,7 @@ pub async fn revert_codex_to_prompt(
             }
 
             log::info!(
-                "[Codex Rewind] Successfully reverted code to state before prompt #{} (reverted {} commits from {} prompts)",
+                "[Codex Rewind] Successfully reverted code to state before prompt #{} (reverted {} of {} prompts)",
                 prompt_index,
                 total_reverted,
                 records_to_revert.len()
@@ -863,23 +853,17 @@ pub async fn revert_codex_to_prompt(
         RewindMode::Both => {
             log::info!("[Codex Rewind] Reverting both to state before prompt #{}", prompt_index);
 
-            // Stash uncommitted changes
-            simple_git::git_stash_save(
+            // Checkpoint the current state for atomic rollback on failure
+            let original_state = checkpoint::create_checkpoint(
                 &project_path,
-                &format!(
-                    "Auto-stash before Codex full revert to prompt #{}",
-                    prompt_index
-                ),
+                &session_id,
+                &format!("[Codex] Before full revert to prompt #{}", prompt_index),
             )
-            .map_err(|e| format!("Failed to stash changes: {}", e))?;
-
-            // Record original HEAD for atomic rollback on failure
-            let original_head = simple_git::git_current_commit(&project_path)
-                .map_err(|e| format!("Failed to get current commit: {}", e))?;
+            .map_err(|e| format!("Failed to create checkpoint: {}", e))?;
 
             log::info!(
-                "[Codex Precise Revert] Original HEAD: {} (will rollback here on failure)",
-                &original_head[..8.min(original_head.len())]
+                "[Codex Precise Revert] Original state: {} (will restore it on failure)",
+                &original_state[..8.min(original_state.len())]
             );
 
             // Load ALL git records for this session
@@ -916,7 +900,7 @@ pub async fn revert_codex_to_prompt(
                     }
                 };
 
-                let has_changes = match simple_git::git_has_changes_between_commits(
+                let has_changes = match checkpoint::has_changes_between(
                     &project_path,
                     &record.commit_before,
                     &commit_after,
@@ -949,21 +933,18 @@ pub async fn revert_codex_to_prompt(
                     &commit_after[..8.min(commit_after.len())]
                 );
 
-                let revert_result = simple_git::git_revert_range_with_retry(
+                let revert_result = checkpoint::revert_checkpoint_range(
                     &project_path,
                     &record.commit_before,
                     &commit_after,
-                    &format!("[Codex Revert] 撤回提示词 #{} 的代码更改", record.prompt_index),
-                    3, // Max 3 retries for Git lock conflicts
                 );
 
                 match revert_result {
                     Ok(result) if result.success => {
                         total_reverted += result.commits_reverted;
                         log::info!(
-                            "[Codex Precise Revert] Successfully reverted prompt #{} ({} commits)",
-                            record.prompt_index,
-                            result.commits_reverted
+                            "[Codex Precise Revert] Successfully reverted prompt #{}",
+                            record.prompt_index
                         );
                     }
                     Ok(result) => {
@@ -985,13 +966,13 @@ pub async fn revert_codex_to_prompt(
                 }
             }
 
-            // If revert failed, rollback to original HEAD (atomic operation)
+            // If revert failed, restore the original state (atomic operation)
             if revert_failed {
                 log::warn!(
-                    "[Codex Precise Revert] Rolling back to original HEAD {} due to failure",
-                    &original_head[..8.min(original_head.len())]
+                    "[Codex Precise Revert] Restoring original state {} due to failure",
+                    &original_state[..8.min(original_state.len())]
                 );
-                simple_git::git_reset_hard(&project_path, &original_head)
+                checkpoint::restore_checkpoint(&project_path, &original_state)
                     .map_err(|e| format!("Failed to rollback: {}", e))?;
 
                 return Err(format!(
@@ -1001,7 +982,7 @@ pub async fn revert_codex_to_prompt(
             }
 
             log::info!(
-                "[Codex Rewind] Successfully reverted code to state before prompt #{} (reverted {} commits from {} prompts)",
+                "[Codex Rewind] Successfully reverted code to state before prompt #{} (reverted {} of {} prompts)",
                 prompt_index,
                 total_reverted,
                 records_to_revert.len()
@@ -1015,7 +996,9 @@ pub async fn revert_codex_to_prompt(
                     e
                 );
 
-                if let Err(rollback_err) = simple_git::git_reset_hard(&project_path, &original_head) {
+                if let Err(rollback_err) =
+                    checkpoint::restore_checkpoint(&project_path, &original_state)
+                {
                     log::error!("[CRITICAL] Git rollback failed: {}", rollback_err);
                     return Err(format!(
                         "会话截断失败且 Git 回滚失败。\n\
@@ -1040,7 +1023,9 @@ pub async fn revert_codex_to_prompt(
                         e
                     );
 
-                    if let Err(rollback_err) = simple_git::git_reset_hard(&project_path, &original_head) {
+                    if let Err(rollback_err) =
+                        checkpoint::restore_checkpoint(&project_path, &original_state)
+                    {
                         log::error!("[CRITICAL] Git rollback failed: {}", rollback_err);
                         return Err(format!(
                             "Git 记录截断失败且回滚失败。\n\
@@ -24,6 +24,12 @@ use crate::process::JobObject;
 use super::super::wsl_utils;
 // Import config module for sessions directory
 use super::config::get_codex_sessions_dir;
+use super::usage::event_usage;
+use crate::commands::budget::{self, UsageSample};
+use crate::commands::checkpoint;
+use crate::commands::enhanced_hooks::{self, HookEvent};
+use crate::commands::session_timeline::SessionEngine;
+use crate::commands::session_worktree;
 
 // ============================================================================
 // Type Definitions
@@ -155,7 +161,7 @@ impl Default for CodexProcessState {
 /// Executes a Codex task in non-interactive mode with streaming output
 #[tauri::command]
 pub async fn execute_codex(
-    options: CodexExecutionOptions,
+    mut options: CodexExecutionOptions,
     app_handle: AppHandle,
 ) -> Result<(), String> {
     // Avoid logging sensitive fields (prompt/api_key). Log only non-sensitive metadata.
@@ -174,23 +180,39 @@ pub async fn execute_codex(
         options.prompt.len()
     );
 
+    // New sessions get their own worktree when session isolation is on
+    options.project_path =
+        session_worktree::prepare_session_worktree(&options.project_path, SessionEngine::Codex)?;
+
     // Build codex exec command
     let (cmd, prompt) = build_codex_command(&options, false, None)?;
 
     // Execute and stream output
     let session_id = format!("codex-{}", uuid::Uuid::new_v4());
-    execute_codex_process(session_id, cmd, prompt, options.project_path.clone(), app_handle).await
+    execute_codex_process(
+        session_id,
+        cmd,
+        prompt,
+        options.project_path.clone(),
+        options.model.clone(),
+        app_handle,
+    )
+    .await
 }
 
 /// Resumes a previous Codex session
 #[tauri::command]
 pub async fn resume_codex(
     session_id: String,
-    options: CodexExecutionOptions,
+    mut options: CodexExecutionOptions,
     app_handle: AppHandle,
 ) -> Result<(), String> {
     log::info!("resume_codex called for session: {}", session_id);
 
+    // A session started in a worktree has to be resumed there
+    options.project_path =
+        session_worktree::resolve_project_path(&session_id, &options.project_path);
+
     // Build codex exec resume command (session_id added inside build function)
     let (cmd, prompt) = build_codex_command(&options, true, Some(&session_id))?;
 
@@ -201,6 +223,7 @@ pub async fn resume_codex(
         cmd,
         prompt,
         options.project_path.clone(),
+        options.model.clone(),
         app_handle,
     )
     .await
@@ -219,7 +242,15 @@ pub async fn resume_last_codex(
 
     // Execute and stream output
     let session_id = format!("codex-{}", uuid::Uuid::new_v4());
-    execute_codex_process(session_id, cmd, prompt, options.project_path.clone(), app_handle).await
+    execute_codex_process(
+        session_id,
+        cmd,
+        prompt,
+        options.project_path.clone(),
+        options.model.clone(),
+        app_handle,
+    )
+    .await
 }
 
 /// Cancels a running Codex execution
@@ -578,10 +609,18 @@ pub async fn delete_codex_session(session_id: String) -> Result<String, String>
     let session_file = find_session_file(&sessions_dir, &session_id)
         .ok_or_else(|| format!("Session file not found for ID: {}", session_id))?;
 
+    let project_path = parse_codex_session_file(&session_file).map(|s| s.project_path);
+
     // Delete the file
     std::fs::remove_file(&session_file)
         .map_err(|e| format!("Failed to delete session file: {}", e))?;
 
+    if let Some(project_path) = project_path.filter(|p| !p.is_empty()) {
+        if let Err(e) = checkpoint::delete_session_checkpoints(&project_path, &session_id) {
+            log::warn!("Failed to delete checkpoints of {}: {}", session_id, e);
+        }
+    }
+
     log::info!(
         "Successfully deleted Codex session file: {:?}",
         session_file
@@ -832,7 +871,8 @@ async fn execute_codex_process(
     session_id: String,
     mut cmd: Command,
     prompt: Option<String>,
-    _project_path: String,
+    project_path: String,
+    model: Option<String>,
     app_handle: AppHandle,
 ) -> Result<(), String> {
     // 启动流程一开始就发送 session_init，确保即使启动失败也能让前端拿到 session_id 做隔离与错误反馈
@@ -972,9 +1012,22 @@ async fn execute_codex_process(
         *last_session = Some(session_id.clone());
     }
 
+    enhanced_hooks::fire(
+        &app_handle,
+        HookEvent::OnSessionStart,
+        &session_id,
+        &project_path,
+        serde_json::json!({
+            "engine": "codex",
+            "model": model,
+            "pid": pid,
+        }),
+    );
+
     // Clone handles for async tasks
     let app_handle_stdout = app_handle.clone();
     let app_handle_complete = app_handle.clone();
+    let project_path_complete = project_path.clone();
     let session_id_stdout = session_id.clone(); // Clone for stdout task
     let session_id_stderr = session_id.clone(); // Clone for stderr task
     let session_id_complete = session_id.clone();
@@ -986,6 +1039,17 @@ async fn execute_codex_process(
     let stderr_buffer_for_stderr = stderr_buffer.clone();
     let stderr_buffer_for_complete = stderr_buffer.clone();
 
+    // Budget tracking: model and provider preset in effect for this run
+    let budget_model = match model {
+        Some(model) => model,
+        None => super::config::get_current_codex_config()
+            .await
+            .ok()
+            .and_then(|c| c.model)
+            .unwrap_or_else(|| "unknown".to_string()),
+    };
+    let budget_provider_id = super::config::active_codex_provider_id().await;
+
     // 🔧 FIX: Use channels to track stdout/stderr closure for timeout detection
     let (done_tx, done_rx) = tokio::sync::oneshot::channel();
     let (stderr_done_tx, _stderr_done_rx) = tokio::sync::oneshot::channel();
@@ -995,11 +1059,23 @@ async fn execute_codex_process(
     tokio::spawn(async move {
         let mut reader = BufReader::new(stdout).lines();
         let mut done_tx = Some(done_tx);
+        let mut usage_state = serde_json::Value::Null;
+        let mut budget_cancelled = false;
         while let Ok(Some(line)) = reader.next_line().await {
             if !line.trim().is_empty() {
                 saw_stdout.store(true, Ordering::Relaxed);
                 // Use trace level to avoid flooding logs in debug mode
                 log::trace!("Codex output: {}", line);
+
+                // Tie the Codex thread to the session worktree it runs in, if any
+                if line.contains("\"thread.started\"") {
+                    if let Some(thread_id) = serde_json::from_str::<serde_json::Value>(&line)
+                        .ok()
+                        .and_then(|v| v["thread_id"].as_str().map(str::to_string))
+                    {
+                        session_worktree::bind_session(&project_path, &thread_id);
+                    }
+                }
                 // Emit to session-specific channel first (for multi-tab isolation)
                 if let Err(e) =
                     app_handle_stdout.emit(&format!("codex-output:{}", session_id_stdout), &line)
@@ -1011,6 +1087,45 @@ async fn execute_codex_process(
                     log::error!("Failed to emit codex-output (global): {}", e);
                 }
 
+                // Check spend against budgets as usage events arrive
+                if !budget_cancelled {
+                    let usage = serde_json::from_str::<serde_json::Value>(&line)
+                        .ok()
+                        .and_then(|event| event_usage(&event, &mut usage_state));
+                    if let Some((input, output, cached)) = usage {
+                        let sample = UsageSample {
+                            engine: SessionEngine::Codex,
+                            session_id: session_id_stdout.clone(),
+                            project_path: project_path.clone(),
+                            model: budget_model.clone(),
+                            provider_id: budget_provider_id.clone(),
+                            input_tokens: input,
+                            output_tokens: output,
+                            cache_creation_tokens: 0,
+                            cache_read_tokens: cached,
+                        };
+                        if let Some(alert) = budget::record_usage(&app_handle_stdout, &sample) {
+                            budget_cancelled = true;
+                            log::warn!(
+                                "[Codex] Budget {} exceeded, cancelling session {}",
+                                alert.rule_id,
+                                alert.session_id
+                            );
+                            let app_for_cancel = app_handle_stdout.clone();
+                            tokio::spawn(async move {
+                                if let Err(e) =
+                                    cancel_codex(Some(alert.session_id), app_for_cancel).await
+                                {
+                                    log::error!(
+                                        "[Codex] Failed to cancel session over budget: {}",
+                                        e
+                                    );
+                                }
+                            });
+                        }
+                    }
+                }
+
                 // Detect turn completion to trigger backend cleanup even if stdout never closes.
                 if done_tx.is_some() {
                     let is_done_event = serde_json::from_str::<serde_json::Value>(&line)
@@ -1104,6 +1219,17 @@ async fn execute_codex_process(
             log::error!("Failed to emit codex-complete (global): {}", e);
         }
 
+        enhanced_hooks::fire(
+            &app_handle_complete,
+            HookEvent::OnSessionEnd,
+            &session_id_complete,
+            &project_path_complete,
+            serde_json::json!({
+                "engine": "codex",
+                "produced_output": saw_stdout_for_complete.load(Ordering::Relaxed),
+            }),
+        );
+
         // Continue waiting for process exit in background (with timeout protection)
         // This ensures proper cleanup but doesn't block the completion event
         // After turn completion, Codex should exit promptly; keep a short grace window to
@@ -3,6 +3,7 @@ use once_cell::sync::Lazy;
  * Claude ↔ Codex Session 转换模块
  *
  * 实现 Claude 与 Codex 引擎之间的 Session 双向转换功能。
+ * Gemini 相关方向见 gemini/session_converter.rs，统一由 convert_session 分发。
  * 支持：
  * - Claude → Codex：将 Claude session 转换为 Codex 可执行的 session
  * - Codex → Claude：将 Codex session 转换为 Claude 可加载的历史记录
@@ -19,6 +20,11 @@ use serde_json::Value;
 use std::collections::HashMap;
 use std::io::{BufRead, BufReader, Write};
 
+use crate::commands::gemini::session_converter::{
+    ClaudeToGeminiConverter, CodexToGeminiConverter, GeminiToClaudeConverter,
+    GeminiToCodexConverter,
+};
+
 // ================================
 // 数据结构定义
 // ================================
@@ -27,7 +33,7 @@ use std::io::{BufRead, BufReader, Write};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ConversionSource {
-    /// 源引擎类型: "claude" | "codex"
+    /// 源引擎类型: "claude" | "codex" | "gemini"
     pub engine: String,
     /// 源 Session ID
     pub session_id: String,
@@ -295,6 +301,208 @@ pub fn map_claude_to_codex_tool(claude_name: &str) -> String {
         .unwrap_or_else(|| claude_name.to_string())
 }
 
+// ================================
+// 共享写入工具（Gemini 转换器同样使用）
+// ================================
+
+/// 转换 content 为标准数组格式
+fn simplify_content(content: Vec<ClaudeContentBlock>) -> Option<Value> {
+    if content.is_empty() {
+        return None;
+    }
+
+    // 统一使用数组格式（与原生 Claude 一致）
+    let array: Vec<Value> = content
+        .iter()
+        .map(|block| match block {
+            ClaudeContentBlock::Text { text } => serde_json::json!({"type": "text", "text": text}),
+            ClaudeContentBlock::ToolUse { id, name, input } => serde_json::json!({
+                "type": "tool_use",
+                "id": id,
+                "name": name,
+                "input": input
+            }),
+            ClaudeContentBlock::ToolResult {
+                tool_use_id,
+                content,
+                is_error,
+            } => serde_json::json!({
+                "type": "tool_result",
+                "tool_use_id": tool_use_id,
+                "content": content,
+                "is_error": is_error
+            }),
+            ClaudeContentBlock::Thinking { thinking } => {
+                serde_json::json!({"type": "thinking", "thinking": thinking})
+            }
+        })
+        .collect();
+
+    Some(Value::Array(array))
+}
+
+/// 创建标准 Claude 消息
+pub(crate) fn build_claude_message(
+    session_id: &str,
+    project_path: &str,
+    message_type: &str,
+    role: &str,
+    content: Vec<ClaudeContentBlock>,
+    timestamp: &str,
+    model: Option<String>,
+) -> ClaudeMessage {
+    ClaudeMessage {
+        message_type: message_type.to_string(),
+        message: Some(ClaudeMessageContent {
+            role: role.to_string(),
+            content: simplify_content(content),
+            usage: None,
+        }),
+        timestamp: Some(timestamp.to_string()),
+        uuid: Some(uuid::Uuid::new_v4().to_string()),
+        parent_uuid: None,
+        session_id: Some(session_id.to_string()),
+        cwd: Some(project_path.to_string()),
+        version: Some("2.0.55".to_string()), // 使用真实版本号，避免被识别为特殊模式
+        git_branch: None,
+        user_type: if role == "user" {
+            Some("external".to_string())
+        } else {
+            None
+        },
+        is_sidechain: Some(false),
+        subtype: None,
+        received_at: if role != "user" {
+            Some(timestamp.to_string())
+        } else {
+            None
+        },
+        sent_at: if role == "user" {
+            Some(timestamp.to_string())
+        } else {
+            None
+        },
+        model,
+        conversion_source: None,
+        extra: HashMap::new(),
+    }
+}
+
+/// file-history-snapshot 消息（Claude session 的第一行，必需！）
+pub(crate) fn file_history_snapshot(timestamp: &str) -> ClaudeMessage {
+    let snapshot_uuid = uuid::Uuid::new_v4().to_string();
+
+    ClaudeMessage {
+        message_type: "file-history-snapshot".to_string(),
+        message: None,
+        timestamp: Some(timestamp.to_string()),
+        uuid: Some(snapshot_uuid.clone()),
+        parent_uuid: None,
+        session_id: None,
+        cwd: None,
+        version: None,
+        git_branch: None,
+        user_type: None,
+        is_sidechain: None,
+        subtype: None,
+        received_at: None,
+        sent_at: None,
+        model: None,
+        conversion_source: None,
+        extra: {
+            let mut map = HashMap::new();
+            map.insert(
+                "messageId".to_string(),
+                Value::String(snapshot_uuid.clone()),
+            );
+            map.insert(
+                "snapshot".to_string(),
+                serde_json::json!({
+                    "messageId": snapshot_uuid,
+                    "trackedFileBackups": {},
+                    "timestamp": timestamp
+                }),
+            );
+            map.insert("isSnapshotUpdate".to_string(), Value::Bool(false));
+            map
+        },
+    }
+}
+
+/// 写入 Claude session 文件（建立 parentUuid 链）
+pub(crate) fn write_claude_session_file(
+    project_id: &str,
+    session_id: &str,
+    messages: &[ClaudeMessage],
+) -> Result<String, String> {
+    let claude_dir = super::super::claude::get_claude_dir()
+        .map_err(|e| format!("Failed to get Claude directory: {}", e))?;
+
+    // 直接使用 project_id（实际的目录名）
+    let project_dir = claude_dir.join("projects").join(project_id);
+
+    std::fs::create_dir_all(&project_dir)
+        .map_err(|e| format!("Failed to create project directory: {}", e))?;
+
+    let file_path = project_dir.join(format!("{}.jsonl", session_id));
+
+    let mut file = std::fs::File::create(&file_path)
+        .map_err(|e| format!("Failed to create session file: {}", e))?;
+
+    // 建立 parentUuid 消息链
+    let mut prev_uuid: Option<String> = None;
+    let mut linked_messages = messages.to_vec();
+
+    for msg in &mut linked_messages {
+        // 设置 parent_uuid 指向前一条消息
+        msg.parent_uuid = prev_uuid.clone();
+        // 更新 prev_uuid 为当前消息的 uuid
+        prev_uuid = msg.uuid.clone();
+    }
+
+    // 写入文件
+    for msg in &linked_messages {
+        let line = serde_json::to_string(msg)
+            .map_err(|e| format!("Failed to serialize message: {}", e))?;
+        writeln!(file, "{}", line).map_err(|e| format!("Failed to write message: {}", e))?;
+    }
+
+    Ok(file_path.to_string_lossy().to_string())
+}
+
+/// 写入 Codex session 文件（sessions/YYYY/MM/DD/<file_stem>.jsonl）
+pub(crate) fn write_codex_session_file(
+    file_stem: &str,
+    events: &[CodexEvent],
+) -> Result<String, String> {
+    let sessions_dir = super::config::get_codex_sessions_dir()
+        .map_err(|e| format!("Failed to get Codex sessions directory: {}", e))?;
+
+    // 创建日期目录结构 YYYY/MM/DD
+    let now = chrono::Utc::now();
+    let date_dir = sessions_dir
+        .join(now.format("%Y").to_string())
+        .join(now.format("%m").to_string())
+        .join(now.format("%d").to_string());
+
+    std::fs::create_dir_all(&date_dir)
+        .map_err(|e| format!("Failed to create date directory: {}", e))?;
+
+    let file_path = date_dir.join(format!("{}.jsonl", file_stem));
+
+    let mut file = std::fs::File::create(&file_path)
+        .map_err(|e| format!("Failed to create session file: {}", e))?;
+
+    // 逐行写入 JSONL
+    for event in events {
+        let line = serde_json::to_string(event)
+            .map_err(|e| format!("Failed to serialize event: {}", e))?;
+        writeln!(file, "{}", line).map_err(|e| format!("Failed to write event: {}", e))?;
+    }
+
+    Ok(file_path.to_string_lossy().to_string())
+}
+
 // ================================
 // Claude → Codex 转换器
 // ================================
@@ -689,32 +897,7 @@ impl ClaudeToCodexConverter {
 
     /// 写入 Codex session 文件
     fn write_codex_session(&self, events: &[CodexEvent]) -> Result<String, String> {
-        let sessions_dir = super::config::get_codex_sessions_dir()
-            .map_err(|e| format!("Failed to get Codex sessions directory: {}", e))?;
-
-        // 创建日期目录结构 YYYY/MM/DD
-        let now = chrono::Utc::now();
-        let date_dir = sessions_dir
-            .join(now.format("%Y").to_string())
-            .join(now.format("%m").to_string())
-            .join(now.format("%d").to_string());
-
-        std::fs::create_dir_all(&date_dir)
-            .map_err(|e| format!("Failed to create date directory: {}", e))?;
-
-        let file_path = date_dir.join(format!("{}.jsonl", self.new_session_filename));
-
-        let mut file = std::fs::File::create(&file_path)
-            .map_err(|e| format!("Failed to create session file: {}", e))?;
-
-        // 逐行写入 JSONL
-        for event in events {
-            let line = serde_json::to_string(event)
-                .map_err(|e| format!("Failed to serialize event: {}", e))?;
-            writeln!(file, "{}", line).map_err(|e| format!("Failed to write event: {}", e))?;
-        }
-
-        Ok(file_path.to_string_lossy().to_string())
+        write_codex_session_file(&self.new_session_filename, events)
     }
 }
 
@@ -741,44 +924,6 @@ impl CodexToClaudeConverter {
         }
     }
 
-    /// 转换 content 为标准数组格式
-    fn simplify_content(&self, content: Vec<ClaudeContentBlock>) -> Option<Value> {
-        if content.is_empty() {
-            return None;
-        }
-
-        // 统一使用数组格式（与原生 Claude 一致）
-        let array: Vec<Value> = content
-            .iter()
-            .filter_map(|block| match block {
-                ClaudeContentBlock::Text { text } => {
-                    Some(serde_json::json!({"type": "text", "text": text}))
-                }
-                ClaudeContentBlock::ToolUse { id, name, input } => Some(serde_json::json!({
-                    "type": "tool_use",
-                    "id": id,
-                    "name": name,
-                    "input": input
-                })),
-                ClaudeContentBlock::ToolResult {
-                    tool_use_id,
-                    content,
-                    is_error,
-                } => Some(serde_json::json!({
-                    "type": "tool_result",
-                    "tool_use_id": tool_use_id,
-                    "content": content,
-                    "is_error": is_error
-                })),
-                ClaudeContentBlock::Thinking { thinking } => {
-                    Some(serde_json::json!({"type": "thinking", "thinking": thinking}))
-                }
-            })
-            .collect();
-
-        Some(Value::Array(array))
-    }
-
     /// 创建标准 Claude 消息的辅助函数
     fn create_claude_message(
         &self,
@@ -788,44 +933,15 @@ impl CodexToClaudeConverter {
         timestamp: &str,
         model: Option<String>,
     ) -> ClaudeMessage {
-        // 将 content 数组转换为简化格式
-        let simplified_content = self.simplify_content(content);
-
-        ClaudeMessage {
-            message_type: message_type.to_string(),
-            message: Some(ClaudeMessageContent {
-                role: role.to_string(),
-                content: simplified_content,
-                usage: None,
-            }),
-            timestamp: Some(timestamp.to_string()),
-            uuid: Some(uuid::Uuid::new_v4().to_string()),
-            parent_uuid: None,
-            session_id: Some(self.new_session_id.clone()),
-            cwd: Some(self.project_path.clone()),
-            version: Some("2.0.55".to_string()), // 使用真实版本号，避免被识别为特殊模式
-            git_branch: None,
-            user_type: if role == "user" {
-                Some("external".to_string())
-            } else {
-                None
-            },
-            is_sidechain: Some(false),
-            subtype: None,
-            received_at: if role != "user" {
-                Some(timestamp.to_string())
-            } else {
-                None
-            },
-            sent_at: if role == "user" {
-                Some(timestamp.to_string())
-            } else {
-                None
-            },
+        build_claude_message(
+            &self.new_session_id,
+            &self.project_path,
+            message_type,
+            role,
+            content,
+            timestamp,
             model,
-            conversion_source: None,
-            extra: HashMap::new(),
-        }
+        )
     }
 
     pub fn convert(&self) -> Result<ConversionResult, String> {
@@ -848,43 +964,7 @@ impl CodexToClaudeConverter {
             .first()
             .and_then(|e| e.timestamp.clone())
             .unwrap_or_else(|| chrono::Utc::now().to_rfc3339());
-        let snapshot_uuid = uuid::Uuid::new_v4().to_string();
-
-        claude_messages.push(ClaudeMessage {
-            message_type: "file-history-snapshot".to_string(),
-            message: None,
-            timestamp: Some(first_timestamp.clone()),
-            uuid: Some(snapshot_uuid.clone()),
-            parent_uuid: None,
-            session_id: None,
-            cwd: None,
-            version: None,
-            git_branch: None,
-            user_type: None,
-            is_sidechain: None,
-            subtype: None,
-            received_at: None,
-            sent_at: None,
-            model: None,
-            conversion_source: None,
-            extra: {
-                let mut map = HashMap::new();
-                map.insert(
-                    "messageId".to_string(),
-                    Value::String(snapshot_uuid.clone()),
-                );
-                map.insert(
-                    "snapshot".to_string(),
-                    serde_json::json!({
-                        "messageId": snapshot_uuid,
-                        "trackedFileBackups": {},
-                        "timestamp": first_timestamp
-                    }),
-                );
-                map.insert("isSnapshotUpdate".to_string(), Value::Bool(false));
-                map
-            },
-        });
+        claude_messages.push(file_history_snapshot(&first_timestamp));
 
         // 3b. 转换 Codex 事件
         for event in &codex_events {
@@ -1176,39 +1256,7 @@ impl CodexToClaudeConverter {
 
     /// 写入 Claude session 文件
     fn write_claude_session(&self, messages: &[ClaudeMessage]) -> Result<String, String> {
-        let claude_dir = super::super::claude::get_claude_dir()
-            .map_err(|e| format!("Failed to get Claude directory: {}", e))?;
-
-        // 直接使用 project_id（实际的目录名）
-        let project_dir = claude_dir.join("projects").join(&self.project_id);
-
-        std::fs::create_dir_all(&project_dir)
-            .map_err(|e| format!("Failed to create project directory: {}", e))?;
-
-        let file_path = project_dir.join(format!("{}.jsonl", self.new_session_id));
-
-        let mut file = std::fs::File::create(&file_path)
-            .map_err(|e| format!("Failed to create session file: {}", e))?;
-
-        // 建立 parentUuid 消息链
-        let mut prev_uuid: Option<String> = None;
-        let mut linked_messages = messages.to_vec();
-
-        for msg in &mut linked_messages {
-            // 设置 parent_uuid 指向前一条消息
-            msg.parent_uuid = prev_uuid.clone();
-            // 更新 prev_uuid 为当前消息的 uuid
-            prev_uuid = msg.uuid.clone();
-        }
-
-        // 写入文件
-        for msg in &linked_messages {
-            let line = serde_json::to_string(msg)
-                .map_err(|e| format!("Failed to serialize message: {}", e))?;
-            writeln!(file, "{}", line).map_err(|e| format!("Failed to write message: {}", e))?;
-        }
-
-        Ok(file_path.to_string_lossy().to_string())
+        write_claude_session_file(&self.project_id, &self.new_session_id, messages)
     }
 }
 
@@ -1217,7 +1265,11 @@ impl CodexToClaudeConverter {
 // ================================
 
 /// 根据文件存在性判断 session 的源引擎类型
-fn detect_session_engine(session_id: &str, project_id: &str) -> Result<String, String> {
+fn detect_session_engine(
+    session_id: &str,
+    project_id: &str,
+    project_path: &str,
+) -> Result<String, String> {
     // 1. 检查是否为 Codex session（查找 sessions 目录）
     if let Ok(sessions_dir) = super::config::get_codex_sessions_dir() {
         if super::session::find_session_file(&sessions_dir, session_id).is_some() {
@@ -1236,8 +1288,13 @@ fn detect_session_engine(session_id: &str, project_id: &str) -> Result<String, S
         }
     }
 
+    // 3. 检查是否为 Gemini session（chats 目录按项目路径哈希存放）
+    if super::super::gemini::config::read_session_detail(project_path, session_id).is_ok() {
+        return Ok("gemini".to_string());
+    }
+
     Err(format!(
-        "Session {} not found in either Claude or Codex directories",
+        "Session {} not found in Claude, Codex or Gemini directories",
         session_id
     ))
 }
@@ -1259,7 +1316,7 @@ pub async fn convert_session(
     );
 
     // 根据文件存在性检测源引擎
-    let source_engine = detect_session_engine(&session_id, &project_id)?;
+    let source_engine = detect_session_engine(&session_id, &project_id, &project_path)?;
 
     if source_engine == target_engine {
         return Err(format!(
@@ -1268,15 +1325,31 @@ pub async fn convert_session(
         ));
     }
 
-    match target_engine.as_str() {
-        "codex" => {
+    match (source_engine.as_str(), target_engine.as_str()) {
+        ("claude", "codex") => {
             let converter = ClaudeToCodexConverter::new(session_id, project_id, project_path);
             converter.convert()
         }
-        "claude" => {
+        ("codex", "claude") => {
             let converter = CodexToClaudeConverter::new(session_id, project_id, project_path);
             converter.convert()
         }
+        ("gemini", "claude") => {
+            let converter = GeminiToClaudeConverter::new(session_id, project_id, project_path);
+            converter.convert()
+        }
+        ("gemini", "codex") => {
+            let converter = GeminiToCodexConverter::new(session_id, project_path);
+            converter.convert()
+        }
+        ("claude", "gemini") => {
+            let converter = ClaudeToGeminiConverter::new(session_id, project_id, project_path);
+            converter.convert()
+        }
+        ("codex", "gemini") => {
+            let converter = CodexToGeminiConverter::new(session_id, project_path);
+            converter.convert()
+        }
         _ => Err(format!("Unknown target engine: {}", target_engine)),
     }
 }
@@ -2,9 +2,12 @@
 ///
 /// These commands integrate the AutoCompactManager with the frontend,
 /// providing comprehensive context window management capabilities.
+use crate::commands::context_history::{self, SessionCompactionHistory};
 use crate::commands::context_manager::{
     AutoCompactConfig, AutoCompactManager, AutoCompactState, SessionContext,
 };
+use crate::commands::session_timeline::SessionEngine;
+use crate::commands::storage::AgentDb;
 use log::{error, info};
 use tauri::{command, AppHandle, Manager, State};
 
@@ -27,13 +30,26 @@ pub async fn init_auto_compact_manager(app: AppHandle) -> Result<(), String> {
 #[command]
 pub async fn register_auto_compact_session(
     state: State<'_, AutoCompactState>,
+    app: AppHandle,
     session_id: String,
     project_path: String,
     model: String,
+    engine: Option<String>,
 ) -> Result<(), String> {
     info!("Registering session {} for auto-compact", session_id);
+    let engine = match engine {
+        Some(engine) => engine.parse::<SessionEngine>()?,
+        None => SessionEngine::Claude,
+    };
 
-    state.0.register_session(session_id, project_path, model)?;
+    state.0.register_session(
+        &app,
+        session_id,
+        project_path,
+        engine,
+        model,
+        crate::commands::provider::active_provider_id(),
+    )?;
     Ok(())
 }
 
@@ -47,13 +63,14 @@ pub async fn update_session_context(
 ) -> Result<bool, String> {
     let compaction_triggered = state
         .0
-        .update_session_tokens(&session_id, token_count)
+        .update_session_tokens(&app, &session_id, token_count)
         .await?;
 
     if compaction_triggered {
         info!("Auto-compaction triggered for session {}", session_id);
 
-        // Execute compaction in background
+        // Execute compaction in background; a session that is still running is
+        // compacted by its runner once the CLI exits
         let manager = state.0.clone();
         let session_id_clone = session_id.clone();
         tokio::spawn(async move {
@@ -76,14 +93,11 @@ pub async fn trigger_manual_compaction(
 ) -> Result<(), String> {
     info!("Manual compaction triggered for session {}", session_id);
 
-    // Temporarily override custom instructions if provided
-    if let Some(instructions) = custom_instructions {
-        let mut config = state.0.get_config()?;
-        config.custom_instructions = Some(instructions);
-        state.0.update_config(config)?;
-    }
-
-    state.0.execute_compaction(app, &session_id).await?;
+    // One-off instructions apply to this compaction only
+    state
+        .0
+        .execute_compaction_with(app, &session_id, custom_instructions)
+        .await?;
     Ok(())
 }
 
@@ -106,13 +120,31 @@ pub async fn update_auto_compact_config(
     Ok(())
 }
 
-/// Get session context statistics
+/// Get session context statistics, falling back to the saved state of a
+/// session that is not monitored in this run
 #[command]
 pub fn get_session_context_stats(
     state: State<'_, AutoCompactState>,
+    db: State<'_, AgentDb>,
     session_id: String,
 ) -> Result<Option<SessionContext>, String> {
-    state.0.get_session_stats(&session_id)
+    if let Some(stats) = state.0.get_session_stats(&session_id)? {
+        return Ok(Some(stats));
+    }
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    context_history::load_session(&conn, &session_id)
+}
+
+/// Get compaction history per session over the last `days` days (default 7),
+/// most frequently compacting sessions first
+#[command]
+pub async fn get_compaction_history(
+    db: State<'_, AgentDb>,
+    session_id: Option<String>,
+    days: Option<u32>,
+) -> Result<Vec<SessionCompactionHistory>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    context_history::query_history(&conn, session_id.as_deref(), days.unwrap_or(7))
 }
 
 /// Get all monitored sessions
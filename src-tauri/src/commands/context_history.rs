@@ -0,0 +1,583 @@
+//! Auto-Compact History
+//!
+//! Keeps what the auto-compact manager knows about each session in agents.db,
+//! so compaction counts, the last compaction time and the token history
+//! survive a restart. The manager writes a session row whenever it changes the
+//! session, a token sample per reported turn and a record per compaction; a
+//! session it does not know yet is restored from its row when it registers.
+//!
+//! [`query_history`] summarises the compaction records per session, which is
+//! how sessions that compact too often (short intervals, small reductions)
+//! show up in the UI.
+
+use chrono::{Duration as ChronoDuration, SecondsFormat, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+use super::context_manager::{SessionContext, SessionStatus};
+use super::session_timeline::SessionEngine;
+use super::storage::AgentDb;
+
+/// Token samples kept per session; older samples are dropped
+const MAX_TOKEN_SAMPLES_PER_SESSION: i64 = 500;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// A compaction attempt of a session
+#[derive(Debug, Clone, Serialize)]
+pub struct CompactionRecord {
+    pub id: i64,
+    pub session_id: String,
+    pub success: bool,
+    pub tokens_before: Option<usize>,
+    /// Reported by the CLI, or measured on the first turn after the compaction
+    pub tokens_after: Option<usize>,
+    pub error: Option<String>,
+    pub created_at: String,
+}
+
+/// Context size reported by one turn
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenSample {
+    pub tokens: usize,
+    pub context_window: usize,
+    pub recorded_at: String,
+}
+
+/// Compaction history of one session over the queried period
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionCompactionHistory {
+    pub session_id: String,
+    pub project_path: String,
+    pub model: String,
+    pub compactions: usize,
+    pub failures: usize,
+    /// Mean time between successful compactions
+    pub average_interval_secs: Option<u64>,
+    /// Mean share of the context removed by a compaction (0.0-1.0)
+    pub average_reduction: Option<f64>,
+    pub last_compaction_at: Option<String>,
+    pub records: Vec<CompactionRecord>,
+    /// Only filled when a single session is queried
+    pub token_history: Vec<TokenSample>,
+}
+
+// ============================================================================
+// Tables
+// ============================================================================
+
+/// Creates the auto-compact tables (called from `storage::init_database`)
+pub fn init_context_history_tables(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS auto_compact_sessions (
+            session_id TEXT PRIMARY KEY,
+            project_path TEXT NOT NULL,
+            model TEXT NOT NULL,
+            provider_id TEXT,
+            engine TEXT NOT NULL DEFAULT 'claude',
+            current_tokens INTEGER NOT NULL,
+            message_count INTEGER NOT NULL,
+            last_compaction INTEGER,
+            compaction_count INTEGER NOT NULL,
+            context_window INTEGER NOT NULL,
+            status TEXT NOT NULL,
+            pre_compaction_tokens INTEGER,
+            updated_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    // Columns added after auto_compact_sessions was first shipped
+    let columns: HashSet<String> = conn
+        .prepare("PRAGMA table_info(auto_compact_sessions)")?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<SqliteResult<_>>()?;
+    if !columns.contains("engine") {
+        conn.execute(
+            "ALTER TABLE auto_compact_sessions ADD COLUMN engine TEXT NOT NULL DEFAULT 'claude'",
+            [],
+        )?;
+    }
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS auto_compact_token_samples (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id TEXT NOT NULL,
+            tokens INTEGER NOT NULL,
+            context_window INTEGER NOT NULL,
+            recorded_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_auto_compact_token_samples_session
+         ON auto_compact_token_samples(session_id, id)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS auto_compact_compactions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id TEXT NOT NULL,
+            project_path TEXT NOT NULL,
+            model TEXT NOT NULL,
+            success INTEGER NOT NULL,
+            tokens_before INTEGER,
+            tokens_after INTEGER,
+            error TEXT,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_auto_compact_compactions_session
+         ON auto_compact_compactions(session_id, id)",
+        [],
+    )?;
+    Ok(())
+}
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Inserts or updates the row of a session
+pub fn save_session(conn: &Connection, session: &SessionContext) -> Result<(), String> {
+    let status = serde_json::to_string(&session.status).map_err(|e| e.to_string())?;
+    let last_compaction = session
+        .last_compaction
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64);
+    conn.execute(
+        "INSERT INTO auto_compact_sessions
+            (session_id, project_path, model, provider_id, current_tokens, message_count,
+             last_compaction, compaction_count, context_window, status,
+             pre_compaction_tokens, updated_at, engine)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+         ON CONFLICT(session_id) DO UPDATE SET
+            project_path = excluded.project_path,
+            engine = excluded.engine,
+            model = excluded.model,
+            provider_id = excluded.provider_id,
+            current_tokens = excluded.current_tokens,
+            message_count = excluded.message_count,
+            last_compaction = excluded.last_compaction,
+            compaction_count = excluded.compaction_count,
+            context_window = excluded.context_window,
+            status = excluded.status,
+            pre_compaction_tokens = excluded.pre_compaction_tokens,
+            updated_at = excluded.updated_at",
+        params![
+            session.session_id,
+            session.project_path,
+            session.model,
+            session.provider_id,
+            session.current_tokens as i64,
+            session.message_count as i64,
+            last_compaction,
+            session.compaction_count as i64,
+            session.context_window as i64,
+            status,
+            session.pre_compaction_tokens.map(|t| t as i64),
+            now(),
+            session.engine.as_str(),
+        ],
+    )
+    .map_err(|e| format!("Failed to save auto-compact session: {}", e))?;
+    Ok(())
+}
+
+/// Loads the saved row of a session
+///
+/// A compaction cannot have survived the restart, so a session saved while
+/// compacting comes back as active.
+pub fn load_session(conn: &Connection, session_id: &str) -> Result<Option<SessionContext>, String> {
+    conn.query_row(
+        "SELECT session_id, project_path, model, provider_id, current_tokens, message_count,
+                last_compaction, compaction_count, context_window, status,
+                pre_compaction_tokens, engine
+         FROM auto_compact_sessions
+         WHERE session_id = ?1",
+        params![session_id],
+        |row| {
+            let status: String = row.get(9)?;
+            let status = match serde_json::from_str(&status) {
+                Ok(SessionStatus::Compacting) | Err(_) => SessionStatus::Active,
+                Ok(status) => status,
+            };
+            Ok(SessionContext {
+                session_id: row.get(0)?,
+                project_path: row.get(1)?,
+                model: row.get(2)?,
+                provider_id: row.get(3)?,
+                current_tokens: row.get::<_, i64>(4)? as usize,
+                message_count: row.get::<_, i64>(5)? as usize,
+                last_compaction: row
+                    .get::<_, Option<i64>>(6)?
+                    .map(|secs| UNIX_EPOCH + Duration::from_secs(secs as u64)),
+                compaction_count: row.get::<_, i64>(7)? as usize,
+                engine: row
+                    .get::<_, String>(11)?
+                    .parse::<SessionEngine>()
+                    .unwrap_or_default(),
+                context_window: row.get::<_, i64>(8)? as usize,
+                status,
+                pre_compaction_tokens: row.get::<_, Option<i64>>(10)?.map(|t| t as usize),
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| format!("Failed to load auto-compact session: {}", e))
+}
+
+/// Moves a session's row and history to the id the CLI continued it under
+pub fn rename_session(conn: &Connection, old_id: &str, new_id: &str) -> Result<(), String> {
+    for table in [
+        "auto_compact_sessions",
+        "auto_compact_token_samples",
+        "auto_compact_compactions",
+    ] {
+        conn.execute(
+            &format!("UPDATE {} SET session_id = ?2 WHERE session_id = ?1", table),
+            params![old_id, new_id],
+        )
+        .map_err(|e| format!("Failed to rename auto-compact session: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Appends a token sample for the session's latest turn
+pub fn insert_token_sample(conn: &Connection, session: &SessionContext) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO auto_compact_token_samples (session_id, tokens, context_window, recorded_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![
+            session.session_id,
+            session.current_tokens as i64,
+            session.context_window as i64,
+            now(),
+        ],
+    )
+    .map_err(|e| format!("Failed to record token sample: {}", e))?;
+    conn.execute(
+        "DELETE FROM auto_compact_token_samples
+         WHERE session_id = ?1 AND id <= (
+            SELECT id FROM auto_compact_token_samples
+            WHERE session_id = ?1
+            ORDER BY id DESC
+            LIMIT 1 OFFSET ?2
+         )",
+        params![session.session_id, MAX_TOKEN_SAMPLES_PER_SESSION],
+    )
+    .map_err(|e| format!("Failed to prune token samples: {}", e))?;
+    Ok(())
+}
+
+/// Records a compaction attempt of the session
+pub fn insert_compaction(
+    conn: &Connection,
+    session: &SessionContext,
+    tokens_before: Option<usize>,
+    tokens_after: Option<usize>,
+    error: Option<&str>,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO auto_compact_compactions
+            (session_id, project_path, model, success, tokens_before, tokens_after, error,
+             created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            session.session_id,
+            session.project_path,
+            session.model,
+            error.is_none(),
+            tokens_before.map(|t| t as i64),
+            tokens_after.map(|t| t as i64),
+            error,
+            now(),
+        ],
+    )
+    .map_err(|e| format!("Failed to record compaction: {}", e))?;
+    Ok(())
+}
+
+/// Fills in the size after the session's last compaction once a turn reports it
+pub fn set_measured_tokens(
+    conn: &Connection,
+    session_id: &str,
+    tokens_after: usize,
+) -> Result<(), String> {
+    conn.execute(
+        "UPDATE auto_compact_compactions SET tokens_after = ?2
+         WHERE id = (
+            SELECT MAX(id) FROM auto_compact_compactions
+            WHERE session_id = ?1 AND success = 1 AND tokens_after IS NULL
+         )",
+        params![session_id, tokens_after as i64],
+    )
+    .map_err(|e| format!("Failed to record measured compaction: {}", e))?;
+    Ok(())
+}
+
+/// Runs `write` against agents.db, logging instead of failing
+///
+/// Auto-compact keeps working from memory when the database is unavailable.
+pub fn record(app: &AppHandle, write: impl FnOnce(&Connection) -> Result<(), String>) {
+    let Some(db) = app.try_state::<AgentDb>() else {
+        return;
+    };
+    let Ok(conn) = db.0.lock() else {
+        log::warn!("[AutoCompact] Database lock poisoned, history not saved");
+        return;
+    };
+    if let Err(e) = write(&conn) {
+        log::warn!("[AutoCompact] {}", e);
+    }
+}
+
+/// Saved state of a session that is not monitored in this run of the app
+pub fn restore_session(app: &AppHandle, session_id: &str) -> Option<SessionContext> {
+    let db = app.try_state::<AgentDb>()?;
+    let conn = db.0.lock().ok()?;
+    load_session(&conn, session_id).unwrap_or_else(|e| {
+        log::warn!("[AutoCompact] {}", e);
+        None
+    })
+}
+
+// ============================================================================
+// History Queries
+// ============================================================================
+
+fn query_compactions(
+    conn: &Connection,
+    session_id: Option<&str>,
+    since: &str,
+) -> Result<Vec<(String, String, CompactionRecord)>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, session_id, project_path, model, success, tokens_before, tokens_after,
+                    error, created_at
+             FROM auto_compact_compactions
+             WHERE (?1 IS NULL OR session_id = ?1) AND created_at >= ?2
+             ORDER BY id",
+        )
+        .map_err(|e| format!("Failed to query compaction history: {}", e))?;
+
+    let rows = stmt
+        .query_map(params![session_id, since], |row| {
+            Ok((
+                row.get(2)?,
+                row.get(3)?,
+                CompactionRecord {
+                    id: row.get(0)?,
+                    session_id: row.get(1)?,
+                    success: row.get(4)?,
+                    tokens_before: row.get::<_, Option<i64>>(5)?.map(|t| t as usize),
+                    tokens_after: row.get::<_, Option<i64>>(6)?.map(|t| t as usize),
+                    error: row.get(7)?,
+                    created_at: row.get(8)?,
+                },
+            ))
+        })
+        .map_err(|e| format!("Failed to query compaction history: {}", e))?;
+
+    rows.collect::<SqliteResult<Vec<_>>>()
+        .map_err(|e| format!("Failed to read compaction history: {}", e))
+}
+
+fn query_token_samples(
+    conn: &Connection,
+    session_id: &str,
+    since: &str,
+) -> Result<Vec<TokenSample>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT tokens, context_window, recorded_at
+             FROM auto_compact_token_samples
+             WHERE session_id = ?1 AND recorded_at >= ?2
+             ORDER BY id",
+        )
+        .map_err(|e| format!("Failed to query token history: {}", e))?;
+
+    let rows = stmt
+        .query_map(params![session_id, since], |row| {
+            Ok(TokenSample {
+                tokens: row.get::<_, i64>(0)? as usize,
+                context_window: row.get::<_, i64>(1)? as usize,
+                recorded_at: row.get(2)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query token history: {}", e))?;
+
+    rows.collect::<SqliteResult<Vec<_>>>()
+        .map_err(|e| format!("Failed to read token history: {}", e))
+}
+
+fn summarize(
+    session_id: String,
+    project_path: String,
+    model: String,
+    records: Vec<CompactionRecord>,
+) -> SessionCompactionHistory {
+    let successful: Vec<&CompactionRecord> = records.iter().filter(|r| r.success).collect();
+
+    let times: Vec<i64> = successful
+        .iter()
+        .filter_map(|r| chrono::DateTime::parse_from_rfc3339(&r.created_at).ok())
+        .map(|t| t.timestamp())
+        .collect();
+    let average_interval_secs = (times.len() > 1).then(|| {
+        let span = times[times.len() - 1] - times[0];
+        (span.max(0) as u64) / (times.len() as u64 - 1)
+    });
+
+    let reductions: Vec<f64> = successful
+        .iter()
+        .filter_map(|r| match (r.tokens_before, r.tokens_after) {
+            (Some(before), Some(after)) if before > 0 => {
+                Some(1.0 - after.min(before) as f64 / before as f64)
+            }
+            _ => None,
+        })
+        .collect();
+    let average_reduction =
+        (!reductions.is_empty()).then(|| reductions.iter().sum::<f64>() / reductions.len() as f64);
+
+    SessionCompactionHistory {
+        session_id,
+        project_path,
+        model,
+        compactions: successful.len(),
+        failures: records.len() - successful.len(),
+        average_interval_secs,
+        average_reduction,
+        last_compaction_at: successful.last().map(|r| r.created_at.clone()),
+        records,
+        token_history: Vec::new(),
+    }
+}
+
+/// Compaction history per session over the last `days` days
+///
+/// Sessions come most frequently compacting first: by number of compactions,
+/// then by shortest interval between them.
+pub fn query_history(
+    conn: &Connection,
+    session_id: Option<&str>,
+    days: u32,
+) -> Result<Vec<SessionCompactionHistory>, String> {
+    let since = (Utc::now() - ChronoDuration::days(days as i64))
+        .to_rfc3339_opts(SecondsFormat::Millis, true);
+
+    let mut grouped: BTreeMap<String, (String, String, Vec<CompactionRecord>)> = BTreeMap::new();
+    for (project_path, model, record) in query_compactions(conn, session_id, &since)? {
+        let entry = grouped
+            .entry(record.session_id.clone())
+            .or_insert_with(|| (String::new(), String::new(), Vec::new()));
+        // The latest record carries the session's current project and model
+        entry.0 = project_path;
+        entry.1 = model;
+        entry.2.push(record);
+    }
+
+    let mut history: Vec<SessionCompactionHistory> = grouped
+        .into_iter()
+        .map(|(id, (project_path, model, records))| summarize(id, project_path, model, records))
+        .collect();
+
+    if let Some(session_id) = session_id {
+        let token_history = query_token_samples(conn, session_id, &since)?;
+        match history.first_mut() {
+            Some(entry) => entry.token_history = token_history,
+            None => {
+                if let Some(session) = load_session(conn, session_id)? {
+                    let mut entry = summarize(
+                        session.session_id,
+                        session.project_path,
+                        session.model,
+                        Vec::new(),
+                    );
+                    entry.token_history = token_history;
+                    history.push(entry);
+                }
+            }
+        }
+    }
+
+    history.sort_by(|a, b| {
+        b.compactions.cmp(&a.compactions).then_with(|| {
+            a.average_interval_secs
+                .unwrap_or(u64::MAX)
+                .cmp(&b.average_interval_secs.unwrap_or(u64::MAX))
+        })
+    });
+    Ok(history)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(id: &str) -> SessionContext {
+        SessionContext {
+            session_id: id.to_string(),
+            project_path: "/work/app".to_string(),
+            current_tokens: 170_000,
+            message_count: 12,
+            last_compaction: Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+            compaction_count: 2,
+            engine: SessionEngine::Codex,
+            model: "sonnet".to_string(),
+            provider_id: Some("proxy".to_string()),
+            context_window: 200_000,
+            status: SessionStatus::Compacting,
+            pre_compaction_tokens: None,
+        }
+    }
+
+    #[test]
+    fn sessions_and_history_survive_reload() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_context_history_tables(&conn).unwrap();
+
+        let mut busy = session("busy");
+        save_session(&conn, &busy).unwrap();
+        insert_token_sample(&conn, &busy).unwrap();
+        insert_compaction(&conn, &busy, Some(170_000), Some(40_000), None).unwrap();
+        insert_compaction(&conn, &busy, Some(160_000), None, None).unwrap();
+        set_measured_tokens(&conn, "busy", 80_000).unwrap();
+        insert_compaction(&conn, &busy, Some(150_000), None, Some("timeout")).unwrap();
+        busy.session_id = "busy-2".to_string();
+        rename_session(&conn, "busy", "busy-2").unwrap();
+
+        let quiet = session("quiet");
+        save_session(&conn, &quiet).unwrap();
+        insert_compaction(&conn, &quiet, Some(170_000), Some(50_000), None).unwrap();
+
+        let restored = load_session(&conn, "busy-2").unwrap().unwrap();
+        assert_eq!(restored.compaction_count, 2);
+        assert_eq!(restored.last_compaction, busy.last_compaction);
+        assert_eq!(restored.provider_id.as_deref(), Some("proxy"));
+        assert_eq!(restored.engine, SessionEngine::Codex);
+        assert!(matches!(restored.status, SessionStatus::Active));
+        assert!(load_session(&conn, "busy").unwrap().is_none());
+
+        let history = query_history(&conn, None, 7).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].session_id, "busy-2");
+        assert_eq!(history[0].compactions, 2);
+        assert_eq!(history[0].failures, 1);
+        assert!(history[0].average_interval_secs.is_some());
+        let reduction = history[0].average_reduction.unwrap();
+        assert!((reduction - (130.0 / 170.0 + 0.5) / 2.0).abs() < 1e-9);
+        assert!(history[0].token_history.is_empty());
+
+        let single = query_history(&conn, Some("busy-2"), 7).unwrap();
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].token_history.len(), 1);
+        assert_eq!(single[0].token_history[0].tokens, 170_000);
+    }
+}
@@ -1,15 +1,27 @@
 use log::{error, info};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
 /// Auto-compact context management system for Claude Code SDK integration
 ///
 /// This module provides intelligent context window management with automatic compaction
 /// based on Claude Code SDK best practices and the official documentation.
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime};
-use tauri::Emitter;
+use tauri::{Emitter, Manager};
 use tokio::time::sleep;
 
+use crate::commands::claude::compact_claude_session;
+use crate::commands::context_history;
+use crate::commands::context_windows::context_windows;
+use crate::commands::enhanced_hooks::{self, HookEvent};
+use crate::commands::session_timeline::SessionEngine;
+use crate::utils::config_utils::{load_json_config, save_json_config};
+
+/// How long a new run waits for a compaction of its session, matching the
+/// timeout of the compaction run itself
+const COMPACTION_WAIT_SECS: u64 = 600;
+
 /// Event payload for compaction status changes
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompactionEvent {
@@ -19,6 +31,9 @@ pub struct CompactionEvent {
     pub message: Option<String>,
     pub tokens_before: Option<usize>,
     pub tokens_after: Option<usize>,
+    /// Id the CLI continued the session under, when compaction changed it
+    #[serde(default)]
+    pub new_session_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,15 +43,21 @@ pub enum CompactionEventType {
     InProgress,
     Completed,
     Failed,
+    /// Post-compaction context size observed on the first turn after compaction
+    Measured,
 }
 
-/// Configuration for auto-compact behavior
+/// Configuration for auto-compact behavior, saved in `~/.anycode/auto_compact.json`
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AutoCompactConfig {
     /// Enable automatic compaction
     pub enabled: bool,
-    /// Maximum context tokens before triggering compaction (default: 120000 for Claude 4)
+    /// Context window used when `use_model_context_windows` is off (default: 120000)
     pub max_context_tokens: usize,
+    /// Size each session by its model's context window (see `context_windows`)
+    #[serde(default = "default_use_model_context_windows")]
+    pub use_model_context_windows: bool,
     /// Threshold percentage to trigger compaction (0.0-1.0, default: 0.85)
     pub compaction_threshold: f64,
     /// Minimum time between compactions in seconds (default: 300s = 5min)
@@ -51,6 +72,10 @@ pub struct AutoCompactConfig {
     pub custom_instructions: Option<String>,
 }
 
+fn default_use_model_context_windows() -> bool {
+    true
+}
+
 /// Compaction strategies matching Claude Code SDK
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CompactionStrategy {
@@ -74,8 +99,21 @@ pub struct SessionContext {
     #[serde(with = "systemtime_serde")]
     pub last_compaction: Option<SystemTime>,
     pub compaction_count: usize,
+    /// CLI the session runs in; model windows are looked up per engine
+    #[serde(default)]
+    pub engine: SessionEngine,
     pub model: String,
+    /// Provider config the session runs through, for context window overrides
+    #[serde(default)]
+    pub provider_id: Option<String>,
+    /// Context window the compaction threshold was last computed from
+    #[serde(default)]
+    pub context_window: usize,
     pub status: SessionStatus,
+    /// Context size before the last compaction, kept until the next turn reports
+    /// the size after it
+    #[serde(default)]
+    pub pre_compaction_tokens: Option<usize>,
 }
 
 mod systemtime_serde {
@@ -110,6 +148,8 @@ mod systemtime_serde {
 pub enum SessionStatus {
     Active,
     Idle,
+    /// Threshold crossed; compaction runs once the current CLI run exits
+    CompactionPending,
     Compacting,
     CompactionFailed(String),
 }
@@ -117,6 +157,8 @@ pub enum SessionStatus {
 /// Auto-compact manager state
 pub struct AutoCompactManager {
     pub sessions: Arc<Mutex<HashMap<String, SessionContext>>>,
+    /// Ids sessions were continued under after a compaction, keyed by the old id
+    pub successors: Arc<Mutex<HashMap<String, String>>>,
     pub config: Arc<Mutex<AutoCompactConfig>>,
     pub is_monitoring: Arc<Mutex<bool>>,
 }
@@ -126,6 +168,7 @@ impl Default for AutoCompactConfig {
         Self {
             enabled: true,
             max_context_tokens: 120000, // Claude 4 context window
+            use_model_context_windows: true,
             compaction_threshold: 0.85,
             min_compaction_interval: 300, // 5 minutes
             compaction_strategy: CompactionStrategy::Smart,
@@ -137,23 +180,58 @@ impl Default for AutoCompactConfig {
 }
 
 impl AutoCompactManager {
-    /// Create a new AutoCompactManager instance
+    /// Create a new AutoCompactManager instance with the saved configuration
     pub fn new() -> Self {
+        let config = load_auto_compact_config().unwrap_or_else(|e| {
+            log::warn!("[AutoCompact] Failed to load config, using defaults: {}", e);
+            AutoCompactConfig::default()
+        });
         Self {
             sessions: Arc::new(Mutex::new(HashMap::new())),
-            config: Arc::new(Mutex::new(AutoCompactConfig::default())),
+            successors: Arc::new(Mutex::new(HashMap::new())),
+            config: Arc::new(Mutex::new(config)),
             is_monitoring: Arc::new(Mutex::new(false)),
         }
     }
 
     /// Register a new session for monitoring
+    ///
+    /// Every resumed turn registers again; a known session keeps its token and
+    /// compaction history and only picks up the model of the new run. A session
+    /// monitored before the app restarted is restored from agents.db.
     pub fn register_session(
         &self,
+        app: &tauri::AppHandle,
         session_id: String,
         project_path: String,
+        engine: SessionEngine,
         model: String,
+        provider_id: Option<String>,
     ) -> Result<(), String> {
         let mut sessions = self.sessions.lock().map_err(|e| e.to_string())?;
+        let config = self.config.lock().map_err(|e| e.to_string())?;
+        let context_window =
+            effective_context_window(&config, engine, &model, provider_id.as_deref());
+
+        if !sessions.contains_key(&session_id) {
+            if let Some(restored) = context_history::restore_session(app, &session_id) {
+                info!(
+                    "Restored session {} for auto-compact monitoring ({} compactions)",
+                    session_id, restored.compaction_count
+                );
+                sessions.insert(session_id.clone(), restored);
+            }
+        }
+
+        if let Some(existing) = sessions.get_mut(&session_id) {
+            existing.project_path = project_path;
+            existing.engine = engine;
+            existing.model = model;
+            existing.provider_id = provider_id;
+            existing.context_window = context_window;
+            context_history::record(app, |conn| context_history::save_session(conn, existing));
+            return Ok(());
+        }
 
         let context = SessionContext {
             session_id: session_id.clone(),
@@ -162,10 +240,15 @@ impl AutoCompactManager {
             message_count: 0,
             last_compaction: None,
             compaction_count: 0,
+            engine,
             model,
+            provider_id,
+            context_window,
             status: SessionStatus::Active,
+            pre_compaction_tokens: None,
         };
 
+        context_history::record(app, |conn| context_history::save_session(conn, &context));
         sessions.insert(session_id.clone(), context);
         info!(
             "Registered session {} for auto-compact monitoring",
@@ -175,70 +258,212 @@ impl AutoCompactManager {
     }
 
     /// Update session token count and trigger compaction if needed
+    ///
+    /// `token_count` is the context size of the latest response. Returns true when
+    /// the session crossed the threshold and was marked for compaction.
     pub async fn update_session_tokens(
         &self,
+        app: &tauri::AppHandle,
         session_id: &str,
         token_count: usize,
     ) -> Result<bool, String> {
         let mut sessions = self.sessions.lock().map_err(|e| e.to_string())?;
         let config = self.config.lock().map_err(|e| e.to_string())?;
 
-        if !config.enabled {
-            return Ok(false);
-        }
-
         if let Some(session) = sessions.get_mut(session_id) {
             session.current_tokens = token_count;
             session.message_count += 1;
 
-            // Check if compaction is needed
-            let threshold_tokens =
-                (config.max_context_tokens as f64 * config.compaction_threshold) as usize;
-            let needs_compaction = token_count >= threshold_tokens;
-
-            // Check minimum interval
-            let interval_ok = if let Some(last_compaction) = session.last_compaction {
-                let elapsed = SystemTime::now()
-                    .duration_since(last_compaction)
-                    .unwrap_or(Duration::from_secs(0));
-                elapsed.as_secs() >= config.min_compaction_interval
-            } else {
-                true // No previous compaction
-            };
-
-            if needs_compaction && interval_ok {
+            // First turn after a compaction tells us what it actually achieved
+            let measured = session.pre_compaction_tokens.take();
+            if let Some(tokens_before) = measured {
                 info!(
-                    "Auto-compaction triggered for session {}: {} tokens (threshold: {})",
-                    session_id, token_count, threshold_tokens
+                    "Context of session {} after compaction: {} tokens (was {})",
+                    session_id, token_count, tokens_before
+                );
+                let _ = app.emit("auto-compact-event", CompactionEvent {
+                    session_id: session_id.to_string(),
+                    event_type: CompactionEventType::Measured,
+                    progress: None,
+                    message: None,
+                    tokens_before: Some(tokens_before),
+                    tokens_after: Some(token_count),
+                    new_session_id: None,
+                });
+            }
+
+            let mut triggered = false;
+            // Compaction resumes the session with Claude's `/compact`; Codex and
+            // Gemini sessions are only monitored
+            if config.enabled
+                && session.engine == SessionEngine::Claude
+                && !matches!(
+                    session.status,
+                    SessionStatus::CompactionPending | SessionStatus::Compacting
+                )
+            {
+                // Check if compaction is needed against this session's model window
+                session.context_window = effective_context_window(
+                    &config,
+                    session.engine,
+                    &session.model,
+                    session.provider_id.as_deref(),
                 );
-                session.status = SessionStatus::Compacting;
-                return Ok(true);
+                let threshold_tokens =
+                    (session.context_window as f64 * config.compaction_threshold) as usize;
+                let needs_compaction = token_count >= threshold_tokens;
+
+                // Check minimum interval
+                let interval_ok = if let Some(last_compaction) = session.last_compaction {
+                    let elapsed = SystemTime::now()
+                        .duration_since(last_compaction)
+                        .unwrap_or(Duration::from_secs(0));
+                    elapsed.as_secs() >= config.min_compaction_interval
+                } else {
+                    true // No previous compaction
+                };
+
+                if needs_compaction && interval_ok {
+                    info!(
+                        "Auto-compaction triggered for session {}: {} tokens (threshold: {})",
+                        session_id, token_count, threshold_tokens
+                    );
+                    session.status = SessionStatus::CompactionPending;
+                    triggered = true;
+                }
             }
+
+            context_history::record(app, |conn| {
+                context_history::insert_token_sample(conn, session)?;
+                if measured.is_some() {
+                    context_history::set_measured_tokens(conn, session_id, token_count)?;
+                }
+                context_history::save_session(conn, session)
+            });
+            return Ok(triggered);
         }
 
         Ok(false)
     }
 
+    /// Whether a session crossed the threshold and waits for compaction
+    pub fn is_compaction_pending(&self, session_id: &str) -> bool {
+        self.sessions
+            .lock()
+            .map(|sessions| {
+                sessions
+                    .get(session_id)
+                    .is_some_and(|s| matches!(s.status, SessionStatus::CompactionPending))
+            })
+            .unwrap_or(false)
+    }
+
+    /// Waits while a compaction of the session is pending or running
+    ///
+    /// Prompts sent in that window are held back instead of resuming the
+    /// session under the compaction. Returns the id to resume, which differs
+    /// from `session_id` when the CLI continued the session under a new id.
+    pub async fn wait_for_compaction(&self, app: &tauri::AppHandle, session_id: &str) -> String {
+        let deadline = std::time::Instant::now() + Duration::from_secs(COMPACTION_WAIT_SECS);
+        let mut session_id = session_id.to_string();
+        let mut announced = false;
+        loop {
+            let busy = match self.sessions.lock() {
+                Ok(sessions) => match sessions.get(&session_id).map(|s| &s.status) {
+                    Some(SessionStatus::Compacting) => true,
+                    // Pending without a run means the finished run is about to compact it
+                    Some(SessionStatus::CompactionPending) => {
+                        !is_claude_run_active(app, &session_id)
+                    }
+                    _ => false,
+                },
+                Err(_) => false,
+            };
+            if !busy {
+                let successor = self
+                    .successors
+                    .lock()
+                    .ok()
+                    .and_then(|successors| successors.get(&session_id).cloned());
+                match successor {
+                    Some(next) => {
+                        info!("Session {} continues as {}", session_id, next);
+                        session_id = next;
+                        continue;
+                    }
+                    None => return session_id,
+                }
+            }
+            if std::time::Instant::now() >= deadline {
+                log::warn!(
+                    "Compaction of session {} still running after {}s, resuming anyway",
+                    session_id,
+                    COMPACTION_WAIT_SECS
+                );
+                return session_id;
+            }
+            if !announced {
+                info!("Waiting for compaction of session {} to finish", session_id);
+                announced = true;
+            }
+            sleep(Duration::from_millis(500)).await;
+        }
+    }
+
     /// Execute compaction for a session
     pub async fn execute_compaction(
         &self,
         app: tauri::AppHandle,
         session_id: &str,
+    ) -> Result<(), String> {
+        self.execute_compaction_with(app, session_id, None).await
+    }
+
+    /// Execute compaction for a session, adding one-off instructions to the configured ones
+    ///
+    /// Resumes the session with `/compact` so the CLI rewrites its own history.
+    /// Token counts come from the CLI: `tokens_before` is what it measured before
+    /// compacting, `tokens_after` is reported when the CLI includes it, otherwise
+    /// a `Measured` event follows on the session's next turn.
+    pub async fn execute_compaction_with(
+        &self,
+        app: tauri::AppHandle,
+        session_id: &str,
+        extra_instructions: Option<String>,
     ) -> Result<(), String> {
         info!("Executing auto-compaction for session {}", session_id);
 
-        let (project_path, custom_instructions, tokens_before) = {
-            let sessions = self.sessions.lock().map_err(|e| e.to_string())?;
+        if is_claude_run_active(&app, session_id) {
+            return Err(format!(
+                "Session {} is still running; compact it once the run finishes",
+                session_id
+            ));
+        }
+
+        let (project_path, model, tracked_tokens, instructions) = {
+            let mut sessions = self.sessions.lock().map_err(|e| e.to_string())?;
             let config = self.config.lock().map_err(|e| e.to_string())?;
 
             let session = sessions
-                .get(session_id)
+                .get_mut(session_id)
                 .ok_or_else(|| format!("Session {} not found", session_id))?;
+            if session.engine != SessionEngine::Claude {
+                return Err(format!(
+                    "Session {} runs in {}, only Claude sessions can be compacted",
+                    session_id,
+                    session.engine.as_str()
+                ));
+            }
+            if matches!(session.status, SessionStatus::Compacting) {
+                return Err(format!("Session {} is already being compacted", session_id));
+            }
+            session.status = SessionStatus::Compacting;
 
             (
                 session.project_path.clone(),
-                config.custom_instructions.clone(),
+                session.model.clone(),
                 session.current_tokens,
+                build_compaction_instructions(&config, extra_instructions.as_deref()),
             )
         };
 
@@ -248,45 +473,77 @@ impl AutoCompactManager {
             event_type: CompactionEventType::Started,
             progress: Some(0),
             message: Some("正在优化上下文...".to_string()),
-            tokens_before: Some(tokens_before),
+            tokens_before: Some(tracked_tokens),
             tokens_after: None,
+            new_session_id: None,
         });
 
-        // Build compaction command based on strategy
-        let compaction_cmd = self.build_compaction_command(&custom_instructions).await?;
-
         // Emit in-progress event
         let _ = app.emit("auto-compact-event", CompactionEvent {
             session_id: session_id.to_string(),
             event_type: CompactionEventType::InProgress,
             progress: Some(50),
             message: Some("正在压缩会话历史...".to_string()),
-            tokens_before: Some(tokens_before),
+            tokens_before: Some(tracked_tokens),
             tokens_after: None,
+            new_session_id: None,
         });
 
         // Execute compaction using Claude CLI
-        match self
-            .execute_claude_compaction(&app, &project_path, &compaction_cmd)
-            .await
-        {
-            Ok(_) => {
+        match compact_claude_session(&app, &project_path, session_id, &model, &instructions).await {
+            Ok(outcome) => {
+                let tokens_before = outcome.pre_tokens.unwrap_or(tracked_tokens);
+                let tokens_after = outcome.post_tokens;
+
                 // Update session state after successful compaction
-                let mut sessions = self.sessions.lock().map_err(|e| e.to_string())?;
-                let tokens_after = if let Some(session) = sessions.get_mut(session_id) {
-                    session.last_compaction = Some(SystemTime::now());
-                    session.compaction_count += 1;
-                    session.status = SessionStatus::Active;
-                    session.current_tokens = session.current_tokens / 3; // Estimated token reduction
+                let mut new_session_id = None;
+                {
+                    let mut sessions = self.sessions.lock().map_err(|e| e.to_string())?;
+                    if let Some(mut session) = sessions.remove(session_id) {
+                        session.last_compaction = Some(SystemTime::now());
+                        session.compaction_count += 1;
+                        session.status = SessionStatus::Active;
+                        session.current_tokens = tokens_after.unwrap_or(0);
+                        session.pre_compaction_tokens =
+                            tokens_after.is_none().then_some(tokens_before);
+
+                        // Follow the session if the CLI continued it under a new id
+                        if let Some(new_id) = outcome.session_id.filter(|id| id != session_id) {
+                            info!(
+                                "Session {} continues as {} after compaction",
+                                session_id, new_id
+                            );
+                            if let Ok(mut successors) = self.successors.lock() {
+                                successors.insert(session_id.to_string(), new_id.clone());
+                            }
+                            new_session_id = Some(new_id.clone());
+                            session.session_id = new_id;
+                        }
 
-                    info!(
-                        "Auto-compaction completed for session {}: compaction #{}, estimated tokens: {}",
-                        session_id, session.compaction_count, session.current_tokens
-                    );
-                    session.current_tokens
-                } else {
-                    tokens_before / 3
-                };
+                        info!(
+                            "Auto-compaction completed for session {}: compaction #{}, tokens: {} -> {:?}",
+                            session_id, session.compaction_count, tokens_before, tokens_after
+                        );
+                        context_history::record(&app, |conn| {
+                            if session.session_id != session_id {
+                                context_history::rename_session(
+                                    conn,
+                                    session_id,
+                                    &session.session_id,
+                                )?;
+                            }
+                            context_history::insert_compaction(
+                                conn,
+                                &session,
+                                Some(tokens_before),
+                                tokens_after,
+                                None,
+                            )?;
+                            context_history::save_session(conn, &session)
+                        });
+                        sessions.insert(session.session_id.clone(), session);
+                    }
+                }
 
                 // Emit compaction completed event
                 let _ = app.emit("auto-compact-event", CompactionEvent {
@@ -295,9 +552,22 @@ impl AutoCompactManager {
                     progress: Some(100),
                     message: Some("上下文优化完成".to_string()),
                     tokens_before: Some(tokens_before),
-                    tokens_after: Some(tokens_after),
+                    tokens_after,
+                    new_session_id,
                 });
 
+                enhanced_hooks::fire(
+                    &app,
+                    HookEvent::OnContextCompact,
+                    session_id,
+                    &project_path,
+                    serde_json::json!({
+                        "success": true,
+                        "tokens_before": tokens_before,
+                        "tokens_after": tokens_after,
+                    }),
+                );
+
                 Ok(())
             }
             Err(e) => {
@@ -305,6 +575,16 @@ impl AutoCompactManager {
                 let mut sessions = self.sessions.lock().map_err(|e| e.to_string())?;
                 if let Some(session) = sessions.get_mut(session_id) {
                     session.status = SessionStatus::CompactionFailed(e.clone());
+                    context_history::record(&app, |conn| {
+                        context_history::insert_compaction(
+                            conn,
+                            session,
+                            Some(tracked_tokens),
+                            None,
+                            Some(e.as_str()),
+                        )?;
+                        context_history::save_session(conn, session)
+                    });
                 }
                 error!("Auto-compaction failed for session {}: {}", session_id, e);
 
@@ -314,103 +594,26 @@ impl AutoCompactManager {
                     event_type: CompactionEventType::Failed,
                     progress: Some(0),
                     message: Some(format!("压缩失败: {}", e)),
-                    tokens_before: Some(tokens_before),
+                    tokens_before: Some(tracked_tokens),
                     tokens_after: None,
+                    new_session_id: None,
                 });
 
-                Err(e)
-            }
-        }
-    }
-
-    /// Build compaction command based on strategy
-    async fn build_compaction_command(
-        &self,
-        custom_instructions: &Option<String>,
-    ) -> Result<String, String> {
-        let config = self.config.lock().map_err(|e| e.to_string())?;
+                enhanced_hooks::fire(
+                    &app,
+                    HookEvent::OnContextCompact,
+                    session_id,
+                    &project_path,
+                    serde_json::json!({
+                        "success": false,
+                        "tokens_before": tracked_tokens,
+                        "error": e,
+                    }),
+                );
 
-        let base_instruction = match &config.compaction_strategy {
-            CompactionStrategy::Smart => {
-                "Focus on preserving key information, decisions made, and current context. \
-                Remove redundant explanations and verbose descriptions while keeping \
-                essential technical details and project state."
-            }
-            CompactionStrategy::Aggressive => {
-                "Preserve only the most critical information: current task, key decisions, \
-                and essential context. Remove all explanatory text and focus on actionable items."
-            }
-            CompactionStrategy::Conservative => {
-                "Maintain comprehensive context while removing only obvious redundancies. \
-                Preserve detailed explanations and keep full context of recent interactions."
+                Err(e)
             }
-            CompactionStrategy::Custom(instructions) => instructions,
-        };
-
-        let final_instruction = if let Some(custom) = custom_instructions {
-            format!(
-                "{}\n\nAdditional instructions: {}",
-                base_instruction, custom
-            )
-        } else {
-            base_instruction.to_string()
-        };
-
-        Ok(final_instruction)
-    }
-
-    /// Execute Claude CLI compaction command
-    async fn execute_claude_compaction(
-        &self,
-        app: &tauri::AppHandle,
-        project_path: &str,
-        instructions: &str,
-    ) -> Result<(), String> {
-        // Find Claude CLI binary
-        let claude_path = crate::claude_binary::find_claude_binary(app)?;
-
-        // Build compaction command
-        let mut cmd = tokio::process::Command::new(&claude_path);
-        cmd.args(&["/compact"])
-            .current_dir(project_path)
-            .stdin(std::process::Stdio::piped())
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped());
-
-        // 🔥 Fix: Apply platform-specific no-window configuration to hide console
-        crate::commands::claude::apply_no_window_async(&mut cmd);
-
-        // Execute compaction
-        let mut child = cmd
-            .spawn()
-            .map_err(|e| format!("Failed to spawn compaction process: {}", e))?;
-
-        // Send instructions to stdin
-        if let Some(stdin) = child.stdin.take() {
-            use tokio::io::AsyncWriteExt;
-            let mut stdin = stdin;
-            stdin
-                .write_all(instructions.as_bytes())
-                .await
-                .map_err(|e| format!("Failed to write compaction instructions: {}", e))?;
-            stdin
-                .shutdown()
-                .await
-                .map_err(|e| format!("Failed to close stdin: {}", e))?;
         }
-
-        // Wait for completion
-        let output = child
-            .wait_with_output()
-            .await
-            .map_err(|e| format!("Failed to wait for compaction: {}", e))?;
-
-        if !output.status.success() {
-            let error = String::from_utf8_lossy(&output.stderr);
-            return Err(format!("Compaction failed: {}", error));
-        }
-
-        Ok(())
     }
 
     /// Start background monitoring
@@ -425,6 +628,7 @@ impl AutoCompactManager {
         drop(is_monitoring);
 
         let sessions = self.sessions.clone();
+        let successors = self.successors.clone();
         let config = self.config.clone();
         let is_monitoring_flag = self.is_monitoring.clone();
 
@@ -451,7 +655,8 @@ impl AutoCompactManager {
                         }
 
                         if let Some(session) = sessions.get(&session_id) {
-                            matches!(session.status, SessionStatus::Compacting)
+                            matches!(session.status, SessionStatus::CompactionPending)
+                                && !is_claude_run_active(&app, &session_id)
                         } else {
                             false
                         }
@@ -463,6 +668,7 @@ impl AutoCompactManager {
                         let session_id_clone = session_id.clone();
                         let manager = AutoCompactManager {
                             sessions: sessions.clone(),
+                            successors: successors.clone(),
                             config: config.clone(),
                             is_monitoring: is_monitoring_flag.clone(),
                         };
@@ -499,9 +705,10 @@ impl AutoCompactManager {
         Ok(())
     }
 
-    /// Update configuration
+    /// Update configuration and save it for the next start
     pub fn update_config(&self, new_config: AutoCompactConfig) -> Result<(), String> {
         let mut config = self.config.lock().map_err(|e| e.to_string())?;
+        save_json_config(&new_config, get_auto_compact_config_path()?)?;
         *config = new_config;
         info!("Auto-compact configuration updated");
         Ok(())
@@ -519,7 +726,7 @@ impl AutoCompactManager {
         Ok(sessions.get(session_id).cloned())
     }
 
-    /// Remove session from monitoring
+    /// Remove session from monitoring; its saved state and history stay in agents.db
     pub fn unregister_session(&self, session_id: &str) -> Result<(), String> {
         let mut sessions = self.sessions.lock().map_err(|e| e.to_string())?;
         sessions.remove(session_id);
@@ -531,6 +738,76 @@ impl AutoCompactManager {
     }
 }
 
+fn get_auto_compact_config_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Failed to get home directory")?;
+    Ok(home.join(".anycode").join("auto_compact.json"))
+}
+
+/// Saved auto-compact configuration, or the defaults when none was saved
+pub fn load_auto_compact_config() -> Result<AutoCompactConfig, String> {
+    load_json_config(get_auto_compact_config_path()?)
+}
+
+/// Instructions passed to `/compact` for the configured strategy
+fn build_compaction_instructions(config: &AutoCompactConfig, extra: Option<&str>) -> String {
+    let base_instruction = match &config.compaction_strategy {
+        CompactionStrategy::Smart => {
+            "Focus on preserving key information, decisions made, and current context. \
+            Remove redundant explanations and verbose descriptions while keeping \
+            essential technical details and project state."
+        }
+        CompactionStrategy::Aggressive => {
+            "Preserve only the most critical information: current task, key decisions, \
+            and essential context. Remove all explanatory text and focus on actionable items."
+        }
+        CompactionStrategy::Conservative => {
+            "Maintain comprehensive context while removing only obvious redundancies. \
+            Preserve detailed explanations and keep full context of recent interactions."
+        }
+        CompactionStrategy::Custom(instructions) => instructions,
+    };
+
+    let mut instructions = base_instruction.to_string();
+    if config.preserve_recent_messages && config.preserve_message_count > 0 {
+        instructions.push_str(&format!(
+            " Keep the last {} messages close to verbatim.",
+            config.preserve_message_count
+        ));
+    }
+    for custom in [config.custom_instructions.as_deref(), extra]
+        .into_iter()
+        .flatten()
+        .filter(|c| !c.trim().is_empty())
+    {
+        instructions.push_str(&format!(" Additional instructions: {}", custom.trim()));
+    }
+    instructions
+}
+
+/// Context window that sizes a session: its model's window from the registry, or
+/// the global `max_context_tokens` when model windows are turned off
+fn effective_context_window(
+    config: &AutoCompactConfig,
+    engine: SessionEngine,
+    model: &str,
+    provider_id: Option<&str>,
+) -> usize {
+    if !config.use_model_context_windows {
+        return config.max_context_tokens;
+    }
+    context_windows()
+        .resolve(engine, model, provider_id)
+        .map(|w| w.context_window)
+        .unwrap_or(config.max_context_tokens)
+}
+
+/// Whether the CLI is still running a turn of the session; resuming it now would fork it
+fn is_claude_run_active(app: &tauri::AppHandle, session_id: &str) -> bool {
+    app.try_state::<crate::process::ProcessRegistryState>()
+        .and_then(|registry| registry.0.find_claude_run(session_id).ok().flatten())
+        .is_some()
+}
+
 /// State wrapper for AutoCompactManager
 #[derive(Clone)]
 pub struct AutoCompactState(pub Arc<AutoCompactManager>);
@@ -0,0 +1,338 @@
+//! Model Context Windows
+//!
+//! Context window sizes for the Claude, Codex and Gemini models, used by the
+//! auto-compact manager to decide when a session is getting full. The built-in
+//! entries carry the published window of each model family;
+//! `~/.anycode/context_windows.json` adds custom entries on top, e.g. for a
+//! proxied provider config that serves a smaller window.
+//!
+//! Entries match a model through a case-insensitive regex and may be limited
+//! to one provider config. Lookups pick, in order: a custom entry for the
+//! provider, a custom entry for any provider, then the built-in list. Within a
+//! group the first matching entry wins.
+
+use once_cell::sync::Lazy;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use super::pricing::normalize_model;
+use super::session_timeline::SessionEngine;
+use crate::utils::config_utils::{load_json_config, save_json_config};
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// A row of the context window table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextWindowEntry {
+    #[serde(default)]
+    pub id: String,
+    pub engine: SessionEngine,
+    /// Case-insensitive regex searched for in the model name
+    pub model_pattern: String,
+    /// Only applies to sessions run through this provider config
+    #[serde(default)]
+    pub provider_id: Option<String>,
+    /// Window size in tokens
+    pub context_window: usize,
+}
+
+/// Custom entries saved in `~/.anycode/context_windows.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextWindowConfig {
+    #[serde(default)]
+    pub entries: Vec<ContextWindowEntry>,
+}
+
+/// Where a resolved window came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContextWindowSource {
+    Custom,
+    Builtin,
+}
+
+/// Result of a context window lookup
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedContextWindow {
+    pub entry_id: String,
+    pub source: ContextWindowSource,
+    pub context_window: usize,
+}
+
+// ============================================================================
+// Built-in Windows
+// Claude: https://docs.claude.com/en/docs/about-claude/models/overview
+// Codex:  model_context_window reported by the Codex CLI
+// Gemini: https://ai.google.dev/gemini-api/docs/models
+// Last Updated: February 2026 (must match frontend tokenCounter.ts)
+// ============================================================================
+
+/// (id, engine, model pattern, context window)
+#[rustfmt::skip]
+const BUILTIN_WINDOWS: &[(&str, SessionEngine, &str, usize)] = &[
+    // Claude: the [1m] variants (frontend ids `sonnet1m` / `opus1m`) get the 1M window
+    ("claude-1m", SessionEngine::Claude, r"\[1m\]|1m$", 1_000_000),
+    ("claude-default", SessionEngine::Claude, r".*", 200_000),
+    // GPT-5.3 Codex
+    ("gpt-5.3-codex", SessionEngine::Codex, r"5\.3-codex|5_3_codex|gpt-?5\.3", 400_000),
+    // GPT-5.2 / GPT-5.1 / GPT-5 Codex
+    ("gpt-5.2", SessionEngine::Codex, r"5\.2|5_2", 272_000),
+    ("gpt-5.1-codex", SessionEngine::Codex, r"5\.1|5_1", 272_000),
+    ("gpt-5-codex", SessionEngine::Codex, r"gpt-?5-codex", 272_000),
+    ("codex-mini-latest", SessionEngine::Codex, r"codex[-_]mini[-_]latest", 272_000),
+    ("o4-mini", SessionEngine::Codex, r"o4[-_]mini", 128_000),
+    ("codex-default", SessionEngine::Codex, r".*", 400_000),
+    // Gemini
+    ("gemini-3.1-pro", SessionEngine::Gemini, r"3\.1-pro|gemini_3_1_pro", 2_000_000),
+    ("gemini-default", SessionEngine::Gemini, r".*", 1_000_000),
+];
+
+/// The built-in windows as table entries
+pub fn builtin_entries() -> Vec<ContextWindowEntry> {
+    BUILTIN_WINDOWS
+        .iter()
+        .map(|(id, engine, pattern, context_window)| ContextWindowEntry {
+            id: id.to_string(),
+            engine: *engine,
+            model_pattern: pattern.to_string(),
+            provider_id: None,
+            context_window: *context_window,
+        })
+        .collect()
+}
+
+// ============================================================================
+// Lookup
+// ============================================================================
+
+struct CompiledEntry {
+    entry: ContextWindowEntry,
+    pattern: Regex,
+}
+
+impl CompiledEntry {
+    fn compile(entry: ContextWindowEntry) -> Result<Self, String> {
+        let pattern = RegexBuilder::new(&entry.model_pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| format!("Invalid model pattern '{}': {}", entry.model_pattern, e))?;
+        Ok(Self { entry, pattern })
+    }
+
+    fn matches(&self, engine: SessionEngine, model: &str) -> bool {
+        self.entry.engine == engine && self.pattern.is_match(model)
+    }
+
+    fn resolve(&self, source: ContextWindowSource) -> ResolvedContextWindow {
+        ResolvedContextWindow {
+            entry_id: self.entry.id.clone(),
+            source,
+            context_window: self.entry.context_window,
+        }
+    }
+}
+
+/// Compiled context window table
+pub struct ContextWindowBook {
+    custom: Vec<CompiledEntry>,
+    builtin: Vec<CompiledEntry>,
+}
+
+impl ContextWindowBook {
+    fn new(config: ContextWindowConfig) -> Self {
+        let custom = config
+            .entries
+            .into_iter()
+            .filter_map(|entry| match CompiledEntry::compile(entry) {
+                Ok(compiled) => Some(compiled),
+                Err(e) => {
+                    log::warn!("[ContextWindows] Ignoring custom entry: {}", e);
+                    None
+                }
+            })
+            .collect();
+        let builtin = builtin_entries()
+            .into_iter()
+            .filter_map(|entry| CompiledEntry::compile(entry).ok())
+            .collect();
+        Self { custom, builtin }
+    }
+
+    /// Context window of `model` when run through `provider_id`
+    pub fn resolve(
+        &self,
+        engine: SessionEngine,
+        model: &str,
+        provider_id: Option<&str>,
+    ) -> Option<ResolvedContextWindow> {
+        let model = normalize_model(model);
+
+        if let Some(provider_id) = provider_id {
+            if let Some(entry) = self.custom.iter().find(|e| {
+                e.entry.provider_id.as_deref() == Some(provider_id) && e.matches(engine, &model)
+            }) {
+                return Some(entry.resolve(ContextWindowSource::Custom));
+            }
+        }
+        if let Some(entry) = self
+            .custom
+            .iter()
+            .find(|e| e.entry.provider_id.is_none() && e.matches(engine, &model))
+        {
+            return Some(entry.resolve(ContextWindowSource::Custom));
+        }
+        self.builtin
+            .iter()
+            .find(|e| e.matches(engine, &model))
+            .map(|e| e.resolve(ContextWindowSource::Builtin))
+    }
+}
+
+static CONTEXT_WINDOWS: Lazy<Mutex<Option<Arc<ContextWindowBook>>>> =
+    Lazy::new(|| Mutex::new(None));
+
+/// The current context window table, loaded from disk on first use
+pub fn context_windows() -> Arc<ContextWindowBook> {
+    let mut cached = CONTEXT_WINDOWS.lock().unwrap_or_else(|e| e.into_inner());
+    cached
+        .get_or_insert_with(|| {
+            let config = load_context_window_config().unwrap_or_else(|e| {
+                log::warn!("[ContextWindows] Failed to load custom entries: {}", e);
+                ContextWindowConfig::default()
+            });
+            Arc::new(ContextWindowBook::new(config))
+        })
+        .clone()
+}
+
+// ============================================================================
+// Config Persistence
+// ============================================================================
+
+fn get_context_window_config_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Failed to get home directory")?;
+    Ok(home.join(".anycode").join("context_windows.json"))
+}
+
+pub fn load_context_window_config() -> Result<ContextWindowConfig, String> {
+    load_json_config(get_context_window_config_path()?)
+}
+
+fn validate_entry(entry: &mut ContextWindowEntry) -> Result<(), String> {
+    if entry.id.trim().is_empty() {
+        entry.id = uuid::Uuid::new_v4().to_string();
+    }
+    if entry.model_pattern.trim().is_empty() {
+        return Err(format!(
+            "Context window '{}' needs a model pattern",
+            entry.id
+        ));
+    }
+    if entry.context_window == 0 {
+        return Err(format!("Context window '{}' must be positive", entry.id));
+    }
+    entry.provider_id = entry
+        .provider_id
+        .take()
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty());
+    CompiledEntry::compile(entry.clone()).map(|_| ())
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Custom context window entries
+#[tauri::command]
+pub async fn get_context_window_config() -> Result<ContextWindowConfig, String> {
+    load_context_window_config()
+}
+
+/// Validates and saves the custom context window entries
+#[tauri::command]
+pub async fn save_context_window_config(
+    mut config: ContextWindowConfig,
+) -> Result<ContextWindowConfig, String> {
+    for entry in &mut config.entries {
+        validate_entry(entry)?;
+    }
+    save_json_config(&config, get_context_window_config_path()?)?;
+    *CONTEXT_WINDOWS.lock().unwrap_or_else(|e| e.into_inner()) =
+        Some(Arc::new(ContextWindowBook::new(config.clone())));
+    log::info!(
+        "[ContextWindows] Saved {} custom entr(ies)",
+        config.entries.len()
+    );
+    Ok(config)
+}
+
+/// Built-in context windows, for display next to the custom entries
+#[tauri::command]
+pub async fn get_builtin_context_windows() -> Result<Vec<ContextWindowEntry>, String> {
+    Ok(builtin_entries())
+}
+
+/// Context window that applies to a model and provider
+#[tauri::command]
+pub async fn resolve_context_window(
+    engine: SessionEngine,
+    model: String,
+    provider_id: Option<String>,
+) -> Result<Option<ResolvedContextWindow>, String> {
+    Ok(context_windows().resolve(engine, &model, provider_id.as_deref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn provider_overrides_and_1m_variants() {
+        let book = ContextWindowBook::new(ContextWindowConfig {
+            entries: vec![ContextWindowEntry {
+                id: "proxy-sonnet".to_string(),
+                engine: SessionEngine::Claude,
+                model_pattern: "sonnet".to_string(),
+                provider_id: Some("proxy".to_string()),
+                context_window: 64_000,
+            }],
+        });
+        let window = |engine, model, provider| {
+            book.resolve(engine, model, provider)
+                .unwrap()
+                .context_window
+        };
+
+        assert_eq!(window(SessionEngine::Claude, "sonnet1m", None), 1_000_000);
+        assert_eq!(window(SessionEngine::Claude, "opus[1m]", None), 1_000_000);
+        assert_eq!(
+            window(SessionEngine::Claude, "claude-sonnet-4-6", None),
+            200_000
+        );
+        assert_eq!(
+            window(SessionEngine::Claude, "sonnet", Some("proxy")),
+            64_000
+        );
+        assert_eq!(
+            window(SessionEngine::Claude, "opus", Some("proxy")),
+            200_000
+        );
+        assert_eq!(
+            window(SessionEngine::Codex, "gpt-5.1-codex-max", None),
+            272_000
+        );
+        assert_eq!(window(SessionEngine::Codex, "o4-mini", None), 128_000);
+        assert_eq!(
+            window(SessionEngine::Gemini, "gemini-3.1-pro-preview", None),
+            2_000_000
+        );
+    }
+}
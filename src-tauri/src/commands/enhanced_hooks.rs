@@ -6,12 +6,23 @@ use log::{debug, error, info, warn};
 /// - Hooks链式执行和条件触发
 /// - 与现有组件深度集成（AutoCompactManager等）
 /// - 错误处理和回滚机制
+use rusqlite::{params, Connection, Result as SqliteResult};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::{Arc, Mutex};
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager, State};
 use tokio::process::Command;
 
+use super::claude::normalize_path_for_comparison;
+use super::hook_condition::Condition;
+use super::pre_commit_review;
+use super::session_timeline::SessionEngine;
+use super::storage::AgentDb;
+use crate::utils::config_utils::{load_json_config, save_json_config};
+
 /// 扩展的Hook事件类型
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "PascalCase")]
@@ -50,6 +61,27 @@ impl HookEvent {
     }
 }
 
+impl FromStr for HookEvent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "PreToolUse" => Ok(HookEvent::PreToolUse),
+            "PostToolUse" => Ok(HookEvent::PostToolUse),
+            "Notification" => Ok(HookEvent::Notification),
+            "Stop" => Ok(HookEvent::Stop),
+            "SubagentStop" => Ok(HookEvent::SubagentStop),
+            "OnContextCompact" => Ok(HookEvent::OnContextCompact),
+            "OnAgentSwitch" => Ok(HookEvent::OnAgentSwitch),
+            "OnFileChange" => Ok(HookEvent::OnFileChange),
+            "OnSessionStart" => Ok(HookEvent::OnSessionStart),
+            "OnSessionEnd" => Ok(HookEvent::OnSessionEnd),
+            "OnTabSwitch" => Ok(HookEvent::OnTabSwitch),
+            _ => Err(format!("Unknown hook event: {}", s)),
+        }
+    }
+}
+
 /// Hook执行上下文
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HookContext {
@@ -67,6 +99,27 @@ pub struct HookExecutionResult {
     pub error: Option<String>,
     pub execution_time_ms: u64,
     pub hook_command: String,
+    #[serde(default)]
+    pub attempts: u32, // 实际执行次数（含重试），跳过时为0
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub follow_ups: Vec<HookExecutionResult>, // on_success / on_failure 命令的结果
+}
+
+impl HookExecutionResult {
+    fn failed(hook_command: &str, error: String) -> Self {
+        Self {
+            success: false,
+            output: String::new(),
+            error: Some(error),
+            execution_time_ms: 0,
+            hook_command: hook_command.to_string(),
+            attempts: 0,
+            exit_code: None,
+            follow_ups: Vec::new(),
+        }
+    }
 }
 
 /// Hook链执行结果
@@ -97,6 +150,103 @@ pub struct EnhancedHook {
     pub condition: Option<ConditionalTrigger>,
     pub on_success: Option<Vec<String>>, // 成功后执行的命令
     pub on_failure: Option<Vec<String>>, // 失败后执行的命令
+    #[serde(default)]
+    pub retry_backoff_ms: Option<u64>, // 首次重试前的等待时间，之后每次翻倍
+    #[serde(default)]
+    pub cwd: Option<String>, // 工作目录，相对路径基于项目目录
+    #[serde(default)]
+    pub env: Option<HashMap<String, String>>, // 额外环境变量
+}
+
+/// 默认的首次重试等待时间
+const DEFAULT_RETRY_BACKOFF_MS: u64 = 1_000;
+
+/// 重试等待时间上限
+const MAX_RETRY_BACKOFF_MS: u64 = 30_000;
+
+/// 通过临时文件传给后续命令的父hook输出上限
+const MAX_PARENT_OUTPUT_BYTES: usize = 64 * 1024;
+
+/// `HOOK_PARENT_OUTPUT` / `HOOK_PARENT_ERROR` 预览的上限，两者合计远低于
+/// Windows 环境块的 32767 字符限制；完整输出见 `HOOK_PARENT_*_FILE`
+const MAX_PARENT_ENV_BYTES: usize = 4 * 1024;
+
+/// 第 `retry`（从1开始）次重试前的等待时间：指数退避并封顶
+fn retry_delay_ms(base_ms: u64, retry: u32) -> u64 {
+    let factor = 1u64 << retry.saturating_sub(1).min(16);
+    base_ms.saturating_mul(factor).min(MAX_RETRY_BACKOFF_MS)
+}
+
+/// 按字符边界截断字符串
+fn truncate_utf8(text: &str, max_bytes: usize) -> &str {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// 把父hook输出写入临时文件，文件在后续命令执行完后随返回值删除
+fn write_parent_output(text: &str) -> Option<tempfile::NamedTempFile> {
+    let write = || -> std::io::Result<tempfile::NamedTempFile> {
+        let mut file = tempfile::Builder::new()
+            .prefix("anycode-hook-")
+            .suffix(".txt")
+            .tempfile()?;
+        file.write_all(truncate_utf8(text, MAX_PARENT_OUTPUT_BYTES).as_bytes())?;
+        file.flush()?;
+        Ok(file)
+    };
+    write()
+        .map_err(|e| warn!("Failed to write hook output for follow-up commands: {}", e))
+        .ok()
+}
+
+/// 传给后续命令的 `HOOK_PARENT_*` 环境变量
+fn parent_env(
+    hook: &EnhancedHook,
+    outcome: &CommandOutcome,
+    attempts: u32,
+    stdout_file: Option<&tempfile::NamedTempFile>,
+    stderr_file: Option<&tempfile::NamedTempFile>,
+) -> Vec<(&'static str, String)> {
+    let mut env = vec![
+        ("HOOK_PARENT_COMMAND", hook.command.clone()),
+        ("HOOK_PARENT_SUCCESS", outcome.success.to_string()),
+        (
+            "HOOK_PARENT_EXIT_CODE",
+            outcome.exit_code.map(|c| c.to_string()).unwrap_or_default(),
+        ),
+        (
+            "HOOK_PARENT_OUTPUT",
+            truncate_utf8(&outcome.stdout, MAX_PARENT_ENV_BYTES).to_string(),
+        ),
+        (
+            "HOOK_PARENT_ERROR",
+            truncate_utf8(&outcome.stderr, MAX_PARENT_ENV_BYTES).to_string(),
+        ),
+        ("HOOK_PARENT_ATTEMPTS", attempts.to_string()),
+    ];
+    for (name, file) in [
+        ("HOOK_PARENT_OUTPUT_FILE", stdout_file),
+        ("HOOK_PARENT_ERROR_FILE", stderr_file),
+    ] {
+        if let Some(file) = file {
+            env.push((name, file.path().to_string_lossy().to_string()));
+        }
+    }
+    env
+}
+
+/// 单次命令执行的结果
+struct CommandOutcome {
+    success: bool,
+    exit_code: Option<i32>,
+    stdout: String,
+    stderr: String,
 }
 
 /// Hook执行器
@@ -110,6 +260,11 @@ impl HookExecutor {
     }
 
     /// 执行单个hook
+    ///
+    /// 失败（包括超时和无法启动）时按 `retry` 重试，等待时间从 `retry_backoff_ms`
+    /// 开始指数递增。最终结果出来后依次执行 `on_success` 或 `on_failure` 命令，
+    /// 父hook的结果通过 `HOOK_PARENT_*` 环境变量传入，完整输出在
+    /// `HOOK_PARENT_OUTPUT_FILE` / `HOOK_PARENT_ERROR_FILE` 指向的临时文件中
     pub async fn execute_hook(
         &self,
         hook: &EnhancedHook,
@@ -127,6 +282,9 @@ impl HookExecutor {
                     error: None,
                     execution_time_ms: 0,
                     hook_command: hook.command.clone(),
+                    attempts: 0,
+                    exit_code: None,
+                    follow_ups: Vec::new(),
                 });
             }
         }
@@ -135,89 +293,148 @@ impl HookExecutor {
         let context_json = serde_json::to_string(context).map_err(|e| e.to_string())?;
 
         // 执行命令
-        let mut retry_count = 0;
         let max_retries = hook.retry.unwrap_or(0);
+        let backoff_ms = hook.retry_backoff_ms.unwrap_or(DEFAULT_RETRY_BACKOFF_MS);
+        let mut attempts = 0;
 
-        loop {
-            let mut cmd = Command::new("bash");
-            cmd.arg("-c")
-                .arg(&hook.command)
-                .stdin(std::process::Stdio::piped())
-                .stdout(std::process::Stdio::piped())
-                .stderr(std::process::Stdio::piped())
-                .env("HOOK_CONTEXT", &context_json)
-                .env("HOOK_EVENT", &context.event)
-                .env("SESSION_ID", &context.session_id)
-                .env("PROJECT_PATH", &context.project_path);
-
-            #[cfg(target_os = "windows")]
-            {
-                cmd.creation_flags(0x08000000);
-            }
+        let outcome = loop {
+            attempts += 1;
+            let outcome = self
+                .run_command(&hook.command, hook, context, &context_json, &[])
+                .await
+                .unwrap_or_else(|e| CommandOutcome {
+                    success: false,
+                    exit_code: None,
+                    stdout: String::new(),
+                    stderr: e,
+                });
 
-            // 设置超时
-            let timeout_duration = tokio::time::Duration::from_secs(hook.timeout.unwrap_or(30));
+            if outcome.success || attempts > max_retries {
+                break outcome;
+            }
 
-            // 生成进程并设置超时
-            let child = cmd
-                .spawn()
-                .map_err(|e| format!("Failed to spawn hook process: {}", e))?;
+            let delay = retry_delay_ms(backoff_ms, attempts);
+            warn!(
+                "Hook failed, retrying in {}ms ({}/{})",
+                delay, attempts, max_retries
+            );
+            tokio::time::sleep(tokio::time::Duration::from_millis(delay)).await;
+        };
 
-            let result = tokio::time::timeout(timeout_duration, child.wait_with_output())
+        // 执行成功/失败后的命令
+        let follow_up_commands = if outcome.success {
+            hook.on_success.as_deref()
+        } else {
+            hook.on_failure.as_deref()
+        };
+        let follow_up_commands = follow_up_commands.unwrap_or_default();
+        let (stdout_file, stderr_file) = if follow_up_commands.is_empty() {
+            (None, None)
+        } else {
+            (
+                write_parent_output(&outcome.stdout),
+                write_parent_output(&outcome.stderr),
+            )
+        };
+        let parent_env = parent_env(
+            hook,
+            &outcome,
+            attempts,
+            stdout_file.as_ref(),
+            stderr_file.as_ref(),
+        );
+        let mut follow_ups = Vec::new();
+        for command in follow_up_commands {
+            let follow_up_start = std::time::Instant::now();
+            let result = match self
+                .run_command(command, hook, context, &context_json, &parent_env)
                 .await
-                .map_err(|_| "Hook execution timeout".to_string())?
-                .map_err(|e| format!("Hook execution failed: {}", e))?;
+            {
+                Ok(result) => HookExecutionResult {
+                    success: result.success,
+                    output: result.stdout,
+                    error: (!result.success).then_some(result.stderr),
+                    execution_time_ms: follow_up_start.elapsed().as_millis() as u64,
+                    hook_command: command.clone(),
+                    attempts: 1,
+                    exit_code: result.exit_code,
+                    follow_ups: Vec::new(),
+                },
+                Err(e) => HookExecutionResult::failed(command, e),
+            };
+            if !result.success {
+                warn!("Follow-up command failed: {}", command);
+            }
+            follow_ups.push(result);
+        }
 
-            let execution_time = start_time.elapsed().as_millis() as u64;
+        Ok(HookExecutionResult {
+            success: outcome.success,
+            output: outcome.stdout,
+            error: (!outcome.success).then_some(outcome.stderr),
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
+            hook_command: hook.command.clone(),
+            attempts,
+            exit_code: outcome.exit_code,
+            follow_ups,
+        })
+    }
 
-            if result.status.success() {
-                let output = String::from_utf8_lossy(&result.stdout).to_string();
+    /// 用hook的工作目录、环境变量和超时执行一条命令
+    async fn run_command(
+        &self,
+        command: &str,
+        hook: &EnhancedHook,
+        context: &HookContext,
+        context_json: &str,
+        extra_env: &[(&str, String)],
+    ) -> Result<CommandOutcome, String> {
+        let mut cmd = Command::new("bash");
+        cmd.arg("-c")
+            .arg(command)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .env("HOOK_CONTEXT", context_json)
+            .env("HOOK_EVENT", &context.event)
+            .env("SESSION_ID", &context.session_id)
+            .env("PROJECT_PATH", &context.project_path);
 
-                // 执行成功后的钩子
-                if let Some(on_success_commands) = &hook.on_success {
-                    for cmd in on_success_commands {
-                        let _ = self.execute_simple_command(cmd, context).await;
-                    }
-                }
+        if let Some(cwd) = hook.cwd.as_deref().filter(|c| !c.trim().is_empty()) {
+            cmd.current_dir(PathBuf::from(&context.project_path).join(cwd));
+        } else if Path::new(&context.project_path).is_dir() {
+            cmd.current_dir(&context.project_path);
+        }
+        if let Some(env) = &hook.env {
+            cmd.envs(env);
+        }
+        cmd.envs(extra_env.iter().map(|(k, v)| (*k, v)));
 
-                return Ok(HookExecutionResult {
-                    success: true,
-                    output,
-                    error: None,
-                    execution_time_ms: execution_time,
-                    hook_command: hook.command.clone(),
-                });
-            } else {
-                // 失败处理
-                let error_output = String::from_utf8_lossy(&result.stderr).to_string();
-
-                if retry_count < max_retries {
-                    warn!(
-                        "Hook failed, retrying ({}/{})",
-                        retry_count + 1,
-                        max_retries
-                    );
-                    retry_count += 1;
-                    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-                    continue;
-                }
+        #[cfg(target_os = "windows")]
+        {
+            cmd.creation_flags(0x08000000);
+        }
 
-                // 执行失败后的钩子
-                if let Some(on_failure_commands) = &hook.on_failure {
-                    for cmd in on_failure_commands {
-                        let _ = self.execute_simple_command(cmd, context).await;
-                    }
-                }
+        // 设置超时
+        let timeout_duration = tokio::time::Duration::from_secs(hook.timeout.unwrap_or(30));
 
-                return Ok(HookExecutionResult {
-                    success: false,
-                    output: String::new(),
-                    error: Some(error_output),
-                    execution_time_ms: execution_time,
-                    hook_command: hook.command.clone(),
-                });
-            }
-        }
+        // 生成进程并设置超时，超时后进程随 child 一起被终止
+        let child = cmd
+            .spawn()
+            .map_err(|e| format!("Failed to spawn hook process: {}", e))?;
+
+        let result = tokio::time::timeout(timeout_duration, child.wait_with_output())
+            .await
+            .map_err(|_| "Hook execution timeout".to_string())?
+            .map_err(|e| format!("Hook execution failed: {}", e))?;
+
+        Ok(CommandOutcome {
+            success: result.status.success(),
+            exit_code: result.status.code(),
+            stdout: String::from_utf8_lossy(&result.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&result.stderr).to_string(),
+        })
     }
 
     /// 执行Hook链
@@ -263,17 +480,14 @@ impl HookExecutor {
                 Err(e) => {
                     error!("Hook execution error: {}", e);
                     failed += 1;
-                    results.push(HookExecutionResult {
-                        success: false,
-                        output: String::new(),
-                        error: Some(e),
-                        execution_time_ms: 0,
-                        hook_command: hook.command.clone(),
-                    });
+                    results.push(HookExecutionResult::failed(&hook.command, e));
                 }
             }
         }
 
+        // 记录执行日志
+        record_executions(&self.app, &context, &results);
+
         // 发送执行结果事件
         let _ = self.app.emit(
             &format!("hook-chain-complete:{}", context.session_id),
@@ -290,84 +504,151 @@ impl HookExecutor {
         })
     }
 
-    /// 执行简单命令（用于on_success和on_failure）
-    async fn execute_simple_command(
-        &self,
-        command: &str,
-        context: &HookContext,
-    ) -> Result<(), String> {
-        let mut cmd = Command::new("bash");
-        cmd.arg("-c")
-            .arg(command)
-            .env("SESSION_ID", &context.session_id)
-            .env("PROJECT_PATH", &context.project_path);
+    /// 评估条件表达式
+    ///
+    /// 语法见 `hook_condition` 模块，例如 `data.tokens > 100000 && event == 'OnContextCompact'`。
+    /// 解析失败时返回错误，而不是默认放行
+    fn evaluate_condition(&self, condition: &str, context: &HookContext) -> Result<bool, String> {
+        super::hook_condition::evaluate(condition, context)
+            .map_err(|e| format!("Invalid hook condition '{}': {}", condition, e))
+    }
+}
 
-        #[cfg(target_os = "windows")]
-        {
-            cmd.creation_flags(0x08000000);
-        }
+// ============ 项目级Hooks配置 ============
 
-        let _ = cmd
-            .spawn()
-            .map_err(|e| format!("Failed to spawn command: {}", e))?
-            .wait()
-            .await;
+/// 单个项目的增强型Hooks配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectHooksConfig {
+    pub project_path: String,
+    pub enabled: bool,
+    pub hooks: HashMap<String, Vec<EnhancedHook>>, // 事件名 -> hooks
+    pub watch_ignore: Vec<String>,                 // OnFileChange 额外忽略的glob模式
+}
 
-        Ok(())
+impl Default for ProjectHooksConfig {
+    fn default() -> Self {
+        Self {
+            project_path: String::new(),
+            enabled: true,
+            hooks: HashMap::new(),
+            watch_ignore: Vec::new(),
+        }
     }
+}
 
-    /// 评估条件表达式
-    fn evaluate_condition(&self, condition: &str, context: &HookContext) -> Result<bool, String> {
-        // 简单的条件评估实现
-        // 支持的格式：
-        // - "session_id == 'xyz'"
-        // - "data.tokens > 100000"
-        // - "event == 'OnContextCompact'"
-
-        // 这里使用简单的字符串匹配，未来可以集成更强大的表达式引擎
-        if condition.contains("==") {
-            let parts: Vec<&str> = condition.split("==").collect();
-            if parts.len() == 2 {
-                let left = parts[0].trim();
-                let right = parts[1].trim().trim_matches(|c| c == '\'' || c == '"');
-
-                match left {
-                    "event" => Ok(context.event == right),
-                    "session_id" => Ok(context.session_id == right),
-                    _ => Ok(false),
-                }
-            } else {
-                Ok(false)
+impl ProjectHooksConfig {
+    /// 是否配置了指定事件的hooks
+    pub fn has_hooks(&self, event: &HookEvent) -> bool {
+        self.enabled
+            && self
+                .hooks
+                .get(event.as_str())
+                .is_some_and(|hooks| !hooks.is_empty())
+    }
+}
+
+/// 所有项目的Hooks配置，保存在 `~/.anycode/enhanced_hooks.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EnhancedHooksStore {
+    #[serde(default)]
+    pub projects: HashMap<String, ProjectHooksConfig>, // 规范化项目路径 -> 配置
+}
+
+fn get_enhanced_hooks_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Failed to get home directory")?;
+    Ok(home.join(".anycode").join("enhanced_hooks.json"))
+}
+
+pub fn load_enhanced_hooks_store() -> Result<EnhancedHooksStore, String> {
+    load_json_config(get_enhanced_hooks_path()?)
+}
+
+fn project_key(project_path: &str) -> String {
+    normalize_path_for_comparison(project_path)
+}
+
+/// 校验事件名和条件表达式，避免保存后才在运行时失败
+fn validate_project_hooks(config: &ProjectHooksConfig) -> Result<(), String> {
+    if config.project_path.trim().is_empty() {
+        return Err("Project path is required".to_string());
+    }
+    for (event, hooks) in &config.hooks {
+        HookEvent::from_str(event)?;
+        for hook in hooks {
+            if hook.command.trim().is_empty() {
+                return Err(format!("{} hook has an empty command", event));
+            }
+            if let Some(trigger) = &hook.condition {
+                Condition::parse(&trigger.condition).map_err(|e| {
+                    format!(
+                        "Invalid condition '{}' for {} hook: {}",
+                        trigger.condition, event, e
+                    )
+                })?;
             }
-        } else {
-            // 默认返回true
-            Ok(true)
         }
     }
+    for pattern in &config.watch_ignore {
+        glob::Pattern::new(pattern)
+            .map_err(|e| format!("Invalid watch ignore pattern '{}': {}", pattern, e))?;
+    }
+    Ok(())
 }
 
 // ============ Hook事件触发器 ============
 
-/// Hook管理器 - 管理hooks的注册和触发，保留用于未来扩展
-#[allow(dead_code)]
+/// Hook管理器 - 按项目加载持久化的hooks并触发事件
+#[derive(Clone)]
 pub struct HookManager {
     executor: Arc<HookExecutor>,
-    registered_hooks: Arc<Mutex<HashMap<String, Vec<EnhancedHook>>>>,
+    registered_hooks: Arc<Mutex<HashMap<String, ProjectHooksConfig>>>,
 }
 
-#[allow(dead_code)]
 impl HookManager {
     pub fn new(app: AppHandle) -> Self {
-        Self {
+        let manager = Self {
             executor: Arc::new(HookExecutor::new(app)),
             registered_hooks: Arc::new(Mutex::new(HashMap::new())),
+        };
+        if let Err(e) = manager.reload() {
+            warn!("Failed to load enhanced hooks config: {}", e);
         }
+        manager
     }
 
-    /// 注册Hook
-    pub fn register_hooks(&self, event: HookEvent, hooks: Vec<EnhancedHook>) {
-        let mut registered = self.registered_hooks.lock().unwrap();
-        registered.insert(event.as_str().to_string(), hooks);
+    /// 从磁盘重新加载所有项目的hooks
+    pub fn reload(&self) -> Result<(), String> {
+        let store = load_enhanced_hooks_store()?;
+        let mut registered = self.registered_hooks.lock().map_err(|e| e.to_string())?;
+        *registered = store.projects;
+        Ok(())
+    }
+
+    /// 获取项目配置
+    pub fn project_config(&self, project_path: &str) -> Option<ProjectHooksConfig> {
+        let registered = self.registered_hooks.lock().ok()?;
+        registered.get(&project_key(project_path)).cloned()
+    }
+
+    /// 所有配置了指定事件的项目
+    pub fn projects_with(&self, event: &HookEvent) -> Vec<ProjectHooksConfig> {
+        self.registered_hooks
+            .lock()
+            .map(|registered| {
+                registered
+                    .values()
+                    .filter(|config| config.has_hooks(event))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// 项目是否配置了指定事件的hooks
+    pub fn has_hooks(&self, project_path: &str, event: &HookEvent) -> bool {
+        self.project_config(project_path)
+            .is_some_and(|config| config.has_hooks(event))
     }
 
     /// 触发Hook事件
@@ -376,10 +657,11 @@ impl HookManager {
         event: HookEvent,
         context: HookContext,
     ) -> Result<HookChainResult, String> {
-        let hooks = {
-            let registered = self.registered_hooks.lock().unwrap();
-            registered.get(event.as_str()).cloned().unwrap_or_default()
-        };
+        let hooks = self
+            .project_config(&context.project_path)
+            .filter(|config| config.enabled)
+            .and_then(|config| config.hooks.get(event.as_str()).cloned())
+            .unwrap_or_default();
 
         if hooks.is_empty() {
             debug!("No hooks registered for event: {:?}", event);
@@ -399,46 +681,204 @@ impl HookManager {
     }
 }
 
+/// 从后端生命周期（会话运行器、自动压缩等）触发Hook事件
+///
+/// 在后台执行，不阻塞调用方；项目未配置该事件时直接返回
+pub fn fire(
+    app: &AppHandle,
+    event: HookEvent,
+    session_id: &str,
+    project_path: &str,
+    data: serde_json::Value,
+) {
+    let Some(manager) = app.try_state::<HookManager>() else {
+        return;
+    };
+    if project_path.is_empty() || !manager.has_hooks(project_path, &event) {
+        return;
+    }
+
+    let manager = manager.inner().clone();
+    let context = HookContext {
+        event: event.as_str().to_string(),
+        session_id: session_id.to_string(),
+        project_path: project_path.to_string(),
+        data,
+    };
+    tauri::async_runtime::spawn(async move {
+        let event_name = event.as_str().to_string();
+        match manager.trigger(event, context).await {
+            Ok(result) => debug!(
+                "{} hooks finished: {} succeeded, {} failed",
+                event_name, result.successful, result.failed
+            ),
+            Err(e) => error!("{} hooks failed: {}", event_name, e),
+        }
+    });
+}
+
+// ============ Hook执行日志 ============
+
+/// 日志表最多保留的记录数，超出后删除最旧的
+const MAX_LOG_ENTRIES: i64 = 2_000;
+
+/// 每条记录保存的输出上限
+const MAX_LOGGED_OUTPUT_BYTES: usize = 16 * 1024;
+
+/// 持久化的Hook执行记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookExecutionLogEntry {
+    pub id: i64,
+    pub session_id: String,
+    pub project_path: String,
+    pub event: String,
+    pub created_at: String,
+    #[serde(flatten)]
+    pub result: HookExecutionResult,
+}
+
+/// 创建Hook执行日志表（在 agents.db 中）
+pub fn init_hook_log_tables(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS hook_executions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id TEXT NOT NULL,
+            project_path TEXT NOT NULL,
+            event TEXT NOT NULL,
+            hook_command TEXT NOT NULL,
+            success INTEGER NOT NULL,
+            output TEXT NOT NULL,
+            error TEXT,
+            exit_code INTEGER,
+            attempts INTEGER NOT NULL,
+            execution_time_ms INTEGER NOT NULL,
+            follow_ups TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_hook_executions_session
+         ON hook_executions(session_id, id)",
+        [],
+    )?;
+    Ok(())
+}
+
+fn insert_execution(
+    conn: &Connection,
+    context: &HookContext,
+    result: &HookExecutionResult,
+) -> Result<(), String> {
+    let truncate = |text: &str| truncate_utf8(text, MAX_LOGGED_OUTPUT_BYTES).to_string();
+    let follow_ups = serde_json::to_string(&result.follow_ups).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO hook_executions
+            (session_id, project_path, event, hook_command, success, output, error,
+             exit_code, attempts, execution_time_ms, follow_ups, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        params![
+            context.session_id,
+            context.project_path,
+            context.event,
+            result.hook_command,
+            result.success,
+            truncate(&result.output),
+            result.error.as_deref().map(truncate),
+            result.exit_code,
+            result.attempts,
+            result.execution_time_ms as i64,
+            follow_ups,
+            chrono::Utc::now().to_rfc3339(),
+        ],
+    )
+    .map_err(|e| format!("Failed to record hook execution: {}", e))?;
+    Ok(())
+}
+
+fn prune_executions(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM hook_executions
+         WHERE id <= (SELECT MAX(id) FROM hook_executions) - ?1",
+        params![MAX_LOG_ENTRIES],
+    )
+    .map_err(|e| format!("Failed to prune hook execution log: {}", e))?;
+    Ok(())
+}
+
+/// 把一次Hook链的结果写入日志，失败只记录警告
+fn record_executions(app: &AppHandle, context: &HookContext, results: &[HookExecutionResult]) {
+    let Some(db) = app.try_state::<AgentDb>() else {
+        return;
+    };
+    let Ok(conn) = db.0.lock() else {
+        warn!("[EnhancedHooks] Database lock poisoned, execution log skipped");
+        return;
+    };
+    for result in results {
+        if let Err(e) = insert_execution(&conn, context, result) {
+            warn!("[EnhancedHooks] {}", e);
+        }
+    }
+    if let Err(e) = prune_executions(&conn) {
+        warn!("[EnhancedHooks] {}", e);
+    }
+}
+
+fn query_executions(
+    conn: &Connection,
+    session_id: Option<&str>,
+    limit: usize,
+) -> Result<Vec<HookExecutionLogEntry>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, session_id, project_path, event, hook_command, success, output, error,
+                    exit_code, attempts, execution_time_ms, follow_ups, created_at
+             FROM hook_executions
+             WHERE ?1 IS NULL OR session_id = ?1
+             ORDER BY id DESC
+             LIMIT ?2",
+        )
+        .map_err(|e| format!("Failed to query hook execution log: {}", e))?;
+
+    let rows = stmt
+        .query_map(params![session_id, limit as i64], |row| {
+            let follow_ups: String = row.get(11)?;
+            Ok(HookExecutionLogEntry {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                project_path: row.get(2)?,
+                event: row.get(3)?,
+                created_at: row.get(12)?,
+                result: HookExecutionResult {
+                    hook_command: row.get(4)?,
+                    success: row.get(5)?,
+                    output: row.get(6)?,
+                    error: row.get(7)?,
+                    exit_code: row.get(8)?,
+                    attempts: row.get(9)?,
+                    execution_time_ms: row.get::<_, i64>(10)? as u64,
+                    follow_ups: serde_json::from_str(&follow_ups).unwrap_or_default(),
+                },
+            })
+        })
+        .map_err(|e| format!("Failed to query hook execution log: {}", e))?;
+
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read hook execution log: {}", e))
+}
+
 // ============ Tauri Commands ============
 
 /// 触发Hook事件
 #[tauri::command]
 pub async fn trigger_hook_event(
-    app: AppHandle,
+    manager: State<'_, HookManager>,
     event: String,
     context: HookContext,
 ) -> Result<HookChainResult, String> {
-    let event_enum = match event.as_str() {
-        "OnContextCompact" => HookEvent::OnContextCompact,
-        "OnAgentSwitch" => HookEvent::OnAgentSwitch,
-        "OnFileChange" => HookEvent::OnFileChange,
-        "OnSessionStart" => HookEvent::OnSessionStart,
-        "OnSessionEnd" => HookEvent::OnSessionEnd,
-        "OnTabSwitch" => HookEvent::OnTabSwitch,
-        _ => return Err(format!("Unknown hook event: {}", event)),
-    };
-
-    // 从配置中加载hooks
-    let hooks_config = crate::commands::claude::get_hooks_config(
-        "project".to_string(),
-        Some(context.project_path.clone()),
-    )
-    .await?;
-
-    let hooks_array = hooks_config
-        .get(&event)
-        .and_then(|v| v.as_array())
-        .map(|arr| {
-            arr.iter()
-                .filter_map(|v| serde_json::from_value::<EnhancedHook>(v.clone()).ok())
-                .collect()
-        })
-        .unwrap_or_default();
-
-    let executor = HookExecutor::new(app);
-    executor
-        .execute_hook_chain(event_enum, context, hooks_array)
-        .await
+    let event_enum = HookEvent::from_str(&event)?;
+    manager.trigger(event_enum, context).await
 }
 
 /// 测试Hook条件
@@ -452,6 +892,70 @@ pub async fn test_hook_condition(
     executor.evaluate_condition(&condition, &context)
 }
 
+/// 获取项目的增强型Hooks配置
+#[tauri::command]
+pub async fn get_project_enhanced_hooks(
+    project_path: String,
+) -> Result<ProjectHooksConfig, String> {
+    let store = load_enhanced_hooks_store()?;
+    Ok(store
+        .projects
+        .get(&project_key(&project_path))
+        .cloned()
+        .unwrap_or_else(|| ProjectHooksConfig {
+            project_path,
+            ..Default::default()
+        }))
+}
+
+/// 保存项目的增强型Hooks配置，并刷新内存中的hooks和文件监听
+#[tauri::command]
+pub async fn save_project_enhanced_hooks(
+    app: AppHandle,
+    manager: State<'_, HookManager>,
+    config: ProjectHooksConfig,
+) -> Result<(), String> {
+    validate_project_hooks(&config)?;
+
+    let mut store = load_enhanced_hooks_store()?;
+    let key = project_key(&config.project_path);
+    if config.hooks.values().all(|hooks| hooks.is_empty()) {
+        store.projects.remove(&key);
+    } else {
+        store.projects.insert(key, config);
+    }
+    save_json_config(&store, get_enhanced_hooks_path()?)?;
+
+    manager.reload()?;
+    super::hook_watcher::sync_watchers(&app);
+    Ok(())
+}
+
+/// 查询Hook执行日志，按时间倒序；不传 session_id 时返回所有会话
+#[tauri::command]
+pub async fn get_hook_execution_log(
+    db: State<'_, AgentDb>,
+    session_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<HookExecutionLogEntry>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    query_executions(&conn, session_id.as_deref(), limit.unwrap_or(100))
+}
+
+/// 清空Hook执行日志，返回删除的记录数
+#[tauri::command]
+pub async fn clear_hook_execution_log(
+    db: State<'_, AgentDb>,
+    session_id: Option<String>,
+) -> Result<usize, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM hook_executions WHERE ?1 IS NULL OR session_id = ?1",
+        params![session_id],
+    )
+    .map_err(|e| format!("Failed to clear hook execution log: {}", e))
+}
+
 // ============ 智能化自动化场景实现 ============
 
 /// 提交前代码审查Hook配置
@@ -465,6 +969,18 @@ pub struct PreCommitCodeReviewConfig {
     pub exclude_patterns: Vec<String>, // 排除的文件模式
     pub max_files_to_review: usize,    // 最大审查文件数量
     pub show_suggestions: bool,        // 是否显示改进建议
+    #[serde(default)]
+    pub engine: SessionEngine, // 执行审查的CLI引擎
+    #[serde(default)]
+    pub model: Option<String>, // 审查使用的模型，默认使用引擎配置
+    #[serde(default = "default_review_timeout")]
+    pub timeout_secs: u64, // 单次审查超时（秒）
+    #[serde(default)]
+    pub fail_closed: bool, // 审查无法完成时是否阻止提交
+}
+
+fn default_review_timeout() -> u64 {
+    300
 }
 
 impl Default for PreCommitCodeReviewConfig {
@@ -486,35 +1002,34 @@ impl Default for PreCommitCodeReviewConfig {
             ],
             max_files_to_review: 20,
             show_suggestions: true,
+            engine: SessionEngine::default(),
+            model: None,
+            timeout_secs: default_review_timeout(),
+            fail_closed: false,
         }
     }
 }
 
 /// 提交前代码审查Hook - 智能化自动化场景的具体实现
-#[allow(dead_code)]
 pub struct PreCommitCodeReviewHook {
     config: PreCommitCodeReviewConfig,
     _app: AppHandle, // 保留用于未来扩展，如通知用户等
 }
 
-#[allow(dead_code)]
 impl PreCommitCodeReviewHook {
     pub fn new(app: AppHandle, config: PreCommitCodeReviewConfig) -> Self {
         Self { config, _app: app }
     }
 
-    /// 执行提交前代码审查 (Disabled - agent functionality removed)
-    pub async fn execute(&self, _project_path: &str) -> Result<CommitDecision, String> {
-        // Agent functionality removed - always allow commits
-        Ok(CommitDecision::Allow {
-            message: "代码审查功能已禁用 (Agent functionality removed)".to_string(),
-            suggestions: vec![],
-        })
+    /// 执行提交前代码审查：由配置的CLI引擎审查暂存区diff
+    pub async fn execute(&self, project_path: &str) -> Result<CommitDecision, String> {
+        pre_commit_review::review_staged_changes(project_path, &self.config).await
     }
 }
 
 /// 提交决策结果
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
 pub enum CommitDecision {
     Allow {
         message: String,
@@ -522,21 +1037,119 @@ pub enum CommitDecision {
     },
     Block {
         reason: String,
-        details: String, // Changed from CodeReviewResult - agent functionality removed
+        details: String, // 评分、摘要及逐条问题
         suggestions: Vec<String>,
     },
 }
 
-/// 执行提交前代码审查Hook (Disabled - agent functionality removed)
+/// 执行提交前代码审查Hook
+///
+/// 未传入配置时使用已安装git hook保存的配置，否则使用默认配置
 #[tauri::command]
 pub async fn execute_pre_commit_review(
-    _app: tauri::AppHandle,
-    _project_path: String,
-    _config: Option<PreCommitCodeReviewConfig>,
+    app: tauri::AppHandle,
+    project_path: String,
+    config: Option<PreCommitCodeReviewConfig>,
 ) -> Result<CommitDecision, String> {
-    // Agent functionality has been removed - return allow decision
-    Ok(CommitDecision::Allow {
-        message: "代码审查功能已禁用 (Agent functionality removed)".to_string(),
-        suggestions: vec![],
-    })
+    let config = config
+        .or_else(|| pre_commit_review::load_hook_config(&project_path))
+        .unwrap_or_default();
+    PreCommitCodeReviewHook::new(app, config)
+        .execute(&project_path)
+        .await
+}
+
+/// 安装提交前代码审查为 `.git/hooks/pre-commit`
+#[tauri::command]
+pub async fn install_pre_commit_hook(
+    project_path: String,
+    config: Option<PreCommitCodeReviewConfig>,
+) -> Result<(), String> {
+    pre_commit_review::install_hook(&project_path, &config.unwrap_or_default())
+}
+
+/// 卸载由本应用安装的pre-commit hook，返回是否确实移除
+#[tauri::command]
+pub async fn uninstall_pre_commit_hook(project_path: String) -> Result<bool, String> {
+    pre_commit_review::uninstall_hook(&project_path)
+}
+
+/// 获取pre-commit hook安装状态及其配置
+#[tauri::command]
+pub async fn get_pre_commit_hook_status(project_path: String) -> Result<serde_json::Value, String> {
+    Ok(serde_json::json!({
+        "installed": pre_commit_review::is_hook_installed(&project_path),
+        "config": pre_commit_review::load_hook_config(&project_path),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn large_parent_output_is_passed_through_files() {
+        let hook = EnhancedHook {
+            command: "npm test".to_string(),
+            timeout: None,
+            retry: None,
+            condition: None,
+            on_success: Some(vec!["notify".to_string()]),
+            on_failure: None,
+            retry_backoff_ms: None,
+            cwd: None,
+            env: None,
+        };
+        let outcome = CommandOutcome {
+            success: true,
+            exit_code: Some(0),
+            stdout: "é".repeat(20 * 1024),
+            stderr: String::new(),
+        };
+        let stdout_file = write_parent_output(&outcome.stdout).unwrap();
+        let env = parent_env(&hook, &outcome, 1, Some(&stdout_file), None);
+        let var = |name: &str| env.iter().find(|(k, _)| *k == name).map(|(_, v)| v.clone());
+
+        let preview = var("HOOK_PARENT_OUTPUT").unwrap();
+        assert_eq!(preview.len(), MAX_PARENT_ENV_BYTES);
+        let total: usize = env.iter().map(|(k, v)| k.len() + v.len() + 2).sum();
+        assert!(total < 32_767, "env block of {} bytes", total);
+
+        let path = var("HOOK_PARENT_OUTPUT_FILE").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), outcome.stdout);
+        assert!(var("HOOK_PARENT_ERROR_FILE").is_none());
+
+        drop(stdout_file);
+        assert!(!Path::new(&path).exists());
+    }
+
+    #[test]
+    fn retry_backoff_and_execution_log() {
+        assert_eq!(retry_delay_ms(500, 1), 500);
+        assert_eq!(retry_delay_ms(500, 3), 2_000);
+        assert_eq!(retry_delay_ms(500, 40), MAX_RETRY_BACKOFF_MS);
+
+        let conn = Connection::open_in_memory().unwrap();
+        init_hook_log_tables(&conn).unwrap();
+
+        let context = |session_id: &str| HookContext {
+            event: "OnSessionEnd".to_string(),
+            session_id: session_id.to_string(),
+            project_path: "/work/app".to_string(),
+            data: serde_json::json!({}),
+        };
+        let mut result = HookExecutionResult::failed("npm test", "boom".to_string());
+        result.attempts = 3;
+        result.exit_code = Some(1);
+        result.follow_ups = vec![HookExecutionResult::failed("notify", "x".to_string())];
+        insert_execution(&conn, &context("a"), &result).unwrap();
+        insert_execution(&conn, &context("b"), &result).unwrap();
+
+        let entries = query_executions(&conn, Some("a"), 10).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].result.attempts, 3);
+        assert_eq!(entries[0].result.exit_code, Some(1));
+        assert_eq!(entries[0].result.follow_ups[0].hook_command, "notify");
+        assert_eq!(query_executions(&conn, None, 10).unwrap().len(), 2);
+    }
 }
@@ -471,6 +471,12 @@ pub fn delete_session(project_path: &str, session_id: &str) -> Result<(), String
                     fs::remove_file(&path)
                         .map_err(|e| format!("Failed to delete session file: {}", e))?;
                     log::info!("Deleted Gemini session: {} at {:?}", session_id, path);
+                    if let Err(e) = crate::commands::checkpoint::delete_session_checkpoints(
+                        project_path,
+                        session_id,
+                    ) {
+                        log::warn!("Failed to delete checkpoints of {}: {}", session_id, e);
+                    }
                     return Ok(());
                 }
             }
@@ -12,8 +12,9 @@ use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
 
-// Import simple_git for rewind operations
-use super::super::simple_git;
+// Import checkpoints for rewind operations
+use super::super::checkpoint;
+use super::super::session_worktree;
 // Import rewind helpers/types shared with Claude
 use super::super::prompt_tracker::{
     load_execution_config, PromptRecord as ClaudePromptRecord, RewindCapabilities, RewindMode,
@@ -197,6 +198,20 @@ pub fn truncate_gemini_git_records(session_id: &str, prompt_index: usize) -> Res
         prompt_index,
         before_count - after_count
     );
+
+    // Checkpoints of the removed prompts are no longer reachable from a record
+    if !git_records.project_path.is_empty() {
+        let keep: Vec<&str> = git_records
+            .records
+            .iter()
+            .flat_map(|r| [Some(r.commit_before.as_str()), r.commit_after.as_deref()])
+            .flatten()
+            .collect();
+        if let Err(e) = checkpoint::retain_checkpoints(&git_records.project_path, session_id, &keep)
+        {
+            log::warn!("[Gemini Rewind] Failed to delete stale checkpoints: {}", e);
+        }
+    }
     Ok(())
 }
 
@@ -300,6 +315,7 @@ pub async fn get_gemini_prompt_list(
     session_id: String,
     project_path: String,
 ) -> Result<Vec<PromptRecord>, String> {
+    let project_path = session_worktree::resolve_project_path(&session_id, &project_path);
     extract_gemini_prompts(&session_id, &project_path)
 }
 
@@ -331,6 +347,7 @@ pub async fn check_gemini_rewind_capabilities(
     project_path: String,
     prompt_index: usize,
 ) -> Result<RewindCapabilities, String> {
+    let project_path = session_worktree::resolve_project_path(&session_id, &project_path);
     log::info!(
         "[Gemini Rewind] Checking capabilities for session {} prompt #{}",
         session_id,
@@ -413,6 +430,7 @@ pub async fn record_gemini_prompt_sent(
     project_path: String,
     _prompt_text: String,
 ) -> Result<usize, String> {
+    let project_path = session_worktree::resolve_project_path(&session_id, &project_path);
     log::info!(
         "[Gemini Record] Recording prompt sent for session: {}",
         session_id
@@ -434,14 +452,6 @@ pub async fn record_gemini_prompt_sent(
         return Ok(prompt_index);
     }
 
-    // Ensure Git repository is initialized
-    simple_git::ensure_git_repo(&project_path)
-        .map_err(|e| format!("Failed to ensure Git repo: {}", e))?;
-
-    // Get current commit (state before prompt execution)
-    let commit_before = simple_git::git_current_commit(&project_path)
-        .map_err(|e| format!("Failed to get current commit: {}", e))?;
-
     // Load existing records
     let mut git_records = load_gemini_git_records(&session_id)?;
 
@@ -453,6 +463,14 @@ pub async fn record_gemini_prompt_sent(
     // Calculate prompt index
     let prompt_index = git_records.records.len();
 
+    // Snapshot the project (state before prompt execution)
+    let commit_before = checkpoint::create_checkpoint(
+        &project_path,
+        &session_id,
+        &format!("[Gemini] Before prompt #{}", prompt_index),
+    )
+    .map_err(|e| format!("Failed to create checkpoint: {}", e))?;
+
     // Create new record
     let record = GeminiPromptGitRecord {
         prompt_index,
@@ -481,6 +499,7 @@ pub async fn record_gemini_prompt_completed(
     prompt_index: usize,
     prompt_text: Option<String>,
 ) -> Result<(), String> {
+    let project_path = session_worktree::resolve_project_path(&session_id, &project_path);
     log::info!(
         "[Gemini Record] Recording prompt #{} completed for session: {}",
         prompt_index,
@@ -498,31 +517,11 @@ pub async fn record_gemini_prompt_completed(
         return Ok(());
     }
 
-    // Auto-commit any changes made by AI
+    // Snapshot the changes made by AI (state after AI completion)
     let commit_message =
         build_prompt_commit_message("[Gemini]", prompt_text.as_deref(), prompt_index);
-    match simple_git::git_commit_changes(&project_path, &commit_message) {
-        Ok(true) => {
-            log::info!(
-                "[Gemini Record] Auto-committed changes after prompt #{}",
-                prompt_index
-            );
-        }
-        Ok(false) => {
-            log::debug!(
-                "[Gemini Record] No changes to commit after prompt #{}",
-                prompt_index
-            );
-        }
-        Err(e) => {
-            log::warn!("[Gemini Record] Failed to auto-commit: {}", e);
-            // Continue anyway
-        }
-    }
-
-    // Get current commit (state after AI completion)
-    let commit_after = simple_git::git_current_commit(&project_path)
-        .map_err(|e| format!("Failed to get current commit: {}", e))?;
+    let commit_after = checkpoint::create_checkpoint(&project_path, &session_id, &commit_message)
+        .map_err(|e| format!("Failed to create checkpoint: {}", e))?;
 
     // Update the record
     let mut git_records = load_gemini_git_records(&session_id)?;
@@ -646,6 +645,7 @@ pub async fn revert_gemini_to_prompt(
     prompt_index: usize,
     mode: RewindMode,
 ) -> Result<String, String> {
+    let project_path = session_worktree::resolve_project_path(&session_id, &project_path);
     log::info!(
         "[Gemini Rewind] Reverting session {} to prompt #{} with mode: {:?}",
         session_id,
@@ -717,23 +717,17 @@ pub async fn revert_gemini_to_prompt(
         RewindMode::CodeOnly => {
             log::info!("[Gemini Rewind] Reverting code to state before prompt #{}", prompt_index);
 
-            // Stash uncommitted changes
-            simple_git::git_stash_save(
+            // Checkpoint the current state for atomic rollback on failure
+            let original_state = checkpoint::create_checkpoint(
                 &project_path,
-                &format!(
-                    "Auto-stash before Gemini code revert to prompt #{}",
-                    prompt_index
-                ),
+                &session_id,
+                &format!("[Gemini] Before code revert to prompt #{}", prompt_index),
             )
-            .map_err(|e| format!("Failed to stash changes: {}", e))?;
-
-            // Record original HEAD for atomic rollback on failure
-            let original_head = simple_git::git_current_commit(&project_path)
-                .map_err(|e| format!("Failed to get current commit: {}", e))?;
+            .map_err(|e| format!("Failed to create checkpoint: {}", e))?;
 
             log::info!(
-                "[Gemini Precise Revert] Original HEAD: {} (will rollback here on failure)",
-                &original_head[..8.min(original_head.len())]
+                "[Gemini Precise Revert] Original state: {} (will restore it on failure)",
+                &original_state[..8.min(original_state.len())]
             );
 
             // Load ALL git records for this session
@@ -770,7 +764,7 @@ pub async fn revert_gemini_to_prompt(
                     }
                 };
 
-                let has_changes = match simple_git::git_has_changes_between_commits(
+                let has_changes = match checkpoint::has_changes_between(
                     &project_path,
                     &record.commit_before,
                     &commit_after,
@@ -803,21 +797,18 @@ pub async fn revert_gemini_to_prompt(
                     &commit_after[..8.min(commit_after.len())]
                 );
 
-                let revert_result = simple_git::git_revert_range_with_retry(
+                let revert_result = checkpoint::revert_checkpoint_range(
                     &project_path,
                     &record.commit_before,
                     &commit_after,
-                    &format!("[Gemini Revert] 撤回提示词 #{} 的代码更改", record.prompt_index),
-                    3, // Max 3 retries for Git lock conflicts
                 );
 
                 match revert_result {
                     Ok(result) if result.success => {
                         total_reverted += result.commits_reverted;
                         log::info!(
-                            "[Gemini Precise Revert] Successfully reverted prompt #{} ({} commits)",
-                            record.prompt_index,
-                            result.commits_reverted
+                            "[Gemini Precise Revert] Successfully reverted prompt #{}",
+                            record.prompt_index
                         );
                     }
                     Ok(result) => {
@@ -839,13 +830,13 @@ pub async fn revert_gemini_to_prompt(
                 }
             }
 
-            // If revert failed, rollback to original HEAD (atomic operation)
+            // If revert failed, restore the original state (atomic operation)
             if revert_failed {
                 log::warn!(
-                    "[Gemini Precise Revert] Rolling back to original HEAD {} due to failure",
-                    &original_head[..8.min(original_head.len())]
+                    "[Gemini Precise Revert] Restoring original state {} due to failure",
+                    &original_state[..8.min(original_state.len())]
                 );
-                simple_git::git_reset_hard(&project_path, &original_head)
+                checkpoint::restore_checkpoint(&project_path, &original_state)
                     .map_err(|e| format!("Failed to rollback: {}", e))?;
 
                 return Err(format!(
@@ -855,7 +846,7 @@ pub async fn revert_gemini_to_prompt(
             }
 
             log::info!(
-                "[Gemini Rewind] Successfully reverted code to state before prompt #{} (reverted {} commits from {} prompts)",
+                "[Gemini Rewind] Successfully reverted code to state before prompt #{} (reverted {} of {} prompts)",
                 prompt_index,
                 total_reverted,
                 records_to_revert.len()
@@ -865,23 +856,17 @@ pub async fn revert_gemini_to_prompt(
         RewindMode::Both => {
             log::info!("[Gemini Rewind] Reverting both to state before prompt #{}", prompt_index);
 
-            // Stash uncommitted changes
-            simple_git::git_stash_save(
+            // Checkpoint the current state for atomic rollback on failure
+            let original_state = checkpoint::create_checkpoint(
                 &project_path,
-                &format!(
-                    "Auto-stash before Gemini full revert to prompt #{}",
-                    prompt_index
-                ),
+                &session_id,
+                &format!("[Gemini] Before full revert to prompt #{}", prompt_index),
             )
-            .map_err(|e| format!("Failed to stash changes: {}", e))?;
-
-            // Record original HEAD for atomic rollback on failure
-            let original_head = simple_git::git_current_commit(&project_path)
-                .map_err(|e| format!("Failed to get current commit: {}", e))?;
+            .map_err(|e| format!("Failed to create checkpoint: {}", e))?;
 
             log::info!(
-                "[Gemini Precise Revert] Original HEAD: {} (will rollback here on failure)",
-                &original_head[..8.min(original_head.len())]
+                "[Gemini Precise Revert] Original state: {} (will restore it on failure)",
+                &original_state[..8.min(original_state.len())]
             );
 
             // Load ALL git records for this session
@@ -918,7 +903,7 @@ pub async fn revert_gemini_to_prompt(
                     }
                 };
 
-                let has_changes = match simple_git::git_has_changes_between_commits(
+                let has_changes = match checkpoint::has_changes_between(
                     &project_path,
                     &record.commit_before,
                     &commit_after,
@@ -951,21 +936,18 @@ pub async fn revert_gemini_to_prompt(
                     &commit_after[..8.min(commit_after.len())]
                 );
 
-                let revert_result = simple_git::git_revert_range_with_retry(
+                let revert_result = checkpoint::revert_checkpoint_range(
                     &project_path,
                     &record.commit_before,
                     &commit_after,
-                    &format!("[Gemini Revert] 撤回提示词 #{} 的代码更改", record.prompt_index),
-                    3, // Max 3 retries for Git lock conflicts
                 );
 
                 match revert_result {
                     Ok(result) if result.success => {
                         total_reverted += result.commits_reverted;
                         log::info!(
-                            "[Gemini Precise Revert] Successfully reverted prompt #{} ({} commits)",
-                            record.prompt_index,
-                            result.commits_reverted
+                            "[Gemini Precise Revert] Successfully reverted prompt #{}",
+                            record.prompt_index
                         );
                     }
                     Ok(result) => {
@@ -987,13 +969,13 @@ pub async fn revert_gemini_to_prompt(
                 }
             }
 
-            // If revert failed, rollback to original HEAD (atomic operation)
+            // If revert failed, restore the original state (atomic operation)
             if revert_failed {
                 log::warn!(
-                    "[Gemini Precise Revert] Rolling back to original HEAD {} due to failure",
-                    &original_head[..8.min(original_head.len())]
+                    "[Gemini Precise Revert] Restoring original state {} due to failure",
+                    &original_state[..8.min(original_state.len())]
                 );
-                simple_git::git_reset_hard(&project_path, &original_head)
+                checkpoint::restore_checkpoint(&project_path, &original_state)
                     .map_err(|e| format!("Failed to rollback: {}", e))?;
 
                 return Err(format!(
@@ -1003,7 +985,7 @@ pub async fn revert_gemini_to_prompt(
             }
 
             log::info!(
-                "[Gemini Rewind] Successfully reverted code to state before prompt #{} (reverted {} commits from {} prompts)",
+                "[Gemini Rewind] Successfully reverted code to state before prompt #{} (reverted {} of {} prompts)",
                 prompt_index,
                 total_reverted,
                 records_to_revert.len()
@@ -1017,7 +999,9 @@ pub async fn revert_gemini_to_prompt(
                     e
                 );
 
-                if let Err(rollback_err) = simple_git::git_reset_hard(&project_path, &original_head) {
+                if let Err(rollback_err) =
+                    checkpoint::restore_checkpoint(&project_path, &original_state)
+                {
                     log::error!("[CRITICAL] Git rollback failed: {}", rollback_err);
                     return Err(format!(
                         "会话截断失败且 Git 回滚失败。\n\
@@ -1042,7 +1026,9 @@ pub async fn revert_gemini_to_prompt(
                         e
                     );
 
-                    if let Err(rollback_err) = simple_git::git_reset_hard(&project_path, &original_head) {
+                    if let Err(rollback_err) =
+                        checkpoint::restore_checkpoint(&project_path, &original_state)
+                    {
                         log::error!("[CRITICAL] Git rollback failed: {}", rollback_err);
                         return Err(format!(
                             "Git 记录截断失败且回滚失败。\n\
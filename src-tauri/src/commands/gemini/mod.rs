@@ -15,6 +15,7 @@ pub mod git_ops;
 pub mod parser;
 pub mod provider;
 pub mod session;
+pub mod session_converter;
 pub mod types;
 pub mod usage;
 
@@ -53,6 +54,12 @@ pub use provider::{
     switch_gemini_provider, test_gemini_provider_connection, update_gemini_provider_config,
 };
 
+// Re-export Gemini Session Conversion commands
+pub use session_converter::{
+    convert_claude_to_gemini, convert_codex_to_gemini, convert_gemini_to_claude,
+    convert_gemini_to_codex,
+};
+
 // Re-export Gemini Usage Statistics commands
 pub use usage::get_gemini_usage_stats;
 
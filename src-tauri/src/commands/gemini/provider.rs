@@ -227,6 +227,24 @@ pub async fn get_current_gemini_provider_config() -> Result<CurrentGeminiProvide
     })
 }
 
+/// Id of the provider preset whose base URL matches the current .env
+pub(crate) async fn active_gemini_provider_id() -> Option<String> {
+    let base_url = get_current_gemini_provider_config().await.ok()?.base_url?;
+    let base_url = base_url.trim_end_matches('/');
+
+    get_gemini_provider_presets()
+        .await
+        .ok()?
+        .into_iter()
+        .find(|p| {
+            p.env
+                .get("GOOGLE_GEMINI_BASE_URL")
+                .map(|u| u.trim_end_matches('/'))
+                == Some(base_url)
+        })
+        .map(|p| p.id)
+}
+
 /// Switch to a Gemini provider configuration
 /// Supports both Native Windows and WSL modes
 #[tauri::command]
@@ -18,7 +18,11 @@ use super::parser::{
 };
 use super::types::{GeminiExecutionOptions, GeminiInstallStatus, GeminiProcessHandle, GeminiProcessState, GeminiSessionDetail, TokenUsage};
 use crate::claude_binary::detect_binary_for_tool;
+use crate::commands::budget::{self, UsageSample};
 use crate::commands::claude::apply_no_window_async;
+use crate::commands::enhanced_hooks::{self, HookEvent};
+use crate::commands::session_timeline::SessionEngine;
+use crate::commands::session_worktree;
 use crate::commands::wsl_utils;
 use crate::process::JobObject;
 
@@ -388,7 +392,7 @@ fn do_check_gemini_installed() -> GeminiInstallStatus {
 /// Execute Gemini CLI with streaming output
 #[tauri::command]
 pub async fn execute_gemini(
-    options: GeminiExecutionOptions,
+    mut options: GeminiExecutionOptions,
     app_handle: AppHandle,
 ) -> Result<(), String> {
     // Avoid logging sensitive fields (prompt). Log only non-sensitive metadata.
@@ -407,6 +411,18 @@ pub async fn execute_gemini(
         options.prompt.len()
     );
 
+    // Resumed sessions run in their worktree; new ones get one when session
+    // isolation is on
+    options.project_path = match &options.session_id {
+        Some(session_id) => {
+            session_worktree::resolve_project_path(session_id, &options.project_path)
+        }
+        None => session_worktree::prepare_session_worktree(
+            &options.project_path,
+            SessionEngine::Gemini,
+        )?,
+    };
+
     // Find Gemini binary
     let gemini_path = find_gemini_binary()?;
     let is_wsl = gemini_path.starts_with("WSL:");
@@ -741,6 +757,18 @@ async fn execute_gemini_process(
 
     log::info!("Gemini session initialized with ID: {}", session_id);
 
+    enhanced_hooks::fire(
+        &app_handle,
+        HookEvent::OnSessionStart,
+        &session_id,
+        &project_path,
+        serde_json::json!({
+            "engine": "gemini",
+            "model": model,
+            "pid": pid,
+        }),
+    );
+
     // 🔧 FIX: Use channels to track stdout/stderr closure for timeout detection
     let (stdout_done_tx, stdout_done_rx) = tokio::sync::oneshot::channel();
     let (stderr_done_tx, stderr_done_rx) = tokio::sync::oneshot::channel();
@@ -756,8 +784,11 @@ async fn execute_gemini_process(
     // Spawn task to read stdout (JSONL events)
     let model_for_messages = model.clone();
     let project_path_for_usage = project_path.clone();
+    // Budget tracking: provider preset in effect for this run
+    let budget_provider_id = super::provider::active_gemini_provider_id().await;
     tokio::spawn(async move {
         let mut reader = BufReader::new(stdout).lines();
+        let mut budget_cancelled = false;
         let mut real_cli_session_id_emitted = false;
         let mut real_cli_session_id: Option<String> = None;
         // Track tool calls to enrich tool_result payloads (e.g., read_file returning empty output)
@@ -782,6 +813,7 @@ async fn execute_gemini_process(
                     } = event
                     {
                         real_cli_session_id = Some(cli_session_id.clone());
+                        session_worktree::bind_session(&project_path_for_usage, cli_session_id);
                         // Emit the real Gemini CLI session ID to frontend
                         log::info!("[Gemini] Detected real CLI session ID: {}", cli_session_id);
                         let cli_session_payload = serde_json::json!({
@@ -816,6 +848,62 @@ async fn execute_gemini_process(
                     }
                 }
 
+                // Check spend against budgets when the run reports its usage
+                if let super::types::GeminiStreamEvent::Result {
+                    stats,
+                    usage_metadata,
+                    ..
+                } = &event
+                {
+                    let input = stats
+                        .as_ref()
+                        .and_then(|s| s.input_tokens)
+                        .or_else(|| usage_metadata.as_ref().and_then(|u| u.prompt_token_count))
+                        .unwrap_or(0);
+                    let output = stats
+                        .as_ref()
+                        .and_then(|s| s.output_tokens)
+                        .or_else(|| {
+                            usage_metadata
+                                .as_ref()
+                                .and_then(|u| u.candidates_token_count)
+                        })
+                        .unwrap_or(0);
+
+                    if !budget_cancelled && (input > 0 || output > 0) {
+                        let sample = UsageSample {
+                            engine: SessionEngine::Gemini,
+                            session_id: session_id_stdout.clone(),
+                            project_path: project_path_for_usage.clone(),
+                            model: model_for_messages.clone(),
+                            provider_id: budget_provider_id.clone(),
+                            input_tokens: input,
+                            output_tokens: output,
+                            cache_creation_tokens: 0,
+                            cache_read_tokens: 0,
+                        };
+                        if let Some(alert) = budget::record_usage(&app_handle_stdout, &sample) {
+                            budget_cancelled = true;
+                            log::warn!(
+                                "[Gemini] Budget {} exceeded, cancelling session {}",
+                                alert.rule_id,
+                                alert.session_id
+                            );
+                            let app_for_cancel = app_handle_stdout.clone();
+                            tokio::spawn(async move {
+                                if let Err(e) =
+                                    cancel_gemini(Some(alert.session_id), app_for_cancel).await
+                                {
+                                    log::error!(
+                                        "[Gemini] Failed to cancel session over budget: {}",
+                                        e
+                                    );
+                                }
+                            });
+                        }
+                    }
+                }
+
                 // Record tool_use params for later enrichment of tool_result
                 if let super::types::GeminiStreamEvent::ToolUse {
                     tool_name,
@@ -1012,6 +1100,7 @@ async fn execute_gemini_process(
     // 🔧 FIX: Add timeout mechanism - if stdout/stderr are closed but process doesn't exit within 30s, force completion
     let state_complete = app_handle.state::<GeminiProcessState>();
     let processes_complete = state_complete.processes.clone();
+    let project_path_complete = project_path.clone();
 
     tokio::spawn(async move {
         // Wait for both stdout and stderr to close
@@ -1098,6 +1187,18 @@ async fn execute_gemini_process(
         let _ =
             app_handle_complete.emit(&format!("gemini-complete:{}", session_id_complete), success);
         let _ = app_handle_complete.emit("gemini-complete", success);
+
+        enhanced_hooks::fire(
+            &app_handle_complete,
+            HookEvent::OnSessionEnd,
+            &session_id_complete,
+            &project_path_complete,
+            serde_json::json!({
+                "engine": "gemini",
+                "success": success,
+                "exit_code": exit_code,
+            }),
+        );
     });
 
     Ok(())
@@ -0,0 +1,959 @@
+//! Gemini ⇄ Claude / Codex Session Conversion
+//!
+//! Extends `codex::session_converter` so Gemini chats can be continued in the
+//! other engines and vice versa.
+//!
+//! ## Design
+//!
+//! - Sources are read through the unified session loaders, so every
+//!   conversion sees the same ordered user/assistant/tool events
+//! - Claude and Codex output reuses the writers of the Claude ↔ Codex
+//!   converter (file-history-snapshot, parentUuid chain, rollout naming)
+//! - Gemini output is a regular `chats/session-*.json` file in the project's
+//!   hashed tmp directory, stamped with the conversion time so that
+//!   `gemini --resume latest` picks it up
+//! - Provenance is recorded as `ConversionSource` (system init message for
+//!   Claude, `session_meta` payload for Codex, top-level field for Gemini)
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use super::config::{get_project_session_dir, hash_project_path};
+use super::types::GeminiSessionDetail;
+use crate::commands::codex::session_converter::{
+    build_claude_message, convert_session, file_history_snapshot, map_claude_to_codex_tool,
+    map_codex_to_claude_tool, write_claude_session_file, write_codex_session_file,
+    ClaudeContentBlock, ClaudeMessage, CodexEvent, ConversionResult, ConversionSource, TokenUsage,
+};
+use crate::commands::session_timeline::{
+    loader_for, EventKind, SessionEngine, UnifiedSession, UnifiedUsage,
+};
+
+// ============================================================================
+// Tool Name Mapping
+// ============================================================================
+
+/// Gemini → Claude tool name mapping
+pub static GEMINI_TO_CLAUDE_TOOL_MAP: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
+    let mut m = HashMap::new();
+    // Command execution
+    m.insert("run_shell_command", "bash");
+    // File operations
+    m.insert("read_file", "read");
+    m.insert("read_many_files", "read");
+    m.insert("write_file", "write");
+    m.insert("replace", "edit");
+    m.insert("edit", "edit");
+    // Search operations
+    m.insert("glob", "glob");
+    m.insert("search_file_content", "grep");
+    m.insert("list_directory", "ls");
+    // Web operations
+    m.insert("web_fetch", "webfetch");
+    m.insert("google_web_search", "websearch");
+    // Planning
+    m.insert("write_todos", "todowrite");
+    m
+});
+
+/// Claude → Gemini tool name mapping (reverse)
+pub static CLAUDE_TO_GEMINI_TOOL_MAP: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
+    let mut m = HashMap::new();
+    m.insert("bash", "run_shell_command");
+    m.insert("read", "read_file");
+    m.insert("write", "write_file");
+    m.insert("edit", "replace");
+    m.insert("multiedit", "replace");
+    m.insert("glob", "glob");
+    m.insert("grep", "search_file_content");
+    m.insert("ls", "list_directory");
+    m.insert("webfetch", "web_fetch");
+    m.insert("websearch", "google_web_search");
+    m.insert("todowrite", "write_todos");
+    m
+});
+
+/// Map a Gemini tool name to its Claude equivalent
+/// MCP tools (mcp__ prefix) are left untouched
+pub fn map_gemini_to_claude_tool(gemini_name: &str) -> String {
+    if gemini_name.starts_with("mcp__") {
+        return gemini_name.to_string();
+    }
+    let lower = gemini_name.to_lowercase();
+    GEMINI_TO_CLAUDE_TOOL_MAP
+        .get(lower.as_str())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| gemini_name.to_string())
+}
+
+/// Map a Claude tool name to its Gemini equivalent
+/// MCP tools (mcp__ prefix) are left untouched
+pub fn map_claude_to_gemini_tool(claude_name: &str) -> String {
+    if claude_name.starts_with("mcp__") {
+        return claude_name.to_string();
+    }
+    let lower = claude_name.to_lowercase();
+    CLAUDE_TO_GEMINI_TOOL_MAP
+        .get(lower.as_str())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| claude_name.to_string())
+}
+
+/// Map a Gemini tool name to its Codex equivalent (via the Claude name)
+pub fn map_gemini_to_codex_tool(gemini_name: &str) -> String {
+    map_claude_to_codex_tool(&map_gemini_to_claude_tool(gemini_name))
+}
+
+/// Map a Codex tool name to its Gemini equivalent (via the Claude name)
+pub fn map_codex_to_gemini_tool(codex_name: &str) -> String {
+    map_claude_to_gemini_tool(&map_codex_to_claude_tool(codex_name))
+}
+
+// ============================================================================
+// Shared Helpers
+// ============================================================================
+
+fn conversion_source(
+    engine: SessionEngine,
+    session_id: &str,
+    project_path: &str,
+) -> ConversionSource {
+    ConversionSource {
+        engine: engine.as_str().to_string(),
+        session_id: session_id.to_string(),
+        converted_at: chrono::Utc::now().to_rfc3339(),
+        source_project_path: project_path.to_string(),
+    }
+}
+
+/// Only completed sessions can be converted (last message must not be from the user)
+fn validate_session_completed(session: &UnifiedSession) -> Result<(), String> {
+    let last = session
+        .events
+        .iter()
+        .rev()
+        .find(|e| !matches!(e.kind, EventKind::System { .. } | EventKind::Usage));
+
+    match last.map(|e| &e.kind) {
+        None => Err("Session is empty".to_string()),
+        Some(EventKind::UserMessage { .. }) => {
+            Err("Session appears incomplete (ends with user message)".to_string())
+        }
+        Some(_) => Ok(()),
+    }
+}
+
+fn event_timestamp(timestamp: &Option<String>) -> String {
+    timestamp
+        .clone()
+        .unwrap_or_else(|| chrono::Utc::now().to_rfc3339())
+}
+
+// ============================================================================
+// Gemini → Claude
+// ============================================================================
+
+/// Gemini Session → Claude Session converter
+pub struct GeminiToClaudeConverter {
+    source_session_id: String,
+    project_id: String,     // Claude project directory name (e.g. C--Users-...)
+    project_path: String,   // Original project path (also locates the Gemini chat)
+    new_session_id: String, // UUID
+}
+
+impl GeminiToClaudeConverter {
+    pub fn new(source_session_id: String, project_id: String, project_path: String) -> Self {
+        Self {
+            source_session_id,
+            project_id,
+            project_path,
+            new_session_id: uuid::Uuid::new_v4().to_string(),
+        }
+    }
+
+    pub fn convert(&self) -> Result<ConversionResult, String> {
+        log::info!(
+            "Converting Gemini session {} to Claude",
+            self.source_session_id
+        );
+
+        let session = loader_for(SessionEngine::Gemini)
+            .load(&self.source_session_id, Some(&self.project_path))?;
+        validate_session_completed(&session)?;
+
+        let source = conversion_source(
+            SessionEngine::Gemini,
+            &self.source_session_id,
+            &self.project_path,
+        );
+        let messages = self.build_messages(&session, source.clone());
+        let target_path =
+            write_claude_session_file(&self.project_id, &self.new_session_id, &messages)?;
+
+        log::info!(
+            "Successfully converted {} events to Claude session {}",
+            messages.len(),
+            self.new_session_id
+        );
+
+        Ok(ConversionResult {
+            success: true,
+            new_session_id: self.new_session_id.clone(),
+            target_engine: "claude".to_string(),
+            message_count: messages.len(),
+            source,
+            target_path,
+            error: None,
+        })
+    }
+
+    fn build_messages(
+        &self,
+        session: &UnifiedSession,
+        source: ConversionSource,
+    ) -> Vec<ClaudeMessage> {
+        let first_timestamp = session
+            .started_at
+            .clone()
+            .unwrap_or_else(|| chrono::Utc::now().to_rfc3339());
+
+        let mut messages = vec![file_history_snapshot(&first_timestamp)];
+
+        // System init message carries the provenance, like Codex session_meta does
+        let mut init = self.message(
+            "system",
+            "system",
+            Vec::new(),
+            &first_timestamp,
+            session.model.clone(),
+        );
+        init.message = None;
+        init.subtype = Some("init".to_string());
+        init.conversion_source = Some(source);
+        messages.push(init);
+
+        for event in &session.events {
+            let timestamp = event_timestamp(&event.timestamp);
+            let mut message = match &event.kind {
+                EventKind::UserMessage { text } => self.message(
+                    "user",
+                    "user",
+                    vec![ClaudeContentBlock::Text { text: text.clone() }],
+                    &timestamp,
+                    None,
+                ),
+                EventKind::AssistantMessage { text } => self.message(
+                    "assistant",
+                    "assistant",
+                    vec![ClaudeContentBlock::Text { text: text.clone() }],
+                    &timestamp,
+                    event.model.clone(),
+                ),
+                EventKind::Thinking { text } => self.message(
+                    "assistant",
+                    "assistant",
+                    vec![ClaudeContentBlock::Thinking {
+                        thinking: text.clone(),
+                    }],
+                    &timestamp,
+                    event.model.clone(),
+                ),
+                EventKind::ToolCall {
+                    call_id,
+                    name,
+                    input,
+                } => self.message(
+                    "assistant",
+                    "assistant",
+                    vec![ClaudeContentBlock::ToolUse {
+                        id: call_id.clone(),
+                        name: map_gemini_to_claude_tool(name),
+                        input: input.clone(),
+                    }],
+                    &timestamp,
+                    event.model.clone(),
+                ),
+                EventKind::ToolResult {
+                    call_id,
+                    output,
+                    is_error,
+                } => self.message(
+                    "user",
+                    "user",
+                    vec![ClaudeContentBlock::ToolResult {
+                        tool_use_id: call_id.clone(),
+                        content: Value::String(output.clone()),
+                        is_error: Some(*is_error),
+                    }],
+                    &timestamp,
+                    None,
+                ),
+                EventKind::System { .. } | EventKind::Usage => continue,
+            };
+
+            if let (Some(usage), Some(content)) = (&event.usage, message.message.as_mut()) {
+                content.usage = Some(TokenUsage {
+                    input_tokens: usage.input_tokens,
+                    output_tokens: usage.output_tokens,
+                    cache_creation_tokens: Some(usage.cache_creation_tokens),
+                    cache_read_tokens: Some(usage.cache_read_tokens),
+                });
+            }
+            messages.push(message);
+        }
+
+        messages
+    }
+
+    fn message(
+        &self,
+        message_type: &str,
+        role: &str,
+        content: Vec<ClaudeContentBlock>,
+        timestamp: &str,
+        model: Option<String>,
+    ) -> ClaudeMessage {
+        build_claude_message(
+            &self.new_session_id,
+            &self.project_path,
+            message_type,
+            role,
+            content,
+            timestamp,
+            model,
+        )
+    }
+}
+
+// ============================================================================
+// Gemini → Codex
+// ============================================================================
+
+/// Gemini Session → Codex Session converter
+pub struct GeminiToCodexConverter {
+    source_session_id: String,
+    project_path: String,
+    new_session_uuid: String,     // Plain UUID (file content)
+    new_session_filename: String, // rollout-{timestamp}-{uuid} (file name)
+}
+
+impl GeminiToCodexConverter {
+    pub fn new(source_session_id: String, project_path: String) -> Self {
+        let uuid = uuid::Uuid::new_v4().to_string();
+        let timestamp = chrono::Utc::now().format("%Y-%m-%dT%H-%M-%S").to_string();
+        let new_session_filename = format!("rollout-{}-{}", timestamp, uuid);
+
+        Self {
+            source_session_id,
+            project_path,
+            new_session_uuid: uuid,
+            new_session_filename,
+        }
+    }
+
+    pub fn convert(&self) -> Result<ConversionResult, String> {
+        log::info!(
+            "Converting Gemini session {} to Codex",
+            self.source_session_id
+        );
+
+        let session = loader_for(SessionEngine::Gemini)
+            .load(&self.source_session_id, Some(&self.project_path))?;
+        validate_session_completed(&session)?;
+
+        let source = conversion_source(
+            SessionEngine::Gemini,
+            &self.source_session_id,
+            &self.project_path,
+        );
+        let events = self.build_events(&session, &source);
+        let target_path = write_codex_session_file(&self.new_session_filename, &events)?;
+
+        log::info!(
+            "Successfully converted {} events to Codex session {}",
+            events.len(),
+            self.new_session_filename
+        );
+
+        Ok(ConversionResult {
+            success: true,
+            new_session_id: self.new_session_filename.clone(),
+            target_engine: "codex".to_string(),
+            message_count: events.len(),
+            source,
+            target_path,
+            error: None,
+        })
+    }
+
+    fn build_events(&self, session: &UnifiedSession, source: &ConversionSource) -> Vec<CodexEvent> {
+        let first_timestamp = session
+            .started_at
+            .clone()
+            .unwrap_or_else(|| chrono::Utc::now().to_rfc3339());
+
+        let mut events = vec![response_event(
+            "session_meta",
+            &first_timestamp,
+            serde_json::json!({
+                "id": self.new_session_uuid,
+                "timestamp": first_timestamp,
+                "cwd": self.project_path,
+                "originator": "session_converter",
+                "cli_version": "converted",
+                "source": "conversion",
+                "model_provider": session.model.as_ref().map(|_| "converted").unwrap_or("unknown"),
+                "conversion_source": {
+                    "engine": source.engine,
+                    "session_id": source.session_id,
+                    "converted_at": source.converted_at,
+                    "source_project_path": source.source_project_path
+                }
+            }),
+        )];
+
+        for event in &session.events {
+            let timestamp = event_timestamp(&event.timestamp);
+            let payload = match &event.kind {
+                EventKind::UserMessage { text } => serde_json::json!({
+                    "type": "message",
+                    "role": "user",
+                    "content": [{ "type": "input_text", "text": text }]
+                }),
+                EventKind::AssistantMessage { text } => serde_json::json!({
+                    "type": "message",
+                    "role": "assistant",
+                    "content": [{ "type": "output_text", "text": text }]
+                }),
+                EventKind::Thinking { text } => {
+                    events.push(response_event(
+                        "event_msg",
+                        &timestamp,
+                        serde_json::json!({
+                            "item": {
+                                "id": format!("reasoning_{}", uuid::Uuid::new_v4()),
+                                "type": "reasoning",
+                                "text": text
+                            },
+                            "phase": "completed"
+                        }),
+                    ));
+                    continue;
+                }
+                EventKind::ToolCall {
+                    call_id,
+                    name,
+                    input,
+                } => serde_json::json!({
+                    "type": "function_call",
+                    "name": map_gemini_to_codex_tool(name),
+                    "arguments": serde_json::to_string(input).unwrap_or_default(),
+                    "call_id": call_id,
+                    "timestamp": timestamp
+                }),
+                EventKind::ToolResult {
+                    call_id,
+                    output,
+                    is_error,
+                } => serde_json::json!({
+                    "type": "function_call_output",
+                    "call_id": call_id,
+                    "output": output,
+                    "is_error": is_error,
+                    "timestamp": timestamp
+                }),
+                EventKind::System { .. } | EventKind::Usage => continue,
+            };
+            events.push(response_event("response_item", &timestamp, payload));
+        }
+
+        events
+    }
+}
+
+fn response_event(event_type: &str, timestamp: &str, payload: Value) -> CodexEvent {
+    CodexEvent {
+        event_type: event_type.to_string(),
+        timestamp: Some(timestamp.to_string()),
+        payload: Some(payload),
+        thread_id: None,
+        usage: None,
+    }
+}
+
+// ============================================================================
+// Claude / Codex → Gemini
+// ============================================================================
+
+/// Gemini chat file with the provenance of a converted session
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConvertedGeminiSession {
+    #[serde(flatten)]
+    detail: GeminiSessionDetail,
+    conversion_source: ConversionSource,
+}
+
+/// One `type: "gemini"` message being assembled from unified events
+#[derive(Default)]
+struct PendingGeminiTurn {
+    timestamp: Option<String>,
+    content: String,
+    thoughts: Vec<Value>,
+    tool_calls: Vec<Value>,
+    model: Option<String>,
+    usage: UnifiedUsage,
+}
+
+impl PendingGeminiTurn {
+    fn into_message(self) -> Value {
+        let timestamp = event_timestamp(&self.timestamp);
+        let mut message = serde_json::json!({
+            "id": uuid::Uuid::new_v4().to_string(),
+            "timestamp": timestamp,
+            "type": "gemini",
+            "content": self.content,
+        });
+        if !self.thoughts.is_empty() {
+            message["thoughts"] = Value::Array(self.thoughts);
+        }
+        if !self.tool_calls.is_empty() {
+            message["toolCalls"] = Value::Array(self.tool_calls);
+        }
+        if !self.usage.is_empty() {
+            message["tokens"] = serde_json::json!({
+                "input": self.usage.input_tokens,
+                "output": self.usage.output_tokens,
+                "cached": self.usage.cache_read_tokens,
+                "thoughts": 0,
+                "tool": 0,
+                "total": self.usage.total_tokens()
+            });
+        }
+        if let Some(model) = self.model {
+            message["model"] = Value::String(model);
+        }
+        message
+    }
+}
+
+/// Regroups unified events into Gemini chat messages.
+///
+/// Gemini stores one `gemini` message per model turn with its thoughts and
+/// tool calls (results inlined) followed by the final text, so a turn is
+/// closed whenever a user prompt arrives or new activity follows its text.
+fn build_gemini_messages(session: &UnifiedSession, map_tool: fn(&str) -> String) -> Vec<Value> {
+    let mut messages = Vec::new();
+    let mut pending: Option<PendingGeminiTurn> = None;
+
+    for event in &session.events {
+        match &event.kind {
+            EventKind::UserMessage { text } => {
+                if let Some(turn) = pending.take() {
+                    messages.push(turn.into_message());
+                }
+                messages.push(serde_json::json!({
+                    "id": uuid::Uuid::new_v4().to_string(),
+                    "timestamp": event_timestamp(&event.timestamp),
+                    "type": "user",
+                    "content": text
+                }));
+                continue;
+            }
+            EventKind::ToolResult {
+                call_id,
+                output,
+                is_error,
+            } => {
+                let call = pending.as_mut().and_then(|turn| {
+                    turn.tool_calls
+                        .iter_mut()
+                        .find(|c| c.get("id").and_then(|v| v.as_str()) == Some(call_id))
+                });
+                if let Some(call) = call {
+                    let name = call.get("name").cloned().unwrap_or(Value::Null);
+                    call["result"] = serde_json::json!([{
+                        "functionResponse": {
+                            "id": call_id,
+                            "name": name,
+                            "response": { "output": output }
+                        }
+                    }]);
+                    call["resultDisplay"] = Value::String(output.clone());
+                    call["status"] =
+                        Value::String(if *is_error { "error" } else { "success" }.to_string());
+                }
+                continue;
+            }
+            EventKind::System { .. } => continue,
+            EventKind::Usage => {
+                if let (Some(turn), Some(usage)) = (pending.as_mut(), &event.usage) {
+                    turn.usage.add(usage);
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        // Activity after the turn's final text starts a new model turn
+        let closes_turn = pending
+            .as_ref()
+            .is_some_and(|turn| !turn.content.is_empty())
+            && !matches!(event.kind, EventKind::AssistantMessage { .. });
+        if closes_turn {
+            if let Some(turn) = pending.take() {
+                messages.push(turn.into_message());
+            }
+        }
+
+        let turn = pending.get_or_insert_with(|| PendingGeminiTurn {
+            timestamp: event.timestamp.clone(),
+            ..Default::default()
+        });
+        if event.model.is_some() {
+            turn.model = event.model.clone();
+        }
+        if let Some(usage) = &event.usage {
+            turn.usage.add(usage);
+        }
+
+        match &event.kind {
+            EventKind::AssistantMessage { text } => {
+                if !turn.content.is_empty() {
+                    turn.content.push_str("\n\n");
+                }
+                turn.content.push_str(text);
+            }
+            EventKind::Thinking { text } => turn.thoughts.push(serde_json::json!({
+                "subject": "",
+                "description": text,
+                "timestamp": event_timestamp(&event.timestamp)
+            })),
+            EventKind::ToolCall {
+                call_id,
+                name,
+                input,
+            } => turn.tool_calls.push(serde_json::json!({
+                "id": call_id,
+                "name": map_tool(name),
+                "args": input,
+                "status": "success",
+                "timestamp": event_timestamp(&event.timestamp)
+            })),
+            _ => {}
+        }
+    }
+
+    if let Some(turn) = pending.take() {
+        messages.push(turn.into_message());
+    }
+
+    messages
+}
+
+/// Writes a converted session into `~/.gemini/tmp/<hash>/chats/`
+fn write_gemini_session(
+    session: &UnifiedSession,
+    new_session_id: &str,
+    project_path: &str,
+    source: ConversionSource,
+    map_tool: fn(&str) -> String,
+) -> Result<(String, usize), String> {
+    let chats_dir = get_project_session_dir(project_path)?.join("chats");
+    std::fs::create_dir_all(&chats_dir)
+        .map_err(|e| format!("Failed to create chats directory: {}", e))?;
+
+    // Stamped with the conversion time so `--resume latest` finds it
+    let now = chrono::Utc::now();
+    let messages = build_gemini_messages(session, map_tool);
+    let message_count = messages.len();
+
+    let converted = ConvertedGeminiSession {
+        detail: GeminiSessionDetail {
+            session_id: new_session_id.to_string(),
+            project_hash: hash_project_path(project_path),
+            start_time: now.to_rfc3339(),
+            last_updated: now.to_rfc3339(),
+            messages,
+        },
+        conversion_source: source,
+    };
+
+    let file_name = format!(
+        "session-{}-{}.json",
+        now.format("%Y-%m-%dT%H-%M"),
+        &new_session_id[..8]
+    );
+    let file_path = chats_dir.join(file_name);
+    let content = serde_json::to_string_pretty(&converted)
+        .map_err(|e| format!("Failed to serialize session: {}", e))?;
+    std::fs::write(&file_path, content)
+        .map_err(|e| format!("Failed to write session file: {}", e))?;
+
+    Ok((file_path.to_string_lossy().to_string(), message_count))
+}
+
+/// Claude Session → Gemini Session converter
+pub struct ClaudeToGeminiConverter {
+    source_session_id: String,
+    project_id: String,   // Claude project directory name
+    project_path: String, // Original project path (determines the Gemini project hash)
+    new_session_id: String,
+}
+
+impl ClaudeToGeminiConverter {
+    pub fn new(source_session_id: String, project_id: String, project_path: String) -> Self {
+        Self {
+            source_session_id,
+            project_id,
+            project_path,
+            new_session_id: uuid::Uuid::new_v4().to_string(),
+        }
+    }
+
+    pub fn convert(&self) -> Result<ConversionResult, String> {
+        log::info!(
+            "Converting Claude session {} to Gemini",
+            self.source_session_id
+        );
+
+        let session = loader_for(SessionEngine::Claude)
+            .load(&self.source_session_id, Some(&self.project_id))?;
+        validate_session_completed(&session)?;
+
+        let source = conversion_source(
+            SessionEngine::Claude,
+            &self.source_session_id,
+            &self.project_path,
+        );
+        let (target_path, message_count) = write_gemini_session(
+            &session,
+            &self.new_session_id,
+            &self.project_path,
+            source.clone(),
+            map_claude_to_gemini_tool,
+        )?;
+
+        log::info!(
+            "Successfully converted {} messages to Gemini session {}",
+            message_count,
+            self.new_session_id
+        );
+
+        Ok(ConversionResult {
+            success: true,
+            new_session_id: self.new_session_id.clone(),
+            target_engine: "gemini".to_string(),
+            message_count,
+            source,
+            target_path,
+            error: None,
+        })
+    }
+}
+
+/// Codex Session → Gemini Session converter
+pub struct CodexToGeminiConverter {
+    source_session_id: String,
+    project_path: String,
+    new_session_id: String,
+}
+
+impl CodexToGeminiConverter {
+    pub fn new(source_session_id: String, project_path: String) -> Self {
+        Self {
+            source_session_id,
+            project_path,
+            new_session_id: uuid::Uuid::new_v4().to_string(),
+        }
+    }
+
+    pub fn convert(&self) -> Result<ConversionResult, String> {
+        log::info!(
+            "Converting Codex session {} to Gemini",
+            self.source_session_id
+        );
+
+        let session = loader_for(SessionEngine::Codex).load(&self.source_session_id, None)?;
+        validate_session_completed(&session)?;
+
+        let source = conversion_source(
+            SessionEngine::Codex,
+            &self.source_session_id,
+            &self.project_path,
+        );
+        let (target_path, message_count) = write_gemini_session(
+            &session,
+            &self.new_session_id,
+            &self.project_path,
+            source.clone(),
+            map_codex_to_gemini_tool,
+        )?;
+
+        log::info!(
+            "Successfully converted {} messages to Gemini session {}",
+            message_count,
+            self.new_session_id
+        );
+
+        Ok(ConversionResult {
+            success: true,
+            new_session_id: self.new_session_id.clone(),
+            target_engine: "gemini".to_string(),
+            message_count,
+            source,
+            target_path,
+            error: None,
+        })
+    }
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Convenience command: Gemini → Claude
+#[tauri::command]
+pub async fn convert_gemini_to_claude(
+    session_id: String,
+    project_id: String,
+    project_path: String,
+) -> Result<ConversionResult, String> {
+    convert_session(session_id, "claude".to_string(), project_id, project_path).await
+}
+
+/// Convenience command: Gemini → Codex
+#[tauri::command]
+pub async fn convert_gemini_to_codex(
+    session_id: String,
+    project_id: String,
+    project_path: String,
+) -> Result<ConversionResult, String> {
+    convert_session(session_id, "codex".to_string(), project_id, project_path).await
+}
+
+/// Convenience command: Claude → Gemini
+#[tauri::command]
+pub async fn convert_claude_to_gemini(
+    session_id: String,
+    project_id: String,
+    project_path: String,
+) -> Result<ConversionResult, String> {
+    convert_session(session_id, "gemini".to_string(), project_id, project_path).await
+}
+
+/// Convenience command: Codex → Gemini
+#[tauri::command]
+pub async fn convert_codex_to_gemini(
+    session_id: String,
+    project_id: String,
+    project_path: String,
+) -> Result<ConversionResult, String> {
+    convert_session(session_id, "gemini".to_string(), project_id, project_path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn maps_tool_names_between_engines() {
+        assert_eq!(map_gemini_to_claude_tool("run_shell_command"), "bash");
+        assert_eq!(map_claude_to_gemini_tool("Edit"), "replace");
+        assert_eq!(
+            map_gemini_to_codex_tool("run_shell_command"),
+            "shell_command"
+        );
+        assert_eq!(map_codex_to_gemini_tool("shell"), "run_shell_command");
+        assert_eq!(
+            map_claude_to_gemini_tool("mcp__github__search"),
+            "mcp__github__search"
+        );
+        assert_eq!(map_gemini_to_claude_tool("save_memory"), "save_memory");
+    }
+
+    #[test]
+    fn groups_events_into_gemini_turns() {
+        let mut session = UnifiedSession::new(
+            "s1".to_string(),
+            SessionEngine::Claude,
+            Path::new("/tmp/s1.jsonl"),
+        );
+        let usage = UnifiedUsage {
+            input_tokens: 10,
+            output_tokens: 5,
+            ..Default::default()
+        };
+        session.push(
+            None,
+            EventKind::UserMessage {
+                text: "fix it".into(),
+            },
+            None,
+            None,
+        );
+        session.push(
+            None,
+            EventKind::ToolCall {
+                call_id: "t1".into(),
+                name: "Bash".into(),
+                input: serde_json::json!({ "command": "ls" }),
+            },
+            Some("claude-sonnet-4".into()),
+            Some(usage),
+        );
+        session.push(
+            None,
+            EventKind::ToolResult {
+                call_id: "t1".into(),
+                output: "a.rs".into(),
+                is_error: false,
+            },
+            None,
+            None,
+        );
+        session.push(
+            None,
+            EventKind::AssistantMessage {
+                text: "Done.".into(),
+            },
+            None,
+            None,
+        );
+        session.push(
+            None,
+            EventKind::Thinking {
+                text: "more".into(),
+            },
+            None,
+            None,
+        );
+        session.push(
+            None,
+            EventKind::AssistantMessage {
+                text: "Also this.".into(),
+            },
+            None,
+            None,
+        );
+
+        let messages = build_gemini_messages(&session, map_claude_to_gemini_tool);
+
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0]["type"], "user");
+        assert_eq!(messages[1]["content"], "Done.");
+        assert_eq!(messages[1]["model"], "claude-sonnet-4");
+        assert_eq!(messages[1]["tokens"]["input"], 10);
+        let call = &messages[1]["toolCalls"][0];
+        assert_eq!(call["name"], "run_shell_command");
+        assert_eq!(
+            call["result"][0]["functionResponse"]["response"]["output"],
+            "a.rs"
+        );
+        assert_eq!(messages[2]["thoughts"][0]["description"], "more");
+        assert_eq!(messages[2]["content"], "Also this.");
+    }
+}
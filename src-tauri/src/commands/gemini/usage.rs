@@ -8,11 +8,18 @@
  * - Per-project statistics
  */
 use chrono::NaiveDate;
+use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
-
+use std::path::Path;
+use tauri::AppHandle;
+
+use super::super::pricing::price_book;
+use super::super::session_timeline::SessionEngine;
+use super::super::usage_ingest::{
+    ingested_sessions, normalize_timestamp, project_name, query_usage, query_usage_total,
+    sync_usage, with_usage_db, IngestedFile, OnConflict, UsageGroup, UsageRange, UsageRow,
+};
 use super::config::get_gemini_dir;
 use super::types::GeminiSessionDetail;
 
@@ -82,225 +89,155 @@ pub struct GeminiUsageStats {
 }
 
 // ============================================================================
-// Pricing (Must match frontend pricing.ts)
+// Incremental Ingestion
 // ============================================================================
 
-#[derive(Debug, Clone, Copy)]
-struct ModelPricing {
-    input: f64,
-    output: f64,
-    cache_read: f64,
-}
-
-fn get_gemini_pricing(model: &str) -> ModelPricing {
-    let normalized = model.to_lowercase();
-
-    // Gemini 3.1 Pro Preview (Latest - February 2026)
-    if normalized.contains("gemini-3.1-pro") || normalized.contains("gemini_3_1_pro")
-        || normalized.contains("3.1-pro") {
-        return ModelPricing {
-            input: 2.50,
-            output: 15.00,
-            cache_read: 0.25,
-        };
-    }
-
-    // Gemini 3 Pro Preview
-    if normalized.contains("gemini-3-pro") || normalized.contains("gemini_3_pro") {
-        return ModelPricing {
-            input: 2.00,
-            output: 12.00,
-            cache_read: 0.20,
-        };
-    }
-
-    // Gemini 2.5 Pro
-    if normalized.contains("2.5-pro") || normalized.contains("2_5_pro") {
-        return ModelPricing {
-            input: 1.25,
-            output: 10.00,
-            cache_read: 0.125,
-        };
-    }
-
-    // Gemini 2.5 Flash-Lite
-    if normalized.contains("2.5-flash-lite") || normalized.contains("2_5_flash_lite") {
-        return ModelPricing {
-            input: 0.10,
-            output: 0.40,
-            cache_read: 0.01,
-        };
-    }
-
-    // Gemini 2.5 Flash
-    if normalized.contains("2.5-flash") || normalized.contains("2_5_flash") {
-        return ModelPricing {
-            input: 0.30,
-            output: 2.50,
-            cache_read: 0.03,
-        };
-    }
-
-    // Gemini 2.0 Flash
-    if normalized.contains("2.0-flash") || normalized.contains("2_0_flash") {
-        return ModelPricing {
-            input: 0.10,
-            output: 0.40,
-            cache_read: 0.025,
-        };
-    }
-
-    // Gemini 3 Flash (default for new sessions)
-    if normalized.contains("gemini-3-flash") || normalized.contains("gemini_3_flash") {
-        return ModelPricing {
-            input: 0.30,
-            output: 2.50,
-            cache_read: 0.03,
-        };
-    }
-
-    // Default to Gemini 2.5 Pro pricing
-    ModelPricing {
-        input: 1.25,
-        output: 10.00,
-        cache_read: 0.125,
-    }
-}
-
-fn calculate_cost(model: &str, input_tokens: u64, output_tokens: u64) -> f64 {
-    let pricing = get_gemini_pricing(model);
-
-    let input_cost = (input_tokens as f64 / 1_000_000.0) * pricing.input;
-    let output_cost = (output_tokens as f64 / 1_000_000.0) * pricing.output;
+const DEFAULT_MODEL: &str = "gemini-3-flash";
 
-    input_cost + output_cost
-}
-
-// ============================================================================
-// Session Parsing
-// ============================================================================
-
-fn read_session_detail_from_path(path: &PathBuf) -> Result<GeminiSessionDetail, String> {
+fn read_session_detail_from_path(path: &Path) -> Result<GeminiSessionDetail, String> {
     let content =
         fs::read_to_string(path).map_err(|e| format!("Failed to read session file: {}", e))?;
 
     serde_json::from_str(&content).map_err(|e| format!("Failed to parse session file: {}", e))
 }
 
-fn parse_session_for_usage(
-    path: &PathBuf,
-    project_hash: &str,
-) -> Option<GeminiSessionUsage> {
-    let detail = read_session_detail_from_path(path).ok()?;
-
-    // Extract token usage from messages
-    let mut total_input_tokens: u64 = 0;
-    let mut total_output_tokens: u64 = 0;
-    let mut model = "gemini-3-flash".to_string();
-    let mut first_message: Option<String> = None;
+/// Parses the messages added to a chat file since the last pass.
+///
+/// Chat files are rewritten as a whole, so `file.offset` counts messages. The
+/// last message seen is parsed again because its token counts may only be
+/// filled in after the response completes; rows are keyed by message id and
+/// replaced.
+fn ingest_chat_file(path: &Path, file: &mut IngestedFile) -> Result<Vec<UsageRow>, String> {
+    let detail = read_session_detail_from_path(path)?;
+
+    // tmp/<project-hash>/chats/session-*.json
+    let project_hash = path
+        .parent()
+        .and_then(|p| p.parent())
+        .and_then(|p| p.file_name())
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| detail.project_hash.clone());
+
+    file.session_id = Some(detail.session_id.clone());
+    file.project_path = Some(format!("project:{}", project_hash));
+    file.started_at = Some(detail.start_time.clone());
+
+    let start = if (detail.messages.len() as u64) < file.offset {
+        0
+    } else {
+        file.offset.saturating_sub(1) as usize
+    };
 
-    for message in &detail.messages {
+    let mut rows = Vec::new();
+    for (index, message) in detail.messages.iter().enumerate() {
         // Extract model if available
         if let Some(m) = message.get("model").and_then(|v| v.as_str()) {
-            model = m.to_string();
-        }
-
-        // Extract tokens if available
-        if let Some(tokens) = message.get("tokens").and_then(|v| v.as_object()) {
-            if let Some(input) = tokens.get("input").and_then(|v| v.as_u64()) {
-                total_input_tokens += input;
-            }
-            if let Some(output) = tokens.get("output").and_then(|v| v.as_u64()) {
-                total_output_tokens += output;
-            }
+            file.model = Some(m.to_string());
         }
 
         // Get first user message
-        if first_message.is_none() {
-            if message.get("type").and_then(|v| v.as_str()) == Some("user") {
-                if let Some(content) = message.get("content").and_then(|v| v.as_str()) {
-                    // Skip task/subagent messages
-                    if !content.trim_start().starts_with("Your task is to") {
-                        first_message = Some(content.to_string());
-                    }
+        if file.first_message.is_none()
+            && message.get("type").and_then(|v| v.as_str()) == Some("user")
+        {
+            if let Some(content) = message.get("content").and_then(|v| v.as_str()) {
+                // Skip task/subagent messages
+                if !content.trim_start().starts_with("Your task is to") {
+                    file.first_message = Some(content.to_string());
                 }
             }
         }
-    }
 
-    // Skip empty sessions
-    if total_input_tokens == 0 && total_output_tokens == 0 {
-        return None;
+        if index < start {
+            continue;
+        }
+
+        // Extract tokens if available
+        let Some(tokens) = message.get("tokens").and_then(|v| v.as_object()) else {
+            continue;
+        };
+        let input = tokens.get("input").and_then(|v| v.as_u64()).unwrap_or(0);
+        let output = tokens.get("output").and_then(|v| v.as_u64()).unwrap_or(0);
+        if input == 0 && output == 0 {
+            continue;
+        }
+
+        let model = file
+            .model
+            .clone()
+            .unwrap_or_else(|| DEFAULT_MODEL.to_string());
+        let message_key = message
+            .get("id")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| index.to_string());
+
+        let timestamp = normalize_timestamp(
+            message
+                .get("timestamp")
+                .and_then(|v| v.as_str())
+                .unwrap_or(&detail.start_time),
+        );
+        rows.push(UsageRow {
+            session_id: detail.session_id.clone(),
+            timestamp,
+            model,
+            input_tokens: input,
+            output_tokens: output,
+            cache_creation_tokens: 0,
+            cache_read_tokens: 0,
+            total_tokens: input + output,
+            project_path: format!("project:{}", project_hash),
+            dedup_key: Some(format!("gemini:{}:{}", detail.session_id, message_key)),
+            ..Default::default()
+        });
     }
 
-    let total_cost = calculate_cost(&model, total_input_tokens, total_output_tokens);
-
-    Some(GeminiSessionUsage {
-        session_id: detail.session_id,
-        project_path: String::new(), // Will be populated later if we can find it
-        project_hash: project_hash.to_string(),
-        model,
-        total_cost,
-        input_tokens: total_input_tokens,
-        output_tokens: total_output_tokens,
-        start_time: detail.start_time,
-        first_message,
-    })
+    file.offset = detail.messages.len() as u64;
+    Ok(rows)
 }
 
-fn collect_all_sessions() -> Vec<GeminiSessionUsage> {
-    let gemini_dir = match get_gemini_dir() {
-        Ok(dir) => dir,
-        Err(_) => return Vec::new(),
-    };
-
-    let tmp_dir = gemini_dir.join("tmp");
+/// Ingests Gemini usage written since the last pass into `usage_entries`
+pub(crate) fn sync_gemini_usage(conn: &mut Connection) -> Result<usize, String> {
+    let tmp_dir = get_gemini_dir()?.join("tmp");
     if !tmp_dir.exists() {
-        return Vec::new();
+        return Ok(0);
     }
 
-    let mut sessions = Vec::new();
-
-    // Iterate over all project hash directories
-    if let Ok(entries) = fs::read_dir(&tmp_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if !path.is_dir() {
-                continue;
-            }
-
-            let project_hash = path
-                .file_name()
-                .and_then(|s| s.to_str())
-                .unwrap_or("")
-                .to_string();
+    // Iterate over all project hash directories (tmp/<hash>/chats/*.json)
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(&tmp_dir)
+        .map_err(|e| format!("Failed to read Gemini tmp directory: {}", e))?
+        .flatten()
+    {
+        let chats_dir = entry.path().join("chats");
+        let Ok(chat_entries) = fs::read_dir(&chats_dir) else {
+            continue;
+        };
+        paths.extend(
+            chat_entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.extension().and_then(|s| s.to_str()) == Some("json")),
+        );
+    }
 
-            // Check chats/ subdirectory
-            let chats_dir = path.join("chats");
-            if !chats_dir.exists() {
-                continue;
-            }
+    sync_usage(
+        conn,
+        SessionEngine::Gemini,
+        paths,
+        OnConflict::Replace,
+        &price_book(),
+        &ingest_chat_file,
+    )
+}
 
-            if let Ok(chat_entries) = fs::read_dir(&chats_dir) {
-                for chat_entry in chat_entries.flatten() {
-                    let chat_path = chat_entry.path();
-                    if chat_path.extension().and_then(|s| s.to_str()) == Some("json") {
-                        if let Some(mut session) = parse_session_for_usage(&chat_path, &project_hash)
-                        {
-                            // Try to find project path from session data
-                            // For now, use the hash as identifier
-                            session.project_path = format!("project:{}", project_hash);
-                            sessions.push(session);
-                        }
-                    }
-                }
-            }
-        }
+/// Display name for a project key (`project:<hash>` shows the short hash)
+pub(crate) fn gemini_project_name(project_path: &str) -> String {
+    match project_path.strip_prefix("project:") {
+        Some(hash) => hash.chars().take(8).collect(),
+        None => project_name(project_path),
     }
-
-    // Sort by start time (newest first)
-    sessions.sort_by(|a, b| b.start_time.cmp(&a.start_time));
-    sessions
 }
 
 // ============================================================================
@@ -310,6 +247,7 @@ fn collect_all_sessions() -> Vec<GeminiSessionUsage> {
 /// Get Gemini usage statistics
 #[tauri::command]
 pub async fn get_gemini_usage_stats(
+    app: AppHandle,
     start_date: Option<String>,
     end_date: Option<String>,
 ) -> Result<GeminiUsageStats, String> {
@@ -319,134 +257,110 @@ pub async fn get_gemini_usage_stats(
         end_date
     );
 
-    let all_sessions = collect_all_sessions();
-
     // Filter by date range if provided
-    let filtered_sessions: Vec<GeminiSessionUsage> = if let (Some(start), Some(end)) =
-        (&start_date, &end_date)
-    {
+    let range = if let (Some(start), Some(end)) = (&start_date, &end_date) {
         let start_naive = NaiveDate::parse_from_str(start, "%Y-%m-%d")
             .map_err(|e| format!("Invalid start date: {}", e))?;
         let end_naive = NaiveDate::parse_from_str(end, "%Y-%m-%d")
             .map_err(|e| format!("Invalid end date: {}", e))?;
-
-        all_sessions
-            .into_iter()
-            .filter(|s| {
-                // Parse start_time (ISO 8601 format)
-                if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(&s.start_time) {
-                    let date = dt.date_naive();
-                    date >= start_naive && date <= end_naive
-                } else {
-                    false
-                }
-            })
-            .collect()
+        UsageRange::local_dates(Some(start_naive), Some(end_naive))
     } else {
-        all_sessions
+        UsageRange::default()
     };
 
-    // Aggregate statistics
-    let mut total_cost = 0.0;
-    let mut total_input_tokens = 0u64;
-    let mut total_output_tokens = 0u64;
-
-    let mut model_stats: HashMap<String, GeminiModelUsage> = HashMap::new();
-    let mut daily_stats: HashMap<String, GeminiDailyUsage> = HashMap::new();
-    let mut project_stats: HashMap<String, GeminiProjectUsage> = HashMap::new();
-
-    for session in &filtered_sessions {
-        total_cost += session.total_cost;
-        total_input_tokens += session.input_tokens;
-        total_output_tokens += session.output_tokens;
-
-        // Update model stats
-        let model_stat = model_stats
-            .entry(session.model.clone())
-            .or_insert(GeminiModelUsage {
-                model: session.model.clone(),
-                total_cost: 0.0,
-                total_tokens: 0,
-                input_tokens: 0,
-                output_tokens: 0,
-                cache_creation_tokens: 0,
-                cache_read_tokens: 0,
-                session_count: 0,
-            });
-        model_stat.total_cost += session.total_cost;
-        model_stat.input_tokens += session.input_tokens;
-        model_stat.output_tokens += session.output_tokens;
-        model_stat.total_tokens = model_stat.input_tokens + model_stat.output_tokens;
-        model_stat.session_count += 1;
-
-        // Update daily stats
-        let date = if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(&session.start_time) {
-            dt.format("%Y-%m-%d").to_string()
-        } else {
-            session.start_time.split('T').next().unwrap_or("unknown").to_string()
-        };
-
-        let daily_stat = daily_stats.entry(date.clone()).or_insert(GeminiDailyUsage {
-            date,
-            total_cost: 0.0,
-            total_tokens: 0,
-            models_used: vec![],
-        });
-        daily_stat.total_cost += session.total_cost;
-        daily_stat.total_tokens += session.input_tokens + session.output_tokens;
-        if !daily_stat.models_used.contains(&session.model) {
-            daily_stat.models_used.push(session.model.clone());
-        }
-
-        // Update project stats (use project_hash as key)
-        let project_name = if session.project_path.starts_with("project:") {
-            session.project_hash.chars().take(8).collect::<String>()
-        } else {
-            session
-                .project_path
-                .split(['/', '\\'])
-                .last()
-                .unwrap_or(&session.project_path)
-                .to_string()
-        };
-
-        let project_stat = project_stats
-            .entry(session.project_hash.clone())
-            .or_insert(GeminiProjectUsage {
-                project_path: session.project_path.clone(),
-                project_name,
-                total_cost: 0.0,
-                total_tokens: 0,
-                session_count: 0,
-                last_used: session.start_time.clone(),
-            });
-        project_stat.total_cost += session.total_cost;
-        project_stat.total_tokens += session.input_tokens + session.output_tokens;
-        project_stat.session_count += 1;
-        if session.start_time > project_stat.last_used {
-            project_stat.last_used = session.start_time.clone();
-        }
-    }
+    with_usage_db(app, move |conn| {
+        sync_gemini_usage(conn)?;
+        build_gemini_usage_stats(conn, &range)
+    })
+    .await
+}
 
-    // Convert to sorted vectors
-    let mut by_model: Vec<GeminiModelUsage> = model_stats.into_values().collect();
-    by_model.sort_by(|a, b| b.total_cost.partial_cmp(&a.total_cost).unwrap());
+/// Aggregates the ingested Gemini usage within `range`
+fn build_gemini_usage_stats(
+    conn: &Connection,
+    range: &UsageRange,
+) -> Result<GeminiUsageStats, String> {
+    let engine = SessionEngine::Gemini;
+    let total = query_usage_total(conn, engine, range)?;
+
+    let mut by_model: Vec<GeminiModelUsage> = query_usage(conn, engine, range, UsageGroup::Model)?
+        .into_iter()
+        .map(|agg| GeminiModelUsage {
+            model: agg.key,
+            total_cost: agg.cost,
+            total_tokens: agg.total_tokens,
+            input_tokens: agg.input_tokens,
+            output_tokens: agg.output_tokens,
+            cache_creation_tokens: 0,
+            cache_read_tokens: 0,
+            session_count: agg.session_count,
+        })
+        .collect();
+    by_model.sort_by(|a, b| b.total_cost.total_cmp(&a.total_cost));
+
+    let by_date: Vec<GeminiDailyUsage> = query_usage(conn, engine, range, UsageGroup::Date)?
+        .into_iter()
+        .map(|agg| GeminiDailyUsage {
+            date: agg.key,
+            total_cost: agg.cost,
+            total_tokens: agg.total_tokens,
+            models_used: agg.models,
+        })
+        .collect();
+
+    let mut by_project: Vec<GeminiProjectUsage> =
+        query_usage(conn, engine, range, UsageGroup::Project)?
+            .into_iter()
+            .map(|agg| GeminiProjectUsage {
+                project_name: gemini_project_name(&agg.key),
+                project_path: agg.key,
+                total_cost: agg.cost,
+                total_tokens: agg.total_tokens,
+                session_count: agg.session_count,
+                last_used: agg.last_used,
+            })
+            .collect();
+    by_project.sort_by(|a, b| b.total_cost.total_cmp(&a.total_cost));
 
-    let mut by_date: Vec<GeminiDailyUsage> = daily_stats.into_values().collect();
-    by_date.sort_by(|a, b| a.date.cmp(&b.date));
+    let mut meta = ingested_sessions(conn, engine)?;
+    let mut sessions: Vec<GeminiSessionUsage> =
+        query_usage(conn, engine, range, UsageGroup::Session)?
+            .into_iter()
+            .map(|agg| {
+                let file = meta.remove(&agg.key).unwrap_or_default();
+                let project_path = file.project_path.unwrap_or_default();
+                GeminiSessionUsage {
+                    project_hash: project_path
+                        .strip_prefix("project:")
+                        .unwrap_or(&project_path)
+                        .to_string(),
+                    project_path,
+                    model: file
+                        .model
+                        .or_else(|| agg.models.last().cloned())
+                        .unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+                    total_cost: agg.cost,
+                    input_tokens: agg.input_tokens,
+                    output_tokens: agg.output_tokens,
+                    start_time: file.started_at.unwrap_or(agg.first_used),
+                    first_message: file.first_message,
+                    session_id: agg.key,
+                }
+            })
+            .collect();
 
-    let mut by_project: Vec<GeminiProjectUsage> = project_stats.into_values().collect();
-    by_project.sort_by(|a, b| b.total_cost.partial_cmp(&a.total_cost).unwrap());
+    // Sort by start time (newest first)
+    sessions.sort_by(|a, b| b.start_time.cmp(&a.start_time));
 
     Ok(GeminiUsageStats {
-        total_cost,
-        total_tokens: total_input_tokens + total_output_tokens,
-        total_input_tokens,
-        total_output_tokens,
-        total_sessions: filtered_sessions.len() as u64,
+        total_cost: total.cost,
+        total_tokens: total.input_tokens + total.output_tokens,
+        total_input_tokens: total.input_tokens,
+        total_output_tokens: total.output_tokens,
+        total_sessions: total.session_count,
         by_model,
         by_date,
         by_project,
-        sessions: filtered_sessions,
+        sessions,
     })
 }
@@ -0,0 +1,687 @@
+//! Condition language for enhanced hooks
+//!
+//! Conditions are evaluated against the serialized `HookContext`, so the
+//! top-level fields are `event`, `session_id`, `project_path` and `data`.
+//!
+//! ```text
+//! data.tokens > 100000 && event == 'OnContextCompact'
+//! data.tool_name =~ '^(Edit|Write)$' and not data.file_path glob 'target/**'
+//! data.files[0].path glob '*.rs' || data.agent_type in ['reviewer', 'tester']
+//! ```
+//!
+//! - Paths: `a.b.c`, `a[0].b`; missing fields resolve to `null`
+//! - Literals: `'str'`, `"str"`, numbers, `true`, `false`, `null`, `[a, b]`
+//! - Comparison: `==`, `!=`, `>`, `>=`, `<`, `<=` (numeric strings compare as numbers)
+//! - Matching: `=~` / `matches` (regex), `!~`, `glob`, `contains`, `in`
+//! - Boolean: `&&` / `and`, `||` / `or`, `!` / `not`, parentheses
+//!
+//! A bare path is tested for truthiness. Regex and glob operands must be string
+//! literals so that invalid patterns are reported when the condition is parsed.
+//! When the left side of `=~` or `glob` is an array, any matching element wins.
+
+use glob::{MatchOptions, Pattern};
+use regex::Regex;
+use serde_json::Value;
+use std::fmt;
+
+use super::enhanced_hooks::HookContext;
+
+/// A parsed hook condition
+#[derive(Debug, Clone)]
+pub struct Condition {
+    expr: Option<Expr>,
+}
+
+impl Condition {
+    /// Parses a condition; an empty string is a condition that always holds
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        if tokens.is_empty() {
+            return Ok(Self { expr: None });
+        }
+
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            source_len: source.len(),
+        };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(format!(
+                "Unexpected {} at position {}",
+                token.kind, token.offset
+            ));
+        }
+
+        Ok(Self { expr: Some(expr) })
+    }
+
+    /// Evaluates the condition against a hook context
+    pub fn evaluate(&self, context: &HookContext) -> bool {
+        let Some(expr) = &self.expr else {
+            return true;
+        };
+        let root = serde_json::to_value(context).unwrap_or(Value::Null);
+        truthy(&expr.eval(&root))
+    }
+}
+
+/// Parses and evaluates a condition in one step
+pub fn evaluate(source: &str, context: &HookContext) -> Result<bool, String> {
+    Ok(Condition::parse(source)?.evaluate(context))
+}
+
+// ============================================================================
+// Tokenizer
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+    Dot,
+    And,
+    Or,
+    Not,
+    Cmp(CmpOp),
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Ident(name) => write!(f, "'{}'", name),
+            TokenKind::Str(s) => write!(f, "string '{}'", s),
+            TokenKind::Num(n) => write!(f, "number {}", n),
+            TokenKind::LParen => write!(f, "'('"),
+            TokenKind::RParen => write!(f, "')'"),
+            TokenKind::LBracket => write!(f, "'['"),
+            TokenKind::RBracket => write!(f, "']'"),
+            TokenKind::Comma => write!(f, "','"),
+            TokenKind::Dot => write!(f, "'.'"),
+            TokenKind::And => write!(f, "'&&'"),
+            TokenKind::Or => write!(f, "'||'"),
+            TokenKind::Not => write!(f, "'!'"),
+            TokenKind::Cmp(op) => write!(f, "'{}'", op.as_str()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    offset: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Match,
+    NotMatch,
+    Glob,
+    Contains,
+    In,
+}
+
+impl CmpOp {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CmpOp::Eq => "==",
+            CmpOp::Ne => "!=",
+            CmpOp::Gt => ">",
+            CmpOp::Ge => ">=",
+            CmpOp::Lt => "<",
+            CmpOp::Le => "<=",
+            CmpOp::Match => "=~",
+            CmpOp::NotMatch => "!~",
+            CmpOp::Glob => "glob",
+            CmpOp::Contains => "contains",
+            CmpOp::In => "in",
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<(usize, char)> = source.char_indices().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (offset, c) = chars[i];
+        let next = chars.get(i + 1).map(|(_, c)| *c);
+        let mut push = |kind: TokenKind, width: usize| {
+            tokens.push(Token { kind, offset });
+            width
+        };
+
+        i += match (c, next) {
+            (c, _) if c.is_whitespace() => 1,
+            ('(', _) => push(TokenKind::LParen, 1),
+            (')', _) => push(TokenKind::RParen, 1),
+            ('[', _) => push(TokenKind::LBracket, 1),
+            (']', _) => push(TokenKind::RBracket, 1),
+            (',', _) => push(TokenKind::Comma, 1),
+            ('.', Some(d)) if !d.is_ascii_digit() => push(TokenKind::Dot, 1),
+            ('&', Some('&')) => push(TokenKind::And, 2),
+            ('|', Some('|')) => push(TokenKind::Or, 2),
+            ('=', Some('=')) => push(TokenKind::Cmp(CmpOp::Eq), 2),
+            ('=', Some('~')) => push(TokenKind::Cmp(CmpOp::Match), 2),
+            ('!', Some('=')) => push(TokenKind::Cmp(CmpOp::Ne), 2),
+            ('!', Some('~')) => push(TokenKind::Cmp(CmpOp::NotMatch), 2),
+            ('!', _) => push(TokenKind::Not, 1),
+            ('>', Some('=')) => push(TokenKind::Cmp(CmpOp::Ge), 2),
+            ('>', _) => push(TokenKind::Cmp(CmpOp::Gt), 1),
+            ('<', Some('=')) => push(TokenKind::Cmp(CmpOp::Le), 2),
+            ('<', _) => push(TokenKind::Cmp(CmpOp::Lt), 1),
+            ('\'' | '"', _) => {
+                let mut value = String::new();
+                let mut j = i + 1;
+                loop {
+                    match chars.get(j) {
+                        None => {
+                            return Err(format!(
+                                "Unterminated string starting at position {}",
+                                offset
+                            ))
+                        }
+                        Some((_, ch)) if *ch == c => break,
+                        Some((_, '\\')) => {
+                            // Only the quote and the backslash itself are escapes, so
+                            // regex escapes like '\d' pass through untouched
+                            match chars.get(j + 1).map(|(_, ch)| *ch) {
+                                Some(esc) if esc == c || esc == '\\' => {
+                                    value.push(esc);
+                                    j += 1;
+                                }
+                                _ => value.push('\\'),
+                            }
+                        }
+                        Some((_, ch)) => value.push(*ch),
+                    }
+                    j += 1;
+                }
+                push(TokenKind::Str(value), j + 1 - i)
+            }
+            (c, _) if c.is_ascii_digit() || c == '-' || c == '.' => {
+                let mut j = i + 1;
+                while chars
+                    .get(j)
+                    .is_some_and(|(_, ch)| ch.is_ascii_digit() || *ch == '.' || *ch == '_')
+                {
+                    j += 1;
+                }
+                let end = chars.get(j).map(|(o, _)| *o).unwrap_or(source.len());
+                let text = source[offset..end].replace('_', "");
+                let number = text
+                    .parse::<f64>()
+                    .map_err(|_| format!("Invalid number '{}' at position {}", text, offset))?;
+                push(TokenKind::Num(number), j - i)
+            }
+            (c, _) if c.is_alphabetic() || c == '_' || c == '$' => {
+                let mut j = i + 1;
+                while chars
+                    .get(j)
+                    .is_some_and(|(_, ch)| ch.is_alphanumeric() || *ch == '_' || *ch == '-')
+                {
+                    j += 1;
+                }
+                let end = chars.get(j).map(|(o, _)| *o).unwrap_or(source.len());
+                let word = &source[offset..end];
+                let kind = match word {
+                    "and" => TokenKind::And,
+                    "or" => TokenKind::Or,
+                    "not" => TokenKind::Not,
+                    "matches" => TokenKind::Cmp(CmpOp::Match),
+                    "glob" => TokenKind::Cmp(CmpOp::Glob),
+                    "contains" => TokenKind::Cmp(CmpOp::Contains),
+                    "in" => TokenKind::Cmp(CmpOp::In),
+                    _ => TokenKind::Ident(word.to_string()),
+                };
+                push(kind, j - i)
+            }
+            (c, _) => {
+                return Err(format!(
+                    "Unexpected character '{}' at position {}",
+                    c, offset
+                ))
+            }
+        };
+    }
+
+    Ok(tokens)
+}
+
+// ============================================================================
+// Parser
+// ============================================================================
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Literal(Value),
+    Path(Vec<Segment>),
+    List(Vec<Expr>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, CmpOp, Box<Expr>),
+    Regex(Box<Expr>, Regex, bool),
+    Glob(Box<Expr>, Pattern),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    source_len: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<Token, String> {
+        let token = self.tokens.get(self.pos).cloned().ok_or_else(|| {
+            format!(
+                "Unexpected end of condition at position {}",
+                self.source_len
+            )
+        })?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        if self.peek().is_some_and(|t| &t.kind == kind) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<(), String> {
+        let token = self.next().map_err(|_| {
+            format!(
+                "Expected {} before end of condition at position {}",
+                kind, self.source_len
+            )
+        })?;
+        if token.kind == kind {
+            Ok(())
+        } else {
+            Err(format!(
+                "Expected {} but found {} at position {}",
+                kind, token.kind, token.offset
+            ))
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_and()?;
+        while self.eat(&TokenKind::Or) {
+            let right = self.parse_and()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_unary()?;
+        while self.eat(&TokenKind::And) {
+            let right = self.parse_unary()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, String> {
+        if self.eat(&TokenKind::Not) {
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, String> {
+        let left = self.parse_operand()?;
+        let op = match self.peek() {
+            Some(Token {
+                kind: TokenKind::Cmp(op),
+                ..
+            }) => *op,
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+
+        match op {
+            CmpOp::Match | CmpOp::NotMatch | CmpOp::Glob => {
+                let token = self.next()?;
+                let TokenKind::Str(pattern) = token.kind else {
+                    return Err(format!(
+                        "'{}' expects a string pattern but found {} at position {}",
+                        op.as_str(),
+                        token.kind,
+                        token.offset
+                    ));
+                };
+                if op == CmpOp::Glob {
+                    let glob = Pattern::new(&pattern).map_err(|e| {
+                        format!(
+                            "Invalid glob '{}' at position {}: {}",
+                            pattern, token.offset, e
+                        )
+                    })?;
+                    Ok(Expr::Glob(Box::new(left), glob))
+                } else {
+                    let regex = Regex::new(&pattern).map_err(|e| {
+                        format!(
+                            "Invalid regex '{}' at position {}: {}",
+                            pattern, token.offset, e
+                        )
+                    })?;
+                    Ok(Expr::Regex(Box::new(left), regex, op == CmpOp::NotMatch))
+                }
+            }
+            _ => {
+                let right = self.parse_operand()?;
+                Ok(Expr::Compare(Box::new(left), op, Box::new(right)))
+            }
+        }
+    }
+
+    fn parse_operand(&mut self) -> Result<Expr, String> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Str(s) => Ok(Expr::Literal(Value::String(s))),
+            TokenKind::Num(n) => Ok(Expr::Literal(
+                serde_json::Number::from_f64(n)
+                    .map(Value::Number)
+                    .unwrap_or(Value::Null),
+            )),
+            TokenKind::LParen => {
+                let expr = self.parse_or()?;
+                self.expect(TokenKind::RParen)?;
+                Ok(expr)
+            }
+            TokenKind::LBracket => {
+                let mut items = Vec::new();
+                if !self.eat(&TokenKind::RBracket) {
+                    loop {
+                        items.push(self.parse_operand()?);
+                        if self.eat(&TokenKind::RBracket) {
+                            break;
+                        }
+                        self.expect(TokenKind::Comma)?;
+                    }
+                }
+                Ok(Expr::List(items))
+            }
+            TokenKind::Ident(name) => match name.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                _ => self.parse_path(name),
+            },
+            kind => Err(format!(
+                "Expected a value but found {} at position {}",
+                kind, token.offset
+            )),
+        }
+    }
+
+    fn parse_path(&mut self, first: String) -> Result<Expr, String> {
+        let mut segments = vec![Segment::Key(first)];
+        loop {
+            if self.eat(&TokenKind::Dot) {
+                let token = self.next()?;
+                match token.kind {
+                    TokenKind::Ident(name) => segments.push(Segment::Key(name)),
+                    // Word operators are still valid field names after a dot
+                    TokenKind::Cmp(op) if op.as_str().chars().all(char::is_alphabetic) => {
+                        segments.push(Segment::Key(op.as_str().to_string()))
+                    }
+                    kind => {
+                        return Err(format!(
+                            "Expected a field name but found {} at position {}",
+                            kind, token.offset
+                        ))
+                    }
+                }
+            } else if self.eat(&TokenKind::LBracket) {
+                let token = self.next()?;
+                match token.kind {
+                    TokenKind::Num(n) if n >= 0.0 && n.fract() == 0.0 => {
+                        segments.push(Segment::Index(n as usize))
+                    }
+                    TokenKind::Str(key) => segments.push(Segment::Key(key)),
+                    kind => {
+                        return Err(format!(
+                            "Expected an index or quoted key but found {} at position {}",
+                            kind, token.offset
+                        ))
+                    }
+                }
+                self.expect(TokenKind::RBracket)?;
+            } else {
+                return Ok(Expr::Path(segments));
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Evaluation
+// ============================================================================
+
+impl Expr {
+    fn eval(&self, root: &Value) -> Value {
+        match self {
+            Expr::Literal(value) => value.clone(),
+            Expr::Path(segments) => segments
+                .iter()
+                .try_fold(root, |value, segment| match segment {
+                    Segment::Key(key) => value.get(key),
+                    Segment::Index(idx) => value.get(*idx),
+                })
+                .cloned()
+                .unwrap_or(Value::Null),
+            Expr::List(items) => Value::Array(items.iter().map(|e| e.eval(root)).collect()),
+            Expr::Not(inner) => Value::Bool(!truthy(&inner.eval(root))),
+            Expr::And(left, right) => {
+                Value::Bool(truthy(&left.eval(root)) && truthy(&right.eval(root)))
+            }
+            Expr::Or(left, right) => {
+                Value::Bool(truthy(&left.eval(root)) || truthy(&right.eval(root)))
+            }
+            Expr::Compare(left, op, right) => {
+                Value::Bool(compare(&left.eval(root), *op, &right.eval(root)))
+            }
+            Expr::Regex(target, regex, negate) => {
+                let matched = any_string(&target.eval(root), |s| regex.is_match(s));
+                Value::Bool(matched != *negate)
+            }
+            Expr::Glob(target, pattern) => {
+                Value::Bool(any_string(&target.eval(root), |s| glob_match(pattern, s)))
+            }
+        }
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn loose_eq(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::String(a), Value::String(b)) => a == b,
+        (Value::Number(_), _) | (_, Value::Number(_)) => {
+            matches!((as_number(left), as_number(right)), (Some(a), Some(b)) if a == b)
+        }
+        _ => left == right,
+    }
+}
+
+fn compare(left: &Value, op: CmpOp, right: &Value) -> bool {
+    match op {
+        CmpOp::Eq => loose_eq(left, right),
+        CmpOp::Ne => !loose_eq(left, right),
+        CmpOp::Gt | CmpOp::Ge | CmpOp::Lt | CmpOp::Le => {
+            let ordering = match (as_number(left), as_number(right)) {
+                (Some(a), Some(b)) => a.partial_cmp(&b),
+                _ => match (left, right) {
+                    (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+                    _ => None,
+                },
+            };
+            let Some(ordering) = ordering else {
+                return false;
+            };
+            match op {
+                CmpOp::Gt => ordering.is_gt(),
+                CmpOp::Ge => ordering.is_ge(),
+                CmpOp::Lt => ordering.is_lt(),
+                _ => ordering.is_le(),
+            }
+        }
+        CmpOp::Contains => contains(left, right),
+        CmpOp::In => contains(right, left),
+        // Pattern operators are compiled into their own nodes by the parser
+        CmpOp::Match | CmpOp::NotMatch | CmpOp::Glob => false,
+    }
+}
+
+fn contains(haystack: &Value, needle: &Value) -> bool {
+    match haystack {
+        Value::String(s) => match needle {
+            Value::String(n) => s.contains(n.as_str()),
+            Value::Number(n) => s.contains(&n.to_string()),
+            _ => false,
+        },
+        Value::Array(items) => items.iter().any(|item| loose_eq(item, needle)),
+        Value::Object(map) => needle.as_str().is_some_and(|key| map.contains_key(key)),
+        _ => false,
+    }
+}
+
+fn any_string(value: &Value, mut test: impl FnMut(&str) -> bool) -> bool {
+    match value {
+        Value::String(s) => test(s),
+        Value::Array(items) => items
+            .iter()
+            .any(|item| item.as_str().is_some_and(&mut test)),
+        _ => false,
+    }
+}
+
+/// Matches a path against a glob; patterns without a `/` also match the file name
+pub(crate) fn glob_match(pattern: &Pattern, path: &str) -> bool {
+    let path = path.replace('\\', "/");
+    let options = MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+    if pattern.matches_with(&path, options) {
+        return true;
+    }
+    !pattern.as_str().contains('/')
+        && path
+            .rsplit('/')
+            .next()
+            .is_some_and(|name| pattern.matches_with(name, options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> HookContext {
+        HookContext {
+            event: "PostToolUse".to_string(),
+            session_id: "abc".to_string(),
+            project_path: "/work/app".to_string(),
+            data: serde_json::json!({
+                "tokens": 120000,
+                "tool_name": "Edit",
+                "usage": { "ratio": "0.85" },
+                "files": [{ "path": "src\\commands\\hooks.rs" }, { "path": "README.md" }],
+                "changed": ["src/main.rs", "target/debug/app"],
+                "agent_type": "code-reviewer"
+            }),
+        }
+    }
+
+    #[test]
+    fn evaluates_paths_comparisons_and_patterns() {
+        let ctx = context();
+        let cases = [
+            ("data.tokens > 100000", true),
+            ("data.tokens >= 120_000 && event == 'PostToolUse'", true),
+            ("data.usage.ratio > 0.8 and data.usage.ratio < 0.9", true),
+            ("data.missing.deep == null && !data.missing", true),
+            ("data.files[0].path glob 'src/**/*.rs'", true),
+            ("data.files[1].path glob '*.rs'", false),
+            ("data.changed glob 'target/**'", true),
+            ("data.tool_name =~ '^(Edit|Write)$' || false", true),
+            ("data.tool_name !~ 'Bash'", true),
+            (r#"session_id matches "^\w+$""#, true),
+            ("data.agent_type in ['reviewer', 'code-reviewer']", true),
+            (
+                "data.changed contains 'src/main.rs' and not (data.tokens < 5)",
+                true,
+            ),
+            ("event == 'Stop' or data.tokens < 100", false),
+            ("", true),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(evaluate(source, &ctx), Ok(expected), "{}", source);
+        }
+    }
+
+    #[test]
+    fn reports_parse_errors_with_positions() {
+        let ctx = context();
+        for (source, fragment) in [
+            ("data.tokens >", "end of condition"),
+            ("data.tokens > 1 1", "position 16"),
+            ("event == 'Stop", "Unterminated string"),
+            ("data.tool_name =~ '('", "Invalid regex"),
+            ("data.tool_name =~ data.x", "expects a string pattern"),
+            ("(event == 'Stop'", "Expected ')'"),
+            ("event # 1", "Unexpected character '#'"),
+        ] {
+            let err = evaluate(source, &ctx).unwrap_err();
+            assert!(err.contains(fragment), "{}: {}", source, err);
+        }
+    }
+}
@@ -0,0 +1,347 @@
+//! Polling file watcher that fires `OnFileChange` hooks
+//!
+//! Every project with `OnFileChange` hooks in its persisted config gets a
+//! background task that snapshots file mtimes and sizes on an interval. Once
+//! a change is seen the watcher keeps rescanning until the files stop changing,
+//! then fires one hook chain for the whole batch with the session id of the
+//! Claude run working in that project. The snapshot is retaken after the chain
+//! finishes so files written by the hooks themselves don't retrigger it.
+
+use glob::Pattern;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager};
+use walkdir::WalkDir;
+
+use super::claude::normalize_path_for_comparison;
+use super::enhanced_hooks::{HookContext, HookEvent, HookManager, ProjectHooksConfig};
+use super::hook_condition::glob_match;
+use crate::process::{ProcessRegistry, ProcessRegistryState};
+
+/// How often each watched project is rescanned
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Quiet period a batch of changes must settle for before the hooks fire
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Fire anyway after this many rescans of files that keep changing
+const MAX_DEBOUNCE_ROUNDS: usize = 10;
+
+/// Stop scanning a project past this many files
+const MAX_WATCHED_FILES: usize = 50_000;
+
+/// Directories never worth watching
+const IGNORED_DIRS: &[&str] = &[
+    ".git",
+    "node_modules",
+    "target",
+    "dist",
+    "build",
+    ".next",
+    ".venv",
+    "__pycache__",
+    ".idea",
+    ".vscode",
+];
+
+/// Stop flags of running watchers, keyed by normalized project path
+#[derive(Default)]
+pub struct FileWatcherState(pub Mutex<HashMap<String, Arc<AtomicBool>>>);
+
+type Snapshot = HashMap<PathBuf, (Option<SystemTime>, u64)>;
+
+/// Restarts the watchers so exactly the projects with `OnFileChange` hooks are watched
+pub fn sync_watchers(app: &AppHandle) {
+    let (Some(manager), Some(state)) = (
+        app.try_state::<HookManager>(),
+        app.try_state::<FileWatcherState>(),
+    ) else {
+        return;
+    };
+
+    let wanted: HashMap<String, ProjectHooksConfig> = manager
+        .projects_with(&HookEvent::OnFileChange)
+        .into_iter()
+        .map(|config| (normalize_path_for_comparison(&config.project_path), config))
+        .collect();
+
+    let Ok(mut watchers) = state.0.lock() else {
+        return;
+    };
+
+    // Restart everything so edited ignore patterns take effect
+    for stop in watchers.values() {
+        stop.store(true, Ordering::Relaxed);
+    }
+    watchers.clear();
+
+    for (key, config) in wanted {
+        let stop = Arc::new(AtomicBool::new(false));
+        watchers.insert(key, stop.clone());
+        log::info!("[HookWatcher] Watching {}", config.project_path);
+        tauri::async_runtime::spawn(watch_project(
+            app.clone(),
+            manager.inner().clone(),
+            config,
+            stop,
+        ));
+    }
+}
+
+async fn watch_project(
+    app: AppHandle,
+    manager: HookManager,
+    config: ProjectHooksConfig,
+    stop: Arc<AtomicBool>,
+) {
+    let root = PathBuf::from(&config.project_path);
+    let ignore: Arc<Vec<Pattern>> = Arc::new(
+        config
+            .watch_ignore
+            .iter()
+            .filter_map(|p| Pattern::new(p).ok())
+            .collect(),
+    );
+
+    let mut previous = scan(&root, &ignore).await;
+    while !stop.load(Ordering::Relaxed) {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        if stop.load(Ordering::Relaxed) {
+            break;
+        }
+        if !root.is_dir() {
+            continue;
+        }
+
+        let current = scan(&root, &ignore).await;
+        if current == previous {
+            continue;
+        }
+        let current = settle(&root, &ignore, current, DEBOUNCE_INTERVAL).await;
+        let changes = diff(&previous, &current);
+        if changes.is_empty() {
+            previous = current;
+            continue;
+        }
+
+        let session_id = app
+            .try_state::<ProcessRegistryState>()
+            .map(|registry| active_session_id(&registry.0, &config.project_path))
+            .unwrap_or_default();
+        let context = change_context(&config.project_path, session_id, &changes);
+        if let Err(e) = manager.trigger(HookEvent::OnFileChange, context).await {
+            log::error!("[HookWatcher] OnFileChange hooks failed: {}", e);
+        }
+
+        previous = scan(&root, &ignore).await;
+    }
+
+    log::info!("[HookWatcher] Stopped watching {}", config.project_path);
+}
+
+/// Rescans every `interval` until two scans agree, so a burst of writes
+/// fires the hooks once
+async fn settle(
+    root: &Path,
+    ignore: &Arc<Vec<Pattern>>,
+    mut current: Snapshot,
+    interval: Duration,
+) -> Snapshot {
+    for _ in 0..MAX_DEBOUNCE_ROUNDS {
+        tokio::time::sleep(interval).await;
+        let next = scan(root, ignore).await;
+        if next == current {
+            break;
+        }
+        current = next;
+    }
+    current
+}
+
+/// Session id of the latest Claude run in the project, empty when none runs
+fn active_session_id(registry: &ProcessRegistry, project_path: &str) -> String {
+    let project = normalize_path_for_comparison(project_path);
+    registry
+        .get_running_claude_sessions()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|info| normalize_path_for_comparison(&info.project_path) == project)
+        .max_by_key(|info| (info.started_at, info.run_id))
+        .and_then(|info| match info.process_type {
+            crate::process::ProcessType::ClaudeSession { session_id, .. } => Some(session_id),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// Hook context for one batch of changes
+fn change_context(
+    project_path: &str,
+    session_id: String,
+    changes: &[(String, &'static str)],
+) -> HookContext {
+    let files: Vec<&str> = changes.iter().map(|(path, _)| path.as_str()).collect();
+    let data = serde_json::json!({
+        "file_path": files.first(),
+        "files": files,
+        "file_count": files.len(),
+        "changes": changes
+            .iter()
+            .map(|(path, kind)| serde_json::json!({ "path": path, "kind": kind }))
+            .collect::<Vec<_>>(),
+    });
+    HookContext {
+        event: HookEvent::OnFileChange.as_str().to_string(),
+        session_id,
+        project_path: project_path.to_string(),
+        data,
+    }
+}
+
+async fn scan(root: &Path, ignore: &Arc<Vec<Pattern>>) -> Snapshot {
+    let root = root.to_path_buf();
+    let ignore = ignore.clone();
+    tokio::task::spawn_blocking(move || snapshot(&root, &ignore))
+        .await
+        .unwrap_or_default()
+}
+
+fn snapshot(root: &Path, ignore: &[Pattern]) -> Snapshot {
+    let mut files = Snapshot::new();
+    let walker = WalkDir::new(root).into_iter().filter_entry(|entry| {
+        let name = entry.file_name().to_string_lossy();
+        !(entry.depth() > 0 && entry.file_type().is_dir() && IGNORED_DIRS.contains(&name.as_ref()))
+    });
+
+    for entry in walker.filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Ok(relative) = entry.path().strip_prefix(root) else {
+            continue;
+        };
+        let relative_str = relative.to_string_lossy();
+        if ignore.iter().any(|p| glob_match(p, &relative_str)) {
+            continue;
+        }
+        if files.len() >= MAX_WATCHED_FILES {
+            log::warn!(
+                "[HookWatcher] {} has more than {} files, ignoring the rest",
+                root.display(),
+                MAX_WATCHED_FILES
+            );
+            break;
+        }
+        let metadata = entry.metadata().ok();
+        files.insert(
+            relative.to_path_buf(),
+            (
+                metadata.as_ref().and_then(|m| m.modified().ok()),
+                metadata.map(|m| m.len()).unwrap_or(0),
+            ),
+        );
+    }
+
+    files
+}
+
+/// Changed files as `(relative path, "created" | "modified" | "deleted")`, sorted by path
+fn diff(previous: &Snapshot, current: &Snapshot) -> Vec<(String, &'static str)> {
+    let mut changes: Vec<(String, &'static str)> = current
+        .iter()
+        .filter_map(|(path, stamp)| match previous.get(path) {
+            None => Some((path, "created")),
+            Some(old) if old != stamp => Some((path, "modified")),
+            _ => None,
+        })
+        .chain(
+            previous
+                .keys()
+                .filter(|path| !current.contains_key(*path))
+                .map(|path| (path, "deleted")),
+        )
+        .map(|(path, kind)| (path.to_string_lossy().replace('\\', "/"), kind))
+        .collect();
+    changes.sort();
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[tokio::test]
+    async fn bursts_of_changes_settle_into_one_batch() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        let ignore = Arc::new(vec![Pattern::new("*.log").unwrap()]);
+        fs::write(root.join("keep.rs"), "fn main() {}").unwrap();
+        let previous = scan(&root, &ignore).await;
+
+        fs::write(root.join("a.rs"), "a").unwrap();
+        fs::write(root.join("temp.rs"), "scratch").unwrap();
+        fs::write(root.join("build.log"), "ignored").unwrap();
+        let current = scan(&root, &ignore).await;
+
+        // Writes landing while the batch settles join it
+        let writer_root = root.clone();
+        let writer = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            fs::write(writer_root.join("b.rs"), "b").unwrap();
+            fs::write(writer_root.join("keep.rs"), "fn main() { run() }").unwrap();
+            fs::remove_file(writer_root.join("temp.rs")).unwrap();
+        });
+        let settled = settle(&root, &ignore, current, Duration::from_millis(200)).await;
+        writer.await.unwrap();
+
+        assert_eq!(
+            diff(&previous, &settled),
+            vec![
+                ("a.rs".to_string(), "created"),
+                ("b.rs".to_string(), "created"),
+                ("keep.rs".to_string(), "modified"),
+            ]
+        );
+    }
+
+    #[test]
+    fn hooks_receive_the_session_working_in_the_project() {
+        let registry = ProcessRegistry::new();
+        let start = |session_id: &str, project: &str| {
+            registry
+                .register_claude_session(
+                    session_id.to_string(),
+                    0,
+                    project.to_string(),
+                    "task".to_string(),
+                    "sonnet".to_string(),
+                )
+                .unwrap()
+        };
+        start("older", "/work/app");
+        start("other-project", "/work/lib");
+        start("", "/work/app");
+        let latest = start("latest", "/work/app");
+
+        assert_eq!(active_session_id(&registry, "/work/app"), "latest");
+        registry.unregister_process(latest).unwrap();
+        assert_eq!(active_session_id(&registry, "/work/app"), "older");
+        assert_eq!(active_session_id(&registry, "/work/none"), "");
+
+        let changes = vec![
+            ("src/a.rs".to_string(), "modified"),
+            ("src/b.rs".to_string(), "deleted"),
+        ];
+        let context = change_context("/work/app", "older".to_string(), &changes);
+        assert_eq!(context.event, HookEvent::OnFileChange.as_str());
+        assert_eq!(context.session_id, "older");
+        assert_eq!(context.project_path, "/work/app");
+        assert_eq!(context.data["file_path"], "src/a.rs");
+        assert_eq!(context.data["file_count"], 2);
+        assert_eq!(context.data["changes"][1]["kind"], "deleted");
+    }
+}
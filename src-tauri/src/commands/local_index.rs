@@ -0,0 +1,740 @@
+//! Local Code Index
+//!
+//! Offline alternative to the acemcp sidecar for prompt enhancement. Source
+//! files of a project are cut into overlapping line windows whose term counts
+//! are kept in `~/.anycode/local_index/<name>-<hash>.json`. The indexes of the
+//! last few projects stay in memory; every search first re-indexes the files
+//! whose mtime or size changed since the last run and writes the file back
+//! only when something changed, so only the first search of a project reads
+//! all of it.
+//!
+//! Chunks are ranked with BM25 over identifier terms: whole identifiers, their
+//! camelCase / snake_case parts, the words of the file path and bigrams of CJK
+//! text. A query term missing from a project still matches close spellings
+//! through a trigram index over the vocabulary (`enhance` → `enhancement`).
+//! The results use the `Path:` snippet format of the sidecar, so the rest of
+//! the enhancement pipeline treats both backends the same.
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+use walkdir::WalkDir;
+
+use super::acemcp::ContextSnippet;
+use super::simple_git;
+
+/// Bumped whenever the tokenizer or the chunking changes, which discards
+/// indexes written by older versions
+const INDEX_VERSION: u32 = 1;
+const CHUNK_LINES: usize = 40;
+const CHUNK_OVERLAP: usize = 10;
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+/// Chunks returned per query round
+const RESULTS_PER_QUERY: usize = 5;
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+/// Minimum trigram similarity of a vocabulary term to a query term
+const FUZZY_MIN_SIMILARITY: f64 = 0.5;
+const FUZZY_MAX_EXPANSIONS: usize = 5;
+/// Project indexes kept in memory between searches
+const MAX_CACHED_INDEXES: usize = 4;
+
+/// File extensions indexed, the same set the sidecar uploads plus a few
+/// common frontend and mobile languages
+const SOURCE_EXTENSIONS: &[&str] = &[
+    "py", "js", "ts", "jsx", "tsx", "mjs", "cjs", "vue", "svelte", "java", "kt", "kts", "scala",
+    "go", "rs", "cpp", "cc", "c", "h", "hpp", "cs", "rb", "php", "swift", "dart", "lua", "md",
+    "txt", "json", "yaml", "yml", "toml", "xml", "html", "css", "scss", "less", "sql", "sh",
+    "bash", "ps1",
+];
+
+/// Directories skipped when the project is not a git repository
+const EXCLUDED_DIRS: &[&str] = &[
+    ".git",
+    ".svn",
+    ".hg",
+    ".idea",
+    ".vscode",
+    "node_modules",
+    "dist",
+    "build",
+    "target",
+    "out",
+    "coverage",
+    "vendor",
+    "__pycache__",
+    "venv",
+    ".venv",
+    ".next",
+    ".nuxt",
+    ".tox",
+];
+
+/// Loaded indexes by index file, least recently used first. The lock also
+/// serialises refreshes between background pre-indexing and searches.
+static INDEXES: Lazy<Mutex<Vec<(PathBuf, LocalIndex)>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+// ============================================================================
+// Index
+// ============================================================================
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct IndexData {
+    version: u32,
+    /// Indexed files by path relative to the project root, with `/` separators
+    files: BTreeMap<String, IndexedFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexedFile {
+    /// Modification time in milliseconds since the epoch
+    mtime: u64,
+    size: u64,
+    chunks: Vec<Chunk>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Chunk {
+    /// 1-based, inclusive
+    start_line: usize,
+    end_line: usize,
+    /// Number of terms in the chunk
+    len: u32,
+    terms: HashMap<String, u32>,
+}
+
+/// A chunk matching a query
+#[derive(Debug, Clone)]
+pub struct LocalSearchHit {
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub score: f64,
+}
+
+/// Files changed by a refresh
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RefreshStats {
+    pub indexed: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+}
+
+/// The index of one project, refreshed against the files on disk
+pub struct LocalIndex {
+    root: PathBuf,
+    data: IndexData,
+    /// Number of chunks containing each term
+    doc_freq: HashMap<String, usize>,
+    /// Vocabulary terms by trigram, for fuzzy matching
+    trigrams: HashMap<String, Vec<String>>,
+    chunk_count: usize,
+    avg_chunk_len: f64,
+}
+
+fn get_index_dir() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Failed to get home directory")?;
+    Ok(home.join(".anycode").join("local_index"))
+}
+
+fn index_file_path(project_path: &str, index_dir: &Path) -> PathBuf {
+    let name = Path::new(project_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "project".to_string());
+    let digest = format!(
+        "{:x}",
+        md5::compute(super::claude::normalize_path_for_comparison(project_path))
+    );
+    index_dir.join(format!("{}-{}.json", name, &digest[..8]))
+}
+
+impl LocalIndex {
+    /// Brings the index of `project_path` up to date and runs `f` on it
+    pub fn with<T>(
+        project_path: &str,
+        f: impl FnOnce(&LocalIndex, RefreshStats) -> T,
+    ) -> Result<T, String> {
+        Self::with_in(project_path, &get_index_dir()?, f)
+    }
+
+    fn with_in<T>(
+        project_path: &str,
+        index_dir: &Path,
+        f: impl FnOnce(&LocalIndex, RefreshStats) -> T,
+    ) -> Result<T, String> {
+        let root = PathBuf::from(project_path);
+        if !root.is_dir() {
+            return Err(format!("Project path does not exist: {}", project_path));
+        }
+
+        let mut indexes = INDEXES.lock().unwrap_or_else(|e| e.into_inner());
+        let index_path = index_file_path(project_path, index_dir);
+        let mut index = match indexes.iter().position(|(path, _)| *path == index_path) {
+            Some(pos) => indexes.remove(pos).1,
+            None => Self::new(root.clone(), load_index_data(&index_path)),
+        };
+        let stats = refresh(&root, &mut index.data);
+
+        let saved = if stats.indexed > 0 || stats.removed > 0 {
+            index = Self::new(root, index.data);
+            save_index_data(&index.data, &index_path)
+        } else {
+            Ok(())
+        };
+        log::info!(
+            "[Local Index] {}: {} file(s) indexed, {} unchanged, {} removed",
+            project_path,
+            stats.indexed,
+            stats.unchanged,
+            stats.removed
+        );
+
+        indexes.push((index_path, index));
+        if indexes.len() > MAX_CACHED_INDEXES {
+            indexes.remove(0);
+        }
+        saved?;
+        let (_, index) = indexes.last().expect("index was just cached");
+        Ok(f(index, stats))
+    }
+
+    fn new(root: PathBuf, data: IndexData) -> Self {
+        let mut doc_freq: HashMap<String, usize> = HashMap::new();
+        let mut chunk_count = 0;
+        let mut total_len = 0u64;
+        for chunk in data.files.values().flat_map(|f| &f.chunks) {
+            chunk_count += 1;
+            total_len += u64::from(chunk.len);
+            for term in chunk.terms.keys() {
+                *doc_freq.entry(term.clone()).or_default() += 1;
+            }
+        }
+
+        let mut trigrams: HashMap<String, Vec<String>> = HashMap::new();
+        for term in doc_freq.keys() {
+            for gram in term_trigrams(term) {
+                trigrams.entry(gram).or_default().push(term.clone());
+            }
+        }
+
+        Self {
+            root,
+            data,
+            doc_freq,
+            trigrams,
+            chunk_count,
+            avg_chunk_len: if chunk_count == 0 {
+                0.0
+            } else {
+                total_len as f64 / chunk_count as f64
+            },
+        }
+    }
+
+    /// Query terms with their weights: the exact terms, plus close spellings
+    /// from the vocabulary at a weight of their similarity
+    fn expand_query(&self, query: &str) -> HashMap<String, f64> {
+        let mut weights = HashMap::new();
+        for term in tokenize(query) {
+            if self.doc_freq.contains_key(&term) {
+                weights.insert(term.clone(), 1.0);
+            }
+            if term.chars().count() < 4 {
+                continue;
+            }
+
+            let grams: HashSet<String> = term_trigrams(&term).into_iter().collect();
+            let mut shared: HashMap<&str, usize> = HashMap::new();
+            for gram in &grams {
+                for candidate in self.trigrams.get(gram).into_iter().flatten() {
+                    *shared.entry(candidate.as_str()).or_default() += 1;
+                }
+            }
+
+            let mut similar: Vec<(&str, f64)> = shared
+                .into_iter()
+                .filter(|(candidate, _)| *candidate != term)
+                .map(|(candidate, count)| {
+                    let candidate_grams = term_trigrams(candidate).len();
+                    let union = grams.len() + candidate_grams - count;
+                    (candidate, count as f64 / union as f64)
+                })
+                .filter(|(_, similarity)| *similarity >= FUZZY_MIN_SIMILARITY)
+                .collect();
+            similar.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+            for (candidate, similarity) in similar.into_iter().take(FUZZY_MAX_EXPANSIONS) {
+                let weight = weights.entry(candidate.to_string()).or_insert(0.0);
+                *weight = f64::max(*weight, similarity);
+            }
+        }
+        weights
+    }
+
+    /// Chunks ranked by BM25 score for `query`, best first
+    pub fn search(&self, query: &str, limit: usize) -> Vec<LocalSearchHit> {
+        let weights = self.expand_query(query);
+        if weights.is_empty() || self.chunk_count == 0 {
+            return Vec::new();
+        }
+
+        let n = self.chunk_count as f64;
+        let idf: HashMap<&str, f64> = weights
+            .keys()
+            .map(|term| {
+                let df = self.doc_freq.get(term).copied().unwrap_or(0) as f64;
+                (term.as_str(), (1.0 + (n - df + 0.5) / (df + 0.5)).ln())
+            })
+            .collect();
+
+        let mut hits = Vec::new();
+        for (path, file) in &self.data.files {
+            for chunk in &file.chunks {
+                let norm = BM25_K1
+                    * (1.0 - BM25_B + BM25_B * f64::from(chunk.len) / self.avg_chunk_len.max(1.0));
+                let score: f64 = weights
+                    .iter()
+                    .filter_map(|(term, weight)| {
+                        let tf = f64::from(*chunk.terms.get(term)?);
+                        Some(weight * idf[term.as_str()] * tf * (BM25_K1 + 1.0) / (tf + norm))
+                    })
+                    .sum();
+                if score > 0.0 {
+                    hits.push(LocalSearchHit {
+                        path: path.clone(),
+                        start_line: chunk.start_line,
+                        end_line: chunk.end_line,
+                        score,
+                    });
+                }
+            }
+        }
+
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits.truncate(limit);
+        hits
+    }
+
+    /// Source lines covered by a hit
+    fn read_hit(&self, hit: &LocalSearchHit) -> Option<String> {
+        let content = fs::read_to_string(self.root.join(&hit.path)).ok()?;
+        let code: Vec<&str> = content
+            .lines()
+            .skip(hit.start_line - 1)
+            .take(hit.end_line + 1 - hit.start_line)
+            .collect();
+        Some(code.join("\n"))
+    }
+}
+
+// ============================================================================
+// Persistence and refresh
+// ============================================================================
+
+/// A missing, unreadable or outdated index starts over empty
+fn load_index_data(path: &Path) -> IndexData {
+    let data = fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<IndexData>(&bytes).ok());
+    match data {
+        Some(data) if data.version == INDEX_VERSION => data,
+        _ => IndexData {
+            version: INDEX_VERSION,
+            files: BTreeMap::new(),
+        },
+    }
+}
+
+fn save_index_data(data: &IndexData, path: &Path) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create index directory: {}", e))?;
+    }
+    let bytes =
+        serde_json::to_vec(data).map_err(|e| format!("Failed to serialize index: {}", e))?;
+    // Written next to the index and renamed so a crash never leaves half a file
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, bytes).map_err(|e| format!("Failed to write index: {}", e))?;
+    fs::rename(&tmp_path, path).map_err(|e| format!("Failed to save index: {}", e))
+}
+
+/// Re-indexes new and modified files and drops deleted ones
+fn refresh(root: &Path, data: &mut IndexData) -> RefreshStats {
+    let mut stats = RefreshStats::default();
+    let mut present = HashSet::new();
+
+    for rel_path in list_source_files(root) {
+        let Ok(metadata) = fs::metadata(root.join(&rel_path)) else {
+            continue;
+        };
+        if !metadata.is_file() || metadata.len() > MAX_FILE_BYTES {
+            continue;
+        }
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let size = metadata.len();
+
+        if data
+            .files
+            .get(&rel_path)
+            .is_some_and(|f| f.mtime == mtime && f.size == size)
+        {
+            present.insert(rel_path);
+            stats.unchanged += 1;
+            continue;
+        }
+
+        // Binary and non UTF-8 files are left out
+        match fs::read_to_string(root.join(&rel_path)) {
+            Ok(content) if !content.contains('\0') => {
+                let chunks = chunk_file(&rel_path, &content);
+                data.files.insert(
+                    rel_path.clone(),
+                    IndexedFile {
+                        mtime,
+                        size,
+                        chunks,
+                    },
+                );
+                present.insert(rel_path);
+                stats.indexed += 1;
+            }
+            _ => {}
+        }
+    }
+
+    let before = data.files.len();
+    data.files.retain(|path, _| present.contains(path));
+    stats.removed = before - data.files.len();
+    stats
+}
+
+/// Source files of a project relative to its root. Git repositories list
+/// their tracked and untracked files so `.gitignore` is honoured; other
+/// directories are walked without [`EXCLUDED_DIRS`].
+fn list_source_files(root: &Path) -> Vec<String> {
+    let root_str = root.to_string_lossy();
+    let files: Vec<String> = match simple_git::git_output(
+        &root_str,
+        &[
+            "ls-files",
+            "-z",
+            "--cached",
+            "--others",
+            "--exclude-standard",
+        ],
+    ) {
+        Ok(output) => output
+            .split('\0')
+            .filter(|p| !p.is_empty())
+            .map(str::to_string)
+            .collect(),
+        Err(_) => WalkDir::new(root)
+            .into_iter()
+            .filter_entry(|entry| {
+                entry.depth() == 0
+                    || !(entry.file_type().is_dir()
+                        && EXCLUDED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref()))
+            })
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .filter_map(|entry| {
+                entry
+                    .path()
+                    .strip_prefix(root)
+                    .ok()
+                    .map(|p| p.to_string_lossy().replace('\\', "/"))
+            })
+            .collect(),
+    };
+
+    files
+        .into_iter()
+        .filter(|path| {
+            let name = path.rsplit('/').next().unwrap_or(path);
+            !name.contains(".min.")
+                && Path::new(name)
+                    .extension()
+                    .map(|ext| ext.to_string_lossy().to_lowercase())
+                    .is_some_and(|ext| SOURCE_EXTENSIONS.contains(&ext.as_str()))
+        })
+        .collect()
+}
+
+/// Overlapping windows of [`CHUNK_LINES`] lines; each also carries the terms
+/// of the file path so file names count towards a match
+fn chunk_file(rel_path: &str, content: &str) -> Vec<Chunk> {
+    let lines: Vec<&str> = content.lines().collect();
+    let path_terms = tokenize(rel_path);
+    let mut chunks = Vec::new();
+
+    let mut start = 0;
+    while start < lines.len() {
+        let end = (start + CHUNK_LINES).min(lines.len());
+        let mut terms: HashMap<String, u32> = HashMap::new();
+        for term in lines[start..end]
+            .iter()
+            .flat_map(|line| tokenize(line))
+            .chain(path_terms.iter().cloned())
+        {
+            *terms.entry(term).or_default() += 1;
+        }
+        if !terms.is_empty() {
+            chunks.push(Chunk {
+                start_line: start + 1,
+                end_line: end,
+                len: terms.values().sum(),
+                terms,
+            });
+        }
+        if end == lines.len() {
+            break;
+        }
+        start = end - CHUNK_OVERLAP;
+    }
+    chunks
+}
+
+// ============================================================================
+// Tokenizer
+// ============================================================================
+
+fn is_cjk(c: char) -> bool {
+    matches!(c, '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{F900}'..='\u{FAFF}')
+}
+
+/// Lowercase camelCase / snake_case parts of an identifier
+/// (`parseHTTPResponse_v2` → `parse`, `http`, `response`, `v2`)
+fn split_identifier(ident: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    for piece in ident.split('_').filter(|p| !p.is_empty()) {
+        let chars: Vec<char> = piece.chars().collect();
+        let mut start = 0;
+        for i in 1..chars.len() {
+            let (prev, cur) = (chars[i - 1], chars[i]);
+            let next_is_lower = chars.get(i + 1).is_some_and(|c| c.is_ascii_lowercase());
+            let boundary = cur.is_ascii_uppercase()
+                && (prev.is_ascii_lowercase()
+                    || prev.is_ascii_digit()
+                    || (prev.is_ascii_uppercase() && next_is_lower));
+            if boundary {
+                parts.push(chars[start..i].iter().collect::<String>().to_lowercase());
+                start = i;
+            }
+        }
+        parts.push(chars[start..].iter().collect::<String>().to_lowercase());
+    }
+    parts
+}
+
+/// Index terms of a piece of text: identifiers and their parts of at least
+/// two characters, and bigrams of CJK runs
+fn tokenize(text: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut push = |term: String| {
+        if term.chars().count() >= 2 && !term.chars().all(|c| c.is_ascii_digit()) {
+            terms.push(term);
+        }
+    };
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_ascii_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let ident: String = chars[start..i].iter().collect();
+            let parts = split_identifier(&ident);
+            let whole = ident.trim_matches('_').to_lowercase();
+            if parts.len() > 1 {
+                parts.into_iter().for_each(&mut push);
+            }
+            push(whole);
+        } else if is_cjk(c) {
+            let start = i;
+            while i < chars.len() && is_cjk(chars[i]) {
+                i += 1;
+            }
+            for pair in chars[start..i].windows(2) {
+                push(pair.iter().collect());
+            }
+        } else {
+            i += 1;
+        }
+    }
+    terms
+}
+
+fn term_trigrams(term: &str) -> Vec<String> {
+    let chars: Vec<char> = term.chars().collect();
+    let mut grams: Vec<String> = chars.windows(3).map(|w| w.iter().collect()).collect();
+    grams.sort();
+    grams.dedup();
+    grams
+}
+
+// ============================================================================
+// Search
+// ============================================================================
+
+/// Brings the index of a project up to date without searching it
+pub fn preindex(project_path: &str) -> Result<RefreshStats, String> {
+    LocalIndex::with(project_path, |_, stats| stats)
+}
+
+/// Runs each query against the project index and collects the distinct
+/// chunks found, stopping once `max_total_length` bytes of code are collected.
+/// Chunks overlapping one already taken from the same file are skipped.
+/// `is_cancelled` is checked before every round.
+pub fn multi_round_search(
+    project_path: &str,
+    queries: &[String],
+    max_total_length: usize,
+    is_cancelled: impl Fn() -> bool,
+) -> Result<Vec<ContextSnippet>, String> {
+    LocalIndex::with(project_path, |index, _| {
+        search_rounds(index, queries, max_total_length, is_cancelled)
+    })?
+}
+
+fn search_rounds(
+    index: &LocalIndex,
+    queries: &[String],
+    max_total_length: usize,
+    is_cancelled: impl Fn() -> bool,
+) -> Result<Vec<ContextSnippet>, String> {
+    let mut taken: Vec<LocalSearchHit> = Vec::new();
+    let mut snippets: Vec<ContextSnippet> = Vec::new();
+    let mut total_length = 0;
+
+    for (round, query) in queries.iter().enumerate() {
+        if query.trim().is_empty() {
+            continue;
+        }
+        if is_cancelled() {
+            return Err("Search cancelled".to_string());
+        }
+        log::info!(
+            "[Local Index] Round {}: searching with query: {}",
+            round + 1,
+            query
+        );
+
+        for hit in index.search(query, RESULTS_PER_QUERY) {
+            let overlaps = taken.iter().any(|t| {
+                t.path == hit.path && t.start_line <= hit.end_line && hit.start_line <= t.end_line
+            });
+            if overlaps {
+                continue;
+            }
+            if let Some(code) = index.read_hit(&hit) {
+                total_length += code.len();
+                snippets.push(ContextSnippet::new(
+                    hit.path.clone(),
+                    Some((hit.start_line, hit.end_line)),
+                    Some(hit.score),
+                    round + 1,
+                    query,
+                    code,
+                ));
+                taken.push(hit);
+            }
+        }
+
+        if total_length >= max_total_length {
+            log::info!(
+                "[Local Index] Reached max length limit, stopping at round {}",
+                round + 1
+            );
+            break;
+        }
+    }
+
+    log::info!(
+        "[Local Index] Search completed: {} unique snippets, {} total chars",
+        snippets.len(),
+        total_length
+    );
+    Ok(snippets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_finds_fuzzy_matches_and_follows_file_changes() {
+        let project = tempfile::tempdir().unwrap();
+        let index_dir = tempfile::tempdir().unwrap();
+        let project_path = project.path().to_string_lossy().to_string();
+
+        fs::write(
+            project.path().join("enhancer.rs"),
+            "fn enhancePromptWithContext(prompt: &str) -> String {\n    prompt.to_string()\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            project.path().join("translate.ts"),
+            "// 翻译服务\nexport function translateText(text: string) { return text; }\n",
+        )
+        .unwrap();
+        fs::create_dir(project.path().join("node_modules")).unwrap();
+        fs::write(
+            project.path().join("node_modules").join("dep.js"),
+            "function enhancement() {}\n",
+        )
+        .unwrap();
+
+        LocalIndex::with_in(&project_path, index_dir.path(), |index, stats| {
+            assert_eq!(stats.indexed, 2);
+
+            let hits = index.search("enhancement", 5);
+            assert_eq!(hits.len(), 1);
+            assert_eq!(hits[0].path, "enhancer.rs");
+            assert_eq!(index.search("翻译", 5)[0].path, "translate.ts");
+            assert_eq!((hits[0].start_line, hits[0].end_line), (1, 3));
+            assert!(index
+                .read_hit(&hits[0])
+                .unwrap()
+                .starts_with("fn enhancePrompt"));
+        })
+        .unwrap();
+
+        // Unchanged projects are searched from memory without rewriting the file
+        let index_path = index_file_path(&project_path, index_dir.path());
+        fs::remove_file(&index_path).unwrap();
+        LocalIndex::with_in(&project_path, index_dir.path(), |index, stats| {
+            assert_eq!((stats.indexed, stats.unchanged), (0, 2));
+            assert_eq!(index.search("enhancement", 5)[0].path, "enhancer.rs");
+        })
+        .unwrap();
+        assert!(!index_path.exists());
+
+        fs::write(
+            project.path().join("translate.ts"),
+            "export function translateBatch(texts: string[]) { return texts; }\n",
+        )
+        .unwrap();
+        fs::remove_file(project.path().join("enhancer.rs")).unwrap();
+
+        LocalIndex::with_in(&project_path, index_dir.path(), |index, stats| {
+            assert_eq!((stats.indexed, stats.unchanged, stats.removed), (1, 0, 1));
+            assert!(index.search("enhance prompt", 5).is_empty());
+            assert_eq!(index.search("batch", 5)[0].path, "translate.ts");
+        })
+        .unwrap();
+        assert!(index_path.exists());
+    }
+}
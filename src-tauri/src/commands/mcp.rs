@@ -15,7 +15,7 @@
 //!
 //! ✅ 保留的命令：
 //! - mcp_serve - 启动 MCP 服务器
-//! - mcp_test_connection - 测试连接
+//! - mcp_test_connection - 测试连接（真实握手 + 工具列表）
 //! - mcp_get_server_status - 获取状态
 //! - mcp_reset_project_choices - 重置项目选择
 //! - mcp_read_project_config - 读取项目配置
@@ -34,13 +34,18 @@
 use anyhow::{Context, Result};
 use dirs;
 use log::{error, info};
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 use std::process::Command;
+use std::sync::Mutex;
+use std::time::Duration;
 use tauri::AppHandle;
 
+use crate::mcp::health::McpHealthReport;
+
 /// Helper function to create a std::process::Command with proper environment variables
 /// This ensures commands like Claude can find Node.js and other dependencies
 fn create_command_with_env(program: &str) -> Command {
@@ -666,16 +671,42 @@ pub async fn mcp_serve(app: AppHandle) -> Result<String, String> {
     }
 }
 
+/// Last health check result per server, reported by `mcp_get_server_status`
+static SERVER_STATUS_CACHE: Lazy<Mutex<HashMap<String, ServerStatus>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
 /// Tests connection to an MCP server
+///
+/// Spawns (stdio) or connects to (http/sse) the server described by its
+/// registry/engine spec, performs the `initialize` handshake and lists the
+/// tools, resources and prompts it exposes.
 #[tauri::command]
-pub async fn mcp_test_connection(app: AppHandle, name: String) -> Result<String, String> {
+pub async fn mcp_test_connection(
+    name: String,
+    engine: Option<String>,
+    timeout_secs: Option<u64>,
+) -> Result<McpHealthReport, String> {
     info!("Testing connection to MCP server: {}", name);
 
-    // For now, we'll use the get command to test if the server exists
-    match execute_claude_mcp_command(&app, vec!["get", &name]) {
-        Ok(_) => Ok(format!("Connection to {} successful", name)),
-        Err(e) => Err(e.to_string()),
+    let spec = crate::mcp::health::resolve_server_spec(&name, engine.as_deref())?;
+    let timeout = Duration::from_secs(
+        timeout_secs.unwrap_or(crate::mcp::health::DEFAULT_TIMEOUT_SECS),
+    );
+
+    let report = crate::mcp::health::check_server_health(&name, &spec, timeout).await;
+
+    if let Ok(mut cache) = SERVER_STATUS_CACHE.lock() {
+        cache.insert(
+            name.clone(),
+            ServerStatus {
+                running: report.success,
+                error: report.error.clone(),
+                last_checked: Some(report.checked_at),
+            },
+        );
     }
+
+    Ok(report)
 }
 
 /// Resets project-scoped server approval choices
@@ -696,13 +727,16 @@ pub async fn mcp_reset_project_choices(app: AppHandle) -> Result<String, String>
 }
 
 /// Gets the status of MCP servers
+///
+/// Returns the result of the most recent `mcp_test_connection` for each server
 #[tauri::command]
 pub async fn mcp_get_server_status() -> Result<HashMap<String, ServerStatus>, String> {
     info!("Getting MCP server status");
 
-    // TODO: Implement actual status checking
-    // For now, return empty status
-    Ok(HashMap::new())
+    SERVER_STATUS_CACHE
+        .lock()
+        .map(|cache| cache.clone())
+        .map_err(|e| format!("Failed to read MCP status cache: {}", e))
 }
 
 /// Exports MCP server configuration from .claude.json
@@ -1,22 +1,40 @@
 pub mod acemcp;
+pub mod budget; // Spend limits and alerts
+pub mod checkpoint; // Rewind snapshots stored under private refs
 pub mod claude;
 pub mod clipboard;
 pub mod codex; // OpenAI Codex integration
 pub mod context_commands;
+pub mod context_history; // Persisted auto-compact sessions and compaction history
 pub mod context_manager;
+pub mod context_windows; // Per-model context window sizes with custom overrides
 pub mod enhanced_hooks;
+pub mod hook_condition; // Expression language for hook conditions
+pub mod hook_watcher; // File watcher for OnFileChange hooks
 pub mod extensions;
 pub mod file_operations;
 pub mod gemini; // Google Gemini CLI integration
 pub mod git_stats;
+pub mod local_index; // Offline BM25 code index for prompt enhancement
 pub mod mcp;
 pub mod permission_config;
+pub mod pre_commit_review; // Engine-backed review of staged changes
+pub mod pricing; // Model price table with custom overrides
 pub mod prompt_tracker;
 pub mod provider;
+pub mod selective_rewind; // Per-file rewind across engines
+pub mod session_export; // Markdown / HTML / JSON session export
+pub mod session_search; // FTS5 search over session histories
+pub mod session_timeline; // Unified Claude/Codex/Gemini session model
+pub mod session_worktree; // Per-session git worktrees for parallel agents
 pub mod simple_git;
 pub mod storage;
+pub mod translation_backend; // OpenAI / Anthropic / DeepL / local command translation providers
+pub mod translation_segmenter; // Keeps code, paths and URLs out of translated prose
 pub mod translator;
 pub mod url_utils; // API URL 规范化工具
 pub mod usage;
+pub mod usage_ingest; // Incremental usage ingestion into usage_entries
+pub mod usage_report; // CSV / JSON usage report export
 pub mod window; // 多窗口管理
 pub mod wsl_utils; // WSL 兼容性工具
@@ -0,0 +1,761 @@
+//! Pre-commit code review backed by a headless CLI engine
+//!
+//! The staged diff (minus `exclude_patterns`, capped at `max_files_to_review`)
+//! is sent to Claude, Codex or Gemini with a JSON schema for the answer. The
+//! findings are filtered by `review_scope` and turned into a `CommitDecision`
+//! using the block flags and `quality_threshold` of `PreCommitCodeReviewConfig`.
+//!
+//! `install_pre_commit_hook` writes a `.git/hooks/pre-commit` script that runs
+//! this executable with `--pre-commit-review <repo>`; the config is saved next
+//! to it as `anycode-review.json` in the common git dir, so linked worktrees
+//! share both.
+
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use super::enhanced_hooks::{CommitDecision, PreCommitCodeReviewConfig};
+use super::hook_condition::glob_match;
+use super::session_timeline::SessionEngine;
+use super::simple_git::git_output;
+use crate::claude_binary::detect_binary_for_tool;
+use crate::commands::claude::apply_no_window_async;
+
+/// Marker line identifying hook scripts written by `install_pre_commit_hook`
+const HOOK_MARKER: &str = "# anycode-pre-commit-review";
+
+/// Review config saved in the common git dir for the installed hook
+const HOOK_CONFIG_FILE: &str = "anycode-review.json";
+
+/// Command line flag handled by `run_from_cli_args`
+pub const CLI_FLAG: &str = "--pre-commit-review";
+
+/// Diffs larger than this are truncated before being sent to the engine
+const MAX_DIFF_BYTES: usize = 200 * 1024;
+
+/// Answer format requested from the engine
+const REVIEW_SCHEMA: &str = r#"{
+  "type": "object",
+  "additionalProperties": false,
+  "required": ["score", "summary", "findings"],
+  "properties": {
+    "score": { "type": "number" },
+    "summary": { "type": "string" },
+    "findings": {
+      "type": "array",
+      "items": {
+        "type": "object",
+        "additionalProperties": false,
+        "required": ["severity", "category", "file", "line", "message", "suggestion"],
+        "properties": {
+          "severity": { "type": "string", "enum": ["critical", "major", "minor", "info"] },
+          "category": {
+            "type": "string",
+            "enum": ["security", "performance", "correctness", "maintainability", "style"]
+          },
+          "file": { "type": "string" },
+          "line": { "type": ["integer", "null"] },
+          "message": { "type": "string" },
+          "suggestion": { "type": ["string", "null"] }
+        }
+      }
+    }
+  }
+}"#;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", from = "String")]
+pub enum Severity {
+    Info,
+    Minor,
+    Major,
+    Critical,
+}
+
+impl From<String> for Severity {
+    /// Maps the labels engines use besides the schema's; anything else counts
+    /// as major so an unexpected label can't slip past the block flags.
+    fn from(label: String) -> Self {
+        match label.trim().to_lowercase().as_str() {
+            "critical" | "blocker" | "fatal" | "severe" => Severity::Critical,
+            "minor" | "low" | "medium" | "moderate" | "warning" | "style" => Severity::Minor,
+            "info" | "note" | "nit" | "suggestion" | "informational" => Severity::Info,
+            _ => Severity::Major,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewFinding {
+    pub severity: Severity,
+    pub category: String,
+    pub file: String,
+    pub line: Option<u32>,
+    pub message: String,
+    pub suggestion: Option<String>,
+}
+
+impl ReviewFinding {
+    fn location(&self) -> String {
+        match self.line {
+            Some(line) => format!("{}:{}", self.file, line),
+            None => self.file.clone(),
+        }
+    }
+}
+
+/// Structured answer of the review engine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewReport {
+    pub score: f64,
+    pub summary: String,
+    #[serde(default)]
+    pub findings: Vec<ReviewFinding>,
+}
+
+/// Staged changes selected for review
+struct StagedChanges {
+    files: Vec<String>,
+    skipped: Vec<String>,
+    diff: String,
+}
+
+// ============================================================================
+// Staged Diff
+// ============================================================================
+
+fn collect_staged_changes(
+    project_path: &str,
+    config: &PreCommitCodeReviewConfig,
+) -> Result<StagedChanges, String> {
+    let excludes: Vec<Pattern> = config
+        .exclude_patterns
+        .iter()
+        .filter_map(|p| Pattern::new(p).ok())
+        .collect();
+
+    let names = git_output(
+        project_path,
+        &["diff", "--cached", "--name-only", "--diff-filter=ACMR"],
+    )?;
+    let (mut files, mut skipped): (Vec<String>, Vec<String>) = names
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .partition(|file| !excludes.iter().any(|p| glob_match(p, file)));
+
+    if config.max_files_to_review > 0 && files.len() > config.max_files_to_review {
+        skipped.extend(files.split_off(config.max_files_to_review));
+    }
+    if files.is_empty() {
+        return Ok(StagedChanges {
+            files,
+            skipped,
+            diff: String::new(),
+        });
+    }
+
+    let mut args = vec!["diff", "--cached", "--no-color", "--unified=3", "--"];
+    args.extend(files.iter().map(String::as_str));
+    let mut diff = git_output(project_path, &args)?;
+    if diff.len() > MAX_DIFF_BYTES {
+        let mut cut = MAX_DIFF_BYTES;
+        while !diff.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        diff.truncate(cut);
+        diff.push_str("\n... (diff truncated)\n");
+    }
+
+    Ok(StagedChanges {
+        files,
+        skipped,
+        diff,
+    })
+}
+
+fn build_prompt(config: &PreCommitCodeReviewConfig, changes: &StagedChanges) -> String {
+    let focus = match config.review_scope.as_str() {
+        "security" => {
+            "Focus only on security issues (injection, secrets, auth, unsafe input handling)."
+        }
+        "performance" => {
+            "Focus only on performance issues (complexity, allocations, blocking I/O, N+1 queries)."
+        }
+        _ => "Review correctness, security, performance and maintainability.",
+    };
+
+    format!(
+        "You are reviewing a git commit before it is created. {}\n\n\
+         Rate the overall quality from 0 (unacceptable) to 10 (excellent). Report each problem \
+         as a finding with a severity: critical (must not be committed: data loss, exploitable \
+         vulnerability, broken build), major (likely bug or serious design flaw), minor, or info. \
+         Only report problems in the changed lines. Use paths exactly as they appear in the diff.\n\n\
+         Reply with a single JSON object matching this schema and nothing else:\n{}\n\n\
+         Files under review:\n{}\n\nStaged diff:\n```diff\n{}\n```",
+        focus,
+        REVIEW_SCHEMA,
+        changes.files.join("\n"),
+        changes.diff
+    )
+}
+
+// ============================================================================
+// Engine Invocation
+// ============================================================================
+
+fn resolve_binary(engine: SessionEngine) -> String {
+    let (tool, env_var) = match engine {
+        SessionEngine::Claude => ("claude", "CLAUDE_PATH"),
+        SessionEngine::Codex => ("codex", "CODEX_PATH"),
+        SessionEngine::Gemini => ("gemini", "GEMINI_CLI_PATH"),
+    };
+    let (_env, detected) = detect_binary_for_tool(tool, env_var, tool);
+    detected.map(|inst| inst.path).unwrap_or_else(|| {
+        log::warn!("[Review] No detected {} binary, falling back to PATH", tool);
+        tool.to_string()
+    })
+}
+
+/// Runs the engine headless and returns the text of its final answer
+async fn run_engine(
+    project_path: &str,
+    config: &PreCommitCodeReviewConfig,
+    prompt: &str,
+) -> Result<String, String> {
+    let binary = resolve_binary(config.engine);
+    let mut cmd = Command::new(&binary);
+    // Codex writes its last message to a file; keep the temp dir alive until it is read
+    let mut codex_output: Option<(tempfile::TempDir, PathBuf)> = None;
+
+    match config.engine {
+        SessionEngine::Claude => {
+            cmd.args(["-p", "--output-format", "json"]);
+            if let Some(model) = &config.model {
+                cmd.args(["--model", model]);
+            }
+        }
+        SessionEngine::Codex => {
+            let dir =
+                tempfile::tempdir().map_err(|e| format!("Failed to create temp dir: {}", e))?;
+            let schema_path = dir.path().join("review-schema.json");
+            let output_path = dir.path().join("review.json");
+            std::fs::write(&schema_path, REVIEW_SCHEMA)
+                .map_err(|e| format!("Failed to write review schema: {}", e))?;
+            cmd.args(["exec", "--sandbox", "read-only", "--output-schema"])
+                .arg(&schema_path)
+                .arg("-o")
+                .arg(&output_path);
+            if let Some(model) = &config.model {
+                cmd.args(["--model", model]);
+            }
+            cmd.arg("-");
+            codex_output = Some((dir, output_path));
+        }
+        SessionEngine::Gemini => {
+            cmd.args(["--output-format", "json"]);
+            if let Some(model) = &config.model {
+                cmd.args(["-m", model]);
+            }
+        }
+    }
+
+    cmd.current_dir(project_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    apply_no_window_async(&mut cmd);
+
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", binary, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(prompt.as_bytes())
+            .await
+            .map_err(|e| format!("Failed to write review prompt: {}", e))?;
+    }
+
+    let output = tokio::time::timeout(
+        Duration::from_secs(config.timeout_secs),
+        child.wait_with_output(),
+    )
+    .await
+    .map_err(|_| format!("Review timed out after {}s", config.timeout_secs))?
+    .map_err(|e| format!("Review process failed: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "{} exited with {}: {}",
+            config.engine.as_str(),
+            output.status,
+            stderr.trim()
+        ));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+
+    match config.engine {
+        SessionEngine::Claude => envelope_field(&stdout, "result"),
+        SessionEngine::Gemini => envelope_field(&stdout, "response"),
+        SessionEngine::Codex => {
+            let (_dir, path) = codex_output.ok_or("Missing Codex output file")?;
+            std::fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read Codex review output: {}", e))
+        }
+    }
+}
+
+/// Extracts the answer text from a `--output-format json` envelope
+fn envelope_field(stdout: &str, field: &str) -> Result<String, String> {
+    let envelope: serde_json::Value = serde_json::from_str(stdout.trim())
+        .map_err(|e| format!("Unexpected review output: {}", e))?;
+    if envelope.get("is_error").and_then(|v| v.as_bool()) == Some(true) {
+        return Err(format!("Review failed: {}", envelope[field]));
+    }
+    envelope
+        .get(field)
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+        .ok_or_else(|| format!("Review output has no '{}' field", field))
+}
+
+/// Parses the report, tolerating code fences or prose around the JSON object
+fn parse_report(text: &str) -> Result<ReviewReport, String> {
+    let start = text
+        .find('{')
+        .ok_or("Review answer contains no JSON object")?;
+    let end = text
+        .rfind('}')
+        .ok_or("Review answer contains no JSON object")?;
+    if end < start {
+        return Err("Review answer contains no JSON object".to_string());
+    }
+    serde_json::from_str(&text[start..=end])
+        .map_err(|e| format!("Failed to parse review answer: {}", e))
+}
+
+// ============================================================================
+// Decision
+// ============================================================================
+
+fn in_scope(finding: &ReviewFinding, scope: &str) -> bool {
+    match scope {
+        "security" | "performance" => finding.category == scope,
+        _ => true,
+    }
+}
+
+fn decide(
+    config: &PreCommitCodeReviewConfig,
+    report: ReviewReport,
+    skipped: &[String],
+) -> CommitDecision {
+    let mut findings: Vec<ReviewFinding> = report
+        .findings
+        .into_iter()
+        .filter(|f| in_scope(f, &config.review_scope))
+        .collect();
+    findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+
+    let count = |severity: Severity| findings.iter().filter(|f| f.severity == severity).count();
+    let critical = count(Severity::Critical);
+    let major = count(Severity::Major);
+
+    let mut reasons = Vec::new();
+    if config.block_critical_issues && critical > 0 {
+        reasons.push(format!("{} critical issue(s)", critical));
+    }
+    if config.block_major_issues && major > 0 {
+        reasons.push(format!("{} major issue(s)", major));
+    }
+    if report.score < config.quality_threshold {
+        reasons.push(format!(
+            "quality score {:.1} is below the threshold {:.1}",
+            report.score, config.quality_threshold
+        ));
+    }
+
+    let suggestions = if config.show_suggestions {
+        findings
+            .iter()
+            .filter_map(|f| {
+                f.suggestion
+                    .as_ref()
+                    .map(|s| format!("{}: {}", f.location(), s))
+            })
+            .collect()
+    } else {
+        vec![]
+    };
+
+    let mut details = format!("Score {:.1}/10 — {}", report.score, report.summary);
+    for finding in &findings {
+        details.push_str(&format!(
+            "\n[{:?}] {} ({}): {}",
+            finding.severity,
+            finding.location(),
+            finding.category,
+            finding.message
+        ));
+    }
+    if !skipped.is_empty() {
+        details.push_str(&format!("\nNot reviewed: {}", skipped.join(", ")));
+    }
+
+    if reasons.is_empty() {
+        CommitDecision::Allow {
+            message: details,
+            suggestions,
+        }
+    } else {
+        CommitDecision::Block {
+            reason: format!("Commit blocked: {}", reasons.join("; ")),
+            details,
+            suggestions,
+        }
+    }
+}
+
+/// Reviews the staged changes of a repository
+pub async fn review_staged_changes(
+    project_path: &str,
+    config: &PreCommitCodeReviewConfig,
+) -> Result<CommitDecision, String> {
+    if !config.enabled {
+        return Ok(CommitDecision::Allow {
+            message: "Pre-commit review is disabled".to_string(),
+            suggestions: vec![],
+        });
+    }
+
+    let changes = collect_staged_changes(project_path, config)?;
+    if changes.files.is_empty() {
+        return Ok(CommitDecision::Allow {
+            message: "No staged changes to review".to_string(),
+            suggestions: vec![],
+        });
+    }
+
+    log::info!(
+        "[Review] Reviewing {} staged file(s) in {} with {}",
+        changes.files.len(),
+        project_path,
+        config.engine.as_str()
+    );
+    let answer = run_engine(project_path, config, &build_prompt(config, &changes)).await?;
+    let report = parse_report(&answer)?;
+    Ok(decide(config, report, &changes.skipped))
+}
+
+// ============================================================================
+// Git Hook Installation
+// ============================================================================
+
+fn git_path(project_path: &str, path: &str) -> Result<PathBuf, String> {
+    let resolved = PathBuf::from(git_output(
+        project_path,
+        &["rev-parse", "--git-path", path],
+    )?);
+    Ok(if resolved.is_absolute() {
+        resolved
+    } else {
+        Path::new(project_path).join(resolved)
+    })
+}
+
+/// The config lives in the common dir: `--git-path` would resolve it per
+/// worktree while the hook itself is shared by all of them.
+fn hook_config_path(project_path: &str) -> Result<PathBuf, String> {
+    let common_dir = PathBuf::from(git_output(
+        project_path,
+        &["rev-parse", "--git-common-dir"],
+    )?);
+    let common_dir = if common_dir.is_absolute() {
+        common_dir
+    } else {
+        Path::new(project_path).join(common_dir)
+    };
+    Ok(common_dir.join(HOOK_CONFIG_FILE))
+}
+
+/// Loads the config saved by `install_pre_commit_hook`, if any
+pub fn load_hook_config(project_path: &str) -> Option<PreCommitCodeReviewConfig> {
+    let path = hook_config_path(project_path).ok()?;
+    let content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn hook_script() -> Result<String, String> {
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("Failed to locate executable: {}", e))?
+        .to_string_lossy()
+        .replace('\\', "/");
+    Ok(format!(
+        "#!/bin/sh\n{}\n# Installed by Any Code. Delete this file to disable the review.\n\
+         exec \"{}\" {} \"$(git rev-parse --show-toplevel)\"\n",
+        HOOK_MARKER, exe, CLI_FLAG
+    ))
+}
+
+/// Installs the review as `.git/hooks/pre-commit`, refusing to replace foreign hooks
+pub fn install_hook(project_path: &str, config: &PreCommitCodeReviewConfig) -> Result<(), String> {
+    let hook_path = git_path(project_path, "hooks/pre-commit")?;
+    if let Ok(existing) = std::fs::read_to_string(&hook_path) {
+        if !existing.contains(HOOK_MARKER) {
+            return Err(format!(
+                "A pre-commit hook already exists at {}",
+                hook_path.display()
+            ));
+        }
+    }
+
+    let config_json = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize review config: {}", e))?;
+    std::fs::write(hook_config_path(project_path)?, config_json)
+        .map_err(|e| format!("Failed to save review config: {}", e))?;
+
+    if let Some(dir) = hook_path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create hooks dir: {}", e))?;
+    }
+    std::fs::write(&hook_path, hook_script()?)
+        .map_err(|e| format!("Failed to write pre-commit hook: {}", e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&hook_path, std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("Failed to make hook executable: {}", e))?;
+    }
+
+    log::info!(
+        "[Review] Installed pre-commit hook at {}",
+        hook_path.display()
+    );
+    Ok(())
+}
+
+/// Removes a hook written by `install_hook`; returns false if none was installed
+pub fn uninstall_hook(project_path: &str) -> Result<bool, String> {
+    let hook_path = git_path(project_path, "hooks/pre-commit")?;
+    match std::fs::read_to_string(&hook_path) {
+        Ok(existing) if existing.contains(HOOK_MARKER) => {
+            std::fs::remove_file(&hook_path)
+                .map_err(|e| format!("Failed to remove pre-commit hook: {}", e))?;
+            let _ = std::fs::remove_file(hook_config_path(project_path)?);
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Whether the review hook is installed in the repository
+pub fn is_hook_installed(project_path: &str) -> bool {
+    git_path(project_path, "hooks/pre-commit")
+        .and_then(|path| std::fs::read_to_string(path).map_err(|e| e.to_string()))
+        .is_ok_and(|content| content.contains(HOOK_MARKER))
+}
+
+/// Entry point for the installed git hook: `<exe> --pre-commit-review <repo>`
+///
+/// Returns the process exit code when the flag is present. Engine failures
+/// are reported and block the commit only when `fail_closed` is set.
+pub fn run_from_cli_args() -> Option<i32> {
+    let mut args = std::env::args().skip(1);
+    if args.next().as_deref() != Some(CLI_FLAG) {
+        return None;
+    }
+    let project_path = args.next().unwrap_or_else(|| ".".to_string());
+    let config = load_hook_config(&project_path).unwrap_or_default();
+
+    let on_error = if config.fail_closed { 1 } else { 0 };
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("[anycode] Failed to start review: {}", e);
+            return Some(on_error);
+        }
+    };
+
+    match runtime.block_on(review_staged_changes(&project_path, &config)) {
+        Ok(CommitDecision::Allow {
+            message,
+            suggestions,
+        }) => {
+            eprintln!("[anycode] Review passed. {}", message);
+            for suggestion in suggestions {
+                eprintln!("  - {}", suggestion);
+            }
+            Some(0)
+        }
+        Ok(CommitDecision::Block {
+            reason,
+            details,
+            suggestions,
+        }) => {
+            eprintln!("[anycode] {}\n{}", reason, details);
+            for suggestion in suggestions {
+                eprintln!("  - {}", suggestion);
+            }
+            eprintln!("[anycode] Use `git commit --no-verify` to skip the review.");
+            Some(1)
+        }
+        Err(e) if config.fail_closed => {
+            eprintln!("[anycode] Commit blocked, the review failed: {}", e);
+            eprintln!("[anycode] Use `git commit --no-verify` to skip the review.");
+            Some(1)
+        }
+        Err(e) => {
+            eprintln!("[anycode] Review skipped: {}", e);
+            Some(0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(severity: Severity, category: &str) -> ReviewFinding {
+        ReviewFinding {
+            severity,
+            category: category.to_string(),
+            file: "src/lib.rs".to_string(),
+            line: Some(3),
+            message: "problem".to_string(),
+            suggestion: Some("fix it".to_string()),
+        }
+    }
+
+    #[test]
+    fn decision_honours_thresholds_and_scope() {
+        let answer = format!(
+            "```json\n{}\n```",
+            serde_json::json!({
+                "score": 7.5,
+                "summary": "ok",
+                "findings": [
+                    { "severity": "major", "category": "performance", "file": "a.rs",
+                      "line": null, "message": "slow", "suggestion": null },
+                    { "severity": "critical", "category": "security", "file": "b.rs",
+                      "line": 9, "message": "sql injection", "suggestion": "bind params" }
+                ]
+            })
+        );
+        let report = parse_report(&answer).unwrap();
+        assert_eq!(report.findings.len(), 2);
+
+        let config = PreCommitCodeReviewConfig::default();
+        match decide(&config, report.clone(), &[]) {
+            CommitDecision::Block {
+                reason,
+                suggestions,
+                ..
+            } => {
+                assert!(reason.contains("1 critical"), "{}", reason);
+                assert_eq!(suggestions, vec!["b.rs:9: bind params".to_string()]);
+            }
+            other => panic!("expected block, got {:?}", other),
+        }
+
+        let performance_only = PreCommitCodeReviewConfig {
+            review_scope: "performance".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            decide(&performance_only, report.clone(), &[]),
+            CommitDecision::Allow { .. }
+        ));
+
+        let strict = PreCommitCodeReviewConfig {
+            review_scope: "performance".to_string(),
+            block_major_issues: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            decide(&strict, report, &[]),
+            CommitDecision::Block { .. }
+        ));
+
+        let low_score = ReviewReport {
+            score: 4.0,
+            summary: String::new(),
+            findings: vec![finding(Severity::Minor, "style")],
+        };
+        match decide(&PreCommitCodeReviewConfig::default(), low_score, &[]) {
+            CommitDecision::Block { reason, .. } => assert!(reason.contains("below the threshold")),
+            other => panic!("expected block, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unknown_severities_still_count() {
+        let answer = serde_json::json!({
+            "score": 8.0,
+            "summary": "ok",
+            "findings": [
+                { "severity": "Blocker", "category": "security", "file": "a.rs",
+                  "line": 1, "message": "secret", "suggestion": null },
+                { "severity": "high", "category": "correctness", "file": "b.rs",
+                  "line": null, "message": "wrong", "suggestion": null },
+                { "severity": "nit", "category": "style", "file": "c.rs",
+                  "line": null, "message": "naming", "suggestion": null }
+            ]
+        })
+        .to_string();
+        let report = parse_report(&answer).unwrap();
+        let severities: Vec<Severity> = report.findings.iter().map(|f| f.severity).collect();
+        assert_eq!(
+            severities,
+            vec![Severity::Critical, Severity::Major, Severity::Info]
+        );
+        assert!(matches!(
+            decide(&PreCommitCodeReviewConfig::default(), report, &[]),
+            CommitDecision::Block { .. }
+        ));
+    }
+
+    #[test]
+    fn linked_worktrees_share_the_hook_config() {
+        let repo = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        let git = |dir: &Path, args: &[&str]| {
+            git_output(dir.to_str().unwrap(), args).unwrap();
+        };
+        git(repo.path(), &["init", "-q"]);
+        git(repo.path(), &["config", "user.name", "Test"]);
+        git(repo.path(), &["config", "user.email", "test@example.com"]);
+        std::fs::write(repo.path().join("README.md"), "hello\n").unwrap();
+        git(repo.path(), &["add", "-A"]);
+        git(repo.path(), &["commit", "-q", "-m", "init"]);
+        let linked = root.path().join("linked");
+        git(
+            repo.path(),
+            &["worktree", "add", "-q", linked.to_str().unwrap()],
+        );
+
+        let config = PreCommitCodeReviewConfig {
+            block_major_issues: true,
+            fail_closed: true,
+            ..Default::default()
+        };
+        install_hook(repo.path().to_str().unwrap(), &config).unwrap();
+
+        let linked_path = linked.to_str().unwrap();
+        assert!(is_hook_installed(linked_path));
+        let loaded = load_hook_config(linked_path).unwrap();
+        assert!(loaded.block_major_issues && loaded.fail_closed);
+
+        assert!(uninstall_hook(linked_path).unwrap());
+        assert!(load_hook_config(repo.path().to_str().unwrap()).is_none());
+    }
+}
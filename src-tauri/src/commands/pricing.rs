@@ -0,0 +1,539 @@
+//! Model Pricing
+//!
+//! One price table for the Claude, Codex and Gemini usage modules. The
+//! built-in entries carry the published list prices; `~/.anycode/pricing.json`
+//! adds custom entries on top, e.g. negotiated rates or the prices of a proxied
+//! provider config.
+//!
+//! An entry matches a model through a case-insensitive regex and may be
+//! limited to one provider config and to a range of local dates. Lookups pick,
+//! in order: a custom entry for the provider, a custom entry for any provider,
+//! then the built-in list. Within a group the first matching entry wins.
+//!
+//! Costs are stored with each usage row when it is ingested, for the provider
+//! config the row's run used. Saving the table reprices the stored rows, and
+//! the date range keeps an older log priced at the rate that applied when it
+//! was written.
+
+use chrono::{DateTime, Local, NaiveDate};
+use once_cell::sync::Lazy;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::State;
+
+use super::session_timeline::SessionEngine;
+use super::storage::AgentDb;
+use super::usage_ingest::reprice_usage;
+use crate::utils::config_utils::{load_json_config, save_json_config};
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Prices in USD per million tokens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenRates {
+    pub input: f64,
+    pub output: f64,
+    #[serde(default)]
+    pub cache_write: f64,
+    #[serde(default)]
+    pub cache_read: f64,
+}
+
+/// Token counts to price
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokenCounts {
+    pub input: u64,
+    pub output: u64,
+    pub cache_write: u64,
+    pub cache_read: u64,
+}
+
+impl TokenRates {
+    pub fn cost(&self, tokens: &TokenCounts) -> f64 {
+        (tokens.input as f64 * self.input
+            + tokens.output as f64 * self.output
+            + tokens.cache_write as f64 * self.cache_write
+            + tokens.cache_read as f64 * self.cache_read)
+            / 1_000_000.0
+    }
+}
+
+/// A row of the price table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceEntry {
+    #[serde(default)]
+    pub id: String,
+    pub engine: SessionEngine,
+    /// Case-insensitive regex searched for in the model name
+    pub model_pattern: String,
+    /// Only applies to sessions run through this provider config
+    #[serde(default)]
+    pub provider_id: Option<String>,
+    /// First local date the price applies (YYYY-MM-DD)
+    #[serde(default)]
+    pub effective_from: Option<String>,
+    /// Last local date the price applies (YYYY-MM-DD)
+    #[serde(default)]
+    pub effective_until: Option<String>,
+    pub rates: TokenRates,
+}
+
+/// Custom entries saved in `~/.anycode/pricing.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingConfig {
+    #[serde(default)]
+    pub entries: Vec<PriceEntry>,
+}
+
+/// Where a resolved price came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceSource {
+    Custom,
+    Builtin,
+}
+
+/// Result of a price lookup
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedPrice {
+    pub entry_id: String,
+    pub source: PriceSource,
+    pub rates: TokenRates,
+}
+
+// ============================================================================
+// Built-in List Prices
+// Claude: https://platform.claude.com/docs/en/about-claude/pricing
+// Codex:  https://platform.openai.com/docs/pricing
+// Gemini: https://ai.google.dev/gemini-api/docs/pricing
+// Last Updated: February 2026 (must match frontend pricing.ts)
+// ============================================================================
+
+/// (id, engine, model pattern, [input, output, cache write, cache read])
+#[rustfmt::skip]
+const BUILTIN_PRICES: &[(&str, SessionEngine, &str, [f64; 4])] = &[
+    // Claude 4.6 Series (Latest - February 2026)
+    ("claude-opus-4.6", SessionEngine::Claude, r"opus.*4[.-]6", [5.0, 25.0, 6.25, 0.50]),
+    ("claude-sonnet-4.6", SessionEngine::Claude, r"sonnet.*4[.-]6", [3.0, 15.0, 3.75, 0.30]),
+    // Claude 4.5 Series
+    ("claude-opus-4.5", SessionEngine::Claude, r"opus.*4[.-]5", [5.0, 25.0, 6.25, 0.50]),
+    ("claude-haiku-4.5", SessionEngine::Claude, r"haiku.*4[.-]5", [1.0, 5.0, 1.25, 0.10]),
+    ("claude-sonnet-4.5", SessionEngine::Claude, r"sonnet.*4[.-]5", [3.0, 15.0, 3.75, 0.30]),
+    // Claude 4.1 Series
+    ("claude-opus-4.1", SessionEngine::Claude, r"opus.*4[.-]1", [15.0, 75.0, 18.75, 1.50]),
+    // Generic family fallbacks (latest of each family)
+    ("claude-haiku", SessionEngine::Claude, r"haiku", [1.0, 5.0, 1.25, 0.10]),
+    ("claude-opus", SessionEngine::Claude, r"opus", [5.0, 25.0, 6.25, 0.50]),
+    ("claude-sonnet", SessionEngine::Claude, r"sonnet", [3.0, 15.0, 3.75, 0.30]),
+    // GPT-5.3 Codex (latest - February 2026)
+    ("gpt-5.3-codex-spark", SessionEngine::Codex, r"5\.3-codex-spark|5_3_codex_spark", [1.50, 12.00, 0.0, 0.15]),
+    ("gpt-5.3-codex", SessionEngine::Codex, r"5\.3-codex|5_3_codex|gpt-?5\.3", [2.00, 16.00, 0.0, 0.20]),
+    // GPT-5.2
+    ("gpt-5.2-codex", SessionEngine::Codex, r"5\.2-codex|5_2_codex|gpt-?5\.2", [1.75, 14.00, 0.0, 0.175]),
+    // GPT-5.1
+    ("gpt-5.1-codex-max", SessionEngine::Codex, r"5\.1-codex-max|5_1_codex_max", [1.25, 10.00, 0.0, 0.125]),
+    ("gpt-5.1-codex-mini", SessionEngine::Codex, r"5\.1-codex-mini|5_1_codex_mini", [0.25, 2.00, 0.0, 0.025]),
+    ("gpt-5.1-codex", SessionEngine::Codex, r"5\.1-codex|5_1_codex|gpt-?5\.1", [1.25, 10.00, 0.0, 0.125]),
+    // codex-mini-latest (default CLI model) and o4-mini
+    ("codex-mini-latest", SessionEngine::Codex, r"codex[-_]mini[-_]latest", [1.50, 6.00, 0.0, 0.375]),
+    ("o4-mini", SessionEngine::Codex, r"o4[-_]mini", [1.10, 4.40, 0.0, 0.275]),
+    ("codex-default", SessionEngine::Codex, r".*", [1.50, 6.00, 0.0, 0.375]),
+    // Gemini 3.x (Latest - February 2026)
+    ("gemini-3.1-pro", SessionEngine::Gemini, r"3\.1-pro|gemini_3_1_pro", [2.50, 15.00, 0.0, 0.25]),
+    ("gemini-3-pro", SessionEngine::Gemini, r"gemini[-_]3[-_]pro", [2.00, 12.00, 0.0, 0.20]),
+    // Gemini 2.x
+    ("gemini-2.5-pro", SessionEngine::Gemini, r"2\.5-pro|2_5_pro", [1.25, 10.00, 0.0, 0.125]),
+    ("gemini-2.5-flash-lite", SessionEngine::Gemini, r"2\.5-flash-lite|2_5_flash_lite", [0.10, 0.40, 0.0, 0.01]),
+    ("gemini-2.5-flash", SessionEngine::Gemini, r"2\.5-flash|2_5_flash", [0.30, 2.50, 0.0, 0.03]),
+    ("gemini-2.0-flash", SessionEngine::Gemini, r"2\.0-flash|2_0_flash", [0.10, 0.40, 0.0, 0.025]),
+    // Gemini 3 Flash (default for new sessions)
+    ("gemini-3-flash", SessionEngine::Gemini, r"gemini[-_]3[-_]flash", [0.30, 2.50, 0.0, 0.03]),
+    ("gemini-default", SessionEngine::Gemini, r".*", [1.25, 10.00, 0.0, 0.125]),
+];
+
+/// The built-in list prices as table entries
+pub fn builtin_entries() -> Vec<PriceEntry> {
+    BUILTIN_PRICES
+        .iter()
+        .map(
+            |(id, engine, pattern, [input, output, cache_write, cache_read])| PriceEntry {
+                id: id.to_string(),
+                engine: *engine,
+                model_pattern: pattern.to_string(),
+                provider_id: None,
+                effective_from: None,
+                effective_until: None,
+                rates: TokenRates {
+                    input: *input,
+                    output: *output,
+                    cache_write: *cache_write,
+                    cache_read: *cache_read,
+                },
+            },
+        )
+        .collect()
+}
+
+// ============================================================================
+// Lookup
+// ============================================================================
+
+struct CompiledEntry {
+    entry: PriceEntry,
+    pattern: Regex,
+    from: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+}
+
+impl CompiledEntry {
+    fn compile(entry: PriceEntry) -> Result<Self, String> {
+        let pattern = RegexBuilder::new(&entry.model_pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| format!("Invalid model pattern '{}': {}", entry.model_pattern, e))?;
+        let from = parse_date(entry.effective_from.as_deref())?;
+        let until = parse_date(entry.effective_until.as_deref())?;
+        if let (Some(from), Some(until)) = (from, until) {
+            if from > until {
+                return Err(format!(
+                    "Price '{}' ends ({}) before it starts ({})",
+                    entry.id, until, from
+                ));
+            }
+        }
+        Ok(Self {
+            entry,
+            pattern,
+            from,
+            until,
+        })
+    }
+
+    fn matches(&self, engine: SessionEngine, model: &str, date: NaiveDate) -> bool {
+        self.entry.engine == engine
+            && self.from.is_none_or(|from| date >= from)
+            && self.until.is_none_or(|until| date <= until)
+            && self.pattern.is_match(model)
+    }
+
+    fn resolve(&self, source: PriceSource) -> ResolvedPrice {
+        ResolvedPrice {
+            entry_id: self.entry.id.clone(),
+            source,
+            rates: self.entry.rates,
+        }
+    }
+}
+
+fn parse_date(value: Option<&str>) -> Result<Option<NaiveDate>, String> {
+    match value.map(str::trim).filter(|s| !s.is_empty()) {
+        Some(s) => NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .map(Some)
+            .map_err(|e| format!("Invalid date '{}': {}", s, e)),
+        None => Ok(None),
+    }
+}
+
+/// Strips the Bedrock and Vertex AI decorations from a model id
+pub(crate) fn normalize_model(model: &str) -> String {
+    let normalized = model
+        .to_lowercase()
+        .replace("anthropic.", "")
+        .replace("-v1:0", "");
+    match normalized.find('@') {
+        Some(pos) => normalized[..pos].to_string(),
+        None => normalized,
+    }
+}
+
+/// Local date of a usage timestamp; today when missing or unparseable
+fn usage_date(timestamp: Option<&str>) -> NaiveDate {
+    timestamp
+        .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+        .map(|dt| dt.with_timezone(&Local).date_naive())
+        .unwrap_or_else(|| Local::now().date_naive())
+}
+
+/// Compiled price table
+pub struct PriceBook {
+    custom: Vec<CompiledEntry>,
+    builtin: Vec<CompiledEntry>,
+}
+
+impl PriceBook {
+    pub(crate) fn new(config: PricingConfig) -> Self {
+        let custom = config
+            .entries
+            .into_iter()
+            .filter_map(|entry| match CompiledEntry::compile(entry) {
+                Ok(compiled) => Some(compiled),
+                Err(e) => {
+                    log::warn!("[Pricing] Ignoring custom price: {}", e);
+                    None
+                }
+            })
+            .collect();
+        let builtin = builtin_entries()
+            .into_iter()
+            .filter_map(|entry| CompiledEntry::compile(entry).ok())
+            .collect();
+        Self { custom, builtin }
+    }
+
+    /// Price of `model` for usage recorded at `timestamp` (RFC 3339, now when `None`)
+    pub fn resolve(
+        &self,
+        engine: SessionEngine,
+        model: &str,
+        provider_id: Option<&str>,
+        timestamp: Option<&str>,
+    ) -> Option<ResolvedPrice> {
+        let model = normalize_model(model);
+        let date = usage_date(timestamp);
+
+        if let Some(provider_id) = provider_id {
+            if let Some(entry) = self.custom.iter().find(|e| {
+                e.entry.provider_id.as_deref() == Some(provider_id)
+                    && e.matches(engine, &model, date)
+            }) {
+                return Some(entry.resolve(PriceSource::Custom));
+            }
+        }
+        if let Some(entry) = self
+            .custom
+            .iter()
+            .find(|e| e.entry.provider_id.is_none() && e.matches(engine, &model, date))
+        {
+            return Some(entry.resolve(PriceSource::Custom));
+        }
+        self.builtin
+            .iter()
+            .find(|e| e.matches(engine, &model, date))
+            .map(|e| e.resolve(PriceSource::Builtin))
+    }
+
+    /// Cost in USD; zero for a model no entry matches
+    pub fn cost(
+        &self,
+        engine: SessionEngine,
+        model: &str,
+        provider_id: Option<&str>,
+        timestamp: Option<&str>,
+        tokens: &TokenCounts,
+    ) -> f64 {
+        match self.resolve(engine, model, provider_id, timestamp) {
+            Some(price) => price.rates.cost(tokens),
+            None => {
+                log::warn!(
+                    "Unknown model detected: '{}'. Cost calculation will return 0.",
+                    model
+                );
+                0.0
+            }
+        }
+    }
+}
+
+static PRICE_BOOK: Lazy<Mutex<Option<Arc<PriceBook>>>> = Lazy::new(|| Mutex::new(None));
+
+/// The current price table, loaded from disk on first use
+pub fn price_book() -> Arc<PriceBook> {
+    let mut cached = PRICE_BOOK.lock().unwrap_or_else(|e| e.into_inner());
+    cached
+        .get_or_insert_with(|| {
+            let config = load_pricing_config().unwrap_or_else(|e| {
+                log::warn!("[Pricing] Failed to load custom prices: {}", e);
+                PricingConfig::default()
+            });
+            Arc::new(PriceBook::new(config))
+        })
+        .clone()
+}
+
+// ============================================================================
+// Config Persistence
+// ============================================================================
+
+fn get_pricing_config_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Failed to get home directory")?;
+    Ok(home.join(".anycode").join("pricing.json"))
+}
+
+pub fn load_pricing_config() -> Result<PricingConfig, String> {
+    load_json_config(get_pricing_config_path()?)
+}
+
+fn validate_entry(entry: &mut PriceEntry) -> Result<(), String> {
+    if entry.id.trim().is_empty() {
+        entry.id = uuid::Uuid::new_v4().to_string();
+    }
+    if entry.model_pattern.trim().is_empty() {
+        return Err(format!("Price '{}' needs a model pattern", entry.id));
+    }
+    let rates = entry.rates;
+    if [
+        rates.input,
+        rates.output,
+        rates.cache_write,
+        rates.cache_read,
+    ]
+    .iter()
+    .any(|r| !r.is_finite() || *r < 0.0)
+    {
+        return Err(format!(
+            "Price '{}' has a negative or invalid rate",
+            entry.id
+        ));
+    }
+    entry.provider_id = entry
+        .provider_id
+        .take()
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty());
+    CompiledEntry::compile(entry.clone()).map(|_| ())
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Custom price entries
+#[tauri::command]
+pub async fn get_pricing_config() -> Result<PricingConfig, String> {
+    load_pricing_config()
+}
+
+/// Validates and saves the custom price entries, then reprices the usage
+/// already ingested so retroactive prices apply to it
+#[tauri::command]
+pub async fn save_pricing_config(
+    db: State<'_, AgentDb>,
+    mut config: PricingConfig,
+) -> Result<PricingConfig, String> {
+    for entry in &mut config.entries {
+        validate_entry(entry)?;
+    }
+    save_json_config(&config, get_pricing_config_path()?)?;
+    let book = Arc::new(PriceBook::new(config.clone()));
+    *PRICE_BOOK.lock().unwrap_or_else(|e| e.into_inner()) = Some(book.clone());
+    log::info!("[Pricing] Saved {} custom price(s)", config.entries.len());
+
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    reprice_usage(&mut conn, &book)?;
+    Ok(config)
+}
+
+/// Recomputes the cost of all ingested usage with the current price table,
+/// returning the number of rows that changed
+#[tauri::command]
+pub async fn reprice_usage_entries(db: State<'_, AgentDb>) -> Result<usize, String> {
+    let book = price_book();
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    reprice_usage(&mut conn, &book)
+}
+
+/// Built-in list prices, for display next to the custom entries
+#[tauri::command]
+pub async fn get_builtin_pricing() -> Result<Vec<PriceEntry>, String> {
+    Ok(builtin_entries())
+}
+
+/// Price that applies to a model, provider and date (YYYY-MM-DD, today when omitted)
+#[tauri::command]
+pub async fn resolve_model_pricing(
+    engine: SessionEngine,
+    model: String,
+    provider_id: Option<String>,
+    date: Option<String>,
+) -> Result<Option<ResolvedPrice>, String> {
+    let timestamp = parse_date(date.as_deref())?.map(super::usage_ingest::local_midnight_utc);
+    Ok(price_book().resolve(engine, &model, provider_id.as_deref(), timestamp.as_deref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom(
+        provider_id: Option<&str>,
+        from: Option<&str>,
+        until: Option<&str>,
+        input: f64,
+    ) -> PriceEntry {
+        PriceEntry {
+            id: format!("custom-{}", input),
+            engine: SessionEngine::Claude,
+            model_pattern: "sonnet".to_string(),
+            provider_id: provider_id.map(str::to_string),
+            effective_from: from.map(str::to_string),
+            effective_until: until.map(str::to_string),
+            rates: TokenRates {
+                input,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn provider_and_date_overrides_take_precedence() {
+        let book = PriceBook::new(PricingConfig {
+            entries: vec![
+                custom(None, None, Some("2025-12-31"), 2.0),
+                custom(Some("proxy"), Some("2026-01-01"), None, 1.0),
+            ],
+        });
+        let model = "claude-sonnet-4-5-20250929";
+        let old = Some("2025-06-01T12:00:00Z");
+        let new = Some("2026-06-01T12:00:00Z");
+
+        let price = book
+            .resolve(SessionEngine::Claude, model, Some("proxy"), new)
+            .unwrap();
+        assert_eq!(
+            (price.source, price.rates.input),
+            (PriceSource::Custom, 1.0)
+        );
+
+        // The provider override has not started yet, the generic one applies
+        let price = book
+            .resolve(SessionEngine::Claude, model, Some("proxy"), old)
+            .unwrap();
+        assert_eq!(price.rates.input, 2.0);
+
+        // Both custom entries are out of range: list price
+        let price = book
+            .resolve(SessionEngine::Claude, model, None, new)
+            .unwrap();
+        assert_eq!(
+            (price.source, price.entry_id.as_str()),
+            (PriceSource::Builtin, "claude-sonnet-4.5")
+        );
+
+        let price = book
+            .resolve(
+                SessionEngine::Claude,
+                "anthropic.claude-opus-4-1-20250805-v1:0",
+                None,
+                new,
+            )
+            .unwrap();
+        assert_eq!(price.rates.input, 15.0);
+        assert!(book
+            .resolve(SessionEngine::Claude, "mystery", None, new)
+            .is_none());
+        assert_eq!(
+            book.resolve(SessionEngine::Codex, "mystery", None, new)
+                .unwrap()
+                .entry_id,
+            "codex-default"
+        );
+    }
+}
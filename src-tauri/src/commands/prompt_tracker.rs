@@ -6,9 +6,10 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
-use super::claude::get_claude_dir;
+use super::checkpoint;
+use super::claude::{get_claude_dir, get_session_project_dir};
 use super::permission_config::ClaudeExecutionConfig;
-use super::simple_git;
+use super::session_worktree;
 
 /// Rewind mode for reverting prompts
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -89,16 +90,17 @@ pub fn load_execution_config() -> Result<ClaudeExecutionConfig> {
 
 /// Get path to git records file
 fn get_git_records_path(session_id: &str, project_id: &str) -> Result<PathBuf> {
-    let claude_dir = get_claude_dir().context("Failed to get claude dir")?;
-    let records_path = claude_dir
-        .join("projects")
-        .join(project_id)
+    let records_path = get_session_project_dir(session_id, project_id)
+        .context("Failed to get claude dir")?
         .join("sessions")
         .join(format!("{}.git-records.json", session_id));
     Ok(records_path)
 }
 /// Load git records from .git-records.json (using prompt_index as key)
-fn load_git_records(session_id: &str, project_id: &str) -> Result<HashMap<usize, GitRecord>> {
+pub(crate) fn load_git_records(
+    session_id: &str,
+    project_id: &str,
+) -> Result<HashMap<usize, GitRecord>> {
     let records_path = get_git_records_path(session_id, project_id)?;
 
     if !records_path.exists() {
@@ -186,9 +188,11 @@ fn build_prompt_commit_message(
 }
 
 /// Truncate git records (remove records for prompts after the specified index)
+/// and drop the checkpoints only they pointed at
 fn truncate_git_records(
     session_id: &str,
     project_id: &str,
+    project_path: &str,
     prompts: &[PromptRecord],
     prompt_index: usize,
 ) -> Result<()> {
@@ -206,6 +210,15 @@ fn truncate_git_records(
         "[Truncate] Truncated git records after prompt #{}",
         prompt_index
     );
+
+    let keep: Vec<&str> = records
+        .values()
+        .flat_map(|r| [Some(r.commit_before.as_str()), r.commit_after.as_deref()])
+        .flatten()
+        .collect();
+    if let Err(e) = checkpoint::retain_checkpoints(project_path, session_id, &keep) {
+        log::warn!("[Truncate] Failed to delete stale checkpoints: {}", e);
+    }
     Ok(())
 }
 
@@ -216,8 +229,8 @@ fn truncate_session_to_prompt(
     project_id: &str,
     prompt_index: usize,
 ) -> Result<()> {
-    let claude_dir = get_claude_dir().context("Failed to get claude dir")?;
-    let project_dir = claude_dir.join("projects").join(project_id);
+    let project_dir =
+        get_session_project_dir(session_id, project_id).context("Failed to get claude dir")?;
     let session_path = project_dir.join(format!("{}.jsonl", session_id));
 
     if !session_path.exists() {
@@ -508,6 +521,8 @@ pub async fn record_prompt_sent(
     project_path: String,
     _prompt_text: String,
 ) -> Result<usize, String> {
+    // Sessions running in a worktree commit their rewind checkpoints there
+    let project_path = session_worktree::resolve_project_path(&session_id, &project_path);
     log::info!(
         "[Record Prompt] Recording prompt sent for session: {}",
         session_id
@@ -530,17 +545,6 @@ pub async fn record_prompt_sent(
         return Ok(prompt_index);
     }
 
-    // Ensure Git repository is initialized
-    simple_git::ensure_git_repo(&project_path)
-        .map_err(|e| format!("Failed to ensure Git repo: {}", e))?;
-
-    // IMPORTANT: Always get the LATEST commit
-    // This ensures we start from the correct state even if previous prompt made no changes
-    let commit_before = simple_git::git_current_commit(&project_path)
-        .map_err(|e| format!("Failed to get current commit: {}", e))?;
-
-    log::info!("[Record Prompt] Current git commit: {}", commit_before);
-
     // 🔧 FIX: Get prompt_index FIRST (from current JSONL state)
     // The new prompt hasn't been written to JSONL yet, so prompts.len() will be the index of the new prompt
     let prompts = extract_prompts_from_jsonl(&session_id, &project_id)
@@ -553,6 +557,19 @@ pub async fn record_prompt_sent(
         prompt_index
     );
 
+    // Snapshot the project as it is now, without committing to the user's branch
+    let commit_before = checkpoint::create_checkpoint(
+        &project_path,
+        &session_id,
+        &format!("[Claude Code] Before prompt #{}", prompt_index),
+    )
+    .map_err(|e| format!("Failed to create checkpoint: {}", e))?;
+
+    log::info!(
+        "[Record Prompt] Checkpoint before prompt: {}",
+        commit_before
+    );
+
     // Create git record
     let git_record = GitRecord {
         commit_before: commit_before.clone(),
@@ -583,6 +600,7 @@ pub async fn mark_prompt_completed(
     prompt_index: usize,
     prompt_text: Option<String>,
 ) -> Result<(), String> {
+    let project_path = session_worktree::resolve_project_path(&session_id, &project_path);
     log::info!("Marking prompt #{} completed", prompt_index);
 
     // Check if Git operations are disabled in config
@@ -596,30 +614,11 @@ pub async fn mark_prompt_completed(
         return Ok(());
     }
 
-    // Auto-commit any changes made by AI
-    // This ensures each prompt has a distinct git state
+    // Snapshot the changes made by AI so each prompt has a distinct checkpoint
     let commit_message =
         build_prompt_commit_message("[Claude Code]", prompt_text.as_deref(), prompt_index);
-    match simple_git::git_commit_changes(&project_path, &commit_message) {
-        Ok(true) => {
-            log::info!("Auto-committed changes after prompt #{}", prompt_index);
-        }
-        Ok(false) => {
-            log::debug!("No changes to commit after prompt #{}", prompt_index);
-        }
-        Err(e) => {
-            log::warn!(
-                "Failed to auto-commit after prompt #{}: {}",
-                prompt_index,
-                e
-            );
-            // Continue anyway, don't fail the whole operation
-        }
-    }
-
-    // Get current commit (state after AI completion and auto-commit)
-    let commit_after = simple_git::git_current_commit(&project_path)
-        .map_err(|e| format!("Failed to get current commit: {}", e))?;
+    let commit_after = checkpoint::create_checkpoint(&project_path, &session_id, &commit_message)
+        .map_err(|e| format!("Failed to create checkpoint: {}", e))?;
 
     // 🔧 FIX: Load existing git record using prompt_index (not hash!)
     let mut git_record = get_git_record(&session_id, &project_id, prompt_index)
@@ -650,6 +649,7 @@ pub async fn revert_to_prompt(
     prompt_index: usize,
     mode: RewindMode,
 ) -> Result<String, String> {
+    let project_path = session_worktree::resolve_project_path(&session_id, &project_path);
     log::info!(
         "Reverting to prompt #{} in session: {} with mode: {:?}",
         prompt_index,
@@ -709,8 +709,14 @@ pub async fn revert_to_prompt(
             // Truncate git records (remove records for prompts after this index)
             // Skip if Git operations are disabled
             if !git_operations_disabled {
-                truncate_git_records(&session_id, &project_id, &prompts, prompt_index)
-                    .map_err(|e| format!("Failed to truncate git records: {}", e))?;
+                truncate_git_records(
+                    &session_id,
+                    &project_id,
+                    &project_path,
+                    &prompts,
+                    prompt_index,
+                )
+                .map_err(|e| format!("Failed to truncate git records: {}", e))?;
             } else {
                 log::info!("Skipping git records truncation (Git operations disabled)");
             }
@@ -724,27 +730,27 @@ pub async fn revert_to_prompt(
         RewindMode::CodeOnly => {
             log::info!("Reverting code only (keeping messages) - revert to state before prompt #{}", prompt_index);
 
-            // 1. Stash any uncommitted changes
-            simple_git::git_stash_save(
+            // 1. Checkpoint the current state for atomic rollback on failure
+            let original_state = checkpoint::create_checkpoint(
                 &project_path,
-                &format!("Auto-stash before code revert to prompt #{}", prompt_index),
+                &session_id,
+                &format!(
+                    "[Claude Code] Before code revert to prompt #{}",
+                    prompt_index
+                ),
             )
-            .map_err(|e| format!("Failed to stash changes: {}", e))?;
-
-            // 2. Record original HEAD for atomic rollback on failure
-            let original_head = simple_git::git_current_commit(&project_path)
-                .map_err(|e| format!("Failed to get current commit: {}", e))?;
+            .map_err(|e| format!("Failed to create checkpoint: {}", e))?;
 
             log::info!(
-                "[Precise Revert] Original HEAD: {} (will rollback here on failure)",
-                &original_head[..8.min(original_head.len())]
+                "[Precise Revert] Original state: {} (will restore it on failure)",
+                &original_state[..8.min(original_state.len())]
             );
 
-            // 3. Load ALL git records for this session
+            // 2. Load ALL git records for this session
             let all_git_records = load_git_records(&session_id, &project_id)
                 .map_err(|e| format!("Failed to load git records: {}", e))?;
 
-            // 4. Filter records for prompt_index and onwards, then sort by index descending
+            // 3. Filter records for prompt_index and onwards, then sort by index descending
             let mut records_to_revert: Vec<(usize, GitRecord)> = all_git_records
                 .into_iter()
                 .filter(|(idx, _)| *idx >= prompt_index)
@@ -759,7 +765,7 @@ pub async fn revert_to_prompt(
                 prompt_index
             );
 
-            // 5. Revert each record's commit_before..commit_after in reverse order
+            // 4. Revert each record's commit_before..commit_after in reverse order
             let mut total_reverted = 0;
             let mut revert_failed = false;
             let mut failure_message = String::new();
@@ -774,7 +780,7 @@ pub async fn revert_to_prompt(
                     }
                 };
 
-                let has_changes = match simple_git::git_has_changes_between_commits(
+                let has_changes = match checkpoint::has_changes_between(
                     &project_path,
                     &record.commit_before,
                     &commit_after,
@@ -804,22 +810,16 @@ pub async fn revert_to_prompt(
                     &commit_after[..8.min(commit_after.len())]
                 );
 
-                let revert_result = simple_git::git_revert_range_with_retry(
+                let revert_result = checkpoint::revert_checkpoint_range(
                     &project_path,
                     &record.commit_before,
                     &commit_after,
-                    &format!("[Revert] 撤回提示词 #{} 的代码更改", idx),
-                    3, // Max 3 retries for Git lock conflicts
                 );
 
                 match revert_result {
                     Ok(result) if result.success => {
                         total_reverted += result.commits_reverted;
-                        log::info!(
-                            "[Precise Revert] Successfully reverted prompt #{} ({} commits)",
-                            idx,
-                            result.commits_reverted
-                        );
+                        log::info!("[Precise Revert] Successfully reverted prompt #{}", idx);
                     }
                     Ok(result) => {
                         log::warn!(
@@ -840,13 +840,13 @@ pub async fn revert_to_prompt(
                 }
             }
 
-            // 6. If revert failed, rollback to original HEAD (atomic operation)
+            // 5. If revert failed, restore the original state (atomic operation)
             if revert_failed {
                 log::warn!(
-                    "[Precise Revert] Rolling back to original HEAD {} due to failure",
-                    &original_head[..8.min(original_head.len())]
+                    "[Precise Revert] Restoring original state {} due to failure",
+                    &original_state[..8.min(original_state.len())]
                 );
-                simple_git::git_reset_hard(&project_path, &original_head)
+                checkpoint::restore_checkpoint(&project_path, &original_state)
                     .map_err(|e| format!("Failed to rollback: {}", e))?;
 
                 return Err(format!(
@@ -856,7 +856,7 @@ pub async fn revert_to_prompt(
             }
 
             log::info!(
-                "Successfully reverted code to state before prompt #{} (reverted {} commits from {} prompts)",
+                "Successfully reverted code to state before prompt #{} (reverted {} of {} prompts)",
                 prompt_index,
                 total_reverted,
                 records_to_revert.len()
@@ -866,27 +866,27 @@ pub async fn revert_to_prompt(
         RewindMode::Both => {
             log::info!("Reverting both conversation and code - revert to state before prompt #{}", prompt_index);
 
-            // 1. Stash any uncommitted changes
-            simple_git::git_stash_save(
+            // 1. Checkpoint the current state for atomic rollback on failure
+            let original_state = checkpoint::create_checkpoint(
                 &project_path,
-                &format!("Auto-stash before full revert to prompt #{}", prompt_index),
+                &session_id,
+                &format!(
+                    "[Claude Code] Before full revert to prompt #{}",
+                    prompt_index
+                ),
             )
-            .map_err(|e| format!("Failed to stash changes: {}", e))?;
-
-            // 2. Record original HEAD for atomic rollback on failure
-            let original_head = simple_git::git_current_commit(&project_path)
-                .map_err(|e| format!("Failed to get current commit: {}", e))?;
+            .map_err(|e| format!("Failed to create checkpoint: {}", e))?;
 
             log::info!(
-                "[Precise Revert] Original HEAD: {} (will rollback here on failure)",
-                &original_head[..8.min(original_head.len())]
+                "[Precise Revert] Original state: {} (will restore it on failure)",
+                &original_state[..8.min(original_state.len())]
             );
 
-            // 3. Load ALL git records for this session
+            // 2. Load ALL git records for this session
             let all_git_records = load_git_records(&session_id, &project_id)
                 .map_err(|e| format!("Failed to load git records: {}", e))?;
 
-            // 4. Filter records for prompt_index and onwards, then sort by index descending
+            // 3. Filter records for prompt_index and onwards, then sort by index descending
             let mut records_to_revert: Vec<(usize, GitRecord)> = all_git_records
                 .into_iter()
                 .filter(|(idx, _)| *idx >= prompt_index)
@@ -901,7 +901,7 @@ pub async fn revert_to_prompt(
                 prompt_index
             );
 
-            // 5. Revert each record's commit_before..commit_after in reverse order
+            // 4. Revert each record's commit_before..commit_after in reverse order
             let mut total_reverted = 0;
             let mut revert_failed = false;
             let mut failure_message = String::new();
@@ -916,7 +916,7 @@ pub async fn revert_to_prompt(
                     }
                 };
 
-                let has_changes = match simple_git::git_has_changes_between_commits(
+                let has_changes = match checkpoint::has_changes_between(
                     &project_path,
                     &record.commit_before,
                     &commit_after,
@@ -946,22 +946,16 @@ pub async fn revert_to_prompt(
                     &commit_after[..8.min(commit_after.len())]
                 );
 
-                let revert_result = simple_git::git_revert_range_with_retry(
+                let revert_result = checkpoint::revert_checkpoint_range(
                     &project_path,
                     &record.commit_before,
                     &commit_after,
-                    &format!("[Revert] 撤回提示词 #{} 的代码更改", idx),
-                    3, // Max 3 retries for Git lock conflicts
                 );
 
                 match revert_result {
                     Ok(result) if result.success => {
                         total_reverted += result.commits_reverted;
-                        log::info!(
-                            "[Precise Revert] Successfully reverted prompt #{} ({} commits)",
-                            idx,
-                            result.commits_reverted
-                        );
+                        log::info!("[Precise Revert] Successfully reverted prompt #{}", idx);
                     }
                     Ok(result) => {
                         log::warn!(
@@ -982,13 +976,13 @@ pub async fn revert_to_prompt(
                 }
             }
 
-            // 6. If revert failed, rollback to original HEAD (atomic operation)
+            // 5. If revert failed, restore the original state (atomic operation)
             if revert_failed {
                 log::warn!(
-                    "[Precise Revert] Rolling back to original HEAD {} due to failure",
-                    &original_head[..8.min(original_head.len())]
+                    "[Precise Revert] Restoring original state {} due to failure",
+                    &original_state[..8.min(original_state.len())]
                 );
-                simple_git::git_reset_hard(&project_path, &original_head)
+                checkpoint::restore_checkpoint(&project_path, &original_state)
                     .map_err(|e| format!("Failed to rollback: {}", e))?;
 
                 return Err(format!(
@@ -998,13 +992,13 @@ pub async fn revert_to_prompt(
             }
 
             log::info!(
-                "Successfully reverted code to state before prompt #{} (reverted {} commits from {} prompts)",
+                "Successfully reverted code to state before prompt #{} (reverted {} of {} prompts)",
                 prompt_index,
                 total_reverted,
                 records_to_revert.len()
             );
 
-            // 7. Truncate session messages (delete prompt #N and all after)
+            // 6. Truncate session messages (delete prompt #N and all after)
             // 🔧 ATOMIC PROTECTION: If session truncation fails, rollback Git changes
             if let Err(e) = truncate_session_to_prompt(&session_id, &project_id, prompt_index) {
                 log::error!(
@@ -1013,7 +1007,9 @@ pub async fn revert_to_prompt(
                 );
 
                 // Attempt to rollback Git changes
-                if let Err(rollback_err) = simple_git::git_reset_hard(&project_path, &original_head) {
+                if let Err(rollback_err) =
+                    checkpoint::restore_checkpoint(&project_path, &original_state)
+                {
                     log::error!("[CRITICAL] Git rollback failed: {}", rollback_err);
                     return Err(format!(
                         "会话文件截断失败，且 Git 回滚也失败，仓库可能处于不一致状态。\n\
@@ -1031,18 +1027,26 @@ pub async fn revert_to_prompt(
                 ));
             }
 
-            // 8. Truncate git records
+            // 7. Truncate git records
             // 🔧 ATOMIC PROTECTION: If git records truncation fails, rollback Git changes
             // Note: Session file is already truncated at this point, cannot easily rollback
             if !git_operations_disabled {
-                if let Err(e) = truncate_git_records(&session_id, &project_id, &prompts, prompt_index) {
+                if let Err(e) = truncate_git_records(
+                    &session_id,
+                    &project_id,
+                    &project_path,
+                    &prompts,
+                    prompt_index,
+                ) {
                     log::error!(
                         "[Atomic Rollback] Git records truncation failed, rolling back Git: {}",
                         e
                     );
 
                     // Attempt to rollback Git changes
-                    if let Err(rollback_err) = simple_git::git_reset_hard(&project_path, &original_head) {
+                    if let Err(rollback_err) =
+                        checkpoint::restore_checkpoint(&project_path, &original_state)
+                    {
                         log::error!("[CRITICAL] Git rollback failed: {}", rollback_err);
                         return Err(format!(
                             "Git 记录截断失败，且 Git 回滚也失败。\n\
@@ -1199,10 +1203,8 @@ pub async fn check_rewind_capabilities(
 /// This function reads the .jsonl file and extracts all user prompts
 /// This is the single source of truth for all prompts (both CLI and project interface)
 fn extract_prompts_from_jsonl(session_id: &str, project_id: &str) -> Result<Vec<PromptRecord>> {
-    let claude_dir = get_claude_dir().context("Failed to get claude dir")?;
-    let session_path = claude_dir
-        .join("projects")
-        .join(project_id)
+    let session_path = get_session_project_dir(session_id, project_id)
+        .context("Failed to get claude dir")?
         .join(format!("{}.jsonl", session_id));
 
     if !session_path.exists() {
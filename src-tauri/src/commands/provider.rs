@@ -225,6 +225,23 @@ pub fn reorder_provider_configs(ids: Vec<String>) -> Result<String, String> {
     Ok("成功重新排序代理商配置".to_string())
 }
 
+// 当前生效的代理商预设 ID（按 ANTHROPIC_BASE_URL 匹配预设），供预算统计使用
+pub(crate) fn active_provider_id() -> Option<String> {
+    let settings = load_settings().ok()?;
+    let base_url = settings
+        .get("env")
+        .and_then(|env| env.get("ANTHROPIC_BASE_URL"))
+        .and_then(|v| v.as_str())?
+        .trim_end_matches('/')
+        .to_string();
+
+    load_legacy_providers()
+        .ok()?
+        .into_iter()
+        .find(|p| p.base_url.trim_end_matches('/') == base_url)
+        .map(|p| p.id)
+}
+
 // 获取当前代理商配置（从settings.json的env字段和apiKeyHelper字段读取）
 #[command]
 pub fn get_current_provider_config() -> Result<CurrentConfig, String> {
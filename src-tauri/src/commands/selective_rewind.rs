@@ -0,0 +1,309 @@
+//! Selective Rewind
+//!
+//! Per-file view of the rewind checkpoints of a Claude, Codex or Gemini
+//! session: which files each prompt changed between its `commit_before` and
+//! `commit_after`, with a diff for each file. The rewind restores only the
+//! chosen paths to their state before a prompt and keeps the agent's changes
+//! to every other file; the conversation is left as it is.
+
+use serde::Serialize;
+
+use super::checkpoint::{self, CheckpointFileChange};
+use super::claude::encode_project_path;
+use super::prompt_tracker::load_execution_config;
+use super::session_export::{load_session_git_records, ExportGitRecord};
+use super::session_timeline::SessionEngine;
+use super::session_worktree;
+use super::simple_git::RevertResult;
+
+/// Files changed by one prompt
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptFileChanges {
+    pub prompt_index: usize,
+    pub commit_before: String,
+    pub commit_after: String,
+    pub files: Vec<CheckpointFileChange>,
+}
+
+/// Project directory and rewind records of a session, resolved to its
+/// worktree when it runs in one
+fn load_rewind_records(
+    engine: SessionEngine,
+    session_id: &str,
+    project_id: Option<String>,
+    project_path: &str,
+) -> Result<(String, Vec<ExportGitRecord>), String> {
+    let project_path = session_worktree::resolve_project_path(session_id, project_path);
+    let project_id = match engine {
+        SessionEngine::Claude => project_id.unwrap_or_else(|| encode_project_path(&project_path)),
+        _ => String::new(),
+    };
+    let records = load_session_git_records(engine, session_id, &project_id)?;
+    Ok((project_path, records))
+}
+
+/// The checkpoint a prompt ended on, if it changed anything
+fn completed_range(record: &ExportGitRecord) -> Option<&str> {
+    record
+        .commit_after
+        .as_deref()
+        .filter(|after| *after != record.commit_before)
+}
+
+/// Files changed by each prompt of a session, or by `prompt_index` only
+#[tauri::command]
+pub async fn get_prompt_file_changes(
+    engine: String,
+    session_id: String,
+    project_id: Option<String>,
+    project_path: String,
+    prompt_index: Option<usize>,
+) -> Result<Vec<PromptFileChanges>, String> {
+    let engine: SessionEngine = engine.parse()?;
+    let (project_path, records) =
+        load_rewind_records(engine, &session_id, project_id, &project_path)?;
+    prompt_file_changes(&project_path, &records, prompt_index)
+}
+
+fn prompt_file_changes(
+    project_path: &str,
+    records: &[ExportGitRecord],
+    prompt_index: Option<usize>,
+) -> Result<Vec<PromptFileChanges>, String> {
+    let mut changes = Vec::new();
+    for record in records
+        .iter()
+        .filter(|r| prompt_index.is_none_or(|index| r.prompt_index == index))
+    {
+        let Some(commit_after) = completed_range(record) else {
+            continue;
+        };
+        let files =
+            checkpoint::changed_files_between(project_path, &record.commit_before, commit_after)
+                .map_err(|e| format!("Failed to diff prompt #{}: {}", record.prompt_index, e))?;
+        changes.push(PromptFileChanges {
+            prompt_index: record.prompt_index,
+            commit_before: record.commit_before.clone(),
+            commit_after: commit_after.to_string(),
+            files,
+        });
+    }
+    Ok(changes)
+}
+
+/// Restores `paths` to their state before `prompt_index`, undoing what that
+/// prompt and every later one changed in them. Other files keep the agent's
+/// changes.
+#[tauri::command]
+pub async fn revert_prompt_files(
+    engine: String,
+    session_id: String,
+    project_id: Option<String>,
+    project_path: String,
+    prompt_index: usize,
+    paths: Vec<String>,
+) -> Result<RevertResult, String> {
+    let engine: SessionEngine = engine.parse()?;
+    if paths.is_empty() {
+        return Err("No files selected to rewind".to_string());
+    }
+
+    let execution_config =
+        load_execution_config().map_err(|e| format!("Failed to load execution config: {}", e))?;
+    if execution_config.disable_rewind_git_operations {
+        return Err("无法回滚代码：Git 操作已在配置中禁用。".to_string());
+    }
+
+    let (project_path, records) =
+        load_rewind_records(engine, &session_id, project_id, &project_path)?;
+    log::info!(
+        "[Selective Rewind] Restoring {} file(s) of {} session {} to before prompt #{}",
+        paths.len(),
+        engine.as_str(),
+        session_id,
+        prompt_index
+    );
+    revert_records(&project_path, &session_id, &records, prompt_index, &paths)
+}
+
+/// Reverse-applies `paths` of `prompt_index` and every later prompt, rolling
+/// the working tree back to where it was when one of them fails
+fn revert_records(
+    project_path: &str,
+    session_id: &str,
+    records: &[ExportGitRecord],
+    prompt_index: usize,
+    paths: &[String],
+) -> Result<RevertResult, String> {
+    if !records.iter().any(|r| r.prompt_index == prompt_index) {
+        return Err(format!(
+            "无法回滚代码：提示词 #{} 没有关联的 Git 记录（可能来自 CLI 终端）",
+            prompt_index
+        ));
+    }
+
+    // Checkpoint the current state for atomic rollback on failure
+    let original_state = checkpoint::create_checkpoint(
+        project_path,
+        session_id,
+        &format!("Before file rewind to prompt #{}", prompt_index),
+    )
+    .map_err(|e| format!("Failed to create checkpoint: {}", e))?;
+
+    // Undo newest prompts first so each reverse patch applies on top of the next
+    let mut reverted = 0;
+    for record in records
+        .iter()
+        .rev()
+        .filter(|r| r.prompt_index >= prompt_index)
+    {
+        let Some(commit_after) = completed_range(record) else {
+            continue;
+        };
+        let result = checkpoint::revert_checkpoint_paths(
+            project_path,
+            &record.commit_before,
+            commit_after,
+            paths,
+        );
+        let failure = match result {
+            Ok(result) if result.success => {
+                reverted += result.commits_reverted;
+                continue;
+            }
+            Ok(result) => result,
+            Err(e) => RevertResult {
+                success: false,
+                commits_reverted: 0,
+                new_commit: None,
+                message: e,
+                has_conflicts: false,
+            },
+        };
+
+        log::warn!(
+            "[Selective Rewind] Prompt #{} failed, restoring {}: {}",
+            record.prompt_index,
+            &original_state[..8.min(original_state.len())],
+            failure.message
+        );
+        checkpoint::restore_checkpoint(project_path, &original_state)
+            .map_err(|e| format!("Failed to rollback: {}", e))?;
+        return Ok(RevertResult {
+            message: format!("撤回失败，已回滚到操作前状态。原因: {}", failure.message),
+            ..failure
+        });
+    }
+
+    Ok(RevertResult {
+        success: true,
+        commits_reverted: reverted,
+        new_commit: None,
+        message: format!(
+            "已撤回 {} 个文件在 {} 个提示词中的更改",
+            paths.len(),
+            reverted
+        ),
+        has_conflicts: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    /// A git project with three prompts: the first edits `a.txt` and
+    /// `b.txt`, the second edits `a.txt` again and creates `c.txt`, the
+    /// third changes nothing
+    fn project_with_prompts(dir: &Path) -> (String, Vec<ExportGitRecord>) {
+        let project = dir.to_string_lossy().to_string();
+        super::super::simple_git::git_output(&project, &["init", "-q"]).unwrap();
+        fs::write(dir.join("a.txt"), "a0\n").unwrap();
+        fs::write(dir.join("b.txt"), "b0\n").unwrap();
+        let c0 = checkpoint::create_checkpoint(&project, "s", "prompt 0").unwrap();
+        fs::write(dir.join("a.txt"), "a1\n").unwrap();
+        fs::write(dir.join("b.txt"), "b1\n").unwrap();
+        let c1 = checkpoint::create_checkpoint(&project, "s", "prompt 1").unwrap();
+        fs::write(dir.join("a.txt"), "a2\n").unwrap();
+        fs::write(dir.join("c.txt"), "c2\n").unwrap();
+        let c2 = checkpoint::create_checkpoint(&project, "s", "prompt 2").unwrap();
+
+        let record = |prompt_index, before: &str, after: &str| ExportGitRecord {
+            prompt_index,
+            commit_before: before.to_string(),
+            commit_after: Some(after.to_string()),
+            timestamp: String::new(),
+        };
+        let records = vec![
+            record(0, &c0, &c1),
+            record(1, &c1, &c2),
+            record(2, &c2, &c2),
+        ];
+        (project, records)
+    }
+
+    #[test]
+    fn rewind_restores_only_the_chosen_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let (project, records) = project_with_prompts(dir.path());
+        let read = |name: &str| fs::read_to_string(dir.path().join(name)).ok();
+
+        let changes = prompt_file_changes(&project, &records, None).unwrap();
+        let files: Vec<(usize, Vec<&str>)> = changes
+            .iter()
+            .map(|c| {
+                let paths = c.files.iter().map(|f| f.path.as_str()).collect();
+                (c.prompt_index, paths)
+            })
+            .collect();
+        assert_eq!(
+            files,
+            vec![(0, vec!["a.txt", "b.txt"]), (1, vec!["a.txt", "c.txt"])]
+        );
+        assert_eq!(
+            prompt_file_changes(&project, &records, Some(1)).unwrap()[0].prompt_index,
+            1
+        );
+
+        // Undoing the file created by the second prompt deletes it
+        let result = revert_records(&project, "s", &records, 1, &["c.txt".to_string()]).unwrap();
+        assert!(result.success);
+        assert_eq!(read("c.txt"), None);
+        assert_eq!(read("a.txt").as_deref(), Some("a2\n"));
+
+        // Rewinding to the first prompt undoes both prompts for the chosen file
+        let result = revert_records(&project, "s", &records, 0, &["a.txt".to_string()]).unwrap();
+        assert!(result.success);
+        assert_eq!(result.commits_reverted, 2);
+        assert_eq!(read("a.txt").as_deref(), Some("a0\n"));
+        assert_eq!(read("b.txt").as_deref(), Some("b1\n"));
+    }
+
+    #[test]
+    fn rewind_without_changes_is_a_no_op() {
+        let dir = tempfile::tempdir().unwrap();
+        let (project, records) = project_with_prompts(dir.path());
+        let read = |name: &str| fs::read_to_string(dir.path().join(name)).unwrap();
+
+        // The last prompt changed nothing, and the second never touched b.txt
+        for (prompt_index, path) in [(2, "a.txt"), (1, "b.txt")] {
+            let result =
+                revert_records(&project, "s", &records, prompt_index, &[path.to_string()]).unwrap();
+            assert!(result.success);
+            assert_eq!(result.commits_reverted, 0);
+        }
+        assert_eq!(
+            (read("a.txt"), read("b.txt"), read("c.txt")),
+            ("a2\n".to_string(), "b1\n".to_string(), "c2\n".to_string())
+        );
+        assert!(prompt_file_changes(&project, &records, Some(2))
+            .unwrap()
+            .is_empty());
+
+        // Prompts without a record cannot be rewound
+        assert!(revert_records(&project, "s", &records, 5, &["a.txt".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,854 @@
+//! Session export (Markdown / HTML / JSON bundle)
+//!
+//! Renders any engine's session - loaded through the unified timeline - into
+//! a shareable document for code reviews and incident reports:
+//!
+//! - **Markdown**: readable transcript, tool calls as fenced blocks
+//! - **HTML**: self-contained page, tool calls and diffs collapsible
+//! - **JSON**: portable bundle with the unified session and its git prompt records
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use super::codex::git_ops::load_codex_git_records;
+use super::gemini::git_ops::load_gemini_git_records;
+use super::prompt_tracker::load_git_records;
+use super::session_timeline::{
+    loader_for, EventKind, SessionEngine, UnifiedEvent, UnifiedSession, UnifiedUsage,
+};
+
+/// Tool output longer than this is truncated in Markdown/HTML exports
+const MAX_TOOL_OUTPUT_CHARS: usize = 4000;
+/// Bump when the JSON bundle layout changes
+const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    Json,
+}
+
+impl ExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Html => "html",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// Export options
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionExportOptions {
+    /// Include thinking/reasoning blocks
+    #[serde(default)]
+    pub include_thinking: bool,
+    /// Include tool outputs (inputs are always included)
+    #[serde(default = "default_true")]
+    pub include_tool_results: bool,
+    /// Include system/context notices
+    #[serde(default)]
+    pub include_system: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for SessionExportOptions {
+    fn default() -> Self {
+        Self {
+            include_thinking: false,
+            include_tool_results: true,
+            include_system: false,
+        }
+    }
+}
+
+/// Git state recorded around a prompt, identical for all engines
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportGitRecord {
+    pub prompt_index: usize,
+    pub commit_before: String,
+    pub commit_after: Option<String>,
+    pub timestamp: String,
+}
+
+/// Portable JSON bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionExportBundle {
+    pub format_version: u32,
+    pub exported_at: String,
+    pub app_version: String,
+    pub session: UnifiedSession,
+    pub total_usage: UnifiedUsage,
+    pub git_records: Vec<ExportGitRecord>,
+}
+
+/// Result returned to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionExportResult {
+    pub format: ExportFormat,
+    /// Suggested file name (`<engine>-<session>.<ext>`)
+    pub file_name: String,
+    /// Rendered document; empty when written to `output_path`
+    pub content: String,
+    /// Where the document was written, if requested
+    pub output_path: Option<String>,
+}
+
+// ============================================================================
+// Git Records
+// ============================================================================
+
+/// Loads the rewind git records of a session, whatever engine produced it
+pub fn load_export_git_records(session: &UnifiedSession) -> Vec<ExportGitRecord> {
+    // Claude stores records per project directory
+    let project_id = Path::new(&session.source_path)
+        .parent()
+        .and_then(|p| p.file_name())
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+    load_session_git_records(session.engine, &session.id, project_id).unwrap_or_default()
+}
+
+/// Rewind git records of a session sorted by prompt index; `project_id` is
+/// only used for Claude sessions
+pub fn load_session_git_records(
+    engine: SessionEngine,
+    session_id: &str,
+    project_id: &str,
+) -> Result<Vec<ExportGitRecord>, String> {
+    let mut records: Vec<ExportGitRecord> = match engine {
+        SessionEngine::Claude => load_git_records(session_id, project_id)
+            .map_err(|e| format!("Failed to load git records: {}", e))?
+            .into_iter()
+            .map(|(prompt_index, r)| ExportGitRecord {
+                prompt_index,
+                commit_before: r.commit_before,
+                commit_after: r.commit_after,
+                timestamp: chrono::DateTime::from_timestamp(r.timestamp, 0)
+                    .map(|dt| dt.to_rfc3339())
+                    .unwrap_or_else(|| r.timestamp.to_string()),
+            })
+            .collect(),
+        SessionEngine::Codex => load_codex_git_records(session_id)?
+            .records
+            .into_iter()
+            .map(|r| ExportGitRecord {
+                prompt_index: r.prompt_index,
+                commit_before: r.commit_before,
+                commit_after: r.commit_after,
+                timestamp: r.timestamp,
+            })
+            .collect(),
+        SessionEngine::Gemini => load_gemini_git_records(session_id)?
+            .records
+            .into_iter()
+            .map(|r| ExportGitRecord {
+                prompt_index: r.prompt_index,
+                commit_before: r.commit_before,
+                commit_after: r.commit_after,
+                timestamp: r.timestamp,
+            })
+            .collect(),
+    };
+    records.sort_by_key(|r| r.prompt_index);
+    Ok(records)
+}
+
+// ============================================================================
+// Rendering Helpers
+// ============================================================================
+
+fn truncate_chars(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max).collect();
+    truncated.push_str("\n… (truncated)");
+    truncated
+}
+
+fn prefixed_lines(text: &str, prefix: char) -> String {
+    text.lines()
+        .map(|line| format!("{}{}", prefix, line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn replacement_diff(path: &str, old: &str, new: &str) -> String {
+    let mut diff = format!("--- a/{}\n+++ b/{}\n", path, path);
+    if !old.is_empty() {
+        diff.push_str(&prefixed_lines(old, '-'));
+        diff.push('\n');
+    }
+    if !new.is_empty() {
+        diff.push_str(&prefixed_lines(new, '+'));
+        diff.push('\n');
+    }
+    diff
+}
+
+/// Builds a diff for file-editing tool calls (Claude Edit/MultiEdit/Write,
+/// Gemini replace/write_file, Codex apply_patch)
+fn tool_call_diff(name: &str, input: &serde_json::Value) -> Option<String> {
+    let str_field = |key: &str| input.get(key).and_then(|v| v.as_str());
+    let path = str_field("file_path")
+        .or_else(|| str_field("absolute_path"))
+        .or_else(|| str_field("path"))
+        .unwrap_or("file");
+
+    match name {
+        "Edit" | "replace" => Some(replacement_diff(
+            path,
+            str_field("old_string")?,
+            str_field("new_string")?,
+        )),
+        "MultiEdit" => {
+            let edits = input.get("edits")?.as_array()?;
+            Some(
+                edits
+                    .iter()
+                    .map(|edit| {
+                        replacement_diff(
+                            path,
+                            edit.get("old_string").and_then(|v| v.as_str()).unwrap_or(""),
+                            edit.get("new_string").and_then(|v| v.as_str()).unwrap_or(""),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )
+        }
+        "Write" | "write_file" => Some(replacement_diff(path, "", str_field("content")?)),
+        _ => {
+            // apply_patch arrives as raw input, {"input": ...} or ["apply_patch", patch]
+            let patch = match input {
+                serde_json::Value::String(s) => Some(s.as_str()),
+                _ => str_field("input").or_else(|| {
+                    input
+                        .get("command")
+                        .and_then(|c| c.as_array())
+                        .and_then(|parts| parts.iter().rev().find_map(|p| p.as_str()))
+                }),
+            }?;
+            patch.contains("*** Begin Patch").then(|| patch.to_string())
+        }
+    }
+}
+
+fn tool_input_text(input: &serde_json::Value) -> String {
+    match input {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Null => String::new(),
+        other => serde_json::to_string_pretty(other).unwrap_or_else(|_| other.to_string()),
+    }
+}
+
+fn include_event(event: &UnifiedEvent, options: &SessionExportOptions) -> bool {
+    match event.kind {
+        EventKind::Thinking { .. } => options.include_thinking,
+        EventKind::ToolResult { .. } => options.include_tool_results,
+        EventKind::System { .. } => options.include_system,
+        EventKind::Usage => false,
+        _ => true,
+    }
+}
+
+fn engine_label(engine: SessionEngine) -> &'static str {
+    match engine {
+        SessionEngine::Claude => "Claude Code",
+        SessionEngine::Codex => "OpenAI Codex",
+        SessionEngine::Gemini => "Gemini CLI",
+    }
+}
+
+// ============================================================================
+// Markdown
+// ============================================================================
+
+/// Fenced code block whose fence is longer than any backtick run in `body`
+fn md_fence(lang: &str, body: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in body.chars() {
+        if c == '`' {
+            run += 1;
+            longest = longest.max(run);
+        } else {
+            run = 0;
+        }
+    }
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{}{}\n{}\n{}\n", fence, lang, body.trim_end_matches('\n'), fence)
+}
+
+pub fn render_markdown(
+    session: &UnifiedSession,
+    git_records: &[ExportGitRecord],
+    options: &SessionExportOptions,
+) -> String {
+    let mut out = String::new();
+    let title = session
+        .first_message()
+        .map(|m| truncate_chars(m.lines().next().unwrap_or(""), 80))
+        .unwrap_or_else(|| session.id.clone());
+    out.push_str(&format!("# {}\n\n", title));
+
+    out.push_str("| | |\n|---|---|\n");
+    out.push_str(&format!("| Engine | {} |\n", engine_label(session.engine)));
+    out.push_str(&format!("| Session | `{}` |\n", session.id));
+    if let Some(project) = &session.project_path {
+        out.push_str(&format!("| Project | `{}` |\n", project));
+    }
+    if let Some(model) = &session.model {
+        out.push_str(&format!("| Model | {} |\n", model));
+    }
+    if let Some(started) = &session.started_at {
+        out.push_str(&format!("| Started | {} |\n", started));
+    }
+    if let Some(updated) = &session.updated_at {
+        out.push_str(&format!("| Updated | {} |\n", updated));
+    }
+    let usage = session.total_usage();
+    if !usage.is_empty() {
+        out.push_str(&format!(
+            "| Tokens | {} in / {} out / {} cache write / {} cache read |\n",
+            usage.input_tokens,
+            usage.output_tokens,
+            usage.cache_creation_tokens,
+            usage.cache_read_tokens
+        ));
+    }
+    out.push('\n');
+
+    let mut prompt_index = 0;
+    for event in session.events.iter().filter(|e| include_event(e, options)) {
+        let time = event
+            .timestamp
+            .as_deref()
+            .map(|t| format!(" · {}", t))
+            .unwrap_or_default();
+
+        match &event.kind {
+            EventKind::UserMessage { text } => {
+                out.push_str(&format!("## User #{}{}\n\n{}\n\n", prompt_index + 1, time, text));
+                if let Some(record) = git_records.iter().find(|r| r.prompt_index == prompt_index) {
+                    out.push_str(&format!(
+                        "> git: `{}` → `{}`\n\n",
+                        short_commit(&record.commit_before),
+                        record
+                            .commit_after
+                            .as_deref()
+                            .map(short_commit)
+                            .unwrap_or("pending")
+                    ));
+                }
+                prompt_index += 1;
+            }
+            EventKind::AssistantMessage { text } => {
+                let model = event
+                    .model
+                    .as_deref()
+                    .map(|m| format!(" ({})", m))
+                    .unwrap_or_default();
+                out.push_str(&format!("### Assistant{}{}\n\n{}\n\n", model, time, text));
+            }
+            EventKind::Thinking { text } => {
+                out.push_str("<details><summary>Thinking</summary>\n\n");
+                for line in text.lines() {
+                    out.push_str(&format!("> {}\n", line));
+                }
+                out.push_str("\n</details>\n\n");
+            }
+            EventKind::ToolCall { name, input, .. } => {
+                out.push_str(&format!("**Tool call: `{}`**{}\n\n", name, time));
+                match tool_call_diff(name, input) {
+                    Some(diff) => out.push_str(&md_fence("diff", &diff)),
+                    None => out.push_str(&md_fence("json", &tool_input_text(input))),
+                }
+                out.push('\n');
+            }
+            EventKind::ToolResult {
+                output, is_error, ..
+            } => {
+                let label = if *is_error { "Tool error" } else { "Tool result" };
+                out.push_str(&format!("<details><summary>{}</summary>\n\n", label));
+                out.push_str(&md_fence("", &truncate_chars(output, MAX_TOOL_OUTPUT_CHARS)));
+                out.push_str("\n</details>\n\n");
+            }
+            EventKind::System { subtype, text } => {
+                let label = subtype.as_deref().unwrap_or("system");
+                out.push_str(&format!("_[{}]_ {}\n\n", label, text.trim()));
+            }
+            EventKind::Usage => {}
+        }
+    }
+
+    out
+}
+
+fn short_commit(commit: &str) -> &str {
+    &commit[..commit.len().min(8)]
+}
+
+// ============================================================================
+// HTML
+// ============================================================================
+
+fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn html_diff(diff: &str) -> String {
+    let lines = diff
+        .lines()
+        .map(|line| {
+            let class = if line.starts_with("+++") || line.starts_with("---") || line.starts_with("***") {
+                "meta"
+            } else if line.starts_with('+') {
+                "add"
+            } else if line.starts_with('-') {
+                "del"
+            } else if line.starts_with("@@") {
+                "hunk"
+            } else {
+                "ctx"
+            };
+            format!("<span class=\"{}\">{}</span>", class, html_escape(line))
+        })
+        .collect::<Vec<_>>()
+        // Spans are block-level, a newline would double the spacing
+        .join("");
+    format!("<pre class=\"diff\">{}</pre>", lines)
+}
+
+const HTML_STYLE: &str = r#"
+body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", sans-serif; max-width: 960px; margin: 2rem auto; padding: 0 1rem; color: #1f2328; background: #fff; line-height: 1.55; }
+h1 { font-size: 1.5rem; margin-bottom: .5rem; }
+table.meta { border-collapse: collapse; margin-bottom: 1.5rem; font-size: .9rem; }
+table.meta td { padding: .2rem .8rem .2rem 0; vertical-align: top; }
+table.meta td:first-child { color: #656d76; }
+.event { margin: .75rem 0; padding: .75rem 1rem; border-radius: 8px; border: 1px solid #d0d7de; }
+.event .head { font-size: .8rem; color: #656d76; margin-bottom: .35rem; }
+.user { background: #ddf4ff; border-color: #54aeff; }
+.assistant { background: #fff; }
+.thinking { background: #f6f8fa; color: #57606a; font-style: italic; }
+.system { background: #fff8c5; font-size: .85rem; }
+.tool { background: #f6f8fa; }
+.tool.error { border-color: #ff8182; }
+.text { white-space: pre-wrap; word-wrap: break-word; }
+details summary { cursor: pointer; font-weight: 600; font-size: .9rem; }
+pre { background: #f6f8fa; padding: .75rem; border-radius: 6px; overflow-x: auto; font-size: .8rem; }
+pre.diff .add { color: #116329; background: #dafbe1; display: block; }
+pre.diff .del { color: #82071e; background: #ffebe9; display: block; }
+pre.diff .meta, pre.diff .hunk { color: #8250df; display: block; }
+pre.diff .ctx { display: block; }
+.git { font-family: monospace; font-size: .75rem; color: #656d76; margin-top: .35rem; }
+@media (prefers-color-scheme: dark) {
+  body { background: #0d1117; color: #e6edf3; }
+  .event { border-color: #30363d; }
+  .user { background: #0c2d6b; border-color: #1f6feb; }
+  .assistant { background: #0d1117; }
+  .thinking, .tool, pre { background: #161b22; color: #c9d1d9; }
+  .system { background: #3b2e00; }
+  pre.diff .add { color: #aff5b4; background: #033a16; }
+  pre.diff .del { color: #ffdcd7; background: #67060c; }
+}
+"#;
+
+pub fn render_html(
+    session: &UnifiedSession,
+    git_records: &[ExportGitRecord],
+    options: &SessionExportOptions,
+) -> String {
+    let title = session
+        .first_message()
+        .map(|m| truncate_chars(m.lines().next().unwrap_or(""), 80))
+        .unwrap_or_else(|| session.id.clone());
+
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+    out.push_str(&format!("<title>{}</title>\n", html_escape(&title)));
+    out.push_str(&format!("<style>{}</style>\n</head>\n<body>\n", HTML_STYLE));
+    out.push_str(&format!("<h1>{}</h1>\n<table class=\"meta\">\n", html_escape(&title)));
+
+    let mut meta_row = |label: &str, value: &str| {
+        out.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td></tr>\n",
+            label,
+            html_escape(value)
+        ));
+    };
+    meta_row("Engine", engine_label(session.engine));
+    meta_row("Session", &session.id);
+    if let Some(project) = &session.project_path {
+        meta_row("Project", project);
+    }
+    if let Some(model) = &session.model {
+        meta_row("Model", model);
+    }
+    if let Some(started) = &session.started_at {
+        meta_row("Started", started);
+    }
+    if let Some(updated) = &session.updated_at {
+        meta_row("Updated", updated);
+    }
+    let usage = session.total_usage();
+    if !usage.is_empty() {
+        meta_row(
+            "Tokens",
+            &format!(
+                "{} in / {} out / {} cache write / {} cache read",
+                usage.input_tokens,
+                usage.output_tokens,
+                usage.cache_creation_tokens,
+                usage.cache_read_tokens
+            ),
+        );
+    }
+    out.push_str("</table>\n");
+
+    let mut prompt_index = 0;
+    for event in session.events.iter().filter(|e| include_event(e, options)) {
+        let time = event.timestamp.as_deref().unwrap_or("");
+
+        match &event.kind {
+            EventKind::UserMessage { text } => {
+                out.push_str(&format!(
+                    "<div class=\"event user\"><div class=\"head\">User #{} · {}</div><div class=\"text\">{}</div>",
+                    prompt_index + 1,
+                    html_escape(time),
+                    html_escape(text)
+                ));
+                if let Some(record) = git_records.iter().find(|r| r.prompt_index == prompt_index) {
+                    out.push_str(&format!(
+                        "<div class=\"git\">git {} → {}</div>",
+                        html_escape(short_commit(&record.commit_before)),
+                        html_escape(
+                            record
+                                .commit_after
+                                .as_deref()
+                                .map(short_commit)
+                                .unwrap_or("pending")
+                        )
+                    ));
+                }
+                out.push_str("</div>\n");
+                prompt_index += 1;
+            }
+            EventKind::AssistantMessage { text } => {
+                out.push_str(&format!(
+                    "<div class=\"event assistant\"><div class=\"head\">Assistant{} · {}</div><div class=\"text\">{}</div></div>\n",
+                    event
+                        .model
+                        .as_deref()
+                        .map(|m| format!(" ({})", html_escape(m)))
+                        .unwrap_or_default(),
+                    html_escape(time),
+                    html_escape(text)
+                ));
+            }
+            EventKind::Thinking { text } => {
+                out.push_str(&format!(
+                    "<div class=\"event thinking\"><details><summary>Thinking</summary><div class=\"text\">{}</div></details></div>\n",
+                    html_escape(text)
+                ));
+            }
+            EventKind::ToolCall { name, input, .. } => {
+                let body = match tool_call_diff(name, input) {
+                    Some(diff) => html_diff(&diff),
+                    None => format!("<pre>{}</pre>", html_escape(&tool_input_text(input))),
+                };
+                out.push_str(&format!(
+                    "<div class=\"event tool\"><details><summary>Tool call: {}</summary><div class=\"head\">{}</div>{}</details></div>\n",
+                    html_escape(name),
+                    html_escape(time),
+                    body
+                ));
+            }
+            EventKind::ToolResult {
+                output, is_error, ..
+            } => {
+                out.push_str(&format!(
+                    "<div class=\"event tool{}\"><details><summary>{}</summary><pre>{}</pre></details></div>\n",
+                    if *is_error { " error" } else { "" },
+                    if *is_error { "Tool error" } else { "Tool result" },
+                    html_escape(&truncate_chars(output, MAX_TOOL_OUTPUT_CHARS))
+                ));
+            }
+            EventKind::System { subtype, text } => {
+                out.push_str(&format!(
+                    "<div class=\"event system\"><div class=\"head\">{}</div><div class=\"text\">{}</div></div>\n",
+                    html_escape(subtype.as_deref().unwrap_or("system")),
+                    html_escape(text.trim())
+                ));
+            }
+            EventKind::Usage => {}
+        }
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+// ============================================================================
+// JSON Bundle
+// ============================================================================
+
+pub fn build_bundle(session: UnifiedSession, git_records: Vec<ExportGitRecord>) -> SessionExportBundle {
+    SessionExportBundle {
+        format_version: BUNDLE_FORMAT_VERSION,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        total_usage: session.total_usage(),
+        session,
+        git_records,
+    }
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Exports a session of any engine as Markdown, HTML or a JSON bundle.
+///
+/// When `output_path` is given the document is written there, otherwise the
+/// rendered content is returned for the frontend to save or copy.
+#[tauri::command]
+pub async fn export_session(
+    engine: String,
+    session_id: String,
+    format: ExportFormat,
+    project_hint: Option<String>,
+    output_path: Option<String>,
+    options: Option<SessionExportOptions>,
+) -> Result<SessionExportResult, String> {
+    let engine: SessionEngine = engine.parse()?;
+    let options = options.unwrap_or_default();
+    log::info!(
+        "Exporting {} session {} as {:?}",
+        engine.as_str(),
+        session_id,
+        format
+    );
+
+    let session = loader_for(engine).load(&session_id, project_hint.as_deref())?;
+    let git_records = load_export_git_records(&session);
+    let file_name = format!("{}-{}.{}", engine.as_str(), session.id, format.extension());
+
+    let content = match format {
+        ExportFormat::Markdown => render_markdown(&session, &git_records, &options),
+        ExportFormat::Html => render_html(&session, &git_records, &options),
+        ExportFormat::Json => serde_json::to_string_pretty(&build_bundle(session, git_records))
+            .map_err(|e| format!("Failed to serialize export bundle: {}", e))?,
+    };
+
+    match output_path {
+        Some(path) => {
+            if let Some(parent) = Path::new(&path).parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create export directory: {}", e))?;
+            }
+            fs::write(&path, &content).map_err(|e| format!("Failed to write export: {}", e))?;
+            log::info!("Session exported to {}", path);
+            Ok(SessionExportResult {
+                format,
+                file_name,
+                content: String::new(),
+                output_path: Some(path),
+            })
+        }
+        None => Ok(SessionExportResult {
+            format,
+            file_name,
+            content,
+            output_path: None,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fixture() -> (UnifiedSession, Vec<ExportGitRecord>) {
+        let mut session = UnifiedSession::new(
+            "abc".to_string(),
+            SessionEngine::Claude,
+            Path::new("/tmp/-repo/abc.jsonl"),
+        );
+        session.project_path = Some("/repo".to_string());
+        session.push(
+            Some("2025-01-01T00:00:00Z".to_string()),
+            EventKind::UserMessage {
+                text: "Fix <b>bold</b> & \"quotes\"".to_string(),
+            },
+            None,
+            None,
+        );
+        session.push(
+            Some("2025-01-01T00:00:01Z".to_string()),
+            EventKind::Thinking {
+                text: "look at lib.rs".to_string(),
+            },
+            Some("claude-sonnet-4".to_string()),
+            None,
+        );
+        session.push(
+            Some("2025-01-01T00:00:02Z".to_string()),
+            EventKind::ToolCall {
+                call_id: "t1".to_string(),
+                name: "Edit".to_string(),
+                input: json!({
+                    "file_path": "src/lib.rs",
+                    "old_string": "a < b",
+                    "new_string": "a <= b",
+                }),
+            },
+            Some("claude-sonnet-4".to_string()),
+            None,
+        );
+        session.push(
+            Some("2025-01-01T00:00:03Z".to_string()),
+            EventKind::ToolCall {
+                call_id: "t2".to_string(),
+                name: "Bash".to_string(),
+                input: json!({ "command": "cargo test" }),
+            },
+            Some("claude-sonnet-4".to_string()),
+            None,
+        );
+        session.push(
+            Some("2025-01-01T00:00:04Z".to_string()),
+            EventKind::ToolResult {
+                call_id: "t2".to_string(),
+                output: "```\nerror: <T> not found\n```".to_string(),
+                is_error: true,
+            },
+            None,
+            None,
+        );
+        session.push(
+            Some("2025-01-01T00:00:05Z".to_string()),
+            EventKind::AssistantMessage {
+                text: "Fixed the comparison.".to_string(),
+            },
+            Some("claude-sonnet-4".to_string()),
+            Some(UnifiedUsage {
+                input_tokens: 10,
+                output_tokens: 5,
+                ..Default::default()
+            }),
+        );
+        let git_records = vec![ExportGitRecord {
+            prompt_index: 0,
+            commit_before: "0123456789abcdef".to_string(),
+            commit_after: None,
+            timestamp: "2025-01-01T00:00:00Z".to_string(),
+        }];
+        (session, git_records)
+    }
+
+    #[test]
+    fn markdown_has_metadata_prompts_and_fenced_tool_blocks() {
+        let (session, git_records) = fixture();
+        let md = render_markdown(&session, &git_records, &SessionExportOptions::default());
+
+        assert!(md.starts_with("# Fix <b>bold</b> & \"quotes\"\n\n"));
+        assert!(md.contains("| Engine | Claude Code |\n"));
+        assert!(md.contains("| Model | claude-sonnet-4 |\n"));
+        assert!(md.contains("| Tokens | 10 in / 5 out / 0 cache write / 0 cache read |\n"));
+        assert!(md.contains("## User #1 · 2025-01-01T00:00:00Z\n"));
+        assert!(md.contains("> git: `01234567` → `pending`\n"));
+        assert!(md.contains("**Tool call: `Edit`**"));
+        assert!(md.contains("```diff\n--- a/src/lib.rs\n+++ b/src/lib.rs\n-a < b\n+a <= b\n```\n"));
+        assert!(md.contains("```json\n{\n  \"command\": \"cargo test\"\n}\n```\n"));
+        // The fence outgrows the backticks inside the output
+        assert!(md.contains(
+            "<details><summary>Tool error</summary>\n\n````\n```\nerror: <T> not found\n```\n````\n"
+        ));
+        assert!(md.contains(
+            "### Assistant (claude-sonnet-4) · 2025-01-01T00:00:05Z\n\nFixed the comparison."
+        ));
+        assert!(!md.contains("Thinking"));
+
+        let options = SessionExportOptions {
+            include_thinking: true,
+            include_tool_results: false,
+            ..SessionExportOptions::default()
+        };
+        let md = render_markdown(&session, &git_records, &options);
+        assert!(md.contains("<details><summary>Thinking</summary>\n\n> look at lib.rs\n"));
+        assert!(!md.contains("Tool error"));
+    }
+
+    #[test]
+    fn html_escapes_text_and_renders_tool_blocks() {
+        let (session, git_records) = fixture();
+        let html = render_html(&session, &git_records, &SessionExportOptions::default());
+
+        assert!(
+            html.contains("<title>Fix &lt;b&gt;bold&lt;/b&gt; &amp; &quot;quotes&quot;</title>")
+        );
+        assert!(!html.contains("<b>bold</b>"));
+        assert!(html.contains("<div class=\"git\">git 01234567 → pending</div>"));
+        assert!(html.contains("<summary>Tool call: Edit</summary>"));
+        assert!(html
+            .contains("<span class=\"del\">-a &lt; b</span><span class=\"add\">+a &lt;= b</span>"));
+        assert!(html.contains("<pre>{\n  &quot;command&quot;: &quot;cargo test&quot;\n}</pre>"));
+        assert!(html.contains(
+            "<div class=\"event tool error\"><details><summary>Tool error</summary><pre>```\nerror: &lt;T&gt; not found\n```</pre>"
+        ));
+        assert!(html.contains("Assistant (claude-sonnet-4) · 2025-01-01T00:00:05Z"));
+        assert!(!html.contains("class=\"event thinking\""));
+        assert!(html.ends_with("</body>\n</html>\n"));
+    }
+
+    #[test]
+    fn json_bundle_round_trips_the_session_and_git_records() {
+        let (session, git_records) = fixture();
+        let events = session.events.clone();
+        let json = serde_json::to_string_pretty(&build_bundle(session, git_records)).unwrap();
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["formatVersion"], BUNDLE_FORMAT_VERSION);
+        assert_eq!(value["totalUsage"]["input_tokens"], 10);
+        assert_eq!(value["gitRecords"][0]["commitBefore"], "0123456789abcdef");
+        assert_eq!(value["session"]["events"][2]["kind"], "tool_call");
+        assert_eq!(
+            value["session"]["events"][2]["input"]["old_string"],
+            "a < b"
+        );
+
+        let bundle: SessionExportBundle = serde_json::from_str(&json).unwrap();
+        assert_eq!(bundle.session.events, events);
+        assert_eq!(bundle.git_records[0].commit_after, None);
+    }
+}
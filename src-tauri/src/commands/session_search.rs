@@ -0,0 +1,771 @@
+//! Full-text search across Claude, Codex and Gemini session histories.
+//!
+//! Sessions are loaded through the unified timeline loaders and indexed into
+//! an FTS5 table inside `agents.db`. Indexing is incremental per file: a file
+//! is only re-read when its size or modification time changed since the last
+//! pass, and files that disappeared are dropped from the index.
+
+use rusqlite::{params, params_from_iter, Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager, State};
+
+use super::claude::encode_project_path;
+use super::gemini::config::hash_project_path;
+use super::session_timeline::{loader_for, EventKind, SessionEngine, SessionRef, UnifiedSession};
+use super::storage::AgentDb;
+
+/// Maximum characters stored per indexed event (tool inputs can be huge)
+const MAX_CONTENT_CHARS: usize = 8000;
+/// Default number of hits returned by `search_sessions`
+const DEFAULT_LIMIT: usize = 50;
+/// Tokens of context around each match in a snippet
+const SNIPPET_TOKENS: usize = 24;
+const HIGHLIGHT_START: &str = "<mark>";
+const HIGHLIGHT_END: &str = "</mark>";
+
+// ============================================================================
+// Schema
+// ============================================================================
+
+/// Creates the search tables; called from `storage::init_database`
+pub fn init_search_tables(conn: &Connection) -> SqliteResult<()> {
+    // One row per indexed session file, used for change detection
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS session_search_files (
+            path TEXT PRIMARY KEY,
+            engine TEXT NOT NULL,
+            session_id TEXT NOT NULL,
+            project_path TEXT,
+            project_key TEXT,
+            modified INTEGER NOT NULL,
+            size INTEGER NOT NULL,
+            event_count INTEGER NOT NULL DEFAULT 0,
+            indexed_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    // Indexed events; the FTS table below only stores the token index
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS session_search_events (
+            id INTEGER PRIMARY KEY,
+            source_path TEXT NOT NULL,
+            engine TEXT NOT NULL,
+            session_id TEXT NOT NULL,
+            project_path TEXT,
+            project_key TEXT,
+            event_index INTEGER NOT NULL,
+            role TEXT NOT NULL,
+            tool_name TEXT,
+            timestamp TEXT,
+            content TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_search_events_source
+         ON session_search_events(source_path)",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_search_events_engine_timestamp
+         ON session_search_events(engine, timestamp DESC)",
+        [],
+    )?;
+
+    // The trigram tokenizer matches substrings, which also covers CJK text
+    // that unicode61 cannot split into words
+    conn.execute(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS session_search_fts USING fts5(
+            content,
+            content = 'session_search_events',
+            content_rowid = 'id',
+            tokenize = 'trigram'
+        )",
+        [],
+    )?;
+
+    // Keep the external-content FTS index in sync
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS session_search_events_ai
+         AFTER INSERT ON session_search_events BEGIN
+            INSERT INTO session_search_fts(rowid, content) VALUES (new.id, new.content);
+         END",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS session_search_events_ad
+         AFTER DELETE ON session_search_events BEGIN
+            INSERT INTO session_search_fts(session_search_fts, rowid, content)
+            VALUES ('delete', old.id, old.content);
+         END",
+        [],
+    )?;
+
+    Ok(())
+}
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Filters applied to a search
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionSearchFilters {
+    /// Restrict to these engines ("claude" | "codex" | "gemini")
+    pub engines: Option<Vec<String>>,
+    /// Project working directory
+    pub project_path: Option<String>,
+    /// Inclusive start date (YYYY-MM-DD or RFC 3339)
+    pub start_date: Option<String>,
+    /// Inclusive end date (YYYY-MM-DD or RFC 3339)
+    pub end_date: Option<String>,
+    /// Restrict to these roles ("user" | "assistant" | "tool")
+    pub roles: Option<Vec<String>>,
+}
+
+/// A single matching event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSearchHit {
+    pub engine: String,
+    pub session_id: String,
+    pub project_path: Option<String>,
+    pub source_path: String,
+    pub event_index: i64,
+    pub role: String,
+    pub tool_name: Option<String>,
+    pub timestamp: Option<String>,
+    /// Excerpt with matches wrapped in `<mark>` tags
+    pub snippet: String,
+    /// bm25 rank (lower is better); 0 for substring fallback matches
+    pub rank: f64,
+}
+
+/// Outcome of an indexing pass
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionIndexStats {
+    pub scanned_files: usize,
+    pub indexed_files: usize,
+    pub removed_files: usize,
+    pub indexed_events: usize,
+    pub failed_files: usize,
+    pub duration_ms: u64,
+}
+
+/// Summary of what is currently indexed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionIndexStatus {
+    pub files: i64,
+    pub events: i64,
+    pub last_indexed_at: Option<String>,
+}
+
+// ============================================================================
+// Indexing
+// ============================================================================
+
+/// One searchable event of a session
+struct SearchRow {
+    role: &'static str,
+    tool_name: Option<String>,
+    content: String,
+    event_index: usize,
+    timestamp: Option<String>,
+}
+
+/// Extracts user prompts, assistant text and tool calls
+fn searchable_rows(session: &UnifiedSession) -> Vec<SearchRow> {
+    let mut rows = Vec::new();
+    for event in &session.events {
+        let (role, tool_name, content) = match &event.kind {
+            EventKind::UserMessage { text } => ("user", None, text.clone()),
+            EventKind::AssistantMessage { text } => ("assistant", None, text.clone()),
+            EventKind::ToolCall { name, input, .. } => {
+                let input = match input {
+                    serde_json::Value::String(s) => s.clone(),
+                    serde_json::Value::Null => String::new(),
+                    other => other.to_string(),
+                };
+                ("tool", Some(name.clone()), format!("{} {}", name, input))
+            }
+            _ => continue,
+        };
+        if content.trim().is_empty() {
+            continue;
+        }
+        let content = if content.chars().count() > MAX_CONTENT_CHARS {
+            content.chars().take(MAX_CONTENT_CHARS).collect()
+        } else {
+            content
+        };
+        rows.push(SearchRow {
+            role,
+            tool_name,
+            content,
+            event_index: event.index,
+            timestamp: event.timestamp.clone(),
+        });
+    }
+    rows
+}
+
+/// Replaces the indexed rows of one session file
+pub fn index_session(
+    conn: &mut Connection,
+    session_ref: &SessionRef,
+    session: &UnifiedSession,
+) -> SqliteResult<usize> {
+    let path = session_ref.path.to_string_lossy().to_string();
+    let rows = searchable_rows(session);
+    let project_path = session.project_path.clone();
+
+    let tx = conn.transaction()?;
+    tx.execute(
+        "DELETE FROM session_search_events WHERE source_path = ?1",
+        params![path],
+    )?;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO session_search_events
+                (source_path, engine, session_id, project_path, project_key, event_index, role, tool_name, timestamp, content)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        )?;
+        for row in &rows {
+            stmt.execute(params![
+                path,
+                session.engine.as_str(),
+                session.id,
+                project_path,
+                session_ref.project_key,
+                row.event_index as i64,
+                row.role,
+                row.tool_name,
+                row.timestamp,
+                row.content,
+            ])?;
+        }
+    }
+    tx.execute(
+        "INSERT OR REPLACE INTO session_search_files
+            (path, engine, session_id, project_path, project_key, modified, size, event_count, indexed_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, CURRENT_TIMESTAMP)",
+        params![
+            path,
+            session.engine.as_str(),
+            session.id,
+            project_path,
+            session_ref.project_key,
+            session_ref.modified as i64,
+            session_ref.size as i64,
+            rows.len() as i64,
+        ],
+    )?;
+    tx.commit()?;
+
+    Ok(rows.len())
+}
+
+/// Drops a file from the index
+fn remove_indexed_file(conn: &mut Connection, path: &str) -> SqliteResult<()> {
+    let tx = conn.transaction()?;
+    tx.execute(
+        "DELETE FROM session_search_events WHERE source_path = ?1",
+        params![path],
+    )?;
+    tx.execute(
+        "DELETE FROM session_search_files WHERE path = ?1",
+        params![path],
+    )?;
+    tx.commit()
+}
+
+/// (modified, size) of every indexed file of the given engines
+fn indexed_file_states(
+    conn: &Connection,
+    engines: &[SessionEngine],
+) -> SqliteResult<HashMap<String, (u64, u64)>> {
+    let mut states = HashMap::new();
+    let mut stmt =
+        conn.prepare("SELECT path, modified, size FROM session_search_files WHERE engine = ?1")?;
+    for engine in engines {
+        let rows = stmt.query_map(params![engine.as_str()], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)? as u64,
+                row.get::<_, i64>(2)? as u64,
+            ))
+        })?;
+        for row in rows {
+            let (path, modified, size) = row?;
+            states.insert(path, (modified, size));
+        }
+    }
+    Ok(states)
+}
+
+/// Brings the index up to date with the session files on disk.
+///
+/// Session files are parsed without holding the database lock; the lock is
+/// only taken for the short per-file write transactions.
+fn refresh_index(db: &AgentDb, engines: &[SessionEngine], full: bool) -> Result<SessionIndexStats, String> {
+    let started = std::time::Instant::now();
+    let mut stats = SessionIndexStats::default();
+
+    let mut known = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        indexed_file_states(&conn, engines).map_err(|e| e.to_string())?
+    };
+
+    for engine in engines {
+        let loader = loader_for(*engine);
+        let refs = match loader.list_sessions() {
+            Ok(refs) => refs,
+            Err(e) => {
+                log::warn!("[SessionSearch] Failed to list {} sessions: {}", engine.as_str(), e);
+                continue;
+            }
+        };
+
+        for session_ref in refs {
+            stats.scanned_files += 1;
+            let path = session_ref.path.to_string_lossy().to_string();
+
+            let unchanged = known
+                .remove(&path)
+                .map(|(modified, size)| modified == session_ref.modified && size == session_ref.size)
+                .unwrap_or(false);
+            if unchanged && !full {
+                continue;
+            }
+
+            let session = match loader.load_file(&session_ref.path) {
+                Ok(session) => session,
+                Err(e) => {
+                    log::warn!("[SessionSearch] Failed to load {}: {}", path, e);
+                    stats.failed_files += 1;
+                    continue;
+                }
+            };
+
+            let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+            match index_session(&mut conn, &session_ref, &session) {
+                Ok(count) => {
+                    stats.indexed_files += 1;
+                    stats.indexed_events += count;
+                }
+                Err(e) => {
+                    log::warn!("[SessionSearch] Failed to index {}: {}", path, e);
+                    stats.failed_files += 1;
+                }
+            }
+        }
+    }
+
+    // Whatever is left was indexed before but no longer exists on disk
+    if !known.is_empty() {
+        let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+        for path in known.keys() {
+            remove_indexed_file(&mut conn, path).map_err(|e| e.to_string())?;
+            stats.removed_files += 1;
+        }
+    }
+
+    stats.duration_ms = started.elapsed().as_millis() as u64;
+    log::info!("[SessionSearch] Index refreshed: {:?}", stats);
+    Ok(stats)
+}
+
+// ============================================================================
+// Querying
+// ============================================================================
+
+/// Splits a user query into terms, honouring "quoted phrases"
+fn split_terms(query: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in query.chars() {
+        match c {
+            '"' => {
+                if !current.trim().is_empty() {
+                    terms.push(current.trim().to_string());
+                }
+                current.clear();
+                in_quotes = !in_quotes;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    terms.push(current.clone());
+                }
+                current.clear();
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.trim().is_empty() {
+        terms.push(current.trim().to_string());
+    }
+    terms
+}
+
+/// Builds an FTS5 MATCH expression where every term is a quoted phrase
+fn build_match_expression(terms: &[String]) -> String {
+    terms
+        .iter()
+        .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" AND ")
+}
+
+fn escape_like(term: &str) -> String {
+    term.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Highlights terms in `content` for substring matches the FTS snippet() can't produce
+fn substring_snippet(content: &str, terms: &[String]) -> String {
+    let lower = content.to_lowercase();
+    // Byte offsets only line up when lowercasing kept lengths intact
+    if lower.len() != content.len() {
+        return content.chars().take(SNIPPET_TOKENS * 8).collect();
+    }
+
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for term in terms {
+        let needle = term.to_lowercase();
+        if needle.is_empty() {
+            continue;
+        }
+        let mut start = 0;
+        while let Some(pos) = lower[start..].find(&needle) {
+            let begin = start + pos;
+            ranges.push((begin, begin + needle.len()));
+            start = begin + needle.len();
+        }
+    }
+    ranges.sort();
+
+    let Some(&(first, _)) = ranges.first() else {
+        return content.chars().take(SNIPPET_TOKENS * 8).collect();
+    };
+
+    // Window of roughly SNIPPET_TOKENS words around the first match
+    let window = SNIPPET_TOKENS * 4;
+    let mut from = first.saturating_sub(window);
+    while !content.is_char_boundary(from) {
+        from -= 1;
+    }
+    let mut to = (first + window * 2).min(content.len());
+    while !content.is_char_boundary(to) {
+        to += 1;
+    }
+
+    let mut snippet = String::new();
+    if from > 0 {
+        snippet.push('…');
+    }
+    let mut cursor = from;
+    for (begin, end) in ranges {
+        if begin < cursor || end > to {
+            continue;
+        }
+        snippet.push_str(&content[cursor..begin]);
+        snippet.push_str(HIGHLIGHT_START);
+        snippet.push_str(&content[begin..end]);
+        snippet.push_str(HIGHLIGHT_END);
+        cursor = end;
+    }
+    snippet.push_str(&content[cursor..to]);
+    if to < content.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+/// Runs a search against the index
+pub fn search(
+    conn: &Connection,
+    query: &str,
+    filters: &SessionSearchFilters,
+    limit: usize,
+    offset: usize,
+) -> Result<Vec<SessionSearchHit>, String> {
+    let terms = split_terms(query);
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Trigram MATCH needs at least three characters per term; shorter terms
+    // fall back to LIKE, which the trigram index can still serve
+    let use_match = terms.iter().all(|t| t.chars().count() >= 3);
+
+    let mut conditions: Vec<String> = Vec::new();
+    let mut values: Vec<String> = Vec::new();
+
+    if use_match {
+        values.push(build_match_expression(&terms));
+        conditions.push(format!("session_search_fts MATCH ?{}", values.len()));
+    } else {
+        for term in &terms {
+            values.push(format!("%{}%", escape_like(term)));
+            conditions.push(format!("session_search_fts.content LIKE ?{} ESCAPE '\\'", values.len()));
+        }
+    }
+
+    if let Some(engines) = filters.engines.as_ref().filter(|e| !e.is_empty()) {
+        let mut placeholders = Vec::new();
+        for engine in engines {
+            let engine = engine.parse::<SessionEngine>()?;
+            values.push(engine.as_str().to_string());
+            placeholders.push(format!("?{}", values.len()));
+        }
+        conditions.push(format!("e.engine IN ({})", placeholders.join(", ")));
+    }
+
+    if let Some(roles) = filters.roles.as_ref().filter(|r| !r.is_empty()) {
+        let mut placeholders = Vec::new();
+        for role in roles {
+            values.push(role.clone());
+            placeholders.push(format!("?{}", values.len()));
+        }
+        conditions.push(format!("e.role IN ({})", placeholders.join(", ")));
+    }
+
+    if let Some(project_path) = filters.project_path.as_ref().filter(|p| !p.is_empty()) {
+        // Claude keys projects by encoded path and Gemini by path hash
+        values.push(project_path.clone());
+        let path_idx = values.len();
+        values.push(encode_project_path(project_path));
+        let claude_idx = values.len();
+        values.push(hash_project_path(project_path));
+        let gemini_idx = values.len();
+        conditions.push(format!(
+            "(e.project_path = ?{} OR e.project_key = ?{} OR e.project_key = ?{} OR e.project_key = ?{})",
+            path_idx, path_idx, claude_idx, gemini_idx
+        ));
+    }
+
+    if let Some(start) = filters.start_date.as_ref().filter(|d| !d.is_empty()) {
+        values.push(start.clone());
+        conditions.push(format!("e.timestamp >= ?{}", values.len()));
+    }
+
+    if let Some(end) = filters.end_date.as_ref().filter(|d| !d.is_empty()) {
+        // A bare date includes the whole day
+        let end = if end.len() == 10 {
+            format!("{}T23:59:59.999Z", end)
+        } else {
+            end.clone()
+        };
+        values.push(end);
+        conditions.push(format!("e.timestamp <= ?{}", values.len()));
+    }
+
+    let select = if use_match {
+        format!(
+            "snippet(session_search_fts, 0, '{}', '{}', '…', {}), bm25(session_search_fts)",
+            HIGHLIGHT_START, HIGHLIGHT_END, SNIPPET_TOKENS
+        )
+    } else {
+        "e.content, 0.0".to_string()
+    };
+    // Column 10 is the bm25 rank
+    let order = if use_match { "10" } else { "e.timestamp DESC" };
+
+    let sql = format!(
+        "SELECT e.engine, e.session_id, e.project_path, e.source_path, e.event_index, e.role, e.tool_name, e.timestamp, {}
+         FROM session_search_fts
+         JOIN session_search_events e ON e.id = session_search_fts.rowid
+         WHERE {}
+         ORDER BY {}
+         LIMIT {} OFFSET {}",
+        select,
+        conditions.join(" AND "),
+        order,
+        limit,
+        offset
+    );
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let hits = stmt
+        .query_map(params_from_iter(values.iter()), |row| {
+            Ok(SessionSearchHit {
+                engine: row.get(0)?,
+                session_id: row.get(1)?,
+                project_path: row.get(2)?,
+                source_path: row.get(3)?,
+                event_index: row.get(4)?,
+                role: row.get(5)?,
+                tool_name: row.get(6)?,
+                timestamp: row.get(7)?,
+                snippet: row.get(8)?,
+                rank: row.get(9)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqliteResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+
+    if use_match {
+        return Ok(hits);
+    }
+
+    Ok(hits
+        .into_iter()
+        .map(|mut hit| {
+            hit.snippet = substring_snippet(&hit.snippet, &terms);
+            hit
+        })
+        .collect())
+}
+
+fn parse_engines(engines: Option<Vec<String>>) -> Result<Vec<SessionEngine>, String> {
+    match engines {
+        Some(list) if !list.is_empty() => list.iter().map(|e| e.parse()).collect(),
+        _ => Ok(SessionEngine::ALL.to_vec()),
+    }
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Incrementally (re)indexes session files; `full` forces every file to be re-read
+#[tauri::command]
+pub async fn refresh_session_search_index(
+    app: AppHandle,
+    engines: Option<Vec<String>>,
+    full: Option<bool>,
+) -> Result<SessionIndexStats, String> {
+    let engines = parse_engines(engines)?;
+    let full = full.unwrap_or(false);
+
+    tokio::task::spawn_blocking(move || {
+        let db = app.state::<AgentDb>();
+        refresh_index(&db, &engines, full)
+    })
+    .await
+    .map_err(|e| format!("Indexing task failed: {}", e))?
+}
+
+/// Searches indexed prompts, assistant text and tool calls
+#[tauri::command]
+pub async fn search_sessions(
+    db: State<'_, AgentDb>,
+    query: String,
+    filters: Option<SessionSearchFilters>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<Vec<SessionSearchHit>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    search(
+        &conn,
+        &query,
+        &filters.unwrap_or_default(),
+        limit.unwrap_or(DEFAULT_LIMIT),
+        offset.unwrap_or(0),
+    )
+}
+
+/// Returns how many files/events are indexed
+#[tauri::command]
+pub async fn get_session_search_status(db: State<'_, AgentDb>) -> Result<SessionIndexStatus, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(event_count), 0), MAX(indexed_at) FROM session_search_files",
+        [],
+        |row| {
+            Ok(SessionIndexStatus {
+                files: row.get(0)?,
+                events: row.get(1)?,
+                last_indexed_at: row.get(2)?,
+            })
+        },
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Drops the whole search index (it is rebuilt on the next refresh)
+#[tauri::command]
+pub async fn clear_session_search_index(db: State<'_, AgentDb>) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM session_search_events", [])
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM session_search_files", [])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    fn indexed_conn() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        init_search_tables(&conn).unwrap();
+
+        let content = [
+            r#"{"type":"user","cwd":"/repo","timestamp":"2025-03-01T10:00:00Z","message":{"role":"user","content":"fix the Redis timeout in the worker"}}"#,
+            r#"{"type":"assistant","timestamp":"2025-03-01T10:00:05Z","message":{"id":"m1","content":[{"type":"tool_use","id":"t1","name":"Grep","input":{"pattern":"REDIS_TIMEOUT"}}]}}"#,
+            r#"{"type":"assistant","timestamp":"2025-03-01T10:00:09Z","message":{"id":"m2","content":[{"type":"text","text":"Raised the timeout to 5s."}]}}"#,
+        ]
+        .join("\n");
+        let path = Path::new("/tmp/claude-1.jsonl");
+        let session = super::super::session_timeline::claude::parse_session(path, &content);
+        let session_ref = SessionRef {
+            engine: SessionEngine::Claude,
+            session_id: session.id.clone(),
+            project_key: Some("-repo".to_string()),
+            path: PathBuf::from(path),
+            modified: 1,
+            size: 1,
+        };
+        index_session(&mut conn, &session_ref, &session).unwrap();
+        conn
+    }
+
+    #[test]
+    fn finds_prompts_and_tool_calls_with_highlight() {
+        let conn = indexed_conn();
+
+        let hits = search(&conn, "redis timeout", &SessionSearchFilters::default(), 10, 0).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(hits.iter().any(|h| h.role == "user" && h.snippet.contains("<mark>Redis</mark>")));
+        assert!(hits.iter().any(|h| h.role == "tool" && h.tool_name.as_deref() == Some("Grep")));
+    }
+
+    #[test]
+    fn applies_filters_and_short_terms() {
+        let conn = indexed_conn();
+
+        let by_project = SessionSearchFilters {
+            project_path: Some("/repo".to_string()),
+            end_date: Some("2025-03-01".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(search(&conn, "timeout", &by_project, 10, 0).unwrap().len(), 3);
+
+        let other_engine = SessionSearchFilters {
+            engines: Some(vec!["codex".to_string()]),
+            ..Default::default()
+        };
+        assert!(search(&conn, "timeout", &other_engine, 10, 0).unwrap().is_empty());
+
+        let later = SessionSearchFilters {
+            start_date: Some("2025-03-02".to_string()),
+            ..Default::default()
+        };
+        assert!(search(&conn, "timeout", &later, 10, 0).unwrap().is_empty());
+
+        let short = search(&conn, "5s", &SessionSearchFilters::default(), 10, 0).unwrap();
+        assert_eq!(short.len(), 1);
+        assert!(short[0].snippet.contains("<mark>5s</mark>"));
+    }
+}
@@ -0,0 +1,388 @@
+//! Claude Code JSONL loader
+//!
+//! Each line is one entry (`user`, `assistant`, `system`, `summary`, ...).
+//! Assistant responses are split across several lines sharing the same
+//! `message.id`, so usage is only counted the first time a `message.id:requestId`
+//! pair is seen - the same rule `usage.rs` uses.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use super::{
+    value_to_text, EventKind, SessionEngine, SessionLoader, SessionRef, UnifiedSession,
+    UnifiedUsage,
+};
+use crate::commands::claude::{get_claude_dir, get_session_project_dir};
+
+pub struct ClaudeSessionLoader;
+
+fn projects_dir() -> Result<PathBuf, String> {
+    get_claude_dir()
+        .map(|dir| dir.join("projects"))
+        .map_err(|e| e.to_string())
+}
+
+/// Subagent transcripts are merged into their parent session by the history view
+fn is_main_session_file(path: &Path) -> bool {
+    path.extension().and_then(|s| s.to_str()) == Some("jsonl")
+        && !path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .map(|name| name.starts_with("agent-"))
+            .unwrap_or(false)
+}
+
+impl SessionLoader for ClaudeSessionLoader {
+    fn engine(&self) -> SessionEngine {
+        SessionEngine::Claude
+    }
+
+    fn list_sessions(&self) -> Result<Vec<SessionRef>, String> {
+        let projects_dir = projects_dir()?;
+        if !projects_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut refs = Vec::new();
+        let projects = fs::read_dir(&projects_dir)
+            .map_err(|e| format!("Failed to read projects directory: {}", e))?;
+
+        for project in projects.flatten() {
+            let project_path = project.path();
+            if !project_path.is_dir() {
+                continue;
+            }
+            let project_id = project.file_name().to_string_lossy().to_string();
+
+            let Ok(entries) = fs::read_dir(&project_path) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if !is_main_session_file(&path) {
+                    continue;
+                }
+                if let Some(session_id) = path.file_stem().and_then(|s| s.to_str()) {
+                    refs.push(SessionRef::from_path(
+                        SessionEngine::Claude,
+                        session_id.to_string(),
+                        Some(project_id.clone()),
+                        path.clone(),
+                    ));
+                }
+            }
+        }
+
+        Ok(refs)
+    }
+
+    fn locate(&self, session_id: &str, project_hint: Option<&str>) -> Result<PathBuf, String> {
+        let projects_dir = projects_dir()?;
+        let file_name = format!("{}.jsonl", session_id);
+
+        if let Some(project_id) = project_hint {
+            let path = get_session_project_dir(session_id, project_id)
+                .map_err(|e| e.to_string())?
+                .join(&file_name);
+            if path.exists() {
+                return Ok(path);
+            }
+        }
+
+        if let Ok(projects) = fs::read_dir(&projects_dir) {
+            for project in projects.flatten() {
+                let path = project.path().join(&file_name);
+                if path.exists() {
+                    return Ok(path);
+                }
+            }
+        }
+
+        Err(format!("Claude session file not found: {}", session_id))
+    }
+
+    fn load_file(&self, path: &Path) -> Result<UnifiedSession, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read session file: {}", e))?;
+        Ok(parse_session(path, &content))
+    }
+}
+
+/// Parses the contents of a Claude JSONL transcript
+pub fn parse_session(path: &Path, content: &str) -> UnifiedSession {
+    let session_id = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default()
+        .to_string();
+    let mut session = UnifiedSession::new(session_id, SessionEngine::Claude, path);
+    let mut seen_usage: HashSet<String> = HashSet::new();
+
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Ok(entry) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+
+        if session.project_path.is_none() {
+            if let Some(cwd) = entry.get("cwd").and_then(|v| v.as_str()) {
+                session.project_path = Some(cwd.to_string());
+            }
+        }
+
+        let timestamp = entry
+            .get("timestamp")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+
+        match entry.get("type").and_then(|v| v.as_str()) {
+            Some("user") => parse_user_entry(&mut session, &entry, timestamp),
+            Some("assistant") => {
+                parse_assistant_entry(&mut session, &entry, timestamp, &mut seen_usage)
+            }
+            Some("system") => {
+                let text = entry
+                    .get("content")
+                    .map(value_to_text)
+                    .unwrap_or_default();
+                if !text.is_empty() {
+                    let subtype = entry
+                        .get("subtype")
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_string());
+                    session.push(timestamp, EventKind::System { subtype, text }, None, None);
+                }
+            }
+            Some("summary") => {
+                if let Some(summary) = entry.get("summary").and_then(|v| v.as_str()) {
+                    session.push(
+                        timestamp,
+                        EventKind::System {
+                            subtype: Some("summary".to_string()),
+                            text: summary.to_string(),
+                        },
+                        None,
+                        None,
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    session
+}
+
+/// Text the CLI injects as a user message rather than something the user typed
+fn injected_subtype(entry: &Value, text: &str) -> Option<&'static str> {
+    if entry.get("isMeta").and_then(|v| v.as_bool()) == Some(true) {
+        return Some("meta");
+    }
+    if text.contains("Caveat: The messages below were generated by the user while running local commands") {
+        return Some("meta");
+    }
+    if text.starts_with("<command-name>") || text.starts_with("<local-command-stdout>") {
+        return Some("command");
+    }
+    if text.trim() == "Warmup" {
+        return Some("warmup");
+    }
+    None
+}
+
+fn parse_user_entry(session: &mut UnifiedSession, entry: &Value, timestamp: Option<String>) {
+    let Some(content) = entry.get("message").and_then(|m| m.get("content")) else {
+        return;
+    };
+
+    let push_text = |session: &mut UnifiedSession, text: String| {
+        if text.trim().is_empty() {
+            return;
+        }
+        let kind = match injected_subtype(entry, &text) {
+            Some(subtype) => EventKind::System {
+                subtype: Some(subtype.to_string()),
+                text,
+            },
+            None => EventKind::UserMessage { text },
+        };
+        session.push(timestamp.clone(), kind, None, None);
+    };
+
+    if let Some(text) = content.as_str() {
+        push_text(session, text.to_string());
+        return;
+    }
+
+    let Some(blocks) = content.as_array() else {
+        return;
+    };
+
+    // Text blocks of one entry form a single prompt
+    let text = blocks
+        .iter()
+        .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("text"))
+        .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    push_text(session, text);
+
+    for block in blocks {
+        if block.get("type").and_then(|t| t.as_str()) != Some("tool_result") {
+            continue;
+        }
+        let call_id = block
+            .get("tool_use_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let output = block.get("content").map(value_to_text).unwrap_or_default();
+        let is_error = block
+            .get("is_error")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        session.push(
+            timestamp.clone(),
+            EventKind::ToolResult {
+                call_id,
+                output,
+                is_error,
+            },
+            None,
+            None,
+        );
+    }
+}
+
+fn parse_assistant_entry(
+    session: &mut UnifiedSession,
+    entry: &Value,
+    timestamp: Option<String>,
+    seen_usage: &mut HashSet<String>,
+) {
+    let Some(message) = entry.get("message") else {
+        return;
+    };
+    let model = message
+        .get("model")
+        .and_then(|v| v.as_str())
+        .filter(|m| *m != "<synthetic>")
+        .map(|s| s.to_string());
+
+    let mut usage = extract_usage(message);
+    if usage.is_some() {
+        let message_id = message.get("id").and_then(|v| v.as_str());
+        let request_id = entry.get("requestId").and_then(|v| v.as_str());
+        if let (Some(message_id), Some(request_id)) = (message_id, request_id) {
+            if !seen_usage.insert(format!("{}:{}", message_id, request_id)) {
+                usage = None;
+            }
+        }
+    }
+
+    let mut kinds = Vec::new();
+    match message.get("content") {
+        Some(Value::String(text)) if !text.trim().is_empty() => {
+            kinds.push(EventKind::AssistantMessage { text: text.clone() });
+        }
+        Some(Value::Array(blocks)) => {
+            for block in blocks {
+                match block.get("type").and_then(|t| t.as_str()) {
+                    Some("text") => {
+                        let text = block.get("text").and_then(|t| t.as_str()).unwrap_or_default();
+                        if !text.trim().is_empty() {
+                            kinds.push(EventKind::AssistantMessage {
+                                text: text.to_string(),
+                            });
+                        }
+                    }
+                    Some("thinking") => {
+                        let text = block
+                            .get("thinking")
+                            .and_then(|t| t.as_str())
+                            .unwrap_or_default();
+                        if !text.trim().is_empty() {
+                            kinds.push(EventKind::Thinking {
+                                text: text.to_string(),
+                            });
+                        }
+                    }
+                    Some("tool_use") => kinds.push(EventKind::ToolCall {
+                        call_id: block
+                            .get("id")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or_default()
+                            .to_string(),
+                        name: block
+                            .get("name")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or_default()
+                            .to_string(),
+                        input: block.get("input").cloned().unwrap_or(Value::Null),
+                    }),
+                    _ => {}
+                }
+            }
+        }
+        _ => {}
+    }
+
+    if kinds.is_empty() {
+        if usage.is_some() {
+            session.push(timestamp, EventKind::Usage, model, usage);
+        }
+        return;
+    }
+
+    for kind in kinds {
+        // Usage belongs to the response, attach it to its first event only
+        session.push(timestamp.clone(), kind, model.clone(), usage.take());
+    }
+}
+
+fn extract_usage(message: &Value) -> Option<UnifiedUsage> {
+    let usage = message.get("usage")?;
+    let field = |name: &str| usage.get(name).and_then(|v| v.as_u64()).unwrap_or(0);
+    Some(UnifiedUsage {
+        input_tokens: field("input_tokens"),
+        output_tokens: field("output_tokens"),
+        cache_creation_tokens: field("cache_creation_input_tokens"),
+        cache_read_tokens: field("cache_read_input_tokens"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_prompts_tools_and_dedupes_usage() {
+        let content = [
+            r#"{"type":"user","cwd":"/repo","timestamp":"2025-01-01T00:00:00Z","message":{"role":"user","content":"fix the bug"}}"#,
+            r#"{"type":"assistant","requestId":"r1","timestamp":"2025-01-01T00:00:01Z","message":{"id":"m1","model":"claude-sonnet-4","content":[{"type":"thinking","thinking":"hmm"}],"usage":{"input_tokens":10,"output_tokens":5}}}"#,
+            r#"{"type":"assistant","requestId":"r1","timestamp":"2025-01-01T00:00:02Z","message":{"id":"m1","model":"claude-sonnet-4","content":[{"type":"tool_use","id":"t1","name":"Read","input":{"file_path":"a.rs"}}],"usage":{"input_tokens":10,"output_tokens":5}}}"#,
+            r#"{"type":"user","timestamp":"2025-01-01T00:00:03Z","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"t1","content":[{"type":"text","text":"fn main() {}"}]}]}}"#,
+            r#"{"type":"user","isMeta":true,"message":{"role":"user","content":"<command-name>/clear</command-name>"}}"#,
+        ]
+        .join("\n");
+
+        let session = parse_session(Path::new("/tmp/abc.jsonl"), &content);
+
+        assert_eq!(session.id, "abc");
+        assert_eq!(session.project_path.as_deref(), Some("/repo"));
+        assert_eq!(session.model.as_deref(), Some("claude-sonnet-4"));
+        assert_eq!(session.user_prompts().len(), 1);
+        assert_eq!(session.first_message(), Some("fix the bug"));
+        assert_eq!(session.events.len(), 5);
+        assert!(matches!(
+            &session.events[3].kind,
+            EventKind::ToolResult { call_id, output, .. } if call_id == "t1" && output == "fn main() {}"
+        ));
+        assert_eq!(session.total_usage().input_tokens, 10);
+    }
+}
@@ -0,0 +1,422 @@
+//! Codex rollout loader
+//!
+//! The first line is `session_meta`; conversation content lives in
+//! `response_item` entries and usage in `event_msg`/`token_count` entries.
+//! `event_msg` also mirrors messages (`user_message`, `agent_message`), those
+//! duplicates are ignored.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use super::{
+    value_to_text, EventKind, SessionEngine, SessionLoader, SessionRef, UnifiedSession,
+    UnifiedUsage,
+};
+use crate::commands::codex::{find_session_file, get_codex_sessions_dir};
+#[cfg(target_os = "windows")]
+use crate::commands::wsl_utils;
+
+pub struct CodexSessionLoader;
+
+impl SessionLoader for CodexSessionLoader {
+    fn engine(&self) -> SessionEngine {
+        SessionEngine::Codex
+    }
+
+    fn list_sessions(&self) -> Result<Vec<SessionRef>, String> {
+        let sessions_dir = get_codex_sessions_dir()?;
+        if !sessions_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut refs = Vec::new();
+        for entry in walkdir::WalkDir::new(&sessions_dir).into_iter().flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("jsonl") {
+                continue;
+            }
+            if let Some((session_id, cwd)) = read_session_meta(path) {
+                refs.push(SessionRef::from_path(
+                    SessionEngine::Codex,
+                    session_id,
+                    cwd,
+                    path.to_path_buf(),
+                ));
+            }
+        }
+
+        Ok(refs)
+    }
+
+    fn locate(&self, session_id: &str, _project_hint: Option<&str>) -> Result<PathBuf, String> {
+        let sessions_dir = get_codex_sessions_dir()?;
+        find_session_file(&sessions_dir, session_id)
+            .ok_or_else(|| format!("Codex session file not found: {}", session_id))
+    }
+
+    fn load_file(&self, path: &Path) -> Result<UnifiedSession, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read session file: {}", e))?;
+        Ok(parse_session(path, &content))
+    }
+}
+
+/// Reads `(session_id, cwd)` from the `session_meta` line
+fn read_session_meta(path: &Path) -> Option<(String, Option<String>)> {
+    use std::io::{BufRead, BufReader};
+
+    let file = fs::File::open(path).ok()?;
+    let first_line = BufReader::new(file).lines().next()?.ok()?;
+    let meta: Value = serde_json::from_str(&first_line).ok()?;
+    if meta["type"].as_str() != Some("session_meta") {
+        return None;
+    }
+
+    let session_id = meta["payload"]["id"].as_str()?.to_string();
+    let cwd = meta["payload"]["cwd"].as_str().map(convert_cwd);
+    Some((session_id, cwd))
+}
+
+/// Converts WSL paths (/mnt/c/...) so project paths match the Windows UI
+fn convert_cwd(cwd: &str) -> String {
+    #[cfg(target_os = "windows")]
+    {
+        if cwd.starts_with("/mnt/") {
+            return wsl_utils::wsl_to_windows_path(cwd);
+        }
+    }
+    cwd.to_string()
+}
+
+/// Context blocks Codex injects as user messages
+fn is_injected_context(text: &str) -> bool {
+    text.contains("<environment_context>")
+        || text.contains("# AGENTS.md instructions")
+        || text.contains("<user_instructions>")
+}
+
+fn usage_from(obj: &Value) -> UnifiedUsage {
+    let field = |name: &str| obj.get(name).and_then(|v| v.as_u64()).unwrap_or(0);
+    UnifiedUsage {
+        input_tokens: field("input_tokens"),
+        output_tokens: field("output_tokens"),
+        cache_creation_tokens: 0,
+        cache_read_tokens: obj
+            .get("cached_input_tokens")
+            .or_else(|| obj.get("cached_tokens"))
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0),
+    }
+}
+
+/// Parses the contents of a Codex rollout file
+pub fn parse_session(path: &Path, content: &str) -> UnifiedSession {
+    let fallback_id = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default()
+        .to_string();
+    let mut session = UnifiedSession::new(fallback_id, SessionEngine::Codex, path);
+    let mut current_model: Option<String> = None;
+    // Running total of the last token_count, used to turn totals into deltas
+    let mut last_total: Option<UnifiedUsage> = None;
+
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Ok(event) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        let timestamp = event["timestamp"].as_str().map(|s| s.to_string());
+        let payload = &event["payload"];
+
+        match event["type"].as_str().unwrap_or("") {
+            "session_meta" => {
+                if let Some(id) = payload["id"].as_str() {
+                    session.id = id.to_string();
+                }
+                if let Some(cwd) = payload["cwd"].as_str() {
+                    session.project_path = Some(convert_cwd(cwd));
+                }
+                if let Some(model) = payload["model"].as_str() {
+                    current_model = Some(model.to_string());
+                }
+                if session.started_at.is_none() {
+                    session.started_at = payload["timestamp"]
+                        .as_str()
+                        .map(|s| s.to_string())
+                        .or(timestamp);
+                }
+            }
+            "turn_context" => {
+                if let Some(model) = payload["model"].as_str() {
+                    current_model = Some(model.to_string());
+                }
+            }
+            "response_item" => {
+                parse_response_item(&mut session, payload, timestamp, &current_model)
+            }
+            "event_msg" => {
+                if payload["type"].as_str() == Some("token_count") {
+                    let info = &payload["info"];
+                    let usage = if let Some(total) = info.get("total_token_usage") {
+                        let total = usage_from(total);
+                        let delta = match &last_total {
+                            Some(prev) if total.input_tokens >= prev.input_tokens => UnifiedUsage {
+                                input_tokens: total.input_tokens - prev.input_tokens,
+                                output_tokens: total.output_tokens.saturating_sub(prev.output_tokens),
+                                cache_creation_tokens: 0,
+                                cache_read_tokens: total
+                                    .cache_read_tokens
+                                    .saturating_sub(prev.cache_read_tokens),
+                            },
+                            _ => total.clone(),
+                        };
+                        last_total = Some(total);
+                        delta
+                    } else if let Some(last) = info.get("last_token_usage") {
+                        usage_from(last)
+                    } else {
+                        continue;
+                    };
+                    if !usage.is_empty() {
+                        session.push(timestamp, EventKind::Usage, current_model.clone(), Some(usage));
+                    }
+                } else if let Some(item) = payload.get("item") {
+                    // Format written by the Claude -> Codex converter
+                    parse_exec_item(&mut session, item, timestamp, &current_model);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    session
+}
+
+fn parse_response_item(
+    session: &mut UnifiedSession,
+    payload: &Value,
+    timestamp: Option<String>,
+    model: &Option<String>,
+) {
+    match payload["type"].as_str().unwrap_or("") {
+        "message" => {
+            let role = payload["role"].as_str().unwrap_or("");
+            let text = payload["content"]
+                .as_array()
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(|item| item["text"].as_str())
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                })
+                .unwrap_or_default();
+            if text.trim().is_empty() {
+                return;
+            }
+
+            match role {
+                "user" if is_injected_context(&text) => session.push(
+                    timestamp,
+                    EventKind::System {
+                        subtype: Some("context".to_string()),
+                        text,
+                    },
+                    None,
+                    None,
+                ),
+                "user" => session.push(timestamp, EventKind::UserMessage { text }, None, None),
+                "assistant" => session.push(
+                    timestamp,
+                    EventKind::AssistantMessage { text },
+                    model.clone(),
+                    None,
+                ),
+                _ => session.push(
+                    timestamp,
+                    EventKind::System {
+                        subtype: Some(role.to_string()),
+                        text,
+                    },
+                    None,
+                    None,
+                ),
+            }
+        }
+        "reasoning" => {
+            let text = payload["summary"]
+                .as_array()
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(|item| item["text"].as_str())
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                })
+                .unwrap_or_default();
+            if !text.trim().is_empty() {
+                session.push(timestamp, EventKind::Thinking { text }, model.clone(), None);
+            }
+        }
+        "function_call" | "custom_tool_call" | "local_shell_call" => {
+            // function_call arguments are a JSON string, custom tools carry raw input
+            let input = match payload.get("arguments") {
+                Some(Value::String(args)) => {
+                    serde_json::from_str(args).unwrap_or_else(|_| Value::String(args.clone()))
+                }
+                Some(other) => other.clone(),
+                None => payload
+                    .get("input")
+                    .or_else(|| payload.get("action"))
+                    .cloned()
+                    .unwrap_or(Value::Null),
+            };
+            let name = payload["name"]
+                .as_str()
+                .unwrap_or(if payload["type"] == "local_shell_call" {
+                    "shell"
+                } else {
+                    ""
+                })
+                .to_string();
+            session.push(
+                timestamp,
+                EventKind::ToolCall {
+                    call_id: call_id_of(payload),
+                    name,
+                    input,
+                },
+                model.clone(),
+                None,
+            );
+        }
+        "function_call_output" | "custom_tool_call_output" => {
+            // Output is either a plain string or {"output": ..., "metadata": {"exit_code": ..}}
+            let raw = &payload["output"];
+            let parsed = raw
+                .as_str()
+                .and_then(|s| serde_json::from_str::<Value>(s).ok())
+                .filter(|v| v.is_object());
+            let source = parsed.as_ref().unwrap_or(raw);
+            let output = match source.get("output") {
+                Some(inner) => value_to_text(inner),
+                None => value_to_text(raw),
+            };
+            let is_error = source["metadata"]["exit_code"]
+                .as_i64()
+                .map(|code| code != 0)
+                .unwrap_or(false);
+            session.push(
+                timestamp,
+                EventKind::ToolResult {
+                    call_id: call_id_of(payload),
+                    output,
+                    is_error,
+                },
+                None,
+                None,
+            );
+        }
+        _ => {}
+    }
+}
+
+fn call_id_of(payload: &Value) -> String {
+    payload["call_id"]
+        .as_str()
+        .or_else(|| payload["id"].as_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Items from `codex exec --json` (`reasoning`, `agent_message`, `command_execution`)
+fn parse_exec_item(
+    session: &mut UnifiedSession,
+    item: &Value,
+    timestamp: Option<String>,
+    model: &Option<String>,
+) {
+    let text = item["text"].as_str().unwrap_or_default().to_string();
+    match item["type"].as_str().unwrap_or("") {
+        "reasoning" if !text.trim().is_empty() => {
+            session.push(timestamp, EventKind::Thinking { text }, model.clone(), None)
+        }
+        "agent_message" if !text.trim().is_empty() => session.push(
+            timestamp,
+            EventKind::AssistantMessage { text },
+            model.clone(),
+            None,
+        ),
+        "command_execution" => {
+            let call_id = item["id"].as_str().unwrap_or_default().to_string();
+            session.push(
+                timestamp.clone(),
+                EventKind::ToolCall {
+                    call_id: call_id.clone(),
+                    name: "shell".to_string(),
+                    input: serde_json::json!({ "command": item["command"] }),
+                },
+                model.clone(),
+                None,
+            );
+            if let Some(output) = item["aggregated_output"].as_str() {
+                session.push(
+                    timestamp,
+                    EventKind::ToolResult {
+                        call_id,
+                        output: output.to_string(),
+                        is_error: item["exit_code"].as_i64().map(|c| c != 0).unwrap_or(false),
+                    },
+                    None,
+                    None,
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rollout_and_turns_totals_into_deltas() {
+        let content = [
+            r#"{"timestamp":"2025-01-01T00:00:00Z","type":"session_meta","payload":{"id":"sess-1","timestamp":"2025-01-01T00:00:00Z","cwd":"/repo"}}"#,
+            r#"{"timestamp":"2025-01-01T00:00:00Z","type":"turn_context","payload":{"model":"gpt-5-codex"}}"#,
+            r#"{"timestamp":"2025-01-01T00:00:01Z","type":"response_item","payload":{"type":"message","role":"user","content":[{"type":"input_text","text":"<environment_context>cwd</environment_context>"}]}}"#,
+            r#"{"timestamp":"2025-01-01T00:00:02Z","type":"response_item","payload":{"type":"message","role":"user","content":[{"type":"input_text","text":"list files"}]}}"#,
+            r#"{"timestamp":"2025-01-01T00:00:03Z","type":"response_item","payload":{"type":"function_call","name":"shell","arguments":"{\"command\":[\"ls\"]}","call_id":"c1"}}"#,
+            r#"{"timestamp":"2025-01-01T00:00:04Z","type":"response_item","payload":{"type":"function_call_output","call_id":"c1","output":"{\"output\":\"a.rs\",\"metadata\":{\"exit_code\":0}}"}}"#,
+            r#"{"timestamp":"2025-01-01T00:00:05Z","type":"event_msg","payload":{"type":"token_count","info":{"total_token_usage":{"input_tokens":100,"cached_input_tokens":40,"output_tokens":10}}}}"#,
+            r#"{"timestamp":"2025-01-01T00:00:06Z","type":"event_msg","payload":{"type":"token_count","info":{"total_token_usage":{"input_tokens":100,"cached_input_tokens":40,"output_tokens":10}}}}"#,
+            r#"{"timestamp":"2025-01-01T00:00:07Z","type":"event_msg","payload":{"type":"token_count","info":{"total_token_usage":{"input_tokens":250,"cached_input_tokens":140,"output_tokens":30}}}}"#,
+        ]
+        .join("\n");
+
+        let session = parse_session(Path::new("/tmp/rollout-x.jsonl"), &content);
+
+        assert_eq!(session.id, "sess-1");
+        assert_eq!(session.project_path.as_deref(), Some("/repo"));
+        assert_eq!(session.first_message(), Some("list files"));
+        assert!(matches!(
+            &session.events[2].kind,
+            EventKind::ToolCall { name, input, .. } if name == "shell" && input["command"][0] == "ls"
+        ));
+        assert!(matches!(
+            &session.events[3].kind,
+            EventKind::ToolResult { output, is_error: false, .. } if output == "a.rs"
+        ));
+
+        let total = session.total_usage();
+        assert_eq!(total.input_tokens, 250);
+        assert_eq!(total.output_tokens, 30);
+        assert_eq!(total.cache_read_tokens, 140);
+    }
+}
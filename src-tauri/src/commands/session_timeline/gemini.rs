@@ -0,0 +1,347 @@
+//! Gemini CLI chat loader
+//!
+//! Chats are stored as one JSON document per session under
+//! `~/.gemini/tmp/<sha256(project_path)>/chats/`. The project path itself is
+//! not recorded, so it is only known when the caller passes it as a hint.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use super::{
+    value_to_text, EventKind, SessionEngine, SessionLoader, SessionRef, UnifiedSession,
+    UnifiedUsage,
+};
+use crate::commands::gemini::config::{get_gemini_dir, get_project_session_dir};
+use crate::commands::gemini::types::GeminiSessionDetail;
+
+pub struct GeminiSessionLoader;
+
+/// Subagent/task sessions start with this prompt and are hidden from history
+fn is_task_session(detail: &GeminiSessionDetail) -> bool {
+    detail
+        .messages
+        .first()
+        .and_then(|m| m.get("content"))
+        .and_then(|c| c.as_str())
+        .map(|s| s.trim_start().starts_with("Your task is to"))
+        .unwrap_or(false)
+}
+
+fn read_detail(path: &Path) -> Result<GeminiSessionDetail, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read session file: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse session file: {}", e))
+}
+
+/// Finds the chat file for `session_id` inside one `chats/` directory
+fn find_in_chats_dir(chats_dir: &Path, session_id: &str) -> Option<PathBuf> {
+    for entry in fs::read_dir(chats_dir).ok()?.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|s| s.to_str()) != Some("json") {
+            continue;
+        }
+        if let Ok(detail) = read_detail(&path) {
+            if detail.session_id == session_id {
+                return Some(path);
+            }
+        }
+    }
+    None
+}
+
+impl SessionLoader for GeminiSessionLoader {
+    fn engine(&self) -> SessionEngine {
+        SessionEngine::Gemini
+    }
+
+    fn list_sessions(&self) -> Result<Vec<SessionRef>, String> {
+        let tmp_dir = get_gemini_dir()?.join("tmp");
+        if !tmp_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut refs = Vec::new();
+        let projects =
+            fs::read_dir(&tmp_dir).map_err(|e| format!("Failed to read Gemini tmp directory: {}", e))?;
+
+        for project in projects.flatten() {
+            let chats_dir = project.path().join("chats");
+            let Ok(entries) = fs::read_dir(&chats_dir) else {
+                continue;
+            };
+            let project_hash = project.file_name().to_string_lossy().to_string();
+
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                    continue;
+                }
+                let Ok(detail) = read_detail(&path) else {
+                    continue;
+                };
+                if is_task_session(&detail) {
+                    continue;
+                }
+                refs.push(SessionRef::from_path(
+                    SessionEngine::Gemini,
+                    detail.session_id,
+                    Some(project_hash.clone()),
+                    path,
+                ));
+            }
+        }
+
+        Ok(refs)
+    }
+
+    fn locate(&self, session_id: &str, project_hint: Option<&str>) -> Result<PathBuf, String> {
+        if let Some(project_path) = project_hint {
+            let chats_dir = get_project_session_dir(project_path)?.join("chats");
+            if let Some(path) = find_in_chats_dir(&chats_dir, session_id) {
+                return Ok(path);
+            }
+        }
+
+        let tmp_dir = get_gemini_dir()?.join("tmp");
+        if let Ok(projects) = fs::read_dir(&tmp_dir) {
+            for project in projects.flatten() {
+                if let Some(path) = find_in_chats_dir(&project.path().join("chats"), session_id) {
+                    return Ok(path);
+                }
+            }
+        }
+
+        Err(format!("Gemini session file not found: {}", session_id))
+    }
+
+    fn load_file(&self, path: &Path) -> Result<UnifiedSession, String> {
+        let detail = read_detail(path)?;
+        Ok(parse_session(path, &detail))
+    }
+
+    fn load(&self, session_id: &str, project_hint: Option<&str>) -> Result<UnifiedSession, String> {
+        let path = self.locate(session_id, project_hint)?;
+        let mut session = self.load_file(&path)?;
+        session.project_path = project_hint.map(|s| s.to_string());
+        Ok(session)
+    }
+}
+
+/// Unwraps `[{ functionResponse: { response: { output } } }]` into the raw output
+fn tool_result_text(tool_call: &Value) -> Option<String> {
+    let result = tool_call.get("result").filter(|v| !v.is_null());
+    if let Some(result) = result {
+        let output = result
+            .as_array()
+            .and_then(|items| items.first())
+            .and_then(|first| first.pointer("/functionResponse/response/output"))
+            .unwrap_or(result);
+        return Some(value_to_text(output));
+    }
+    // Older sessions only keep the display summary
+    tool_call
+        .get("resultDisplay")
+        .filter(|v| !v.is_null())
+        .map(value_to_text)
+}
+
+fn extract_usage(message: &Value) -> Option<UnifiedUsage> {
+    let tokens = message.get("tokens")?;
+    let field = |name: &str| tokens.get(name).and_then(|v| v.as_u64()).unwrap_or(0);
+    Some(UnifiedUsage {
+        input_tokens: field("input"),
+        // Thinking tokens are billed as output
+        output_tokens: field("output") + field("thoughts"),
+        cache_creation_tokens: 0,
+        cache_read_tokens: field("cached"),
+    })
+}
+
+/// Converts a parsed Gemini chat into the unified model
+pub fn parse_session(path: &Path, detail: &GeminiSessionDetail) -> UnifiedSession {
+    let mut session = UnifiedSession::new(detail.session_id.clone(), SessionEngine::Gemini, path);
+
+    for message in &detail.messages {
+        let timestamp = message
+            .get("timestamp")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+        let text = message.get("content").map(value_to_text).unwrap_or_default();
+
+        match message.get("type").and_then(|v| v.as_str()).unwrap_or("") {
+            "user" => {
+                if !text.trim().is_empty() {
+                    session.push(timestamp, EventKind::UserMessage { text }, None, None);
+                }
+            }
+            "gemini" => parse_model_message(&mut session, message, timestamp, text),
+            other => {
+                if !text.trim().is_empty() {
+                    session.push(
+                        timestamp,
+                        EventKind::System {
+                            subtype: Some(other.to_string()),
+                            text,
+                        },
+                        None,
+                        None,
+                    );
+                }
+            }
+        }
+    }
+
+    if session.started_at.is_none() && !detail.start_time.is_empty() {
+        session.started_at = Some(detail.start_time.clone());
+    }
+    if !detail.last_updated.is_empty() {
+        session.updated_at = Some(detail.last_updated.clone());
+    }
+
+    session
+}
+
+fn parse_model_message(
+    session: &mut UnifiedSession,
+    message: &Value,
+    timestamp: Option<String>,
+    text: String,
+) {
+    let model = message
+        .get("model")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    let mut usage = extract_usage(message);
+
+    if let Some(thoughts) = message.get("thoughts").and_then(|v| v.as_array()) {
+        let text = thoughts
+            .iter()
+            .map(|t| {
+                let subject = t.get("subject").and_then(|v| v.as_str()).unwrap_or_default();
+                let description = t
+                    .get("description")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default();
+                if subject.is_empty() {
+                    description.to_string()
+                } else {
+                    format!("**{}** {}", subject, description)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        if !text.trim().is_empty() {
+            session.push(
+                timestamp.clone(),
+                EventKind::Thinking { text },
+                model.clone(),
+                usage.take(),
+            );
+        }
+    }
+
+    if let Some(tool_calls) = message.get("toolCalls").and_then(|v| v.as_array()) {
+        for tool_call in tool_calls {
+            let call_id = tool_call
+                .get("id")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string();
+            let call_timestamp = tool_call
+                .get("timestamp")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+                .or_else(|| timestamp.clone());
+
+            session.push(
+                call_timestamp.clone(),
+                EventKind::ToolCall {
+                    call_id: call_id.clone(),
+                    name: tool_call
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+                    input: tool_call.get("args").cloned().unwrap_or(Value::Null),
+                },
+                model.clone(),
+                usage.take(),
+            );
+
+            if let Some(output) = tool_result_text(tool_call) {
+                let is_error = tool_call.get("status").and_then(|v| v.as_str()) == Some("error");
+                session.push(
+                    call_timestamp,
+                    EventKind::ToolResult {
+                        call_id,
+                        output,
+                        is_error,
+                    },
+                    None,
+                    None,
+                );
+            }
+        }
+    }
+
+    if !text.trim().is_empty() {
+        session.push(
+            timestamp,
+            EventKind::AssistantMessage { text },
+            model,
+            usage.take(),
+        );
+    } else if usage.is_some() {
+        session.push(timestamp, EventKind::Usage, model, usage);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tool_calls_and_function_response_output() {
+        let detail: GeminiSessionDetail = serde_json::from_value(serde_json::json!({
+            "sessionId": "g-1",
+            "projectHash": "abc",
+            "startTime": "2025-01-01T00:00:00Z",
+            "lastUpdated": "2025-01-01T00:01:00Z",
+            "messages": [
+                { "id": "1", "timestamp": "2025-01-01T00:00:00Z", "type": "user", "content": "read main.rs" },
+                {
+                    "id": "2",
+                    "timestamp": "2025-01-01T00:00:05Z",
+                    "type": "gemini",
+                    "content": "Done.",
+                    "model": "gemini-2.5-pro",
+                    "tokens": { "input": 100, "output": 20, "cached": 50, "thoughts": 5 },
+                    "toolCalls": [{
+                        "id": "t1",
+                        "name": "read_file",
+                        "args": { "absolute_path": "/repo/main.rs" },
+                        "status": "success",
+                        "resultDisplay": "",
+                        "result": [{ "functionResponse": { "response": { "output": "fn main() {}" } } }]
+                    }]
+                }
+            ]
+        }))
+        .unwrap();
+
+        let session = parse_session(Path::new("/tmp/session-1.json"), &detail);
+
+        assert_eq!(session.id, "g-1");
+        assert_eq!(session.model.as_deref(), Some("gemini-2.5-pro"));
+        assert_eq!(session.events.len(), 4);
+        assert!(matches!(
+            &session.events[2].kind,
+            EventKind::ToolResult { call_id, output, .. } if call_id == "t1" && output == "fn main() {}"
+        ));
+        assert_eq!(session.events[1].usage.as_ref().map(|u| u.output_tokens), Some(25));
+        assert_eq!(session.total_usage().cache_read_tokens, 50);
+        assert_eq!(session.updated_at.as_deref(), Some("2025-01-01T00:01:00Z"));
+    }
+}
@@ -0,0 +1,359 @@
+//! Unified Session Timeline
+//!
+//! Claude JSONL transcripts, Codex rollout files and Gemini chat logs all
+//! describe the same thing in three different shapes. This module normalises
+//! them into a single [`UnifiedSession`] made of [`UnifiedEvent`]s so that
+//! downstream features (search, export, usage, rewind) only deal with one type.
+//!
+//! ## Module Structure
+//!
+//! - `claude` - `~/.claude/projects/<project-id>/<session-id>.jsonl`
+//! - `codex` - `~/.codex/sessions/YYYY/MM/DD/rollout-*.jsonl`
+//! - `gemini` - `~/.gemini/tmp/<project-hash>/chats/session-*.json`
+//!
+//! Each engine implements [`SessionLoader`]; use [`loader_for`] to get one.
+
+pub mod claude;
+pub mod codex;
+pub mod gemini;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Engine that produced a session
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionEngine {
+    #[default]
+    Claude,
+    Codex,
+    Gemini,
+}
+
+impl SessionEngine {
+    pub const ALL: [SessionEngine; 3] = [
+        SessionEngine::Claude,
+        SessionEngine::Codex,
+        SessionEngine::Gemini,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionEngine::Claude => "claude",
+            SessionEngine::Codex => "codex",
+            SessionEngine::Gemini => "gemini",
+        }
+    }
+}
+
+impl std::str::FromStr for SessionEngine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "claude" => Ok(SessionEngine::Claude),
+            "codex" => Ok(SessionEngine::Codex),
+            "gemini" => Ok(SessionEngine::Gemini),
+            other => Err(format!("Unknown engine: '{}'", other)),
+        }
+    }
+}
+
+/// Token usage attached to an event
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UnifiedUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+}
+
+impl UnifiedUsage {
+    pub fn is_empty(&self) -> bool {
+        self.input_tokens == 0
+            && self.output_tokens == 0
+            && self.cache_creation_tokens == 0
+            && self.cache_read_tokens == 0
+    }
+
+    pub fn add(&mut self, other: &UnifiedUsage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_tokens += other.cache_creation_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens + self.cache_creation_tokens + self.cache_read_tokens
+    }
+}
+
+/// What happened at a point of the timeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EventKind {
+    /// Prompt typed by the user
+    UserMessage { text: String },
+    /// Text answer from the model
+    AssistantMessage { text: String },
+    /// Reasoning / thinking output
+    Thinking { text: String },
+    /// Tool invocation requested by the model (name as reported by the engine)
+    ToolCall {
+        call_id: String,
+        name: String,
+        input: Value,
+    },
+    /// Output of a tool invocation
+    ToolResult {
+        call_id: String,
+        output: String,
+        is_error: bool,
+    },
+    /// Engine/system notice (init, summaries, injected context, errors)
+    System {
+        subtype: Option<String>,
+        text: String,
+    },
+    /// Usage report not attached to any content (e.g. Codex `token_count`)
+    Usage,
+}
+
+/// A single entry of the unified timeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnifiedEvent {
+    /// Position in the timeline (0-based)
+    pub index: usize,
+    /// ISO 8601 timestamp, if the source recorded one
+    pub timestamp: Option<String>,
+    #[serde(flatten)]
+    pub kind: EventKind,
+    /// Model that produced the event (assistant side only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Token usage reported with this event (counted once per API response)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<UnifiedUsage>,
+}
+
+/// A whole session in the unified format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnifiedSession {
+    pub id: String,
+    pub engine: SessionEngine,
+    /// Working directory of the session, when the engine records it
+    pub project_path: Option<String>,
+    /// File the session was loaded from
+    pub source_path: String,
+    /// Last model seen in the session
+    pub model: Option<String>,
+    pub started_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub events: Vec<UnifiedEvent>,
+}
+
+impl UnifiedSession {
+    pub(crate) fn new(id: String, engine: SessionEngine, source_path: &Path) -> Self {
+        Self {
+            id,
+            engine,
+            project_path: None,
+            source_path: source_path.to_string_lossy().to_string(),
+            model: None,
+            started_at: None,
+            updated_at: None,
+            events: Vec::new(),
+        }
+    }
+
+    /// Appends an event, assigning its index and tracking timestamps/model
+    pub(crate) fn push(
+        &mut self,
+        timestamp: Option<String>,
+        kind: EventKind,
+        model: Option<String>,
+        usage: Option<UnifiedUsage>,
+    ) {
+        if let Some(ts) = &timestamp {
+            if self.started_at.is_none() {
+                self.started_at = Some(ts.clone());
+            }
+            self.updated_at = Some(ts.clone());
+        }
+        if model.is_some() {
+            self.model = model.clone();
+        }
+        self.events.push(UnifiedEvent {
+            index: self.events.len(),
+            timestamp,
+            kind,
+            model,
+            usage: usage.filter(|u| !u.is_empty()),
+        });
+    }
+
+    /// User prompts in order (what rewind and search treat as "prompts")
+    pub fn user_prompts(&self) -> Vec<&UnifiedEvent> {
+        self.events
+            .iter()
+            .filter(|e| matches!(e.kind, EventKind::UserMessage { .. }))
+            .collect()
+    }
+
+    /// First user prompt, used as the session title
+    pub fn first_message(&self) -> Option<&str> {
+        self.events.iter().find_map(|e| match &e.kind {
+            EventKind::UserMessage { text } => Some(text.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Sum of all usage in the session
+    pub fn total_usage(&self) -> UnifiedUsage {
+        let mut total = UnifiedUsage::default();
+        for usage in self.events.iter().filter_map(|e| e.usage.as_ref()) {
+            total.add(usage);
+        }
+        total
+    }
+}
+
+/// Lightweight pointer to a session file on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRef {
+    pub engine: SessionEngine,
+    pub session_id: String,
+    /// Engine-specific project key: Claude project dir name, Gemini project hash, Codex cwd
+    pub project_key: Option<String>,
+    pub path: PathBuf,
+    /// File modification time (Unix seconds)
+    pub modified: u64,
+    /// File size in bytes
+    pub size: u64,
+}
+
+impl SessionRef {
+    pub(crate) fn from_path(
+        engine: SessionEngine,
+        session_id: String,
+        project_key: Option<String>,
+        path: PathBuf,
+    ) -> Self {
+        let metadata = std::fs::metadata(&path).ok();
+        let modified = metadata
+            .as_ref()
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let size = metadata.map(|m| m.len()).unwrap_or(0);
+
+        Self {
+            engine,
+            session_id,
+            project_key,
+            path,
+            modified,
+            size,
+        }
+    }
+}
+
+// ============================================================================
+// Loader Trait
+// ============================================================================
+
+/// One implementation per engine
+pub trait SessionLoader: Send + Sync {
+    fn engine(&self) -> SessionEngine;
+
+    /// Enumerates every session file the engine has written
+    fn list_sessions(&self) -> Result<Vec<SessionRef>, String>;
+
+    /// Finds the session file for an id.
+    ///
+    /// `project_hint` is the Claude project id or the Gemini project path;
+    /// without it the loader scans all projects.
+    fn locate(&self, session_id: &str, project_hint: Option<&str>) -> Result<PathBuf, String>;
+
+    /// Parses a session file into the unified model
+    fn load_file(&self, path: &Path) -> Result<UnifiedSession, String>;
+
+    fn load(&self, session_id: &str, project_hint: Option<&str>) -> Result<UnifiedSession, String> {
+        let path = self.locate(session_id, project_hint)?;
+        self.load_file(&path)
+    }
+}
+
+/// Returns the loader for an engine
+pub fn loader_for(engine: SessionEngine) -> Box<dyn SessionLoader> {
+    match engine {
+        SessionEngine::Claude => Box::new(claude::ClaudeSessionLoader),
+        SessionEngine::Codex => Box::new(codex::CodexSessionLoader),
+        SessionEngine::Gemini => Box::new(gemini::GeminiSessionLoader),
+    }
+}
+
+/// Renders a tool output value (string, content-block array or object) as text
+pub(crate) fn value_to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| match item.get("text").and_then(|t| t.as_str()) {
+                Some(text) => text.to_string(),
+                None => value_to_text(item),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        other => other.to_string(),
+    }
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Loads any engine's session as a unified timeline
+#[tauri::command]
+pub async fn load_unified_session(
+    engine: String,
+    session_id: String,
+    project_hint: Option<String>,
+) -> Result<UnifiedSession, String> {
+    let engine: SessionEngine = engine.parse()?;
+    log::info!(
+        "Loading unified {} session: {} (hint: {:?})",
+        engine.as_str(),
+        session_id,
+        project_hint
+    );
+
+    loader_for(engine).load(&session_id, project_hint.as_deref())
+}
+
+/// Lists session files for one engine, or all engines when `engine` is omitted
+#[tauri::command]
+pub async fn list_unified_sessions(engine: Option<String>) -> Result<Vec<SessionRef>, String> {
+    let engines = match engine {
+        Some(e) => vec![e.parse::<SessionEngine>()?],
+        None => SessionEngine::ALL.to_vec(),
+    };
+
+    let mut refs = Vec::new();
+    for engine in engines {
+        match loader_for(engine).list_sessions() {
+            Ok(list) => refs.extend(list),
+            Err(e) => log::warn!("Failed to list {} sessions: {}", engine.as_str(), e),
+        }
+    }
+
+    refs.sort_by_key(|r| std::cmp::Reverse(r.modified));
+    Ok(refs)
+}
@@ -0,0 +1,768 @@
+//! Session Worktrees
+//!
+//! Opt-in isolation for agents working on the same repository: each new
+//! session gets its own `git worktree` on a dedicated branch and the runner
+//! starts the CLI there instead of in the project directory. File edits and
+//! rewind checkpoints of the session therefore stay in its worktree and no
+//! longer interleave with other sessions.
+//!
+//! Worktrees live under `~/.anycode/worktrees/<repo>-<hash>/<id>` and are
+//! recorded in `~/.anycode/session_worktrees.json` together with the config.
+//! A worktree is created before the CLI knows its session ID; the runner binds
+//! the ID once the CLI reports it, and later runs and prompt records of that
+//! session are resolved to the worktree through [`resolve_project_path`].
+//!
+//! A worktree starts from the project's `HEAD`; uncommitted changes in the
+//! project directory are not carried over.
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use super::claude::{encode_project_path, normalize_path_for_comparison};
+use super::session_timeline::SessionEngine;
+use super::simple_git::{self, git_output};
+use crate::utils::config_utils::{load_json_config, save_json_config};
+
+/// Patches larger than this are cut off in [`WorktreeDiff::patch`]
+const MAX_PATCH_BYTES: usize = 512 * 1024;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// When sessions get their own worktree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WorktreeConfig {
+    pub enabled: bool,
+    /// Limit isolation to these projects; empty means every project
+    pub project_paths: Vec<String>,
+    /// Directory the worktrees are created in (default `~/.anycode/worktrees`)
+    pub root_dir: Option<String>,
+    /// Session branches are named `<prefix>/<worktree id>`
+    pub branch_prefix: String,
+}
+
+impl Default for WorktreeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            project_paths: Vec::new(),
+            root_dir: None,
+            branch_prefix: "anycode/session".to_string(),
+        }
+    }
+}
+
+impl WorktreeConfig {
+    fn applies_to(&self, project_path: &str) -> bool {
+        self.enabled
+            && (self.project_paths.is_empty()
+                || self
+                    .project_paths
+                    .iter()
+                    .any(|p| same_path(p, project_path)))
+    }
+}
+
+/// A worktree created for a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionWorktree {
+    pub id: String,
+    pub engine: SessionEngine,
+    /// Project the worktree was created from and merges back into
+    pub project_path: String,
+    pub worktree_path: String,
+    pub branch: String,
+    /// Project `HEAD` the branch started from
+    pub base_commit: String,
+    /// Sessions run in the worktree, in the order the CLI reported them
+    #[serde(default)]
+    pub session_ids: Vec<String>,
+    pub created_at: String,
+}
+
+/// Worktrees and config saved in `~/.anycode/session_worktrees.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WorktreeStore {
+    #[serde(default)]
+    config: WorktreeConfig,
+    #[serde(default)]
+    worktrees: Vec<SessionWorktree>,
+}
+
+/// A worktree with its current git state
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionWorktreeInfo {
+    #[serde(flatten)]
+    pub worktree: SessionWorktree,
+    /// The worktree directory is still there
+    pub exists: bool,
+    /// Uncommitted changes in the worktree
+    pub dirty: bool,
+    /// Commits on the session branch that the project `HEAD` does not have
+    pub commits_ahead: usize,
+    /// The session branch is contained in the project `HEAD`
+    pub merged: bool,
+}
+
+/// A file changed in a session worktree
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorktreeFileChange {
+    pub path: String,
+    /// Git status letter (`A`, `M`, `D`, `R`...), `?` for untracked files
+    pub status: String,
+    pub additions: Option<usize>,
+    pub deletions: Option<usize>,
+}
+
+/// Changes of a session worktree against the point it forked from the project
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorktreeDiff {
+    pub base: String,
+    pub files: Vec<WorktreeFileChange>,
+    pub patch: String,
+    pub truncated: bool,
+}
+
+/// Outcome of merging a session branch back into the project
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorktreeMergeResult {
+    pub merged: bool,
+    /// Project `HEAD` after the merge
+    pub commit: Option<String>,
+    /// Files that conflicted; the merge is aborted when there are any
+    pub conflicts: Vec<String>,
+    pub message: String,
+}
+
+// ============================================================================
+// Registry
+// ============================================================================
+
+/// Serialises load-modify-save cycles of the registry file
+static STORE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+fn get_store_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Failed to get home directory")?;
+    Ok(home.join(".anycode").join("session_worktrees.json"))
+}
+
+fn load_store() -> Result<WorktreeStore, String> {
+    load_json_config(get_store_path()?)
+}
+
+/// Loads the registry, applies `update` and saves it back
+fn update_store<T>(update: impl FnOnce(&mut WorktreeStore) -> T) -> Result<T, String> {
+    let _guard = STORE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut store = load_store()?;
+    let result = update(&mut store);
+    save_json_config(&store, get_store_path()?)?;
+    Ok(result)
+}
+
+fn same_path(a: &str, b: &str) -> bool {
+    normalize_path_for_comparison(a) == normalize_path_for_comparison(b)
+}
+
+fn find_worktree(id: &str) -> Result<SessionWorktree, String> {
+    load_store()?
+        .worktrees
+        .into_iter()
+        .find(|w| w.id == id)
+        .ok_or_else(|| format!("Session worktree '{}' not found", id))
+}
+
+fn find_session_worktree<'a>(
+    worktrees: &'a [SessionWorktree],
+    session_id: &str,
+) -> Option<&'a SessionWorktree> {
+    worktrees
+        .iter()
+        .find(|w| w.session_ids.iter().any(|s| s == session_id))
+        .filter(|w| Path::new(&w.worktree_path).exists())
+}
+
+/// Worktree a session runs in, if it still exists
+pub fn worktree_for_session(session_id: &str) -> Option<SessionWorktree> {
+    find_session_worktree(&load_store().ok()?.worktrees, session_id).cloned()
+}
+
+/// Directory a session's runs and rewind commits belong in: its worktree when
+/// it has one, `project_path` otherwise
+pub fn resolve_project_path(session_id: &str, project_path: &str) -> String {
+    match worktree_for_session(session_id) {
+        Some(worktree) if !same_path(&worktree.worktree_path, project_path) => {
+            log::debug!(
+                "[Worktree] Session {} runs in {}",
+                session_id,
+                worktree.worktree_path
+            );
+            worktree.worktree_path
+        }
+        _ => project_path.to_string(),
+    }
+}
+
+/// `~/.claude/projects` directory name of a Claude session, pointing at its
+/// worktree when it has one
+///
+/// Session file loaders go through `claude::get_session_project_dir`, which
+/// applies this mapping.
+pub fn resolve_claude_project_id(session_id: &str, project_id: &str) -> String {
+    let worktrees = load_store().map(|s| s.worktrees).unwrap_or_default();
+    claude_project_id_in(&worktrees, session_id, project_id)
+}
+
+pub(crate) fn claude_project_id_in(
+    worktrees: &[SessionWorktree],
+    session_id: &str,
+    project_id: &str,
+) -> String {
+    match find_session_worktree(worktrees, session_id) {
+        Some(worktree) => encode_project_path(&worktree.worktree_path),
+        None => project_id.to_string(),
+    }
+}
+
+/// Directory a new session should run in
+///
+/// Creates a worktree when isolation applies to the project; a project that is
+/// itself a session worktree is used as is.
+pub fn prepare_session_worktree(
+    project_path: &str,
+    engine: SessionEngine,
+) -> Result<String, String> {
+    let store = load_store()?;
+    if !store.config.applies_to(project_path)
+        || store
+            .worktrees
+            .iter()
+            .any(|w| same_path(&w.worktree_path, project_path))
+    {
+        return Ok(project_path.to_string());
+    }
+
+    let root = match &store.config.root_dir {
+        Some(dir) if !dir.trim().is_empty() => PathBuf::from(dir),
+        _ => dirs::home_dir()
+            .ok_or("Failed to get home directory")?
+            .join(".anycode")
+            .join("worktrees"),
+    };
+    let worktree = create_worktree(project_path, engine, &root, &store.config.branch_prefix)?;
+    let path = worktree.worktree_path.clone();
+    update_store(|store| store.worktrees.push(worktree))?;
+    Ok(path)
+}
+
+/// Records that the CLI reported `session_id` for a run started in `run_path`
+///
+/// Does nothing when `run_path` is not a session worktree.
+pub fn bind_session(run_path: &str, session_id: &str) {
+    let result = update_store(|store| {
+        let worktree = store
+            .worktrees
+            .iter_mut()
+            .find(|w| same_path(&w.worktree_path, run_path))?;
+        if worktree.session_ids.iter().any(|s| s == session_id) {
+            return None;
+        }
+        worktree.session_ids.push(session_id.to_string());
+        Some(worktree.id.clone())
+    });
+    match result {
+        Ok(Some(id)) => log::info!("[Worktree] Bound session {} to worktree {}", session_id, id),
+        Ok(None) => {}
+        Err(e) => log::warn!("[Worktree] Failed to bind session {}: {}", session_id, e),
+    }
+}
+
+// ============================================================================
+// Git Operations
+// ============================================================================
+
+fn create_worktree(
+    project_path: &str,
+    engine: SessionEngine,
+    root: &Path,
+    branch_prefix: &str,
+) -> Result<SessionWorktree, String> {
+    // Never initialise a repository in the user's project
+    if git_output(project_path, &["rev-parse", "--git-dir"]).is_err() {
+        return Err(format!(
+            "Session worktrees need a Git repository: {} is not one",
+            project_path
+        ));
+    }
+    let base_commit = simple_git::git_current_commit(project_path)
+        .map_err(|_| format!("{} has no commits to start a worktree from", project_path))?;
+
+    let project_name = Path::new(project_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "project".to_string());
+    let mut hasher = DefaultHasher::new();
+    normalize_path_for_comparison(project_path).hash(&mut hasher);
+    let project_dir = format!("{}-{:08x}", project_name, hasher.finish() as u32);
+
+    let id = format!(
+        "{}-{}",
+        engine.as_str(),
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    );
+    let worktree_path = root.join(project_dir).join(&id);
+    let branch = format!("{}/{}", branch_prefix.trim_end_matches('/'), id);
+
+    if let Some(parent) = worktree_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create worktree directory: {}", e))?;
+    }
+    let worktree_path = worktree_path.to_string_lossy().to_string();
+    git_output(
+        project_path,
+        &[
+            "worktree",
+            "add",
+            "-b",
+            &branch,
+            &worktree_path,
+            &base_commit,
+        ],
+    )?;
+
+    log::info!(
+        "[Worktree] Created {} on branch {} for {}",
+        worktree_path,
+        branch,
+        project_path
+    );
+    Ok(SessionWorktree {
+        id,
+        engine,
+        project_path: project_path.to_string(),
+        worktree_path,
+        branch,
+        base_commit,
+        session_ids: Vec::new(),
+        created_at: Utc::now().to_rfc3339(),
+    })
+}
+
+fn is_dirty(path: &str) -> Result<bool, String> {
+    Ok(!git_output(path, &["status", "--porcelain"])?.is_empty())
+}
+
+fn inspect(worktree: SessionWorktree) -> SessionWorktreeInfo {
+    let exists = Path::new(&worktree.worktree_path).exists();
+    let dirty = exists && is_dirty(&worktree.worktree_path).unwrap_or(false);
+    let range = format!("HEAD..{}", worktree.branch);
+    let commits_ahead = git_output(&worktree.project_path, &["rev-list", "--count", &range])
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(0);
+    let merged = git_output(
+        &worktree.project_path,
+        &["merge-base", "--is-ancestor", &worktree.branch, "HEAD"],
+    )
+    .is_ok();
+    SessionWorktreeInfo {
+        worktree,
+        exists,
+        dirty,
+        commits_ahead,
+        merged,
+    }
+}
+
+fn parse_numstat(numstat: &str) -> Vec<(String, Option<usize>, Option<usize>)> {
+    numstat
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            let additions = parts.next()?.parse().ok();
+            let deletions = parts.next()?.parse().ok();
+            Some((parts.next()?.to_string(), additions, deletions))
+        })
+        .collect()
+}
+
+fn worktree_diff(worktree: &SessionWorktree) -> Result<WorktreeDiff, String> {
+    let path = worktree.worktree_path.as_str();
+    let project_head = simple_git::git_current_commit(&worktree.project_path)?;
+    let base = git_output(path, &["merge-base", "HEAD", &project_head])
+        .unwrap_or_else(|_| worktree.base_commit.clone());
+
+    let numstat = parse_numstat(&git_output(
+        path,
+        &["diff", "--numstat", "--no-renames", &base],
+    )?);
+    let mut files: Vec<WorktreeFileChange> =
+        git_output(path, &["diff", "--name-status", "--no-renames", &base])?
+            .lines()
+            .filter_map(|line| {
+                let (status, file) = line.split_once('\t')?;
+                let counts = numstat.iter().find(|(p, _, _)| p == file);
+                Some(WorktreeFileChange {
+                    path: file.to_string(),
+                    status: status.to_string(),
+                    additions: counts.and_then(|c| c.1),
+                    deletions: counts.and_then(|c| c.2),
+                })
+            })
+            .collect();
+    files.extend(
+        git_output(path, &["ls-files", "--others", "--exclude-standard"])?
+            .lines()
+            .map(|file| WorktreeFileChange {
+                path: file.to_string(),
+                status: "?".to_string(),
+                additions: None,
+                deletions: None,
+            }),
+    );
+
+    let mut patch = git_output(path, &["diff", "--no-renames", &base])?;
+    let truncated = patch.len() > MAX_PATCH_BYTES;
+    if truncated {
+        let mut end = MAX_PATCH_BYTES;
+        while !patch.is_char_boundary(end) {
+            end -= 1;
+        }
+        patch.truncate(end);
+    }
+
+    Ok(WorktreeDiff {
+        base,
+        files,
+        patch,
+        truncated,
+    })
+}
+
+fn merge_worktree(
+    worktree: &SessionWorktree,
+    squash: bool,
+    message: Option<&str>,
+) -> Result<WorktreeMergeResult, String> {
+    let project = worktree.project_path.as_str();
+
+    // Whatever the session left uncommitted goes onto its branch first
+    if Path::new(&worktree.worktree_path).exists() && is_dirty(&worktree.worktree_path)? {
+        simple_git::git_commit_changes(
+            &worktree.worktree_path,
+            &format!("[Worktree] Uncommitted changes of {}", worktree.id),
+        )?;
+    }
+
+    if !git_output(project, &["status", "--porcelain", "--untracked-files=no"])?.is_empty() {
+        return Err(format!(
+            "{} has uncommitted changes; commit or stash them before merging",
+            project
+        ));
+    }
+
+    let info = inspect(worktree.clone());
+    if info.commits_ahead == 0 {
+        return Ok(WorktreeMergeResult {
+            merged: false,
+            commit: None,
+            conflicts: Vec::new(),
+            message: format!("{} has nothing to merge", worktree.branch),
+        });
+    }
+
+    let message = message
+        .filter(|m| !m.trim().is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("[Worktree] Merge {}", worktree.branch));
+    let merge = if squash {
+        git_output(project, &["merge", "--squash", &worktree.branch])
+            .and_then(|_| git_output(project, &["commit", "-m", &message]))
+    } else {
+        git_output(
+            project,
+            &["merge", "--no-ff", "-m", &message, &worktree.branch],
+        )
+    };
+
+    if let Err(e) = merge {
+        let conflicts: Vec<String> =
+            git_output(project, &["diff", "--name-only", "--diff-filter=U"])
+                .unwrap_or_default()
+                .lines()
+                .map(str::to_string)
+                .collect();
+        // `reset --merge` also backs out a squash merge, which `merge --abort` does not
+        let _ = git_output(project, &["reset", "--merge"]);
+        if conflicts.is_empty() {
+            return Err(e);
+        }
+        return Ok(WorktreeMergeResult {
+            merged: false,
+            commit: None,
+            message: format!(
+                "Merging {} conflicts in {} file(s); nothing was changed",
+                worktree.branch,
+                conflicts.len()
+            ),
+            conflicts,
+        });
+    }
+
+    let commit = simple_git::git_current_commit(project)?;
+    log::info!(
+        "[Worktree] Merged {} into {} at {}",
+        worktree.branch,
+        project,
+        commit
+    );
+    Ok(WorktreeMergeResult {
+        merged: true,
+        commit: Some(commit),
+        conflicts: Vec::new(),
+        message: format!(
+            "Merged {} commit(s) from {}",
+            info.commits_ahead, worktree.branch
+        ),
+    })
+}
+
+fn remove_worktree(
+    worktree: &SessionWorktree,
+    delete_branch: bool,
+    force: bool,
+) -> Result<(), String> {
+    let project = worktree.project_path.as_str();
+    if Path::new(&worktree.worktree_path).exists() {
+        let mut args = vec!["worktree", "remove"];
+        if force {
+            args.push("--force");
+        }
+        args.push(&worktree.worktree_path);
+        git_output(project, &args)?;
+    } else {
+        git_output(project, &["worktree", "prune"])?;
+    }
+    if delete_branch {
+        let flag = if force { "-D" } else { "-d" };
+        git_output(project, &["branch", flag, &worktree.branch])?;
+    }
+    log::info!("[Worktree] Removed {}", worktree.worktree_path);
+    Ok(())
+}
+
+/// Whether the CLI is running one of the worktree's Claude sessions
+fn has_active_run(app: &AppHandle, worktree: &SessionWorktree) -> bool {
+    let Some(registry) = app.try_state::<crate::process::ProcessRegistryState>() else {
+        return false;
+    };
+    worktree
+        .session_ids
+        .iter()
+        .any(|id| registry.0.find_claude_run(id).ok().flatten().is_some())
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+#[tauri::command]
+pub async fn get_worktree_config() -> Result<WorktreeConfig, String> {
+    Ok(load_store()?.config)
+}
+
+#[tauri::command]
+pub async fn save_worktree_config(mut config: WorktreeConfig) -> Result<WorktreeConfig, String> {
+    config.project_paths.retain(|p| !p.trim().is_empty());
+    config.branch_prefix = config.branch_prefix.trim().trim_matches('/').to_string();
+    if config.branch_prefix.is_empty() {
+        config.branch_prefix = WorktreeConfig::default().branch_prefix;
+    }
+    let saved = config.clone();
+    update_store(|store| store.config = config)?;
+    log::info!("[Worktree] Session isolation enabled: {}", saved.enabled);
+    Ok(saved)
+}
+
+/// Session worktrees with their git state, optionally of one project
+#[tauri::command]
+pub async fn list_session_worktrees(
+    project_path: Option<String>,
+) -> Result<Vec<SessionWorktreeInfo>, String> {
+    Ok(load_store()?
+        .worktrees
+        .into_iter()
+        .filter(|w| {
+            project_path
+                .as_deref()
+                .is_none_or(|p| same_path(&w.project_path, p))
+        })
+        .map(inspect)
+        .collect())
+}
+
+/// Changes of a session worktree, committed or not, against the project
+#[tauri::command]
+pub async fn get_session_worktree_diff(worktree_id: String) -> Result<WorktreeDiff, String> {
+    worktree_diff(&find_worktree(&worktree_id)?)
+}
+
+/// Merges a session branch into the project's current branch
+///
+/// Uncommitted changes in the worktree are committed to the branch first. A
+/// conflicting merge is backed out and reported with the conflicting files.
+#[tauri::command]
+pub async fn merge_session_worktree(
+    worktree_id: String,
+    squash: Option<bool>,
+    message: Option<String>,
+) -> Result<WorktreeMergeResult, String> {
+    let worktree = find_worktree(&worktree_id)?;
+    merge_worktree(&worktree, squash.unwrap_or(false), message.as_deref())
+}
+
+/// Removes a session worktree and, optionally, its branch
+#[tauri::command]
+pub async fn remove_session_worktree(
+    app: AppHandle,
+    worktree_id: String,
+    delete_branch: Option<bool>,
+    force: Option<bool>,
+) -> Result<(), String> {
+    let worktree = find_worktree(&worktree_id)?;
+    if has_active_run(&app, &worktree) {
+        return Err(format!(
+            "A session is still running in {}",
+            worktree.worktree_path
+        ));
+    }
+    remove_worktree(
+        &worktree,
+        delete_branch.unwrap_or(false),
+        force.unwrap_or(false),
+    )?;
+    update_store(|store| store.worktrees.retain(|w| w.id != worktree_id))
+}
+
+/// Removes worktrees whose branch is merged and that have no uncommitted
+/// changes, and forgets worktrees whose directory is gone. Returns the ids of
+/// the worktrees removed.
+#[tauri::command]
+pub async fn cleanup_session_worktrees(
+    app: AppHandle,
+    project_path: Option<String>,
+) -> Result<Vec<String>, String> {
+    let mut removed = Vec::new();
+    for info in list_session_worktrees(project_path).await? {
+        let worktree = &info.worktree;
+        let remove = if !info.exists {
+            let _ = git_output(&worktree.project_path, &["worktree", "prune"]);
+            true
+        } else if info.merged && !info.dirty && !has_active_run(&app, worktree) {
+            match remove_worktree(worktree, true, false) {
+                Ok(()) => true,
+                Err(e) => {
+                    log::warn!("[Worktree] Failed to clean up {}: {}", worktree.id, e);
+                    false
+                }
+            }
+        } else {
+            false
+        };
+        if remove {
+            removed.push(worktree.id.clone());
+        }
+    }
+
+    if !removed.is_empty() {
+        update_store(|store| store.worktrees.retain(|w| !removed.contains(&w.id)))?;
+        log::info!("[Worktree] Cleaned up {} worktree(s)", removed.len());
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) {
+        git_output(dir.to_str().unwrap(), args).unwrap();
+    }
+
+    #[test]
+    fn worktree_diff_merge_and_remove() {
+        let repo = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        git(repo.path(), &["init", "-q"]);
+        git(repo.path(), &["config", "user.name", "Test"]);
+        git(repo.path(), &["config", "user.email", "test@example.com"]);
+        std::fs::write(repo.path().join("README.md"), "hello\n").unwrap();
+        git(repo.path(), &["add", "-A"]);
+        git(repo.path(), &["commit", "-q", "-m", "init"]);
+        let project = repo.path().to_str().unwrap();
+
+        let worktree = create_worktree(
+            project,
+            SessionEngine::Claude,
+            root.path(),
+            "anycode/session",
+        )
+        .unwrap();
+        assert!(worktree.branch.starts_with("anycode/session/claude-"));
+        let wt_path = Path::new(&worktree.worktree_path);
+        std::fs::write(wt_path.join("README.md"), "hello\nworld\n").unwrap();
+        std::fs::write(wt_path.join("notes.txt"), "new\n").unwrap();
+
+        let diff = worktree_diff(&worktree).unwrap();
+        assert_eq!(diff.base, worktree.base_commit);
+        let readme = diff.files.iter().find(|f| f.path == "README.md").unwrap();
+        assert_eq!((readme.status.as_str(), readme.additions), ("M", Some(1)));
+        assert!(diff
+            .files
+            .iter()
+            .any(|f| f.path == "notes.txt" && f.status == "?"));
+        assert!(diff.patch.contains("+world"));
+
+        let result = merge_worktree(&worktree, true, Some("Session work")).unwrap();
+        assert!(result.merged, "{}", result.message);
+        assert_eq!(
+            std::fs::read_to_string(repo.path().join("README.md")).unwrap(),
+            "hello\nworld\n"
+        );
+        let info = inspect(worktree.clone());
+        assert!(info.exists && !info.dirty);
+
+        remove_worktree(&worktree, true, true).unwrap();
+        assert!(!wt_path.exists());
+        assert!(git_output(project, &["rev-parse", "--verify", &worktree.branch]).is_err());
+    }
+
+    #[test]
+    fn projects_without_a_repository_are_left_alone() {
+        let plain = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(plain.path().join("main.rs"), "fn main() {}\n").unwrap();
+
+        let result = create_worktree(
+            plain.path().to_str().unwrap(),
+            SessionEngine::Claude,
+            root.path(),
+            "anycode/session",
+        );
+        assert!(result.unwrap_err().contains("need a Git repository"));
+        assert!(!plain.path().join(".git").exists());
+    }
+}
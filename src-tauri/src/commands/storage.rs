@@ -95,6 +95,21 @@ pub fn init_database(app: &AppHandle) -> SqliteResult<Connection> {
 
     log::info!("✅ Database indexes created successfully (6 indexes)");
 
+    // Incremental usage ingestion bookkeeping
+    super::usage_ingest::init_usage_ingest_tables(&conn)?;
+
+    // Spend ledger for budget limits
+    super::budget::init_budget_tables(&conn)?;
+
+    // Full-text search index over session histories
+    super::session_search::init_search_tables(&conn)?;
+
+    // Execution log of enhanced hooks
+    super::enhanced_hooks::init_hook_log_tables(&conn)?;
+
+    // Auto-compact session state and compaction history
+    super::context_history::init_context_history_tables(&conn)?;
+
     Ok(conn)
 }
 
@@ -0,0 +1,774 @@
+//! Translation Backends
+//!
+//! The providers `TranslationService` sends text to: an OpenAI-compatible
+//! chat completion endpoint, the Anthropic Messages API, a DeepL-style REST
+//! API, or a local command (e.g. `ollama run <model>`) that reads the prompt
+//! on stdin and prints the translation. Each backend reports which language
+//! pairs it can translate; LLM prompts are built from the language names so
+//! any pair in [`LANGUAGES`] works, not just Chinese and English.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use super::claude::apply_no_window_async;
+use super::translator::TranslationConfig;
+use super::url_utils::{normalize_api_url, ApiEndpointType};
+
+/// Upper bound on the translation length requested from LLM backends
+const MAX_OUTPUT_TOKENS: u32 = 4000;
+
+/// Characters of text sent in one batch request, so the translation fits in
+/// [`MAX_OUTPUT_TOKENS`]
+const MAX_BATCH_CHARS: usize = 6000;
+
+/// DeepL rejects requests with more texts than this
+const DEEPL_MAX_TEXTS: usize = 50;
+
+/// DeepL rejects request bodies larger than this
+const DEEPL_MAX_REQUEST_BYTES: usize = 128 * 1024;
+
+/// Room left in a DeepL request for the language codes and JSON framing
+const DEEPL_REQUEST_OVERHEAD: usize = 1024;
+
+/// Which provider translations are sent to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranslationBackendKind {
+    #[default]
+    OpenAI,
+    Anthropic,
+    DeepL,
+    Local,
+}
+
+/// A language translations can be requested in
+pub struct Language {
+    pub code: &'static str,
+    /// English name used in LLM prompts
+    pub name: &'static str,
+    /// DeepL `source_lang` / `target_lang` codes, if DeepL supports it
+    deepl_source: Option<&'static str>,
+    deepl_target: Option<&'static str>,
+}
+
+const fn language(
+    code: &'static str,
+    name: &'static str,
+    deepl_source: Option<&'static str>,
+    deepl_target: Option<&'static str>,
+) -> Language {
+    Language {
+        code,
+        name,
+        deepl_source,
+        deepl_target,
+    }
+}
+
+/// Languages the backends know about, by the codes used in the config and
+/// the frontend
+pub const LANGUAGES: &[Language] = &[
+    language("zh", "Chinese", Some("ZH"), Some("ZH-HANS")),
+    language("zh-TW", "Traditional Chinese", Some("ZH"), Some("ZH-HANT")),
+    language("en", "English", Some("EN"), Some("EN-US")),
+    language("ja", "Japanese", Some("JA"), Some("JA")),
+    language("ko", "Korean", Some("KO"), Some("KO")),
+    language("fr", "French", Some("FR"), Some("FR")),
+    language("de", "German", Some("DE"), Some("DE")),
+    language("es", "Spanish", Some("ES"), Some("ES")),
+    language("it", "Italian", Some("IT"), Some("IT")),
+    language("pt", "Portuguese", Some("PT"), Some("PT-BR")),
+    language("ru", "Russian", Some("RU"), Some("RU")),
+    language("vi", "Vietnamese", None, None),
+];
+
+pub fn find_language(code: &str) -> Option<&'static Language> {
+    LANGUAGES
+        .iter()
+        .find(|lang| lang.code.eq_ignore_ascii_case(code))
+}
+
+/// One implementation per provider
+#[async_trait]
+pub trait TranslationBackend: Send + Sync {
+    fn kind(&self) -> TranslationBackendKind;
+
+    /// Whether the backend can translate from `from` (`None` when the source
+    /// language is left to the backend) to `to`
+    fn supports(&self, from: Option<&str>, to: &str) -> bool;
+
+    /// Translates `text`; fails instead of falling back to the original
+    async fn translate(&self, text: &str, from: Option<&str>, to: &str) -> Result<String>;
+
+    /// Translates several texts, in order, with as few requests as possible
+    ///
+    /// The default joins them with numbered separator lines and splits the
+    /// reply; a chunk whose reply lost a separator is translated text by text.
+    async fn translate_batch(
+        &self,
+        texts: &[String],
+        from: Option<&str>,
+        to: &str,
+    ) -> Result<Vec<String>> {
+        let mut results = Vec::with_capacity(texts.len());
+        for chunk in batch_chunks(texts) {
+            if let [text] = chunk {
+                results.push(self.translate(text, from, to).await?);
+                continue;
+            }
+            let translated = self.translate(&join_batch(chunk), from, to).await?;
+            match split_batch(&translated, chunk.len()) {
+                Some(parts) => results.extend(parts),
+                None => {
+                    warn!(
+                        "[Translation] {:?} backend lost batch separators, translating {} texts one by one",
+                        self.kind(),
+                        chunk.len()
+                    );
+                    for text in chunk {
+                        results.push(self.translate(text, from, to).await?);
+                    }
+                }
+            }
+        }
+        Ok(results)
+    }
+}
+
+/// Separator line between the texts of a batch request
+fn batch_separator(index: usize) -> String {
+    format!("⟦#{}⟧", index)
+}
+
+/// Consecutive texts that fit in one batch request
+fn batch_chunks(texts: &[String]) -> Vec<&[String]> {
+    let mut chunks = Vec::new();
+    let (mut start, mut size) = (0, 0);
+    for (index, text) in texts.iter().enumerate() {
+        let len = text.chars().count();
+        if index > start && size + len > MAX_BATCH_CHARS {
+            chunks.push(&texts[start..index]);
+            (start, size) = (index, 0);
+        }
+        size += len;
+    }
+    if start < texts.len() {
+        chunks.push(&texts[start..]);
+    }
+    chunks
+}
+
+fn join_batch(texts: &[String]) -> String {
+    let mut joined = String::new();
+    for (index, text) in texts.iter().enumerate() {
+        if index > 0 {
+            joined.push_str(&format!("\n\n{}\n\n", batch_separator(index)));
+        }
+        joined.push_str(text);
+    }
+    joined
+}
+
+/// Splits a batch translation back into `count` texts, or `None` when a
+/// separator was lost, duplicated or left a text empty
+fn split_batch(translated: &str, count: usize) -> Option<Vec<String>> {
+    let mut parts = Vec::with_capacity(count);
+    let mut rest = translated;
+    for index in 1..count {
+        let (part, after) = rest.split_once(&batch_separator(index))?;
+        parts.push(part.trim().to_string());
+        rest = after;
+    }
+    if rest.contains("⟦#") {
+        return None;
+    }
+    parts.push(rest.trim().to_string());
+    (!parts.iter().any(String::is_empty)).then_some(parts)
+}
+
+/// Returns the backend selected in the config
+pub fn backend_for(config: &TranslationConfig) -> Box<dyn TranslationBackend> {
+    let timeout = Duration::from_secs(config.timeout_seconds);
+    let client = || {
+        Client::builder()
+            .timeout(timeout)
+            .build()
+            .expect("Failed to create HTTP client")
+    };
+    match config.backend {
+        TranslationBackendKind::OpenAI => Box::new(OpenAIBackend {
+            client: client(),
+            api_url: normalize_api_url(&config.api_base_url, ApiEndpointType::OpenAI),
+            api_key: config.api_key.clone(),
+            model: config.model.clone(),
+        }),
+        TranslationBackendKind::Anthropic => Box::new(AnthropicBackend {
+            client: client(),
+            api_url: normalize_api_url(&config.api_base_url, ApiEndpointType::Anthropic),
+            api_key: config.api_key.clone(),
+            model: config.model.clone(),
+        }),
+        TranslationBackendKind::DeepL => Box::new(DeepLBackend {
+            client: client(),
+            api_url: normalize_deepl_url(&config.api_base_url),
+            api_key: config.api_key.clone(),
+        }),
+        TranslationBackendKind::Local => Box::new(LocalBackend {
+            command: config.local_command.clone(),
+            timeout,
+        }),
+    }
+}
+
+/// Code, paths and batch separators reach the backends as `⟦0⟧` / `⟦#1⟧`
+/// markers that have to come back untouched
+const PLACEHOLDER_RULE: &str = "Markers such as ⟦0⟧ or ⟦#1⟧ stand for code and \
+    separators: keep every marker exactly as written, once, at the matching place in the \
+    translation.";
+
+/// System prompt for LLM backends; the zh/en wording predates the other pairs
+fn system_prompt(from: Option<&str>, to: &str) -> String {
+    let target = find_language(to).map_or(to, |lang| lang.name);
+    match from.and_then(find_language).map(|lang| lang.name) {
+        Some(source) => format!(
+            "You are a professional {source} to {target} translator. Translate the following \
+             {source} text to natural, fluent {target} while preserving the original meaning \
+             and tone. {PLACEHOLDER_RULE} Only return the translated text, nothing else.",
+        ),
+        None => format!(
+            "You are a professional translator. Translate the following text to natural, \
+             fluent {target} while preserving the original meaning and tone. \
+             {PLACEHOLDER_RULE} Only return the translated text, nothing else.",
+        ),
+    }
+}
+
+/// LLMs translate between any pair they have a language name for
+fn llm_supports(from: Option<&str>, to: &str) -> bool {
+    from.is_none_or(|code| find_language(code).is_some()) && find_language(to).is_some()
+}
+
+fn require_api_key(api_key: &str) -> Result<()> {
+    if api_key.is_empty() {
+        return Err(anyhow::anyhow!(
+            "API密钥未配置，请在设置中填写翻译服务的API密钥"
+        ));
+    }
+    Ok(())
+}
+
+/// Sends a JSON request and returns the JSON response, failing on non-2xx
+async fn post_json(request: reqwest::RequestBuilder, body: &Value) -> Result<Value> {
+    let response = request
+        .header("Content-Type", "application/json")
+        .json(body)
+        .send()
+        .await
+        .context("Failed to send translation request")?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(anyhow::anyhow!(
+            "Translation API error: {} - {}",
+            status,
+            error_text
+        ));
+    }
+
+    response
+        .json()
+        .await
+        .context("Failed to parse API response")
+}
+
+// ============================================================================
+// OpenAI-compatible chat completions
+// ============================================================================
+
+struct OpenAIBackend {
+    client: Client,
+    api_url: String,
+    api_key: String,
+    model: String,
+}
+
+#[async_trait]
+impl TranslationBackend for OpenAIBackend {
+    fn kind(&self) -> TranslationBackendKind {
+        TranslationBackendKind::OpenAI
+    }
+
+    fn supports(&self, from: Option<&str>, to: &str) -> bool {
+        llm_supports(from, to)
+    }
+
+    async fn translate(&self, text: &str, from: Option<&str>, to: &str) -> Result<String> {
+        require_api_key(&self.api_key)?;
+        debug!("Using normalized API URL: {}", self.api_url);
+
+        let body = json!({
+            "model": self.model,
+            "messages": [
+                { "role": "system", "content": system_prompt(from, to) },
+                { "role": "user", "content": text }
+            ],
+            "temperature": 0.1,
+            "max_tokens": MAX_OUTPUT_TOKENS,
+            "stream": false
+        });
+        let request = self
+            .client
+            .post(&self.api_url)
+            .header("Authorization", format!("Bearer {}", self.api_key));
+        let response = post_json(request, &body).await?;
+
+        response
+            .get("choices")
+            .and_then(|choices| choices.get(0))
+            .and_then(|choice| choice.get("message"))
+            .and_then(|message| message.get("content"))
+            .and_then(|content| content.as_str())
+            .map(|text| text.trim().to_string())
+            .ok_or_else(|| anyhow::anyhow!("Invalid API response format"))
+    }
+}
+
+// ============================================================================
+// Anthropic Messages API
+// ============================================================================
+
+struct AnthropicBackend {
+    client: Client,
+    api_url: String,
+    api_key: String,
+    model: String,
+}
+
+#[async_trait]
+impl TranslationBackend for AnthropicBackend {
+    fn kind(&self) -> TranslationBackendKind {
+        TranslationBackendKind::Anthropic
+    }
+
+    fn supports(&self, from: Option<&str>, to: &str) -> bool {
+        llm_supports(from, to)
+    }
+
+    async fn translate(&self, text: &str, from: Option<&str>, to: &str) -> Result<String> {
+        require_api_key(&self.api_key)?;
+        debug!("Using normalized API URL: {}", self.api_url);
+
+        let body = json!({
+            "model": self.model,
+            "system": system_prompt(from, to),
+            "messages": [{ "role": "user", "content": text }],
+            "temperature": 0.1,
+            "max_tokens": MAX_OUTPUT_TOKENS
+        });
+        let request = self
+            .client
+            .post(&self.api_url)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", "2023-06-01");
+        let response = post_json(request, &body).await?;
+
+        let translated: String = response
+            .get("content")
+            .and_then(|content| content.as_array())
+            .ok_or_else(|| anyhow::anyhow!("Invalid API response format"))?
+            .iter()
+            .filter(|block| block.get("type").and_then(|t| t.as_str()) == Some("text"))
+            .filter_map(|block| block.get("text").and_then(|t| t.as_str()))
+            .collect();
+        Ok(translated.trim().to_string())
+    }
+}
+
+// ============================================================================
+// DeepL-style REST API
+// ============================================================================
+
+struct DeepLBackend {
+    client: Client,
+    api_url: String,
+    api_key: String,
+}
+
+/// Accepts `https://api-free.deepl.com`, `.../v2` or the full `.../v2/translate`
+fn normalize_deepl_url(base_url: &str) -> String {
+    let url = base_url.trim().trim_end_matches('/');
+    if url.ends_with("/translate") {
+        url.to_string()
+    } else if url.ends_with("/v2") {
+        format!("{}/translate", url)
+    } else {
+        format!("{}/v2/translate", url)
+    }
+}
+
+/// Consecutive texts that fit in one DeepL request
+fn deepl_chunks(texts: &[String]) -> Vec<&[String]> {
+    let mut chunks = Vec::new();
+    let (mut start, mut size) = (0, DEEPL_REQUEST_OVERHEAD);
+    for (index, text) in texts.iter().enumerate() {
+        // Quoted, comma separated and escaped once more inside the JSON body
+        let len = json!(text).to_string().len() + 1;
+        if index > start
+            && (index - start == DEEPL_MAX_TEXTS || size + len > DEEPL_MAX_REQUEST_BYTES)
+        {
+            chunks.push(&texts[start..index]);
+            (start, size) = (index, DEEPL_REQUEST_OVERHEAD);
+        }
+        size += len;
+    }
+    if start < texts.len() {
+        chunks.push(&texts[start..]);
+    }
+    chunks
+}
+
+static MARKER: Lazy<Regex> = Lazy::new(|| Regex::new(r"⟦(#?\d+)⟧").unwrap());
+static MARKER_TAG: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"<x id="(#?\d+)"\s*(?:/>|></x>)"#).unwrap());
+
+/// Escapes text for DeepL's XML tag handling and turns `⟦0⟧` markers into
+/// `<x id="0"/>` tags, which DeepL is told to leave alone
+fn to_deepl_xml(text: &str) -> String {
+    let escaped = text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    MARKER.replace_all(&escaped, r#"<x id="$1"/>"#).into_owned()
+}
+
+/// Reverses [`to_deepl_xml`] on a translation
+fn from_deepl_xml(text: &str) -> String {
+    MARKER_TAG
+        .replace_all(text, "⟦$1⟧")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[async_trait]
+impl TranslationBackend for DeepLBackend {
+    fn kind(&self) -> TranslationBackendKind {
+        TranslationBackendKind::DeepL
+    }
+
+    fn supports(&self, from: Option<&str>, to: &str) -> bool {
+        let source_ok = from
+            .is_none_or(|code| find_language(code).is_some_and(|lang| lang.deepl_source.is_some()));
+        source_ok && find_language(to).is_some_and(|lang| lang.deepl_target.is_some())
+    }
+
+    async fn translate(&self, text: &str, from: Option<&str>, to: &str) -> Result<String> {
+        self.translate_batch(&[text.to_string()], from, to)
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("Invalid API response format"))
+    }
+
+    /// DeepL takes a list of texts natively; larger batches are split to
+    /// its per-request limits
+    async fn translate_batch(
+        &self,
+        texts: &[String],
+        from: Option<&str>,
+        to: &str,
+    ) -> Result<Vec<String>> {
+        require_api_key(&self.api_key)?;
+
+        let target = find_language(to)
+            .and_then(|lang| lang.deepl_target)
+            .ok_or_else(|| anyhow::anyhow!("DeepL does not support target language {}", to))?;
+        // Without a source language DeepL detects it
+        let source = from
+            .and_then(find_language)
+            .and_then(|lang| lang.deepl_source);
+
+        let mut results = Vec::with_capacity(texts.len());
+        for chunk in deepl_chunks(texts) {
+            let text: Vec<String> = chunk.iter().map(|text| to_deepl_xml(text)).collect();
+            let mut body = json!({
+                "text": text,
+                "target_lang": target,
+                "tag_handling": "xml",
+                "ignore_tags": ["x"],
+            });
+            if let Some(source) = source {
+                body["source_lang"] = json!(source);
+            }
+
+            let request = self
+                .client
+                .post(&self.api_url)
+                .header("Authorization", format!("DeepL-Auth-Key {}", self.api_key));
+            let response = post_json(request, &body).await?;
+
+            let translations: Vec<String> = response
+                .get("translations")
+                .and_then(|translations| translations.as_array())
+                .ok_or_else(|| anyhow::anyhow!("Invalid API response format"))?
+                .iter()
+                .filter_map(|translation| translation.get("text").and_then(|text| text.as_str()))
+                .map(from_deepl_xml)
+                .collect();
+            if translations.len() != chunk.len() {
+                return Err(anyhow::anyhow!("Invalid API response format"));
+            }
+            results.extend(translations);
+        }
+        Ok(results)
+    }
+}
+
+// ============================================================================
+// Local command
+// ============================================================================
+
+/// Runs a shell command per translation. The command gets the system prompt
+/// and the text on stdin, and `TRANSLATE_FROM` / `TRANSLATE_TO` language codes
+/// in its environment; its stdout is the translation.
+struct LocalBackend {
+    command: String,
+    timeout: Duration,
+}
+
+#[async_trait]
+impl TranslationBackend for LocalBackend {
+    fn kind(&self) -> TranslationBackendKind {
+        TranslationBackendKind::Local
+    }
+
+    fn supports(&self, from: Option<&str>, to: &str) -> bool {
+        llm_supports(from, to)
+    }
+
+    async fn translate(&self, text: &str, from: Option<&str>, to: &str) -> Result<String> {
+        if self.command.trim().is_empty() {
+            return Err(anyhow::anyhow!("本地翻译命令未配置，请在设置中填写"));
+        }
+
+        #[cfg(target_os = "windows")]
+        let mut cmd = {
+            let mut cmd = Command::new("cmd");
+            cmd.args(["/C", &self.command]);
+            cmd
+        };
+        #[cfg(not(target_os = "windows"))]
+        let mut cmd = {
+            let mut cmd = Command::new("sh");
+            cmd.args(["-c", &self.command]);
+            cmd
+        };
+        cmd.env("TRANSLATE_FROM", from.unwrap_or("auto"))
+            .env("TRANSLATE_TO", to)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        apply_no_window_async(&mut cmd);
+
+        let mut child = cmd
+            .spawn()
+            .context("Failed to start local translation command")?;
+        let input = format!("{}\n\n{}", system_prompt(from, to), text);
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(input.as_bytes())
+                .await
+                .context("Failed to write to local translation command")?;
+        }
+
+        let output = tokio::time::timeout(self.timeout, child.wait_with_output())
+            .await
+            .map_err(|_| anyhow::anyhow!("Local translation command timed out"))?
+            .context("Failed to run local translation command")?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "Local translation command failed ({}): {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        let translated = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if translated.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Local translation command returned nothing"
+            ));
+        }
+        Ok(translated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompts_and_language_support_follow_the_backend() {
+        assert!(system_prompt(Some("zh"), "en").starts_with(
+            "You are a professional Chinese to English translator. Translate the following Chinese text to natural, fluent English"
+        ));
+        assert!(system_prompt(None, "ja").contains("fluent Japanese"));
+
+        let config = TranslationConfig {
+            backend: TranslationBackendKind::DeepL,
+            ..TranslationConfig::default()
+        };
+        let deepl = backend_for(&config);
+        assert!(deepl.supports(Some("en"), "zh-TW"));
+        assert!(!deepl.supports(Some("en"), "vi"));
+        assert!(backend_for(&TranslationConfig::default()).supports(None, "vi"));
+
+        assert_eq!(
+            normalize_deepl_url("https://api-free.deepl.com/"),
+            "https://api-free.deepl.com/v2/translate"
+        );
+    }
+
+    #[test]
+    fn deepl_requests_stay_within_limits_and_keep_markers() {
+        let texts: Vec<String> = (0..120).map(|i| format!("text {}", i)).collect();
+        let chunks = deepl_chunks(&texts);
+        assert_eq!(
+            chunks.iter().map(|chunk| chunk.len()).collect::<Vec<_>>(),
+            vec![50, 50, 20]
+        );
+        assert_eq!(chunks.concat(), texts);
+
+        let large = vec!["x".repeat(50 * 1024); 5];
+        let chunks = deepl_chunks(&large);
+        assert_eq!(
+            chunks.iter().map(|chunk| chunk.len()).collect::<Vec<_>>(),
+            vec![2, 2, 1]
+        );
+        assert!(chunks
+            .iter()
+            .all(|chunk| json!({ "text": chunk }).to_string().len() < DEEPL_MAX_REQUEST_BYTES));
+
+        let text = "Run ⟦0⟧ if a < b && ⟦#1⟧ > c";
+        let xml = to_deepl_xml(text);
+        assert_eq!(
+            xml,
+            r##"Run <x id="0"/> if a &lt; b &amp;&amp; <x id="#1"/> &gt; c"##
+        );
+        assert_eq!(from_deepl_xml(&xml), text);
+        assert_eq!(from_deepl_xml(r#"运行 <x id="0"></x>"#), "运行 ⟦0⟧");
+    }
+
+    /// Appends the target language, optionally dropping batch separators
+    struct TaggingBackend {
+        keep_separators: bool,
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait]
+    impl TranslationBackend for TaggingBackend {
+        fn kind(&self) -> TranslationBackendKind {
+            TranslationBackendKind::Local
+        }
+
+        fn supports(&self, _from: Option<&str>, _to: &str) -> bool {
+            true
+        }
+
+        async fn translate(&self, text: &str, _from: Option<&str>, to: &str) -> Result<String> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let text = if self.keep_separators {
+                text.to_string()
+            } else {
+                text.replace("⟦#", "⟦")
+            };
+            Ok(text
+                .split("\n\n")
+                .map(|part| format!("{} [{}]", part, to))
+                .collect::<Vec<_>>()
+                .join("\n\n"))
+        }
+    }
+
+    #[tokio::test]
+    async fn batches_are_split_back_or_retried_one_by_one() {
+        let texts = vec![
+            "one ⟦0⟧".to_string(),
+            "two".to_string(),
+            "three".to_string(),
+        ];
+        let joined = join_batch(&texts);
+        assert_eq!(joined, "one ⟦0⟧\n\n⟦#1⟧\n\ntwo\n\n⟦#2⟧\n\nthree");
+        assert_eq!(split_batch(&joined, 3).unwrap(), texts);
+        assert!(split_batch(&joined, 2).is_none());
+        assert!(split_batch("one\n⟦#1⟧\n", 2).is_none());
+
+        let backend = TaggingBackend {
+            keep_separators: true,
+            calls: Default::default(),
+        };
+        let translated = backend.translate_batch(&texts, None, "fr").await.unwrap();
+        assert_eq!(translated[0], "one ⟦0⟧ [fr]");
+        assert_eq!(translated.len(), 3);
+        assert_eq!(backend.calls.into_inner(), 1);
+
+        let lossy = TaggingBackend {
+            keep_separators: false,
+            calls: Default::default(),
+        };
+        let translated = lossy.translate_batch(&texts, None, "fr").await.unwrap();
+        assert_eq!(translated, vec!["one ⟦0⟧ [fr]", "two [fr]", "three [fr]"]);
+        assert_eq!(lossy.calls.into_inner(), 4);
+
+        let long = vec![
+            "x".repeat(MAX_BATCH_CHARS),
+            "y".to_string(),
+            "z".to_string(),
+        ];
+        let chunks = batch_chunks(&long);
+        assert_eq!(
+            chunks.iter().map(|c| c.len()).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn local_backend_pipes_the_prompt_through_the_command() {
+        let config = TranslationConfig {
+            backend: TranslationBackendKind::Local,
+            local_command: r#"printf '%s:' "$TRANSLATE_TO"; tail -n 1"#.to_string(),
+            ..TranslationConfig::default()
+        };
+        let translated = backend_for(&config)
+            .translate("hello", Some("en"), "fr")
+            .await
+            .unwrap();
+        assert_eq!(translated, "fr:hello");
+
+        let failing = TranslationConfig {
+            local_command: "exit 3".to_string(),
+            ..config
+        };
+        assert!(backend_for(&failing)
+            .translate("hello", Some("en"), "fr")
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,368 @@
+//! Translation Segmenter
+//!
+//! Splits assistant replies and prompts into the prose that should be
+//! translated and the technical content that must come back byte for byte:
+//! fenced code blocks and stack traces are kept as verbatim blocks, while
+//! inline code, URLs, file paths and identifiers inside prose are replaced
+//! by numbered placeholders (`⟦0⟧`). After translation the placeholders are
+//! put back; a prose run whose translation lost or duplicated a placeholder
+//! falls back to its original text, so reassembly never loses content.
+
+use log::warn;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Source file extensions recognised on bare file names (`main.rs`)
+const FILE_EXTENSIONS: &str = "rs|ts|tsx|js|jsx|mjs|cjs|json|py|go|java|kt|c|h|cc|cpp|hpp|cs|rb|php|swift|toml|yaml|yml|md|sh|ps1|sql|css|scss|html|vue|lock|txt|log";
+
+/// URLs, file paths and code identifiers in prose, in priority order
+static TECHNICAL_TOKEN: Lazy<Regex> = Lazy::new(|| {
+    let patterns = [
+        // URLs
+        r"[A-Za-z][A-Za-z0-9+.-]*://[^\s<>()\[\]{}`]+".to_string(),
+        // Windows paths
+        r"[A-Za-z]:\\(?:[[:word:].@-]+\\?)+".to_string(),
+        // Absolute, home and ./ ../ relative paths
+        r"(?:~|\.{1,2})?/[[:word:].@+-]+(?:/[[:word:].@+-]+)*/?(?::\d+){0,2}".to_string(),
+        // Relative paths ending in a file name
+        r"[[:word:].@-]+(?:/[[:word:].@-]+)*/[[:word:]@-]+\.[A-Za-z0-9]+(?::\d+){0,2}".to_string(),
+        // Bare file names
+        format!(
+            r"[[:word:]-]+\.(?:{})(?::\d+){{0,2}}(?-u:\b)",
+            FILE_EXTENSIONS
+        ),
+        // Calls, `a::b` paths, snake_case, camelCase and SCREAMING_CASE
+        r"[A-Za-z_][A-Za-z0-9_]*(?:(?:::|->|\.)[A-Za-z_][A-Za-z0-9_]*)*\(\)".to_string(),
+        r"[A-Za-z_][A-Za-z0-9_]*(?:::[A-Za-z_][A-Za-z0-9_]*)+".to_string(),
+        r"[a-z][a-z0-9]*(?:_[a-z0-9]+)+(?-u:\b)".to_string(),
+        r"[a-z]+[A-Z][A-Za-z0-9]*(?-u:\b)".to_string(),
+        r"[A-Z][A-Z0-9]*(?:_[A-Z0-9]+)+(?-u:\b)".to_string(),
+    ];
+    Regex::new(&patterns.join("|")).unwrap()
+});
+
+/// Stack trace lines of JavaScript/Java, Python and Rust
+static STACK_TRACE_LINE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"^(?:\s+at\s+\S|\s*File ".+", line \d+|Traceback \(most recent call last\)|\s*\d+:\s+[\w<][\w:<>$]*::|thread '.*' panicked at)"#,
+    )
+    .unwrap()
+});
+
+static PLACEHOLDER: Lazy<Regex> = Lazy::new(|| Regex::new(r"⟦(\d+)⟧").unwrap());
+
+/// Prose between verbatim blocks, with technical tokens masked
+#[derive(Debug)]
+struct ProseRun {
+    original: String,
+    masked: String,
+    tokens: Vec<String>,
+}
+
+#[derive(Debug)]
+enum Part {
+    Verbatim(String),
+    Prose(usize),
+}
+
+/// A text split into verbatim parts and translatable prose
+#[derive(Debug)]
+pub struct SegmentedText {
+    parts: Vec<Part>,
+    prose: Vec<ProseRun>,
+}
+
+impl SegmentedText {
+    pub fn parse(text: &str) -> Self {
+        let mut segmented = Self {
+            parts: Vec::new(),
+            prose: Vec::new(),
+        };
+        let mut prose = String::new();
+        // Opening fence (character and length) while inside a code block
+        let mut fence: Option<(char, usize)> = None;
+        let mut after_python_frame = false;
+
+        for line in text.split_inclusive('\n') {
+            if let Some((fence_char, fence_len)) = fence {
+                if closes_fence(line, fence_char, fence_len) {
+                    fence = None;
+                }
+                segmented.push_verbatim(line);
+                continue;
+            }
+
+            let python_frame_source = after_python_frame && line.starts_with([' ', '\t']);
+            after_python_frame = line.trim_start().starts_with("File \"");
+
+            if let Some(opening) = opens_fence(line) {
+                fence = Some(opening);
+            } else if !python_frame_source && !STACK_TRACE_LINE.is_match(line) {
+                prose.push_str(line);
+                continue;
+            }
+            segmented.flush_prose(&mut prose);
+            segmented.push_verbatim(line);
+        }
+        segmented.flush_prose(&mut prose);
+
+        segmented
+    }
+
+    /// Masked prose to translate, in order
+    pub fn prose(&self) -> Vec<String> {
+        self.prose.iter().map(|run| run.masked.clone()).collect()
+    }
+
+    /// Puts the translated prose (same order as [`Self::prose`]) back
+    /// between the verbatim parts
+    pub fn reassemble(&self, translated: &[String]) -> String {
+        let mut output = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Verbatim(text) => output.push_str(text),
+                Part::Prose(index) => {
+                    let run = &self.prose[*index];
+                    let restored = translated
+                        .get(*index)
+                        .and_then(|text| restore_tokens(text, &run.tokens));
+                    if restored.is_none() {
+                        warn!(
+                            "[Translation] Placeholders lost in prose run {}, keeping the original text",
+                            index
+                        );
+                    }
+                    output.push_str(restored.as_deref().unwrap_or(&run.original));
+                }
+            }
+        }
+        output
+    }
+
+    fn push_verbatim(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        match self.parts.last_mut() {
+            Some(Part::Verbatim(last)) => last.push_str(text),
+            _ => self.parts.push(Part::Verbatim(text.to_string())),
+        }
+    }
+
+    /// Adds buffered prose; surrounding whitespace stays verbatim since
+    /// backends trim their output
+    fn flush_prose(&mut self, prose: &mut String) {
+        let text = std::mem::take(prose);
+        let core = text.trim();
+        if core.is_empty() {
+            self.push_verbatim(&text);
+            return;
+        }
+        let start = text.find(core).unwrap_or(0);
+        let (leading, rest) = text.split_at(start);
+        let (core, trailing) = rest.split_at(core.len());
+
+        self.push_verbatim(leading);
+        let (masked, tokens) = mask_tokens(core);
+        let translatable = PLACEHOLDER
+            .replace_all(&masked, "")
+            .chars()
+            .any(char::is_alphabetic);
+        if translatable {
+            self.parts.push(Part::Prose(self.prose.len()));
+            self.prose.push(ProseRun {
+                original: core.to_string(),
+                masked,
+                tokens,
+            });
+        } else {
+            self.push_verbatim(core);
+        }
+        self.push_verbatim(trailing);
+    }
+}
+
+/// ``` or ~~~ (three or more, indented at most three spaces) opens a code block
+fn opens_fence(line: &str) -> Option<(char, usize)> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+    let rest = &line[indent..];
+    let fence_char = rest.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = rest.chars().take_while(|c| *c == fence_char).count();
+    // A backtick fence's info string cannot contain backticks
+    let info_ok = fence_char == '~' || !rest[len..].contains('`');
+    (len >= 3 && info_ok).then_some((fence_char, len))
+}
+
+fn closes_fence(line: &str, fence_char: char, fence_len: usize) -> bool {
+    let trimmed = line.trim();
+    trimmed.len() >= fence_len && trimmed.chars().all(|c| c == fence_char)
+}
+
+/// Replaces inline code spans and technical tokens with placeholders
+fn mask_tokens(text: &str) -> (String, Vec<String>) {
+    let mut masked = String::new();
+    let mut tokens = Vec::new();
+    // Text that already contains placeholder brackets is sent as it is
+    if text.contains('⟦') {
+        return (text.to_string(), tokens);
+    }
+
+    let mut rest = text;
+    while let Some((start, end)) = find_code_span(rest) {
+        mask_technical_tokens(&rest[..start], &mut masked, &mut tokens);
+        push_placeholder(&rest[start..end], &mut masked, &mut tokens);
+        rest = &rest[end..];
+    }
+    mask_technical_tokens(rest, &mut masked, &mut tokens);
+
+    (masked, tokens)
+}
+
+/// Next `code` span: a backtick run closed by a run of the same length
+fn find_code_span(text: &str) -> Option<(usize, usize)> {
+    let bytes = text.as_bytes();
+    let mut pos = 0;
+    while let Some(offset) = text[pos..].find('`') {
+        let start = pos + offset;
+        let run = bytes[start..].iter().take_while(|b| **b == b'`').count();
+        let mut search = start + run;
+        while let Some(offset) = text[search..].find('`') {
+            let close = search + offset;
+            let close_run = bytes[close..].iter().take_while(|b| **b == b'`').count();
+            if close_run == run {
+                return Some((start, close + run));
+            }
+            search = close + close_run;
+        }
+        pos = start + run;
+    }
+    None
+}
+
+fn mask_technical_tokens(text: &str, masked: &mut String, tokens: &mut Vec<String>) {
+    let mut pos = 0;
+    let mut copied = 0;
+    while let Some(m) = TECHNICAL_TOKEN.find_at(text, pos) {
+        // Tokens must start at a word boundary (`and/or` is not a path)
+        let starts_mid_word = text[..m.start()]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '/');
+        if starts_mid_word {
+            pos = m.start() + text[m.start()..].chars().next().map_or(1, char::len_utf8);
+            continue;
+        }
+        // Sentence punctuation after a URL or path is not part of it
+        let token = m
+            .as_str()
+            .trim_end_matches(['.', ',', ';', ':', '!', '?', '\'', '"']);
+        if token.is_empty() {
+            pos = m.end();
+            continue;
+        }
+        masked.push_str(&text[copied..m.start()]);
+        push_placeholder(token, masked, tokens);
+        copied = m.start() + token.len();
+        pos = copied;
+    }
+    masked.push_str(&text[copied..]);
+}
+
+fn push_placeholder(token: &str, masked: &mut String, tokens: &mut Vec<String>) {
+    masked.push_str(&format!("⟦{}⟧", tokens.len()));
+    tokens.push(token.to_string());
+}
+
+/// Puts tokens back into a translation, or `None` unless every placeholder
+/// appears exactly once
+fn restore_tokens(translated: &str, tokens: &[String]) -> Option<String> {
+    let mut seen = vec![false; tokens.len()];
+    for caps in PLACEHOLDER.captures_iter(translated) {
+        let index: usize = caps[1].parse().ok()?;
+        if index >= tokens.len() || std::mem::replace(&mut seen[index], true) {
+            return None;
+        }
+    }
+    if seen.contains(&false) {
+        return None;
+    }
+    Some(
+        PLACEHOLDER
+            .replace_all(translated, |caps: &regex::Captures| {
+                tokens[caps[1].parse::<usize>().unwrap()].clone()
+            })
+            .into_owned(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPLY: &str = "Run `cargo test` and open src/main.rs:12 or https://example.com/docs.\n\n\
+        ```rust\nfn main() {\n    println!(\"hi\");\n}\n```\n\
+        The call to get_user_name() fails:\n\
+        Traceback (most recent call last):\n  File \"app.py\", line 3, in <module>\n    main()\n\
+        \nCheck ~/.config/app.toml and try again.\n";
+
+    #[test]
+    fn code_paths_and_traces_are_kept_out_of_prose() {
+        let segmented = SegmentedText::parse(REPLY);
+        assert_eq!(
+            segmented.prose(),
+            vec![
+                "Run ⟦0⟧ and open ⟦1⟧ or ⟦2⟧.",
+                "The call to ⟦0⟧ fails:",
+                "Check ⟦0⟧ and try again.",
+            ]
+        );
+        assert_eq!(
+            SegmentedText::parse("plain prose and/or text\n").prose(),
+            vec!["plain prose and/or text"]
+        );
+        assert!(SegmentedText::parse("```\nunclosed code\n")
+            .prose()
+            .is_empty());
+    }
+
+    #[test]
+    fn reassembly_is_lossless_and_falls_back_on_lost_placeholders() {
+        let segmented = SegmentedText::parse(REPLY);
+
+        // Untranslated prose reproduces the input exactly
+        assert_eq!(segmented.reassemble(&segmented.prose()), REPLY);
+
+        let translated = vec![
+            "运行 ⟦0⟧ 并打开 ⟦1⟧ 或 ⟦2⟧。".to_string(),
+            "调用 ⟦0⟧ 失败：".to_string(),
+            "检查配置后重试。".to_string(), // placeholder dropped
+        ];
+        let output = segmented.reassemble(&translated);
+        assert!(output.starts_with(
+            "运行 `cargo test` 并打开 src/main.rs:12 或 https://example.com/docs。\n\n```rust\n"
+        ));
+        assert!(output.contains("调用 get_user_name() 失败：\nTraceback"));
+        assert!(output.contains("    main()\n"));
+        assert!(output.ends_with("\nCheck ~/.config/app.toml and try again.\n"));
+    }
+
+    #[test]
+    fn lost_duplicated_or_unknown_placeholders_are_not_restored() {
+        let tokens = vec!["`a`".to_string(), "src/b.rs".to_string()];
+        assert_eq!(
+            restore_tokens("⟦1⟧ 和 ⟦0⟧", &tokens).as_deref(),
+            Some("src/b.rs 和 `a`")
+        );
+        assert_eq!(restore_tokens("只有 ⟦0⟧", &tokens), None);
+        assert_eq!(restore_tokens("⟦0⟧ ⟦0⟧ ⟦1⟧", &tokens), None);
+        assert_eq!(restore_tokens("⟦0⟧ ⟦1⟧ ⟦2⟧", &tokens), None);
+
+        // The run falls back to its original text, the others are translated
+        let segmented = SegmentedText::parse("Open src/b.rs now.\n```\nls\n```\nThen retry.\n");
+        let output = segmented.reassemble(&["打开文件。".to_string(), "然后重试。".to_string()]);
+        assert_eq!(output, "Open src/b.rs now.\n```\nls\n```\n然后重试。\n");
+    }
+}
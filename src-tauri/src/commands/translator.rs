@@ -1,6 +1,5 @@
-use anyhow::{Context, Result};
+use anyhow::Result;
 use log::{debug, error, info, warn};
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
@@ -9,7 +8,10 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 
-use super::url_utils::{normalize_api_url, ApiEndpointType};
+use super::translation_backend::{
+    backend_for, find_language, TranslationBackend, TranslationBackendKind,
+};
+use super::translation_segmenter::SegmentedText;
 
 /// 翻译配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +28,26 @@ pub struct TranslationConfig {
     pub timeout_seconds: u64,
     /// 缓存有效期（秒）
     pub cache_ttl_seconds: u64,
+    /// 翻译后端（OpenAI 兼容 / Anthropic / DeepL / 本地命令）
+    #[serde(default)]
+    pub backend: TranslationBackendKind,
+    /// 本地翻译命令（仅 local 后端使用），从 stdin 读取提示词，输出译文
+    #[serde(default)]
+    pub local_command: String,
+    /// 用户使用的语言（未指定目标语言时，其他语言的文本翻译为该语言）
+    #[serde(default = "default_user_language")]
+    pub user_language: String,
+    /// 发送给 AI 的语言（用户语言的文本翻译为该语言）
+    #[serde(default = "default_agent_language")]
+    pub agent_language: String,
+}
+
+fn default_user_language() -> String {
+    "zh".to_string()
+}
+
+fn default_agent_language() -> String {
+    "en".to_string()
 }
 
 impl Default for TranslationConfig {
@@ -37,6 +59,10 @@ impl Default for TranslationConfig {
             model: "tencent/Hunyuan-MT-7B".to_string(),
             timeout_seconds: 30,
             cache_ttl_seconds: 3600, // 1小时
+            backend: TranslationBackendKind::default(),
+            local_command: String::new(),
+            user_language: default_user_language(),
+            agent_language: default_agent_language(),
         }
     }
 }
@@ -63,34 +89,164 @@ impl CacheEntry {
     }
 }
 
+impl TranslationConfig {
+    /// 检查用户语言和 AI 语言：必须是支持的语言，且不能是同一种语言
+    pub fn validate_languages(&self) -> Result<(), String> {
+        for code in [&self.user_language, &self.agent_language] {
+            if find_language(code).is_none() {
+                return Err(format!("Unsupported translation language: {}", code));
+            }
+        }
+        if same_language(&self.user_language, &self.agent_language) {
+            return Err(format!(
+                "User and agent languages must differ: {} / {}",
+                self.user_language, self.agent_language
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 比较语言代码的主语言部分（`zh-TW` 与 `zh` 视为同一种语言）
+fn same_language(a: &str, b: &str) -> bool {
+    let primary = |code: &str| code.split('-').next().unwrap_or(code).to_ascii_lowercase();
+    primary(a) == primary(b)
+}
+
+/// 根据文字系统判断语言：假名为日文，谚文为韩文，西里尔字母为俄文
+fn detect_language_by_script(text: &str) -> Option<&'static str> {
+    let (mut letters, mut han, mut kana, mut hangul, mut cyrillic) = (0usize, 0, 0, 0, 0);
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        letters += 1;
+        match c as u32 {
+            0x3040..=0x30FF | 0x31F0..=0x31FF | 0xFF66..=0xFF9F => kana += 1,
+            0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => hangul += 1,
+            0x0400..=0x04FF => cyrillic += 1,
+            0x4E00..=0x9FFF | 0x3400..=0x4DBF | 0xF900..=0xFAFF => han += 1,
+            _ => {}
+        }
+    }
+
+    // 占字母总数的 30% 以上才认为是该语言，避免英文中夹杂的个别词语误判
+    let dominant = |count: usize| count > 0 && count * 10 >= letters * 3;
+    if kana > 0 && dominant(kana + han) {
+        Some("ja")
+    } else if dominant(hangul) {
+        Some("ko")
+    } else if dominant(cyrillic) {
+        Some("ru")
+    } else {
+        None
+    }
+}
+
+/// 拉丁字母语言的常用词
+const LATIN_STOPWORDS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "the", "and", "is", "are", "of", "to", "in", "that", "this", "with", "for", "it",
+            "you", "be", "not", "have", "what", "how", "can", "please", "why", "does",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "le", "la", "les", "des", "est", "et", "une", "un", "du", "qui", "pour", "dans", "pas",
+            "avec", "sur", "je", "vous", "ce", "cette", "mais", "ne", "au", "pourquoi",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "der", "die", "das", "und", "ist", "nicht", "ein", "eine", "ich", "mit", "zu", "den",
+            "dem", "auf", "für", "sie", "auch", "wie", "bitte", "warum", "wird",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "los", "las", "del", "y", "una", "por", "para", "con", "se", "como", "está",
+            "pero", "qué", "esta", "este", "hay", "son",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "il", "gli", "della", "di", "che", "è", "non", "per", "sono", "questo", "questa",
+            "anche", "nel", "perché", "come", "ho", "una",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            "os", "da", "do", "das", "dos", "não", "uma", "com", "em", "isso", "você", "mas",
+            "está", "seu", "sua", "também",
+        ],
+    ),
+    (
+        "vi",
+        &[
+            "và", "của", "là", "không", "có", "những", "một", "được", "cho", "với", "này", "tôi",
+            "bạn", "các", "trong", "tại", "sao",
+        ],
+    ),
+];
+
+/// 按常用词判断拉丁字母文本的语言，无法判断时视为英文
+fn detect_latin_language(text: &str) -> &'static str {
+    let lowercase = text.to_lowercase();
+    let words: Vec<&str> = lowercase
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    let mut best = ("en", 0);
+    for (lang, stopwords) in LATIN_STOPWORDS {
+        let score = words.iter().filter(|w| stopwords.contains(w)).count();
+        if score > best.1 {
+            best = (lang, score);
+        }
+    }
+    best.0
+}
+
 /// 翻译服务
+///
+/// 克隆开销很小：后端和缓存在副本之间共享
+#[derive(Clone)]
 pub struct TranslationService {
     config: TranslationConfig,
-    client: Client,
+    backend: Arc<dyn TranslationBackend>,
     cache: Arc<Mutex<HashMap<String, CacheEntry>>>,
 }
 
 impl TranslationService {
     /// 创建新的翻译服务实例
     pub fn new(config: TranslationConfig) -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(config.timeout_seconds))
-            .build()
-            .expect("Failed to create HTTP client");
+        let backend = Arc::from(backend_for(&config));
 
         Self {
             config,
-            client,
+            backend,
             cache: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
     /// 改进的文本语言检测，与前端保持一致
+    ///
+    /// 中文沿用原有的比例判断；日文、韩文、俄文按文字系统判断，
+    /// 其余拉丁字母文本按常用词判断（见 [`detect_latin_language`]）
     fn detect_language(&self, text: &str) -> String {
         if text.trim().is_empty() {
             return "en".to_string();
         }
 
+        // 日文也包含汉字，需要先于中文判断
+        if let Some(lang) = detect_language_by_script(text) {
+            return lang.to_string();
+        }
+
         // 扩展的中文字符检测范围
         let chinese_chars: Vec<char> = text
             .chars()
@@ -106,7 +262,7 @@ impl TranslationService {
             .collect();
 
         if chinese_chars.is_empty() {
-            return "en".to_string();
+            return detect_latin_language(text).to_string();
         }
 
         // 简化预处理，移除明显的非文本内容
@@ -168,7 +324,7 @@ impl TranslationService {
             }
         }
 
-        "en".to_string()
+        detect_latin_language(text).to_string()
     }
 
     /// 生成缓存键
@@ -208,105 +364,78 @@ impl TranslationService {
         debug!("Cleaned up expired cache entries");
     }
 
-    /// 翻译API请求
+    /// 翻译API请求（发送到配置的翻译后端），多段文字尽量合并为一次请求
     async fn call_translation_api(
         &self,
-        text: &str,
-        from_lang: &str,
+        texts: &[String],
+        from_lang: Option<&str>,
         to_lang: &str,
-    ) -> Result<String> {
-        // 检查API密钥是否已配置
-        if self.config.api_key.is_empty() {
+    ) -> Result<Vec<String>> {
+        if !self.backend.supports(from_lang, to_lang) {
             return Err(anyhow::anyhow!(
-                "API密钥未配置，请在设置中填写您的Silicon Flow API密钥"
+                "{:?} backend does not support translating {} to {}",
+                self.backend.kind(),
+                from_lang.unwrap_or("auto"),
+                to_lang
             ));
         }
-        let system_prompt = match (from_lang, to_lang) {
-            ("zh", "en") => "You are a professional Chinese to English translator. Translate the following Chinese text to natural, fluent English while preserving the original meaning and tone. Only return the translated text, nothing else.",
-            ("en", "zh") => "You are a professional English to Chinese translator. Translate the following English text to natural, fluent Chinese while preserving the original meaning and tone. Only return the translated text, nothing else.",
-            _ => "You are a professional translator. Translate the text to the target language while preserving the original meaning and tone. Only return the translated text, nothing else.",
-        };
-
-        let request_body = serde_json::json!({
-            "model": self.config.model,
-            "messages": [
-                {
-                    "role": "system",
-                    "content": system_prompt
-                },
-                {
-                    "role": "user",
-                    "content": text
-                }
-            ],
-            "temperature": 0.1,
-            "max_tokens": 4000,
-            "stream": false
-        });
 
         // Avoid logging potentially sensitive content (source code, secrets, etc.)
+        let input_len: usize = texts.iter().map(|text| text.chars().count()).sum();
         debug!(
-            "Sending translation request: from={} to={}, input_len={}",
-            from_lang,
+            "Sending translation request: backend={:?} from={} to={}, segments={}, input_len={}",
+            self.backend.kind(),
+            from_lang.unwrap_or("auto"),
             to_lang,
-            text.chars().count()
+            texts.len(),
+            input_len
         );
 
-        // 智能规范化 API URL（支持用户输入简化的基础 URL）
-        let api_url = normalize_api_url(&self.config.api_base_url, ApiEndpointType::OpenAI);
-        debug!("Using normalized API URL: {}", api_url);
-
-        let response = self
-            .client
-            .post(&api_url)
-            .header("Authorization", format!("Bearer {}", self.config.api_key))
-            .header("Content-Type", "application/json")
-            .json(&request_body)
-            .send()
-            .await
-            .context("Failed to send translation request")?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(anyhow::anyhow!(
-                "Translation API error: {} - {}",
-                status,
-                error_text
-            ));
-        }
-
-        let response_json: serde_json::Value = response
-            .json()
-            .await
-            .context("Failed to parse API response")?;
-
-        // 提取翻译结果
-        let translated_text = response_json
-            .get("choices")
-            .and_then(|choices| choices.get(0))
-            .and_then(|choice| choice.get("message"))
-            .and_then(|message| message.get("content"))
-            .and_then(|content| content.as_str())
-            .ok_or_else(|| anyhow::anyhow!("Invalid API response format"))?
-            .trim()
-            .to_string();
+        let translated = self
+            .backend
+            .translate_batch(texts, from_lang, to_lang)
+            .await?;
 
         debug!(
             "Translation successful: from={} to={}, input_len={}, output_len={}",
-            from_lang,
+            from_lang.unwrap_or("auto"),
             to_lang,
-            text.chars().count(),
-            translated_text.chars().count()
+            input_len,
+            translated
+                .iter()
+                .map(|text| text.chars().count())
+                .sum::<usize>()
         );
 
-        Ok(translated_text)
+        Ok(translated)
+    }
+
+    /// 确定翻译方向：用户语言的文本翻译为 AI 语言，其他文本翻译为用户语言
+    ///
+    /// 检测结果与配置的语言都不一致时，源语言交由后端自行判断（返回 None）
+    fn resolve_languages(
+        &self,
+        detected: &str,
+        target_lang: Option<&str>,
+    ) -> (Option<String>, String) {
+        let user = self.config.user_language.as_str();
+        let agent = self.config.agent_language.as_str();
+
+        let from_lang = if same_language(detected, user) {
+            Some(user)
+        } else if same_language(detected, agent) {
+            Some(agent)
+        } else {
+            None
+        };
+        let to_lang = target_lang.unwrap_or(if from_lang == Some(user) { agent } else { user });
+
+        (from_lang.map(str::to_string), to_lang.to_string())
     }
 
     /// 智能翻译文本
+    ///
+    /// 代码块、堆栈信息、行内代码、路径和 URL 原样保留，只有其余的文字发送给翻译后端
     pub async fn translate(&self, text: &str, target_lang: Option<&str>) -> Result<String> {
         if !self.config.enabled {
             debug!("Translation disabled, returning original text");
@@ -317,48 +446,87 @@ impl TranslationService {
             return Ok(text.to_string());
         }
 
-        // 检测源语言
-        let from_lang = self.detect_language(text);
+        let segmented = SegmentedText::parse(text);
+        let prose = segmented.prose();
+        if prose.is_empty() {
+            debug!("No prose to translate, returning original text");
+            return Ok(text.to_string());
+        }
 
-        // 确定目标语言
-        let to_lang = target_lang.unwrap_or_else(|| {
-            match from_lang.as_str() {
-                "zh" => "en", // 中文翻译为英文
-                _ => "zh",    // 其他语言翻译为中文
-            }
-        });
+        // 根据文字部分检测源语言，确定目标语言
+        let detected = self.detect_language(&prose.join("\n"));
+        let (from_lang, to_lang) = self.resolve_languages(&detected, target_lang);
+        let to_lang = to_lang.as_str();
 
         // 如果源语言和目标语言相同，直接返回
-        if from_lang == to_lang {
+        if from_lang.as_deref() == Some(to_lang) {
             debug!("Source and target languages are the same, skipping translation");
             return Ok(text.to_string());
         }
 
-        // 生成缓存键
-        let cache_key = self.cache_key(text, &from_lang, to_lang);
+        let translated = self
+            .translate_segments(&prose, from_lang.as_deref(), to_lang)
+            .await;
+        info!(
+            "Translation completed: {} -> {} ({} segments)",
+            from_lang.as_deref().unwrap_or("auto"),
+            to_lang,
+            prose.len()
+        );
+        Ok(segmented.reassemble(&translated))
+    }
 
-        // 尝试从缓存获取
-        if let Some(cached_result) = self.get_cached_translation(&cache_key).await {
-            info!("Using cached translation");
-            return Ok(cached_result);
+    /// 翻译各段文字：未命中缓存的段落合并为一次批量请求，翻译失败时保留原文
+    async fn translate_segments(
+        &self,
+        segments: &[String],
+        from_lang: Option<&str>,
+        to_lang: &str,
+    ) -> Vec<String> {
+        let mut results = segments.to_vec();
+        let mut missing = Vec::new();
+
+        for (index, segment) in segments.iter().enumerate() {
+            // 生成缓存键，尝试从缓存获取
+            let cache_key = self.cache_key(segment, from_lang.unwrap_or("auto"), to_lang);
+            match self.get_cached_translation(&cache_key).await {
+                Some(cached_result) => results[index] = cached_result,
+                None => missing.push((index, cache_key)),
+            }
+        }
+        if missing.len() < segments.len() {
+            info!(
+                "Using cached translation for {} of {} segments",
+                segments.len() - missing.len(),
+                segments.len()
+            );
+        }
+        if missing.is_empty() {
+            return results;
         }
 
         // 调用翻译API
-        match self.call_translation_api(text, &from_lang, to_lang).await {
-            Ok(translated_text) => {
-                // 缓存结果
-                self.cache_translation(cache_key, translated_text.clone())
-                    .await;
-                info!("Translation completed: {} -> {}", from_lang, to_lang);
-                Ok(translated_text)
+        let texts: Vec<String> = missing
+            .iter()
+            .map(|(index, _)| segments[*index].clone())
+            .collect();
+        match self.call_translation_api(&texts, from_lang, to_lang).await {
+            Ok(translated) => {
+                for ((index, cache_key), translated_text) in missing.into_iter().zip(translated) {
+                    // 缓存结果
+                    self.cache_translation(cache_key, translated_text.clone())
+                        .await;
+                    results[index] = translated_text;
+                }
             }
             Err(e) => {
                 error!("Translation failed: {}", e);
                 // 降级策略：返回原文
                 warn!("Using fallback: returning original text due to translation failure");
-                Ok(text.to_string())
             }
         }
+
+        results
     }
 
     /// 批量翻译
@@ -385,6 +553,7 @@ impl TranslationService {
     /// 更新配置
     #[allow(dead_code)]
     pub fn update_config(&mut self, new_config: TranslationConfig) {
+        self.backend = Arc::from(backend_for(&new_config));
         self.config = new_config;
     }
 
@@ -454,10 +623,14 @@ fn get_translation_service() -> Arc<Mutex<TranslationService>> {
     TRANSLATION_SERVICE.clone()
 }
 
+/// 当前翻译服务的副本，翻译请求期间不占用全局锁
+async fn current_translation_service() -> TranslationService {
+    TRANSLATION_SERVICE.lock().await.clone()
+}
+
 /// 翻译文本（公共接口）
 pub async fn translate_text(text: &str, target_lang: Option<&str>) -> Result<String> {
-    let service_arc = get_translation_service();
-    let service = service_arc.lock().await;
+    let service = current_translation_service().await;
     service.translate(text, target_lang).await
 }
 
@@ -477,8 +650,7 @@ pub async fn translate_batch(
     texts: Vec<String>,
     target_lang: Option<String>,
 ) -> Result<Vec<String>, String> {
-    let service_arc = get_translation_service();
-    let service = service_arc.lock().await;
+    let service = current_translation_service().await;
     let target = target_lang.as_deref();
 
     service
@@ -510,6 +682,8 @@ pub async fn get_translation_config() -> Result<TranslationConfig, String> {
 /// Tauri命令：更新翻译配置
 #[tauri::command]
 pub async fn update_translation_config(config: TranslationConfig) -> Result<String, String> {
+    config.validate_languages()?;
+
     // 保存配置到文件
     save_translation_config_to_file(&config)
         .map_err(|e| format!("Failed to save translation config: {}", e))?;
@@ -597,3 +771,58 @@ pub async fn init_translation_service_command(
     init_translation_service(final_config).await;
     Ok("Translation service initialized successfully".to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configured_languages_are_detected_and_translated_the_right_way() {
+        let service = TranslationService::new(TranslationConfig {
+            user_language: "fr".to_string(),
+            agent_language: "en".to_string(),
+            ..TranslationConfig::default()
+        });
+
+        let french = "Pourquoi est-ce que le test échoue dans la fonction de connexion ?";
+        assert_eq!(service.detect_language(french), "fr");
+        assert_eq!(
+            service.resolve_languages(&service.detect_language(french), None),
+            (Some("fr".to_string()), "en".to_string())
+        );
+        let english = "The test fails because the login function is not awaited.";
+        assert_eq!(
+            service.resolve_languages(&service.detect_language(english), None),
+            (Some("en".to_string()), "fr".to_string())
+        );
+
+        assert_eq!(service.detect_language("请帮我修复这个测试"), "zh");
+        assert_eq!(service.detect_language("このテストを直してください"), "ja");
+        assert_eq!(service.detect_language("이 테스트를 고쳐 주세요"), "ko");
+        assert_eq!(
+            service.detect_language("Почему этот тест не проходит?"),
+            "ru"
+        );
+        assert_eq!(
+            service.detect_language("Warum schlägt der Test mit dieser Meldung fehl?"),
+            "de"
+        );
+        assert_eq!(
+            service.detect_language("Fix `parse_config` in src/main.rs"),
+            "en"
+        );
+
+        assert!(service.config.validate_languages().is_ok());
+        let unsupported = TranslationConfig {
+            user_language: "xx".to_string(),
+            ..TranslationConfig::default()
+        };
+        assert!(unsupported.validate_languages().is_err());
+        let same = TranslationConfig {
+            user_language: "zh-TW".to_string(),
+            agent_language: "zh".to_string(),
+            ..TranslationConfig::default()
+        };
+        assert!(same.validate_languages().is_err());
+    }
+}
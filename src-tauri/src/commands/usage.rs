@@ -2,25 +2,19 @@
 // Source: https://github.com/meistrari/opcode
 
 use chrono::{DateTime, Local, NaiveDate};
+use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
-use serde_json;
-use std::collections::{HashMap, HashSet};
-use std::fs;
-use std::path::PathBuf;
-use tauri::command;
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct UsageEntry {
-    timestamp: String,
-    model: String,
-    input_tokens: u64,
-    output_tokens: u64,
-    cache_creation_tokens: u64,
-    cache_read_tokens: u64,
-    cost: f64,
-    session_id: String,
-    project_path: String,
-}
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tauri::{command, AppHandle};
+
+use super::pricing::price_book;
+use super::session_timeline::SessionEngine;
+use super::usage_ingest::{
+    normalize_timestamp, project_name, query_usage, query_usage_total, read_appended_lines,
+    sync_usage, with_usage_db, IngestedFile, OnConflict, UsageAggregate, UsageGroup, UsageRange,
+    UsageRow,
+};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UsageStats {
@@ -66,146 +60,6 @@ pub struct ProjectUsage {
     last_used: String,
 }
 
-// ============================================================================
-// Claude Model Pricing - Single Source of Truth
-// Source: https://platform.claude.com/docs/en/about-claude/pricing
-// Last Updated: February 2026
-// ============================================================================
-
-/// Model pricing structure (prices per million tokens)
-#[derive(Debug, Clone, Copy)]
-struct ModelPricing {
-    input: f64,
-    output: f64,
-    cache_write: f64,
-    cache_read: f64,
-}
-
-/// Model family enumeration for categorization
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum ModelFamily {
-    Opus46,   // Claude 4.6 Opus
-    Sonnet46, // Claude 4.6 Sonnet
-    Opus45,   // Claude 4.5 Opus
-    Opus41,   // Claude 4.1 Opus
-    Sonnet45, // Claude 4.5 Sonnet
-    Haiku45,  // Claude 4.5 Haiku
-    Unknown,  // Unknown model
-}
-
-impl ModelPricing {
-    /// Get pricing for a specific model family
-    const fn for_family(family: ModelFamily) -> Self {
-        match family {
-            // Claude 4.6 Series (Latest - February 2026)
-            ModelFamily::Opus46 => ModelPricing {
-                input: 5.0,
-                output: 25.0,
-                cache_write: 6.25,
-                cache_read: 0.50,
-            },
-            ModelFamily::Sonnet46 => ModelPricing {
-                input: 3.0,
-                output: 15.0,
-                cache_write: 3.75,
-                cache_read: 0.30,
-            },
-            // Claude 4.5 Series
-            ModelFamily::Opus45 => ModelPricing {
-                input: 5.0,
-                output: 25.0,
-                cache_write: 6.25,
-                cache_read: 0.50,
-            },
-            ModelFamily::Sonnet45 => ModelPricing {
-                input: 3.0,
-                output: 15.0,
-                cache_write: 3.75,
-                cache_read: 0.30,
-            },
-            ModelFamily::Haiku45 => ModelPricing {
-                input: 1.0,
-                output: 5.0,
-                cache_write: 1.25,
-                cache_read: 0.10,
-            },
-            // Claude 4.1 Series
-            ModelFamily::Opus41 => ModelPricing {
-                input: 15.0,
-                output: 75.0,
-                cache_write: 18.75,
-                cache_read: 1.50,
-            },
-            ModelFamily::Unknown => ModelPricing {
-                input: 0.0,
-                output: 0.0,
-                cache_write: 0.0,
-                cache_read: 0.0,
-            },
-        }
-    }
-}
-
-/// Parse model name and determine its family
-///
-/// This function handles various model name formats including:
-/// - Full names: claude-sonnet-4-5-20250929
-/// - Aliases: claude-sonnet-4-5
-/// - Short names: sonnet-4-5
-/// - Bedrock format: anthropic.claude-sonnet-4-5-20250929-v1:0
-/// - Vertex AI format: claude-sonnet-4-5@20250929
-fn parse_model_family(model: &str) -> ModelFamily {
-    // Normalize the model name (lowercase + remove common prefixes/suffixes)
-    let mut normalized = model.to_lowercase();
-    normalized = normalized.replace("anthropic.", "");
-    normalized = normalized.replace("-v1:0", "");
-
-    // Handle @ symbol for Vertex AI format
-    if let Some(pos) = normalized.find('@') {
-        normalized = normalized[..pos].to_string();
-    }
-
-    // Priority-based matching (order matters!)
-    // Check for specific model families in order from most to least specific
-
-    // Claude 4.6 Series (Latest)
-    if normalized.contains("opus") && (normalized.contains("4.6") || normalized.contains("4-6")) {
-        return ModelFamily::Opus46;
-    }
-    if normalized.contains("sonnet") && (normalized.contains("4.6") || normalized.contains("4-6")) {
-        return ModelFamily::Sonnet46;
-    }
-
-    // Claude 4.5 Series (Latest)
-    if normalized.contains("opus") && (normalized.contains("4.5") || normalized.contains("4-5")) {
-        return ModelFamily::Opus45;
-    }
-    if normalized.contains("haiku") && (normalized.contains("4.5") || normalized.contains("4-5")) {
-        return ModelFamily::Haiku45;
-    }
-    if normalized.contains("sonnet") && (normalized.contains("4.5") || normalized.contains("4-5")) {
-        return ModelFamily::Sonnet45;
-    }
-
-    // Claude 4.1 Series
-    if normalized.contains("opus") && (normalized.contains("4.1") || normalized.contains("4-1")) {
-        return ModelFamily::Opus41;
-    }
-
-    // Generic family detection (fallback)
-    if normalized.contains("haiku") {
-        return ModelFamily::Haiku45; // Default to latest Haiku
-    }
-    if normalized.contains("opus") {
-        return ModelFamily::Opus46; // Default to latest Opus
-    }
-    if normalized.contains("sonnet") {
-        return ModelFamily::Sonnet46; // Default to latest Sonnet
-    }
-
-    ModelFamily::Unknown
-}
-
 #[derive(Debug, Deserialize)]
 struct JsonlEntry {
     timestamp: String,
@@ -233,603 +87,303 @@ struct UsageData {
     cache_read_input_tokens: Option<u64>,
 }
 
-/// Calculate cost for a model usage
-///
-/// This is the single source of truth for cost calculations.
-/// All cost computations in the application should ultimately use this function.
-fn calculate_cost(model: &str, usage: &UsageData) -> f64 {
-    let input_tokens = usage.input_tokens.unwrap_or(0) as f64;
-    let output_tokens = usage.output_tokens.unwrap_or(0) as f64;
-    let cache_creation_tokens = usage.cache_creation_input_tokens.unwrap_or(0) as f64;
-    let cache_read_tokens = usage.cache_read_input_tokens.unwrap_or(0) as f64;
-
-    // Parse model and get pricing
-    let family = parse_model_family(model);
-    let pricing = ModelPricing::for_family(family);
-
-    // Log unrecognized models for debugging
-    if family == ModelFamily::Unknown {
-        log::warn!(
-            "Unknown model detected: '{}'. Cost calculation will return 0.",
-            model
-        );
-    }
+// ============================================================================
+// Incremental Ingestion
+// ============================================================================
 
-    // Calculate cost (prices are per million tokens)
-    let cost = (input_tokens * pricing.input / 1_000_000.0)
-        + (output_tokens * pricing.output / 1_000_000.0)
-        + (cache_creation_tokens * pricing.cache_write / 1_000_000.0)
-        + (cache_read_tokens * pricing.cache_read / 1_000_000.0);
+fn claude_projects_dir() -> Result<PathBuf, String> {
+    Ok(dirs::home_dir()
+        .ok_or("Failed to get home directory")?
+        .join(".claude")
+        .join("projects"))
+}
 
-    cost
+/// Encoded project directory (`projects/<name>/...`) a transcript belongs to
+fn encoded_project_name(path: &Path) -> String {
+    path.ancestors()
+        .find(|p| {
+            p.parent()
+                .and_then(|parent| parent.file_name())
+                .map(|name| name == "projects")
+                .unwrap_or(false)
+        })
+        .and_then(|p| p.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
 }
 
-fn parse_jsonl_file(
-    path: &PathBuf,
-    encoded_project_name: &str,
-    processed_hashes: &mut HashSet<String>,
-) -> Vec<UsageEntry> {
-    let mut entries = Vec::new();
-    let mut actual_project_path: Option<String> = None;
-
-    if let Ok(content) = fs::read_to_string(path) {
-        // Extract session ID from the file path
-        let session_id = path
-            .parent()
-            .and_then(|p| p.file_name())
-            .and_then(|n| n.to_str())
-            .unwrap_or("unknown")
-            .to_string();
-
-        for line in content.lines() {
-            if line.trim().is_empty() {
-                continue;
-            }
+/// Parses the lines appended to a Claude transcript since the last pass
+fn ingest_claude_file(path: &Path, file: &mut IngestedFile) -> Result<Vec<UsageRow>, String> {
+    let (lines, offset) = read_appended_lines(path, file.offset)?;
+    file.offset = offset;
+
+    let fallback_session_id = path
+        .file_stem()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+    let mut rows = Vec::new();
+
+    for line in lines {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Ok(json_value) = serde_json::from_str::<serde_json::Value>(&line) else {
+            continue;
+        };
 
-            if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(line) {
-                // Extract the actual project path from cwd if we haven't already
-                if actual_project_path.is_none() {
-                    if let Some(cwd) = json_value.get("cwd").and_then(|v| v.as_str()) {
-                        actual_project_path = Some(cwd.to_string());
-                    }
-                }
-
-                // Try to parse as JsonlEntry for usage data
-                if let Ok(entry) = serde_json::from_value::<JsonlEntry>(json_value) {
-                    if let Some(message) = &entry.message {
-                        // Deduplication based on message ID and request ID
-                        if let (Some(msg_id), Some(req_id)) = (&message.id, &entry.request_id) {
-                            let unique_hash = format!("{}:{}", msg_id, req_id);
-                            if processed_hashes.contains(&unique_hash) {
-                                continue; // Skip duplicate entry
-                            }
-                            processed_hashes.insert(unique_hash);
-                        }
-
-                        if let Some(usage) = &message.usage {
-                            // Skip entries without meaningful token usage
-                            if usage.input_tokens.unwrap_or(0) == 0
-                                && usage.output_tokens.unwrap_or(0) == 0
-                                && usage.cache_creation_input_tokens.unwrap_or(0) == 0
-                                && usage.cache_read_input_tokens.unwrap_or(0) == 0
-                            {
-                                continue;
-                            }
-
-                            let cost = entry.cost_usd.unwrap_or_else(|| {
-                                if let Some(model_str) = &message.model {
-                                    calculate_cost(model_str, usage)
-                                } else {
-                                    0.0
-                                }
-                            });
-
-                            // Use actual project path if found, otherwise use encoded name
-                            let project_path = actual_project_path
-                                .clone()
-                                .unwrap_or_else(|| encoded_project_name.to_string());
-
-                            entries.push(UsageEntry {
-                                timestamp: entry.timestamp,
-                                model: message
-                                    .model
-                                    .clone()
-                                    .unwrap_or_else(|| "unknown".to_string()),
-                                input_tokens: usage.input_tokens.unwrap_or(0),
-                                output_tokens: usage.output_tokens.unwrap_or(0),
-                                cache_creation_tokens: usage
-                                    .cache_creation_input_tokens
-                                    .unwrap_or(0),
-                                cache_read_tokens: usage.cache_read_input_tokens.unwrap_or(0),
-                                cost,
-                                session_id: entry.session_id.unwrap_or_else(|| session_id.clone()),
-                                project_path,
-                            });
-                        }
-                    }
-                }
+        // Extract the actual project path from cwd if we haven't already
+        if file.project_path.is_none() {
+            if let Some(cwd) = json_value.get("cwd").and_then(|v| v.as_str()) {
+                file.project_path = Some(cwd.to_string());
             }
         }
-    }
 
-    entries
-}
+        let Ok(entry) = serde_json::from_value::<JsonlEntry>(json_value) else {
+            continue;
+        };
+        let Some(message) = &entry.message else {
+            continue;
+        };
+        let Some(usage) = &message.usage else {
+            continue;
+        };
 
-fn get_earliest_timestamp(path: &PathBuf) -> Option<String> {
-    if let Ok(content) = fs::read_to_string(path) {
-        let mut earliest_timestamp: Option<String> = None;
-        for line in content.lines() {
-            if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(line) {
-                if let Some(timestamp_str) = json_value.get("timestamp").and_then(|v| v.as_str()) {
-                    if let Some(current_earliest) = &earliest_timestamp {
-                        if timestamp_str < current_earliest.as_str() {
-                            earliest_timestamp = Some(timestamp_str.to_string());
-                        }
-                    } else {
-                        earliest_timestamp = Some(timestamp_str.to_string());
-                    }
-                }
-            }
+        let input_tokens = usage.input_tokens.unwrap_or(0);
+        let output_tokens = usage.output_tokens.unwrap_or(0);
+        let cache_creation_tokens = usage.cache_creation_input_tokens.unwrap_or(0);
+        let cache_read_tokens = usage.cache_read_input_tokens.unwrap_or(0);
+
+        // Skip entries without meaningful token usage
+        if input_tokens == 0
+            && output_tokens == 0
+            && cache_creation_tokens == 0
+            && cache_read_tokens == 0
+        {
+            continue;
         }
-        return earliest_timestamp;
-    }
-    None
-}
 
-fn get_all_usage_entries(claude_path: &PathBuf) -> Vec<UsageEntry> {
-    let mut all_entries = Vec::new();
-    let mut processed_hashes = HashSet::new();
-    let projects_dir = claude_path.join("projects");
-
-    let mut files_to_process: Vec<(PathBuf, String)> = Vec::new();
-
-    if let Ok(projects) = fs::read_dir(&projects_dir) {
-        for project in projects.flatten() {
-            if project.file_type().map(|t| t.is_dir()).unwrap_or(false) {
-                let project_name = project.file_name().to_string_lossy().to_string();
-                let project_path = project.path();
-
-                walkdir::WalkDir::new(&project_path)
-                    .into_iter()
-                    .filter_map(Result::ok)
-                    .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("jsonl"))
-                    .for_each(|entry| {
-                        files_to_process.push((entry.path().to_path_buf(), project_name.clone()));
-                    });
-            }
+        let session_id = entry
+            .session_id
+            .clone()
+            .unwrap_or_else(|| fallback_session_id.clone());
+        if file.session_id.is_none() {
+            file.session_id = Some(session_id.clone());
         }
+
+        rows.push(UsageRow {
+            session_id,
+            timestamp: normalize_timestamp(&entry.timestamp),
+            model: message
+                .model
+                .clone()
+                .unwrap_or_else(|| "unknown".to_string()),
+            input_tokens,
+            output_tokens,
+            cache_creation_tokens,
+            cache_read_tokens,
+            total_tokens: input_tokens + output_tokens + cache_creation_tokens + cache_read_tokens,
+            // Use actual project path if found, otherwise use encoded name
+            project_path: file
+                .project_path
+                .clone()
+                .unwrap_or_else(|| encoded_project_name(path)),
+            // Deduplication based on message ID and request ID
+            dedup_key: match (&message.id, &entry.request_id) {
+                (Some(msg_id), Some(req_id)) => Some(format!("claude:{}:{}", msg_id, req_id)),
+                _ => None,
+            },
+            reported_cost: entry.cost_usd,
+            ..Default::default()
+        });
     }
 
-    // Sort files by their earliest timestamp to ensure chronological processing
-    // and deterministic deduplication
-    files_to_process.sort_by_cached_key(|(path, _)| get_earliest_timestamp(path));
+    Ok(rows)
+}
 
-    for (path, project_name) in files_to_process {
-        let entries = parse_jsonl_file(&path, &project_name, &mut processed_hashes);
-        all_entries.extend(entries);
+/// Ingests Claude usage written since the last pass into `usage_entries`
+pub(crate) fn sync_claude_usage(conn: &mut Connection) -> Result<usize, String> {
+    let projects_dir = claude_projects_dir()?;
+    if !projects_dir.exists() {
+        return Ok(0);
     }
 
-    // Sort by timestamp
-    all_entries.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    let mut files: Vec<(PathBuf, SystemTime)> = walkdir::WalkDir::new(&projects_dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("jsonl"))
+        .map(|e| {
+            let modified = e
+                .metadata()
+                .ok()
+                .and_then(|m| m.modified().ok())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            (e.into_path(), modified)
+        })
+        .collect();
 
-    all_entries
+    // Oldest first, so a response repeated in a resumed transcript stays
+    // attributed to the session that produced it
+    files.sort_by_key(|(_, modified)| *modified);
+
+    sync_usage(
+        conn,
+        SessionEngine::Claude,
+        files.into_iter().map(|(path, _)| path).collect(),
+        OnConflict::KeepFirst,
+        &price_book(),
+        &ingest_claude_file,
+    )
 }
 
-#[command]
-pub fn get_usage_stats(days: Option<u32>) -> Result<UsageStats, String> {
-    let claude_path = dirs::home_dir()
-        .ok_or("Failed to get home directory")?
-        .join(".claude");
-
-    let all_entries = get_all_usage_entries(&claude_path);
-
-    if all_entries.is_empty() {
-        return Ok(UsageStats {
-            total_cost: 0.0,
-            total_tokens: 0,
-            total_input_tokens: 0,
-            total_output_tokens: 0,
-            total_cache_creation_tokens: 0,
-            total_cache_read_tokens: 0,
-            total_sessions: 0,
-            by_model: vec![],
-            by_date: vec![],
-            by_project: vec![],
-        });
-    }
-
-    // Filter by days if specified
-    // 🚀 修复时区问题：使用本地时区进行日期比较
-    let filtered_entries = if let Some(days) = days {
-        let cutoff = Local::now().date_naive() - chrono::Duration::days(days as i64);
-        all_entries
-            .into_iter()
-            .filter(|e| {
-                if let Ok(dt) = DateTime::parse_from_rfc3339(&e.timestamp) {
-                    // 转换为本地时区后提取日期进行比较
-                    dt.with_timezone(&Local).date_naive() >= cutoff
-                } else {
-                    false
-                }
-            })
-            .collect()
-    } else {
-        all_entries
-    };
+// ============================================================================
+// Aggregation
+// ============================================================================
 
-    // Calculate aggregated stats
-    let mut total_cost = 0.0;
-    let mut total_input_tokens = 0u64;
-    let mut total_output_tokens = 0u64;
-    let mut total_cache_creation_tokens = 0u64;
-    let mut total_cache_read_tokens = 0u64;
-
-    let mut model_stats: HashMap<String, ModelUsage> = HashMap::new();
-    let mut daily_stats: HashMap<String, DailyUsage> = HashMap::new();
-    let mut project_stats: HashMap<String, ProjectUsage> = HashMap::new();
-
-    for entry in &filtered_entries {
-        // Update totals
-        total_cost += entry.cost;
-        total_input_tokens += entry.input_tokens;
-        total_output_tokens += entry.output_tokens;
-        total_cache_creation_tokens += entry.cache_creation_tokens;
-        total_cache_read_tokens += entry.cache_read_tokens;
-
-        // Update model stats
-        let model_stat = model_stats
-            .entry(entry.model.clone())
-            .or_insert(ModelUsage {
-                model: entry.model.clone(),
-                total_cost: 0.0,
-                total_tokens: 0,
-                input_tokens: 0,
-                output_tokens: 0,
-                cache_creation_tokens: 0,
-                cache_read_tokens: 0,
-                session_count: 0,
-            });
-        model_stat.total_cost += entry.cost;
-        model_stat.input_tokens += entry.input_tokens;
-        model_stat.output_tokens += entry.output_tokens;
-        model_stat.cache_creation_tokens += entry.cache_creation_tokens;
-        model_stat.cache_read_tokens += entry.cache_read_tokens;
-        model_stat.total_tokens = model_stat.input_tokens + model_stat.output_tokens;
-        model_stat.session_count += 1;
-
-        // Update daily stats
-        // 🚀 修复时区问题：使用本地日期而不是 UTC 日期
-        let date = if let Ok(dt) = DateTime::parse_from_rfc3339(&entry.timestamp) {
-            // 转换为本地时间后提取日期
-            dt.with_timezone(&Local).format("%Y-%m-%d").to_string()
-        } else {
-            // 降级：直接从字符串提取（可能不准确）
-            entry
-                .timestamp
-                .split('T')
-                .next()
-                .unwrap_or(&entry.timestamp)
-                .to_string()
-        };
-        let daily_stat = daily_stats.entry(date.clone()).or_insert(DailyUsage {
-            date,
-            total_cost: 0.0,
-            total_tokens: 0,
-            models_used: vec![],
-        });
-        daily_stat.total_cost += entry.cost;
-        daily_stat.total_tokens += entry.input_tokens
-            + entry.output_tokens
-            + entry.cache_creation_tokens
-            + entry.cache_read_tokens;
-        if !daily_stat.models_used.contains(&entry.model) {
-            daily_stat.models_used.push(entry.model.clone());
-        }
+fn model_usage(agg: UsageAggregate) -> ModelUsage {
+    ModelUsage {
+        model: agg.key,
+        total_cost: agg.cost,
+        total_tokens: agg.input_tokens + agg.output_tokens,
+        input_tokens: agg.input_tokens,
+        output_tokens: agg.output_tokens,
+        cache_creation_tokens: agg.cache_creation_tokens,
+        cache_read_tokens: agg.cache_read_tokens,
+        session_count: agg.session_count,
+    }
+}
 
-        // Update project stats
-        let project_stat =
-            project_stats
-                .entry(entry.project_path.clone())
-                .or_insert(ProjectUsage {
-                    project_path: entry.project_path.clone(),
-                    project_name: entry
-                        .project_path
-                        .split('/')
-                        .last()
-                        .unwrap_or(&entry.project_path)
-                        .to_string(),
-                    total_cost: 0.0,
-                    total_tokens: 0,
-                    session_count: 0,
-                    last_used: entry.timestamp.clone(),
-                });
-        project_stat.total_cost += entry.cost;
-        project_stat.total_tokens += entry.input_tokens
-            + entry.output_tokens
-            + entry.cache_creation_tokens
-            + entry.cache_read_tokens;
-        project_stat.session_count += 1;
-        if entry.timestamp > project_stat.last_used {
-            project_stat.last_used = entry.timestamp.clone();
-        }
+fn project_usage(agg: UsageAggregate) -> ProjectUsage {
+    ProjectUsage {
+        project_name: project_name(&agg.key),
+        project_path: agg.key,
+        total_cost: agg.cost,
+        total_tokens: agg.total_tokens,
+        session_count: agg.session_count,
+        last_used: agg.last_used,
     }
+}
 
-    let total_tokens = total_input_tokens
-        + total_output_tokens
-        + total_cache_creation_tokens
-        + total_cache_read_tokens;
-    let total_sessions = filtered_entries.len() as u64;
+fn build_usage_stats(conn: &Connection, range: &UsageRange) -> Result<UsageStats, String> {
+    let total = query_usage_total(conn, SessionEngine::Claude, range)?;
 
-    // Convert hashmaps to sorted vectors
-    let mut by_model: Vec<ModelUsage> = model_stats.into_values().collect();
-    by_model.sort_by(|a, b| b.total_cost.partial_cmp(&a.total_cost).unwrap());
+    let mut by_model: Vec<ModelUsage> =
+        query_usage(conn, SessionEngine::Claude, range, UsageGroup::Model)?
+            .into_iter()
+            .map(model_usage)
+            .collect();
+    by_model.sort_by(|a, b| b.total_cost.total_cmp(&a.total_cost));
 
-    let mut by_date: Vec<DailyUsage> = daily_stats.into_values().collect();
-    by_date.sort_by(|a, b| a.date.cmp(&b.date));
+    let by_date: Vec<DailyUsage> =
+        query_usage(conn, SessionEngine::Claude, range, UsageGroup::Date)?
+            .into_iter()
+            .map(|agg| DailyUsage {
+                date: agg.key,
+                total_cost: agg.cost,
+                total_tokens: agg.total_tokens,
+                models_used: agg.models,
+            })
+            .collect();
 
-    let mut by_project: Vec<ProjectUsage> = project_stats.into_values().collect();
-    by_project.sort_by(|a, b| b.total_cost.partial_cmp(&a.total_cost).unwrap());
+    let mut by_project: Vec<ProjectUsage> =
+        query_usage(conn, SessionEngine::Claude, range, UsageGroup::Project)?
+            .into_iter()
+            .map(project_usage)
+            .collect();
+    by_project.sort_by(|a, b| b.total_cost.total_cmp(&a.total_cost));
 
     Ok(UsageStats {
-        total_cost,
-        total_tokens,
-        total_input_tokens,
-        total_output_tokens,
-        total_cache_creation_tokens,
-        total_cache_read_tokens,
-        total_sessions,
+        total_cost: total.cost,
+        total_tokens: total.total_tokens,
+        total_input_tokens: total.input_tokens,
+        total_output_tokens: total.output_tokens,
+        total_cache_creation_tokens: total.cache_creation_tokens,
+        total_cache_read_tokens: total.cache_read_tokens,
+        total_sessions: total.session_count,
         by_model,
         by_date,
         by_project,
     })
 }
 
-#[command]
-pub fn get_usage_by_date_range(start_date: String, end_date: String) -> Result<UsageStats, String> {
-    let claude_path = dirs::home_dir()
-        .ok_or("Failed to get home directory")?
-        .join(".claude");
-
-    let all_entries = get_all_usage_entries(&claude_path);
-
-    // Parse dates
-    let start = NaiveDate::parse_from_str(&start_date, "%Y-%m-%d").or_else(|_| {
-        DateTime::parse_from_rfc3339(&start_date)
-            .map(|dt| dt.naive_local().date())
-            .map_err(|e| format!("Invalid start date: {}", e))
-    })?;
-    let end = NaiveDate::parse_from_str(&end_date, "%Y-%m-%d").or_else(|_| {
-        DateTime::parse_from_rfc3339(&end_date)
+pub(crate) fn parse_range_date(value: &str, label: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").or_else(|_| {
+        DateTime::parse_from_rfc3339(value)
             .map(|dt| dt.naive_local().date())
-            .map_err(|e| format!("Invalid end date: {}", e))
-    })?;
-
-    // Filter entries by date range
-    // 🚀 修复时区问题：转换为本地时区后进行日期比较
-    let filtered_entries: Vec<_> = all_entries
-        .into_iter()
-        .filter(|e| {
-            if let Ok(dt) = DateTime::parse_from_rfc3339(&e.timestamp) {
-                // 先转换为本地时区，再提取日期进行比较
-                let date = dt.with_timezone(&Local).date_naive();
-                date >= start && date <= end
-            } else {
-                false
-            }
-        })
-        .collect();
-
-    if filtered_entries.is_empty() {
-        return Ok(UsageStats {
-            total_cost: 0.0,
-            total_tokens: 0,
-            total_input_tokens: 0,
-            total_output_tokens: 0,
-            total_cache_creation_tokens: 0,
-            total_cache_read_tokens: 0,
-            total_sessions: 0,
-            by_model: vec![],
-            by_date: vec![],
-            by_project: vec![],
-        });
-    }
-
-    // Calculate aggregated stats from filtered entries
-    let mut total_cost = 0.0;
-    let mut total_input_tokens = 0u64;
-    let mut total_output_tokens = 0u64;
-    let mut total_cache_creation_tokens = 0u64;
-    let mut total_cache_read_tokens = 0u64;
-
-    let mut model_stats: HashMap<String, ModelUsage> = HashMap::new();
-    let mut daily_stats: HashMap<String, DailyUsage> = HashMap::new();
-    let mut project_stats: HashMap<String, ProjectUsage> = HashMap::new();
-
-    for entry in &filtered_entries {
-        // Update totals
-        total_cost += entry.cost;
-        total_input_tokens += entry.input_tokens;
-        total_output_tokens += entry.output_tokens;
-        total_cache_creation_tokens += entry.cache_creation_tokens;
-        total_cache_read_tokens += entry.cache_read_tokens;
-
-        // Update model stats
-        let model_stat = model_stats
-            .entry(entry.model.clone())
-            .or_insert(ModelUsage {
-                model: entry.model.clone(),
-                total_cost: 0.0,
-                total_tokens: 0,
-                input_tokens: 0,
-                output_tokens: 0,
-                cache_creation_tokens: 0,
-                cache_read_tokens: 0,
-                session_count: 0,
-            });
-        model_stat.total_cost += entry.cost;
-        model_stat.input_tokens += entry.input_tokens;
-        model_stat.output_tokens += entry.output_tokens;
-        model_stat.cache_creation_tokens += entry.cache_creation_tokens;
-        model_stat.cache_read_tokens += entry.cache_read_tokens;
-        model_stat.total_tokens = model_stat.input_tokens + model_stat.output_tokens;
-        model_stat.session_count += 1;
-
-        // Update daily stats
-        // 🚀 修复时区问题：使用本地日期而不是 UTC 日期
-        let date = if let Ok(dt) = DateTime::parse_from_rfc3339(&entry.timestamp) {
-            // 转换为本地时间后提取日期
-            dt.with_timezone(&Local).format("%Y-%m-%d").to_string()
-        } else {
-            // 降级：直接从字符串提取（可能不准确）
-            entry
-                .timestamp
-                .split('T')
-                .next()
-                .unwrap_or(&entry.timestamp)
-                .to_string()
-        };
-        let daily_stat = daily_stats.entry(date.clone()).or_insert(DailyUsage {
-            date,
-            total_cost: 0.0,
-            total_tokens: 0,
-            models_used: vec![],
-        });
-        daily_stat.total_cost += entry.cost;
-        daily_stat.total_tokens += entry.input_tokens
-            + entry.output_tokens
-            + entry.cache_creation_tokens
-            + entry.cache_read_tokens;
-        if !daily_stat.models_used.contains(&entry.model) {
-            daily_stat.models_used.push(entry.model.clone());
-        }
-
-        // Update project stats
-        let project_stat =
-            project_stats
-                .entry(entry.project_path.clone())
-                .or_insert(ProjectUsage {
-                    project_path: entry.project_path.clone(),
-                    project_name: entry
-                        .project_path
-                        .split('/')
-                        .last()
-                        .unwrap_or(&entry.project_path)
-                        .to_string(),
-                    total_cost: 0.0,
-                    total_tokens: 0,
-                    session_count: 0,
-                    last_used: entry.timestamp.clone(),
-                });
-        project_stat.total_cost += entry.cost;
-        project_stat.total_tokens += entry.input_tokens
-            + entry.output_tokens
-            + entry.cache_creation_tokens
-            + entry.cache_read_tokens;
-        project_stat.session_count += 1;
-        if entry.timestamp > project_stat.last_used {
-            project_stat.last_used = entry.timestamp.clone();
-        }
-    }
-
-    let unique_sessions: HashSet<_> = filtered_entries.iter().map(|e| &e.session_id).collect();
+            .map_err(|e| format!("Invalid {} date: {}", label, e))
+    })
+}
 
-    let mut by_model: Vec<ModelUsage> = model_stats.into_values().collect();
-    by_model.sort_by(|a, b| b.total_cost.partial_cmp(&a.total_cost).unwrap());
+// ============================================================================
+// Tauri Commands
+// ============================================================================
 
-    let mut by_date: Vec<DailyUsage> = daily_stats.into_values().collect();
-    by_date.sort_by(|a, b| a.date.cmp(&b.date));
+#[command]
+pub async fn get_usage_stats(app: AppHandle, days: Option<u32>) -> Result<UsageStats, String> {
+    // 🚀 修复时区问题：使用本地时区进行日期比较
+    let since = days.map(|days| Local::now().date_naive() - chrono::Duration::days(days as i64));
+    let range = UsageRange::local_dates(since, None);
 
-    let mut by_project: Vec<ProjectUsage> = project_stats.into_values().collect();
-    by_project.sort_by(|a, b| b.total_cost.partial_cmp(&a.total_cost).unwrap());
+    with_usage_db(app, move |conn| {
+        sync_claude_usage(conn)?;
+        build_usage_stats(conn, &range)
+    })
+    .await
+}
 
-    Ok(UsageStats {
-        total_cost,
-        total_tokens: total_input_tokens
-            + total_output_tokens
-            + total_cache_creation_tokens
-            + total_cache_read_tokens,
-        total_input_tokens,
-        total_output_tokens,
-        total_cache_creation_tokens,
-        total_cache_read_tokens,
-        total_sessions: unique_sessions.len() as u64,
-        by_model,
-        by_date,
-        by_project,
+#[command]
+pub async fn get_usage_by_date_range(
+    app: AppHandle,
+    start_date: String,
+    end_date: String,
+) -> Result<UsageStats, String> {
+    let start = parse_range_date(&start_date, "start")?;
+    let end = parse_range_date(&end_date, "end")?;
+    let range = UsageRange::local_dates(Some(start), Some(end));
+
+    with_usage_db(app, move |conn| {
+        sync_claude_usage(conn)?;
+        build_usage_stats(conn, &range)
     })
+    .await
 }
 
 #[command]
-pub fn get_session_stats(
+pub async fn get_session_stats(
+    app: AppHandle,
     since: Option<String>,
     until: Option<String>,
     order: Option<String>,
 ) -> Result<Vec<ProjectUsage>, String> {
-    let claude_path = dirs::home_dir()
-        .ok_or("Failed to get home directory")?
-        .join(".claude");
-
-    let all_entries = get_all_usage_entries(&claude_path);
-
-    // Filter by date range if provided
-    // 🚀 修复时区问题：转换为本地时区后进行日期比较
-    let filtered_entries: Vec<_> = all_entries
-        .into_iter()
-        .filter(|e| {
-            if let (Some(since_str), Some(until_str)) = (&since, &until) {
-                if let (Ok(since_date), Ok(until_date)) = (
-                    NaiveDate::parse_from_str(since_str, "%Y%m%d"),
-                    NaiveDate::parse_from_str(until_str, "%Y%m%d"),
-                ) {
-                    if let Ok(dt) = DateTime::parse_from_rfc3339(&e.timestamp) {
-                        // 先转换为本地时区，再提取日期进行比较
-                        let date = dt.with_timezone(&Local).date_naive();
-                        return date >= since_date && date <= until_date;
-                    }
-                }
+    // Only filter when both bounds parse, like before
+    let range = match (&since, &until) {
+        (Some(since_str), Some(until_str)) => match (
+            NaiveDate::parse_from_str(since_str, "%Y%m%d"),
+            NaiveDate::parse_from_str(until_str, "%Y%m%d"),
+        ) {
+            (Ok(since_date), Ok(until_date)) => {
+                UsageRange::local_dates(Some(since_date), Some(until_date))
             }
-            true
-        })
-        .collect();
-
-    // Group by project
-    let mut project_stats: HashMap<String, ProjectUsage> = HashMap::new();
-    for entry in filtered_entries {
-        let project_stat =
-            project_stats
-                .entry(entry.project_path.clone())
-                .or_insert(ProjectUsage {
-                    project_path: entry.project_path.clone(),
-                    project_name: entry
-                        .project_path
-                        .split('/')
-                        .last()
-                        .unwrap_or(&entry.project_path)
-                        .to_string(),
-                    total_cost: 0.0,
-                    total_tokens: 0,
-                    session_count: 0,
-                    last_used: entry.timestamp.clone(),
-                });
-        project_stat.total_cost += entry.cost;
-        project_stat.total_tokens += entry.input_tokens
-            + entry.output_tokens
-            + entry.cache_creation_tokens
-            + entry.cache_read_tokens;
-        project_stat.session_count += 1;
-        if entry.timestamp > project_stat.last_used {
-            project_stat.last_used = entry.timestamp.clone();
-        }
-    }
+            _ => UsageRange::default(),
+        },
+        _ => UsageRange::default(),
+    };
 
-    let mut by_session: Vec<ProjectUsage> = project_stats.into_values().collect();
+    let mut by_session: Vec<ProjectUsage> = with_usage_db(app, move |conn| {
+        sync_claude_usage(conn)?;
+        query_usage(conn, SessionEngine::Claude, &range, UsageGroup::Project)
+    })
+    .await?
+    .into_iter()
+    .map(project_usage)
+    .collect();
 
     // Sort by order
     let order_str = order.unwrap_or_else(|| "desc".to_string());
     if order_str == "asc" {
-        by_session.sort_by(|a, b| a.total_cost.partial_cmp(&b.total_cost).unwrap());
+        by_session.sort_by(|a, b| a.total_cost.total_cmp(&b.total_cost));
     } else {
-        by_session.sort_by(|a, b| b.total_cost.partial_cmp(&a.total_cost).unwrap());
+        by_session.sort_by(|a, b| b.total_cost.total_cmp(&a.total_cost));
     }
 
     Ok(by_session)
@@ -0,0 +1,921 @@
+//! Incremental usage ingestion into `usage_entries`.
+//!
+//! Claude and Codex transcripts are append-only JSONL, Gemini chats are JSON
+//! documents rewritten in place. For every session file the read position and
+//! any parser state (model, Codex cumulative totals, ...) is kept in
+//! `usage_ingest_files`, so a stats request only parses what was written since
+//! the previous request and everything else is plain SQL aggregation.
+//!
+//! Rows are never removed when a session file is deleted: the table is the
+//! usage ledger. A file that shrank was rewritten and is ingested again from
+//! the start, replacing the rows it produced before.
+//!
+//! Rows are priced here rather than by the parsers. Each row keeps the provider
+//! config its run used, taken from the spend the app recorded for that session
+//! in `budget_ledger` (none for sessions run outside the app), and the cost
+//! reported by the CLI, so [`reprice_usage`] can recompute every stored cost
+//! when the price table changes.
+
+use chrono::{DateTime, Local, NaiveDate, SecondsFormat, TimeZone, Utc};
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use super::budget::RunProviders;
+use super::pricing::{PriceBook, PriceSource, TokenCounts};
+use super::session_timeline::SessionEngine;
+use super::storage::AgentDb;
+
+// ============================================================================
+// Schema
+// ============================================================================
+
+/// Adds the ingestion columns and tables; called from `storage::init_database`
+pub fn init_usage_ingest_tables(conn: &Connection) -> SqliteResult<()> {
+    // Columns added after usage_entries was first shipped
+    let columns: HashSet<String> = conn
+        .prepare("PRAGMA table_info(usage_entries)")?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<SqliteResult<_>>()?;
+
+    for (name, definition) in [
+        ("engine", "TEXT NOT NULL DEFAULT 'claude'"),
+        ("source_path", "TEXT"),
+        ("dedup_key", "TEXT"),
+        ("provider_id", "TEXT"),
+        ("reported_cost", "REAL"),
+    ] {
+        if !columns.contains(name) {
+            conn.execute(
+                &format!(
+                    "ALTER TABLE usage_entries ADD COLUMN {} {}",
+                    name, definition
+                ),
+                [],
+            )?;
+        }
+    }
+
+    // Claude repeats a response in every resumed transcript; the key keeps one copy
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_usage_dedup_key
+         ON usage_entries(dedup_key)",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_usage_engine_timestamp
+         ON usage_entries(engine, timestamp)",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_usage_source_path
+         ON usage_entries(source_path)",
+        [],
+    )?;
+
+    // One row per session file: read position plus session metadata
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS usage_ingest_files (
+            path TEXT PRIMARY KEY,
+            engine TEXT NOT NULL,
+            read_offset INTEGER NOT NULL DEFAULT 0,
+            size INTEGER NOT NULL DEFAULT 0,
+            modified INTEGER NOT NULL DEFAULT 0,
+            session_id TEXT,
+            project_path TEXT,
+            model TEXT,
+            first_message TEXT,
+            started_at TEXT,
+            state TEXT,
+            ingested_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_usage_ingest_files_engine
+         ON usage_ingest_files(engine, session_id)",
+        [],
+    )?;
+
+    log::info!("[UsageIngest] Usage ingestion tables ready");
+    Ok(())
+}
+
+// ============================================================================
+// Ingestion
+// ============================================================================
+
+/// Per-file progress carried between ingestion passes
+#[derive(Debug, Clone, Default)]
+pub struct IngestedFile {
+    /// Byte offset for JSONL files, message count for Gemini chats
+    pub offset: u64,
+    pub session_id: Option<String>,
+    pub project_path: Option<String>,
+    pub model: Option<String>,
+    pub first_message: Option<String>,
+    pub started_at: Option<String>,
+    /// Engine-specific parser state (e.g. Codex cumulative token totals)
+    pub state: Value,
+}
+
+/// One usage record destined for `usage_entries`
+#[derive(Debug, Clone, Default)]
+pub struct UsageRow {
+    pub session_id: String,
+    pub timestamp: String,
+    pub model: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub total_tokens: u64,
+    /// Filled in by [`sync_usage`] from the price table
+    pub cost: f64,
+    pub project_path: String,
+    pub dedup_key: Option<String>,
+    /// Provider config of the run, filled in by [`sync_usage`]
+    pub provider_id: Option<String>,
+    /// Cost the CLI reported for the row (Claude `costUSD`)
+    pub reported_cost: Option<f64>,
+}
+
+/// Cost of a row: a custom price beats the CLI's reported cost, which is
+/// always at list price, and that beats the built-in list
+pub fn price_row(
+    prices: &PriceBook,
+    engine: SessionEngine,
+    model: &str,
+    provider_id: Option<&str>,
+    timestamp: &str,
+    tokens: &TokenCounts,
+    reported_cost: Option<f64>,
+) -> f64 {
+    let price = prices.resolve(engine, model, provider_id, Some(timestamp));
+    match (price, reported_cost) {
+        (Some(price), _) if price.source == PriceSource::Custom => price.rates.cost(tokens),
+        (_, Some(cost)) => cost,
+        (Some(price), None) => price.rates.cost(tokens),
+        (None, None) => {
+            log::warn!(
+                "Unknown model detected: '{}'. Cost calculation will return 0.",
+                model
+            );
+            0.0
+        }
+    }
+}
+
+impl UsageRow {
+    fn tokens(&self) -> TokenCounts {
+        TokenCounts {
+            input: self.input_tokens,
+            output: self.output_tokens,
+            cache_write: self.cache_creation_tokens,
+            cache_read: self.cache_read_tokens,
+        }
+    }
+}
+
+/// What to do when a row's `dedup_key` already exists
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OnConflict {
+    /// Keep the first copy (Claude responses duplicated across transcripts)
+    KeepFirst,
+    /// Overwrite (Gemini messages whose token counts are filled in later)
+    Replace,
+}
+
+/// Parses whatever is new in `path`, advancing `file`
+pub type IngestFn<'a> = &'a dyn Fn(&Path, &mut IngestedFile) -> Result<Vec<UsageRow>, String>;
+
+/// Reads the complete lines appended after `offset`.
+///
+/// A line still being written has no trailing newline yet; it is left for the
+/// next pass, so the returned offset always points at a line boundary.
+pub fn read_appended_lines(path: &Path, offset: u64) -> Result<(Vec<String>, u64), String> {
+    let mut file =
+        fs::File::open(path).map_err(|e| format!("Failed to open session file: {}", e))?;
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| format!("Failed to seek session file: {}", e))?;
+
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer)
+        .map_err(|e| format!("Failed to read session file: {}", e))?;
+
+    let complete = buffer
+        .iter()
+        .rposition(|b| *b == b'\n')
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    let lines = String::from_utf8_lossy(&buffer[..complete])
+        .lines()
+        .map(|l| l.to_string())
+        .collect();
+
+    Ok((lines, offset + complete as u64))
+}
+
+/// Normalizes RFC 3339 timestamps to UTC (`...000Z`) so they sort as text
+pub fn normalize_timestamp(timestamp: &str) -> String {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|dt| {
+            dt.with_timezone(&Utc)
+                .to_rfc3339_opts(SecondsFormat::Millis, true)
+        })
+        .unwrap_or_else(|_| timestamp.to_string())
+}
+
+fn modified_secs(metadata: &fs::Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Loads the stored progress of every file of `engine`, keyed by path
+fn load_ingested_files(
+    conn: &Connection,
+    engine: SessionEngine,
+) -> Result<HashMap<String, (IngestedFile, u64, i64)>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT path, read_offset, size, modified, session_id, project_path, model,
+                    first_message, started_at, state
+             FROM usage_ingest_files WHERE engine = ?1",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([engine.as_str()], |row| {
+            let state: Option<String> = row.get(9)?;
+            Ok((
+                row.get::<_, String>(0)?,
+                (
+                    IngestedFile {
+                        offset: row.get::<_, i64>(1)? as u64,
+                        session_id: row.get(4)?,
+                        project_path: row.get(5)?,
+                        model: row.get(6)?,
+                        first_message: row.get(7)?,
+                        started_at: row.get(8)?,
+                        state: state
+                            .and_then(|s| serde_json::from_str(&s).ok())
+                            .unwrap_or(Value::Null),
+                    },
+                    row.get::<_, i64>(2)? as u64,
+                    row.get::<_, i64>(3)?,
+                ),
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+
+    rows.collect::<SqliteResult<HashMap<_, _>>>()
+        .map_err(|e| e.to_string())
+}
+
+/// Brings `usage_entries` up to date with the given session files, pricing
+/// the new rows with `prices`.
+///
+/// Files whose size and mtime are unchanged are skipped without being opened.
+/// Returns the number of rows written.
+pub fn sync_usage(
+    conn: &mut Connection,
+    engine: SessionEngine,
+    paths: Vec<PathBuf>,
+    on_conflict: OnConflict,
+    prices: &PriceBook,
+    ingest: IngestFn<'_>,
+) -> Result<usize, String> {
+    let known = load_ingested_files(conn, engine)?;
+    let providers = RunProviders::load(conn, engine);
+    let insert_sql = match on_conflict {
+        OnConflict::KeepFirst => "INSERT OR IGNORE INTO usage_entries",
+        OnConflict::Replace => "INSERT OR REPLACE INTO usage_entries",
+    };
+    let mut written = 0;
+
+    for path in paths {
+        let Ok(metadata) = fs::metadata(&path) else {
+            continue;
+        };
+        let size = metadata.len();
+        let modified = modified_secs(&metadata);
+        let key = path.to_string_lossy().to_string();
+
+        let (mut file, reset) = match known.get(&key) {
+            Some((_, known_size, known_modified))
+                if *known_size == size && *known_modified == modified =>
+            {
+                continue
+            }
+            // Shrunk: the file was rewritten, start over
+            Some((_, known_size, _)) if size < *known_size => (IngestedFile::default(), true),
+            Some((file, _, _)) => (file.clone(), false),
+            None => (IngestedFile::default(), false),
+        };
+
+        let mut rows = match ingest(&path, &mut file) {
+            Ok(rows) => rows,
+            Err(e) => {
+                log::warn!("[UsageIngest] Skipping {}: {}", key, e);
+                continue;
+            }
+        };
+        for row in &mut rows {
+            row.provider_id = providers.provider_at(&row.session_id, &row.timestamp);
+            row.cost = price_row(
+                prices,
+                engine,
+                &row.model,
+                row.provider_id.as_deref(),
+                &row.timestamp,
+                &row.tokens(),
+                row.reported_cost,
+            );
+        }
+
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        // Drop what a rewritten file produced before inserting it again, or
+        // KeepFirst would keep the stale copy of every rewritten line
+        if reset {
+            tx.execute("DELETE FROM usage_entries WHERE source_path = ?1", [&key])
+                .map_err(|e| e.to_string())?;
+        }
+        {
+            let mut stmt = tx
+                .prepare_cached(&format!(
+                    "{} (session_id, timestamp, model, input_tokens, output_tokens,
+                        cache_creation_tokens, cache_read_tokens, total_tokens, cost,
+                        project_path, engine, source_path, dedup_key, provider_id,
+                        reported_cost)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14,
+                        ?15)",
+                    insert_sql
+                ))
+                .map_err(|e| e.to_string())?;
+            for row in &rows {
+                written += stmt
+                    .execute(params![
+                        row.session_id,
+                        row.timestamp,
+                        row.model,
+                        row.input_tokens as i64,
+                        row.output_tokens as i64,
+                        row.cache_creation_tokens as i64,
+                        row.cache_read_tokens as i64,
+                        row.total_tokens as i64,
+                        row.cost,
+                        row.project_path,
+                        engine.as_str(),
+                        key,
+                        row.dedup_key,
+                        row.provider_id,
+                        row.reported_cost,
+                    ])
+                    .map_err(|e| format!("Failed to insert usage entry: {}", e))?;
+            }
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO usage_ingest_files
+                (path, engine, read_offset, size, modified, session_id, project_path, model,
+                 first_message, started_at, state, ingested_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                key,
+                engine.as_str(),
+                file.offset as i64,
+                size as i64,
+                modified,
+                file.session_id,
+                file.project_path,
+                file.model,
+                file.first_message,
+                file.started_at,
+                (!file.state.is_null()).then(|| file.state.to_string()),
+                Utc::now().to_rfc3339(),
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+
+    if written > 0 {
+        log::info!(
+            "[UsageIngest] Ingested {} {} usage entries",
+            written,
+            engine.as_str()
+        );
+    }
+    Ok(written)
+}
+
+/// Runs `f` with the locked database on the blocking thread pool, so a stats
+/// command ingesting a backlog of session files doesn't stall the async
+/// runtime
+pub async fn with_usage_db<T, F>(app: AppHandle, f: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut Connection) -> Result<T, String> + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || {
+        let db = app.state::<AgentDb>();
+        let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+        f(&mut conn)
+    })
+    .await
+    .map_err(|e| format!("Usage ingestion task failed: {}", e))?
+}
+
+/// Recomputes the stored cost of every row with `prices`, attributing rows
+/// ingested before their run's spend was recorded. Returns the number of rows
+/// whose cost or provider changed.
+pub fn reprice_usage(conn: &mut Connection, prices: &PriceBook) -> Result<usize, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut updated = 0;
+    for engine in SessionEngine::ALL {
+        let providers = RunProviders::load(&tx, engine);
+        let rows: Vec<(i64, UsageRow, f64)> = {
+            let mut stmt = tx
+                .prepare(
+                    "SELECT id, session_id, timestamp, model, input_tokens, output_tokens,
+                            cache_creation_tokens, cache_read_tokens, cost, provider_id,
+                            reported_cost
+                     FROM usage_entries WHERE engine = ?1",
+                )
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map([engine.as_str()], |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        UsageRow {
+                            session_id: row.get(1)?,
+                            timestamp: row.get(2)?,
+                            model: row.get(3)?,
+                            input_tokens: row.get::<_, i64>(4)? as u64,
+                            output_tokens: row.get::<_, i64>(5)? as u64,
+                            cache_creation_tokens: row.get::<_, i64>(6)? as u64,
+                            cache_read_tokens: row.get::<_, i64>(7)? as u64,
+                            provider_id: row.get(9)?,
+                            reported_cost: row.get(10)?,
+                            ..Default::default()
+                        },
+                        row.get::<_, f64>(8)?,
+                    ))
+                })
+                .map_err(|e| e.to_string())?;
+            rows.collect::<SqliteResult<_>>()
+                .map_err(|e| format!("Failed to read usage rows: {}", e))?
+        };
+
+        let mut stmt = tx
+            .prepare_cached("UPDATE usage_entries SET cost = ?1, provider_id = ?2 WHERE id = ?3")
+            .map_err(|e| e.to_string())?;
+        for (id, row, old_cost) in rows {
+            let provider_id = row
+                .provider_id
+                .clone()
+                .or_else(|| providers.provider_at(&row.session_id, &row.timestamp));
+            let cost = price_row(
+                prices,
+                engine,
+                &row.model,
+                provider_id.as_deref(),
+                &row.timestamp,
+                &row.tokens(),
+                row.reported_cost,
+            );
+            if cost != old_cost || provider_id != row.provider_id {
+                stmt.execute(params![cost, provider_id, id])
+                    .map_err(|e| format!("Failed to update usage entry: {}", e))?;
+                updated += 1;
+            }
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    log::info!("[UsageIngest] Repriced {} usage entries", updated);
+    Ok(updated)
+}
+
+// ============================================================================
+// Queries
+// ============================================================================
+
+/// Time window for aggregation, as UTC bounds of local calendar days
+#[derive(Debug, Clone, Default)]
+pub struct UsageRange {
+    start: Option<String>,
+    end: Option<String>,
+}
+
+/// UTC instant of local midnight on `date` (RFC 3339, millis)
+pub fn local_midnight_utc(date: NaiveDate) -> String {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    let instant = Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc.from_utc_datetime(&midnight));
+    instant.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+impl UsageRange {
+    /// Inclusive range of local dates; `None` leaves that side open
+    pub fn local_dates(since: Option<NaiveDate>, until: Option<NaiveDate>) -> Self {
+        Self {
+            start: since.map(local_midnight_utc),
+            end: until.and_then(|d| d.succ_opt()).map(local_midnight_utc),
+        }
+    }
+}
+
+/// Dimension to aggregate `usage_entries` by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UsageGroup {
+    Total,
+    Model,
+    /// Local calendar day (YYYY-MM-DD)
+    Date,
+    /// Local calendar month (YYYY-MM)
+    Month,
+    Project,
+    Session,
+    /// Local calendar month and project, keyed `YYYY-MM <project_path>`
+    MonthProject,
+    /// Local calendar month and model, keyed `YYYY-MM <model>`
+    MonthModel,
+}
+
+/// Aggregated usage for one group
+#[derive(Debug, Clone, Default)]
+pub struct UsageAggregate {
+    pub key: String,
+    pub cost: f64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub total_tokens: u64,
+    pub entry_count: u64,
+    pub session_count: u64,
+    pub first_used: String,
+    pub last_used: String,
+    pub models: Vec<String>,
+    /// Project of the group's rows (the greatest one if they differ)
+    pub project_path: String,
+}
+
+/// Aggregates the usage of one engine within `range`
+pub fn query_usage(
+    conn: &Connection,
+    engine: SessionEngine,
+    range: &UsageRange,
+    group: UsageGroup,
+) -> Result<Vec<UsageAggregate>, String> {
+    const MONTH: &str =
+        "COALESCE(strftime('%Y-%m', timestamp, 'localtime'), substr(timestamp, 1, 7))";
+    let key_expr = match group {
+        UsageGroup::Total => "''".to_string(),
+        UsageGroup::Model => "model".to_string(),
+        UsageGroup::Date => {
+            "COALESCE(date(timestamp, 'localtime'), substr(timestamp, 1, 10))".to_string()
+        }
+        UsageGroup::Month => MONTH.to_string(),
+        UsageGroup::Project => "COALESCE(project_path, '')".to_string(),
+        UsageGroup::Session => "session_id".to_string(),
+        UsageGroup::MonthProject => format!("{} || ' ' || COALESCE(project_path, '')", MONTH),
+        UsageGroup::MonthModel => format!("{} || ' ' || model", MONTH),
+    };
+
+    let sql = format!(
+        "SELECT {} AS group_key,
+                SUM(cost), SUM(input_tokens), SUM(output_tokens),
+                SUM(cache_creation_tokens), SUM(cache_read_tokens), SUM(total_tokens),
+                COUNT(*), COUNT(DISTINCT session_id),
+                MIN(timestamp), MAX(timestamp), GROUP_CONCAT(DISTINCT model),
+                MAX(COALESCE(project_path, ''))
+         FROM usage_entries
+         WHERE engine = ?1
+           AND (?2 IS NULL OR timestamp >= ?2)
+           AND (?3 IS NULL OR timestamp < ?3)
+         GROUP BY group_key
+         ORDER BY group_key",
+        key_expr
+    );
+
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| format!("Failed to prepare usage query: {}", e))?;
+    let rows = stmt
+        .query_map(params![engine.as_str(), range.start, range.end], |row| {
+            let models: Option<String> = row.get(11)?;
+            Ok(UsageAggregate {
+                key: row.get(0)?,
+                cost: row.get(1)?,
+                input_tokens: row.get::<_, i64>(2)? as u64,
+                output_tokens: row.get::<_, i64>(3)? as u64,
+                cache_creation_tokens: row.get::<_, i64>(4)? as u64,
+                cache_read_tokens: row.get::<_, i64>(5)? as u64,
+                total_tokens: row.get::<_, i64>(6)? as u64,
+                entry_count: row.get::<_, i64>(7)? as u64,
+                session_count: row.get::<_, i64>(8)? as u64,
+                first_used: row.get(9)?,
+                last_used: row.get(10)?,
+                models: models
+                    .map(|m| m.split(',').map(|s| s.to_string()).collect())
+                    .unwrap_or_default(),
+                project_path: row.get(12)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query usage: {}", e))?;
+
+    rows.collect::<SqliteResult<Vec<_>>>()
+        .map_err(|e| format!("Failed to read usage rows: {}", e))
+}
+
+/// Totals of one engine within `range` (all zero when nothing matched)
+pub fn query_usage_total(
+    conn: &Connection,
+    engine: SessionEngine,
+    range: &UsageRange,
+) -> Result<UsageAggregate, String> {
+    Ok(query_usage(conn, engine, range, UsageGroup::Total)?
+        .into_iter()
+        .next()
+        .unwrap_or_default())
+}
+
+/// Session metadata recorded while ingesting, keyed by session id
+pub fn ingested_sessions(
+    conn: &Connection,
+    engine: SessionEngine,
+) -> Result<HashMap<String, IngestedFile>, String> {
+    Ok(load_ingested_files(conn, engine)?
+        .into_values()
+        .filter_map(|(file, _, _)| file.session_id.clone().map(|id| (id, file)))
+        .collect())
+}
+
+/// Last path component of a project path (handles both separators)
+pub fn project_name(project_path: &str) -> String {
+    project_path
+        .split(['/', '\\'])
+        .next_back()
+        .unwrap_or(project_path)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::budget::init_budget_tables;
+    use crate::commands::pricing::{PriceEntry, PricingConfig, TokenRates};
+    use std::io::Write;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE usage_entries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                model TEXT NOT NULL,
+                input_tokens INTEGER DEFAULT 0,
+                output_tokens INTEGER DEFAULT 0,
+                cache_creation_tokens INTEGER DEFAULT 0,
+                cache_read_tokens INTEGER DEFAULT 0,
+                total_tokens INTEGER DEFAULT 0,
+                cost REAL DEFAULT 0.0,
+                project_path TEXT,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )",
+            [],
+        )
+        .unwrap();
+        init_usage_ingest_tables(&conn).unwrap();
+        conn
+    }
+
+    /// One row per line: `<timestamp> <tokens>`
+    fn ingest_lines(path: &Path, file: &mut IngestedFile) -> Result<Vec<UsageRow>, String> {
+        let (lines, offset) = read_appended_lines(path, file.offset)?;
+        file.offset = offset;
+        file.session_id = Some("s1".to_string());
+        Ok(lines
+            .iter()
+            .map(|line| {
+                let (timestamp, tokens) = line.split_once(' ').unwrap();
+                let tokens: u64 = tokens.parse().unwrap();
+                UsageRow {
+                    session_id: "s1".to_string(),
+                    timestamp: normalize_timestamp(timestamp),
+                    model: "m".to_string(),
+                    input_tokens: tokens,
+                    total_tokens: tokens,
+                    reported_cost: Some(tokens as f64 / 100.0),
+                    project_path: "/repo".to_string(),
+                    dedup_key: Some(format!("test:{}", timestamp)),
+                    ..Default::default()
+                }
+            })
+            .collect())
+    }
+
+    #[test]
+    fn ingests_only_appended_complete_lines() {
+        let mut conn = test_conn();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("s1.jsonl");
+
+        let mut f = fs::File::create(&path).unwrap();
+        write!(
+            f,
+            "2025-01-01T10:00:00Z 10\n2025-01-02T10:00:00+00:00 20\n2025-01-03"
+        )
+        .unwrap();
+        f.flush().unwrap();
+        let paths = vec![path.clone()];
+
+        let written = sync_usage(
+            &mut conn,
+            SessionEngine::Claude,
+            paths.clone(),
+            OnConflict::KeepFirst,
+            &PriceBook::new(PricingConfig::default()),
+            &ingest_lines,
+        )
+        .unwrap();
+        assert_eq!(written, 2);
+
+        // Finish the partial line and append another one
+        write!(f, "T10:00:00Z 30\n2025-01-04T10:00:00Z 40\n").unwrap();
+        f.flush().unwrap();
+        let written = sync_usage(
+            &mut conn,
+            SessionEngine::Claude,
+            paths,
+            OnConflict::KeepFirst,
+            &PriceBook::new(PricingConfig::default()),
+            &ingest_lines,
+        )
+        .unwrap();
+        assert_eq!(written, 2);
+
+        let total =
+            query_usage_total(&conn, SessionEngine::Claude, &UsageRange::default()).unwrap();
+        assert_eq!(total.input_tokens, 100);
+        assert_eq!(total.entry_count, 4);
+        assert_eq!(total.session_count, 1);
+        assert_eq!(total.first_used, "2025-01-01T10:00:00.000Z");
+
+        let utc_range = UsageRange {
+            start: Some("2025-01-02T00:00:00.000Z".to_string()),
+            end: Some("2025-01-04T00:00:00.000Z".to_string()),
+        };
+        let ranged = query_usage_total(&conn, SessionEngine::Claude, &utc_range).unwrap();
+        assert_eq!(ranged.input_tokens, 50);
+
+        assert!(query_usage(
+            &conn,
+            SessionEngine::Codex,
+            &UsageRange::default(),
+            UsageGroup::Model
+        )
+        .unwrap()
+        .is_empty());
+    }
+
+    #[test]
+    fn rewritten_files_replace_their_rows() {
+        let mut conn = test_conn();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("s1.jsonl");
+        let sync = |conn: &mut Connection| {
+            sync_usage(
+                conn,
+                SessionEngine::Claude,
+                vec![path.clone()],
+                OnConflict::KeepFirst,
+                &PriceBook::new(PricingConfig::default()),
+                &ingest_lines,
+            )
+            .unwrap()
+        };
+
+        fs::write(&path, "2025-01-01T10:00:00Z 10\n2025-01-02T10:00:00Z 20\n").unwrap();
+        assert_eq!(sync(&mut conn), 2);
+
+        // Shorter, and the kept line now reports different usage
+        fs::write(&path, "2025-01-01T10:00:00Z 15\n").unwrap();
+        assert_eq!(sync(&mut conn), 1);
+
+        let total =
+            query_usage_total(&conn, SessionEngine::Claude, &UsageRange::default()).unwrap();
+        assert_eq!(total.entry_count, 1);
+        assert_eq!(total.input_tokens, 15);
+    }
+
+    #[test]
+    fn rows_are_priced_for_their_run_provider_and_repriced() {
+        let mut conn = test_conn();
+        init_budget_tables(&conn).unwrap();
+        // The app ran s1 through the relay provider; s2 ran outside the app
+        conn.execute(
+            "INSERT INTO budget_ledger
+                (engine, session_id, project_path, model, provider_id, cost, timestamp)
+             VALUES ('claude', 's1', '/repo', 'sonnet', 'relay', 0, '2025-01-01T10:00:01.000Z')",
+            [],
+        )
+        .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let paths: Vec<PathBuf> = ["s1", "s2"]
+            .iter()
+            .map(|name| {
+                let path = dir.path().join(format!("{}.jsonl", name));
+                fs::write(&path, format!("{} 2025-01-01T10:00:00Z\n", name)).unwrap();
+                path
+            })
+            .collect();
+        let ingest = |path: &Path, file: &mut IngestedFile| -> Result<Vec<UsageRow>, String> {
+            let (lines, offset) = read_appended_lines(path, file.offset)?;
+            file.offset = offset;
+            Ok(lines
+                .iter()
+                .map(|line| {
+                    let (session_id, timestamp) = line.split_once(' ').unwrap();
+                    UsageRow {
+                        session_id: session_id.to_string(),
+                        timestamp: normalize_timestamp(timestamp),
+                        model: "claude-sonnet-4-5".to_string(),
+                        input_tokens: 1_000_000,
+                        total_tokens: 1_000_000,
+                        reported_cost: Some(3.0),
+                        dedup_key: Some(format!("test:{}", session_id)),
+                        ..Default::default()
+                    }
+                })
+                .collect())
+        };
+        let relay_price = |input: f64| PriceEntry {
+            id: "relay".to_string(),
+            engine: SessionEngine::Claude,
+            model_pattern: "sonnet".to_string(),
+            provider_id: Some("relay".to_string()),
+            effective_from: Some("2024-01-01".to_string()),
+            effective_until: None,
+            rates: TokenRates {
+                input,
+                ..Default::default()
+            },
+        };
+        let costs = |conn: &Connection| -> Vec<(String, Option<String>, f64)> {
+            conn.prepare(
+                "SELECT session_id, provider_id, cost FROM usage_entries ORDER BY session_id",
+            )
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<SqliteResult<_>>()
+            .unwrap()
+        };
+
+        let book = PriceBook::new(PricingConfig {
+            entries: vec![relay_price(1.0)],
+        });
+        sync_usage(
+            &mut conn,
+            SessionEngine::Claude,
+            paths,
+            OnConflict::KeepFirst,
+            &book,
+            &ingest,
+        )
+        .unwrap();
+        assert_eq!(
+            costs(&conn),
+            vec![
+                ("s1".to_string(), Some("relay".to_string()), 1.0),
+                ("s2".to_string(), None, 3.0),
+            ]
+        );
+
+        // A changed relay price applies to the rows already stored
+        let book = PriceBook::new(PricingConfig {
+            entries: vec![relay_price(0.5)],
+        });
+        assert_eq!(reprice_usage(&mut conn, &book).unwrap(), 1);
+        assert_eq!(costs(&conn)[0].2, 0.5);
+        assert_eq!(costs(&conn)[1].2, 3.0);
+    }
+}
@@ -0,0 +1,587 @@
+//! Usage report export (CSV / JSON)
+//!
+//! Combines the ingested usage of every engine for a date range into one
+//! report: a row per session plus totals, and in JSON also breakdowns by
+//! engine, project, model and month, and by month crossed with project and
+//! with model. The `monthly_csv` format exports those cross-tabulations as
+//! rows instead of the sessions. Rows come from `usage_entries` with the
+//! same local-date bounds as `get_usage_by_date_range`, so the Claude totals
+//! of a report match the usage dashboard for the same range.
+
+use chrono::{Local, SecondsFormat};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+use super::codex::usage::sync_codex_usage;
+use super::gemini::usage::{gemini_project_name, sync_gemini_usage};
+use super::session_timeline::SessionEngine;
+use super::usage::{parse_range_date, sync_claude_usage};
+use super::usage_ingest::{
+    project_name, query_usage, with_usage_db, UsageAggregate, UsageGroup, UsageRange,
+};
+
+// ============================================================================
+// Types
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Csv,
+    Json,
+    /// Usage per month and project and per month and model
+    #[serde(rename = "monthly_csv")]
+    MonthlyCsv,
+}
+
+impl ReportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Csv | ReportFormat::MonthlyCsv => "csv",
+            ReportFormat::Json => "json",
+        }
+    }
+}
+
+/// Usage of one session within the report range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageReportSession {
+    pub engine: SessionEngine,
+    pub session_id: String,
+    pub project_path: String,
+    pub project_name: String,
+    pub models: Vec<String>,
+    pub first_used: String,
+    pub last_used: String,
+    pub entry_count: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub total_tokens: u64,
+    pub total_cost: f64,
+}
+
+/// Summed usage for one key of a breakdown
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageReportTotal {
+    pub key: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub total_tokens: u64,
+    pub total_cost: f64,
+    pub session_count: u64,
+}
+
+impl UsageReportTotal {
+    fn add(&mut self, agg: &UsageAggregate) {
+        self.input_tokens += agg.input_tokens;
+        self.output_tokens += agg.output_tokens;
+        self.cache_creation_tokens += agg.cache_creation_tokens;
+        self.cache_read_tokens += agg.cache_read_tokens;
+        self.total_tokens += agg.total_tokens;
+        self.total_cost += agg.cost;
+        self.session_count += agg.session_count;
+    }
+}
+
+/// Summed usage for one month and project or model
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageReportMonthTotal {
+    pub month: String,
+    #[serde(flatten)]
+    pub total: UsageReportTotal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageReport {
+    pub start_date: String,
+    pub end_date: String,
+    pub generated_at: String,
+    pub engines: Vec<SessionEngine>,
+    pub totals: UsageReportTotal,
+    pub by_engine: Vec<UsageReportTotal>,
+    pub by_project: Vec<UsageReportTotal>,
+    pub by_model: Vec<UsageReportTotal>,
+    pub by_month: Vec<UsageReportTotal>,
+    pub by_month_project: Vec<UsageReportMonthTotal>,
+    pub by_month_model: Vec<UsageReportMonthTotal>,
+    pub sessions: Vec<UsageReportSession>,
+}
+
+/// Result returned to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageReportResult {
+    pub format: ReportFormat,
+    /// Suggested file name (`usage-<start>-<end>.<ext>`)
+    pub file_name: String,
+    /// Rendered report; empty when written to `output_path`
+    pub content: String,
+    /// Where the report was written, if requested
+    pub output_path: Option<String>,
+}
+
+// ============================================================================
+// Report Building
+// ============================================================================
+
+fn display_project_name(engine: SessionEngine, project_path: &str) -> String {
+    match engine {
+        SessionEngine::Gemini => gemini_project_name(project_path),
+        _ => project_name(project_path),
+    }
+}
+
+/// Merges per-engine aggregates into totals keyed by `key_of`, sorted by cost
+fn merge_totals(
+    groups: &[(SessionEngine, Vec<UsageAggregate>)],
+    key_of: impl Fn(SessionEngine, &UsageAggregate) -> String,
+) -> Vec<UsageReportTotal> {
+    let mut merged: BTreeMap<String, UsageReportTotal> = BTreeMap::new();
+    for (engine, aggs) in groups {
+        for agg in aggs {
+            let key = key_of(*engine, agg);
+            merged
+                .entry(key.clone())
+                .or_insert_with(|| UsageReportTotal {
+                    key,
+                    ..Default::default()
+                })
+                .add(agg);
+        }
+    }
+    let mut totals: Vec<UsageReportTotal> = merged.into_values().collect();
+    totals.sort_by(|a, b| b.total_cost.total_cmp(&a.total_cost));
+    totals
+}
+
+/// Splits `YYYY-MM <key>` totals into months, sorted by month and then cost
+fn month_totals(groups: &[(SessionEngine, Vec<UsageAggregate>)]) -> Vec<UsageReportMonthTotal> {
+    let mut totals: Vec<UsageReportMonthTotal> = merge_totals(groups, |_, agg| agg.key.clone())
+        .into_iter()
+        .map(|mut total| {
+            let (month, key) = total.key.split_once(' ').unwrap_or((&total.key, ""));
+            let month = month.to_string();
+            total.key = key.to_string();
+            UsageReportMonthTotal { month, total }
+        })
+        .collect();
+    totals.sort_by(|a, b| a.month.cmp(&b.month));
+    totals
+}
+
+/// Builds the report from already ingested usage
+pub fn build_usage_report(
+    conn: &Connection,
+    engines: &[SessionEngine],
+    start_date: &str,
+    end_date: &str,
+) -> Result<UsageReport, String> {
+    let start = parse_range_date(start_date, "start")?;
+    let end = parse_range_date(end_date, "end")?;
+    let range = UsageRange::local_dates(Some(start), Some(end));
+
+    let query_all =
+        |group: UsageGroup| -> Result<Vec<(SessionEngine, Vec<UsageAggregate>)>, String> {
+            engines
+                .iter()
+                .map(|engine| Ok((*engine, query_usage(conn, *engine, &range, group)?)))
+                .collect()
+        };
+
+    let engine_totals = query_all(UsageGroup::Total)?;
+    let by_engine = merge_totals(&engine_totals, |engine, _| engine.as_str().to_string());
+    let totals = by_engine.iter().fold(
+        UsageReportTotal {
+            key: "total".to_string(),
+            ..Default::default()
+        },
+        |mut acc, t| {
+            acc.input_tokens += t.input_tokens;
+            acc.output_tokens += t.output_tokens;
+            acc.cache_creation_tokens += t.cache_creation_tokens;
+            acc.cache_read_tokens += t.cache_read_tokens;
+            acc.total_tokens += t.total_tokens;
+            acc.total_cost += t.total_cost;
+            acc.session_count += t.session_count;
+            acc
+        },
+    );
+
+    let mut sessions: Vec<UsageReportSession> = query_all(UsageGroup::Session)?
+        .into_iter()
+        .flat_map(|(engine, aggs)| {
+            aggs.into_iter().map(move |agg| UsageReportSession {
+                engine,
+                project_name: display_project_name(engine, &agg.project_path),
+                session_id: agg.key,
+                project_path: agg.project_path,
+                models: agg.models,
+                first_used: agg.first_used,
+                last_used: agg.last_used,
+                entry_count: agg.entry_count,
+                input_tokens: agg.input_tokens,
+                output_tokens: agg.output_tokens,
+                cache_creation_tokens: agg.cache_creation_tokens,
+                cache_read_tokens: agg.cache_read_tokens,
+                total_tokens: agg.total_tokens,
+                total_cost: agg.cost,
+            })
+        })
+        .collect();
+    sessions.sort_by(|a, b| a.first_used.cmp(&b.first_used));
+
+    let mut by_month = merge_totals(&query_all(UsageGroup::Month)?, |_, agg| agg.key.clone());
+    by_month.sort_by(|a, b| a.key.cmp(&b.key));
+
+    Ok(UsageReport {
+        start_date: start.to_string(),
+        end_date: end.to_string(),
+        generated_at: Local::now().to_rfc3339_opts(SecondsFormat::Secs, false),
+        engines: engines.to_vec(),
+        totals,
+        by_engine,
+        by_project: merge_totals(&query_all(UsageGroup::Project)?, |_, agg| agg.key.clone()),
+        by_model: merge_totals(&query_all(UsageGroup::Model)?, |_, agg| agg.key.clone()),
+        by_month,
+        by_month_project: month_totals(&query_all(UsageGroup::MonthProject)?),
+        by_month_model: month_totals(&query_all(UsageGroup::MonthModel)?),
+        sessions,
+    })
+}
+
+// ============================================================================
+// CSV
+// ============================================================================
+
+const CSV_HEADER: &[&str] = &[
+    "engine",
+    "session_id",
+    "project_path",
+    "project_name",
+    "models",
+    "first_used",
+    "last_used",
+    "entries",
+    "input_tokens",
+    "output_tokens",
+    "cache_creation_tokens",
+    "cache_read_tokens",
+    "total_tokens",
+    "cost_usd",
+];
+
+/// Quotes a field when it contains a delimiter, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_line(fields: &[String]) -> String {
+    let mut line = fields
+        .iter()
+        .map(|f| csv_field(f))
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push_str("\r\n");
+    line
+}
+
+/// One row per session followed by a `TOTAL` row
+pub fn render_csv(report: &UsageReport) -> String {
+    let mut out = csv_line(&CSV_HEADER.iter().map(|h| h.to_string()).collect::<Vec<_>>());
+    for s in &report.sessions {
+        out.push_str(&csv_line(&[
+            s.engine.as_str().to_string(),
+            s.session_id.clone(),
+            s.project_path.clone(),
+            s.project_name.clone(),
+            s.models.join(";"),
+            s.first_used.clone(),
+            s.last_used.clone(),
+            s.entry_count.to_string(),
+            s.input_tokens.to_string(),
+            s.output_tokens.to_string(),
+            s.cache_creation_tokens.to_string(),
+            s.cache_read_tokens.to_string(),
+            s.total_tokens.to_string(),
+            format!("{:.6}", s.total_cost),
+        ]));
+    }
+    let t = &report.totals;
+    out.push_str(&csv_line(&[
+        "TOTAL".to_string(),
+        String::new(),
+        String::new(),
+        String::new(),
+        String::new(),
+        report.start_date.clone(),
+        report.end_date.clone(),
+        report
+            .sessions
+            .iter()
+            .map(|s| s.entry_count)
+            .sum::<u64>()
+            .to_string(),
+        t.input_tokens.to_string(),
+        t.output_tokens.to_string(),
+        t.cache_creation_tokens.to_string(),
+        t.cache_read_tokens.to_string(),
+        t.total_tokens.to_string(),
+        format!("{:.6}", t.total_cost),
+    ]));
+    out
+}
+
+const MONTHLY_CSV_HEADER: &[&str] = &[
+    "month",
+    "dimension",
+    "key",
+    "sessions",
+    "input_tokens",
+    "output_tokens",
+    "cache_creation_tokens",
+    "cache_read_tokens",
+    "total_tokens",
+    "cost_usd",
+];
+
+/// One row per month and project, then one per month and model
+pub fn render_monthly_csv(report: &UsageReport) -> String {
+    let mut out = csv_line(
+        &MONTHLY_CSV_HEADER
+            .iter()
+            .map(|h| h.to_string())
+            .collect::<Vec<_>>(),
+    );
+    let sections = [
+        ("project", &report.by_month_project),
+        ("model", &report.by_month_model),
+    ];
+    for (dimension, rows) in sections {
+        for row in rows {
+            let t = &row.total;
+            out.push_str(&csv_line(&[
+                row.month.clone(),
+                dimension.to_string(),
+                t.key.clone(),
+                t.session_count.to_string(),
+                t.input_tokens.to_string(),
+                t.output_tokens.to_string(),
+                t.cache_creation_tokens.to_string(),
+                t.cache_read_tokens.to_string(),
+                t.total_tokens.to_string(),
+                format!("{:.6}", t.total_cost),
+            ]));
+        }
+    }
+    out
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Exports the usage of all (or the given) engines between two local dates.
+///
+/// Usage is ingested first, like the dashboards do. When `output_path` is
+/// given the report is written there, otherwise its content is returned.
+#[tauri::command]
+pub async fn export_usage_report(
+    app: AppHandle,
+    start_date: String,
+    end_date: String,
+    format: ReportFormat,
+    engines: Option<Vec<SessionEngine>>,
+    output_path: Option<String>,
+) -> Result<UsageReportResult, String> {
+    let engines = engines
+        .filter(|e| !e.is_empty())
+        .unwrap_or_else(|| SessionEngine::ALL.to_vec());
+    log::info!(
+        "[UsageReport] Exporting {:?} usage {} - {} as {:?}",
+        engines,
+        start_date,
+        end_date,
+        format
+    );
+
+    let report = with_usage_db(app, move |conn| {
+        for engine in &engines {
+            match engine {
+                SessionEngine::Claude => sync_claude_usage(conn)?,
+                SessionEngine::Codex => sync_codex_usage(conn)?,
+                SessionEngine::Gemini => sync_gemini_usage(conn)?,
+            };
+        }
+        build_usage_report(conn, &engines, &start_date, &end_date)
+    })
+    .await?;
+
+    let file_name = format!(
+        "usage-{}-{}.{}",
+        report.start_date,
+        report.end_date,
+        format.extension()
+    );
+    let content = match format {
+        ReportFormat::Csv => render_csv(&report),
+        ReportFormat::MonthlyCsv => render_monthly_csv(&report),
+        ReportFormat::Json => serde_json::to_string_pretty(&report)
+            .map_err(|e| format!("Failed to serialize usage report: {}", e))?,
+    };
+
+    match output_path {
+        Some(path) => {
+            if let Some(parent) = Path::new(&path).parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create export directory: {}", e))?;
+            }
+            fs::write(&path, &content)
+                .map_err(|e| format!("Failed to write usage report: {}", e))?;
+            log::info!("[UsageReport] Report written to {}", path);
+            Ok(UsageReportResult {
+                format,
+                file_name,
+                content: String::new(),
+                output_path: Some(path),
+            })
+        }
+        None => Ok(UsageReportResult {
+            format,
+            file_name,
+            content,
+            output_path: None,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::usage_ingest::{init_usage_ingest_tables, query_usage_total};
+    use rusqlite::params;
+
+    #[test]
+    fn report_totals_match_per_engine_queries() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE usage_entries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                model TEXT NOT NULL,
+                input_tokens INTEGER DEFAULT 0,
+                output_tokens INTEGER DEFAULT 0,
+                cache_creation_tokens INTEGER DEFAULT 0,
+                cache_read_tokens INTEGER DEFAULT 0,
+                total_tokens INTEGER DEFAULT 0,
+                cost REAL DEFAULT 0.0,
+                project_path TEXT,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )",
+            [],
+        )
+        .unwrap();
+        init_usage_ingest_tables(&conn).unwrap();
+
+        let rows = [
+            (
+                "claude",
+                "c1",
+                "2025-03-10T12:00:00.000Z",
+                "opus",
+                100,
+                1.5,
+                "/work/a,b",
+            ),
+            (
+                "claude",
+                "c1",
+                "2025-03-11T12:00:00.000Z",
+                "sonnet",
+                50,
+                0.5,
+                "/work/a,b",
+            ),
+            (
+                "codex",
+                "x1",
+                "2025-03-12T12:00:00.000Z",
+                "gpt-5.3",
+                30,
+                0.25,
+                "/work/c",
+            ),
+            // Outside the range
+            (
+                "codex",
+                "x2",
+                "2025-05-01T12:00:00.000Z",
+                "gpt-5.3",
+                99,
+                9.0,
+                "/work/c",
+            ),
+        ];
+        for (engine, session, ts, model, tokens, cost, project) in rows {
+            conn.execute(
+                "INSERT INTO usage_entries (engine, session_id, timestamp, model,
+                    input_tokens, total_tokens, cost, project_path)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?5, ?6, ?7)",
+                params![engine, session, ts, model, tokens, cost, project],
+            )
+            .unwrap();
+        }
+
+        let report =
+            build_usage_report(&conn, &SessionEngine::ALL, "2025-03-01", "2025-03-31").unwrap();
+
+        let range = UsageRange::local_dates(
+            Some(parse_range_date("2025-03-01", "start").unwrap()),
+            Some(parse_range_date("2025-03-31", "end").unwrap()),
+        );
+        let claude = query_usage_total(&conn, SessionEngine::Claude, &range).unwrap();
+        assert_eq!(report.by_engine[0].key, "claude");
+        assert_eq!(report.by_engine[0].total_cost, claude.cost);
+        assert_eq!(report.totals.total_tokens, 180);
+        assert_eq!(report.totals.session_count, 2);
+        assert_eq!(report.sessions.len(), 2);
+        assert_eq!(report.sessions[0].models, vec!["opus", "sonnet"]);
+
+        let csv = render_csv(&report);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].starts_with("claude,c1,\"/work/a,b\",\"a,b\",opus;sonnet,"));
+        assert!(lines[3].starts_with("TOTAL,,,,,2025-03-01,2025-03-31,3,180,"));
+        assert!(lines[3].ends_with(",2.250000"));
+
+        let months: Vec<(&str, &str, f64)> = report
+            .by_month_project
+            .iter()
+            .map(|m| (m.month.as_str(), m.total.key.as_str(), m.total.total_cost))
+            .collect();
+        assert_eq!(
+            months,
+            vec![("2025-03", "/work/a,b", 2.0), ("2025-03", "/work/c", 0.25)]
+        );
+        assert_eq!(report.by_month_model.len(), 3);
+        assert_eq!(report.by_month_model[0].total.key, "opus");
+
+        let monthly = render_monthly_csv(&report);
+        let lines: Vec<&str> = monthly.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert!(lines[1].starts_with("2025-03,project,\"/work/a,b\",1,150,"));
+        assert!(lines[5].starts_with("2025-03,model,gpt-5.3,1,30,"));
+    }
+}
@@ -17,8 +17,18 @@ use claude_binary::init_shell_environment;
 use std::sync::{Arc, Mutex};
 
 use commands::acemcp::{
-    enhance_prompt_with_context, export_acemcp_sidecar, get_extracted_sidecar_path,
-    load_acemcp_config, preindex_project, save_acemcp_config, test_acemcp_availability,
+    cancel_prompt_enhancement, enhance_prompt_with_context, export_acemcp_sidecar,
+    format_enhancement_context, get_extracted_sidecar_path, load_acemcp_config, preindex_project,
+    save_acemcp_config, test_acemcp_availability,
+};
+use commands::budget::{get_budget_config, get_budget_status, save_budget_config, BudgetState};
+use commands::context_windows::{
+    get_builtin_context_windows, get_context_window_config, resolve_context_window,
+    save_context_window_config,
+};
+use commands::pricing::{
+    get_builtin_pricing, get_pricing_config, reprice_usage_entries, resolve_model_pricing,
+    save_pricing_config,
 };
 use commands::claude::{
     cancel_claude_execution, check_claude_version, clear_custom_claude_path, continue_claude_code,
@@ -27,14 +37,14 @@ use commands::claude::{
     get_claude_path, get_claude_permission_config, get_claude_session_output, get_claude_settings,
     get_codex_system_prompt, get_hooks_config, get_permission_presets, get_project_sessions,
     get_system_prompt, list_directory_contents, list_hidden_projects, list_projects,
-    list_running_claude_sessions, load_session_history, open_new_session, read_claude_md_file,
-    reset_claude_execution_config, restore_project, resume_claude_code, save_claude_md_file,
-    save_claude_settings, save_codex_system_prompt, save_system_prompt, search_files,
+    list_running_claude_runs, list_running_claude_sessions, load_session_history,
+    open_new_session, read_claude_md_file, reset_claude_execution_config, restore_project,
+    resume_claude_code, save_claude_md_file, save_claude_settings, save_codex_system_prompt,
+    save_system_prompt, search_files,
     set_custom_claude_path, update_claude_execution_config, update_claude_permission_config,
     update_hooks_config, update_thinking_mode, validate_hook_command, validate_permission_config,
     // Claude WSL mode configuration
     get_claude_wsl_mode_config, set_claude_wsl_mode_config,
-    ClaudeProcessState,
 };
 use commands::mcp::{
     mcp_add, mcp_add_from_claude_desktop, mcp_add_json, mcp_export_config, mcp_get,
@@ -60,7 +70,13 @@ use commands::provider::{
     get_current_provider_config, get_provider_config, get_provider_presets, query_provider_usage,
     reorder_provider_configs, switch_provider_config, test_provider_connection, update_provider_config,
 };
-use commands::simple_git::{check_and_init_git, check_reset_safety, precise_revert_code};
+use commands::checkpoint::prune_checkpoints;
+use commands::selective_rewind::{get_prompt_file_changes, revert_prompt_files};
+use commands::session_worktree::{
+    cleanup_session_worktrees, get_session_worktree_diff, get_worktree_config,
+    list_session_worktrees, merge_session_worktree, remove_session_worktree, save_worktree_config,
+};
+use commands::simple_git::{check_reset_safety, precise_revert_code};
 use commands::storage::{
     storage_analyze_query, storage_delete_row, storage_execute_sql, storage_get_performance_stats,
     storage_insert_row, storage_list_tables, storage_read_table, storage_reset_database,
@@ -72,6 +88,7 @@ use commands::translator::{
     update_translation_config,
 };
 use commands::usage::{get_session_stats, get_usage_by_date_range, get_usage_stats};
+use commands::usage_report::export_usage_report;
 use commands::window::{
     broadcast_to_session_windows, close_session_window, create_session_window, emit_to_window,
     focus_session_window, list_session_windows, set_titlebar_theme,
@@ -121,8 +138,12 @@ use commands::codex::{
     CodexProcessState,
 };
 use commands::enhanced_hooks::{
-    execute_pre_commit_review, test_hook_condition, trigger_hook_event,
+    clear_hook_execution_log, execute_pre_commit_review, get_hook_execution_log,
+    get_pre_commit_hook_status, get_project_enhanced_hooks, install_pre_commit_hook,
+    save_project_enhanced_hooks, test_hook_condition, trigger_hook_event,
+    uninstall_pre_commit_hook, HookManager,
 };
+use commands::hook_watcher::{sync_watchers, FileWatcherState};
 use commands::extensions::{
     create_skill, create_subagent, list_agent_skills, list_custom_slash_commands,
     list_gemini_custom_slash_commands, list_plugins, list_subagents, open_agents_directory,
@@ -136,6 +157,11 @@ use commands::gemini::{
     check_gemini_installed,
     check_gemini_rewind_capabilities,
     clear_gemini_provider_config,
+    // Session conversion
+    convert_claude_to_gemini,
+    convert_codex_to_gemini,
+    convert_gemini_to_claude,
+    convert_gemini_to_codex,
     delete_gemini_provider_config,
     delete_gemini_session,
     execute_gemini,
@@ -167,6 +193,12 @@ use commands::gemini::{
     GeminiProcessState,
 };
 use commands::git_stats::{get_git_diff_stats, get_session_code_changes};
+use commands::session_export::export_session;
+use commands::session_search::{
+    clear_session_search_index, get_session_search_status, refresh_session_search_index,
+    search_sessions,
+};
+use commands::session_timeline::{list_unified_sessions, load_unified_session};
 use process::ProcessRegistryState;
 use tauri::{Manager, WindowEvent};
 use tauri_plugin_window_state::Builder as WindowStatePlugin;
@@ -175,6 +207,11 @@ fn main() {
     // Initialize logger
     env_logger::init();
 
+    // Invoked by an installed `.git/hooks/pre-commit` script: review and exit without a window
+    if let Some(code) = commands::pre_commit_review::run_from_cli_args() {
+        std::process::exit(code);
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
@@ -201,15 +238,20 @@ fn main() {
             // Initialize process registry
             app.manage(ProcessRegistryState::default());
 
-            // Initialize Claude process state
-            app.manage(ClaudeProcessState::default());
-
             // Initialize Codex process state
             app.manage(CodexProcessState::default());
 
             // Initialize Gemini process state
             app.manage(GeminiProcessState::default());
 
+            // Initialize budget alert tracking
+            app.manage(BudgetState::default());
+
+            // Load persisted enhanced hooks and start file watchers for OnFileChange
+            app.manage(HookManager::new(app.handle().clone()));
+            app.manage(FileWatcherState::default());
+            sync_watchers(app.handle());
+
             // Initialize auto-compact manager for context management
             let auto_compact_manager =
                 Arc::new(commands::context_manager::AutoCompactManager::new());
@@ -315,6 +357,7 @@ fn main() {
             resume_claude_code,
             cancel_claude_execution,
             list_running_claude_sessions,
+            list_running_claude_runs,
             get_claude_session_output,
             list_directory_contents,
             search_files,
@@ -338,6 +381,8 @@ fn main() {
             set_claude_wsl_mode_config,
             // Acemcp Integration
             enhance_prompt_with_context,
+            format_enhancement_context,
+            cancel_prompt_enhancement,
             test_acemcp_availability,
             save_acemcp_config,
             load_acemcp_config,
@@ -348,10 +393,33 @@ fn main() {
             trigger_hook_event,
             test_hook_condition,
             execute_pre_commit_review,
+            install_pre_commit_hook,
+            uninstall_pre_commit_hook,
+            get_pre_commit_hook_status,
+            get_project_enhanced_hooks,
+            save_project_enhanced_hooks,
+            get_hook_execution_log,
+            clear_hook_execution_log,
             // Usage & Analytics (Simplified from opcode)
             get_usage_stats,
             get_usage_by_date_range,
             get_session_stats,
+            export_usage_report,
+            // Budget limits
+            get_budget_config,
+            save_budget_config,
+            get_budget_status,
+            // Model pricing
+            get_pricing_config,
+            save_pricing_config,
+            reprice_usage_entries,
+            get_builtin_pricing,
+            resolve_model_pricing,
+            // Model context windows
+            get_context_window_config,
+            save_context_window_config,
+            get_builtin_context_windows,
+            resolve_context_window,
             // MCP (Model Context Protocol)
             mcp_add,
             mcp_list,
@@ -430,8 +498,8 @@ fn main() {
             commands::context_commands::stop_auto_compact_monitoring,
             commands::context_commands::start_auto_compact_monitoring,
             commands::context_commands::get_auto_compact_status,
+            commands::context_commands::get_compaction_history,
             // Prompt Revert System
-            check_and_init_git,
             check_reset_safety,
             precise_revert_code,
             record_prompt_sent,
@@ -440,6 +508,18 @@ fn main() {
             get_prompt_list,
             get_unified_prompt_list,
             check_rewind_capabilities,
+            // Selective Rewind
+            get_prompt_file_changes,
+            revert_prompt_files,
+            prune_checkpoints,
+            // Session Worktrees
+            get_worktree_config,
+            save_worktree_config,
+            list_session_worktrees,
+            get_session_worktree_diff,
+            merge_session_worktree,
+            remove_session_worktree,
+            cleanup_session_worktrees,
             // Claude Extensions (Plugins, Subagents, Skills & Custom Commands)
             list_plugins,
             toggle_plugin_enabled,
@@ -501,10 +581,14 @@ fn main() {
             reorder_codex_provider_configs,
             // Codex Usage Statistics
             get_codex_usage_stats,
-            // Session Conversion (Claude ↔ Codex)
+            // Session Conversion (Claude ↔ Codex ↔ Gemini)
             convert_session,
             convert_claude_to_codex,
             convert_codex_to_claude,
+            convert_gemini_to_claude,
+            convert_gemini_to_codex,
+            convert_claude_to_gemini,
+            convert_codex_to_gemini,
             // Window Management (Multi-window support)
             create_session_window,
             close_session_window,
@@ -549,6 +633,16 @@ fn main() {
             set_gemini_wsl_mode_config,
             // Gemini Usage Statistics
             get_gemini_usage_stats,
+            // Unified Session Timeline (Claude / Codex / Gemini)
+            load_unified_session,
+            list_unified_sessions,
+            // Session Search (FTS5 over all engines)
+            refresh_session_search_index,
+            search_sessions,
+            get_session_search_status,
+            clear_session_search_index,
+            // Session Export (Markdown / HTML / JSON bundle)
+            export_session,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
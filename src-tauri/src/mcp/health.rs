@@ -0,0 +1,1054 @@
+//! MCP 服务器健康检查模块
+//!
+//! 根据服务器规范（与注册表 / 引擎配置中的 spec 结构一致）真正建立连接，
+//! 执行 JSON-RPC `initialize` 握手，并枚举服务器提供的 tools / resources / prompts。
+//!
+//! ## 支持的传输类型
+//! - `stdio`: 启动子进程，通过换行分隔的 JSON 与其通信
+//! - `http`: Streamable HTTP，每个请求一次 POST，响应可以是 JSON 或 SSE
+//! - `sse`: 旧版 HTTP+SSE，先 GET 事件流获取 `endpoint`，再 POST 消息
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout};
+
+/// 握手时声明的协议版本（服务器可协商为其它版本）
+const CLIENT_PROTOCOL_VERSION: &str = "2025-03-26";
+
+/// 列表接口最多翻页次数，避免异常服务器无限返回 nextCursor
+const MAX_LIST_PAGES: usize = 20;
+
+/// 保留的 stderr 末尾字节数
+const STDERR_TAIL_BYTES: usize = 4096;
+
+/// 默认单步超时（秒）
+pub const DEFAULT_TIMEOUT_SECS: u64 = 15;
+
+/// 工具信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpToolInfo {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_schema: Option<Value>,
+}
+
+/// 资源信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpResourceInfo {
+    pub uri: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+/// 提示词模板信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpPromptInfo {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub arguments: Vec<String>,
+}
+
+/// 健康检查报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpHealthReport {
+    /// 服务器 ID
+    pub server_id: String,
+    /// 传输类型：stdio / http / sse
+    pub transport: String,
+    /// 握手是否成功
+    pub success: bool,
+    /// initialize 请求往返耗时（毫秒）
+    pub latency_ms: Option<u64>,
+    /// 整个检查的耗时（毫秒）
+    pub total_ms: u64,
+    /// 服务器协商的协议版本
+    pub protocol_version: Option<String>,
+    /// 服务器名称（serverInfo.name）
+    pub server_name: Option<String>,
+    /// 服务器版本（serverInfo.version）
+    pub server_version: Option<String>,
+    /// 服务器声明的能力
+    pub capabilities: Option<Value>,
+    pub tools: Vec<McpToolInfo>,
+    pub resources: Vec<McpResourceInfo>,
+    pub prompts: Vec<McpPromptInfo>,
+    /// 握手失败原因
+    pub error: Option<String>,
+    /// 非致命问题（例如某个 list 接口失败）
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    /// stdio 服务器 stderr 输出末尾（便于排查启动失败）
+    pub stderr: Option<String>,
+    /// 检查时间（Unix 秒）
+    pub checked_at: u64,
+}
+
+impl McpHealthReport {
+    fn new(server_id: &str, transport: &str) -> Self {
+        Self {
+            server_id: server_id.to_string(),
+            transport: transport.to_string(),
+            success: false,
+            latency_ms: None,
+            total_ms: 0,
+            protocol_version: None,
+            server_name: None,
+            server_version: None,
+            capabilities: None,
+            tools: Vec::new(),
+            resources: Vec::new(),
+            prompts: Vec::new(),
+            error: None,
+            warnings: Vec::new(),
+            stderr: None,
+            checked_at: chrono::Utc::now().timestamp() as u64,
+        }
+    }
+}
+
+/// 从服务器规范中解析出的连接参数
+#[derive(Debug, Clone, PartialEq)]
+enum TransportSpec {
+    Stdio {
+        command: String,
+        args: Vec<String>,
+        env: HashMap<String, String>,
+        cwd: Option<String>,
+    },
+    Http {
+        url: String,
+        headers: HashMap<String, String>,
+    },
+    Sse {
+        url: String,
+        headers: HashMap<String, String>,
+    },
+}
+
+impl TransportSpec {
+    fn kind(&self) -> &'static str {
+        match self {
+            TransportSpec::Stdio { .. } => "stdio",
+            TransportSpec::Http { .. } => "http",
+            TransportSpec::Sse { .. } => "sse",
+        }
+    }
+}
+
+fn string_map(value: Option<&Value>) -> HashMap<String, String> {
+    value
+        .and_then(|v| v.as_object())
+        .map(|obj| {
+            obj.iter()
+                .filter_map(|(k, v)| v.as_str().map(|s| (k.clone(), s.to_string())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 解析服务器规范
+///
+/// 兼容 Gemini 原始格式（`httpUrl`）以及 `server` 包装字段
+fn parse_transport(spec: &Value) -> Result<TransportSpec, String> {
+    let spec = spec.get("server").unwrap_or(spec);
+    let obj = spec
+        .as_object()
+        .ok_or_else(|| "MCP 服务器定义必须为 JSON 对象".to_string())?;
+
+    let http_url = obj.get("httpUrl").and_then(|v| v.as_str());
+    let url = obj.get("url").and_then(|v| v.as_str());
+    let typ = obj
+        .get("type")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| {
+            if http_url.is_some() {
+                "http".to_string()
+            } else if url.is_some() && !obj.contains_key("command") {
+                "sse".to_string()
+            } else {
+                "stdio".to_string()
+            }
+        });
+
+    match typ.as_str() {
+        "stdio" => {
+            let command = obj
+                .get("command")
+                .and_then(|v| v.as_str())
+                .filter(|s| !s.trim().is_empty())
+                .ok_or_else(|| "stdio 类型的 MCP 服务器缺少 command 字段".to_string())?;
+            let args = obj
+                .get("args")
+                .and_then(|v| v.as_array())
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|x| x.as_str().map(|s| s.to_string()))
+                        .collect()
+                })
+                .unwrap_or_default();
+            let cwd = obj
+                .get("cwd")
+                .and_then(|v| v.as_str())
+                .filter(|s| !s.trim().is_empty())
+                .map(|s| s.to_string());
+            Ok(TransportSpec::Stdio {
+                command: command.to_string(),
+                args,
+                env: string_map(obj.get("env")),
+                cwd,
+            })
+        }
+        "http" | "sse" => {
+            let url = http_url
+                .or(url)
+                .filter(|s| !s.trim().is_empty())
+                .ok_or_else(|| format!("{} 类型的 MCP 服务器缺少 url 字段", typ))?
+                .to_string();
+            let headers = string_map(obj.get("headers"));
+            if typ == "http" {
+                Ok(TransportSpec::Http { url, headers })
+            } else {
+                Ok(TransportSpec::Sse { url, headers })
+            }
+        }
+        other => Err(format!(
+            "传输类型必须是 'stdio'、'http' 或 'sse'，当前为 '{}'",
+            other
+        )),
+    }
+}
+
+// ============================================================================
+// SSE 解析
+// ============================================================================
+
+/// 单个 SSE 事件
+#[derive(Debug, Clone, PartialEq)]
+struct SseEvent {
+    event: String,
+    data: String,
+}
+
+/// 增量 SSE 解析器（按空行切分事件）
+///
+/// 以字节缓冲，避免多字节字符被 chunk 边界截断
+#[derive(Debug, Default)]
+struct SseParser {
+    buffer: Vec<u8>,
+}
+
+impl SseParser {
+    fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend(chunk.iter().filter(|b| **b != b'\r'));
+
+        let mut events = Vec::new();
+        while let Some(pos) = self.buffer.windows(2).position(|w| w == b"\n\n") {
+            let block: Vec<u8> = self.buffer.drain(..pos + 2).collect();
+            let block = String::from_utf8_lossy(&block);
+            let mut event = String::from("message");
+            let mut data_lines = Vec::new();
+
+            for line in block.lines() {
+                if line.starts_with(':') {
+                    continue;
+                }
+                let (field, value) = match line.split_once(':') {
+                    Some((f, v)) => (f, v.strip_prefix(' ').unwrap_or(v)),
+                    None => (line, ""),
+                };
+                match field {
+                    "event" => event = value.to_string(),
+                    "data" => data_lines.push(value.to_string()),
+                    _ => {}
+                }
+            }
+
+            if !data_lines.is_empty() {
+                events.push(SseEvent {
+                    event,
+                    data: data_lines.join("\n"),
+                });
+            }
+        }
+        events
+    }
+}
+
+/// 从 JSON-RPC 消息中取出结果（或错误）
+fn extract_rpc_result(message: &Value) -> Result<Value, String> {
+    if let Some(error) = message.get("error") {
+        let code = error.get("code").and_then(|c| c.as_i64()).unwrap_or(0);
+        let msg = error
+            .get("message")
+            .and_then(|m| m.as_str())
+            .unwrap_or("unknown error");
+        return Err(format!("MCP error {}: {}", code, msg));
+    }
+    message
+        .get("result")
+        .cloned()
+        .ok_or_else(|| "响应中缺少 result 字段".to_string())
+}
+
+/// 判断消息是否为指定请求的响应
+fn is_response_to(message: &Value, id: u64) -> bool {
+    message.get("id").and_then(|v| v.as_u64()) == Some(id)
+        && (message.get("result").is_some() || message.get("error").is_some())
+}
+
+// ============================================================================
+// 连接实现
+// ============================================================================
+
+struct StdioConnection {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    stderr_tail: Arc<Mutex<String>>,
+}
+
+struct HttpConnection {
+    client: reqwest::Client,
+    url: String,
+    headers: HashMap<String, String>,
+    session_id: Option<String>,
+}
+
+struct SseConnection {
+    client: reqwest::Client,
+    headers: HashMap<String, String>,
+    endpoint: reqwest::Url,
+    stream: reqwest::Response,
+    parser: SseParser,
+    pending: Vec<SseEvent>,
+}
+
+enum Connection {
+    Stdio(StdioConnection),
+    Http(HttpConnection),
+    Sse(SseConnection),
+}
+
+/// 已建立的 MCP 客户端会话（仅用于健康检查）
+struct HealthClient {
+    connection: Connection,
+    timeout: Duration,
+    request_id: u64,
+}
+
+fn build_http_client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))
+}
+
+fn apply_headers(
+    mut builder: reqwest::RequestBuilder,
+    headers: &HashMap<String, String>,
+) -> reqwest::RequestBuilder {
+    for (k, v) in headers {
+        builder = builder.header(k.as_str(), v.as_str());
+    }
+    builder
+}
+
+/// 在 PATH 中解析可执行文件（Windows 下补全 PATHEXT 扩展名）
+fn resolve_program(command: &str) -> String {
+    #[cfg(windows)]
+    {
+        if command.contains('/') || command.contains('\\') {
+            return command.to_string();
+        }
+        let exts: Vec<String> = std::env::var("PATHEXT")
+            .unwrap_or(".COM;.EXE;.BAT;.CMD".into())
+            .split(';')
+            .map(|s| s.trim().to_lowercase())
+            .collect();
+        let path_var = std::env::var_os("PATH").unwrap_or_default();
+        for dir in std::env::split_paths(&path_var) {
+            for ext in &exts {
+                let candidate = dir.join(format!("{}{}", command, ext));
+                if candidate.is_file() {
+                    return candidate.to_string_lossy().to_string();
+                }
+            }
+        }
+    }
+    command.to_string()
+}
+
+impl HealthClient {
+    async fn connect(spec: &TransportSpec, timeout: Duration) -> Result<Self, String> {
+        let connection = match spec {
+            TransportSpec::Stdio {
+                command,
+                args,
+                env,
+                cwd,
+            } => Connection::Stdio(Self::spawn_stdio(command, args, env, cwd.as_deref())?),
+            TransportSpec::Http { url, headers } => Connection::Http(HttpConnection {
+                client: build_http_client()?,
+                url: url.clone(),
+                headers: headers.clone(),
+                session_id: None,
+            }),
+            TransportSpec::Sse { url, headers } => {
+                let conn = tokio::time::timeout(timeout, Self::open_sse(url, headers))
+                    .await
+                    .map_err(|_| format!("连接 SSE 端点超时（{}s）", timeout.as_secs()))??;
+                Connection::Sse(conn)
+            }
+        };
+
+        Ok(Self {
+            connection,
+            timeout,
+            request_id: 0,
+        })
+    }
+
+    fn spawn_stdio(
+        command: &str,
+        args: &[String],
+        env: &HashMap<String, String>,
+        cwd: Option<&str>,
+    ) -> Result<StdioConnection, String> {
+        let program = resolve_program(command);
+        let mut std_cmd = crate::claude_binary::create_command_with_env(&program);
+        std_cmd.args(args);
+        std_cmd.envs(env);
+        if let Some(dir) = cwd {
+            std_cmd.current_dir(dir);
+        }
+
+        let mut cmd = tokio::process::Command::from(std_cmd);
+        cmd.stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| format!("启动 MCP 服务器失败 ({}): {}", command, e))?;
+
+        let stdin = child.stdin.take().ok_or("无法获取子进程 stdin")?;
+        let stdout = child.stdout.take().ok_or("无法获取子进程 stdout")?;
+        let stderr_tail = Arc::new(Mutex::new(String::new()));
+
+        if let Some(mut stderr) = child.stderr.take() {
+            let tail = stderr_tail.clone();
+            tokio::spawn(async move {
+                let mut buf = [0u8; 1024];
+                while let Ok(n) = stderr.read(&mut buf).await {
+                    if n == 0 {
+                        break;
+                    }
+                    if let Ok(mut t) = tail.lock() {
+                        t.push_str(&String::from_utf8_lossy(&buf[..n]));
+                        if t.len() > STDERR_TAIL_BYTES {
+                            let mut cut = t.len() - STDERR_TAIL_BYTES;
+                            while !t.is_char_boundary(cut) {
+                                cut += 1;
+                            }
+                            t.drain(..cut);
+                        }
+                    }
+                }
+            });
+        }
+
+        Ok(StdioConnection {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+            stderr_tail,
+        })
+    }
+
+    async fn open_sse(url: &str, headers: &HashMap<String, String>) -> Result<SseConnection, String> {
+        let client = build_http_client()?;
+        let base = reqwest::Url::parse(url).map_err(|e| format!("无效的 SSE 地址: {}", e))?;
+
+        let mut stream = apply_headers(client.get(base.clone()), headers)
+            .header("Accept", "text/event-stream")
+            .send()
+            .await
+            .map_err(|e| format!("连接 SSE 端点失败: {}", e))?;
+
+        if !stream.status().is_success() {
+            return Err(format!("SSE 端点返回 HTTP {}", stream.status()));
+        }
+
+        // 第一个 endpoint 事件给出后续 POST 消息的地址（通常为相对路径）
+        let mut parser = SseParser::default();
+        let mut pending = Vec::new();
+        let endpoint = loop {
+            let chunk = stream
+                .chunk()
+                .await
+                .map_err(|e| format!("读取 SSE 事件流失败: {}", e))?
+                .ok_or("SSE 事件流在收到 endpoint 事件前关闭")?;
+
+            let mut found = None;
+            for event in parser.push(&chunk) {
+                if found.is_none() && event.event == "endpoint" {
+                    found = Some(event.data);
+                } else {
+                    pending.push(event);
+                }
+            }
+            if let Some(data) = found {
+                break base
+                    .join(data.trim())
+                    .map_err(|e| format!("无效的 endpoint 地址 '{}': {}", data, e))?;
+            }
+        };
+
+        log::debug!("MCP SSE endpoint: {}", endpoint);
+
+        Ok(SseConnection {
+            client,
+            headers: headers.clone(),
+            endpoint,
+            stream,
+            parser,
+            pending,
+        })
+    }
+
+    /// 发送 JSON-RPC 请求并等待对应 id 的响应
+    async fn request(&mut self, method: &str, params: Value) -> Result<Value, String> {
+        self.request_id += 1;
+        let id = self.request_id;
+        let message = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+
+        let timeout = self.timeout;
+        let response = tokio::time::timeout(timeout, self.exchange(message, Some(id)))
+            .await
+            .map_err(|_| format!("{} 请求超时（{}s）", method, timeout.as_secs()))??;
+
+        extract_rpc_result(&response.unwrap_or(Value::Null))
+    }
+
+    /// 发送通知（不等待响应）
+    async fn notify(&mut self, method: &str) -> Result<(), String> {
+        let message = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+        });
+        let timeout = self.timeout;
+        tokio::time::timeout(timeout, self.exchange(message, None))
+            .await
+            .map_err(|_| format!("{} 通知超时（{}s）", method, timeout.as_secs()))??;
+        Ok(())
+    }
+
+    async fn exchange(&mut self, message: Value, id: Option<u64>) -> Result<Option<Value>, String> {
+        match &mut self.connection {
+            Connection::Stdio(conn) => Self::exchange_stdio(conn, &message, id).await,
+            Connection::Http(conn) => Self::exchange_http(conn, &message, id).await,
+            Connection::Sse(conn) => Self::exchange_sse(conn, &message, id).await,
+        }
+    }
+
+    async fn exchange_stdio(
+        conn: &mut StdioConnection,
+        message: &Value,
+        id: Option<u64>,
+    ) -> Result<Option<Value>, String> {
+        let line = serde_json::to_string(message).map_err(|e| e.to_string())?;
+        conn.stdin
+            .write_all(line.as_bytes())
+            .await
+            .map_err(|e| format!("写入服务器 stdin 失败: {}", e))?;
+        conn.stdin
+            .write_all(b"\n")
+            .await
+            .map_err(|e| format!("写入服务器 stdin 失败: {}", e))?;
+        conn.stdin
+            .flush()
+            .await
+            .map_err(|e| format!("写入服务器 stdin 失败: {}", e))?;
+
+        let Some(id) = id else {
+            return Ok(None);
+        };
+
+        let mut buf = String::new();
+        loop {
+            buf.clear();
+            let n = conn
+                .stdout
+                .read_line(&mut buf)
+                .await
+                .map_err(|e| format!("读取服务器输出失败: {}", e))?;
+            if n == 0 {
+                return Err("服务器进程已退出，未返回响应".to_string());
+            }
+
+            // 部分服务器会在 stdout 打印日志，非 JSON 行直接跳过
+            let Ok(value) = serde_json::from_str::<Value>(buf.trim()) else {
+                log::debug!("Skipping non JSON-RPC stdout line: {}", buf.trim());
+                continue;
+            };
+            if is_response_to(&value, id) {
+                return Ok(Some(value));
+            }
+        }
+    }
+
+    async fn exchange_http(
+        conn: &mut HttpConnection,
+        message: &Value,
+        id: Option<u64>,
+    ) -> Result<Option<Value>, String> {
+        let mut builder = apply_headers(conn.client.post(&conn.url), &conn.headers)
+            .header("Accept", "application/json, text/event-stream")
+            .json(message);
+        if let Some(session_id) = &conn.session_id {
+            builder = builder.header("Mcp-Session-Id", session_id.as_str());
+        }
+
+        let mut response = builder
+            .send()
+            .await
+            .map_err(|e| format!("HTTP 请求失败: {}", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("服务器返回 HTTP {}: {}", status, body.trim()));
+        }
+
+        if let Some(session_id) = response
+            .headers()
+            .get("mcp-session-id")
+            .and_then(|v| v.to_str().ok())
+        {
+            conn.session_id = Some(session_id.to_string());
+        }
+
+        let Some(id) = id else {
+            return Ok(None);
+        };
+
+        let is_event_stream = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|ct| ct.starts_with("text/event-stream"))
+            .unwrap_or(false);
+
+        if is_event_stream {
+            let mut parser = SseParser::default();
+            while let Some(chunk) = response
+                .chunk()
+                .await
+                .map_err(|e| format!("读取 SSE 响应失败: {}", e))?
+            {
+                for event in parser.push(&chunk) {
+                    if let Ok(value) = serde_json::from_str::<Value>(&event.data) {
+                        if is_response_to(&value, id) {
+                            return Ok(Some(value));
+                        }
+                    }
+                }
+            }
+            return Err("SSE 响应结束但未收到结果".to_string());
+        }
+
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| format!("解析 JSON 响应失败: {}", e))?;
+        let found = match body {
+            Value::Array(items) => items.into_iter().find(|v| is_response_to(v, id)),
+            single => Some(single).filter(|v| is_response_to(v, id)),
+        };
+        found.map(Some).ok_or_else(|| "响应中没有匹配的 id".to_string())
+    }
+
+    async fn exchange_sse(
+        conn: &mut SseConnection,
+        message: &Value,
+        id: Option<u64>,
+    ) -> Result<Option<Value>, String> {
+        let response = apply_headers(conn.client.post(conn.endpoint.clone()), &conn.headers)
+            .json(message)
+            .send()
+            .await
+            .map_err(|e| format!("HTTP 请求失败: {}", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("服务器返回 HTTP {}: {}", status, body.trim()));
+        }
+
+        let Some(id) = id else {
+            return Ok(None);
+        };
+
+        // 响应通过 GET 事件流异步返回
+        loop {
+            while !conn.pending.is_empty() {
+                let event = conn.pending.remove(0);
+                if event.event != "message" {
+                    continue;
+                }
+                if let Ok(value) = serde_json::from_str::<Value>(&event.data) {
+                    if is_response_to(&value, id) {
+                        return Ok(Some(value));
+                    }
+                }
+            }
+
+            let chunk = conn
+                .stream
+                .chunk()
+                .await
+                .map_err(|e| format!("读取 SSE 事件流失败: {}", e))?
+                .ok_or("SSE 事件流已关闭")?;
+            let events = conn.parser.push(&chunk);
+            conn.pending.extend(events);
+        }
+    }
+
+    /// 分页获取 list 接口的全部条目
+    async fn list_all(&mut self, method: &str, key: &str) -> Result<Vec<Value>, String> {
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        for _ in 0..MAX_LIST_PAGES {
+            let params = match &cursor {
+                Some(c) => json!({ "cursor": c }),
+                None => json!({}),
+            };
+            let result = self.request(method, params).await?;
+            if let Some(arr) = result.get(key).and_then(|v| v.as_array()) {
+                items.extend(arr.iter().cloned());
+            }
+            cursor = result
+                .get("nextCursor")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string());
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        Ok(items)
+    }
+
+    /// 关闭连接，返回 stdio 服务器的 stderr 末尾
+    async fn close(self) -> Option<String> {
+        match self.connection {
+            Connection::Stdio(mut conn) => {
+                drop(conn.stdin);
+                if let Err(e) = conn.child.start_kill() {
+                    log::debug!("Failed to kill MCP server process: {}", e);
+                }
+                let _ = tokio::time::timeout(Duration::from_secs(2), conn.child.wait()).await;
+                let tail = conn.stderr_tail.lock().ok()?.trim().to_string();
+                if tail.is_empty() {
+                    None
+                } else {
+                    Some(tail)
+                }
+            }
+            Connection::Http(conn) => {
+                // 显式结束会话（服务器可能不支持，忽略结果）
+                if let Some(session_id) = &conn.session_id {
+                    let _ = apply_headers(conn.client.delete(&conn.url), &conn.headers)
+                        .header("Mcp-Session-Id", session_id.as_str())
+                        .timeout(Duration::from_secs(2))
+                        .send()
+                        .await;
+                }
+                None
+            }
+            Connection::Sse(_) => None,
+        }
+    }
+}
+
+fn parse_tool(value: &Value) -> Option<McpToolInfo> {
+    Some(McpToolInfo {
+        name: value.get("name")?.as_str()?.to_string(),
+        description: value
+            .get("description")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        input_schema: value.get("inputSchema").cloned(),
+    })
+}
+
+fn parse_resource(value: &Value) -> Option<McpResourceInfo> {
+    let uri = value.get("uri")?.as_str()?.to_string();
+    Some(McpResourceInfo {
+        name: value
+            .get("name")
+            .and_then(|v| v.as_str())
+            .unwrap_or(&uri)
+            .to_string(),
+        description: value
+            .get("description")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        mime_type: value
+            .get("mimeType")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        uri,
+    })
+}
+
+fn parse_prompt(value: &Value) -> Option<McpPromptInfo> {
+    Some(McpPromptInfo {
+        name: value.get("name")?.as_str()?.to_string(),
+        description: value
+            .get("description")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        arguments: value
+            .get("arguments")
+            .and_then(|v| v.as_array())
+            .map(|args| {
+                args.iter()
+                    .filter_map(|a| a.get("name").and_then(|n| n.as_str()))
+                    .map(|s| s.to_string())
+                    .collect()
+            })
+            .unwrap_or_default(),
+    })
+}
+
+/// 执行握手并枚举能力，填充报告
+async fn run_session(client: &mut HealthClient, report: &mut McpHealthReport) -> Result<(), String> {
+    let params = json!({
+        "protocolVersion": CLIENT_PROTOCOL_VERSION,
+        "capabilities": {},
+        "clientInfo": {
+            "name": "any-code",
+            "version": env!("CARGO_PKG_VERSION")
+        }
+    });
+
+    let started = Instant::now();
+    let init = client.request("initialize", params).await?;
+    report.latency_ms = Some(started.elapsed().as_millis() as u64);
+
+    report.protocol_version = init
+        .get("protocolVersion")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    report.server_name = init
+        .pointer("/serverInfo/name")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    report.server_version = init
+        .pointer("/serverInfo/version")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    let capabilities = init.get("capabilities").cloned().unwrap_or(json!({}));
+    report.capabilities = Some(capabilities.clone());
+
+    client.notify("notifications/initialized").await?;
+    report.success = true;
+
+    // 仅查询服务器声明支持的能力；单个列表失败不影响握手结果
+    if capabilities.get("tools").is_some() {
+        match client.list_all("tools/list", "tools").await {
+            Ok(items) => report.tools = items.iter().filter_map(parse_tool).collect(),
+            Err(e) => report.warnings.push(format!("tools/list 失败: {}", e)),
+        }
+    }
+    if capabilities.get("resources").is_some() {
+        match client.list_all("resources/list", "resources").await {
+            Ok(items) => report.resources = items.iter().filter_map(parse_resource).collect(),
+            Err(e) => report.warnings.push(format!("resources/list 失败: {}", e)),
+        }
+    }
+    if capabilities.get("prompts").is_some() {
+        match client.list_all("prompts/list", "prompts").await {
+            Ok(items) => report.prompts = items.iter().filter_map(parse_prompt).collect(),
+            Err(e) => report.warnings.push(format!("prompts/list 失败: {}", e)),
+        }
+    }
+
+    Ok(())
+}
+
+/// 对单个 MCP 服务器执行真实的健康检查
+///
+/// 握手失败不会返回 Err，而是体现在报告的 `success` / `error` 字段中
+pub async fn check_server_health(server_id: &str, spec: &Value, timeout: Duration) -> McpHealthReport {
+    let started = Instant::now();
+
+    let transport = match parse_transport(spec) {
+        Ok(t) => t,
+        Err(e) => {
+            let mut report = McpHealthReport::new(server_id, "unknown");
+            report.error = Some(e);
+            return report;
+        }
+    };
+
+    let mut report = McpHealthReport::new(server_id, transport.kind());
+    log::info!(
+        "Checking MCP server '{}' over {} transport",
+        server_id,
+        transport.kind()
+    );
+
+    match HealthClient::connect(&transport, timeout).await {
+        Ok(mut client) => {
+            if let Err(e) = run_session(&mut client, &mut report).await {
+                report.success = false;
+                report.error = Some(e);
+            }
+            report.stderr = client.close().await;
+        }
+        Err(e) => report.error = Some(e),
+    }
+
+    report.total_ms = started.elapsed().as_millis() as u64;
+
+    if report.success {
+        log::info!(
+            "MCP server '{}' healthy: protocol={:?}, {} tools, {}ms",
+            server_id,
+            report.protocol_version,
+            report.tools.len(),
+            report.total_ms
+        );
+    } else {
+        log::warn!(
+            "MCP server '{}' health check failed: {:?}",
+            server_id,
+            report.error
+        );
+    }
+
+    report
+}
+
+/// 按名称查找服务器规范
+///
+/// 优先使用注册表（包含已禁用的服务器），其次按 `engine` 或 Claude → Codex → Gemini 顺序查找引擎配置
+pub fn resolve_server_spec(name: &str, engine: Option<&str>) -> Result<Value, String> {
+    if let Some(entry) = super::registry::get_server(name)? {
+        return Ok(entry.server);
+    }
+
+    let apps = match engine {
+        Some(e) => vec![super::AppType::from_str(e)?],
+        None => vec![
+            super::AppType::Claude,
+            super::AppType::Codex,
+            super::AppType::Gemini,
+        ],
+    };
+
+    for app in apps {
+        match super::import_from_app(&app) {
+            Ok(servers) => {
+                if let Some(spec) = servers.get(name) {
+                    return Ok(spec.clone());
+                }
+            }
+            Err(e) => log::warn!("读取 {} MCP 配置失败: {}", app.as_str(), e),
+        }
+    }
+
+    Err(format!("未找到 MCP 服务器 '{}'", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_transport_variants() {
+        let stdio = parse_transport(&json!({
+            "command": "npx",
+            "args": ["-y", "server"],
+            "env": { "TOKEN": "x" }
+        }))
+        .unwrap();
+        assert_eq!(stdio.kind(), "stdio");
+
+        // Gemini 原始格式：httpUrl 无 type
+        let http = parse_transport(&json!({ "httpUrl": "http://localhost:3000/mcp" })).unwrap();
+        assert_eq!(
+            http,
+            TransportSpec::Http {
+                url: "http://localhost:3000/mcp".to_string(),
+                headers: HashMap::new()
+            }
+        );
+
+        let sse = parse_transport(&json!({ "url": "http://localhost/sse" })).unwrap();
+        assert_eq!(sse.kind(), "sse");
+
+        assert!(parse_transport(&json!({ "type": "stdio" })).is_err());
+        assert!(parse_transport(&json!({ "type": "ws", "url": "x" })).is_err());
+    }
+
+    #[test]
+    fn test_sse_parser_handles_split_chunks() {
+        let mut parser = SseParser::default();
+        assert!(parser.push(b"event: endpoint\r\ndata: /mess").is_empty());
+
+        let events = parser.push(b"ages?id=1\r\n\r\n: ping\n\ndata: {\"id\":1}\n\n");
+        assert_eq!(
+            events,
+            vec![
+                SseEvent {
+                    event: "endpoint".to_string(),
+                    data: "/messages?id=1".to_string()
+                },
+                SseEvent {
+                    event: "message".to_string(),
+                    data: "{\"id\":1}".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_rpc_result() {
+        let ok = json!({ "jsonrpc": "2.0", "id": 1, "result": { "tools": [] } });
+        assert!(is_response_to(&ok, 1));
+        assert!(!is_response_to(&ok, 2));
+        assert!(extract_rpc_result(&ok).is_ok());
+
+        let err = json!({ "jsonrpc": "2.0", "id": 1, "error": { "code": -32601, "message": "nope" } });
+        assert_eq!(
+            extract_rpc_result(&err).unwrap_err(),
+            "MCP error -32601: nope"
+        );
+
+        // 服务器发起的请求不应被当作响应
+        let server_request = json!({ "jsonrpc": "2.0", "id": 1, "method": "roots/list" });
+        assert!(!is_response_to(&server_request, 1));
+    }
+}
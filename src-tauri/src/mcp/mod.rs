@@ -8,6 +8,7 @@
 //! - `claude` - Claude MCP 同步和导入
 //! - `codex` - Codex MCP 同步和导入
 //! - `gemini` - Gemini MCP 同步和导入
+//! - `health` - 服务器连接与握手健康检查
 //!
 //! ## 应用类型
 //!
@@ -19,6 +20,7 @@
 mod claude;
 mod codex;
 mod gemini;
+pub mod health;
 pub mod registry;
 mod validation;
 
@@ -8,8 +8,17 @@ use tokio::process::Child;
 /// Type of process being tracked
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ProcessType {
-    AgentRun { agent_id: i64, agent_name: String },
-    ClaudeSession { session_id: String },
+    AgentRun {
+        agent_id: i64,
+        agent_name: String,
+    },
+    ClaudeSession {
+        /// Empty until the CLI reports its session id in the init message
+        session_id: String,
+        /// Frontend tab that started the run
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tab_id: Option<String>,
+    },
 }
 
 /// Information about a running agent process
@@ -129,7 +138,10 @@ impl ProcessRegistry {
 
         let process_info = ProcessInfo {
             run_id,
-            process_type: ProcessType::ClaudeSession { session_id },
+            process_type: ProcessType::ClaudeSession {
+                session_id,
+                tab_id: None,
+            },
             pid,
             started_at: Utc::now(),
             project_path,
@@ -201,7 +213,10 @@ impl ProcessRegistry {
 
         let process_info = ProcessInfo {
             run_id,
-            process_type: ProcessType::ClaudeSession { session_id },
+            process_type: ProcessType::ClaudeSession {
+                session_id,
+                tab_id: None,
+            },
             pid,
             started_at: Utc::now(),
             project_path,
@@ -271,18 +286,81 @@ impl ProcessRegistry {
         Ok(())
     }
 
-    /// Get all running Claude sessions
+    /// Get all running Claude sessions (runs whose session id is known)
     pub fn get_running_claude_sessions(&self) -> Result<Vec<ProcessInfo>, String> {
         let processes = self.processes.lock().map_err(|e| e.to_string())?;
         Ok(processes
             .values()
             .filter_map(|handle| match &handle.info.process_type {
-                ProcessType::ClaudeSession { .. } => Some(handle.info.clone()),
+                ProcessType::ClaudeSession { session_id, .. } if !session_id.is_empty() => {
+                    Some(handle.info.clone())
+                }
                 _ => None,
             })
             .collect())
     }
 
+    /// Get all running Claude runs, including those still waiting for a session id
+    pub fn get_running_claude_runs(&self) -> Result<Vec<ProcessInfo>, String> {
+        let processes = self.processes.lock().map_err(|e| e.to_string())?;
+        Ok(processes
+            .values()
+            .filter(|handle| matches!(handle.info.process_type, ProcessType::ClaudeSession { .. }))
+            .map(|handle| handle.info.clone())
+            .collect())
+    }
+
+    /// Find a Claude run by session ID, falling back to the tab that started it
+    pub fn find_claude_run(&self, key: &str) -> Result<Option<ProcessInfo>, String> {
+        if key.is_empty() {
+            return Ok(None);
+        }
+        if let Some(info) = self.get_claude_session_by_id(key)? {
+            return Ok(Some(info));
+        }
+        let processes = self.processes.lock().map_err(|e| e.to_string())?;
+        Ok(processes
+            .values()
+            .find(|handle| match &handle.info.process_type {
+                ProcessType::ClaudeSession { tab_id, .. } => tab_id.as_deref() == Some(key),
+                _ => false,
+            })
+            .map(|handle| handle.info.clone()))
+    }
+
+    /// The most recently started Claude run
+    pub fn latest_claude_run(&self) -> Result<Option<ProcessInfo>, String> {
+        Ok(self
+            .get_running_claude_runs()?
+            .into_iter()
+            .max_by_key(|info| (info.started_at, info.run_id)))
+    }
+
+    /// Record the session ID the CLI reported for a run
+    pub fn set_claude_session_id(&self, run_id: i64, session_id: &str) -> Result<(), String> {
+        let mut processes = self.processes.lock().map_err(|e| e.to_string())?;
+        if let Some(handle) = processes.get_mut(&run_id) {
+            if let ProcessType::ClaudeSession {
+                session_id: sid, ..
+            } = &mut handle.info.process_type
+            {
+                *sid = session_id.to_string();
+            }
+        }
+        Ok(())
+    }
+
+    /// Record the frontend tab that started a run
+    pub fn set_claude_tab_id(&self, run_id: i64, tab_id: &str) -> Result<(), String> {
+        let mut processes = self.processes.lock().map_err(|e| e.to_string())?;
+        if let Some(handle) = processes.get_mut(&run_id) {
+            if let ProcessType::ClaudeSession { tab_id: tid, .. } = &mut handle.info.process_type {
+                *tid = Some(tab_id.to_string());
+            }
+        }
+        Ok(())
+    }
+
     /// Get a specific Claude session by session ID
     pub fn get_claude_session_by_id(
         &self,
@@ -292,7 +370,9 @@ impl ProcessRegistry {
         Ok(processes
             .values()
             .find(|handle| match &handle.info.process_type {
-                ProcessType::ClaudeSession { session_id: sid } => sid == session_id,
+                ProcessType::ClaudeSession {
+                    session_id: sid, ..
+                } => !sid.is_empty() && sid == session_id,
                 _ => false,
             })
             .map(|handle| handle.info.clone()))
@@ -330,7 +410,6 @@ impl ProcessRegistry {
     }
 
     /// Get a specific running process
-    #[allow(dead_code)]
     pub fn get_process(&self, run_id: i64) -> Result<Option<ProcessInfo>, String> {
         let processes = self.processes.lock().map_err(|e| e.to_string())?;
         Ok(processes.get(&run_id).map(|handle| handle.info.clone()))
@@ -865,3 +944,96 @@ impl Drop for ProcessRegistryState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn start_run(registry: &ProcessRegistry, session_id: &str, tab_id: Option<&str>) -> i64 {
+        let run_id = registry
+            .register_claude_session(
+                session_id.to_string(),
+                0,
+                "/work/project".to_string(),
+                "task".to_string(),
+                "sonnet".to_string(),
+            )
+            .unwrap();
+        if let Some(tab) = tab_id {
+            registry.set_claude_tab_id(run_id, tab).unwrap();
+        }
+        run_id
+    }
+
+    fn ids(info: &ProcessInfo) -> (String, Option<String>) {
+        match &info.process_type {
+            ProcessType::ClaudeSession { session_id, tab_id } => {
+                (session_id.clone(), tab_id.clone())
+            }
+            _ => panic!("not a Claude run"),
+        }
+    }
+
+    #[test]
+    fn claude_runs_are_found_by_session_id_before_tab_id() {
+        let registry = ProcessRegistry::new();
+        let resumed = start_run(&registry, "shared", Some("tab-1"));
+        // A new run whose tab id happens to equal the other run's session id
+        let fresh = start_run(&registry, "", Some("shared"));
+
+        let found = |key: &str| {
+            registry
+                .find_claude_run(key)
+                .unwrap()
+                .map(|info| info.run_id)
+        };
+        assert_eq!(found("shared"), Some(resumed));
+        assert_eq!(found("tab-1"), Some(resumed));
+        assert_eq!(found("missing"), None);
+        // Runs still waiting for a session id never match an empty key
+        assert_eq!(found(""), None);
+
+        assert_eq!(registry.get_running_claude_sessions().unwrap().len(), 1);
+        assert_eq!(registry.get_running_claude_runs().unwrap().len(), 2);
+        assert_eq!(
+            registry
+                .latest_claude_run()
+                .unwrap()
+                .map(|info| info.run_id),
+            Some(fresh)
+        );
+    }
+
+    #[test]
+    fn reported_ids_override_and_persist_until_the_run_ends() {
+        let registry = ProcessRegistry::new();
+        let run_id = start_run(&registry, "", Some("tab-1"));
+
+        registry.set_claude_session_id(run_id, "first").unwrap();
+        registry.set_claude_session_id(run_id, "second").unwrap();
+        registry.set_claude_tab_id(run_id, "tab-2").unwrap();
+        // Unknown runs are ignored
+        registry.set_claude_session_id(run_id + 1, "other").unwrap();
+
+        let info = registry.get_process(run_id).unwrap().unwrap();
+        assert_eq!(
+            ids(&info),
+            ("second".to_string(), Some("tab-2".to_string()))
+        );
+        assert!(registry.find_claude_run("first").unwrap().is_none());
+        assert!(registry.find_claude_run("tab-1").unwrap().is_none());
+        assert_eq!(
+            registry
+                .find_claude_run("second")
+                .unwrap()
+                .map(|i| i.run_id),
+            Some(run_id)
+        );
+        assert!(registry.get_process(run_id + 1).unwrap().is_none());
+
+        registry.unregister_process(run_id).unwrap();
+        assert!(registry.get_process(run_id).unwrap().is_none());
+        assert!(registry.find_claude_run("second").unwrap().is_none());
+        assert!(registry.latest_claude_run().unwrap().is_none());
+    }
+}
@@ -5,7 +5,14 @@ import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
 import { Card } from "@/components/ui/card";
 import { Badge } from "@/components/ui/badge";
-import { api } from "@/lib/api";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
+import { api, type ContextBackend } from "@/lib/api";
 import { cn } from "@/lib/utils";
 import { copyTextToClipboard } from "@/lib/clipboard";
 import { useTranslation } from "@/hooks/useTranslation";
@@ -19,6 +26,7 @@ interface AcemcpConfig {
   token: string;
   batchSize?: number;
   maxLinesPerBlob?: number;
+  backend: ContextBackend;
 }
 
 export function AcemcpConfigSettings({ className }: AcemcpConfigSettingsProps) {
@@ -28,6 +36,7 @@ export function AcemcpConfigSettings({ className }: AcemcpConfigSettingsProps) {
     token: '',
     batchSize: 10,
     maxLinesPerBlob: 800,
+    backend: 'acemcp',
   });
 
   const [showToken, setShowToken] = useState(false);
@@ -62,7 +71,8 @@ export function AcemcpConfigSettings({ className }: AcemcpConfigSettingsProps) {
         config.baseUrl,
         config.token,
         config.batchSize,
-        config.maxLinesPerBlob
+        config.maxLinesPerBlob,
+        config.backend
       );
       setHasChanges(false);
       setTestStatus('idle');
@@ -80,6 +90,7 @@ export function AcemcpConfigSettings({ className }: AcemcpConfigSettingsProps) {
       token: '',
       batchSize: 10,
       maxLinesPerBlob: 800,
+      backend: 'acemcp',
     });
     setHasChanges(true);
   };
@@ -185,6 +196,26 @@ export function AcemcpConfigSettings({ className }: AcemcpConfigSettingsProps) {
           </div>
         ) : (
           <div className="space-y-4">
+            {/* Retrieval Backend */}
+            <div>
+              <Label htmlFor="acemcp-backend">{t('acemcp.backend')}</Label>
+              <Select
+                value={config.backend}
+                onValueChange={(v) => handleChange('backend', v as ContextBackend)}
+              >
+                <SelectTrigger id="acemcp-backend">
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent>
+                  <SelectItem value="acemcp">{t('acemcp.backendAcemcp')}</SelectItem>
+                  <SelectItem value="local">{t('acemcp.backendLocal')}</SelectItem>
+                </SelectContent>
+              </Select>
+              <p className="text-xs text-muted-foreground mt-1">
+                {t('acemcp.backendDescription')}
+              </p>
+            </div>
+
             {/* API Base URL */}
             <div>
               <Label htmlFor="acemcp-base-url">{t('acemcp.apiEndpoint')} *</Label>
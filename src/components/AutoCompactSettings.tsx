@@ -201,6 +201,11 @@ export const AutoCompactSettings: React.FC<AutoCompactSettingsProps> = ({
     );
   }
 
+  // Sessions are sized by their model's window unless that is turned off
+  const useModelWindows = config.use_model_context_windows ?? true;
+  const sessionContextWindow = (session: SessionContext) =>
+    (useModelWindows && session.context_window) || config.max_context_tokens;
+
   return (
     <Dialog open={open} onOpenChange={onOpenChange}>
       <DialogContent className={cn("max-w-4xl max-h-[85vh] overflow-hidden", className)}>
@@ -267,6 +272,19 @@ export const AutoCompactSettings: React.FC<AutoCompactSettingsProps> = ({
 
                 <hr className="border-t border-border my-4" />
 
+                <div className="flex items-center justify-between">
+                  <div className="space-y-0.5">
+                    <Label className="text-base">{t('autoCompact.useModelContextWindows')}</Label>
+                    <p className="text-sm text-muted-foreground">
+                      {t('autoCompact.useModelContextWindowsDescription')}
+                    </p>
+                  </div>
+                  <Switch
+                    checked={useModelWindows}
+                    onCheckedChange={(use_model_context_windows) => handleConfigChange({ use_model_context_windows })}
+                  />
+                </div>
+
                 <div className="space-y-3">
                   <div className="flex items-center gap-2">
                     <Gauge className="h-4 w-4 text-blue-500" />
@@ -291,6 +309,7 @@ export const AutoCompactSettings: React.FC<AutoCompactSettingsProps> = ({
                     min={10000}
                     max={200000}
                     step={1000}
+                    disabled={useModelWindows}
                   />
                 </div>
 
@@ -644,22 +663,22 @@ export const AutoCompactSettings: React.FC<AutoCompactSettingsProps> = ({
                             <div className="flex justify-between text-xs text-muted-foreground">
                               <span>{t('autoCompact.usageLabel')}</span>
                               <span>
-                                {Math.round((session.current_tokens / config.max_context_tokens) * 100)}%
+                                {Math.round((session.current_tokens / sessionContextWindow(session)) * 100)}%
                               </span>
                             </div>
                             <div className="w-full bg-gray-200 rounded-full h-2">
                               <div
                                 className={cn(
                                   "h-2 rounded-full transition-all duration-300",
-                                  session.current_tokens / config.max_context_tokens > config.compaction_threshold
+                                  session.current_tokens / sessionContextWindow(session) > config.compaction_threshold
                                     ? "bg-red-500"
-                                    : session.current_tokens / config.max_context_tokens > 0.7
+                                    : session.current_tokens / sessionContextWindow(session) > 0.7
                                     ? "bg-yellow-500"
                                     : "bg-green-500"
                                 )}
                                 style={{
                                   width: `${Math.min(
-                                    (session.current_tokens / config.max_context_tokens) * 100,
+                                    (session.current_tokens / sessionContextWindow(session)) * 100,
                                     100
                                   )}%`,
                                 }}
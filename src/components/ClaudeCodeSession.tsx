@@ -10,7 +10,7 @@ import {
 import { Button } from "@/components/ui/button";
 import { api, type Session, type Project } from "@/lib/api";
 import { cn } from "@/lib/utils";
-import { type UnlistenFn } from "@tauri-apps/api/event";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import { FloatingPromptInput, type FloatingPromptInputRef, type ModelType } from "./FloatingPromptInput";
 import { ErrorBoundary } from "./ErrorBoundary";
 import { RevertPromptPicker } from "./RevertPromptPicker";
@@ -26,6 +26,7 @@ import { useSmartAutoScroll } from '@/hooks/useSmartAutoScroll';
 import { useMessageTranslation } from '@/hooks/useMessageTranslation';
 import { useSessionStream } from '@/hooks/useSessionStream';
 import { usePromptExecution } from '@/hooks/usePromptExecution';
+import type { CompactionEvent } from '@/hooks/useAutoCompactStatus';
 import { MessagesProvider, useMessagesContext } from '@/contexts/MessagesContext';
 import { SessionProvider } from '@/contexts/SessionContext';
 import { PlanModeProvider, usePlanMode } from '@/contexts/PlanModeContext';
@@ -218,6 +219,37 @@ const ClaudeCodeSessionInner: React.FC<ClaudeCodeSessionProps> = ({
     }
   }, [extractedSessionInfo, projectPath, onSessionInfoChange]);
 
+  // Follow the session when compaction makes the CLI continue it under a new id,
+  // so the tab and the next prompt use that id
+  useEffect(() => {
+    if (!claudeSessionId) return;
+
+    let unlisten: UnlistenFn | null = null;
+    let cancelled = false;
+    listen<CompactionEvent>('auto-compact-event', (event) => {
+      const { event_type, session_id, new_session_id } = event.payload;
+      if (event_type !== 'completed' || session_id !== claudeSessionId || !new_session_id) return;
+
+      console.debug('[ClaudeCodeSession] Session continued after compaction:', session_id, '->', new_session_id);
+      setClaudeSessionId(new_session_id);
+      setExtractedSessionInfo(prev => ({
+        sessionId: new_session_id,
+        projectId: prev?.projectId ?? session?.project_id ?? projectPath.replace(/[^a-zA-Z0-9]/g, '-'),
+        engine: 'claude',
+      }));
+    }).then(fn => {
+      if (cancelled) fn();
+      else unlisten = fn;
+    }).catch(error => {
+      console.warn('[ClaudeCodeSession] Failed to listen for compaction events:', error);
+    });
+
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, [claudeSessionId, session?.project_id, projectPath]);
+
   const displayableMessages = useDisplayableMessages(messages, {
     hideWarmupMessages: filterConfig.hideWarmupMessages
   });
@@ -383,15 +415,6 @@ const ClaudeCodeSessionInner: React.FC<ClaudeCodeSessionProps> = ({
     }
   }, [projectPath, initialProjectPath, onProjectPathChange]);
 
-  // ⚡ PERFORMANCE FIX: Git 初始化延迟到真正需要时
-  // 原问题：每次加载会话都立即执行 git init + git add + git commit
-  // 在大项目中，git add . 可能需要数秒，导致会话加载卡顿
-  // 解决方案：只在发送提示词时才初始化 Git（在 recordPromptSent 中已有）
-  // useEffect(() => {
-  //   if (!projectPath) return;
-  //   api.checkAndInitGit(projectPath).then(...);
-  // }, [projectPath]);
-
   // Get effective session info (from prop or extracted) - use useMemo to ensure it updates
   const effectiveSession = useMemo(() => {
     // 🔧 FIX: 当会话历史不存在时，返回 null 以显示路径选择界面
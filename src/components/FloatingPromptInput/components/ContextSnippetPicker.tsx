@@ -0,0 +1,152 @@
+/**
+ * ContextSnippetPicker Component
+ *
+ * 提示词优化时展示检索到的项目代码片段
+ * 用户可以取消勾选不相关的片段，只把保留的片段（带 文件:行号 引用）发送给优化 API
+ */
+
+import { useEffect, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { FileCode } from "lucide-react";
+import {
+  Dialog,
+  DialogContent,
+  DialogHeader,
+  DialogTitle,
+  DialogDescription,
+  DialogFooter,
+} from "@/components/ui/dialog";
+import { Button } from "@/components/ui/button";
+import { ScrollArea } from "@/components/ui/scroll-area";
+import { Checkbox } from "@/components/ui/checkbox";
+import { cn } from "@/lib/utils";
+import type { ContextSnippet } from "@/lib/api";
+
+// 预览显示的最大行数
+const PREVIEW_LINES = 8;
+
+interface ContextSnippetPickerProps {
+  /** 待选择的片段（为 null 时不显示） */
+  snippets: ContextSnippet[] | null;
+  /** 确认选择；传入空数组表示不使用项目上下文 */
+  onConfirm: (selected: ContextSnippet[]) => void;
+}
+
+/**
+ * 片段的 文件:行号 引用
+ */
+function snippetCitation(snippet: ContextSnippet): string {
+  if (snippet.startLine == null) {
+    return snippet.path;
+  }
+  if (snippet.endLine != null && snippet.endLine > snippet.startLine) {
+    return `${snippet.path}:${snippet.startLine}-${snippet.endLine}`;
+  }
+  return `${snippet.path}:${snippet.startLine}`;
+}
+
+export function ContextSnippetPicker({ snippets, onConfirm }: ContextSnippetPickerProps) {
+  const { t } = useTranslation();
+  const [selectedIds, setSelectedIds] = useState<Set<string>>(new Set());
+
+  // 每次打开时默认全选
+  useEffect(() => {
+    setSelectedIds(new Set((snippets ?? []).map((s) => s.id)));
+  }, [snippets]);
+
+  const toggle = (id: string, checked: boolean) => {
+    setSelectedIds((prev) => {
+      const next = new Set(prev);
+      if (checked) {
+        next.add(id);
+      } else {
+        next.delete(id);
+      }
+      return next;
+    });
+  };
+
+  const list = snippets ?? [];
+
+  return (
+    <Dialog open={snippets !== null} onOpenChange={(isOpen) => !isOpen && onConfirm([])}>
+      <DialogContent className="sm:max-w-2xl max-h-[85vh] flex flex-col">
+        <DialogHeader>
+          <DialogTitle className="flex items-center gap-2 text-lg">
+            <FileCode className="h-5 w-5 text-primary" />
+            {t("promptInput.contextSnippets.title")}
+          </DialogTitle>
+          <DialogDescription>
+            {t("promptInput.contextSnippets.description", { count: list.length })}
+          </DialogDescription>
+        </DialogHeader>
+
+        <div className="flex-1 min-h-0 my-2">
+          <ScrollArea className="h-[400px]">
+            <div className="space-y-2 pr-4">
+              {list.map((snippet) => {
+                const isSelected = selectedIds.has(snippet.id);
+                const preview = snippet.content.split("\n").slice(0, PREVIEW_LINES).join("\n");
+
+                return (
+                  <div
+                    key={snippet.id}
+                    className={cn(
+                      "p-3 rounded-md border cursor-pointer transition-all",
+                      isSelected
+                        ? "border-primary/40 bg-primary/5"
+                        : "border-border/50 bg-background opacity-60 hover:opacity-100"
+                    )}
+                    onClick={() => toggle(snippet.id, !isSelected)}
+                  >
+                    <div className="flex items-start gap-2.5">
+                      <Checkbox
+                        checked={isSelected}
+                        onCheckedChange={(checked) => toggle(snippet.id, checked as boolean)}
+                        onClick={(e) => e.stopPropagation()}
+                        className="mt-0.5"
+                      />
+                      <div className="flex-1 min-w-0">
+                        <div className="flex items-center justify-between gap-2">
+                          <span className="text-sm font-mono font-medium truncate">
+                            {snippetCitation(snippet)}
+                          </span>
+                          <span className="text-xs text-muted-foreground whitespace-nowrap">
+                            {snippet.score != null
+                              ? t("promptInput.contextSnippets.roundWithScore", {
+                                  round: snippet.round,
+                                  score: snippet.score.toFixed(2),
+                                })
+                              : t("promptInput.contextSnippets.round", { round: snippet.round })}
+                          </span>
+                        </div>
+                        <div className="text-xs text-muted-foreground truncate" title={snippet.query}>
+                          {t("promptInput.contextSnippets.query", { query: snippet.query })}
+                        </div>
+                        <pre className="mt-2 p-2 rounded bg-muted/40 text-xs font-mono overflow-hidden whitespace-pre-wrap break-all">
+                          {preview}
+                        </pre>
+                      </div>
+                    </div>
+                  </div>
+                );
+              })}
+            </div>
+          </ScrollArea>
+        </div>
+
+        <DialogFooter className="gap-2">
+          <Button variant="outline" onClick={() => onConfirm([])}>
+            {t("promptInput.contextSnippets.skip")}
+          </Button>
+          <Button
+            onClick={() => onConfirm(list.filter((s) => selectedIds.has(s.id)))}
+            disabled={selectedIds.size === 0}
+          >
+            {t("promptInput.contextSnippets.useSelected", { count: selectedIds.size })}
+          </Button>
+        </DialogFooter>
+      </DialogContent>
+    </Dialog>
+  );
+}
@@ -1,5 +1,5 @@
-import { useState } from "react";
-import { api } from "@/lib/api";
+import { useEffect, useRef, useState } from "react";
+import { api, type ContextSnippet } from "@/lib/api";
 import { callEnhancementAPI, getProvider } from "@/lib/promptEnhancementService";
 import { enhancePromptWithDualAPI } from "@/lib/dualAPIEnhancement";
 import { loadContextConfig } from "@/lib/promptContextConfig";
@@ -81,6 +81,45 @@ export function usePromptEnhancement({
   enableMultiRound = true, // 🆕 默认启用多轮搜索
 }: UsePromptEnhancementOptions) {
   const [isEnhancing, setIsEnhancing] = useState(false);
+  // 正在增强的提示词（未在增强时为 null），以及本次增强是否已被取消
+  const enhancingPromptRef = useRef<string | null>(null);
+  const cancelledRef = useRef(false);
+  // 等待用户勾选的上下文片段（未在选择时为 null），以及选择完成的回调
+  const [snippetSelection, setSnippetSelection] = useState<ContextSnippet[] | null>(null);
+  const snippetResolverRef = useRef<((selected: ContextSnippet[]) => void) | null>(null);
+
+  /**
+   * 用户确认（或跳过）片段选择
+   */
+  const handleSnippetSelection = (selected: ContextSnippet[]) => {
+    const resolve = snippetResolverRef.current;
+    snippetResolverRef.current = null;
+    setSnippetSelection(null);
+    resolve?.(selected);
+  };
+
+  /**
+   * 显示片段选择对话框，等待用户确认
+   */
+  const chooseSnippets = (snippets: ContextSnippet[]) =>
+    new Promise<ContextSnippet[]>((resolve) => {
+      snippetResolverRef.current = resolve;
+      setSnippetSelection(snippets);
+    });
+
+  // 增强过程中用户再次编辑了提示词：取消正在进行的上下文搜索，并放弃本次增强结果
+  useEffect(() => {
+    const enhancingPrompt = enhancingPromptRef.current;
+    if (enhancingPrompt === null || prompt.trim() === enhancingPrompt) {
+      return;
+    }
+    enhancingPromptRef.current = null;
+    cancelledRef.current = true;
+    if (projectPath) {
+      api.cancelPromptEnhancement(projectPath);
+    }
+    handleSnippetSelection([]);
+  }, [prompt, projectPath]);
 
   // 🆕 智能上下文提取开关（默认启用）
   const [enableDualAPI, setEnableDualAPI] = useState(() => {
@@ -91,6 +130,7 @@ export function usePromptEnhancement({
   /**
    * 获取项目上下文（如果启用）
    * 🆕 v2: 支持历史上下文感知和多轮搜索
+   * 检索到的片段先交给用户勾选，只保留选中的片段（带 文件:行号 引用）
    */
   const getProjectContext = async (): Promise<string | null> => {
     if (!enableProjectContext || !projectPath) {
@@ -108,20 +148,17 @@ export function usePromptEnhancement({
         enableMultiRound  // 🆕 启用多轮搜索
       );
 
-      if (result.acemcpUsed && result.contextCount > 0) {
-        // 只返回上下文部分（不包括原提示词）
-        const contextMatch = result.enhancedPrompt.match(/--- 项目上下文.*?---\n([\s\S]*)/);
+      if (!result.backend || result.snippets.length === 0 || cancelledRef.current) {
+        return null;
+      }
 
-        if (contextMatch) {
-          const extractedContext = contextMatch[0];
-          return extractedContext;
-        } else {
-          console.warn('[getProjectContext] Failed to extract context with regex');
-          return null;
-        }
+      const selected = await chooseSnippets(result.snippets);
+      if (selected.length === 0 || cancelledRef.current) {
+        return null;
       }
 
-      return null;
+      // 只返回上下文部分（不包括原提示词）
+      return await api.formatEnhancementContext(selected);
     } catch (error) {
       console.error('[getProjectContext] Failed:', error);
       return null;
@@ -149,10 +186,15 @@ export function usePromptEnhancement({
     }
 
     setIsEnhancing(true);
+    enhancingPromptRef.current = trimmedPrompt;
+    cancelledRef.current = false;
 
     try {
       // 获取项目上下文（如果启用）
       const projectContext = await getProjectContext();
+      if (cancelledRef.current) {
+        return;
+      }
 
       let result: string;
 
@@ -187,7 +229,13 @@ export function usePromptEnhancement({
 
         result = await callEnhancementAPI(provider, trimmedPrompt, context);
       }
-      
+
+      // 提示词已被编辑，不再覆盖用户的输入
+      if (cancelledRef.current) {
+        return;
+      }
+      enhancingPromptRef.current = null;
+
       if (result && result.trim()) {
         // 使用可撤销的方式更新文本
         const target = isExpanded ? expandedTextareaRef.current : textareaRef.current;
@@ -201,6 +249,10 @@ export function usePromptEnhancement({
         }
       }
     } catch (error) {
+      if (cancelledRef.current) {
+        return;
+      }
+      enhancingPromptRef.current = null;
       console.error('[handleEnhancePromptWithAPI] Failed:', error);
       let errorMessage = '未知错误';
       
@@ -215,6 +267,7 @@ export function usePromptEnhancement({
         updateTextareaWithUndo(target, trimmedPrompt + `\n\n❌ ${provider.name}: ${errorMessage}`);
       }
     } finally {
+      enhancingPromptRef.current = null;
       setIsEnhancing(false);
     }
   };
@@ -224,5 +277,7 @@ export function usePromptEnhancement({
     handleEnhancePromptWithAPI,
     enableDualAPI,       // 🆕 暴露智能上下文开关状态
     setEnableDualAPI,    // 🆕 暴露开关控制函数
+    snippetSelection,        // 等待用户勾选的上下文片段
+    handleSnippetSelection,  // 确认片段选择
   };
 }
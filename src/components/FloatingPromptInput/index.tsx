@@ -23,6 +23,7 @@ import { InputArea } from "./InputArea";
 import { AttachmentPreview } from "./AttachmentPreview";
 import { ControlBar } from "./ControlBar";
 import { ExpandedModal } from "./ExpandedModal";
+import { ContextSnippetPicker } from "./components/ContextSnippetPicker";
 
 // Re-export types for external use
 export type { FloatingPromptInputRef, FloatingPromptInputProps, ThinkingMode, ModelType } from "./types";
@@ -254,6 +255,8 @@ const FloatingPromptInputInner = (
     handleEnhancePromptWithAPI,
     enableDualAPI,
     setEnableDualAPI,
+    snippetSelection,
+    handleSnippetSelection,
   } = usePromptEnhancement({
     prompt: state.prompt,
     isExpanded: state.isExpanded,
@@ -607,6 +610,9 @@ const FloatingPromptInputInner = (
 
   return (
     <>
+      {/* Project context snippet selection during prompt enhancement */}
+      <ContextSnippetPicker snippets={snippetSelection} onConfirm={handleSnippetSelection} />
+
       {/* Expanded Modal */}
       <AnimatePresence>
         {state.isExpanded && (
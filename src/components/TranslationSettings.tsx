@@ -6,7 +6,13 @@ import { Input } from './ui/input';
 import { Label } from './ui/label';
 import { Badge } from './ui/badge';
 import { Alert, AlertDescription } from './ui/alert';
-import { api, type TranslationConfig, type TranslationCacheStats } from '@/lib/api';
+import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from './ui/select';
+import {
+  api,
+  type TranslationConfig,
+  type TranslationCacheStats,
+  type TranslationBackendKind,
+} from '@/lib/api';
 import { translationMiddleware } from '@/lib/translationMiddleware';
 import { Loader2, RefreshCw, Settings, Languages, Database, AlertTriangle } from 'lucide-react';
 import { useTranslation } from '@/hooks/useTranslation';
@@ -15,6 +21,30 @@ interface TranslationSettingsProps {
   onClose?: () => void;
 }
 
+// 各翻译后端的默认 API 地址（作为输入框占位符）
+const BACKEND_URL_PLACEHOLDERS: Record<TranslationBackendKind, string> = {
+  openai: 'https://api.siliconflow.cn/v1',
+  anthropic: 'https://api.anthropic.com',
+  deepl: 'https://api-free.deepl.com',
+  local: '',
+};
+
+// 可选语言（与后端 translation_backend::LANGUAGES 保持一致）
+const LANGUAGE_OPTIONS = [
+  { code: 'zh', label: '简体中文' },
+  { code: 'zh-TW', label: '繁體中文' },
+  { code: 'en', label: 'English' },
+  { code: 'ja', label: '日本語' },
+  { code: 'ko', label: '한국어' },
+  { code: 'fr', label: 'Français' },
+  { code: 'de', label: 'Deutsch' },
+  { code: 'es', label: 'Español' },
+  { code: 'it', label: 'Italiano' },
+  { code: 'pt', label: 'Português' },
+  { code: 'ru', label: 'Русский' },
+  { code: 'vi', label: 'Tiếng Việt' },
+];
+
 export const TranslationSettings: React.FC<TranslationSettingsProps> = ({ onClose }) => {
   const { t } = useTranslation();
   const [config, setConfig] = useState<TranslationConfig | null>(null);
@@ -26,6 +56,10 @@ export const TranslationSettings: React.FC<TranslationSettingsProps> = ({ onClos
   const [error, setError] = useState<string | null>(null);
   const [success, setSuccess] = useState<string | null>(null);
 
+  // 本地命令后端不需要 API 密钥，也不使用 API 地址和模型
+  const needsApiKey = config?.backend !== 'local';
+  const isConfigured = needsApiKey ? !!config?.api_key.trim() : !!config?.local_command.trim();
+
   // 加载初始数据
   useEffect(() => {
     loadData();
@@ -75,8 +109,8 @@ export const TranslationSettings: React.FC<TranslationSettingsProps> = ({ onClos
   const handleTestConnection = async () => {
     if (!config) return;
 
-    if (!config.api_key.trim()) {
-      setError(t('translation.pleaseEnterApiKey'));
+    if (!isConfigured) {
+      setError(t(needsApiKey ? 'translation.pleaseEnterApiKey' : 'translation.pleaseEnterLocalCommand'));
       return;
     }
 
@@ -84,7 +118,7 @@ export const TranslationSettings: React.FC<TranslationSettingsProps> = ({ onClos
       setTestingConnection(true);
       setError(null);
 
-      await api.translateText('Hello', 'zh');
+      await api.translateText('Hello', config.user_language);
 
       setSuccess(t('translation.connectionSuccess'));
       setTimeout(() => setSuccess(null), 3000);
@@ -188,25 +222,84 @@ export const TranslationSettings: React.FC<TranslationSettingsProps> = ({ onClos
             />
           </div>
 
+          <div className="space-y-2">
+            <Label htmlFor="translation-backend">{t('translation.backend')}</Label>
+            <Select
+              value={config.backend}
+              onValueChange={(v) => handleConfigChange('backend', v as TranslationBackendKind)}
+            >
+              <SelectTrigger id="translation-backend">
+                <SelectValue />
+              </SelectTrigger>
+              <SelectContent>
+                <SelectItem value="openai">{t('translation.backendOpenAI')}</SelectItem>
+                <SelectItem value="anthropic">{t('translation.backendAnthropic')}</SelectItem>
+                <SelectItem value="deepl">{t('translation.backendDeepL')}</SelectItem>
+                <SelectItem value="local">{t('translation.backendLocal')}</SelectItem>
+              </SelectContent>
+            </Select>
+            <p className="text-xs text-muted-foreground">
+              {t('translation.backendDescription')}
+            </p>
+          </div>
+
           <div className="grid grid-cols-1 md:grid-cols-2 gap-4">
+            {needsApiKey && (
+              <div className="space-y-2">
+                <Label htmlFor="api-base-url">{t('translation.apiBaseUrl')}</Label>
+                <Input
+                  id="api-base-url"
+                  value={config.api_base_url}
+                  onChange={(e) => handleConfigChange('api_base_url', e.target.value)}
+                  placeholder={BACKEND_URL_PLACEHOLDERS[config.backend]}
+                />
+              </div>
+            )}
+
+            {(config.backend === 'openai' || config.backend === 'anthropic') && (
+              <div className="space-y-2">
+                <Label htmlFor="model">{t('translation.translationModel')}</Label>
+                <Input
+                  id="model"
+                  value={config.model}
+                  onChange={(e) => handleConfigChange('model', e.target.value)}
+                  placeholder="tencent/Hunyuan-MT-7B"
+                />
+              </div>
+            )}
+
             <div className="space-y-2">
-              <Label htmlFor="api-base-url">{t('translation.apiBaseUrl')}</Label>
-              <Input
-                id="api-base-url"
-                value={config.api_base_url}
-                onChange={(e) => handleConfigChange('api_base_url', e.target.value)}
-                placeholder="https://api.siliconflow.cn/v1"
-              />
+              <Label htmlFor="user-language">{t('translation.userLanguage')}</Label>
+              <Select
+                value={config.user_language}
+                onValueChange={(v) => handleConfigChange('user_language', v)}
+              >
+                <SelectTrigger id="user-language">
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent>
+                  {LANGUAGE_OPTIONS.map((lang) => (
+                    <SelectItem key={lang.code} value={lang.code}>{lang.label}</SelectItem>
+                  ))}
+                </SelectContent>
+              </Select>
             </div>
 
             <div className="space-y-2">
-              <Label htmlFor="model">{t('translation.translationModel')}</Label>
-              <Input
-                id="model"
-                value={config.model}
-                onChange={(e) => handleConfigChange('model', e.target.value)}
-                placeholder="tencent/Hunyuan-MT-7B"
-              />
+              <Label htmlFor="agent-language">{t('translation.agentLanguage')}</Label>
+              <Select
+                value={config.agent_language}
+                onValueChange={(v) => handleConfigChange('agent_language', v)}
+              >
+                <SelectTrigger id="agent-language">
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent>
+                  {LANGUAGE_OPTIONS.map((lang) => (
+                    <SelectItem key={lang.code} value={lang.code}>{lang.label}</SelectItem>
+                  ))}
+                </SelectContent>
+              </Select>
             </div>
 
             <div className="space-y-2">
@@ -234,35 +327,53 @@ export const TranslationSettings: React.FC<TranslationSettingsProps> = ({ onClos
             </div>
           </div>
 
-          <div className="space-y-2">
-            <Label htmlFor="api-key" className="flex items-center space-x-2">
-              <span>{t('translation.apiKey')}</span>
-              {!config.api_key && (
-                <Badge variant="destructive" className="text-xs">{t('translation.apiKeyRequired')}</Badge>
-              )}
-            </Label>
-            <Input
-              id="api-key"
-              type="password"
-              value={config.api_key}
-              onChange={(e) => handleConfigChange('api_key', e.target.value)}
-              placeholder={t('translation.apiKeyPlaceholder')}
-              className={!config.api_key ? "border-red-300" : ""}
-            />
-            <div className="space-y-1">
+          {!needsApiKey && (
+            <div className="space-y-2">
+              <Label htmlFor="local-command">{t('translation.localCommand')}</Label>
+              <Input
+                id="local-command"
+                value={config.local_command}
+                onChange={(e) => handleConfigChange('local_command', e.target.value)}
+                placeholder="ollama run qwen2.5:7b"
+                className="font-mono"
+              />
               <p className="text-xs text-muted-foreground">
-                {t('translation.apiKeyDescription')}
-              </p>
-              <p className="text-xs text-blue-600">
-                {t('translation.apiKeyHint')}
+                {t('translation.localCommandDescription')}
               </p>
-              {!config.api_key && (
-                <p className="text-xs text-red-600">
-                  {t('translation.apiKeyWarning')}
+            </div>
+          )}
+
+          {needsApiKey && (
+            <div className="space-y-2">
+              <Label htmlFor="api-key" className="flex items-center space-x-2">
+                <span>{t('translation.apiKey')}</span>
+                {!config.api_key && (
+                  <Badge variant="destructive" className="text-xs">{t('translation.apiKeyRequired')}</Badge>
+                )}
+              </Label>
+              <Input
+                id="api-key"
+                type="password"
+                value={config.api_key}
+                onChange={(e) => handleConfigChange('api_key', e.target.value)}
+                placeholder={t('translation.apiKeyPlaceholder')}
+                className={!config.api_key ? "border-red-300" : ""}
+              />
+              <div className="space-y-1">
+                <p className="text-xs text-muted-foreground">
+                  {t('translation.apiKeyDescription')}
+                </p>
+                <p className="text-xs text-blue-600">
+                  {t('translation.apiKeyHint')}
                 </p>
-              )}
+                {!config.api_key && (
+                  <p className="text-xs text-red-600">
+                    {t('translation.apiKeyWarning')}
+                  </p>
+                )}
+              </div>
             </div>
-          </div>
+          )}
 
           <div className="flex space-x-2 pt-4">
             <Button
@@ -276,14 +387,14 @@ export const TranslationSettings: React.FC<TranslationSettingsProps> = ({ onClos
             <Button
               variant="outline"
               onClick={handleTestConnection}
-              disabled={testingConnection || !config.enabled || !config.api_key.trim()}
+              disabled={testingConnection || !config.enabled || !isConfigured}
             >
               {testingConnection && <Loader2 className="mr-2 h-4 w-4 animate-spin" />}
               {t('translation.testConnection')}
             </Button>
           </div>
 
-          {!config.api_key.trim() && (
+          {needsApiKey && !config.api_key.trim() && (
             <Alert className="mt-4">
               <AlertTriangle className="h-4 w-4" />
               <AlertDescription>
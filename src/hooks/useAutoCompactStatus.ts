@@ -5,7 +5,7 @@ import { api } from '@/lib/api';
 /**
  * Compaction event types from backend
  */
-export type CompactionEventType = 'started' | 'in_progress' | 'completed' | 'failed';
+export type CompactionEventType = 'started' | 'in_progress' | 'completed' | 'failed' | 'measured';
 
 /**
  * Compaction event payload from Tauri
@@ -17,6 +17,8 @@ export interface CompactionEvent {
   message: string | null;
   tokens_before: number | null;
   tokens_after: number | null;
+  /** Id the CLI continued the session under, when compaction changed it */
+  new_session_id?: string | null;
 }
 
 /**
@@ -1650,7 +1650,7 @@ export function usePromptExecution(config: UsePromptExecutionConfig): UsePromptE
           } catch (resumeError) {
             console.warn('[usePromptExecution] Resume failed, falling back to continue mode:', resumeError);
             // Fallback to continue mode if resume fails
-            await api.continueClaudeCode(projectPath, processedPrompt, model, currentPlanMode, maxThinkingTokens, tabId);
+            await api.continueClaudeCode(projectPath, processedPrompt, model, currentPlanMode, maxThinkingTokens, tabId, effectiveSession.id);
           }
         } else {
           // Start new session
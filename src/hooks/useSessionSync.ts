@@ -97,7 +97,8 @@ export const useSessionSync = () => {
       try {
         unlisten = await listen<{
           session_id: string;
-          status: 'started' | 'stopped';
+          status: 'spawned' | 'started' | 'stopped' | 'cancelled';
+          tab_id?: string | null;
           success?: boolean;
           error?: string;
           project_path?: string;
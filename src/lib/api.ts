@@ -6,7 +6,7 @@ import { codexProviderPresets } from '@/config/codexProviderPresets';
 /** Process type for tracking in ProcessRegistry */
 export type ProcessType =
   | { AgentRun: { agent_id: number; agent_name: string } }
-  | { ClaudeSession: { session_id: string } };
+  | { ClaudeSession: { session_id: string; tab_id?: string } };
 
 /** Information about a running process */
 export interface ProcessInfo {
@@ -63,7 +63,7 @@ export interface Session {
  * Session conversion source information
  */
 export interface ConversionSource {
-  /** Source engine type: "claude" | "codex" */
+  /** Source engine type: "claude" | "codex" | "gemini" */
   engine: string;
   /** Source session ID */
   sessionId: string;
@@ -93,6 +93,142 @@ export interface ConversionResult {
   error?: string;
 }
 
+/**
+ * Engine that produced a session
+ */
+export type SessionEngine = "claude" | "codex" | "gemini";
+
+/**
+ * Token usage attached to a unified timeline event
+ */
+export interface UnifiedUsage {
+  input_tokens: number;
+  output_tokens: number;
+  cache_creation_tokens: number;
+  cache_read_tokens: number;
+}
+
+/**
+ * Event kinds of the unified session timeline
+ */
+export type UnifiedEventKind =
+  | { kind: "user_message"; text: string }
+  | { kind: "assistant_message"; text: string }
+  | { kind: "thinking"; text: string }
+  | { kind: "tool_call"; call_id: string; name: string; input: any }
+  | { kind: "tool_result"; call_id: string; output: string; is_error: boolean }
+  | { kind: "system"; subtype: string | null; text: string }
+  | { kind: "usage" };
+
+/**
+ * A single entry of the unified session timeline
+ */
+export type UnifiedEvent = UnifiedEventKind & {
+  index: number;
+  timestamp: string | null;
+  model?: string;
+  usage?: UnifiedUsage;
+};
+
+/**
+ * Claude / Codex / Gemini session normalised into one shape
+ */
+export interface UnifiedSession {
+  id: string;
+  engine: SessionEngine;
+  project_path: string | null;
+  source_path: string;
+  model: string | null;
+  started_at: string | null;
+  updated_at: string | null;
+  events: UnifiedEvent[];
+}
+
+/**
+ * Pointer to a session file on disk
+ */
+export interface SessionRef {
+  engine: SessionEngine;
+  session_id: string;
+  /** Claude project id, Codex cwd or Gemini project hash */
+  project_key: string | null;
+  path: string;
+  /** Modification time (Unix seconds) */
+  modified: number;
+  size: number;
+}
+
+/**
+ * Filters for full-text session search
+ */
+export interface SessionSearchFilters {
+  engines?: SessionEngine[];
+  project_path?: string;
+  /** Inclusive start date (YYYY-MM-DD or ISO 8601) */
+  start_date?: string;
+  /** Inclusive end date (YYYY-MM-DD or ISO 8601) */
+  end_date?: string;
+  roles?: Array<"user" | "assistant" | "tool">;
+}
+
+/**
+ * A matching event of a session search
+ */
+export interface SessionSearchHit {
+  engine: SessionEngine;
+  session_id: string;
+  project_path: string | null;
+  source_path: string;
+  event_index: number;
+  role: "user" | "assistant" | "tool";
+  tool_name: string | null;
+  timestamp: string | null;
+  /** Excerpt with matches wrapped in <mark> tags */
+  snippet: string;
+  rank: number;
+}
+
+/**
+ * Result of a search index refresh
+ */
+export interface SessionIndexStats {
+  scanned_files: number;
+  indexed_files: number;
+  removed_files: number;
+  indexed_events: number;
+  failed_files: number;
+  duration_ms: number;
+}
+
+export interface SessionIndexStatus {
+  files: number;
+  events: number;
+  last_indexed_at: string | null;
+}
+
+export type SessionExportFormat = "markdown" | "html" | "json";
+
+/**
+ * Options for session export
+ */
+export interface SessionExportOptions {
+  includeThinking?: boolean;
+  includeToolResults?: boolean;
+  includeSystem?: boolean;
+}
+
+/**
+ * Result of a session export
+ */
+export interface SessionExportResult {
+  format: SessionExportFormat;
+  /** Suggested file name */
+  fileName: string;
+  /** Rendered document (empty when written to outputPath) */
+  content: string;
+  outputPath: string | null;
+}
+
 /**
  * Represents the settings from ~/.claude/settings.json
  */
@@ -201,6 +337,103 @@ export interface RewindCapabilities {
   source: "project" | "cli";
 }
 
+/**
+ * A file changed by a prompt, between its two rewind checkpoints
+ */
+export interface CheckpointFileChange {
+  /** Path relative to the repository root */
+  path: string;
+  /** Git status letter (A, M, D, T) */
+  status: string;
+  /** Line counts; null for binary files */
+  additions: number | null;
+  deletions: number | null;
+  patch: string;
+  truncated: boolean;
+}
+
+/**
+ * Files changed by one prompt
+ */
+export interface PromptFileChanges {
+  promptIndex: number;
+  commitBefore: string;
+  commitAfter: string;
+  files: CheckpointFileChange[];
+}
+
+/**
+ * Outcome of a code rewind
+ */
+export interface RevertResult {
+  success: boolean;
+  /** Number of prompts whose changes were undone */
+  commitsReverted: number;
+  newCommit: string | null;
+  message: string;
+  hasConflicts: boolean;
+}
+
+/**
+ * When new sessions get their own git worktree
+ */
+export interface WorktreeConfig {
+  enabled: boolean;
+  /** Limit isolation to these projects; empty means every project */
+  projectPaths: string[];
+  /** Directory worktrees are created in (default ~/.anycode/worktrees) */
+  rootDir?: string | null;
+  /** Session branches are named `<prefix>/<worktree id>` */
+  branchPrefix: string;
+}
+
+/**
+ * A session worktree with its current git state
+ */
+export interface SessionWorktreeInfo {
+  id: string;
+  engine: "claude" | "codex" | "gemini";
+  projectPath: string;
+  worktreePath: string;
+  branch: string;
+  baseCommit: string;
+  sessionIds: string[];
+  createdAt: string;
+  exists: boolean;
+  /** Uncommitted changes in the worktree */
+  dirty: boolean;
+  /** Commits on the session branch the project HEAD does not have */
+  commitsAhead: number;
+  merged: boolean;
+}
+
+/**
+ * Changes of a session worktree against the project
+ */
+export interface WorktreeDiff {
+  base: string;
+  files: {
+    path: string;
+    /** Git status letter, `?` for untracked files */
+    status: string;
+    additions: number | null;
+    deletions: number | null;
+  }[];
+  patch: string;
+  truncated: boolean;
+}
+
+/**
+ * Outcome of merging a session branch back into the project
+ */
+export interface WorktreeMergeResult {
+  merged: boolean;
+  commit: string | null;
+  /** Conflicting files; the merge is backed out when there are any */
+  conflicts: string[];
+  message: string;
+}
+
 /**
  * Information about the safety of a git reset operation
  * Used to warn users when reverting might lose commits from other engines or user manual commits
@@ -303,6 +536,117 @@ export interface UsageStats {
   by_api_base_url?: ApiBaseUrlUsage[];
 }
 
+/** Scope of a budget rule */
+export type BudgetScope = 'global' | 'project' | 'model' | 'provider';
+
+/** A daily or monthly spend limit (stored in ~/.anycode/budgets.json) */
+export interface BudgetRule {
+  id: string;
+  name?: string;
+  scope: BudgetScope;
+  /** Project path, model name (substring) or provider config id */
+  target?: string;
+  /** Only count one engine; all engines when omitted */
+  engine?: 'claude' | 'codex' | 'gemini';
+  period: 'daily' | 'monthly';
+  limitUsd: number;
+  /** Fractions of the limit that raise a warning, e.g. [0.5, 0.8, 1.0] */
+  warnThresholds: number[];
+  /** Cancel the running session once the limit is exceeded */
+  hardCap: boolean;
+  enabled: boolean;
+}
+
+export interface BudgetConfig {
+  rules: BudgetRule[];
+}
+
+export interface BudgetStatus {
+  rule: BudgetRule;
+  periodStart: string;
+  spentUsd: number;
+  remainingUsd: number;
+  ratio: number;
+}
+
+/** Payload of the `budget-alert` event */
+export interface BudgetAlert {
+  ruleId: string;
+  ruleName?: string;
+  scope: BudgetScope;
+  target?: string;
+  period: 'daily' | 'monthly';
+  limitUsd: number;
+  spentUsd: number;
+  threshold: number;
+  exceeded: boolean;
+  cancelled: boolean;
+  engine: 'claude' | 'codex' | 'gemini';
+  sessionId: string;
+}
+
+export interface TokenRates {
+  input: number;
+  output: number;
+  cacheWrite: number;
+  cacheRead: number;
+}
+
+export interface PriceEntry {
+  id: string;
+  engine: 'claude' | 'codex' | 'gemini';
+  /** Case-insensitive regex searched for in the model name */
+  modelPattern: string;
+  providerId?: string | null;
+  /** YYYY-MM-DD, inclusive */
+  effectiveFrom?: string | null;
+  /** YYYY-MM-DD, inclusive */
+  effectiveUntil?: string | null;
+  rates: TokenRates;
+}
+
+export interface PricingConfig {
+  entries: PriceEntry[];
+}
+
+export interface ResolvedPrice {
+  entryId: string;
+  source: 'custom' | 'builtin';
+  rates: TokenRates;
+}
+
+export interface ContextWindowEntry {
+  id: string;
+  engine: 'claude' | 'codex' | 'gemini';
+  /** Case-insensitive regex searched for in the model name */
+  modelPattern: string;
+  /** Only applies to sessions run through this provider config */
+  providerId?: string | null;
+  /** Window size in tokens */
+  contextWindow: number;
+}
+
+export interface ContextWindowConfig {
+  entries: ContextWindowEntry[];
+}
+
+export interface ResolvedContextWindow {
+  entryId: string;
+  source: 'custom' | 'builtin';
+  contextWindow: number;
+}
+
+export type UsageReportFormat = 'csv' | 'json' | 'monthly_csv';
+
+export interface UsageReportResult {
+  format: UsageReportFormat;
+  /** Suggested file name */
+  fileName: string;
+  /** Rendered report (empty when written to outputPath) */
+  content: string;
+  outputPath: string | null;
+}
+
 export interface UsageOverview {
   total_cost: number;
   total_sessions: number;
@@ -529,6 +873,28 @@ export interface ServerStatus {
   last_checked?: number;
 }
 
+/**
+ * Result of a real MCP handshake health check
+ */
+export interface MCPHealthReport {
+  server_id: string;
+  transport: string;
+  success: boolean;
+  latency_ms?: number;
+  total_ms: number;
+  protocol_version?: string;
+  server_name?: string;
+  server_version?: string;
+  capabilities?: Record<string, any>;
+  tools: { name: string; description?: string; input_schema?: any }[];
+  resources: { uri: string; name: string; description?: string; mime_type?: string }[];
+  prompts: { name: string; description?: string; arguments: string[] }[];
+  error?: string;
+  warnings: string[];
+  stderr?: string;
+  checked_at: number;
+}
+
 /**
  * MCP configuration for project scope (.mcp.json)
  */
@@ -575,8 +941,21 @@ export interface TranslationConfig {
   model: string;
   timeout_seconds: number;
   cache_ttl_seconds: number;
+  /** Provider translations are sent to */
+  backend: TranslationBackendKind;
+  /** Shell command for the local backend; reads the prompt on stdin */
+  local_command: string;
+  /** Language other text is translated into (e.g. "zh") */
+  user_language: string;
+  /** Language text in the user language is translated into (e.g. "en") */
+  agent_language: string;
 }
 
+/**
+ * Translation provider: OpenAI-compatible, Anthropic Messages, DeepL or a local command
+ */
+export type TranslationBackendKind = "openai" | "anthropic" | "deepl" | "local";
+
 /**
  * Translation cache statistics
  */
@@ -586,6 +965,26 @@ export interface TranslationCacheStats {
   active_entries: number;
 }
 
+/**
+ * Retrieval backend for project context in prompt enhancement
+ */
+export type ContextBackend = "acemcp" | "local";
+
+/**
+ * Code snippet found by a prompt enhancement context search
+ */
+export interface ContextSnippet {
+  id: string;
+  path: string;
+  startLine?: number | null;
+  endLine?: number | null;
+  /** Relevance score (local index only) */
+  score?: number | null;
+  /** 1-based search round that found the snippet */
+  round: number;
+  query: string;
+  content: string;
+}
 
 /**
  * Auto-compact configuration
@@ -593,8 +992,10 @@ export interface TranslationCacheStats {
 export interface AutoCompactConfig {
   /** Enable automatic compaction */
   enabled: boolean;
-  /** Maximum context tokens before triggering compaction */
+  /** Context window used when use_model_context_windows is off */
   max_context_tokens: number;
+  /** Size each session by its model's context window */
+  use_model_context_windows?: boolean;
   /** Threshold percentage to trigger compaction (0.0-1.0) */
   compaction_threshold: number;
   /** Minimum time between compactions in seconds */
@@ -628,8 +1029,12 @@ export interface SessionContext {
   message_count: number;
   last_compaction?: string; // ISO timestamp
   compaction_count: number;
+  engine?: 'claude' | 'codex' | 'gemini';
   model: string;
+  provider_id?: string | null;
+  context_window?: number; // Window the compaction threshold is computed from
   status: SessionStatus;
+  pre_compaction_tokens?: number | null; // Set until the turn after a compaction reports its size
 }
 
 /**
@@ -638,9 +1043,48 @@ export interface SessionContext {
 export type SessionStatus =
   | 'Active'
   | 'Idle'
+  | 'CompactionPending'
   | 'Compacting'
   | { CompactionFailed: string };
 
+/**
+ * A saved compaction attempt of a session
+ */
+export interface CompactionRecord {
+  id: number;
+  session_id: string;
+  success: boolean;
+  tokens_before: number | null;
+  tokens_after: number | null; // Reported by the CLI or measured on the next turn
+  error: string | null;
+  created_at: string;
+}
+
+/**
+ * Context size reported by one turn of a session
+ */
+export interface TokenSample {
+  tokens: number;
+  context_window: number;
+  recorded_at: string;
+}
+
+/**
+ * Compaction history of a session over the queried period
+ */
+export interface SessionCompactionHistory {
+  session_id: string;
+  project_path: string;
+  model: string;
+  compactions: number;
+  failures: number;
+  average_interval_secs: number | null;
+  average_reduction: number | null; // Share of the context removed (0-1)
+  last_compaction_at: string | null;
+  records: CompactionRecord[];
+  token_history: TokenSample[]; // Only filled when a single session is queried
+}
+
 /**
  * Auto-compact status information
  */
@@ -1086,9 +1530,10 @@ export const api = {
    * Continues an existing Claude Code conversation with streaming output
    * @param planMode - Enable Plan Mode for read-only research and planning
    * @param tabId - Unique identifier for the tab, used to filter global events
+   * @param sessionId - Session being continued, so one that ran in a worktree continues there
    */
-  async continueClaudeCode(projectPath: string, prompt: string, model: string, planMode?: boolean, maxThinkingTokens?: number, tabId?: string): Promise<void> {
-    return invoke("continue_claude_code", { projectPath, prompt, model, planMode, maxThinkingTokens, tabId });
+  async continueClaudeCode(projectPath: string, prompt: string, model: string, planMode?: boolean, maxThinkingTokens?: number, tabId?: string, sessionId?: string): Promise<void> {
+    return invoke("continue_claude_code", { projectPath, prompt, model, planMode, maxThinkingTokens, tabId, sessionId });
   },
 
   /**
@@ -1101,8 +1546,8 @@ export const api = {
   },
 
   /**
-   * Cancels the currently running Claude Code execution
-   * @param sessionId - Optional session ID to cancel a specific session
+   * Cancels a running Claude Code execution
+   * @param sessionId - Optional session or tab ID; defaults to the most recently started run
    */
   async cancelClaudeExecution(sessionId?: string): Promise<void> {
     return invoke("cancel_claude_execution", { sessionId });
@@ -1116,6 +1561,14 @@ export const api = {
     return invoke("list_running_claude_sessions");
   },
 
+  /**
+   * Lists all running Claude runs, including ones still waiting for a session ID
+   * @returns Promise resolving to list of running Claude runs
+   */
+  async listRunningClaudeRuns(): Promise<ProcessInfo[]> {
+    return invoke("list_running_claude_runs");
+  },
+
   /**
    * Gets live output from a Claude session
    * @param sessionId - The session ID to get output for
@@ -1192,6 +1645,211 @@ export const api = {
     }
   },
 
+  /**
+   * Gets the budget rules
+   * @returns Promise resolving to the budget configuration
+   */
+  async getBudgetConfig(): Promise<BudgetConfig> {
+    try {
+      return await invoke<BudgetConfig>("get_budget_config");
+    } catch (error) {
+      console.error("Failed to get budget config:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Saves the budget rules (ids are assigned to new rules)
+   * @param config - Budget configuration to save
+   * @returns Promise resolving to the saved configuration
+   */
+  async saveBudgetConfig(config: BudgetConfig): Promise<BudgetConfig> {
+    try {
+      return await invoke<BudgetConfig>("save_budget_config", { config });
+    } catch (error) {
+      console.error("Failed to save budget config:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets the spend of every budget rule in its current period
+   * @returns Promise resolving to budget statuses
+   */
+  async getBudgetStatus(): Promise<BudgetStatus[]> {
+    try {
+      return await invoke<BudgetStatus[]>("get_budget_status");
+    } catch (error) {
+      console.error("Failed to get budget status:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Exports a usage report (per-session rows plus totals) across engines
+   * @param startDate - First local date (YYYY-MM-DD)
+   * @param endDate - Last local date (YYYY-MM-DD)
+   * @param format - 'csv', 'json' or 'monthly_csv' (month × project/model rows)
+   * @param engines - Optional engines to include (all when omitted)
+   * @param outputPath - Optional file to write the report to
+   * @returns Promise resolving to the export result
+   */
+  async exportUsageReport(
+    startDate: string,
+    endDate: string,
+    format: UsageReportFormat,
+    engines?: SessionEngine[],
+    outputPath?: string
+  ): Promise<UsageReportResult> {
+    try {
+      return await invoke<UsageReportResult>("export_usage_report", {
+        startDate,
+        endDate,
+        format,
+        engines,
+        outputPath,
+      });
+    } catch (error) {
+      console.error("Failed to export usage report:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets the custom price entries
+   * @returns Promise resolving to the pricing configuration
+   */
+  async getPricingConfig(): Promise<PricingConfig> {
+    try {
+      return await invoke<PricingConfig>("get_pricing_config");
+    } catch (error) {
+      console.error("Failed to get pricing config:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Saves the custom price entries and reprices the usage already ingested
+   * @param config - Pricing configuration to save
+   * @returns Promise resolving to the saved configuration
+   */
+  async savePricingConfig(config: PricingConfig): Promise<PricingConfig> {
+    try {
+      return await invoke<PricingConfig>("save_pricing_config", { config });
+    } catch (error) {
+      console.error("Failed to save pricing config:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Recomputes the cost of all ingested usage with the current prices
+   * @returns Promise resolving to the number of rows whose cost changed
+   */
+  async repriceUsageEntries(): Promise<number> {
+    try {
+      return await invoke<number>("reprice_usage_entries");
+    } catch (error) {
+      console.error("Failed to reprice usage entries:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets the built-in list prices
+   * @returns Promise resolving to the built-in price entries
+   */
+  async getBuiltinPricing(): Promise<PriceEntry[]> {
+    try {
+      return await invoke<PriceEntry[]>("get_builtin_pricing");
+    } catch (error) {
+      console.error("Failed to get builtin pricing:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Resolves the price that applies to a model
+   * @param engine - Engine that runs the model
+   * @param model - Model name
+   * @param providerId - Optional provider config id
+   * @param date - Optional date (YYYY-MM-DD), defaults to today
+   * @returns Promise resolving to the matching price, or null
+   */
+  async resolveModelPricing(
+    engine: 'claude' | 'codex' | 'gemini',
+    model: string,
+    providerId?: string,
+    date?: string
+  ): Promise<ResolvedPrice | null> {
+    try {
+      return await invoke<ResolvedPrice | null>("resolve_model_pricing", { engine, model, providerId, date });
+    } catch (error) {
+      console.error("Failed to resolve model pricing:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets the custom context window entries
+   * @returns Promise resolving to the context window configuration
+   */
+  async getContextWindowConfig(): Promise<ContextWindowConfig> {
+    try {
+      return await invoke<ContextWindowConfig>("get_context_window_config");
+    } catch (error) {
+      console.error("Failed to get context window config:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Saves the custom context window entries
+   * @param config - Context window configuration to save
+   * @returns Promise resolving to the saved configuration
+   */
+  async saveContextWindowConfig(config: ContextWindowConfig): Promise<ContextWindowConfig> {
+    try {
+      return await invoke<ContextWindowConfig>("save_context_window_config", { config });
+    } catch (error) {
+      console.error("Failed to save context window config:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets the built-in context windows
+   * @returns Promise resolving to the built-in entries
+   */
+  async getBuiltinContextWindows(): Promise<ContextWindowEntry[]> {
+    try {
+      return await invoke<ContextWindowEntry[]>("get_builtin_context_windows");
+    } catch (error) {
+      console.error("Failed to get builtin context windows:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Resolves the context window that applies to a model
+   * @param engine - Engine that runs the model
+   * @param model - Model name
+   * @param providerId - Optional provider config id
+   * @returns Promise resolving to the matching window, or null
+   */
+  async resolveContextWindow(
+    engine: 'claude' | 'codex' | 'gemini',
+    model: string,
+    providerId?: string
+  ): Promise<ResolvedContextWindow | null> {
+    try {
+      return await invoke<ResolvedContextWindow | null>("resolve_context_window", { engine, model, providerId });
+    } catch (error) {
+      console.error("Failed to resolve context window:", error);
+      throw error;
+    }
+  },
+
 
 
 
@@ -1259,6 +1917,139 @@ export const api = {
     }
   },
 
+  // ============================================================================
+  // UNIFIED SESSION TIMELINE
+  // ============================================================================
+
+  /**
+   * Loads a session from any engine as a unified timeline
+   * @param engine - "claude" | "codex" | "gemini"
+   * @param sessionId - The session ID
+   * @param projectHint - Claude project id or Gemini project path (speeds up lookup)
+   */
+  async loadUnifiedSession(
+    engine: SessionEngine,
+    sessionId: string,
+    projectHint?: string
+  ): Promise<UnifiedSession> {
+    try {
+      return await invoke<UnifiedSession>("load_unified_session", {
+        engine,
+        sessionId,
+        projectHint,
+      });
+    } catch (error) {
+      console.error("Failed to load unified session:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Lists session files, newest first
+   * @param engine - Restrict to one engine; all engines when omitted
+   */
+  async listUnifiedSessions(engine?: SessionEngine): Promise<SessionRef[]> {
+    try {
+      return await invoke<SessionRef[]>("list_unified_sessions", { engine });
+    } catch (error) {
+      console.error("Failed to list unified sessions:", error);
+      throw error;
+    }
+  },
+
+  // ============================================================================
+  // SESSION SEARCH
+  // ============================================================================
+
+  /**
+   * Incrementally indexes session files for full-text search
+   * @param engines - Engines to index (all when omitted)
+   * @param full - Re-read every file instead of only changed ones
+   */
+  async refreshSessionSearchIndex(
+    engines?: SessionEngine[],
+    full?: boolean
+  ): Promise<SessionIndexStats> {
+    try {
+      return await invoke<SessionIndexStats>("refresh_session_search_index", { engines, full });
+    } catch (error) {
+      console.error("Failed to refresh session search index:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Searches prompts, assistant replies and tool calls across all engines
+   */
+  async searchSessions(
+    query: string,
+    filters?: SessionSearchFilters,
+    limit?: number,
+    offset?: number
+  ): Promise<SessionSearchHit[]> {
+    try {
+      return await invoke<SessionSearchHit[]>("search_sessions", { query, filters, limit, offset });
+    } catch (error) {
+      console.error("Failed to search sessions:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets the number of indexed files and events
+   */
+  async getSessionSearchStatus(): Promise<SessionIndexStatus> {
+    try {
+      return await invoke<SessionIndexStatus>("get_session_search_status");
+    } catch (error) {
+      console.error("Failed to get session search status:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Clears the session search index
+   */
+  async clearSessionSearchIndex(): Promise<void> {
+    try {
+      return await invoke<void>("clear_session_search_index");
+    } catch (error) {
+      console.error("Failed to clear session search index:", error);
+      throw error;
+    }
+  },
+
+  // ============================================================================
+  // SESSION EXPORT
+  // ============================================================================
+
+  /**
+   * Exports a session as Markdown, self-contained HTML or a JSON bundle
+   * @param outputPath - Write the document to this path instead of returning it
+   */
+  async exportSession(
+    engine: SessionEngine,
+    sessionId: string,
+    format: SessionExportFormat,
+    projectHint?: string,
+    outputPath?: string,
+    options?: SessionExportOptions
+  ): Promise<SessionExportResult> {
+    try {
+      return await invoke<SessionExportResult>("export_session", {
+        engine,
+        sessionId,
+        format,
+        projectHint,
+        outputPath,
+        options,
+      });
+    } catch (error) {
+      console.error("Failed to export session:", error);
+      throw error;
+    }
+  },
+
   // ============================================================================
   // MCP SERVER OPERATIONS
   // ============================================================================
@@ -1366,9 +2157,13 @@ export const api = {
   /**
    * Tests connection to an MCP server
    */
-  async mcpTestConnection(name: string): Promise<string> {
+  async mcpTestConnection(
+    name: string,
+    engine?: string,
+    timeoutSecs?: number
+  ): Promise<MCPHealthReport> {
     try {
-      return await invoke<string>("mcp_test_connection", { name });
+      return await invoke<MCPHealthReport>("mcp_test_connection", { name, engine, timeoutSecs });
     } catch (error) {
       console.error("Failed to test MCP connection:", error);
       throw error;
@@ -2177,6 +2972,8 @@ export const api = {
     enhancedPrompt: string;
     contextCount: number;
     acemcpUsed: boolean;
+    backend: ContextBackend | null;
+    snippets: ContextSnippet[];
     error?: string;
   }> {
     try {
@@ -2194,6 +2991,35 @@ export const api = {
     }
   },
 
+  /**
+   * Formats the snippets kept by the user as the project context of a prompt,
+   * each cited by `path:start-end`
+   * @param snippets - Snippets from enhancePromptWithContext to include
+   * @returns Promise resolving to the context block (empty if no snippets)
+   */
+  async formatEnhancementContext(snippets: ContextSnippet[]): Promise<string> {
+    try {
+      return await invoke<string>("format_enhancement_context", { snippets });
+    } catch (error) {
+      console.error("Failed to format enhancement context:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Cancels the context search of a running prompt enhancement
+   * @param projectPath - Project the enhancement runs for
+   * @returns Promise resolving to true if an enhancement of the project was cancelled
+   */
+  async cancelPromptEnhancement(projectPath: string): Promise<boolean> {
+    try {
+      return await invoke<boolean>("cancel_prompt_enhancement", { projectPath });
+    } catch (error) {
+      console.error("Failed to cancel prompt enhancement:", error);
+      return false;
+    }
+  },
+
   /**
    * Tests if acemcp is available and can be used
    * @returns Promise resolving to true if acemcp is available
@@ -2214,7 +3040,8 @@ export const api = {
     baseUrl: string,
     token: string,
     batchSize?: number,
-    maxLinesPerBlob?: number
+    maxLinesPerBlob?: number,
+    backend?: ContextBackend
   ): Promise<void> {
     try {
       return await invoke("save_acemcp_config", {
@@ -2222,6 +3049,7 @@ export const api = {
         token,
         batchSize,
         maxLinesPerBlob,
+        backend,
       });
     } catch (error) {
       console.error("Failed to save acemcp config:", error);
@@ -2237,6 +3065,7 @@ export const api = {
     token: string;
     batchSize?: number;
     maxLinesPerBlob?: number;
+    backend: ContextBackend;
   }> {
     try {
       return await invoke("load_acemcp_config");
@@ -2248,6 +3077,7 @@ export const api = {
         token: '',
         batchSize: 10,
         maxLinesPerBlob: 800,
+        backend: 'acemcp',
       };
     }
   },
@@ -2425,11 +3255,12 @@ export const api = {
    * @param sessionId - The session ID to register
    * @param projectPath - The project path
    * @param model - The model being used
+   * @param engine - CLI the session runs in (defaults to Claude), for its model's context window
    * @returns Promise resolving when session is registered
    */
-  async registerAutoCompactSession(sessionId: string, projectPath: string, model: string): Promise<void> {
+  async registerAutoCompactSession(sessionId: string, projectPath: string, model: string, engine?: 'claude' | 'codex' | 'gemini'): Promise<void> {
     try {
-      return await invoke<void>("register_auto_compact_session", { sessionId, projectPath, model });
+      return await invoke<void>("register_auto_compact_session", { sessionId, projectPath, model, engine });
     } catch (error) {
       console.error("Failed to register auto-compact session:", error);
       throw error;
@@ -2498,11 +3329,26 @@ export const api = {
    * @param sessionId - The session ID
    * @returns Promise resolving to session context information
    */
-  async getSessionContextStats(sessionId: string): Promise<SessionContext | null> {
+  async getSessionContextStats(sessionId: string): Promise<SessionContext | null> {
+    try {
+      return await invoke<SessionContext | null>("get_session_context_stats", { sessionId });
+    } catch (error) {
+      console.error("Failed to get session context stats:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets the saved compaction history per session, most frequently compacting first
+   * @param sessionId - Only this session, including its token history
+   * @param days - Period to look back over (default 7)
+   * @returns Promise resolving to the history of each session
+   */
+  async getCompactionHistory(sessionId?: string, days?: number): Promise<SessionCompactionHistory[]> {
     try {
-      return await invoke<SessionContext | null>("get_session_context_stats", { sessionId });
+      return await invoke<SessionCompactionHistory[]>("get_compaction_history", { sessionId, days });
     } catch (error) {
-      console.error("Failed to get session context stats:", error);
+      console.error("Failed to get compaction history:", error);
       throw error;
     }
   },
@@ -2627,6 +3473,76 @@ export const api = {
     }
   },
 
+  /**
+   * Gets the persisted enhanced hooks of a project, fired by the backend on
+   * session start/end, context compaction and file changes
+   * @param projectPath - The project path
+   * @returns Promise resolving to the project's hooks config
+   */
+  async getProjectEnhancedHooks(
+    projectPath: string
+  ): Promise<import('@/types/enhanced-hooks').ProjectHooksConfig> {
+    try {
+      return await invoke<import('@/types/enhanced-hooks').ProjectHooksConfig>(
+        "get_project_enhanced_hooks",
+        { projectPath }
+      );
+    } catch (error) {
+      console.error("Failed to get project enhanced hooks:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Saves the enhanced hooks of a project and restarts its file watcher
+   * @param config - The project's hooks config
+   */
+  async saveProjectEnhancedHooks(
+    config: import('@/types/enhanced-hooks').ProjectHooksConfig
+  ): Promise<void> {
+    try {
+      await invoke("save_project_enhanced_hooks", { config });
+    } catch (error) {
+      console.error("Failed to save project enhanced hooks:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets the persisted enhanced hook execution log, newest first
+   * @param sessionId - Only return executions of this session
+   * @param limit - Maximum number of entries (defaults to 100)
+   * @returns Promise resolving to log entries
+   */
+  async getHookExecutionLog(
+    sessionId?: string,
+    limit?: number
+  ): Promise<import('@/types/enhanced-hooks').HookExecutionLogEntry[]> {
+    try {
+      return await invoke<import('@/types/enhanced-hooks').HookExecutionLogEntry[]>(
+        "get_hook_execution_log",
+        { sessionId, limit }
+      );
+    } catch (error) {
+      console.error("Failed to get hook execution log:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Clears the enhanced hook execution log
+   * @param sessionId - Only clear executions of this session
+   * @returns Promise resolving to the number of removed entries
+   */
+  async clearHookExecutionLog(sessionId?: string): Promise<number> {
+    try {
+      return await invoke<number>("clear_hook_execution_log", { sessionId });
+    } catch (error) {
+      console.error("Failed to clear hook execution log:", error);
+      throw error;
+    }
+  },
+
   /**
    * Executes pre-commit code review hook with intelligent decision making
    * @param projectPath - The project path to review
@@ -2648,6 +3564,53 @@ export const api = {
     }
   },
 
+  /**
+   * Installs the pre-commit review as the repository's `.git/hooks/pre-commit`
+   * @param projectPath - The repository path
+   * @param config - Review configuration saved for the hook
+   */
+  async installPreCommitHook(
+    projectPath: string,
+    config?: import('@/types/enhanced-hooks').PreCommitCodeReviewConfig
+  ): Promise<void> {
+    try {
+      await invoke("install_pre_commit_hook", { projectPath, config });
+    } catch (error) {
+      console.error("Failed to install pre-commit hook:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Removes a pre-commit hook installed by installPreCommitHook
+   * @param projectPath - The repository path
+   * @returns Promise resolving to whether a hook was removed
+   */
+  async uninstallPreCommitHook(projectPath: string): Promise<boolean> {
+    try {
+      return await invoke<boolean>("uninstall_pre_commit_hook", { projectPath });
+    } catch (error) {
+      console.error("Failed to uninstall pre-commit hook:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets whether the pre-commit review hook is installed and its saved configuration
+   * @param projectPath - The repository path
+   */
+  async getPreCommitHookStatus(projectPath: string): Promise<{
+    installed: boolean;
+    config: import('@/types/enhanced-hooks').PreCommitCodeReviewConfig | null;
+  }> {
+    try {
+      return await invoke("get_pre_commit_hook_status", { projectPath });
+    } catch (error) {
+      console.error("Failed to get pre-commit hook status:", error);
+      throw error;
+    }
+  },
+
   // ==================== Checkpoint API Methods ====================
 
   /**
@@ -2675,18 +3638,6 @@ export const api = {
 
   // ==================== Prompt Revert System ====================
 
-  /**
-   * Check and initialize Git repository
-   */
-  async checkAndInitGit(projectPath: string): Promise<boolean> {
-    try {
-      return await invoke<boolean>("check_and_init_git", { projectPath });
-    } catch (error) {
-      console.error("Failed to check/init Git:", error);
-      return false;
-    }
-  },
-
   /**
    * Check if a git reset operation is safe
    * This prevents accidentally reverting to a much older version when
@@ -2852,6 +3803,176 @@ export const api = {
     }
   },
 
+  /**
+   * List the files changed by each prompt of a session, with per-file diffs
+   * @param engine - "claude", "codex" or "gemini"
+   * @param projectId - Claude project id; derived from projectPath when omitted
+   * @param promptIndex - Only list this prompt
+   */
+  async getPromptFileChanges(
+    engine: "claude" | "codex" | "gemini",
+    sessionId: string,
+    projectPath: string,
+    projectId?: string,
+    promptIndex?: number
+  ): Promise<PromptFileChanges[]> {
+    try {
+      return await invoke<PromptFileChanges[]>("get_prompt_file_changes", {
+        engine,
+        sessionId,
+        projectId,
+        projectPath,
+        promptIndex,
+      });
+    } catch (error) {
+      console.error("Failed to get prompt file changes:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Restore only the given files to their state before a prompt, keeping the
+   * agent's changes to all other files
+   * @param paths - Repository-relative paths from getPromptFileChanges
+   */
+  async revertPromptFiles(
+    engine: "claude" | "codex" | "gemini",
+    sessionId: string,
+    projectPath: string,
+    promptIndex: number,
+    paths: string[],
+    projectId?: string
+  ): Promise<RevertResult> {
+    try {
+      return await invoke<RevertResult>("revert_prompt_files", {
+        engine,
+        sessionId,
+        projectId,
+        projectPath,
+        promptIndex,
+        paths,
+      });
+    } catch (error) {
+      console.error("Failed to revert prompt files:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Delete rewind checkpoints of a project older than the given age; prompts
+   * from before then can no longer be rewound
+   * @param olderThanDays - Defaults to 30 days
+   * @returns Number of checkpoints deleted
+   */
+  async pruneCheckpoints(projectPath: string, olderThanDays?: number): Promise<number> {
+    try {
+      return await invoke<number>("prune_checkpoints", { projectPath, olderThanDays });
+    } catch (error) {
+      console.error("Failed to prune checkpoints:", error);
+      throw error;
+    }
+  },
+
+  // ==================== Session Worktrees ====================
+
+  /**
+   * Get the session worktree isolation settings
+   */
+  async getWorktreeConfig(): Promise<WorktreeConfig> {
+    try {
+      return await invoke<WorktreeConfig>("get_worktree_config");
+    } catch (error) {
+      console.error("Failed to get worktree config:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Save the session worktree isolation settings
+   */
+  async saveWorktreeConfig(config: WorktreeConfig): Promise<WorktreeConfig> {
+    try {
+      return await invoke<WorktreeConfig>("save_worktree_config", { config });
+    } catch (error) {
+      console.error("Failed to save worktree config:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * List session worktrees, optionally of one project
+   */
+  async listSessionWorktrees(projectPath?: string): Promise<SessionWorktreeInfo[]> {
+    try {
+      return await invoke<SessionWorktreeInfo[]>("list_session_worktrees", { projectPath });
+    } catch (error) {
+      console.error("Failed to list session worktrees:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Get the changes of a session worktree, committed or not
+   */
+  async getSessionWorktreeDiff(worktreeId: string): Promise<WorktreeDiff> {
+    try {
+      return await invoke<WorktreeDiff>("get_session_worktree_diff", { worktreeId });
+    } catch (error) {
+      console.error("Failed to get session worktree diff:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Merge a session branch into the project's current branch
+   * @param squash - Squash the session's commits into one
+   */
+  async mergeSessionWorktree(
+    worktreeId: string,
+    squash?: boolean,
+    message?: string
+  ): Promise<WorktreeMergeResult> {
+    try {
+      return await invoke<WorktreeMergeResult>("merge_session_worktree", {
+        worktreeId,
+        squash,
+        message
+      });
+    } catch (error) {
+      console.error("Failed to merge session worktree:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Remove a session worktree and optionally its branch
+   */
+  async removeSessionWorktree(
+    worktreeId: string,
+    deleteBranch?: boolean,
+    force?: boolean
+  ): Promise<void> {
+    try {
+      await invoke("remove_session_worktree", { worktreeId, deleteBranch, force });
+    } catch (error) {
+      console.error("Failed to remove session worktree:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Remove merged, clean session worktrees and forget missing ones
+   * @returns IDs of the removed worktrees
+   */
+  async cleanupSessionWorktrees(projectPath?: string): Promise<string[]> {
+    try {
+      return await invoke<string[]>("cleanup_session_worktrees", { projectPath });
+    } catch (error) {
+      console.error("Failed to clean up session worktrees:", error);
+      throw error;
+    }
+  },
+
   // ==================== Claude Extensions (Plugins, Subagents & Skills) ====================
 
   /**
@@ -4006,20 +5127,20 @@ export const api = {
   },
 
   // ============================================================================
-  // Session Conversion (Claude ↔ Codex)
+  // Session Conversion (Claude ↔ Codex ↔ Gemini)
   // ============================================================================
 
   /**
-   * Convert a session between Claude and Codex formats
+   * Convert a session between Claude, Codex and Gemini formats
    * @param sessionId - The source session ID
-   * @param targetEngine - The target engine ('claude' | 'codex')
+   * @param targetEngine - The target engine ('claude' | 'codex' | 'gemini')
    * @param projectId - The project ID (directory name)
    * @param projectPath - The project path
    * @returns Promise resolving to conversion result
    */
   async convertSession(
     sessionId: string,
-    targetEngine: 'claude' | 'codex',
+    targetEngine: 'claude' | 'codex' | 'gemini',
     projectId: string,
     projectPath: string
   ): Promise<ConversionResult> {
@@ -4084,6 +5205,102 @@ export const api = {
     }
   },
 
+  /**
+   * Convert a Gemini session to Claude format
+   * @param sessionId - The Gemini session ID
+   * @param projectId - The project ID (directory name)
+   * @param projectPath - The project path (locates the Gemini chat directory)
+   * @returns Promise resolving to conversion result
+   */
+  async convertGeminiToClaude(
+    sessionId: string,
+    projectId: string,
+    projectPath: string
+  ): Promise<ConversionResult> {
+    try {
+      return await invoke<ConversionResult>("convert_gemini_to_claude", {
+        sessionId,
+        projectId,
+        projectPath,
+      });
+    } catch (error) {
+      console.error("Failed to convert Gemini to Claude:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Convert a Gemini session to Codex format
+   * @param sessionId - The Gemini session ID
+   * @param projectId - The project ID (directory name)
+   * @param projectPath - The project path (locates the Gemini chat directory)
+   * @returns Promise resolving to conversion result
+   */
+  async convertGeminiToCodex(
+    sessionId: string,
+    projectId: string,
+    projectPath: string
+  ): Promise<ConversionResult> {
+    try {
+      return await invoke<ConversionResult>("convert_gemini_to_codex", {
+        sessionId,
+        projectId,
+        projectPath,
+      });
+    } catch (error) {
+      console.error("Failed to convert Gemini to Codex:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Convert a Claude session to Gemini format
+   * @param sessionId - The Claude session ID (UUID format)
+   * @param projectId - The project ID (directory name)
+   * @param projectPath - The project path (locates the Gemini chat directory)
+   * @returns Promise resolving to conversion result
+   */
+  async convertClaudeToGemini(
+    sessionId: string,
+    projectId: string,
+    projectPath: string
+  ): Promise<ConversionResult> {
+    try {
+      return await invoke<ConversionResult>("convert_claude_to_gemini", {
+        sessionId,
+        projectId,
+        projectPath,
+      });
+    } catch (error) {
+      console.error("Failed to convert Claude to Gemini:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Convert a Codex session to Gemini format
+   * @param sessionId - The Codex session ID (rollout-* format)
+   * @param projectId - The project ID (directory name)
+   * @param projectPath - The project path (locates the Gemini chat directory)
+   * @returns Promise resolving to conversion result
+   */
+  async convertCodexToGemini(
+    sessionId: string,
+    projectId: string,
+    projectPath: string
+  ): Promise<ConversionResult> {
+    try {
+      return await invoke<ConversionResult>("convert_codex_to_gemini", {
+        sessionId,
+        projectId,
+        projectPath,
+      });
+    } catch (error) {
+      console.error("Failed to convert Codex to Gemini:", error);
+      throw error;
+    }
+  },
+
   // ==================== Google Gemini CLI Integration ====================
 
   /**
@@ -1,6 +1,6 @@
 /**
  * 统一的 AI 模型定价模块
- * ⚠️ MUST MATCH: src-tauri/src/commands/usage.rs::ModelPricing
+ * ⚠️ MUST MATCH: src-tauri/src/commands/pricing.rs::BUILTIN_PRICES
  *
  * Claude 定价：https://platform.claude.com/docs/en/about-claude/pricing
  * Codex 定价：https://platform.openai.com/docs/pricing (codex-mini-latest)
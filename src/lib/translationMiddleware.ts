@@ -29,8 +29,8 @@ interface QueueItem {
  * 翻译中间件 - 提供透明的中英文翻译功能 (性能优化版)
  *
  * 核心功能：
- * 1. 中文输入自动翻译为英文发送给Claude API
- * 2. Claude英文响应自动翻译为中文显示给用户
+ * 1. 用户语言输入自动翻译为 AI 语言发送给Claude API（默认中文 -> 英文）
+ * 2. Claude的 AI 语言响应自动翻译为用户语言显示给用户
  * 3. 对用户完全透明
  * 4. 智能速率限制管理 (RPM: 1,000, TPM: 80,000)
  * 5. 请求队列和批处理优化
@@ -419,6 +419,10 @@ export class TranslationMiddleware {
         model: "tencent/Hunyuan-MT-7B",
         timeout_seconds: 30,
         cache_ttl_seconds: 3600,
+        backend: "openai",
+        local_command: "",
+        user_language: "zh",
+        agent_language: "en",
       };
       this.initialized = true;
       
@@ -455,6 +459,26 @@ export class TranslationMiddleware {
     }
   }
 
+  /**
+   * 比较语言代码的主语言部分（zh-TW 与 zh 视为同一种语言）
+   */
+  private isSameLanguage(a: string | undefined, b: string | undefined): boolean {
+    if (!a || !b) {
+      return false;
+    }
+    return a.split('-')[0].toLowerCase() === b.split('-')[0].toLowerCase();
+  }
+
+  /**
+   * 配置的用户语言与 AI 语言
+   */
+  private getLanguagePair(): { user: string; agent: string } {
+    return {
+      user: this.config?.user_language || 'zh',
+      agent: this.config?.agent_language || 'en',
+    };
+  }
+
   /**
    * 改进的中文内容检测，更智能地处理混合内容
    */
@@ -543,11 +567,11 @@ export class TranslationMiddleware {
   }
 
   /**
-   * 翻译用户输入（中文->英文）
+   * 翻译用户输入（用户语言->AI 语言）
    *
    * 在发送给Claude API之前调用此方法
-   * 如果输入是中文，则翻译为英文
-   * 如果输入已经是英文或翻译功能未启用，则直接返回原文
+   * 如果输入是配置的用户语言，则翻译为 AI 语言
+   * 如果输入已经是其他语言或翻译功能未启用，则直接返回原文
    *
    * 特殊处理：
    * - 跳过斜杠命令（以 / 开头的命令）的翻译，保持原样传递
@@ -588,22 +612,26 @@ export class TranslationMiddleware {
     }
 
     try {
+      const { user, agent } = this.getLanguagePair();
+
       // 检测语言
       const detectedLanguage = await this.detectLanguage(userInput);
-      // 改进的中文检测策略：同时使用语言代码检测和内容检测
-      const isChineseByCode = detectedLanguage?.toLowerCase().startsWith('zh');
-      const isChineseByContent = this.detectChineseContent(userInput);
+      let shouldTranslate = this.isSameLanguage(detectedLanguage, user);
 
-      // 优先信任内容检测，因为它更准确
-      const isAsciiOnly = /^[\u0000-\u007F]*$/.test(userInput);
-      const shouldTranslate = isChineseByContent || (isChineseByCode && !isAsciiOnly);
+      // 用户语言为中文时沿用改进的中文检测策略：同时使用语言代码检测和内容检测
+      if (this.isSameLanguage(user, 'zh')) {
+        const isChineseByCode = detectedLanguage?.toLowerCase().startsWith('zh');
+        const isChineseByContent = this.detectChineseContent(userInput);
 
-      
+        // 优先信任内容检测，因为它更准确
+        const isAsciiOnly = /^[\u0000-\u007F]*$/.test(userInput);
+        shouldTranslate = isChineseByContent || (isChineseByCode && !isAsciiOnly);
+      }
 
-      // 如果检测到中文，使用队列化翻译为英文
-      if (shouldTranslate) {
+      // 如果检测到用户语言，使用队列化翻译为 AI 语言
+      if (shouldTranslate && !this.isSameLanguage(user, agent)) {
         try {
-          const translatedText = await this.queueTranslation(userInput, 'en', 3); // 高优先级
+          const translatedText = await this.queueTranslation(userInput, agent, 3); // 高优先级
 
           // 验证翻译结果不为空且不等于原文
           if (translatedText && translatedText.trim() !== userInput.trim()) {
@@ -623,7 +651,7 @@ export class TranslationMiddleware {
         }
       }
 
-      // 如果已经是英文或其他语言，直接返回
+      // 如果已经是 AI 语言或其他语言，直接返回
       return {
         translatedText: userInput,
         originalText: userInput,
@@ -644,10 +672,10 @@ export class TranslationMiddleware {
   }
 
   /**
-   * 翻译Claude响应（英文->中文）
+   * 翻译Claude响应（AI 语言->用户语言）
    *
    * 在显示Claude响应给用户之前调用此方法
-   * 如果响应是英文且用户原始输入是中文，则翻译为中文
+   * 如果响应是配置的 AI 语言，则翻译为用户语言
    * 如果翻译功能未启用或用户输入本来就是英文，则直接返回原文
    *
    * @param claudeResponse Claude API返回的响应文本
@@ -702,10 +730,12 @@ export class TranslationMiddleware {
       const detectedLanguage = await this.detectLanguage(claudeResponse);
       
 
-       // 🔧 优化：只翻译确定为英文的响应
-       if (detectedLanguage === 'en') {
+       const { user, agent } = this.getLanguagePair();
+
+       // 🔧 优化：只翻译确定为 AI 语言的响应
+       if (this.isSameLanguage(detectedLanguage, agent) && !this.isSameLanguage(user, agent)) {
          try {
-           const translatedText = await this.queueTranslation(claudeResponse, 'zh', 2); // 中等优先级
+           const translatedText = await this.queueTranslation(claudeResponse, user, 2); // 中等优先级
 
            
 
@@ -727,7 +757,7 @@ export class TranslationMiddleware {
          }
        }
 
-       // 如果响应已经是中文或其他语言，直接返回原文
+       // 如果响应已经是用户语言或其他语言，直接返回原文
        return {
          translatedText: claudeResponse,
          originalText: claudeResponse,
@@ -749,17 +779,18 @@ export class TranslationMiddleware {
 
   /**
    * 批量翻译文本（用于处理多条消息）- 性能优化版
-   * 使用队列化处理和智能去重
+   * 使用队列化处理和智能去重；未指定目标语言时翻译为用户语言
    */
   public async translateBatch(
     texts: string[],
-    targetLanguage: string = 'zh'
+    targetLanguage?: string
   ): Promise<string[]> {
     await this.ensureInitialized();
 
     if (!this.config?.enabled) {
       return texts;
     }
+    const target = targetLanguage ?? this.getLanguagePair().user;
 
     try {
       // 过滤空文本
@@ -770,7 +801,7 @@ export class TranslationMiddleware {
       }
       // 使用 Promise.all 并行处理，队列系统会自动管理速率限制
       const translationPromises = validTexts.map((text) =>
-        this.queueTranslation(text, targetLanguage, 1) // 标准优先级
+        this.queueTranslation(text, target, 1) // 标准优先级
       );
 
       const translatedTexts = await Promise.all(translationPromises);
@@ -872,11 +903,13 @@ export class TranslationMiddleware {
     }
 
     try {
-      // 检测语言，如果是英文则翻译为中文
+      const { user, agent } = this.getLanguagePair();
+
+      // 检测语言，如果是 AI 语言则翻译为用户语言
       const detectedLanguage = await this.detectLanguage(message);
 
-      if (detectedLanguage === 'en') {
-        const result = await this.queueTranslation(message, 'zh', 2); // 中等优先级
+      if (this.isSameLanguage(detectedLanguage, agent) && !this.isSameLanguage(user, agent)) {
+        const result = await this.queueTranslation(message, user, 2); // 中等优先级
         return result || message;
       }
 
@@ -41,6 +41,20 @@ export interface HookExecutionResult {
   error?: string;
   execution_time_ms: number;
   hook_command: string;
+  attempts?: number;                     // 实际执行次数（含重试）
+  exit_code?: number | null;
+  follow_ups?: HookExecutionResult[];    // on_success / on_failure 命令的结果
+}
+
+/**
+ * 持久化的Hook执行记录
+ */
+export interface HookExecutionLogEntry extends HookExecutionResult {
+  id: number;
+  session_id: string;
+  project_path: string;
+  event: string;
+  created_at: string;
 }
 
 /**
@@ -74,6 +88,9 @@ export interface EnhancedHook {
   condition?: ConditionalTrigger;
   on_success?: string[];    // 成功后执行的命令
   on_failure?: string[];    // 失败后执行的命令
+  retry_backoff_ms?: number; // 首次重试前的等待时间，之后每次翻倍
+  cwd?: string;              // 工作目录，相对路径基于项目目录
+  env?: Record<string, string>; // 额外环境变量
 }
 
 /**
@@ -96,6 +113,16 @@ export interface EnhancedHooksConfiguration {
   OnTabSwitch?: EnhancedHook[];
 }
 
+/**
+ * 项目级增强型Hooks配置（保存在 ~/.anycode/enhanced_hooks.json，由后端自动触发）
+ */
+export interface ProjectHooksConfig {
+  project_path: string;
+  enabled: boolean;
+  hooks: EnhancedHooksConfiguration;
+  watch_ignore: string[];   // OnFileChange 额外忽略的glob模式
+}
+
 /**
  * 增强型Hooks API接口
  */
@@ -215,6 +242,10 @@ export const CONDITION_EXAMPLES = [
   'data.tokens > 100000',
   'data.file_count > 50',
   'data.agent_type == "code-reviewer"',
+  'data.tokens > 100000 && event == "OnContextCompact"',
+  'data.tool_name =~ "^(Edit|Write)$"',
+  'data.file_path glob "src/**/*.rs" and not data.file_path glob "*.test.*"',
+  'data.agent_type in ["code-reviewer", "tester"]',
 ] as const;
 
 // ============ 智能化自动化场景类型定义 ============
@@ -231,6 +262,10 @@ export interface PreCommitCodeReviewConfig {
   exclude_patterns: string[];       // 排除的文件模式
   max_files_to_review: number;      // 最大审查文件数量
   show_suggestions: boolean;        // 是否显示改进建议
+  engine?: 'claude' | 'codex' | 'gemini'; // 执行审查的CLI引擎，默认 claude
+  model?: string | null;            // 审查使用的模型
+  timeout_secs?: number;            // 单次审查超时（秒），默认 300
+  fail_closed?: boolean;            // 审查无法完成时是否阻止提交，默认 false
 }
 
 /**